@@ -0,0 +1,3 @@
+ALTER TABLE perf_logs ADD COLUMN first_token_ms INTEGER;
+CREATE INDEX IF NOT EXISTS idx_perf_logs_first_token
+  ON perf_logs(created_at DESC) WHERE first_token_ms IS NOT NULL;
@@ -0,0 +1,23 @@
+CREATE TABLE IF NOT EXISTS saved_prompts (
+  id TEXT PRIMARY KEY,
+  user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  title TEXT NOT NULL,
+  description TEXT,
+  template TEXT NOT NULL,
+  variables TEXT NOT NULL DEFAULT '[]',
+  task_type TEXT,
+  use_count INTEGER NOT NULL DEFAULT 0,
+  last_used_at TEXT,
+  metadata TEXT NOT NULL DEFAULT '{}',
+  created_at TEXT NOT NULL DEFAULT (datetime('now','utc')),
+  updated_at TEXT NOT NULL DEFAULT (datetime('now','utc'))
+);
+CREATE INDEX IF NOT EXISTS idx_saved_prompts_user_id ON saved_prompts(user_id);
+CREATE INDEX IF NOT EXISTS idx_saved_prompts_last_used_at ON saved_prompts(last_used_at DESC);
+
+CREATE TRIGGER IF NOT EXISTS trg_saved_prompts_updated_at
+AFTER UPDATE ON saved_prompts
+FOR EACH ROW WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE saved_prompts SET updated_at = datetime('now','utc') WHERE id = OLD.id;
+END;
@@ -0,0 +1,2 @@
+ALTER TABLE sessions ADD COLUMN summarized_at TEXT;
+CREATE INDEX IF NOT EXISTS idx_sessions_summarized_at ON sessions(summarized_at);
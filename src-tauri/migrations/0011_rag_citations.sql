@@ -0,0 +1,14 @@
+ALTER TABLE rag_retrievals ADD COLUMN namespace TEXT;
+CREATE INDEX IF NOT EXISTS idx_rag_retrievals_namespace ON rag_retrievals(namespace);
+
+CREATE TABLE IF NOT EXISTS rag_citations (
+  id TEXT PRIMARY KEY,
+  chunk_id TEXT NOT NULL REFERENCES document_chunks(id) ON DELETE CASCADE,
+  document_id TEXT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+  namespace TEXT NOT NULL,
+  session_id TEXT REFERENCES sessions(id) ON DELETE SET NULL,
+  message_id TEXT REFERENCES messages(id) ON DELETE SET NULL,
+  created_at TEXT NOT NULL DEFAULT (datetime('now','utc'))
+);
+CREATE INDEX IF NOT EXISTS idx_rag_citations_namespace ON rag_citations(namespace);
+CREATE INDEX IF NOT EXISTS idx_rag_citations_chunk_id ON rag_citations(chunk_id);
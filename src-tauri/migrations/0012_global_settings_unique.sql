@@ -0,0 +1,7 @@
+-- UNIQUE(user_id, namespace, key) never matches NULL user ids, so global settings piled up duplicates.
+DELETE FROM settings
+WHERE user_id IS NULL
+  AND rowid NOT IN (
+    SELECT MAX(rowid) FROM settings WHERE user_id IS NULL GROUP BY namespace, key
+  );
+CREATE UNIQUE INDEX IF NOT EXISTS idx_settings_global_key ON settings(namespace, key) WHERE user_id IS NULL;
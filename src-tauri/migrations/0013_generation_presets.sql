@@ -0,0 +1 @@
+ALTER TABLE saved_prompts ADD COLUMN preset TEXT;
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS session_context_pins (
+  id TEXT PRIMARY KEY,
+  session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+  kind TEXT NOT NULL CHECK (kind IN ('document', 'chunk', 'note')),
+  document_id TEXT REFERENCES documents(id) ON DELETE CASCADE,
+  chunk_id TEXT REFERENCES document_chunks(id) ON DELETE CASCADE,
+  note TEXT,
+  label TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT (datetime('now','utc'))
+);
+CREATE INDEX IF NOT EXISTS idx_session_context_pins_session_id ON session_context_pins(session_id);
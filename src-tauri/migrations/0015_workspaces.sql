@@ -0,0 +1,26 @@
+CREATE TABLE IF NOT EXISTS workspaces (
+  id TEXT PRIMARY KEY,
+  user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  name TEXT NOT NULL,
+  description TEXT,
+  rag_namespace TEXT NOT NULL,
+  persona TEXT,
+  routing_rules TEXT NOT NULL DEFAULT '{}',
+  is_active INTEGER NOT NULL DEFAULT 0,
+  created_at TEXT NOT NULL DEFAULT (datetime('now','utc')),
+  updated_at TEXT NOT NULL DEFAULT (datetime('now','utc')),
+  UNIQUE (user_id, name)
+);
+CREATE INDEX IF NOT EXISTS idx_workspaces_user_id ON workspaces(user_id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_workspaces_active_per_user
+  ON workspaces(user_id) WHERE is_active = 1;
+
+CREATE TRIGGER IF NOT EXISTS trg_workspaces_updated_at
+AFTER UPDATE ON workspaces
+FOR EACH ROW WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE workspaces SET updated_at = datetime('now','utc') WHERE id = OLD.id;
+END;
+
+ALTER TABLE sessions ADD COLUMN workspace_id TEXT REFERENCES workspaces(id) ON DELETE SET NULL;
+CREATE INDEX IF NOT EXISTS idx_sessions_workspace_id ON sessions(workspace_id);
@@ -0,0 +1,19 @@
+CREATE TABLE IF NOT EXISTS adapters (
+  id TEXT PRIMARY KEY,
+  model_id TEXT NOT NULL REFERENCES models(id) ON DELETE CASCADE,
+  name TEXT NOT NULL,
+  file_path TEXT NOT NULL,
+  scale REAL NOT NULL DEFAULT 1.0,
+  is_active INTEGER NOT NULL DEFAULT 0,
+  created_at TEXT NOT NULL DEFAULT (datetime('now','utc')),
+  updated_at TEXT NOT NULL DEFAULT (datetime('now','utc')),
+  UNIQUE (model_id, file_path)
+);
+CREATE INDEX IF NOT EXISTS idx_adapters_model_id ON adapters(model_id);
+
+CREATE TRIGGER IF NOT EXISTS trg_adapters_updated_at
+AFTER UPDATE ON adapters
+FOR EACH ROW WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE adapters SET updated_at = datetime('now','utc') WHERE id = OLD.id;
+END;
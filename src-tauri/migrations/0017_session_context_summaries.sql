@@ -0,0 +1,2 @@
+ALTER TABLE sessions ADD COLUMN context_summary TEXT;
+ALTER TABLE sessions ADD COLUMN context_summary_position INTEGER;
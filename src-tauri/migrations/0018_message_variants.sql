@@ -0,0 +1,2 @@
+ALTER TABLE messages ADD COLUMN is_active_variant INTEGER NOT NULL DEFAULT 1;
+CREATE INDEX IF NOT EXISTS idx_messages_parent_message_id ON messages(parent_message_id);
@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS watched_folders (
+  id TEXT PRIMARY KEY,
+  user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  path TEXT NOT NULL,
+  namespace TEXT NOT NULL,
+  is_active INTEGER NOT NULL DEFAULT 1,
+  last_scan_at TEXT,
+  created_at TEXT NOT NULL DEFAULT (datetime('now','utc')),
+  updated_at TEXT NOT NULL DEFAULT (datetime('now','utc')),
+  UNIQUE (user_id, path)
+);
+CREATE INDEX IF NOT EXISTS idx_watched_folders_user_id ON watched_folders(user_id);
+
+CREATE TRIGGER IF NOT EXISTS trg_watched_folders_updated_at
+AFTER UPDATE ON watched_folders
+FOR EACH ROW WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE watched_folders SET updated_at = datetime('now','utc') WHERE id = OLD.id;
+END;
+
+CREATE INDEX IF NOT EXISTS idx_documents_file_path ON documents(user_id, file_path);
@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS rag_collections (
+  id TEXT PRIMARY KEY,
+  user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  name TEXT NOT NULL,
+  namespace TEXT NOT NULL,
+  description TEXT,
+  created_at TEXT NOT NULL DEFAULT (datetime('now','utc')),
+  updated_at TEXT NOT NULL DEFAULT (datetime('now','utc')),
+  UNIQUE (user_id, namespace)
+);
+CREATE INDEX IF NOT EXISTS idx_rag_collections_user_id ON rag_collections(user_id);
+
+CREATE TRIGGER IF NOT EXISTS trg_rag_collections_updated_at
+AFTER UPDATE ON rag_collections
+FOR EACH ROW WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE rag_collections SET updated_at = datetime('now','utc') WHERE id = OLD.id;
+END;
@@ -0,0 +1,31 @@
+CREATE TABLE IF NOT EXISTS reindex_jobs (
+  id TEXT PRIMARY KEY,
+  source_model TEXT NOT NULL,
+  target_model TEXT NOT NULL,
+  status TEXT NOT NULL DEFAULT 'running',
+  total_items INTEGER NOT NULL DEFAULT 0,
+  processed_items INTEGER NOT NULL DEFAULT 0,
+  error_message TEXT,
+  completed_at TEXT,
+  created_at TEXT NOT NULL DEFAULT (datetime('now','utc')),
+  updated_at TEXT NOT NULL DEFAULT (datetime('now','utc'))
+);
+CREATE INDEX IF NOT EXISTS idx_reindex_jobs_status ON reindex_jobs(status);
+
+-- Vectors from the target model, kept apart from `embeddings` until the job
+-- swaps them in with one transaction.
+CREATE TABLE IF NOT EXISTS reindex_staged_vectors (
+  job_id TEXT NOT NULL REFERENCES reindex_jobs(id) ON DELETE CASCADE,
+  embedding_id TEXT NOT NULL,
+  vector BLOB NOT NULL,
+  dimensions INTEGER NOT NULL,
+  norm REAL,
+  PRIMARY KEY (job_id, embedding_id)
+);
+
+CREATE TRIGGER IF NOT EXISTS trg_reindex_jobs_updated_at
+AFTER UPDATE ON reindex_jobs
+FOR EACH ROW WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE reindex_jobs SET updated_at = datetime('now','utc') WHERE id = OLD.id;
+END;
@@ -0,0 +1,2 @@
+-- JSON array of files attached to a message: [{"path", "kind", "ocrText"?}].
+ALTER TABLE messages ADD COLUMN attachments TEXT NOT NULL DEFAULT '[]';
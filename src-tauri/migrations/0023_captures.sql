@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS captures (
+  id TEXT PRIMARY KEY,
+  session_id TEXT REFERENCES sessions(id) ON DELETE SET NULL,
+  capture_type TEXT NOT NULL CHECK (capture_type IN ('screenshot', 'recording')),
+  file_path TEXT NOT NULL,
+  mime_type TEXT NOT NULL,
+  thumbnail_path TEXT,
+  duration_ms INTEGER,
+  width INTEGER,
+  height INTEGER,
+  file_size_bytes INTEGER,
+  created_at TEXT NOT NULL DEFAULT (datetime('now','utc'))
+);
+CREATE INDEX IF NOT EXISTS idx_captures_created_at ON captures(created_at);
+CREATE INDEX IF NOT EXISTS idx_captures_session_id ON captures(session_id);
@@ -0,0 +1,27 @@
+-- Persistent background jobs. `run_at` is when the job is next due; recurring
+-- jobs carry a cron expression and are re-queued after each run.
+CREATE TABLE IF NOT EXISTS jobs (
+  id TEXT PRIMARY KEY,
+  job_type TEXT NOT NULL,
+  payload TEXT NOT NULL DEFAULT '{}',
+  cron TEXT,
+  run_at TEXT NOT NULL DEFAULT (datetime('now','utc')),
+  status TEXT NOT NULL DEFAULT 'queued'
+    CHECK (status IN ('queued', 'running', 'completed', 'failed', 'cancelled')),
+  attempts INTEGER NOT NULL DEFAULT 0,
+  max_attempts INTEGER NOT NULL DEFAULT 3,
+  last_error TEXT,
+  started_at TEXT,
+  completed_at TEXT,
+  created_at TEXT NOT NULL DEFAULT (datetime('now','utc')),
+  updated_at TEXT NOT NULL DEFAULT (datetime('now','utc'))
+);
+CREATE INDEX IF NOT EXISTS idx_jobs_due ON jobs(status, run_at);
+CREATE INDEX IF NOT EXISTS idx_jobs_type ON jobs(job_type, created_at DESC);
+
+CREATE TRIGGER IF NOT EXISTS trg_jobs_updated_at
+AFTER UPDATE ON jobs
+FOR EACH ROW WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE jobs SET updated_at = datetime('now','utc') WHERE id = OLD.id;
+END;
@@ -0,0 +1,23 @@
+CREATE TABLE IF NOT EXISTS personas (
+  id TEXT PRIMARY KEY,
+  user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  name TEXT NOT NULL,
+  description TEXT,
+  system_prompt TEXT NOT NULL,
+  temperature REAL,
+  preferred_model_id TEXT REFERENCES models(id) ON DELETE SET NULL,
+  created_at TEXT NOT NULL DEFAULT (datetime('now','utc')),
+  updated_at TEXT NOT NULL DEFAULT (datetime('now','utc')),
+  UNIQUE (user_id, name)
+);
+CREATE INDEX IF NOT EXISTS idx_personas_user_id ON personas(user_id);
+
+CREATE TRIGGER IF NOT EXISTS trg_personas_updated_at
+AFTER UPDATE ON personas
+FOR EACH ROW WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE personas SET updated_at = datetime('now','utc') WHERE id = OLD.id;
+END;
+
+ALTER TABLE sessions ADD COLUMN persona_id TEXT REFERENCES personas(id) ON DELETE SET NULL;
+CREATE INDEX IF NOT EXISTS idx_sessions_persona_id ON sessions(persona_id);
@@ -0,0 +1,27 @@
+-- Prompts the assistant runs on a cron schedule. Each run is queued as a job;
+-- replies land in the prompt's own session.
+CREATE TABLE IF NOT EXISTS scheduled_prompts (
+  id TEXT PRIMARY KEY,
+  user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  name TEXT NOT NULL,
+  prompt TEXT NOT NULL,
+  schedule TEXT NOT NULL,
+  cron TEXT NOT NULL,
+  session_id TEXT REFERENCES sessions(id) ON DELETE SET NULL,
+  is_enabled INTEGER NOT NULL DEFAULT 1,
+  next_run_at TEXT,
+  last_run_at TEXT,
+  last_status TEXT CHECK (last_status IN ('succeeded', 'failed')),
+  last_error TEXT,
+  created_at TEXT NOT NULL DEFAULT (datetime('now','utc')),
+  updated_at TEXT NOT NULL DEFAULT (datetime('now','utc'))
+);
+CREATE INDEX IF NOT EXISTS idx_scheduled_prompts_user_id ON scheduled_prompts(user_id);
+CREATE INDEX IF NOT EXISTS idx_scheduled_prompts_due ON scheduled_prompts(is_enabled, next_run_at);
+
+CREATE TRIGGER IF NOT EXISTS trg_scheduled_prompts_updated_at
+AFTER UPDATE ON scheduled_prompts
+FOR EACH ROW WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE scheduled_prompts SET updated_at = datetime('now','utc') WHERE id = OLD.id;
+END;
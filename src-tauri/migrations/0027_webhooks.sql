@@ -0,0 +1,24 @@
+-- Outgoing webhooks: URLs that receive a signed JSON POST when one of their
+-- events happens. Each delivery is queued as a job, so failures are retried.
+CREATE TABLE IF NOT EXISTS webhooks (
+  id TEXT PRIMARY KEY,
+  user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  name TEXT NOT NULL,
+  url TEXT NOT NULL,
+  events TEXT NOT NULL DEFAULT '[]',
+  secret TEXT NOT NULL,
+  is_enabled INTEGER NOT NULL DEFAULT 1,
+  last_delivery_at TEXT,
+  last_status TEXT CHECK (last_status IN ('succeeded', 'failed')),
+  last_error TEXT,
+  created_at TEXT NOT NULL DEFAULT (datetime('now','utc')),
+  updated_at TEXT NOT NULL DEFAULT (datetime('now','utc'))
+);
+CREATE INDEX IF NOT EXISTS idx_webhooks_user_id ON webhooks(user_id);
+
+CREATE TRIGGER IF NOT EXISTS trg_webhooks_updated_at
+AFTER UPDATE ON webhooks
+FOR EACH ROW WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE webhooks SET updated_at = datetime('now','utc') WHERE id = OLD.id;
+END;
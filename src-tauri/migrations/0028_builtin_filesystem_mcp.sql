@@ -0,0 +1,14 @@
+-- Built-in file tools, served from the backend instead of an external server.
+-- The tool schemas are written at startup from `filesystem_tools::tool_schemas`.
+INSERT OR IGNORE INTO mcps (
+  id, name, display_name, description, category, mcp_type,
+  is_installed, is_active, is_builtin
+) VALUES (
+  'builtin-filesystem',
+  'builtin_filesystem',
+  'Files',
+  'Lists, reads, searches and writes files in folders you approve.',
+  'system',
+  'builtin',
+  1, 1, 1
+);
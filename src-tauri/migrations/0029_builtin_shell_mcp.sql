@@ -0,0 +1,14 @@
+-- Built-in `run_command` tool. Only allowlisted programs run, and each call
+-- waits for the user's confirmation.
+INSERT OR IGNORE INTO mcps (
+  id, name, display_name, description, category, mcp_type,
+  is_installed, is_active, is_builtin
+) VALUES (
+  'builtin-shell',
+  'builtin_shell',
+  'Terminal',
+  'Runs allowed programs such as git after you confirm each command.',
+  'system',
+  'builtin',
+  1, 1, 1
+);
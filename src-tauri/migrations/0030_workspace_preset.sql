@@ -0,0 +1,2 @@
+-- The workspace column holds a generation preset name, not a persona id.
+ALTER TABLE workspaces RENAME COLUMN persona TO preset;
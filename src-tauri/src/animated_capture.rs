@@ -0,0 +1,327 @@
+use std::ffi::{c_int, CStr};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::mem::MaybeUninit;
+use std::path::PathBuf;
+use std::ptr::{self, NonNull};
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::imageops::{self, FilterType};
+use image::{Delay, Frame as ImageFrame, RgbaImage};
+use libwebp_sys::{
+    WebPAnimEncoder, WebPAnimEncoderAdd, WebPAnimEncoderAssemble, WebPAnimEncoderDelete,
+    WebPAnimEncoderGetError, WebPAnimEncoderNewInternal, WebPAnimEncoderOptions,
+    WebPAnimEncoderOptionsInitInternal, WebPConfig, WebPData, WebPDataClear, WebPGetMuxABIVersion,
+    WebPPicture, WebPPictureFree, WebPPictureImportRGBA, WebPPreset,
+};
+use serde::Deserialize;
+
+const MAX_FPS: u32 = 30;
+const MIN_SCALE: f32 = 0.1;
+const WEBP_QUALITY: f32 = 75.0;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordingFormat {
+    #[default]
+    Mp4,
+    Gif,
+    Webp,
+}
+
+impl RecordingFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Mp4 => "mp4",
+            Self::Gif => "gif",
+            Self::Webp => "webp",
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            Self::Mp4 => "video/mp4",
+            Self::Gif => "image/gif",
+            Self::Webp => "image/webp",
+        }
+    }
+
+    pub fn is_animation(self) -> bool {
+        self != Self::Mp4
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AnimationOptions {
+    /// Frames kept per second; the rest of what the capture delivers is skipped.
+    pub fps: u32,
+    /// Output size relative to the captured surface.
+    pub scale: f32,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        Self {
+            fps: 10,
+            scale: 0.5,
+        }
+    }
+}
+
+enum AnimationSink {
+    /// Frames are written as they come, each once the next one fixes its delay.
+    Gif {
+        encoder: GifEncoder<BufWriter<File>>,
+        pending: Option<(RgbaImage, i64)>,
+    },
+    /// libwebp assembles the animation in memory and it's written on finish.
+    /// Created with the first frame, once the dimensions are known.
+    Webp(Option<WebpAnimation>),
+}
+
+/// Encodes captured frames into a GIF or animated WebP clip. Timestamps are
+/// in 100 ns units from the start of the recording.
+pub struct AnimationEncoder {
+    sink: AnimationSink,
+    path: PathBuf,
+    /// Time between kept frames.
+    frame_interval: i64,
+    next_frame_at: i64,
+    scale: f32,
+    /// Output dimensions, fixed by the first frame.
+    size: Option<(u32, u32)>,
+}
+
+impl AnimationEncoder {
+    pub fn new(
+        format: RecordingFormat,
+        path: PathBuf,
+        options: AnimationOptions,
+    ) -> Result<Self, String> {
+        let sink = match format {
+            RecordingFormat::Gif => {
+                let file = File::create(&path)
+                    .map_err(|error| format!("Failed to create recording file: {error}"))?;
+                let mut encoder = GifEncoder::new_with_speed(BufWriter::new(file), 10);
+                encoder
+                    .set_repeat(Repeat::Infinite)
+                    .map_err(|error| format!("Failed to start GIF encoder: {error}"))?;
+                AnimationSink::Gif {
+                    encoder,
+                    pending: None,
+                }
+            }
+            RecordingFormat::Webp => AnimationSink::Webp(None),
+            RecordingFormat::Mp4 => {
+                return Err("MP4 recordings go through the video encoder.".to_string())
+            }
+        };
+
+        Ok(Self {
+            sink,
+            path,
+            frame_interval: 10_000_000 / i64::from(options.fps.clamp(1, MAX_FPS)),
+            next_frame_at: 0,
+            scale: options.scale.clamp(MIN_SCALE, 1.0),
+            size: None,
+        })
+    }
+
+    /// Adds a BGRA frame unless it falls before the next kept frame is due.
+    pub fn push(
+        &mut self,
+        bgra: &[u8],
+        width: u32,
+        height: u32,
+        timestamp: i64,
+    ) -> Result<(), String> {
+        if timestamp < self.next_frame_at {
+            return Ok(());
+        }
+        self.next_frame_at = timestamp + self.frame_interval;
+
+        let mut rgba = bgra.to_vec();
+        for pixel in rgba.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+        let image = RgbaImage::from_raw(width, height, rgba)
+            .ok_or_else(|| "Captured frame had an unexpected size.".to_string())?;
+        let scale = self.scale;
+        let (out_width, out_height) = *self.size.get_or_insert_with(|| {
+            (
+                ((width as f32 * scale) as u32).max(1),
+                ((height as f32 * scale) as u32).max(1),
+            )
+        });
+        // Window captures can change size; every frame is fit to the first.
+        let image = if (width, height) == (out_width, out_height) {
+            image
+        } else {
+            imageops::resize(&image, out_width, out_height, FilterType::Triangle)
+        };
+
+        match &mut self.sink {
+            AnimationSink::Gif { encoder, pending } => {
+                if let Some((previous, previous_at)) = pending.take() {
+                    write_gif_frame(encoder, previous, timestamp - previous_at)?;
+                }
+                *pending = Some((image, timestamp));
+            }
+            AnimationSink::Webp(encoder) => {
+                let encoder = match encoder {
+                    Some(encoder) => encoder,
+                    None => encoder.insert(WebpAnimation::new(out_width, out_height)?),
+                };
+                encoder.add_frame(image.as_raw(), to_millis(timestamp))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes out the last frame and closes the file.
+    pub fn finish(self, timestamp: i64) -> Result<(), String> {
+        match self.sink {
+            AnimationSink::Gif {
+                mut encoder,
+                pending,
+            } => {
+                if let Some((previous, previous_at)) = pending {
+                    let shown = (timestamp - previous_at).max(self.frame_interval);
+                    write_gif_frame(&mut encoder, previous, shown)?;
+                }
+                // Dropping the encoder writes the GIF trailer.
+                drop(encoder);
+                Ok(())
+            }
+            AnimationSink::Webp(None) => Err("No frames were captured.".to_string()),
+            AnimationSink::Webp(Some(encoder)) => {
+                let end = timestamp.max(self.next_frame_at);
+                let data = encoder.finish(to_millis(end))?;
+                fs::write(&self.path, data)
+                    .map_err(|error| format!("Failed to write recording: {error}"))
+            }
+        }
+    }
+}
+
+/// libwebp's animation encoder, fed one frame at a time so only the
+/// compressed frames are held until the file is assembled.
+struct WebpAnimation {
+    encoder: NonNull<WebPAnimEncoder>,
+    config: WebPConfig,
+    width: u32,
+    height: u32,
+}
+
+// SAFETY: the encoder is owned by this value and only used through `&mut self`.
+unsafe impl Send for WebpAnimation {}
+
+impl WebpAnimation {
+    fn new(width: u32, height: u32) -> Result<Self, String> {
+        let config = WebPConfig::new_with_preset(WebPPreset::WEBP_PRESET_DEFAULT, WEBP_QUALITY)
+            .map_err(|_| "Failed to configure the WebP encoder.".to_string())?;
+        let mut options = MaybeUninit::<WebPAnimEncoderOptions>::uninit();
+        // SAFETY: libwebp fills in the options before the encoder reads them.
+        let encoder = unsafe {
+            if WebPAnimEncoderOptionsInitInternal(options.as_mut_ptr(), WebPGetMuxABIVersion()) == 0
+            {
+                return Err("Failed to start WebP encoder: incompatible libwebp.".to_string());
+            }
+            WebPAnimEncoderNewInternal(
+                width as c_int,
+                height as c_int,
+                options.as_ptr(),
+                WebPGetMuxABIVersion(),
+            )
+        };
+        let encoder = NonNull::new(encoder)
+            .ok_or_else(|| "Failed to start WebP encoder: out of memory.".to_string())?;
+        Ok(Self {
+            encoder,
+            config,
+            width,
+            height,
+        })
+    }
+
+    /// `rgba` must be `width` x `height` pixels, and timestamps must increase.
+    fn add_frame(&mut self, rgba: &[u8], timestamp_ms: i32) -> Result<(), String> {
+        let mut picture =
+            WebPPicture::new().map_err(|_| "Failed to encode WebP frame.".to_string())?;
+        picture.use_argb = 1;
+        picture.width = self.width as c_int;
+        picture.height = self.height as c_int;
+        // SAFETY: `rgba` holds `height` rows of `width * 4` bytes. The picture
+        // keeps its own copy of the pixels, which is freed once the encoder
+        // has taken the frame.
+        let added = unsafe {
+            if WebPPictureImportRGBA(&mut picture, rgba.as_ptr(), (self.width * 4) as c_int) == 0 {
+                return Err("Failed to encode WebP frame: out of memory.".to_string());
+            }
+            let added = WebPAnimEncoderAdd(
+                self.encoder.as_ptr(),
+                &mut picture,
+                timestamp_ms,
+                &self.config,
+            );
+            WebPPictureFree(&mut picture);
+            added
+        };
+        if added == 0 {
+            return Err(format!("Failed to encode WebP frame: {}", self.error()));
+        }
+        Ok(())
+    }
+
+    /// Ends the last frame at `end_ms` and returns the assembled file.
+    fn finish(self, end_ms: i32) -> Result<Vec<u8>, String> {
+        let mut data = WebPData::default();
+        // SAFETY: a null frame closes the animation. `data` is allocated by
+        // libwebp on success and freed after it's copied out.
+        unsafe {
+            if WebPAnimEncoderAdd(self.encoder.as_ptr(), ptr::null_mut(), end_ms, ptr::null()) == 0
+                || WebPAnimEncoderAssemble(self.encoder.as_ptr(), &mut data) == 0
+            {
+                return Err(format!("Failed to finish WebP encoding: {}", self.error()));
+            }
+            let bytes = std::slice::from_raw_parts(data.bytes, data.size).to_vec();
+            WebPDataClear(&mut data);
+            Ok(bytes)
+        }
+    }
+
+    fn error(&self) -> String {
+        // SAFETY: libwebp returns null or a string owned by the encoder.
+        let message = unsafe { WebPAnimEncoderGetError(self.encoder.as_ptr()) };
+        if message.is_null() {
+            return "unknown error".to_string();
+        }
+        unsafe { CStr::from_ptr(message) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+impl Drop for WebpAnimation {
+    fn drop(&mut self) {
+        // SAFETY: the encoder came from `WebPAnimEncoderNewInternal` and is
+        // deleted only here.
+        unsafe { WebPAnimEncoderDelete(self.encoder.as_ptr()) };
+    }
+}
+
+fn write_gif_frame(
+    encoder: &mut GifEncoder<BufWriter<File>>,
+    image: RgbaImage,
+    shown: i64,
+) -> Result<(), String> {
+    let delay = Delay::from_numer_denom_ms(to_millis(shown).max(10) as u32, 1);
+    encoder
+        .encode_frame(ImageFrame::from_parts(image, 0, 0, delay))
+        .map_err(|error| format!("Failed to encode GIF frame: {error}"))
+}
+
+fn to_millis(timestamp: i64) -> i32 {
+    (timestamp / 10_000).clamp(0, i64::from(i32::MAX)) as i32
+}
@@ -0,0 +1,326 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{Device, FromSample, SampleFormat, SizedSample, Stream, StreamConfig};
+use serde::{Deserialize, Serialize};
+
+/// Layout of the mixed track handed to the MP4 encoder: 48 kHz, stereo, 16-bit.
+pub const MIX_SAMPLE_RATE: u32 = 48_000;
+pub const MIX_CHANNELS: u32 = 2;
+/// Mixing trails the wall clock by this much so late device buffers still make it in.
+const MIX_LATENCY: Duration = Duration::from_millis(100);
+/// Audio queued beyond this is dropped so a stalled encoder can't grow memory.
+const MAX_QUEUED_SECONDS: usize = 5;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioDeviceInfo {
+    /// Also what a recording's device selection refers to.
+    pub name: String,
+    /// `output` (recorded through loopback) or `input` (a microphone).
+    pub kind: String,
+    pub is_default: bool,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RecordingAudioOptions {
+    /// Record what the speakers play, through WASAPI loopback.
+    pub system_audio: bool,
+    pub microphone: bool,
+    /// Output device to record instead of the default one.
+    pub system_device: Option<String>,
+    /// Input device to record instead of the default one.
+    pub microphone_device: Option<String>,
+}
+
+impl RecordingAudioOptions {
+    pub fn is_enabled(&self) -> bool {
+        self.system_audio || self.microphone
+    }
+}
+
+struct MixState {
+    /// Interleaved stereo samples per source, already at the mix rate.
+    sources: Vec<VecDeque<f32>>,
+    frames_written: u64,
+}
+
+/// Sources push converted samples from their device threads; the encoder
+/// drains the mix from the capture thread.
+pub struct AudioMix {
+    state: Mutex<MixState>,
+    started: Instant,
+}
+
+impl AudioMix {
+    fn new(source_count: usize) -> Self {
+        Self {
+            state: Mutex::new(MixState {
+                sources: vec![VecDeque::new(); source_count],
+                frames_written: 0,
+            }),
+            started: Instant::now(),
+        }
+    }
+
+    fn push(&self, source: usize, samples: &[f32]) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let queue = &mut state.sources[source];
+        queue.extend(samples);
+        let max_len = MAX_QUEUED_SECONDS * (MIX_SAMPLE_RATE * MIX_CHANNELS) as usize;
+        if queue.len() > max_len {
+            let excess = queue.len() - max_len;
+            queue.drain(..excess);
+        }
+    }
+
+    /// Mixes every frame due since the last call as 16-bit PCM, with its
+    /// timestamp in 100 ns units. The wall clock decides how many frames are
+    /// due; a source that delivered less is padded with silence, since
+    /// loopback delivers nothing at all while the system is quiet.
+    pub fn drain(&self) -> Option<(Vec<u8>, i64)> {
+        let mut state = self.state.lock().ok()?;
+        let elapsed = self.started.elapsed().saturating_sub(MIX_LATENCY);
+        let due = (elapsed.as_secs_f64() * MIX_SAMPLE_RATE as f64) as u64;
+        let frames = due.saturating_sub(state.frames_written);
+        if frames == 0 {
+            return None;
+        }
+
+        let timestamp = (state.frames_written as i64) * 10_000_000 / MIX_SAMPLE_RATE as i64;
+        let samples = frames as usize * MIX_CHANNELS as usize;
+        let mut pcm = Vec::with_capacity(samples * 2);
+        for _ in 0..samples {
+            let mixed = state
+                .sources
+                .iter_mut()
+                .map(|queue| queue.pop_front().unwrap_or(0.0))
+                .sum::<f32>();
+            let sample = (mixed.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            pcm.extend_from_slice(&sample.to_le_bytes());
+        }
+        state.frames_written += frames;
+        Some((pcm, timestamp))
+    }
+}
+
+/// Running device streams and the mix they feed. The streams stop when this
+/// is dropped; they can't leave the thread that created them.
+pub struct AudioCapture {
+    mix: Arc<AudioMix>,
+    _streams: Vec<Stream>,
+}
+
+impl AudioCapture {
+    pub fn mix(&self) -> Arc<AudioMix> {
+        Arc::clone(&self.mix)
+    }
+}
+
+/// Linear resampler working on stereo frames.
+pub struct StereoResampler {
+    /// Input frames per output frame.
+    step: f64,
+    /// Where the next output frame falls between `previous` (0) and the next input (1).
+    position: f64,
+    previous: [f32; 2],
+}
+
+impl StereoResampler {
+    pub fn new(input_rate: u32, output_rate: u32) -> Self {
+        Self {
+            step: input_rate as f64 / output_rate as f64,
+            position: 0.0,
+            previous: [0.0; 2],
+        }
+    }
+
+    /// Appends the resampled frames to `out`, interleaved.
+    pub fn process(&mut self, frames: impl Iterator<Item = [f32; 2]>, out: &mut Vec<f32>) {
+        for frame in frames {
+            while self.position < 1.0 {
+                let t = self.position as f32;
+                out.push(self.previous[0] + (frame[0] - self.previous[0]) * t);
+                out.push(self.previous[1] + (frame[1] - self.previous[1]) * t);
+                self.position += self.step;
+            }
+            self.position -= 1.0;
+            self.previous = frame;
+        }
+    }
+}
+
+#[tauri::command]
+pub fn list_audio_devices() -> Result<Vec<AudioDeviceInfo>, String> {
+    crate::log_info!("sarah.command", "list_audio_devices invoked");
+    let host = cpal::default_host();
+    let default_output = host.default_output_device().and_then(|d| d.name().ok());
+    let default_input = host.default_input_device().and_then(|d| d.name().ok());
+
+    let mut devices = Vec::new();
+    let outputs = host
+        .output_devices()
+        .map_err(|error| format!("Failed to list audio devices: {error}"))?;
+    push_devices(&mut devices, "output", outputs, default_output.as_deref());
+    let inputs = host
+        .input_devices()
+        .map_err(|error| format!("Failed to list audio devices: {error}"))?;
+    push_devices(&mut devices, "input", inputs, default_input.as_deref());
+    Ok(devices)
+}
+
+fn push_devices(
+    devices: &mut Vec<AudioDeviceInfo>,
+    kind: &str,
+    found: impl Iterator<Item = Device>,
+    default_name: Option<&str>,
+) {
+    for device in found {
+        let Ok(name) = device.name() else {
+            continue;
+        };
+        devices.push(AudioDeviceInfo {
+            is_default: default_name == Some(name.as_str()),
+            name,
+            kind: kind.to_string(),
+        });
+    }
+}
+
+/// Opens the requested sources and starts feeding a new mix.
+pub fn start_capture(options: &RecordingAudioOptions) -> Result<AudioCapture, String> {
+    let host = cpal::default_host();
+    let mut devices = Vec::new();
+    if options.system_audio {
+        let device = match options.system_device.as_deref() {
+            Some(name) => host
+                .output_devices()
+                .ok()
+                .and_then(|mut found| find_device(&mut found, name)),
+            None => host.default_output_device(),
+        }
+        .ok_or_else(|| "No speaker output is available to record.".to_string())?;
+        devices.push((device, true));
+    }
+    if options.microphone {
+        let device = match options.microphone_device.as_deref() {
+            Some(name) => host
+                .input_devices()
+                .ok()
+                .and_then(|mut found| find_device(&mut found, name)),
+            None => host.default_input_device(),
+        }
+        .ok_or_else(|| "No microphone is available to record.".to_string())?;
+        devices.push((device, false));
+    }
+
+    let mix = Arc::new(AudioMix::new(devices.len()));
+    let mut streams = Vec::new();
+    for (source, (device, loopback)) in devices.iter().enumerate() {
+        let mix = Arc::clone(&mix);
+        let mut resampler = None;
+        let mut converted = Vec::new();
+        let stream = open_stream(device, *loopback, move |frames, sample_rate| {
+            let resampler =
+                resampler.get_or_insert_with(|| StereoResampler::new(sample_rate, MIX_SAMPLE_RATE));
+            converted.clear();
+            resampler.process(frames.iter().copied(), &mut converted);
+            mix.push(source, &converted);
+        })?;
+        stream
+            .play()
+            .map_err(|error| format!("Failed to start audio capture: {error}"))?;
+        streams.push(stream);
+    }
+
+    Ok(AudioCapture {
+        mix,
+        _streams: streams,
+    })
+}
+
+/// Starts recording from a microphone, the default one unless `device_name`
+/// names another. `on_frames` gets stereo frames at the device's rate, which
+/// is passed along with them. The stream stops when dropped.
+pub fn start_microphone(
+    device_name: Option<&str>,
+    on_frames: impl FnMut(&[[f32; 2]], u32) + Send + 'static,
+) -> Result<Stream, String> {
+    let host = cpal::default_host();
+    let device = match device_name {
+        Some(name) => host
+            .input_devices()
+            .ok()
+            .and_then(|mut found| find_device(&mut found, name)),
+        None => host.default_input_device(),
+    }
+    .ok_or_else(|| "No microphone is available to record.".to_string())?;
+
+    let stream = open_stream(&device, false, on_frames)?;
+    stream
+        .play()
+        .map_err(|error| format!("Failed to start audio capture: {error}"))?;
+    Ok(stream)
+}
+
+fn find_device(found: &mut impl Iterator<Item = Device>, name: &str) -> Option<Device> {
+    found.find(|device| device.name().is_ok_and(|device_name| device_name == name))
+}
+
+/// An input stream on an output device records it through loopback.
+fn open_stream(
+    device: &Device,
+    loopback: bool,
+    on_frames: impl FnMut(&[[f32; 2]], u32) + Send + 'static,
+) -> Result<Stream, String> {
+    let config = if loopback {
+        device.default_output_config()
+    } else {
+        device.default_input_config()
+    }
+    .map_err(|error| format!("Failed to read audio device format: {error}"))?;
+    let sample_format = config.sample_format();
+    let config = StreamConfig::from(config);
+
+    let stream = match sample_format {
+        SampleFormat::F32 => build_stream::<f32>(device, &config, on_frames),
+        SampleFormat::I16 => build_stream::<i16>(device, &config, on_frames),
+        SampleFormat::I32 => build_stream::<i32>(device, &config, on_frames),
+        SampleFormat::U16 => build_stream::<u16>(device, &config, on_frames),
+        other => return Err(format!("Unsupported audio sample format: {other}")),
+    };
+    stream.map_err(|error| format!("Failed to open audio device: {error}"))
+}
+
+fn build_stream<T>(
+    device: &Device,
+    config: &StreamConfig,
+    mut on_frames: impl FnMut(&[[f32; 2]], u32) + Send + 'static,
+) -> Result<Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels.max(1) as usize;
+    let sample_rate = config.sample_rate.0;
+    let mut frames = Vec::new();
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            frames.clear();
+            frames.extend(data.chunks(channels).map(|frame| match frame {
+                [mono] => [f32::from_sample(*mono); 2],
+                [left, right, ..] => [f32::from_sample(*left), f32::from_sample(*right)],
+                [] => [0.0; 2],
+            }));
+            on_frames(&frames, sample_rate);
+        },
+        |error| crate::log_warn!("sarah.capture", "Audio capture error: {}", error),
+        None,
+    )
+}
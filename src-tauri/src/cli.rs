@@ -0,0 +1,307 @@
+//! `sarah ask`: sends one prompt to the running app's local API and prints the
+//! reply, for scripts and quick checks from a terminal.
+
+use std::io::{IsTerminal, Read, Write};
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+
+use crate::services::local_api_service::{
+    LocalApiDiscovery, DEFAULT_LOCAL_API_PORT, LOCAL_API_DISCOVERY_FILE,
+};
+
+/// Must match `identifier` in tauri.conf.json; the app data directory is named after it.
+const APP_IDENTIFIER: &str = "com.ai.sarah";
+/// Larger files are cut off; a local model's context can't hold much more.
+const MAX_CONTEXT_FILE_BYTES: usize = 256 * 1024;
+
+const USAGE: &str = "\
+Usage: sarah ask [OPTIONS] [QUESTION]...
+
+Sends QUESTION to the running Sarah app and streams the reply to stdout.
+Text piped on stdin is added as context.
+
+Options:
+  -m, --model <NAME>    Model to answer with (default: auto, picked by Sarah)
+  -f, --file <PATH>     Add a file as context; repeatable
+  -s, --system <TEXT>   System prompt
+      --json            Print the whole response as JSON once it is done
+      --port <PORT>     Local API port (default: from the running app)
+      --token <TOKEN>   Local API token (default: from the running app)
+  -h, --help            Show this help
+
+The local API must be turned on in Sarah's settings. SARAH_API_PORT and
+SARAH_API_TOKEN can stand in for --port and --token.";
+
+struct AskOptions {
+    question: String,
+    model: String,
+    files: Vec<PathBuf>,
+    system: Option<String>,
+    json: bool,
+    port: Option<u16>,
+    token: Option<String>,
+}
+
+/// Runs the command line when the process was started as `sarah ask ...`
+/// and returns its exit code; `None` means start the desktop app as usual.
+pub fn try_run() -> Option<i32> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) != Some("ask") {
+        return None;
+    }
+    #[cfg(windows)]
+    attach_parent_console();
+
+    let options = match parse_args(&args[1..]) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{USAGE}");
+            return Some(0);
+        }
+        Err(message) => {
+            eprintln!("sarah: {message}\n\n{USAGE}");
+            return Some(2);
+        }
+    };
+
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(error) => {
+            eprintln!("sarah: {error}");
+            return Some(1);
+        }
+    };
+    match runtime.block_on(ask(options)) {
+        Ok(()) => Some(0),
+        Err(message) => {
+            eprintln!("sarah: {message}");
+            Some(1)
+        }
+    }
+}
+
+/// `Ok(None)` when help was asked for.
+fn parse_args(args: &[String]) -> Result<Option<AskOptions>, String> {
+    let mut options = AskOptions {
+        question: String::new(),
+        model: "auto".to_string(),
+        files: Vec::new(),
+        system: None,
+        json: false,
+        port: std::env::var("SARAH_API_PORT")
+            .ok()
+            .and_then(|port| port.parse().ok()),
+        token: std::env::var("SARAH_API_TOKEN").ok(),
+    };
+    let mut words = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| {
+            args.next()
+                .cloned()
+                .ok_or_else(|| format!("{flag} needs a value"))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-m" | "--model" => options.model = value(arg)?,
+            "-f" | "--file" => options.files.push(PathBuf::from(value(arg)?)),
+            "-s" | "--system" => options.system = Some(value(arg)?),
+            "--json" => options.json = true,
+            "--port" => {
+                let port = value(arg)?;
+                options.port = Some(
+                    port.parse()
+                        .map_err(|_| format!("'{port}' is not a port number"))?,
+                );
+            }
+            "--token" => options.token = Some(value(arg)?),
+            "--" => {
+                words.extend(args.by_ref().cloned());
+            }
+            flag if flag.starts_with('-') && flag.len() > 1 => {
+                return Err(format!("unknown option '{flag}'"));
+            }
+            word => words.push(word.to_string()),
+        }
+    }
+    options.question = words.join(" ");
+    Ok(Some(options))
+}
+
+async fn ask(options: AskOptions) -> Result<(), String> {
+    let (port, token) = connection(&options)?;
+    let content = build_prompt(&options)?;
+
+    let mut messages = Vec::new();
+    if let Some(system) = options.system.as_deref() {
+        messages.push(json!({ "role": "system", "content": system }));
+    }
+    messages.push(json!({ "role": "user", "content": content }));
+    let body = json!({
+        "model": options.model,
+        "messages": messages,
+        "stream": !options.json,
+    });
+
+    let response = reqwest::Client::new()
+        .post(format!("http://127.0.0.1:{port}/v1/chat/completions"))
+        .bearer_auth(token)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|error| {
+            if error.is_connect() {
+                format!(
+                    "couldn't reach Sarah on port {port}. Is the app running with the local API turned on?"
+                )
+            } else {
+                error.to_string()
+            }
+        })?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        let message = body
+            .pointer("/error/message")
+            .and_then(Value::as_str)
+            .unwrap_or("request failed");
+        return Err(format!("{message} ({status})"));
+    }
+
+    if options.json {
+        let body: Value = response.json().await.map_err(|error| error.to_string())?;
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&body).unwrap_or_default()
+        );
+        return Ok(());
+    }
+    stream_reply(response).await
+}
+
+/// Prints each token of a server-sent event stream as it arrives.
+async fn stream_reply(mut response: reqwest::Response) -> Result<(), String> {
+    let mut stdout = std::io::stdout();
+    // Bytes, not text: a chunk can end partway through a UTF-8 character.
+    let mut pending: Vec<u8> = Vec::new();
+    while let Some(bytes) = response.chunk().await.map_err(|error| error.to_string())? {
+        pending.extend_from_slice(&bytes);
+        while let Some(end) = pending.iter().position(|byte| *byte == b'\n') {
+            let line = String::from_utf8_lossy(&pending[..end]).trim().to_string();
+            pending.drain(..=end);
+            let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+                continue;
+            };
+            if data == "[DONE]" {
+                let _ = writeln!(stdout);
+                return Ok(());
+            }
+            let chunk: Value = serde_json::from_str(data).unwrap_or(Value::Null);
+            if let Some(token) = chunk
+                .pointer("/choices/0/delta/content")
+                .and_then(Value::as_str)
+            {
+                let _ = write!(stdout, "{token}");
+                let _ = stdout.flush();
+            }
+        }
+    }
+    let _ = writeln!(stdout);
+    Ok(())
+}
+
+/// The question, after any piped stdin and `--file` contents.
+fn build_prompt(options: &AskOptions) -> Result<String, String> {
+    let mut sections = Vec::new();
+    for path in &options.files {
+        let bytes = std::fs::read(path)
+            .map_err(|error| format!("couldn't read {}: {error}", path.display()))?;
+        sections.push(format!(
+            "File: {}\n```\n{}\n```",
+            path.display(),
+            context_text(&bytes)
+        ));
+    }
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        let mut piped = Vec::new();
+        stdin
+            .lock()
+            .read_to_end(&mut piped)
+            .map_err(|error| format!("couldn't read stdin: {error}"))?;
+        if !piped.is_empty() {
+            sections.push(format!("Input:\n```\n{}\n```", context_text(&piped)));
+        }
+    }
+
+    let question = options.question.trim();
+    if question.is_empty() && sections.is_empty() {
+        return Err("nothing to ask".to_string());
+    }
+    if !question.is_empty() {
+        sections.push(question.to_string());
+    }
+    Ok(sections.join("\n\n"))
+}
+
+fn context_text(bytes: &[u8]) -> String {
+    let mut text = String::from_utf8_lossy(&bytes[..bytes.len().min(MAX_CONTEXT_FILE_BYTES)])
+        .trim_end()
+        .to_string();
+    if bytes.len() > MAX_CONTEXT_FILE_BYTES {
+        text.push_str("\n[...]");
+    }
+    text
+}
+
+/// Port and token from the flags, the environment, or the file the running
+/// app writes when its local API starts.
+fn connection(options: &AskOptions) -> Result<(u16, String), String> {
+    let discovery = app_data_dir()
+        .map(|dir| dir.join(LOCAL_API_DISCOVERY_FILE))
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str::<LocalApiDiscovery>(&contents).ok());
+    let token = options
+        .token
+        .clone()
+        .or_else(|| discovery.as_ref().map(|found| found.token.clone()))
+        .ok_or_else(|| {
+            "Sarah's local API isn't running. Turn it on in Settings, or pass --token.".to_string()
+        })?;
+    let port = options
+        .port
+        .or_else(|| discovery.as_ref().map(|found| found.port))
+        .unwrap_or(DEFAULT_LOCAL_API_PORT);
+    Ok((port, token))
+}
+
+/// Tauri's `app_data_dir`, worked out without a running app.
+fn app_data_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+            })
+    };
+    base.map(|base| base.join(APP_IDENTIFIER))
+}
+
+/// Release builds use the GUI subsystem on Windows and start without a
+/// console; borrow the terminal's so output shows up there.
+#[cfg(windows)]
+fn attach_parent_console() {
+    use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+    unsafe {
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
@@ -0,0 +1,269 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use arboard::{Clipboard, ImageData};
+use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
+
+use crate::error::AppError;
+use crate::state::AppState;
+
+const CLIPBOARD_SETTINGS_NAMESPACE: &str = "overlay";
+const USE_CLIPBOARD_CONTEXT_KEY: &str = "use_clipboard_context";
+pub const CLIPBOARD_CONTEXT_EVENT: &str = "sarah://clipboard-context";
+/// Copied text beyond this is cut so it can't crowd out the conversation.
+pub const MAX_CLIPBOARD_CHARS: usize = 12_000;
+/// How long the focused app gets to answer a simulated copy.
+const COPY_TIMEOUT: Duration = Duration::from_millis(600);
+const COPY_POLL_INTERVAL: Duration = Duration::from_millis(25);
+/// How long the focused app gets to read the clipboard after a simulated paste,
+/// before the user's own clipboard is put back.
+const PASTE_SETTLE: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardContext {
+    pub text: String,
+    /// Length of the copied text before it was cut.
+    pub char_count: usize,
+    pub truncated: bool,
+}
+
+/// What was on the clipboard before a simulated copy or paste borrowed it.
+enum SavedClipboard {
+    Empty,
+    Text(String),
+    /// Rich text, with the plain text apps that don't take HTML fall back to.
+    Html {
+        html: String,
+        alt_text: String,
+    },
+    Image(ImageData<'static>),
+}
+
+/// The clipboard's text, if it holds any.
+pub fn read_text() -> Result<Option<String>, String> {
+    let mut clipboard =
+        Clipboard::new().map_err(|error| format!("Failed to open clipboard: {error}"))?;
+    match clipboard.get_text() {
+        Ok(text) if !text.trim().is_empty() => Ok(Some(text)),
+        Ok(_) | Err(arboard::Error::ContentNotAvailable) => Ok(None),
+        Err(error) => Err(format!("Failed to read clipboard: {error}")),
+    }
+}
+
+pub fn write_text(text: &str) -> Result<(), String> {
+    Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|error| format!("Failed to write clipboard: {error}"))
+}
+
+/// Copies the focused app's selection by pressing the copy shortcut for it,
+/// then puts the user's clipboard back. `None` when nothing was selected.
+/// Blocks while the app answers.
+pub fn copy_selection() -> Result<Option<String>, String> {
+    let mut clipboard =
+        Clipboard::new().map_err(|error| format!("Failed to open clipboard: {error}"))?;
+    let previous = save(&mut clipboard)?;
+    // Cleared first, so an unchanged clipboard can't pass for the selection.
+    clipboard
+        .clear()
+        .map_err(|error| format!("Failed to clear clipboard: {error}"))?;
+
+    let pressed = press_shortcut('c');
+    let deadline = Instant::now() + COPY_TIMEOUT;
+    let mut selection = None;
+    while pressed.is_ok() && Instant::now() < deadline {
+        thread::sleep(COPY_POLL_INTERVAL);
+        if let Ok(text) = clipboard.get_text() {
+            if !text.trim().is_empty() {
+                selection = Some(text);
+                break;
+            }
+        }
+    }
+
+    restore(&mut clipboard, previous);
+    pressed?;
+    Ok(selection)
+}
+
+/// Types `text` over the focused app's selection by pasting it, then puts the
+/// user's clipboard back.
+pub fn paste_text(text: &str) -> Result<(), String> {
+    let mut clipboard =
+        Clipboard::new().map_err(|error| format!("Failed to open clipboard: {error}"))?;
+    let previous = save(&mut clipboard)?;
+    clipboard
+        .set_text(text)
+        .map_err(|error| format!("Failed to write clipboard: {error}"))?;
+
+    let pressed = press_shortcut('v');
+    thread::sleep(PASTE_SETTLE);
+    restore(&mut clipboard, previous);
+    pressed
+}
+
+/// Presses the platform's command modifier with `key`. Modifiers still held
+/// from a global shortcut are let go first, or Ctrl+C would arrive as
+/// Ctrl+Alt+C.
+fn press_shortcut(key: char) -> Result<(), String> {
+    let mut enigo = Enigo::new(&Settings::default())
+        .map_err(|error| format!("Failed to start keyboard input: {error}"))?;
+    let modifier = if cfg!(target_os = "macos") {
+        Key::Meta
+    } else {
+        Key::Control
+    };
+    let send = |enigo: &mut Enigo| -> Result<(), enigo::InputError> {
+        for held in [Key::Shift, Key::Alt, Key::Meta, Key::Control] {
+            enigo.key(held, Direction::Release)?;
+        }
+        enigo.key(modifier, Direction::Press)?;
+        enigo.key(Key::Unicode(key), Direction::Click)?;
+        enigo.key(modifier, Direction::Release)
+    };
+    send(&mut enigo).map_err(|error| format!("Failed to send keyboard input: {error}"))
+}
+
+/// Reads what `restore` needs to put the clipboard back. Contents it couldn't
+/// put back, such as copied files, are refused rather than lost.
+fn save(clipboard: &mut Clipboard) -> Result<SavedClipboard, String> {
+    // File managers often offer the paths as text too, so files go first.
+    if clipboard
+        .get()
+        .file_list()
+        .is_ok_and(|files| !files.is_empty())
+    {
+        return Err(
+            "The clipboard holds copied files. Paste them first, then try again.".to_string(),
+        );
+    }
+    match clipboard.get_text() {
+        Ok(text) => Ok(match clipboard.get().html() {
+            Ok(html) => SavedClipboard::Html {
+                html,
+                alt_text: text,
+            },
+            Err(_) => SavedClipboard::Text(text),
+        }),
+        Err(arboard::Error::ContentNotAvailable) => match clipboard.get_image() {
+            Ok(image) => Ok(SavedClipboard::Image(image)),
+            Err(arboard::Error::ContentNotAvailable) => Ok(SavedClipboard::Empty),
+            Err(error) => Err(format!("Failed to read clipboard: {error}")),
+        },
+        Err(error) => Err(format!("Failed to read clipboard: {error}")),
+    }
+}
+
+fn restore(clipboard: &mut Clipboard, previous: SavedClipboard) {
+    let result = match previous {
+        SavedClipboard::Empty => clipboard.clear(),
+        SavedClipboard::Text(text) => clipboard.set_text(text),
+        SavedClipboard::Html { html, alt_text } => clipboard.set_html(html, Some(alt_text)),
+        SavedClipboard::Image(image) => clipboard.set_image(image),
+    };
+    if let Err(error) = result {
+        crate::log_warn!("sarah.clipboard", "Failed to restore clipboard: {}", error);
+    }
+}
+
+/// The copied text, cut to what's sent along with a prompt.
+#[tauri::command]
+pub fn read_clipboard_context() -> Result<Option<ClipboardContext>, String> {
+    crate::log_info!("sarah.command", "read_clipboard_context invoked");
+    Ok(read_text()?.map(|text| {
+        let char_count = text.chars().count();
+        ClipboardContext {
+            text: text.chars().take(MAX_CLIPBOARD_CHARS).collect(),
+            char_count,
+            truncated: char_count > MAX_CLIPBOARD_CHARS,
+        }
+    }))
+}
+
+#[tauri::command]
+pub async fn get_use_clipboard_context(state: State<'_, Arc<AppState>>) -> Result<bool, AppError> {
+    crate::log_info!("sarah.command", "get_use_clipboard_context invoked");
+    Ok(state
+        .settings_repo
+        .get_setting(
+            None,
+            CLIPBOARD_SETTINGS_NAMESPACE,
+            USE_CLIPBOARD_CONTEXT_KEY,
+        )
+        .await?
+        .is_some_and(|setting| setting.value == "true"))
+}
+
+/// With this on, opening the overlay attaches whatever text is on the clipboard.
+#[tauri::command]
+pub async fn set_use_clipboard_context(
+    state: State<'_, Arc<AppState>>,
+    enabled: bool,
+) -> Result<bool, AppError> {
+    crate::log_info!("sarah.command", "set_use_clipboard_context invoked");
+    state
+        .settings_repo
+        .upsert_setting(
+            None,
+            CLIPBOARD_SETTINGS_NAMESPACE,
+            USE_CLIPBOARD_CONTEXT_KEY,
+            if enabled { "true" } else { "false" },
+            "bool",
+            false,
+        )
+        .await?;
+    Ok(enabled)
+}
+
+/// Ctrl+Shift+Space opens the overlay with the clipboard attached, whatever
+/// `use_clipboard_context` is set to.
+pub fn register_clipboard_shortcut(app: &AppHandle) {
+    let shortcut = Shortcut::new(Some(Modifiers::CONTROL | Modifiers::SHIFT), Code::Space);
+    let result = app
+        .global_shortcut()
+        .on_shortcut(shortcut, |app, _shortcut, event| {
+            if event.state != ShortcutState::Pressed {
+                return;
+            }
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let context = match read_clipboard_context() {
+                    Ok(context) => context,
+                    Err(error) => {
+                        crate::log_warn!("sarah.clipboard", "{}", error);
+                        None
+                    }
+                };
+                crate::overlay_position::place_main_window(&app).await;
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.show();
+                    let _ = window.set_focus();
+                }
+                let _ = app.emit(CLIPBOARD_CONTEXT_EVENT, context);
+            });
+        });
+    if let Err(error) = result {
+        crate::log_warn!(
+            "sarah.clipboard",
+            "Failed to register clipboard shortcut: {}",
+            error
+        );
+    }
+}
+
+/// Puts copied text ahead of the question it was attached to.
+pub fn clipboard_prompt(question: &str, clipboard: &str) -> String {
+    let mut text = clipboard
+        .chars()
+        .take(MAX_CLIPBOARD_CHARS)
+        .collect::<String>();
+    if clipboard.chars().count() > MAX_CLIPBOARD_CHARS {
+        text.push_str("\n[...]");
+    }
+    format!("Text I copied:\n\"\"\"\n{text}\n\"\"\"\n\n{question}")
+}
@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use tauri::State;
+
+use crate::error::AppError;
+use crate::services::local_api_service::{LocalApiSettings, LocalApiStatus};
+use crate::state::AppState;
+
+#[tauri::command]
+pub async fn get_local_api_status(
+    state: State<'_, Arc<AppState>>,
+) -> Result<LocalApiStatus, AppError> {
+    crate::log_info!("sarah.command", "get_local_api_status invoked");
+    state.local_api.status().await
+}
+
+/// Turns the OpenAI-compatible server on or off, or moves it to another port.
+#[tauri::command]
+pub async fn set_local_api_settings(
+    state: State<'_, Arc<AppState>>,
+    settings: LocalApiSettings,
+) -> Result<LocalApiStatus, AppError> {
+    crate::log_info!("sarah.command", "set_local_api_settings invoked");
+    state.local_api.set_settings(settings).await
+}
+
+/// The bearer token to paste into an editor or tool using the local API.
+#[tauri::command]
+pub async fn get_local_api_token(state: State<'_, Arc<AppState>>) -> Result<String, AppError> {
+    crate::log_info!("sarah.command", "get_local_api_token invoked");
+    state.local_api.token().await
+}
+
+#[tauri::command]
+pub async fn rotate_local_api_token(state: State<'_, Arc<AppState>>) -> Result<String, AppError> {
+    crate::log_info!("sarah.command", "rotate_local_api_token invoked");
+    state.local_api.rotate_token().await
+}
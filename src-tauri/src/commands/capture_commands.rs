@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use tauri::State;
+
+use crate::db::models::Capture;
+use crate::error::AppError;
+use crate::state::AppState;
+
+/// Screenshots and recordings, newest first. `capture_type` is `screenshot`
+/// or `recording`.
+#[tauri::command]
+pub async fn list_captures(
+    state: State<'_, Arc<AppState>>,
+    capture_type: Option<String>,
+    session_id: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<Capture>, AppError> {
+    crate::log_info!("sarah.command", "list_captures invoked");
+    state
+        .captures
+        .list(
+            capture_type.as_deref(),
+            session_id.as_deref(),
+            limit,
+            offset,
+        )
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_capture(
+    state: State<'_, Arc<AppState>>,
+    capture_id: String,
+) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "delete_capture invoked");
+    state.captures.delete(&capture_id).await
+}
+
+#[tauri::command]
+pub async fn reveal_capture_in_explorer(
+    state: State<'_, Arc<AppState>>,
+    capture_id: String,
+) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "reveal_capture_in_explorer invoked");
+    state.captures.reveal(&capture_id).await
+}
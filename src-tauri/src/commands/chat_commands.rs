@@ -1,12 +1,38 @@
+use std::path::Path;
 use std::sync::Arc;
 
 use tauri::State;
+use tokio_stream::wrappers::ReceiverStream;
 use tokio_stream::StreamExt;
 
-use crate::db::models::{Message, MessageSearchResult, Session};
+use crate::clipboard::clipboard_prompt;
+use crate::commands::mcp_commands::TOOL_APPROVAL_EVENT;
+use crate::db::models::{
+    Message, MessageSearchResult, MessageStreamChunk, PinContextItem, PinnedContextItem, Session,
+    SessionFilter,
+};
 use crate::error::AppError;
+use crate::native_capture::{self, CaptureSurface};
+use crate::services::conversation_service::{DEFAULT_MAX_TOOL_ROUNDS, MAX_TOOL_ROUNDS};
+use crate::services::hardware_service::PerformanceMode;
+use crate::services::history_search::HistorySearchHit;
+use crate::services::ocr::{self, OcrText};
+use crate::services::runtime_orchestrator_service::resolve_requested_qos;
+use crate::services::session_export::{
+    archive_entry_name, export_file_name, render_session_export, render_session_html,
+    write_zip_archive, ExportFormat, SessionExport,
+};
+use crate::services::stream_coalescer::{CoalescePolicy, TokenCoalescer};
 use crate::state::AppState;
 
+const MAX_PINNED_ITEMS: usize = 20;
+const MAX_PINNED_NOTE_CHARS: usize = 8000;
+const MAX_SESSION_TAGS: usize = 16;
+const MAX_SESSION_TAG_CHARS: usize = 40;
+/// Screen text beyond this is cut so it can't crowd out the conversation.
+const MAX_SCREEN_TEXT_CHARS: usize = 6000;
+const DEFAULT_SCREEN_QUESTION: &str = "Describe what's on my screen.";
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SendMessageRequest {
@@ -18,7 +44,11 @@ pub struct SendMessageRequest {
     pub selected_model: Option<String>,
     pub task_type: Option<String>,
     pub qos: Option<String>,
+    pub response_mode: Option<String>,
     pub allow_background_defer: Option<bool>,
+    pub preset: Option<String>,
+    /// Copied text sent ahead of `content`, from clipboard-context mode.
+    pub clipboard_context: Option<String>,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -31,40 +61,48 @@ pub struct SendMessageResponse {
 #[tauri::command]
 pub async fn send_message(
     app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
     state: State<'_, Arc<AppState>>,
     request: SendMessageRequest,
 ) -> Result<SendMessageResponse, AppError> {
     crate::log_info!("sarah.command", "send_message invoked");
-    let mut stream = state
+    let qos = resolve_requested_qos(request.qos.as_deref(), request.response_mode.as_deref());
+    let content = match request
+        .clipboard_context
+        .as_deref()
+        .filter(|text| !text.trim().is_empty())
+    {
+        Some(clipboard) => clipboard_prompt(&request.content, clipboard),
+        None => request.content.clone(),
+    };
+    let stream = state
         .conversation
         .send_message(
             &request.user_id,
             &request.session_id,
-            &request.content,
+            &content,
             &request.attachments,
             request.model_selection_mode.as_deref(),
             request.selected_model.as_deref(),
             request.task_type.as_deref(),
-            request.qos.as_deref(),
+            qos.as_deref(),
             request.allow_background_defer.unwrap_or(false),
-            Some(app.clone()),
+            request.preset.as_deref(),
+            false,
         )
         .await?;
 
-    let session_id_clone = request.session_id.clone();
-    tokio::spawn(async move {
-        use tauri::Emitter;
-        while let Some(chunk) = stream.next().await {
-            let _ = app.emit("ai:token", serde_json::json!({
-                "sessionId": chunk.session_id,
-                "token": chunk.token,
-                "done": chunk.done,
-            }));
-        }
-        let _ = app.emit("ai:done", serde_json::json!({
-            "sessionId": session_id_clone,
-        }));
-    });
+    let mode = state
+        .hardware_service
+        .get_performance_mode(Some(&request.user_id))
+        .await;
+    forward_stream_to_window(
+        app,
+        window.label().to_string(),
+        request.session_id.clone(),
+        mode,
+        stream,
+    );
 
     Ok(SendMessageResponse {
         accepted: true,
@@ -72,33 +110,587 @@ pub async fn send_message(
     })
 }
 
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScreenQuestionRequest {
+    pub user_id: String,
+    pub session_id: String,
+    /// Sent along with the capture; defaults to asking for a description.
+    pub question: Option<String>,
+    pub surface: Option<CaptureSurface>,
+    pub window_hwnd: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DescribeScreenResponse {
+    pub accepted: bool,
+    pub session_id: String,
+    pub screenshot_path: String,
+    pub ocr: OcrText,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AskAboutScreenResponse {
+    pub accepted: bool,
+    pub session_id: String,
+    pub screenshot_path: String,
+}
+
+/// Captures the screen (or a window), reads its text and sends it with the
+/// question as the next user message. The reply streams like `send_message`.
+#[tauri::command]
+pub async fn describe_screen(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    state: State<'_, Arc<AppState>>,
+    request: ScreenQuestionRequest,
+) -> Result<DescribeScreenResponse, AppError> {
+    crate::log_info!("sarah.command", "describe_screen invoked");
+    let screenshot_path = capture_screen(&state, &request).await?;
+    let recognized = ocr::recognize_text(Path::new(&screenshot_path)).await?;
+
+    let content = screen_prompt(screen_question(&request), &recognized.text);
+    let stream = state
+        .conversation
+        .send_message(
+            &request.user_id,
+            &request.session_id,
+            &content,
+            &[],
+            None,
+            None,
+            None,
+            None,
+            false,
+            None,
+            false,
+        )
+        .await?;
+
+    let mode = state
+        .hardware_service
+        .get_performance_mode(Some(&request.user_id))
+        .await;
+    forward_stream_to_window(
+        app,
+        window.label().to_string(),
+        request.session_id.clone(),
+        mode,
+        stream,
+    );
+
+    Ok(DescribeScreenResponse {
+        accepted: true,
+        session_id: request.session_id,
+        screenshot_path,
+        ocr: recognized,
+    })
+}
+
+/// Captures the screen (or a window) and sends the question with the
+/// screenshot attached, so a vision model looks at it and other models get its
+/// OCR text. The screenshot stays on the stored message for later reference.
+#[tauri::command]
+pub async fn ask_about_screen(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    state: State<'_, Arc<AppState>>,
+    request: ScreenQuestionRequest,
+) -> Result<AskAboutScreenResponse, AppError> {
+    crate::log_info!("sarah.command", "ask_about_screen invoked");
+    let screenshot_path = capture_screen(&state, &request).await?;
+
+    let stream = state
+        .conversation
+        .send_message(
+            &request.user_id,
+            &request.session_id,
+            screen_question(&request),
+            std::slice::from_ref(&screenshot_path),
+            None,
+            None,
+            None,
+            None,
+            false,
+            None,
+            false,
+        )
+        .await?;
+
+    let mode = state
+        .hardware_service
+        .get_performance_mode(Some(&request.user_id))
+        .await;
+    forward_stream_to_window(
+        app,
+        window.label().to_string(),
+        request.session_id.clone(),
+        mode,
+        stream,
+    );
+
+    Ok(AskAboutScreenResponse {
+        accepted: true,
+        session_id: request.session_id,
+        screenshot_path,
+    })
+}
+
+/// Takes the screenshot a screen question is about, files it in the capture
+/// library under the session, and returns its path.
+async fn capture_screen(
+    state: &AppState,
+    request: &ScreenQuestionRequest,
+) -> Result<String, AppError> {
+    let surface = request.surface.unwrap_or(CaptureSurface::Screen);
+    let window_hwnd = request.window_hwnd.clone();
+    let screenshot = tokio::task::spawn_blocking(move || {
+        native_capture::capture_screenshot(surface, window_hwnd, None)
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?
+    .map_err(AppError::Io)?;
+    if let Err(error) = state
+        .captures
+        .record_screenshot(Path::new(&screenshot.screenshot_path), Some(&request.session_id))
+        .await
+    {
+        crate::log_warn!("sarah.capture", "Failed to add screenshot to library: {}", error);
+    }
+    Ok(screenshot.screenshot_path)
+}
+
+fn screen_question(request: &ScreenQuestionRequest) -> &str {
+    request
+        .question
+        .as_deref()
+        .map(str::trim)
+        .filter(|question| !question.is_empty())
+        .unwrap_or(DEFAULT_SCREEN_QUESTION)
+}
+
+fn screen_prompt(question: &str, screen_text: &str) -> String {
+    if screen_text.trim().is_empty() {
+        return format!("{question}\n\n(No readable text was found on my screen.)");
+    }
+    let mut text = screen_text.chars().take(MAX_SCREEN_TEXT_CHARS).collect::<String>();
+    if screen_text.chars().count() > MAX_SCREEN_TEXT_CHARS {
+        text.push_str("\n[...]");
+    }
+    format!("{question}\n\nText read from my screen:\n\"\"\"\n{text}\n\"\"\"")
+}
+
+/// Streams a new answer to the turn behind `message_id` like `send_message`,
+/// storing it as another variant of that turn's reply.
+#[tauri::command]
+pub async fn regenerate_message(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    message_id: String,
+    model_override: Option<String>,
+) -> Result<SendMessageResponse, AppError> {
+    crate::log_info!("sarah.command", "regenerate_message invoked");
+    let stream = state
+        .conversation
+        .regenerate_message(&session_id, &message_id, model_override.as_deref())
+        .await?;
+
+    let mode = state.hardware_service.get_performance_mode(None).await;
+    forward_stream_to_window(
+        app,
+        window.label().to_string(),
+        session_id.clone(),
+        mode,
+        stream,
+    );
+
+    Ok(SendMessageResponse {
+        accepted: true,
+        session_id,
+    })
+}
+
+/// Rewrites a user message and starts a new branch from it: later messages
+/// leave the conversation. With `regenerate` the edited turn is answered
+/// again, streamed like `send_message`.
+#[tauri::command]
+pub async fn edit_message(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    state: State<'_, Arc<AppState>>,
+    message_id: String,
+    new_content: String,
+    regenerate: Option<bool>,
+) -> Result<Message, AppError> {
+    crate::log_info!("sarah.command", "edit_message invoked");
+    if new_content.trim().is_empty() {
+        return Err(AppError::Validation {
+            field: "new_content".to_string(),
+            message: "Message cannot be empty".to_string(),
+        });
+    }
+    let message = state
+        .conversation_repo
+        .get_message_by_id(&message_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound {
+            entity: "message".to_string(),
+            id: message_id.clone(),
+        })?;
+    if message.role != "user" {
+        return Err(AppError::Validation {
+            field: "message_id".to_string(),
+            message: "Only user messages can be edited".to_string(),
+        });
+    }
+
+    let edited = state
+        .conversation_repo
+        .edit_message(&message_id, &new_content)
+        .await?;
+
+    if regenerate.unwrap_or(true) {
+        let stream = state
+            .conversation
+            .regenerate_message(&edited.session_id, &edited.id, None)
+            .await?;
+        let mode = state.hardware_service.get_performance_mode(None).await;
+        forward_stream_to_window(
+            app,
+            window.label().to_string(),
+            edited.session_id.clone(),
+            mode,
+            stream,
+        );
+    }
+
+    Ok(edited)
+}
+
+#[tauri::command]
+pub async fn list_message_variants(
+    state: State<'_, Arc<AppState>>,
+    message_id: String,
+) -> Result<Vec<Message>, AppError> {
+    crate::log_info!("sarah.command", "list_message_variants invoked");
+    state.conversation.message_variants(&message_id).await
+}
+
+/// Shows `message_id` in place of its sibling replies and uses it as history
+/// for later turns.
+#[tauri::command]
+pub async fn select_message_variant(
+    state: State<'_, Arc<AppState>>,
+    message_id: String,
+) -> Result<Message, AppError> {
+    crate::log_info!("sarah.command", "select_message_variant invoked");
+    state.conversation_repo.select_variant(&message_id).await?;
+    state
+        .conversation_repo
+        .get_message_by_id(&message_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound {
+            entity: "message".to_string(),
+            id: message_id,
+        })
+}
+
+/// Answers in one piece, letting the model call active MCP tools for up to
+/// `max_rounds` rounds first. Tool progress is emitted as `sarah://tool-call`.
+#[tauri::command]
+pub async fn send_agent_message(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+    session_id: String,
+    content: String,
+    max_rounds: Option<u32>,
+) -> Result<Message, AppError> {
+    use tauri::Emitter;
+
+    crate::log_info!("sarah.command", "send_agent_message invoked");
+    if content.trim().is_empty() {
+        return Err(AppError::Validation {
+            field: "content".to_string(),
+            message: "Message cannot be empty".to_string(),
+        });
+    }
+    let max_rounds = max_rounds
+        .map(|rounds| rounds as usize)
+        .unwrap_or(DEFAULT_MAX_TOOL_ROUNDS)
+        .clamp(1, MAX_TOOL_ROUNDS);
+    let window_label = window.label().to_string();
+
+    state
+        .conversation
+        .send_agent_message(
+            &user_id,
+            &session_id,
+            &content,
+            max_rounds,
+            |event| {
+                let _ = app.emit_to(window_label.as_str(), "sarah://tool-call", event);
+            },
+            |request| {
+                let _ = app.emit(TOOL_APPROVAL_EVENT, request);
+            },
+        )
+        .await
+}
+
+/// One-off generation constrained to `json_schema`, for intent extraction and
+/// tool arguments. Needs a loaded model but no session.
+#[tauri::command]
+pub async fn generate_structured(
+    state: State<'_, Arc<AppState>>,
+    prompt: String,
+    json_schema: serde_json::Value,
+) -> Result<serde_json::Value, AppError> {
+    crate::log_info!("sarah.command", "generate_structured invoked");
+    if prompt.trim().is_empty() {
+        return Err(AppError::Validation {
+            field: "prompt".to_string(),
+            message: "Prompt cannot be empty".to_string(),
+        });
+    }
+    state
+        .inference
+        .generate_structured(&prompt, &json_schema)
+        .await
+}
+
+/// Relays a generation stream to the requesting window as coalesced `ai:token` batches.
+/// With read-aloud on for the session's owner, finished sentences are spoken too.
+pub(crate) fn forward_stream_to_window(
+    app: tauri::AppHandle,
+    window_label: String,
+    session_id: String,
+    mode: PerformanceMode,
+    mut stream: ReceiverStream<MessageStreamChunk>,
+) {
+    tokio::spawn(async move {
+        use tauri::{Emitter, Manager};
+
+        let mut read_aloud = match app.try_state::<Arc<AppState>>() {
+            Some(state) => state.tts.read_aloud_for_session(&session_id).await,
+            None => None,
+        };
+
+        let emit_token = |token: String, done: bool| {
+            let _ = app.emit_to(
+                window_label.as_str(),
+                "ai:token",
+                serde_json::json!({
+                    "sessionId": session_id,
+                    "token": token,
+                    "done": done,
+                }),
+            );
+        };
+
+        let mut coalescer = TokenCoalescer::new(CoalescePolicy::for_mode(&mode));
+        let mut ticker = tokio::time::interval(coalescer.flush_interval());
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut finish_reason = None;
+        let mut citations = Vec::new();
+
+        loop {
+            tokio::select! {
+                chunk = stream.next() => match chunk {
+                    Some(chunk) if !chunk.done => {
+                        if let Some(reader) = read_aloud.as_mut() {
+                            reader.push(&chunk.token);
+                        }
+                        if let Some(batch) = coalescer.push(&chunk.token) {
+                            emit_token(batch, false);
+                        }
+                    }
+                    Some(chunk) => {
+                        finish_reason = chunk.finish_reason;
+                        citations = chunk.citations;
+                        break;
+                    }
+                    None => break,
+                },
+                _ = ticker.tick() => {
+                    if let Some(batch) = coalescer.take() {
+                        emit_token(batch, false);
+                    }
+                }
+            }
+        }
+
+        emit_token(coalescer.take().unwrap_or_default(), true);
+        if let Some(reader) = read_aloud {
+            reader.finish();
+        }
+        let _ = app.emit_to(
+            window_label.as_str(),
+            "ai:done",
+            serde_json::json!({
+                "sessionId": session_id,
+                "finishReason": finish_reason,
+                "citations": citations,
+            }),
+        );
+    });
+}
+
+#[tauri::command]
+pub async fn stop_generation(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<bool, AppError> {
+    crate::log_info!("sarah.command", "stop_generation invoked");
+    Ok(state.inference.cancel_generation(&session_id))
+}
+
 #[tauri::command]
 pub async fn create_session(
+    app: tauri::AppHandle,
     state: State<'_, Arc<AppState>>,
     user_id: String,
     model_id: Option<String>,
 ) -> Result<Session, AppError> {
     crate::log_info!("sarah.command", "create_session invoked");
-    state
+    let session = state
         .conversation_repo
         .create_session(&user_id, model_id.as_deref())
+        .await?;
+    crate::tray::refresh(&app);
+
+    // New chats join whichever workspace is active so they share its documents and defaults.
+    match state.workspace_repo.active_workspace(&user_id).await? {
+        Some(workspace) => {
+            state
+                .conversation_repo
+                .set_session_workspace(&session.id, Some(&workspace.id))
+                .await?;
+            Ok(Session {
+                workspace_id: Some(workspace.id),
+                ..session
+            })
+        }
+        None => Ok(session),
+    }
+}
+
+/// Copies the conversation up to `message_id` into a new session so another
+/// direction can be explored without touching the original.
+#[tauri::command]
+pub async fn fork_session(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    message_id: String,
+) -> Result<Session, AppError> {
+    crate::log_info!("sarah.command", "fork_session invoked");
+    state
+        .conversation_repo
+        .fork_session(&session_id, &message_id)
         .await
 }
 
+#[tauri::command]
+pub async fn set_last_session(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "set_last_session invoked");
+    state.launch_state.set_last_session(&session_id).await
+}
+
+#[tauri::command]
+pub async fn get_last_session(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Option<Session>, AppError> {
+    crate::log_info!("sarah.command", "get_last_session invoked");
+    state.launch_state.last_session().await
+}
+
+/// Lists sessions pinned-first, optionally narrowed by tags, pin or archive status.
 #[tauri::command]
 pub async fn list_sessions(
     state: State<'_, Arc<AppState>>,
     user_id: String,
     cursor: Option<String>,
     limit: Option<i64>,
+    filter: Option<SessionFilter>,
 ) -> Result<Vec<Session>, AppError> {
     crate::log_info!("sarah.command", "list_sessions invoked");
+    let mut filter = filter.unwrap_or_default();
+    filter.tags = normalize_tags(filter.tags);
+    if let Some(status) = filter.status.as_deref() {
+        if !matches!(status, "active" | "archived") {
+            return Err(AppError::Validation {
+                field: "status".to_string(),
+                message: format!("Unknown session status '{status}'"),
+            });
+        }
+    }
     state
         .conversation_repo
-        .list_sessions(&user_id, limit.unwrap_or(50).min(100), cursor.as_deref())
+        .list_sessions(&user_id, &filter, limit.unwrap_or(50).min(100), cursor.as_deref())
         .await
 }
 
+/// Replaces the session's tags. Tags are trimmed, lowercased and de-duplicated;
+/// the stored list is returned.
+#[tauri::command]
+pub async fn set_session_tags(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    tags: Vec<String>,
+) -> Result<Vec<String>, AppError> {
+    crate::log_info!("sarah.command", "set_session_tags invoked");
+    let tags = normalize_tags(tags);
+    if tags.len() > MAX_SESSION_TAGS {
+        return Err(AppError::Validation {
+            field: "tags".to_string(),
+            message: format!("A session can have at most {MAX_SESSION_TAGS} tags"),
+        });
+    }
+    if let Some(tag) = tags
+        .iter()
+        .find(|tag| tag.chars().count() > MAX_SESSION_TAG_CHARS)
+    {
+        return Err(AppError::Validation {
+            field: "tags".to_string(),
+            message: format!("Tag '{tag}' is longer than {MAX_SESSION_TAG_CHARS} characters"),
+        });
+    }
+    state
+        .conversation_repo
+        .set_session_tags(&session_id, &tags)
+        .await?;
+    Ok(tags)
+}
+
+/// Pins or unpins the session; returns whether it is now pinned.
+#[tauri::command]
+pub async fn toggle_session_pin(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<bool, AppError> {
+    crate::log_info!("sarah.command", "toggle_session_pin invoked");
+    state.conversation_repo.toggle_session_pin(&session_id).await
+}
+
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
 #[tauri::command]
 pub async fn get_session_messages(
     state: State<'_, Arc<AppState>>,
@@ -113,13 +705,338 @@ pub async fn get_session_messages(
         .await
 }
 
+/// Writes the session to a standalone HTML file picked through the native save dialog.
+/// Returns the saved path, or `None` if the dialog was cancelled.
+#[tauri::command]
+pub async fn share_session(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<Option<String>, AppError> {
+    crate::log_info!("sarah.command", "share_session invoked");
+    let session = state
+        .conversation_repo
+        .get_session(&session_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound {
+            entity: "session".to_string(),
+            id: session_id.clone(),
+        })?;
+    let messages = state
+        .conversation_repo
+        .get_messages(&session_id, 10_000, 0)
+        .await?;
+    let model_names = model_display_names(&state).await?;
+
+    let html = render_session_html(&session, &messages, &model_names);
+
+    let Some(file) = rfd::AsyncFileDialog::new()
+        .set_title("Share conversation")
+        .set_file_name(export_file_name(&session))
+        .add_filter("HTML", &["html"])
+        .save_file()
+        .await
+    else {
+        return Ok(None);
+    };
+
+    let path = file.path().to_path_buf();
+    tokio::fs::write(&path, html).await?;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+/// Writes one session, including replaced variants, tool calls and timestamps, to
+/// `path` as `markdown`, `json` or `html`. Returns the path actually written.
+#[tauri::command]
+pub async fn export_session(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    format: String,
+    path: String,
+) -> Result<String, AppError> {
+    crate::log_info!("sarah.command", "export_session invoked");
+    let format = parse_export_format(&format)?;
+    let path = export_target(&path, format.extension())?;
+    let session = state
+        .conversation_repo
+        .get_session(&session_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound {
+            entity: "session".to_string(),
+            id: session_id.clone(),
+        })?;
+    let export = load_session_export(&state, session).await?;
+    let model_names = model_display_names(&state).await?;
+
+    let contents = render_session_export(&export, format, &model_names)?;
+    tokio::fs::write(&path, contents).await?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportArchiveResponse {
+    pub path: String,
+    pub session_count: usize,
+}
+
+/// Bundles every non-deleted session of the user into one zip archive, one file
+/// per session in `format` (JSON unless given).
+#[tauri::command]
+pub async fn export_all_sessions(
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+    format: Option<String>,
+    path: String,
+) -> Result<ExportArchiveResponse, AppError> {
+    crate::log_info!("sarah.command", "export_all_sessions invoked");
+    let format = parse_export_format(format.as_deref().unwrap_or("json"))?;
+    let path = export_target(&path, "zip")?;
+    let sessions = state
+        .conversation_repo
+        .list_sessions(&user_id, &SessionFilter::default(), i64::MAX, None)
+        .await?;
+    let model_names = model_display_names(&state).await?;
+
+    let mut entries = Vec::with_capacity(sessions.len());
+    for session in sessions {
+        let export = load_session_export(&state, session).await?;
+        entries.push((
+            archive_entry_name(&export.session, format),
+            render_session_export(&export, format, &model_names)?,
+        ));
+    }
+
+    let session_count = entries.len();
+    let archive_path = path.clone();
+    tokio::task::spawn_blocking(move || write_zip_archive(&archive_path, &entries))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))??;
+
+    Ok(ExportArchiveResponse {
+        path: path.to_string_lossy().to_string(),
+        session_count,
+    })
+}
+
+async fn load_session_export(
+    state: &AppState,
+    session: Session,
+) -> Result<SessionExport, AppError> {
+    let messages = state.conversation_repo.get_message_tree(&session.id).await?;
+    let tool_calls = state
+        .conversation_repo
+        .list_session_tool_calls(&session.id)
+        .await?;
+    Ok(SessionExport {
+        session,
+        messages,
+        tool_calls,
+    })
+}
+
+async fn model_display_names(
+    state: &AppState,
+) -> Result<std::collections::HashMap<String, String>, AppError> {
+    Ok(state
+        .model_repo
+        .list_all()
+        .await?
+        .into_iter()
+        .map(|model| (model.id, model.display_name))
+        .collect())
+}
+
+fn parse_export_format(format: &str) -> Result<ExportFormat, AppError> {
+    ExportFormat::parse(format).ok_or_else(|| AppError::Validation {
+        field: "format".to_string(),
+        message: format!("Unsupported export format '{format}'; use markdown, json or html"),
+    })
+}
+
+/// Resolves the destination, adding `extension` when the path has none.
+pub(crate) fn export_target(path: &str, extension: &str) -> Result<std::path::PathBuf, AppError> {
+    let mut target = std::path::PathBuf::from(path.trim());
+    if target.file_name().is_none() {
+        return Err(AppError::Validation {
+            field: "path".to_string(),
+            message: "Export path must name a file".to_string(),
+        });
+    }
+    if let Some(parent) = target.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        if !parent.is_dir() {
+            return Err(AppError::Validation {
+                field: "path".to_string(),
+                message: format!("Folder '{}' does not exist", parent.display()),
+            });
+        }
+    }
+    if target.extension().is_none() {
+        target.set_extension(extension);
+    }
+    Ok(target)
+}
+
+/// Archives the session, or restores it with `archived: false`.
 #[tauri::command]
 pub async fn archive_session(
+    app: tauri::AppHandle,
     state: State<'_, Arc<AppState>>,
     session_id: String,
+    archived: Option<bool>,
 ) -> Result<(), AppError> {
     crate::log_info!("sarah.command", "archive_session invoked");
-    state.conversation_repo.archive_session(&session_id).await
+    state
+        .conversation_repo
+        .set_session_archived(&session_id, archived.unwrap_or(true))
+        .await?;
+    crate::tray::refresh(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_session_preset(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    preset: Option<String>,
+) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "set_session_preset invoked");
+    // Resolve first so a typo is rejected instead of silently pinned.
+    let preset = match preset.as_deref().filter(|name| !name.trim().is_empty()) {
+        Some(name) => Some(state.generation_presets.get(name).await?.name),
+        None => None,
+    };
+    state
+        .conversation_repo
+        .set_session_preset(&session_id, preset.as_deref())
+        .await
+}
+
+/// Pins a document, chunk or note so every turn of the session sees it ahead of
+/// retrieved chunks.
+#[tauri::command]
+pub async fn pin_context_item(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    item: PinContextItem,
+) -> Result<PinnedContextItem, AppError> {
+    crate::log_info!("sarah.command", "pin_context_item invoked");
+    if state.conversation_repo.get_session(&session_id).await?.is_none() {
+        return Err(AppError::NotFound {
+            entity: "session".to_string(),
+            id: session_id,
+        });
+    }
+
+    let existing = state
+        .conversation_repo
+        .list_context_pins(&session_id)
+        .await?;
+    if existing.len() >= MAX_PINNED_ITEMS {
+        return Err(AppError::Validation {
+            field: "item".to_string(),
+            message: format!("A conversation can have at most {MAX_PINNED_ITEMS} pinned items"),
+        });
+    }
+
+    let label = match &item {
+        PinContextItem::Document { document_id } => {
+            if existing
+                .iter()
+                .any(|pin| pin.document_id.as_deref() == Some(document_id.as_str()))
+            {
+                return Err(AppError::Validation {
+                    field: "documentId".to_string(),
+                    message: "This document is already pinned".to_string(),
+                });
+            }
+            state
+                .document_repo
+                .get_document(document_id)
+                .await?
+                .filter(|document| document.is_deleted == 0)
+                .ok_or_else(|| AppError::NotFound {
+                    entity: "document".to_string(),
+                    id: document_id.clone(),
+                })?
+                .title
+        }
+        PinContextItem::Chunk { chunk_id } => {
+            if existing
+                .iter()
+                .any(|pin| pin.chunk_id.as_deref() == Some(chunk_id.as_str()))
+            {
+                return Err(AppError::Validation {
+                    field: "chunkId".to_string(),
+                    message: "This passage is already pinned".to_string(),
+                });
+            }
+            let chunk = state
+                .document_repo
+                .get_chunk(chunk_id)
+                .await?
+                .ok_or_else(|| AppError::NotFound {
+                    entity: "chunk".to_string(),
+                    id: chunk_id.clone(),
+                })?;
+            match chunk.section_title.filter(|title| !title.trim().is_empty()) {
+                Some(title) => title,
+                None => state
+                    .document_repo
+                    .get_document(&chunk.document_id)
+                    .await?
+                    .map(|document| document.title)
+                    .unwrap_or_else(|| "passage".to_string()),
+            }
+        }
+        PinContextItem::Note { text, label } => {
+            let text = text.trim();
+            if text.is_empty() {
+                return Err(AppError::Validation {
+                    field: "text".to_string(),
+                    message: "Note cannot be empty".to_string(),
+                });
+            }
+            if text.chars().count() > MAX_PINNED_NOTE_CHARS {
+                return Err(AppError::Validation {
+                    field: "text".to_string(),
+                    message: format!("Note must be at most {MAX_PINNED_NOTE_CHARS} characters"),
+                });
+            }
+            label
+                .as_deref()
+                .map(str::trim)
+                .filter(|label| !label.is_empty())
+                .unwrap_or("note")
+                .to_string()
+        }
+    };
+
+    state
+        .conversation_repo
+        .insert_context_pin(&session_id, &item, &label)
+        .await
+}
+
+#[tauri::command]
+pub async fn unpin_context_item(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    pin_id: String,
+) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "unpin_context_item invoked");
+    state
+        .conversation_repo
+        .delete_context_pin(&session_id, &pin_id)
+        .await
+}
+
+#[tauri::command]
+pub async fn list_pinned_context(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<Vec<PinnedContextItem>, AppError> {
+    crate::log_info!("sarah.command", "list_pinned_context invoked");
+    state.conversation_repo.list_context_pins(&session_id).await
 }
 
 #[tauri::command]
@@ -134,3 +1051,43 @@ pub async fn search_conversations(
         .search_messages(&user_id, &query)
         .await
 }
+
+/// Finds past messages by meaning rather than keywords, favouring recent ones.
+#[tauri::command]
+pub async fn semantic_search_history(
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+    query: String,
+    top_k: Option<usize>,
+) -> Result<Vec<HistorySearchHit>, AppError> {
+    crate::log_info!("sarah.command", "semantic_search_history invoked");
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    state
+        .history_search
+        .search(&user_id, &query, top_k.unwrap_or(10).clamp(1, 50))
+        .await
+}
+
+#[tauri::command]
+pub async fn rate_message(
+    state: State<'_, Arc<AppState>>,
+    message_id: String,
+    rating: i64,
+) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "rate_message invoked");
+    if !(-1..=1).contains(&rating) {
+        return Err(AppError::Validation {
+            field: "rating".to_string(),
+            message: "Rating must be -1, 0 or 1".to_string(),
+        });
+    }
+
+    state
+        .conversation_repo
+        .set_message_feedback(&message_id, rating)
+        .await?;
+    state.background.request_recommendation_refresh();
+    Ok(())
+}
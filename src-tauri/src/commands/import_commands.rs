@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::model_commands::refresh_installed_cache;
+use crate::db::models::{ImportCandidate, ImportSummary};
+use crate::error::AppError;
+use crate::state::AppState;
+
+fn home_dir(app: &AppHandle) -> Result<PathBuf, AppError> {
+    app.path()
+        .home_dir()
+        .map_err(|e| AppError::Io(format!("Failed to resolve home directory: {e}")))
+}
+
+#[tauri::command]
+pub async fn scan_for_importable_data(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<ImportCandidate>, AppError> {
+    crate::log_info!("sarah.command", "scan_for_importable_data invoked");
+    let home = home_dir(&app)?;
+    Ok(state.importer.scan(home).await)
+}
+
+#[tauri::command]
+pub async fn import_from(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+    source: String,
+    path: Option<String>,
+) -> Result<ImportSummary, AppError> {
+    crate::log_info!("sarah.command", "import_from invoked");
+    let home = home_dir(&app)?;
+    let summary = state
+        .importer
+        .import_from(&user_id, &source, home, path.map(PathBuf::from))
+        .await?;
+
+    if summary.models_imported > 0 {
+        refresh_installed_cache(state.inner()).await?;
+    }
+    Ok(summary)
+}
+
+/// Imports a ChatGPT or Claude account export; `source_format` is detected when omitted.
+#[tauri::command]
+pub async fn import_conversations(
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+    path: String,
+    source_format: Option<String>,
+) -> Result<ImportSummary, AppError> {
+    crate::log_info!("sarah.command", "import_conversations invoked");
+    state
+        .importer
+        .import_conversations(&user_id, PathBuf::from(path), source_format.as_deref())
+        .await
+}
@@ -1,14 +1,32 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
 use std::time::Duration;
 
 use serde_json::Value;
 use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Shortcut, ShortcutState};
 use tokio::process::{Child, Command};
 use tokio::sync::{oneshot, Mutex};
 
+use crate::services::launch_state_service::RESTORABLE_WINDOWS;
+use crate::services::mcp_service::StdioServerSpec;
+use crate::state::AppState;
+
 const APP_ENTRY: &str = "index.html";
+pub const AUDIO_WINDOW_LABEL: &str = "audio";
+pub const WRITING_WINDOW_LABEL: &str = "writing";
+const SPOTIFY_MCP_KEY: &str = "spotify";
+
+/// OS media keys and the audio-control action each one forwards. "toggle" is
+/// resolved to play or pause by the audio window from its live playback state.
+const MEDIA_KEYS: &[(Code, &str)] = &[
+    (Code::MediaPlayPause, "toggle"),
+    (Code::MediaTrackNext, "next"),
+    (Code::MediaTrackPrevious, "prev"),
+    (Code::MediaStop, "stop"),
+];
 
 struct SpotifyMcpProcess {
     child: Child,
@@ -100,7 +118,62 @@ async fn open_or_focus_window_async(
     .map_err(|error| format!("Failed to schedule {label} window creation: {error}"))?;
 
     rx.await
-        .map_err(|_| format!("Window task for {label} was cancelled"))?
+        .map_err(|_| format!("Window task for {label} was cancelled"))??;
+
+    if let Some(state) = app.try_state::<Arc<AppState>>() {
+        let _ = state.launch_state.set_window_open(label, true).await;
+    }
+    Ok(())
+}
+
+/// Records that an aux window was closed by the user so it isn't reopened next launch.
+pub fn forget_window_on_close(app: &AppHandle, label: &str) {
+    if !RESTORABLE_WINDOWS.contains(&label) {
+        return;
+    }
+    if let Some(state) = app.try_state::<Arc<AppState>>() {
+        let launch_state = Arc::clone(&state.launch_state);
+        let label = label.to_string();
+        tauri::async_runtime::spawn(async move {
+            let _ = launch_state.set_window_open(&label, false).await;
+        });
+    }
+}
+
+/// Reopens the aux windows that were open at shutdown and warms the last session,
+/// then tells the overlay which session to resume.
+pub async fn restore_previous_state(app: AppHandle) -> Result<(), String> {
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return Ok(());
+    };
+    let launch_state = Arc::clone(&state.launch_state);
+
+    if let Ok(Some(session)) = launch_state.prewarm_last_session().await {
+        let _ = app.emit_to(
+            "main",
+            "session:restored",
+            serde_json::json!({ "sessionId": session.id }),
+        );
+    }
+
+    let windows = launch_state
+        .open_windows()
+        .await
+        .map_err(|error| error.to_string())?;
+    for label in windows {
+        let result = match label.as_str() {
+            "history" => open_history_window(app.clone()).await,
+            "settings" => open_settings_window(app.clone()).await,
+            "models" => open_models_window(app.clone()).await,
+            "mcp" => open_mcp_window(app.clone()).await,
+            _ => Ok(()),
+        };
+        if let Err(error) = result {
+            crate::log_warn!("sarah.restore", "Failed to restore {} window: {}", label, error);
+        }
+    }
+
+    Ok(())
 }
 
 fn build_window_type_init_script(label: &str) -> String {
@@ -316,6 +389,47 @@ pub async fn emit_audio_command(app: AppHandle, action: String) -> Result<(), St
     Ok(())
 }
 
+/// Claims the OS media keys while the audio window is open. A key that another
+/// app already holds is skipped rather than failing the window.
+pub fn register_media_keys(app: &AppHandle) {
+    let shortcuts = app.global_shortcut();
+    for (code, action) in MEDIA_KEYS {
+        let shortcut = Shortcut::new(None, *code);
+        if shortcuts.is_registered(shortcut) {
+            continue;
+        }
+        let action = action.to_string();
+        let result = shortcuts.on_shortcut(shortcut, move |app, _shortcut, event| {
+            if event.state != ShortcutState::Pressed {
+                return;
+            }
+            let app = app.clone();
+            let action = action.clone();
+            tauri::async_runtime::spawn(async move {
+                let _ = emit_audio_command(app, action).await;
+            });
+        });
+        if let Err(error) = result {
+            crate::log_warn!(
+                "sarah.audio",
+                "Failed to register media key {:?}: {}",
+                code,
+                error
+            );
+        }
+    }
+}
+
+pub fn unregister_media_keys(app: &AppHandle) {
+    let shortcuts = app.global_shortcut();
+    for (code, _) in MEDIA_KEYS {
+        let shortcut = Shortcut::new(None, *code);
+        if shortcuts.is_registered(shortcut) {
+            let _ = shortcuts.unregister(shortcut);
+        }
+    }
+}
+
 #[tauri::command]
 pub fn read_spotify_config(server_root: String) -> Result<SpotifyConfigSnapshot, String> {
     let server_root = server_root.trim().to_string();
@@ -452,13 +566,43 @@ pub async fn open_mcp_window(app: AppHandle) -> Result<(), String> {
 #[tauri::command]
 pub async fn open_audio_window(app: AppHandle) -> Result<(), String> {
     crate::log_info!("sarah.command", "open_audio_window invoked");
-    open_or_focus_window_async(app, "audio", "Sarah AI Audio", 520.0, 260.0, 420.0, 220.0).await
+    open_or_focus_window_async(
+        app.clone(),
+        AUDIO_WINDOW_LABEL,
+        "Sarah AI Audio",
+        520.0,
+        260.0,
+        420.0,
+        220.0,
+    )
+    .await?;
+    register_media_keys(&app);
+    Ok(())
+}
+
+/// Small always-on-top popup for a selection writing result.
+pub async fn open_writing_window(app: AppHandle) -> Result<(), String> {
+    open_or_focus_window_async(
+        app.clone(),
+        WRITING_WINDOW_LABEL,
+        "Sarah AI",
+        460.0,
+        320.0,
+        340.0,
+        200.0,
+    )
+    .await?;
+    if let Some(window) = app.get_webview_window(WRITING_WINDOW_LABEL) {
+        let _ = window.set_always_on_top(true);
+    }
+    Ok(())
 }
 
 #[tauri::command]
 pub fn close_audio_window(app: AppHandle) -> Result<(), String> {
     crate::log_info!("sarah.command", "close_audio_window invoked");
-    if let Some(window) = app.get_webview_window("audio") {
+    unregister_media_keys(&app);
+    if let Some(window) = app.get_webview_window(AUDIO_WINDOW_LABEL) {
         window
             .close()
             .map_err(|error| format!("Failed to close audio window: {error}"))?;
@@ -630,6 +774,39 @@ pub async fn run_spotify_tool(
 
     maybe_emit_audio_event(&app, tool_name);
 
+    // Prefer the long-lived MCP connection; the per-call runners below only
+    // cover servers that can't complete the MCP handshake.
+    let entry = server_root.join("build").join("index.js");
+    let state = app.try_state::<Arc<AppState>>();
+    if let Some(state) = state.filter(|_| entry.exists()) {
+        let spec = StdioServerSpec {
+            command: node_executable().to_string(),
+            args: vec![entry.to_string_lossy().to_string()],
+            env: HashMap::new(),
+            cwd: Some(server_root.clone()),
+        };
+        match state.mcp.stdio_client(SPOTIFY_MCP_KEY, spec).await {
+            Ok(client) => {
+                let output = client
+                    .call_tool(tool_name, args, Duration::from_secs(180))
+                    .await
+                    .map_err(|error| error.to_string())?;
+                return if output.is_error {
+                    Err(output.text)
+                } else {
+                    Ok(output.text)
+                };
+            }
+            Err(error) => {
+                crate::log_warn!(
+                    "sarah.mcp",
+                    "Spotify MCP handshake failed, using per-call runner: {}",
+                    error
+                );
+            }
+        }
+    }
+
     let args_json = serde_json::to_string(&args)
         .map_err(|error| format!("Failed to serialize tool arguments: {error}"))?;
     let scripts = read_npm_scripts(&server_root).ok();
@@ -650,18 +827,17 @@ pub async fn run_spotify_tool(
         }
     }
 
-    let fallback_entry = server_root.join("build").join("index.js");
-    if !fallback_entry.exists() {
+    if !entry.exists() {
         return Err(format!(
             "No tool runner script found and fallback entry is missing: {}. Run build_spotify_mcp first.",
-            fallback_entry.display()
+            entry.display()
         ));
     }
 
     let mut command = Command::new(node_executable());
     command
         .current_dir(&server_root)
-        .arg(fallback_entry)
+        .arg(entry)
         .arg(tool_name)
         .arg(args_json);
 
@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+use tauri::State;
+
+use crate::db::models::BackgroundJob;
+use crate::error::AppError;
+use crate::state::AppState;
+
+/// Queued, running and finished background jobs, newest first. `status`
+/// narrows to one of `queued`, `running`, `completed`, `failed` or
+/// `cancelled`.
+#[tauri::command]
+pub async fn list_background_jobs(
+    state: State<'_, Arc<AppState>>,
+    status: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<BackgroundJob>, AppError> {
+    crate::log_info!("sarah.command", "list_background_jobs invoked");
+    state
+        .background
+        .list_jobs(status.as_deref(), limit.unwrap_or(100))
+        .await
+}
+
+#[tauri::command]
+pub async fn cancel_job(
+    state: State<'_, Arc<AppState>>,
+    job_id: String,
+) -> Result<BackgroundJob, AppError> {
+    crate::log_info!("sarah.command", "cancel_job invoked");
+    state.background.cancel_job(&job_id).await
+}
+
+/// Puts a failed or cancelled job back in the queue with a fresh set of
+/// attempts.
+#[tauri::command]
+pub async fn retry_job(
+    state: State<'_, Arc<AppState>>,
+    job_id: String,
+) -> Result<BackgroundJob, AppError> {
+    crate::log_info!("sarah.command", "retry_job invoked");
+    state.background.retry_job(&job_id).await
+}
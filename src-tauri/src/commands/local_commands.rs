@@ -1,10 +1,13 @@
 use std::sync::Arc;
 
 use serde_json::Value;
-use tauri::{State, Manager, Runtime};
+use tauri::{Emitter, State, Manager, Runtime};
 
 use crate::commands::model_commands::start_model_download;
-use crate::db::models::{Message, Model, NewMessage};
+use crate::db::models::{ClarificationCandidate, GenerationOptions, Message, Model, NewMessage};
+use crate::error::AppError;
+use crate::services::clarification_service::ClarificationOutcome;
+use crate::services::runtime_orchestrator_service::resolve_requested_qos;
 use crate::state::AppState;
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -48,12 +51,19 @@ const SPOTIFY_CONFIG_NAMESPACE: &str = "spotify_mcp";
 const SPOTIFY_CONFIG_KEY: &str = "config";
 const DEFAULT_SPOTIFY_SERVER_ROOT: &str =
     "C:\\Users\\jesud\\OneDrive\\Desktop\\personal\\Sarah\\mcp\\spotify-mcp-server";
+/// The quick-prompt window has no session, so its clarifications share one scope.
+const LOCAL_CLARIFICATION_SCOPE: &str = "local";
+const MAX_CLARIFICATION_CANDIDATES: usize = 4;
+const DEFAULT_OLLAMA_MODEL: &str = "qwen2.5-coder:7b";
+const OLLAMA_CHAT_ROLES: &[&str] = &["system", "user", "assistant", "tool"];
 
 #[derive(Debug, Clone)]
 enum AudioIntent {
     Play {
         query: Option<String>,
         media_type: &'static str,
+        /// False for a bare "play X", which could name an artist as easily as a song.
+        explicit_type: bool,
     },
     Queue {
         query: String,
@@ -193,6 +203,7 @@ fn parse_audio_intent(input: &str) -> Option<AudioIntent> {
             return Some(AudioIntent::Play {
                 query: None,
                 media_type: "track",
+                explicit_type: false,
             });
         }
 
@@ -207,6 +218,7 @@ fn parse_audio_intent(input: &str) -> Option<AudioIntent> {
             return Some(AudioIntent::Play {
                 query: None,
                 media_type: "track",
+                explicit_type: false,
             });
         }
 
@@ -216,6 +228,7 @@ fn parse_audio_intent(input: &str) -> Option<AudioIntent> {
                 return Some(AudioIntent::Play {
                     query: Some(query.to_string()),
                     media_type: "playlist",
+                    explicit_type: true,
                 });
             }
         }
@@ -225,6 +238,7 @@ fn parse_audio_intent(input: &str) -> Option<AudioIntent> {
                 return Some(AudioIntent::Play {
                     query: Some(query.to_string()),
                     media_type: "album",
+                    explicit_type: true,
                 });
             }
         }
@@ -234,13 +248,16 @@ fn parse_audio_intent(input: &str) -> Option<AudioIntent> {
                 return Some(AudioIntent::Play {
                     query: Some(query.to_string()),
                     media_type: "artist",
+                    explicit_type: true,
                 });
             }
         }
 
+        let explicit_type = rest.contains("song") || rest.contains("track");
         return Some(AudioIntent::Play {
             query: Some(rest.to_string()),
             media_type: "track",
+            explicit_type,
         });
     }
 
@@ -295,7 +312,159 @@ fn parse_search_result(raw: &str) -> (Option<String>, Option<String>, Option<Str
     (id, title, artist)
 }
 
-async fn resolve_installed_model(
+#[derive(Debug, Clone)]
+struct SearchHit {
+    id: String,
+    title: String,
+    artist: Option<String>,
+}
+
+/// Reads every numbered row of a `searchSpotify` result, e.g.
+/// `2. "Thank You" by Dido (3:38) - ID: abc` or `1. Dido - ID: xyz` for artists.
+fn parse_search_results(raw: &str) -> Vec<SearchHit> {
+    let tool_payload = serde_json::from_str::<Value>(raw).ok();
+    let text = tool_payload
+        .as_ref()
+        .and_then(|value| value.get("content"))
+        .and_then(Value::as_array)
+        .and_then(|rows| rows.first())
+        .and_then(|row| row.get("text"))
+        .and_then(Value::as_str)
+        .unwrap_or(raw);
+
+    text.lines()
+        .filter_map(|line| {
+            let (_, row) = line.trim().split_once(". ")?;
+            let (label, id) = row.rsplit_once(" - ID:")?;
+            let id = id.trim().to_string();
+            if id.is_empty() {
+                return None;
+            }
+
+            let (title, artist) = match label.strip_prefix('"') {
+                Some(quoted) => {
+                    let (title, after) = quoted.split_once('"')?;
+                    let artist = after.split_once(" by ").map(|(_, by)| {
+                        by.split(" (").next().unwrap_or(by).trim().to_string()
+                    });
+                    (title.trim().to_string(), artist)
+                }
+                None => (label.trim().to_string(), None),
+            };
+            Some(SearchHit { id, title, artist })
+        })
+        .collect()
+}
+
+/// Decides whether a bare "play X" needs a follow-up question. It does when X is
+/// exactly an artist's name (the artist, or one of their songs?) or when several
+/// artists have a song titled X. Otherwise the first track hit is good enough.
+fn clarification_candidates(
+    query: &str,
+    tracks: &[SearchHit],
+    artists: &[SearchHit],
+) -> Option<Vec<ClarificationCandidate>> {
+    let query = normalize_spaces(query).to_lowercase();
+    let same = |text: &str| normalize_spaces(text).to_lowercase() == query;
+
+    let artist_match = artists.iter().find(|artist| same(&artist.title));
+    let exact_tracks = tracks
+        .iter()
+        .filter(|track| same(&track.title))
+        .collect::<Vec<_>>();
+    let mut exact_artists = exact_tracks
+        .iter()
+        .filter_map(|track| track.artist.as_deref())
+        .collect::<Vec<_>>();
+    exact_artists.sort_unstable();
+    exact_artists.dedup();
+
+    let ambiguous = (artist_match.is_some() && !tracks.is_empty()) || exact_artists.len() > 1;
+    if !ambiguous {
+        return None;
+    }
+
+    let track_candidate = |track: &SearchHit| ClarificationCandidate {
+        id: track.id.clone(),
+        kind: "track".to_string(),
+        title: track.title.clone(),
+        subtitle: track.artist.as_ref().map(|artist| format!("Song by {artist}")),
+    };
+    let mut candidates = Vec::new();
+    if let Some(artist) = artist_match {
+        candidates.push(ClarificationCandidate {
+            id: artist.id.clone(),
+            kind: "artist".to_string(),
+            title: artist.title.clone(),
+            subtitle: Some("Artist".to_string()),
+        });
+    }
+    let rest = if exact_tracks.is_empty() {
+        tracks.iter().collect::<Vec<_>>()
+    } else {
+        exact_tracks
+    };
+    for track in rest {
+        if candidates.len() >= MAX_CLARIFICATION_CANDIDATES {
+            break;
+        }
+        if !candidates.iter().any(|candidate| candidate.id == track.id) {
+            candidates.push(track_candidate(track));
+        }
+    }
+    Some(candidates)
+}
+
+fn clarification_question(query: &str, candidates: &[ClarificationCandidate]) -> String {
+    let options = candidates
+        .iter()
+        .enumerate()
+        .map(|(index, candidate)| match &candidate.subtitle {
+            Some(subtitle) => format!("{}. {} ({})", index + 1, candidate.title, subtitle),
+            None => format!("{}. {}", index + 1, candidate.title),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!("Which \"{query}\" did you mean?\n{options}\nReply with a number, or \"cancel\".")
+}
+
+async fn run_chosen_candidate(
+    app: &tauri::AppHandle,
+    state: &Arc<AppState>,
+    action: &str,
+    candidate: &ClarificationCandidate,
+) -> Result<String, String> {
+    let server_root = resolve_spotify_server_root(state).await?;
+    ensure_spotify_mcp_running(&server_root).await?;
+
+    let (tool, verb) = if action == "queue" {
+        ("addToQueue", "Queued")
+    } else {
+        ("playMusic", "Playing")
+    };
+    crate::commands::integration_commands::run_spotify_tool(
+        app.clone(),
+        server_root,
+        tool.to_string(),
+        serde_json::json!({
+            "type": candidate.kind,
+            "id": candidate.id,
+        }),
+    )
+    .await?;
+
+    Ok(match (candidate.kind.as_str(), &candidate.subtitle) {
+        ("artist", _) => format!("{verb} {}.", candidate.title),
+        (_, Some(subtitle)) => format!(
+            "{verb} \"{}\" {}.",
+            candidate.title,
+            subtitle.trim_start_matches("Song ")
+        ),
+        _ => format!("{verb} \"{}\".", candidate.title),
+    })
+}
+
+pub(crate) async fn resolve_installed_model(
     state: &Arc<AppState>,
     requested: Option<&str>,
 ) -> Result<Model, String> {
@@ -348,7 +517,7 @@ async fn resolve_installed_model(
     Ok(installed.remove(0))
 }
 
-async fn ensure_model_loaded(state: &Arc<AppState>, model: &Model) -> Result<(), String> {
+pub(crate) async fn ensure_model_loaded(state: &Arc<AppState>, model: &Model) -> Result<(), String> {
     let model_path = model
         .file_path
         .as_ref()
@@ -425,6 +594,52 @@ async fn ensure_spotify_mcp_running(server_root: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Searches before committing to a hit. When the query is ambiguous the intent is
+/// held, the candidates are sent as a `sarah://intent-clarification` event, and the
+/// question is returned as the reply.
+async fn ask_if_ambiguous(
+    app: &tauri::AppHandle,
+    state: &Arc<AppState>,
+    server_root: &str,
+    action: &str,
+    query: &str,
+) -> Result<Option<String>, String> {
+    let search = |media_type: &'static str, limit: i64| {
+        crate::commands::integration_commands::run_spotify_tool(
+            app.clone(),
+            server_root.to_string(),
+            "searchSpotify".to_string(),
+            serde_json::json!({
+                "query": query,
+                "type": media_type,
+                "limit": limit,
+            }),
+        )
+    };
+
+    let tracks = parse_search_results(&search("track", 5).await?);
+    // Only tracks can be queued, so an artist is never a queue candidate.
+    let artists = if action == "play" {
+        parse_search_results(&search("artist", 3).await?)
+    } else {
+        Vec::new()
+    };
+
+    let Some(candidates) = clarification_candidates(query, &tracks, &artists) else {
+        return Ok(None);
+    };
+    let question = clarification_question(query, &candidates);
+    let pending = state.clarifications.hold(
+        LOCAL_CLARIFICATION_SCOPE,
+        action,
+        query,
+        question.clone(),
+        candidates,
+    );
+    let _ = app.emit("sarah://intent-clarification", &pending);
+    Ok(Some(question))
+}
+
 async fn execute_audio_intent(
     app: &tauri::AppHandle,
     state: &Arc<AppState>,
@@ -502,8 +717,20 @@ async fn execute_audio_intent(
                 "Volume decreased.".to_string()
             })
         }
-        AudioIntent::Play { query, media_type } => {
+        AudioIntent::Play {
+            query,
+            media_type,
+            explicit_type,
+        } => {
             if let Some(query_text) = query {
+                if !explicit_type {
+                    if let Some(question) =
+                        ask_if_ambiguous(app, state, &server_root, "play", &query_text).await?
+                    {
+                        return Ok(question);
+                    }
+                }
+
                 let search_raw = crate::commands::integration_commands::run_spotify_tool(
                     app.clone(),
                     server_root.clone(),
@@ -553,6 +780,12 @@ async fn execute_audio_intent(
             }
         }
         AudioIntent::Queue { query } => {
+            if let Some(question) =
+                ask_if_ambiguous(app, state, &server_root, "queue", &query).await?
+            {
+                return Ok(question);
+            }
+
             let search_raw = crate::commands::integration_commands::run_spotify_tool(
                 app.clone(),
                 server_root.clone(),
@@ -625,6 +858,7 @@ async fn persist_prompt_response(
             token_count: Some((prompt.len() / 4) as i64 + 1),
             model_id: model_id.map(ToString::to_string),
             metadata: "{}".to_string(),
+            attachments: "[]".to_string(),
             position: 0,
         })
         .await
@@ -640,6 +874,7 @@ async fn persist_prompt_response(
             token_count: Some((response.len() / 4) as i64 + 1),
             model_id: model_id.map(ToString::to_string),
             metadata: "{}".to_string(),
+            attachments: "[]".to_string(),
             position: 1,
         })
         .await
@@ -653,6 +888,48 @@ struct OllamaGenerateResponse {
     response: String,
 }
 
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct OllamaChatMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OllamaChatOptions {
+    /// Prepended as a system message unless `messages` already starts with one.
+    system: Option<String>,
+    temperature: Option<f32>,
+    num_ctx: Option<u32>,
+    top_p: Option<f32>,
+    top_k: Option<u32>,
+    num_predict: Option<i32>,
+}
+
+#[derive(serde::Deserialize)]
+struct OllamaChatApiResponse {
+    #[serde(default)]
+    model: Option<String>,
+    message: OllamaChatMessage,
+    #[serde(default)]
+    prompt_eval_count: Option<u64>,
+    #[serde(default)]
+    eval_count: Option<u64>,
+    #[serde(default)]
+    total_duration: Option<u64>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OllamaChatResponse {
+    message: OllamaChatMessage,
+    model: String,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    total_tokens: u64,
+    total_duration_ms: u64,
+}
+
 #[derive(serde::Deserialize)]
 struct OllamaTagItem {
     name: String,
@@ -668,6 +945,8 @@ struct OllamaTagItem {
 
 #[derive(serde::Deserialize)]
 struct OllamaTagDetails {
+    #[serde(default)]
+    format: Option<String>,
     #[serde(default)]
     family: Option<String>,
     #[serde(default)]
@@ -681,6 +960,43 @@ struct OllamaTagsResponse {
     models: Vec<OllamaTagItem>,
 }
 
+#[derive(serde::Deserialize)]
+struct OllamaShowResponse {
+    #[serde(default)]
+    license: Option<String>,
+    #[serde(default)]
+    modelfile: Option<String>,
+    #[serde(default)]
+    parameters: Option<String>,
+    #[serde(default)]
+    template: Option<String>,
+    #[serde(default)]
+    system: Option<String>,
+    #[serde(default)]
+    details: Option<OllamaTagDetails>,
+    #[serde(default)]
+    model_info: Option<serde_json::Map<String, serde_json::Value>>,
+    #[serde(default)]
+    modified_at: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OllamaModelDetails {
+    name: String,
+    family: String,
+    parameter_size: String,
+    quantization_level: String,
+    format: String,
+    context_length: Option<u64>,
+    parameters: Option<String>,
+    template: Option<String>,
+    system: Option<String>,
+    license: Option<String>,
+    modelfile: Option<String>,
+    modified_at: Option<String>,
+}
+
 #[derive(serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OllamaModelSummary {
@@ -731,7 +1047,7 @@ pub async fn generate_ollama_response<R: Runtime>(
     let model = model
         .map(|value| value.trim().to_string())
         .filter(|value| !value.is_empty())
-        .unwrap_or_else(|| "qwen2.5-coder:7b".to_string());
+        .unwrap_or_else(|| DEFAULT_OLLAMA_MODEL.to_string());
 
     let client = app.state::<reqwest::Client>();
 
@@ -767,6 +1083,112 @@ pub async fn generate_ollama_response<R: Runtime>(
     Ok(text)
 }
 
+#[tauri::command]
+pub async fn chat_ollama<R: Runtime>(
+    messages: Vec<OllamaChatMessage>,
+    model: Option<String>,
+    options: Option<OllamaChatOptions>,
+    app: tauri::AppHandle<R>,
+) -> Result<OllamaChatResponse, String> {
+    let options = options.unwrap_or_default();
+    let mut history: Vec<OllamaChatMessage> = Vec::with_capacity(messages.len() + 1);
+    let system = options
+        .system
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty());
+    if let Some(system) = system {
+        if messages.first().map(|message| message.role.as_str()) != Some("system") {
+            history.push(OllamaChatMessage {
+                role: "system".to_string(),
+                content: system.to_string(),
+            });
+        }
+    }
+    for message in messages {
+        let role = message.role.trim().to_lowercase();
+        if !OLLAMA_CHAT_ROLES.contains(&role.as_str()) {
+            return Err(format!(
+                "Unknown message role '{}'. Expected one of {}.",
+                message.role,
+                OLLAMA_CHAT_ROLES.join(", ")
+            ));
+        }
+        history.push(OllamaChatMessage { role, content: message.content });
+    }
+    if !history.iter().any(|message| message.role == "user" && !message.content.trim().is_empty()) {
+        return Err("Conversation has no user message.".to_string());
+    }
+
+    let model = model
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| DEFAULT_OLLAMA_MODEL.to_string());
+
+    let mut generation = serde_json::Map::new();
+    if let Some(value) = options.temperature {
+        generation.insert("temperature".to_string(), serde_json::json!(value.clamp(0.0, 2.0)));
+    }
+    if let Some(value) = options.num_ctx {
+        generation.insert("num_ctx".to_string(), serde_json::json!(value.max(256)));
+    }
+    if let Some(value) = options.top_p {
+        generation.insert("top_p".to_string(), serde_json::json!(value.clamp(0.0, 1.0)));
+    }
+    if let Some(value) = options.top_k {
+        generation.insert("top_k".to_string(), serde_json::json!(value));
+    }
+    if let Some(value) = options.num_predict {
+        generation.insert("num_predict".to_string(), serde_json::json!(value));
+    }
+
+    let client = app.state::<reqwest::Client>();
+
+    let response = client
+        .post("http://127.0.0.1:11434/api/chat")
+        .json(&serde_json::json!({
+            "model": model,
+            "messages": history,
+            "options": generation,
+            "stream": false
+        }))
+        .send()
+        .await
+        .map_err(|error| {
+            format!("Failed to connect to Ollama at http://127.0.0.1:11434. Start Ollama and verify the model is installed. {error}")
+        })?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Ollama chat request failed with status {status}. {body}"));
+    }
+
+    let payload = response
+        .json::<OllamaChatApiResponse>()
+        .await
+        .map_err(|error| format!("Invalid Ollama chat response: {error}"))?;
+
+    let content = payload.message.content.trim().to_string();
+    if content.is_empty() {
+        return Err("Ollama returned an empty response.".to_string());
+    }
+
+    let prompt_tokens = payload.prompt_eval_count.unwrap_or(0);
+    let completion_tokens = payload.eval_count.unwrap_or(0);
+    Ok(OllamaChatResponse {
+        message: OllamaChatMessage {
+            role: payload.message.role,
+            content,
+        },
+        model: payload.model.unwrap_or(model),
+        prompt_tokens,
+        completion_tokens,
+        total_tokens: prompt_tokens + completion_tokens,
+        total_duration_ms: payload.total_duration.unwrap_or(0) / 1_000_000,
+    })
+}
+
 #[tauri::command]
 pub async fn list_ollama_models<R: Runtime>(app: tauri::AppHandle<R>) -> Result<Vec<String>, String> {
     let payload = fetch_ollama_tags(&app).await?;
@@ -876,6 +1298,98 @@ pub async fn pull_ollama_model<R: Runtime>(model: String, app: tauri::AppHandle<
     Ok(status)
 }
 
+#[tauri::command]
+pub async fn delete_ollama_model<R: Runtime>(model: String, app: tauri::AppHandle<R>) -> Result<String, String> {
+    let normalized = model.trim().to_string();
+    if normalized.is_empty() {
+        return Err("Model name is empty.".to_string());
+    }
+
+    let client = app.state::<reqwest::Client>();
+
+    let response = client
+        .delete("http://127.0.0.1:11434/api/delete")
+        .json(&serde_json::json!({ "name": normalized }))
+        .send()
+        .await
+        .map_err(|error| {
+            format!("Failed to connect to Ollama at http://127.0.0.1:11434. Start Ollama first. {error}")
+        })?;
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(format!("Model '{normalized}' is not installed in Ollama."));
+    }
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Ollama delete request failed with status {status}. {body}"));
+    }
+
+    Ok(format!("Deleted {normalized}."))
+}
+
+#[tauri::command]
+pub async fn show_ollama_model<R: Runtime>(model: String, app: tauri::AppHandle<R>) -> Result<OllamaModelDetails, String> {
+    let normalized = model.trim().to_string();
+    if normalized.is_empty() {
+        return Err("Model name is empty.".to_string());
+    }
+
+    let client = app.state::<reqwest::Client>();
+
+    let response = client
+        .post("http://127.0.0.1:11434/api/show")
+        .json(&serde_json::json!({ "name": normalized }))
+        .send()
+        .await
+        .map_err(|error| {
+            format!("Failed to connect to Ollama at http://127.0.0.1:11434. Start Ollama first. {error}")
+        })?;
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(format!("Model '{normalized}' is not installed in Ollama."));
+    }
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Ollama show request failed with status {status}. {body}"));
+    }
+
+    let payload = response
+        .json::<OllamaShowResponse>()
+        .await
+        .map_err(|error| format!("Invalid Ollama show response: {error}"))?;
+
+    // model_info keys are prefixed with the architecture, e.g. "llama.context_length".
+    let context_length = payload.model_info.as_ref().and_then(|info| {
+        info.iter()
+            .find(|(key, _)| key.ends_with(".context_length"))
+            .and_then(|(_, value)| value.as_u64())
+    });
+    let details = payload.details;
+    let detail = |pick: fn(&OllamaTagDetails) -> Option<String>| {
+        details
+            .as_ref()
+            .and_then(pick)
+            .unwrap_or_else(|| "Unknown".to_string())
+    };
+
+    Ok(OllamaModelDetails {
+        name: normalized,
+        family: detail(|entry| entry.family.clone()),
+        parameter_size: detail(|entry| entry.parameter_size.clone()),
+        quantization_level: detail(|entry| entry.quantization_level.clone()),
+        format: detail(|entry| entry.format.clone()),
+        context_length,
+        parameters: payload.parameters,
+        template: payload.template,
+        system: payload.system,
+        license: payload.license,
+        modelfile: payload.modelfile,
+        modified_at: payload.modified_at,
+    })
+}
+
 #[tauri::command]
 pub fn greet(name: &str) -> String {
     crate::log_info!("sarah.command", "greet invoked");
@@ -898,12 +1412,44 @@ pub async fn get_default_user(state: State<'_, Arc<AppState>>) -> Result<Default
     })
 }
 
+async fn plan_local_generation(
+    state: &Arc<AppState>,
+    prompt: &str,
+    task_type: Option<&str>,
+    qos: Option<&str>,
+) -> Result<GenerationOptions, AppError> {
+    let user = state.user_repo.get_or_create_default_user().await?;
+    let orchestrated = state
+        .runtime_orchestrator
+        .plan_request(&user.id, prompt, task_type, qos, false)
+        .await?;
+    let policy = state.runtime_governor.get_policy(Some(&user.id)).await?;
+    let pressure = state
+        .runtime_governor
+        .classify_pressure(&state.runtime_governor.current_stats(), &policy);
+
+    let options = GenerationOptions {
+        max_tokens: orchestrated.max_tokens_hint,
+        ..GenerationOptions::default()
+    };
+    Ok(state.runtime_governor.tune_generation(
+        options,
+        &policy,
+        &orchestrated.qos,
+        &pressure,
+        false,
+    ))
+}
+
 #[tauri::command]
 pub async fn generate_local_response(
     app: tauri::AppHandle,
     state: State<'_, Arc<AppState>>,
     prompt: String,
     model: Option<String>,
+    task_type: Option<String>,
+    qos: Option<String>,
+    response_mode: Option<String>,
 ) -> Result<String, String> {
     crate::log_info!("sarah.command", "generate_local_response invoked");
     let prompt = prompt.trim().to_string();
@@ -911,6 +1457,21 @@ pub async fn generate_local_response(
         return Err("Prompt is empty.".to_string());
     }
 
+    match state
+        .clarifications
+        .answer(LOCAL_CLARIFICATION_SCOPE, &prompt)
+    {
+        Some(ClarificationOutcome::Chosen { pending, candidate }) => {
+            let response = run_chosen_candidate(&app, &state, &pending.action, &candidate).await?;
+            let _ = persist_prompt_response(&state, &prompt, &response, None).await;
+            return Ok(response);
+        }
+        Some(ClarificationOutcome::Cancelled) => {
+            return Ok("Okay, never mind.".to_string());
+        }
+        Some(ClarificationOutcome::Unrelated) | None => {}
+    }
+
     if let Some(intent) = parse_audio_intent(&prompt) {
         let response = execute_audio_intent(&app, &state, intent).await?;
         let _ = persist_prompt_response(&state, &prompt, &response, None).await;
@@ -935,17 +1496,28 @@ pub async fn generate_local_response(
         is_error: 0,
         error_message: None,
         parent_message_id: None,
+        is_active_variant: 1,
         edited_at: None,
         original_content: None,
         metadata: "{}".to_string(),
+        attachments: "[]".to_string(),
         position: 0,
         created_at: String::new(),
         updated_at: String::new(),
     };
 
+    let options = plan_local_generation(
+        &state,
+        &prompt,
+        task_type.as_deref(),
+        resolve_requested_qos(qos.as_deref(), response_mode.as_deref()).as_deref(),
+    )
+    .await
+    .map_err(|error| error.to_string())?;
+
     let result = state
         .inference
-        .generate_with_tools(vec![user_message], &[])
+        .generate_with_options(vec![user_message], &[], options)
         .await
         .map_err(|error| error.to_string())?;
 
@@ -958,6 +1530,28 @@ pub async fn generate_local_response(
     Ok(text)
 }
 
+/// Answers a pending clarification from its candidate list; `None` cancels it.
+#[tauri::command]
+pub async fn answer_clarification(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    clarification_id: String,
+    candidate_index: Option<usize>,
+) -> Result<String, String> {
+    crate::log_info!("sarah.command", "answer_clarification invoked");
+    let Some(index) = candidate_index else {
+        state.clarifications.cancel(&clarification_id);
+        return Ok("Okay, never mind.".to_string());
+    };
+
+    match state.clarifications.choose(&clarification_id, index) {
+        Some(ClarificationOutcome::Chosen { pending, candidate }) => {
+            run_chosen_candidate(&app, &state, &pending.action, &candidate).await
+        }
+        _ => Err("That question has expired. Ask again.".to_string()),
+    }
+}
+
 #[tauri::command]
 pub async fn list_local_models(state: State<'_, Arc<AppState>>) -> Result<Vec<String>, String> {
     crate::log_info!("sarah.command", "list_local_models invoked");
@@ -1035,6 +1629,7 @@ pub async fn get_local_chat_history(
                 FROM messages a
                 WHERE a.session_id = m.session_id
                   AND a.role = 'assistant'
+                  AND a.is_active_variant = 1
                   AND a.position > m.position
                 ORDER BY a.position ASC
                 LIMIT 1
@@ -1,11 +1,18 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use tauri::State;
+use tauri::{Emitter, State};
 
-use crate::db::models::{Mcp, McpHealthStatus, McpUsageStat};
+use crate::db::models::{Mcp, McpHealthStatus, McpUsageStat, ToolResult};
 use crate::error::AppError;
+use crate::services::conversation_service::ToolCallRequest;
+use crate::services::filesystem_tools::FilesystemRoot;
+use crate::services::tool_approval_service::ToolPermission;
 use crate::state::AppState;
 
+const MAX_SERVER_NAME_CHARS: usize = 64;
+pub(crate) const TOOL_APPROVAL_EVENT: &str = "mcp://tool-approval-request";
+
 #[tauri::command]
 pub async fn list_mcps(
     state: State<'_, Arc<AppState>>,
@@ -36,6 +43,118 @@ pub async fn deactivate_mcp(
     state.mcp_repo.set_active(&mcp_id, false).await
 }
 
+/// Registers a stdio MCP server and connects once to discover its tools.
+#[tauri::command]
+pub async fn add_mcp_server(
+    state: State<'_, Arc<AppState>>,
+    name: String,
+    command: String,
+    args: Option<Vec<String>>,
+    env: Option<HashMap<String, String>>,
+) -> Result<Mcp, AppError> {
+    crate::log_info!("sarah.command", "add_mcp_server invoked");
+    let name = validate_server_name(&name)?;
+    let command = command.trim().to_string();
+    if command.is_empty() {
+        return Err(AppError::Validation {
+            field: "command".to_string(),
+            message: "Command cannot be empty".to_string(),
+        });
+    }
+    ensure_unique_server_name(&state, &name).await?;
+
+    let mcp = state
+        .mcp_repo
+        .create_custom_mcp(
+            &name,
+            &command,
+            &args.unwrap_or_default(),
+            &env.unwrap_or_default(),
+        )
+        .await?;
+    connect_new_server(&state, mcp).await
+}
+
+/// Registers a remote MCP endpoint (streamable HTTP or HTTP+SSE). `auth_header`
+/// is either a full `Name: value` header or just the `Authorization` value;
+/// it is stored encrypted and never returned with the MCP.
+#[tauri::command]
+pub async fn add_remote_mcp_server(
+    state: State<'_, Arc<AppState>>,
+    name: String,
+    url: String,
+    auth_header: Option<String>,
+) -> Result<Mcp, AppError> {
+    crate::log_info!("sarah.command", "add_remote_mcp_server invoked");
+    let name = validate_server_name(&name)?;
+    let url = url.trim().to_string();
+    let valid_url = reqwest::Url::parse(&url)
+        .map(|parsed| matches!(parsed.scheme(), "http" | "https"))
+        .unwrap_or(false);
+    if !valid_url {
+        return Err(AppError::Validation {
+            field: "url".to_string(),
+            message: "Expected an http:// or https:// URL".to_string(),
+        });
+    }
+    ensure_unique_server_name(&state, &name).await?;
+
+    let mut headers = HashMap::new();
+    if let Some(header) = auth_header
+        .as_deref()
+        .map(str::trim)
+        .filter(|h| !h.is_empty())
+    {
+        let (key, value) = match header.split_once(':') {
+            Some((key, value)) if !key.trim().contains(' ') => (key.trim(), value.trim()),
+            _ => ("Authorization", header),
+        };
+        headers.insert(key.to_string(), value.to_string());
+    }
+
+    let header_names = headers.keys().cloned().collect::<Vec<_>>();
+    let mcp = state
+        .mcp_repo
+        .create_remote_mcp(&name, &url, &header_names)
+        .await?;
+    if let Err(error) = state.mcp.save_remote_headers(&mcp.id, &headers).await {
+        let _ = state.mcp_repo.delete_mcp(&mcp.id).await;
+        return Err(error);
+    }
+    connect_new_server(&state, mcp).await
+}
+
+#[tauri::command]
+pub async fn remove_mcp_server(
+    state: State<'_, Arc<AppState>>,
+    mcp_id: String,
+) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "remove_mcp_server invoked");
+    state.mcp.disconnect(&mcp_id).await;
+    state.mcp_repo.delete_mcp(&mcp_id).await
+}
+
+/// Disabling also stops the server process; it is restarted on the next call.
+#[tauri::command]
+pub async fn enable_mcp_server(
+    state: State<'_, Arc<AppState>>,
+    mcp_id: String,
+    enabled: bool,
+) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "enable_mcp_server invoked");
+    if state.mcp_repo.get_mcp(&mcp_id).await?.is_none() {
+        return Err(AppError::NotFound {
+            entity: "mcp".to_string(),
+            id: mcp_id,
+        });
+    }
+    state.mcp_repo.set_active(&mcp_id, enabled).await?;
+    if !enabled {
+        state.mcp.disconnect(&mcp_id).await;
+    }
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn save_mcp_secret(
     state: State<'_, Arc<AppState>>,
@@ -57,16 +176,13 @@ pub async fn test_mcp_connection(
     mcp_id: String,
 ) -> Result<McpHealthStatus, AppError> {
     crate::log_info!("sarah.command", "test_mcp_connection invoked");
-    let _ = state.mcp.ensure_connected(&mcp_id).await?;
-    let statuses = state.mcp.health_check_all().await?;
-
-    statuses
-        .into_iter()
-        .find(|status| status.mcp_id == mcp_id)
-        .ok_or_else(|| AppError::NotFound {
-            entity: "mcp_health".to_string(),
-            id: mcp_id,
-        })
+    let mcp = state.mcp.ensure_connected(&mcp_id).await?;
+    let health = state.mcp.check_health(&mcp).await;
+    state
+        .mcp_repo
+        .update_health(&mcp_id, &health.health_status, health.last_error.as_deref())
+        .await?;
+    Ok(health)
 }
 
 #[tauri::command]
@@ -77,3 +193,182 @@ pub async fn get_mcp_stats(
     crate::log_info!("sarah.command", "get_mcp_stats invoked");
     state.mcp.get_stats(&mcp_id).await
 }
+
+/// Answers a `mcp://tool-approval-request`. Returns `false` if the request
+/// already timed out.
+#[tauri::command]
+pub async fn respond_tool_approval(
+    state: State<'_, Arc<AppState>>,
+    approval_id: String,
+    approved: bool,
+) -> Result<bool, AppError> {
+    crate::log_info!("sarah.command", "respond_tool_approval invoked");
+    Ok(state.tool_approvals.respond(&approval_id, approved))
+}
+
+#[tauri::command]
+pub async fn get_mcp_tool_permission(
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+    mcp_id: String,
+) -> Result<ToolPermission, AppError> {
+    crate::log_info!("sarah.command", "get_mcp_tool_permission invoked");
+    state.tool_approvals.policy(&user_id, &mcp_id).await
+}
+
+/// `permission` is always_allow, ask or deny; without a user it sets the default.
+#[tauri::command]
+pub async fn set_mcp_tool_permission(
+    state: State<'_, Arc<AppState>>,
+    user_id: Option<String>,
+    mcp_id: String,
+    permission: String,
+) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "set_mcp_tool_permission invoked");
+    let permission = ToolPermission::parse(&permission).ok_or_else(|| AppError::Validation {
+        field: "permission".to_string(),
+        message: "Expected always_allow, ask or deny".to_string(),
+    })?;
+    if state.mcp_repo.get_mcp(&mcp_id).await?.is_none() {
+        return Err(AppError::NotFound {
+            entity: "mcp".to_string(),
+            id: mcp_id,
+        });
+    }
+    state
+        .tool_approvals
+        .set_policy(user_id.as_deref(), &mcp_id, permission)
+        .await
+}
+
+#[tauri::command]
+pub async fn run_tool_calls(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+    session_id: String,
+    message_id: String,
+    tool_calls: Vec<ToolCallRequest>,
+) -> Result<Vec<ToolResult>, AppError> {
+    crate::log_info!("sarah.command", "run_tool_calls invoked");
+    let window_label = window.label().to_string();
+    state
+        .conversation
+        .process_tool_calls(
+            tool_calls,
+            &session_id,
+            &message_id,
+            &user_id,
+            |event| {
+                let _ = app.emit_to(window_label.as_str(), "sarah://tool-call", event);
+            },
+            |request| {
+                let _ = app.emit(TOOL_APPROVAL_EVENT, request);
+            },
+        )
+        .await
+}
+
+/// The folders the built-in file tools may use.
+#[tauri::command]
+pub async fn list_filesystem_roots(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<FilesystemRoot>, AppError> {
+    crate::log_info!("sarah.command", "list_filesystem_roots invoked");
+    state.mcp.filesystem().roots().await
+}
+
+/// Approves a folder for the file tools; `writable` also allows `write_file`.
+#[tauri::command]
+pub async fn add_filesystem_root(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    writable: bool,
+) -> Result<Vec<FilesystemRoot>, AppError> {
+    crate::log_info!("sarah.command", "add_filesystem_root invoked");
+    state.mcp.filesystem().add_root(&path, writable).await
+}
+
+#[tauri::command]
+pub async fn remove_filesystem_root(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+) -> Result<Vec<FilesystemRoot>, AppError> {
+    crate::log_info!("sarah.command", "remove_filesystem_root invoked");
+    state.mcp.filesystem().remove_root(&path).await
+}
+
+/// The programs the built-in `run_command` tool may start.
+#[tauri::command]
+pub async fn get_shell_allowlist(state: State<'_, Arc<AppState>>) -> Result<Vec<String>, AppError> {
+    crate::log_info!("sarah.command", "get_shell_allowlist invoked");
+    state.mcp.shell().allowlist().await
+}
+
+/// Replaces the allowlist with `commands`, bare program names such as `git`.
+#[tauri::command]
+pub async fn set_shell_allowlist(
+    state: State<'_, Arc<AppState>>,
+    commands: Vec<String>,
+) -> Result<Vec<String>, AppError> {
+    crate::log_info!("sarah.command", "set_shell_allowlist invoked");
+    state.mcp.shell().set_allowlist(commands).await
+}
+
+fn validate_server_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_SERVER_NAME_CHARS {
+        return Err(AppError::Validation {
+            field: "name".to_string(),
+            message: format!("Server names must be 1-{MAX_SERVER_NAME_CHARS} characters"),
+        });
+    }
+    Ok(name.to_string())
+}
+
+async fn ensure_unique_server_name(state: &Arc<AppState>, name: &str) -> Result<(), AppError> {
+    if state.mcp_repo.get_mcp_by_name(name).await?.is_some() {
+        return Err(AppError::Validation {
+            field: "name".to_string(),
+            message: format!("An MCP server named '{name}' already exists"),
+        });
+    }
+    Ok(())
+}
+
+/// Connects once to discover tools. A failed first connect keeps the server,
+/// marked down with the error.
+async fn connect_new_server(state: &Arc<AppState>, mcp: Mcp) -> Result<Mcp, AppError> {
+    match state.mcp.ensure_connected(&mcp.id).await {
+        Ok(connected) => {
+            state
+                .mcp_repo
+                .update_health(&mcp.id, "healthy", None)
+                .await?;
+            Ok(Mcp {
+                health_status: "healthy".to_string(),
+                ..connected
+            })
+        }
+        Err(error) => {
+            let message = error.to_string();
+            crate::log_warn!(
+                "sarah.mcp",
+                "MCP server '{}' failed to connect: {}",
+                mcp.name,
+                message
+            );
+            state.mcp.disconnect(&mcp.id).await;
+            state
+                .mcp_repo
+                .update_health(&mcp.id, "down", Some(&message))
+                .await?;
+            Ok(Mcp {
+                health_status: "down".to_string(),
+                last_error: Some(message),
+                ..mcp
+            })
+        }
+    }
+}
@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use tauri::State;
 
-use crate::db::models::{Memory, MemoryGraph};
+use crate::db::models::{Memory, MemoryFilter, MemoryGraph, MemoryPatch};
 use crate::error::AppError;
 use crate::state::AppState;
 
@@ -20,16 +20,28 @@ pub async fn get_memories(
         .await
 }
 
+/// What the Memories window shows: filtered by type, category, subject or
+/// pin, pinned memories first.
+#[tauri::command]
+pub async fn list_memories(
+    state: State<'_, Arc<AppState>>,
+    filter: MemoryFilter,
+) -> Result<Vec<Memory>, AppError> {
+    crate::log_info!("sarah.command", "list_memories invoked");
+    state.memory_repo.list_memories(&filter).await
+}
+
 #[tauri::command]
 pub async fn search_memories(
     state: State<'_, Arc<AppState>>,
     user_id: String,
     query: String,
+    limit: Option<i64>,
 ) -> Result<Vec<Memory>, AppError> {
     crate::log_info!("sarah.command", "search_memories invoked");
     state
         .memory_repo
-        .search_memories_text(&user_id, &query, 100)
+        .search_memories_text(&user_id, &query, limit.unwrap_or(100).clamp(1, 500))
         .await
 }
 
@@ -39,7 +51,19 @@ pub async fn delete_memory(
     memory_id: String,
 ) -> Result<(), AppError> {
     crate::log_info!("sarah.command", "delete_memory invoked");
-    state.memory_repo.delete_memory(&memory_id).await
+    state.memory.delete_memory(&memory_id).await
+}
+
+/// Deletes every memory about `subject`, by subject, object or a mention in
+/// its text. Returns how many were removed.
+#[tauri::command]
+pub async fn forget_about(
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+    subject: String,
+) -> Result<usize, AppError> {
+    crate::log_info!("sarah.command", "forget_about invoked");
+    state.memory.forget_about(&user_id, &subject).await
 }
 
 #[tauri::command]
@@ -49,48 +73,31 @@ pub async fn pin_memory(
     pinned: bool,
 ) -> Result<(), AppError> {
     crate::log_info!("sarah.command", "pin_memory invoked");
-    sqlx::query("UPDATE memories SET is_pinned = ?1 WHERE id = ?2")
-        .bind(if pinned { 1 } else { 0 })
-        .bind(&memory_id)
-        .execute(state.db.write_pool())
-        .await?;
-
-    Ok(())
+    state.memory_repo.set_pinned(&memory_id, pinned).await
 }
 
 #[tauri::command]
 pub async fn update_memory(
     state: State<'_, Arc<AppState>>,
     memory_id: String,
-    content: String,
+    patch: MemoryPatch,
 ) -> Result<Memory, AppError> {
     crate::log_info!("sarah.command", "update_memory invoked");
-    sqlx::query("UPDATE memories SET content = ?1 WHERE id = ?2")
-        .bind(&content)
-        .bind(&memory_id)
-        .execute(state.db.write_pool())
-        .await?;
-
-    state
-        .memory_repo
-        .get_memory(&memory_id)
-        .await?
-        .ok_or_else(|| AppError::NotFound {
-            entity: "memory".to_string(),
-            id: memory_id,
-        })
+    state.memory.update_memory(&memory_id, &patch).await
 }
 
+/// Memories related to `root_memory_id`, walked breadth-first in both
+/// directions up to `depth` hops (1-4, default 2), with the relations between
+/// them, for the knowledge graph view.
 #[tauri::command]
 pub async fn get_memory_graph(
     state: State<'_, Arc<AppState>>,
-    user_id: String,
-    memory_id: String,
+    root_memory_id: String,
     depth: Option<i64>,
 ) -> Result<MemoryGraph, AppError> {
     crate::log_info!("sarah.command", "get_memory_graph invoked");
     state
         .memory
-        .get_memory_graph(&user_id, &memory_id, depth.unwrap_or(2))
+        .get_memory_graph(&root_memory_id, depth.unwrap_or(2))
         .await
 }
@@ -1,11 +1,22 @@
 pub mod analytics_commands;
+pub mod api_commands;
+pub mod capture_commands;
 pub mod chat_commands;
+pub mod import_commands;
 pub mod integration_commands;
+pub mod job_commands;
 pub mod local_commands;
 pub mod mcp_commands;
 pub mod memory_commands;
 pub mod model_commands;
+pub mod persona_commands;
+pub mod prompt_commands;
 pub mod rag_commands;
+pub mod recovery_commands;
 pub mod runtime_commands;
+pub mod schedule_commands;
 pub mod settings_commands;
 pub mod system_commands;
+pub mod voice_commands;
+pub mod webhook_commands;
+pub mod workspace_commands;
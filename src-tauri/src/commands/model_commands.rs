@@ -4,13 +4,16 @@ use std::sync::Arc;
 use dashmap::DashMap;
 use futures::StreamExt;
 use once_cell::sync::Lazy;
-use tauri::{Manager, State};
+use tauri::{Emitter, Manager, State};
 use tokio::sync::OnceCell;
 use tokio::io::AsyncWriteExt;
 use uuid::Uuid;
 
-use crate::db::models::{Model, ModelRecommendation, NewModel};
+use crate::db::models::{LoraAdapter, Model, ModelRecommendation, NewModel};
 use crate::error::AppError;
+use crate::services::notification_service::NotificationCategory;
+use crate::services::speech_service::SPEECH_MODEL_CATEGORY;
+use crate::services::webhook_service::WebhookEvent;
 use crate::state::AppState;
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -64,6 +67,8 @@ struct SeedModel {
     performance_tier: &'static str,
     energy_tier: &'static str,
     download_url: &'static str,
+    /// Multimodal projector for vision models, downloaded next to the weights.
+    mmproj_url: Option<&'static str>,
 }
 
 const MODEL_CATALOG: &[SeedModel] = &[
@@ -80,6 +85,7 @@ const MODEL_CATALOG: &[SeedModel] = &[
         performance_tier: "fast",
         energy_tier: "low",
         download_url: "https://huggingface.co/TinyLlama/TinyLlama-1.1B-Chat-v1.0-GGUF/resolve/main/tinyllama-1.1b-chat-v1.0.Q4_K_M.gguf?download=true",
+        mmproj_url: None,
     },
     SeedModel {
         name: "qwen2.5-0.5b-instruct-q4_k_m",
@@ -94,6 +100,7 @@ const MODEL_CATALOG: &[SeedModel] = &[
         performance_tier: "fast",
         energy_tier: "low",
         download_url: "https://huggingface.co/Qwen/Qwen2.5-0.5B-Instruct-GGUF/resolve/main/qwen2.5-0.5b-instruct-q4_k_m.gguf?download=true",
+        mmproj_url: None,
     },
     SeedModel {
         name: "llama-3.2-1b-instruct-q4_k_m",
@@ -108,6 +115,7 @@ const MODEL_CATALOG: &[SeedModel] = &[
         performance_tier: "balanced",
         energy_tier: "medium",
         download_url: "https://huggingface.co/unsloth/Llama-3.2-1B-Instruct-GGUF/resolve/main/Llama-3.2-1B-Instruct-Q4_K_M.gguf?download=true",
+        mmproj_url: None,
     },
     SeedModel {
         name: "qwen2.5-1.5b-instruct-q4_k_m",
@@ -122,6 +130,7 @@ const MODEL_CATALOG: &[SeedModel] = &[
         performance_tier: "quality",
         energy_tier: "medium",
         download_url: "https://huggingface.co/Qwen/Qwen2.5-1.5B-Instruct-GGUF/resolve/main/qwen2.5-1.5b-instruct-q4_k_m.gguf?download=true",
+        mmproj_url: None,
     },
     SeedModel {
         name: "gemma-2-2b-it-q4_k_m",
@@ -136,6 +145,7 @@ const MODEL_CATALOG: &[SeedModel] = &[
         performance_tier: "balanced",
         energy_tier: "medium",
         download_url: "https://huggingface.co/bartowski/gemma-2-2b-it-GGUF/resolve/main/gemma-2-2b-it-Q4_K_M.gguf?download=true",
+        mmproj_url: None,
     },
     SeedModel {
         name: "qwen2.5-3b-instruct-q4_k_m",
@@ -150,6 +160,7 @@ const MODEL_CATALOG: &[SeedModel] = &[
         performance_tier: "balanced",
         energy_tier: "medium",
         download_url: "https://huggingface.co/Qwen/Qwen2.5-3B-Instruct-GGUF/resolve/main/qwen2.5-3b-instruct-q4_k_m.gguf?download=true",
+        mmproj_url: None,
     },
     SeedModel {
         name: "phi-3.5-mini-instruct-q4_k_m",
@@ -164,6 +175,7 @@ const MODEL_CATALOG: &[SeedModel] = &[
         performance_tier: "balanced",
         energy_tier: "medium",
         download_url: "https://huggingface.co/bartowski/Phi-3.5-mini-instruct-GGUF/resolve/main/Phi-3.5-mini-instruct-Q4_K_M.gguf?download=true",
+        mmproj_url: None,
     },
     SeedModel {
         name: "llama-3.2-3b-instruct-q4_k_m",
@@ -178,6 +190,7 @@ const MODEL_CATALOG: &[SeedModel] = &[
         performance_tier: "balanced",
         energy_tier: "medium",
         download_url: "https://huggingface.co/unsloth/Llama-3.2-3B-Instruct-GGUF/resolve/main/Llama-3.2-3B-Instruct-Q4_K_M.gguf?download=true",
+        mmproj_url: None,
     },
     SeedModel {
         name: "mistral-7b-instruct-v0.3-q4_k_m",
@@ -192,6 +205,7 @@ const MODEL_CATALOG: &[SeedModel] = &[
         performance_tier: "quality",
         energy_tier: "high",
         download_url: "https://huggingface.co/bartowski/Mistral-7B-Instruct-v0.3-GGUF/resolve/main/Mistral-7B-Instruct-v0.3-Q4_K_M.gguf?download=true",
+        mmproj_url: None,
     },
     SeedModel {
         name: "qwen2.5-7b-instruct-q4_k_m",
@@ -206,6 +220,7 @@ const MODEL_CATALOG: &[SeedModel] = &[
         performance_tier: "quality",
         energy_tier: "high",
         download_url: "https://huggingface.co/bartowski/Qwen2.5-7B-Instruct-GGUF/resolve/main/Qwen2.5-7B-Instruct-Q4_K_M.gguf?download=true",
+        mmproj_url: None,
     },
     SeedModel {
         name: "qwen2.5-coder-7b-instruct-q4_k_m",
@@ -220,6 +235,7 @@ const MODEL_CATALOG: &[SeedModel] = &[
         performance_tier: "quality",
         energy_tier: "high",
         download_url: "https://huggingface.co/bartowski/Qwen2.5-Coder-7B-Instruct-GGUF/resolve/main/Qwen2.5-Coder-7B-Instruct-Q4_K_M.gguf?download=true",
+        mmproj_url: None,
     },
     SeedModel {
         name: "qwen2.5-math-7b-instruct-q4_k_m",
@@ -234,6 +250,102 @@ const MODEL_CATALOG: &[SeedModel] = &[
         performance_tier: "quality",
         energy_tier: "high",
         download_url: "https://huggingface.co/bartowski/Qwen2.5-Math-7B-Instruct-GGUF/resolve/main/Qwen2.5-Math-7B-Instruct-Q4_K_M.gguf?download=true",
+        mmproj_url: None,
+    },
+    SeedModel {
+        name: "qwen2.5-vl-3b-instruct-q4_k_m",
+        display_name: "Qwen2.5 VL 3B Instruct (Q4_K_M, vision)",
+        family: "qwen",
+        parameter_count: "3B",
+        quantization: "Q4_K_M",
+        context_length: 32768,
+        min_ram_mb: 9000,
+        recommended_ram_mb: 14000,
+        min_vram_mb: 0,
+        performance_tier: "balanced",
+        energy_tier: "medium",
+        download_url: "https://huggingface.co/ggml-org/Qwen2.5-VL-3B-Instruct-GGUF/resolve/main/Qwen2.5-VL-3B-Instruct-Q4_K_M.gguf?download=true",
+        mmproj_url: Some("https://huggingface.co/ggml-org/Qwen2.5-VL-3B-Instruct-GGUF/resolve/main/mmproj-Qwen2.5-VL-3B-Instruct-Q8_0.gguf?download=true"),
+    },
+    SeedModel {
+        name: "qwen2.5-vl-7b-instruct-q4_k_m",
+        display_name: "Qwen2.5 VL 7B Instruct (Q4_K_M, vision)",
+        family: "qwen",
+        parameter_count: "7B",
+        quantization: "Q4_K_M",
+        context_length: 32768,
+        min_ram_mb: 16000,
+        recommended_ram_mb: 25000,
+        min_vram_mb: 0,
+        performance_tier: "quality",
+        energy_tier: "high",
+        download_url: "https://huggingface.co/ggml-org/Qwen2.5-VL-7B-Instruct-GGUF/resolve/main/Qwen2.5-VL-7B-Instruct-Q4_K_M.gguf?download=true",
+        mmproj_url: Some("https://huggingface.co/ggml-org/Qwen2.5-VL-7B-Instruct-GGUF/resolve/main/mmproj-Qwen2.5-VL-7B-Instruct-Q8_0.gguf?download=true"),
+    },
+];
+
+/// whisper.cpp models for speech-to-text, downloaded through the same
+/// pipeline but never picked for chat.
+const SPEECH_CATALOG: &[SeedModel] = &[
+    SeedModel {
+        name: "whisper-tiny-en",
+        display_name: "Whisper Tiny (English)",
+        family: "whisper",
+        parameter_count: "39M",
+        quantization: "F16",
+        context_length: 448,
+        min_ram_mb: 400,
+        recommended_ram_mb: 1000,
+        min_vram_mb: 0,
+        performance_tier: "fast",
+        energy_tier: "low",
+        download_url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-tiny.en.bin?download=true",
+        mmproj_url: None,
+    },
+    SeedModel {
+        name: "whisper-base-en",
+        display_name: "Whisper Base (English)",
+        family: "whisper",
+        parameter_count: "74M",
+        quantization: "F16",
+        context_length: 448,
+        min_ram_mb: 600,
+        recommended_ram_mb: 1500,
+        min_vram_mb: 0,
+        performance_tier: "balanced",
+        energy_tier: "low",
+        download_url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.en.bin?download=true",
+        mmproj_url: None,
+    },
+    SeedModel {
+        name: "whisper-base",
+        display_name: "Whisper Base (multilingual)",
+        family: "whisper",
+        parameter_count: "74M",
+        quantization: "F16",
+        context_length: 448,
+        min_ram_mb: 600,
+        recommended_ram_mb: 1500,
+        min_vram_mb: 0,
+        performance_tier: "balanced",
+        energy_tier: "low",
+        download_url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin?download=true",
+        mmproj_url: None,
+    },
+    SeedModel {
+        name: "whisper-small-en",
+        display_name: "Whisper Small (English)",
+        family: "whisper",
+        parameter_count: "244M",
+        quantization: "F16",
+        context_length: 448,
+        min_ram_mb: 1200,
+        recommended_ram_mb: 3000,
+        min_vram_mb: 0,
+        performance_tier: "quality",
+        energy_tier: "medium",
+        download_url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.en.bin?download=true",
+        mmproj_url: None,
     },
 ];
 
@@ -256,16 +368,77 @@ fn normalize_filename(url: &str, fallback_name: &str) -> String {
         })
         .collect::<String>();
 
-    if !out.to_ascii_lowercase().ends_with(".gguf") {
+    // whisper.cpp models keep their ggml `.bin` name.
+    let lower = out.to_ascii_lowercase();
+    if !lower.ends_with(".gguf") && !lower.ends_with(".bin") {
         out.push_str(".gguf");
     }
     out
 }
 
+/// Fetches a vision model's projector (mmproj) into `models_dir` unless it is
+/// already there, and records where it lives. Text-only models are a no-op.
+async fn ensure_vision_projector(
+    state: &Arc<AppState>,
+    model: &Model,
+    models_dir: &Path,
+) -> Result<(), AppError> {
+    let metadata = serde_json::from_str::<serde_json::Value>(&model.metadata).unwrap_or_default();
+    let Some(url) = metadata
+        .pointer("/visionProjector/url")
+        .and_then(|value| value.as_str())
+    else {
+        return Ok(());
+    };
+
+    let fallback_name = format!("{}-mmproj.gguf", model.name);
+    let final_path = models_dir.join(normalize_filename(url, &fallback_name));
+    if !final_path.exists() {
+        let temp_path = PathBuf::from(format!("{}.part", final_path.to_string_lossy()));
+        let download = async {
+            let response = reqwest::get(url).await.map_err(|error| {
+                AppError::Inference(format!("Failed to start projector download: {error}"))
+            })?;
+            if !response.status().is_success() {
+                return Err(AppError::Inference(format!(
+                    "Projector download failed with status {}",
+                    response.status()
+                )));
+            }
+
+            let mut stream = response.bytes_stream();
+            let mut file = tokio::fs::File::create(&temp_path).await?;
+            while let Some(chunk) = stream.next().await {
+                let chunk = chunk.map_err(|error| {
+                    AppError::Inference(format!("Download stream error: {error}"))
+                })?;
+                file.write_all(&chunk).await?;
+            }
+            file.flush().await?;
+            drop(file);
+            tokio::fs::rename(&temp_path, &final_path).await?;
+            Ok::<(), AppError>(())
+        };
+        if let Err(error) = download.await {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return Err(error);
+        }
+    }
+
+    state
+        .model_repo
+        .record_vision_projector(&model.id, &final_path.to_string_lossy())
+        .await
+}
+
 pub(crate) async fn ensure_catalog_seeded(state: &Arc<AppState>) -> Result<(), AppError> {
     CATALOG_SEEDED
         .get_or_try_init(|| async {
-            for item in MODEL_CATALOG {
+            let catalog = MODEL_CATALOG
+                .iter()
+                .map(|item| (item, "chat"))
+                .chain(SPEECH_CATALOG.iter().map(|item| (item, SPEECH_MODEL_CATEGORY)));
+            for (item, category) in catalog {
                 if state.model_repo.get_by_name(item.name).await?.is_some() {
                     continue;
                 }
@@ -277,13 +450,17 @@ pub(crate) async fn ensure_catalog_seeded(state: &Arc<AppState>) -> Result<(), A
                     version: None,
                     parameter_count: Some(item.parameter_count.to_string()),
                     quantization: Some(item.quantization.to_string()),
-                    file_format: "gguf".to_string(),
+                    file_format: if category == "chat" { "gguf" } else { "ggml" }.to_string(),
                     file_path: None,
                     file_size_mb: None,
                     context_length: item.context_length,
                     embedding_size: None,
-                    category: "chat".to_string(),
-                    capabilities: r#"["chat","local"]"#.to_string(),
+                    category: category.to_string(),
+                    capabilities: match (category, item.mmproj_url) {
+                        ("chat", Some(_)) => r#"["chat","local","vision"]"#.to_string(),
+                        ("chat", None) => r#"["chat","local"]"#.to_string(),
+                        _ => r#"["stt","local"]"#.to_string(),
+                    },
                     min_ram_mb: item.min_ram_mb,
                     recommended_ram_mb: item.recommended_ram_mb,
                     min_vram_mb: item.min_vram_mb,
@@ -291,8 +468,17 @@ pub(crate) async fn ensure_catalog_seeded(state: &Arc<AppState>) -> Result<(), A
                     energy_tier: item.energy_tier.to_string(),
                     download_url: Some(item.download_url.to_string()),
                     sha256_checksum: None,
-                    tags: r#"["gguf","local"]"#.to_string(),
-                    metadata: "{}".to_string(),
+                    tags: if category == "chat" {
+                        r#"["gguf","local"]"#
+                    } else {
+                        r#"["ggml","local"]"#
+                    }
+                    .to_string(),
+                    metadata: match item.mmproj_url {
+                        Some(url) => serde_json::json!({ "visionProjector": { "url": url } })
+                            .to_string(),
+                        None => "{}".to_string(),
+                    },
                 };
 
                 let _ = state.model_repo.insert_model(new_model).await?;
@@ -461,7 +647,12 @@ pub(crate) async fn run_nlp_setup_inner(
         }
 
         if target.is_none() {
-            target = state.model_repo.list_all().await?.into_iter().next();
+            target = state
+                .model_repo
+                .list_all()
+                .await?
+                .into_iter()
+                .find(|model| model.category == "chat");
         }
 
         target.ok_or_else(|| AppError::NotFound {
@@ -509,14 +700,46 @@ pub async fn get_recommended_models(
     state.recommendation.recompute(&profile, mode).await
 }
 
+#[tauri::command]
+pub async fn refresh_recommendations(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<ModelRecommendation>, AppError> {
+    crate::log_info!("sarah.command", "refresh_recommendations invoked");
+    ensure_catalog_seeded(&state).await?;
+
+    let profile = state
+        .hardware
+        .read()
+        .await
+        .clone()
+        .ok_or_else(|| AppError::NotFound {
+            entity: "system_profile".to_string(),
+            id: "current".to_string(),
+        })?;
+
+    let mode = state.hardware_service.get_performance_mode(None).await;
+    let recs = state.recommendation.recompute(&profile, mode).await?;
+    let _ = app.emit(
+        "recommendations:updated",
+        serde_json::json!({
+            "systemProfileId": profile.id,
+            "count": recs.len(),
+        }),
+    );
+    Ok(recs)
+}
+
 #[tauri::command]
 pub async fn set_default_model(
+    app: tauri::AppHandle,
     state: State<'_, Arc<AppState>>,
     model_id: String,
 ) -> Result<(), AppError> {
     crate::log_info!("sarah.command", "set_default_model invoked");
     state.model_repo.set_default_model(&model_id).await?;
     refresh_installed_cache(&state).await?;
+    crate::tray::refresh(&app);
     Ok(())
 }
 
@@ -628,6 +851,19 @@ pub(crate) async fn start_model_download_inner(
         DOWNLOAD_TRACKER.insert(canonical_id.clone(), completed);
         refresh_installed_cache(&state).await?;
 
+        // Weights downloaded before the projector was known still get one.
+        let state_cloned = Arc::clone(&state);
+        tokio::spawn(async move {
+            if let Err(error) = ensure_vision_projector(&state_cloned, &model, &models_dir).await {
+                crate::log_warn!(
+                    "sarah.models",
+                    "Vision projector for {} unavailable: {}",
+                    model.name,
+                    error
+                );
+            }
+        });
+
         return Ok(DownloadHandle {
             model_id: canonical_id,
             status: "already_downloaded".to_string(),
@@ -645,6 +881,7 @@ pub(crate) async fn start_model_download_inner(
     let model_url_cloned = model_url.clone();
     let final_path_cloned = final_path.clone();
     let temp_path_cloned = temp_path.clone();
+    let model_cloned = model.clone();
 
     tokio::spawn(async move {
         let run = async {
@@ -739,14 +976,15 @@ pub(crate) async fn start_model_download_inner(
             .bind(&canonical_id_cloned)
             .execute(state_cloned.db.write_pool())
             .await?;
+            ensure_vision_projector(&state_cloned, &model_cloned, &models_dir).await?;
 
             let has_default: (i64,) = sqlx::query_as(
-                "SELECT COUNT(*) as count FROM models WHERE is_default = 1 AND is_downloaded = 1",
+                "SELECT COUNT(*) as count FROM models WHERE is_default = 1 AND is_downloaded = 1 AND category = 'chat'",
             )
             .fetch_one(state_cloned.db.read_pool())
             .await?;
 
-            if has_default.0 == 0 {
+            if has_default.0 == 0 && model_cloned.category == "chat" {
                 sqlx::query("UPDATE models SET is_default = 1, is_active = 1 WHERE id = ?1")
                     .bind(&canonical_id_cloned)
                     .execute(state_cloned.db.write_pool())
@@ -772,6 +1010,28 @@ pub(crate) async fn start_model_download_inner(
             .await?;
 
             refresh_installed_cache(&state_cloned).await?;
+            state_cloned.background.request_recommendation_refresh();
+            state_cloned
+                .notifications
+                .notify(
+                    NotificationCategory::ModelDownloads,
+                    "Model downloaded",
+                    &format!("{} is ready to use.", model_cloned.display_name),
+                )
+                .await;
+            state_cloned
+                .webhooks
+                .publish(
+                    None,
+                    WebhookEvent::DownloadFinished,
+                    serde_json::json!({
+                        "modelId": canonical_id_cloned,
+                        "name": model_cloned.display_name,
+                        "status": "completed",
+                        "filePath": completed.file_path,
+                    }),
+                )
+                .await;
             Ok::<(), AppError>(())
         };
 
@@ -794,6 +1054,27 @@ pub(crate) async fn start_model_download_inner(
                 &failed,
             )
             .await;
+            state_cloned
+                .notifications
+                .notify(
+                    NotificationCategory::ModelDownloads,
+                    "Model download failed",
+                    &format!("{}: {error}", model_cloned.display_name),
+                )
+                .await;
+            state_cloned
+                .webhooks
+                .publish(
+                    None,
+                    WebhookEvent::DownloadFinished,
+                    serde_json::json!({
+                        "modelId": canonical_id_cloned,
+                        "name": model_cloned.display_name,
+                        "status": "failed",
+                        "error": error.to_string(),
+                    }),
+                )
+                .await;
         }
     });
 
@@ -866,3 +1147,106 @@ pub async fn get_download_progress(
         file_path: None,
     })
 }
+
+/// Adapter scales above this mostly produce garbage.
+const MAX_LORA_SCALE: f32 = 4.0;
+
+#[tauri::command]
+pub async fn load_lora_adapter(
+    state: State<'_, Arc<AppState>>,
+    model_id: String,
+    adapter_path: String,
+    scale: Option<f32>,
+) -> Result<LoraAdapter, AppError> {
+    crate::log_info!("sarah.command", "load_lora_adapter invoked");
+    let scale = scale.unwrap_or(1.0);
+    if !(0.0..=MAX_LORA_SCALE).contains(&scale) {
+        return Err(AppError::Validation {
+            field: "scale".to_string(),
+            message: format!("LoRA scale must be between 0 and {MAX_LORA_SCALE}"),
+        });
+    }
+
+    let path = Path::new(&adapter_path);
+    let is_gguf = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gguf"));
+    if !is_gguf || !path.is_file() {
+        return Err(AppError::Validation {
+            field: "adapter_path".to_string(),
+            message: format!("{adapter_path} is not a GGUF adapter file"),
+        });
+    }
+
+    ensure_catalog_seeded(&state).await?;
+    let model = resolve_model(&state, &model_id).await?;
+    let model_path = model
+        .file_path
+        .clone()
+        .ok_or_else(|| AppError::Validation {
+            field: "model_id".to_string(),
+            message: format!("Model {} is not downloaded", model.display_name),
+        })?;
+
+    let name = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(&adapter_path)
+        .to_string();
+    let adapter = state
+        .adapter_repo
+        .upsert_active(&model.id, &name, &adapter_path, scale as f64)
+        .await?;
+
+    // Models that aren't resident pick the adapter up when they're loaded.
+    if state.inference.is_resident(&model_path) {
+        if let Err(error) = state
+            .inference
+            .apply_lora_adapter(&model_path, &adapter_path, scale)
+        {
+            state.adapter_repo.set_active(&adapter.id, false).await?;
+            return Err(error);
+        }
+    }
+
+    Ok(adapter)
+}
+
+#[tauri::command]
+pub async fn unload_lora_adapter(
+    state: State<'_, Arc<AppState>>,
+    adapter_id: String,
+) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "unload_lora_adapter invoked");
+    let adapter = state
+        .adapter_repo
+        .get_adapter(&adapter_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound {
+            entity: "adapter".to_string(),
+            id: adapter_id.clone(),
+        })?;
+    state.adapter_repo.set_active(&adapter.id, false).await?;
+
+    if let Some(model_path) = state
+        .model_repo
+        .get_by_id(&adapter.model_id)
+        .await?
+        .and_then(|model| model.file_path)
+    {
+        state
+            .inference
+            .remove_lora_adapter(&model_path, &adapter.file_path);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_lora_adapters(
+    state: State<'_, Arc<AppState>>,
+    model_id: Option<String>,
+) -> Result<Vec<LoraAdapter>, AppError> {
+    crate::log_info!("sarah.command", "list_lora_adapters invoked");
+    state.adapter_repo.list_adapters(model_id.as_deref()).await
+}
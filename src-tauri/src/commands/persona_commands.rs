@@ -0,0 +1,157 @@
+use std::sync::Arc;
+
+use tauri::State;
+
+use crate::db::models::{NewPersona, Persona};
+use crate::error::AppError;
+use crate::services::context_service::MAX_DEFAULT_INSTRUCTIONS_CHARS;
+use crate::state::AppState;
+
+const MAX_PERSONA_NAME_CHARS: usize = 64;
+
+#[tauri::command]
+pub async fn list_personas(
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+) -> Result<Vec<Persona>, AppError> {
+    crate::log_info!("sarah.command", "list_personas invoked");
+    state.persona_repo.list_personas(&user_id).await
+}
+
+#[tauri::command]
+pub async fn create_persona(
+    state: State<'_, Arc<AppState>>,
+    persona: NewPersona,
+) -> Result<Persona, AppError> {
+    crate::log_info!("sarah.command", "create_persona invoked");
+    let persona = validate_persona(&state, persona, None).await?;
+    state.persona_repo.create_persona(persona).await
+}
+
+/// Replaces the persona's name, prompt, temperature and preferred model.
+/// Sessions using it pick the changes up on their next turn.
+#[tauri::command]
+pub async fn update_persona(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    persona: NewPersona,
+) -> Result<Persona, AppError> {
+    crate::log_info!("sarah.command", "update_persona invoked");
+    let existing =
+        state
+            .persona_repo
+            .get_persona(&id)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "persona".to_string(),
+                id: id.clone(),
+            })?;
+    let persona = validate_persona(
+        &state,
+        NewPersona {
+            user_id: existing.user_id,
+            ..persona
+        },
+        Some(&id),
+    )
+    .await?;
+    state.persona_repo.update_persona(&id, &persona).await
+}
+
+#[tauri::command]
+pub async fn delete_persona(state: State<'_, Arc<AppState>>, id: String) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "delete_persona invoked");
+    state.persona_repo.delete_persona(&id).await
+}
+
+/// Has the session speak as `persona_id`, or as Sarah again when it is `None`.
+#[tauri::command]
+pub async fn set_session_persona(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    persona_id: Option<String>,
+) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "set_session_persona invoked");
+    let persona_id = persona_id.filter(|id| !id.trim().is_empty());
+    if let Some(id) = persona_id.as_deref() {
+        if state.persona_repo.get_persona(id).await?.is_none() {
+            return Err(AppError::NotFound {
+                entity: "persona".to_string(),
+                id: id.to_string(),
+            });
+        }
+    }
+    state
+        .persona_repo
+        .set_session_persona(&session_id, persona_id.as_deref())
+        .await
+}
+
+async fn validate_persona(
+    state: &Arc<AppState>,
+    persona: NewPersona,
+    except_id: Option<&str>,
+) -> Result<NewPersona, AppError> {
+    let name = persona.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_PERSONA_NAME_CHARS {
+        return Err(AppError::Validation {
+            field: "name".to_string(),
+            message: format!("Persona names must be 1-{MAX_PERSONA_NAME_CHARS} characters"),
+        });
+    }
+    let taken = state
+        .persona_repo
+        .list_personas(&persona.user_id)
+        .await?
+        .iter()
+        .any(|existing| {
+            existing.name.eq_ignore_ascii_case(&name) && Some(existing.id.as_str()) != except_id
+        });
+    if taken {
+        return Err(AppError::Validation {
+            field: "name".to_string(),
+            message: format!("A persona named '{name}' already exists"),
+        });
+    }
+
+    let system_prompt = persona.system_prompt.trim().to_string();
+    if system_prompt.is_empty() || system_prompt.chars().count() > MAX_DEFAULT_INSTRUCTIONS_CHARS {
+        return Err(AppError::Validation {
+            field: "system_prompt".to_string(),
+            message: format!("Prompts must be 1-{MAX_DEFAULT_INSTRUCTIONS_CHARS} characters"),
+        });
+    }
+
+    if persona
+        .temperature
+        .is_some_and(|temperature| !(0.0..=2.0).contains(&temperature))
+    {
+        return Err(AppError::Validation {
+            field: "temperature".to_string(),
+            message: "Temperature must be between 0 and 2".to_string(),
+        });
+    }
+
+    let preferred_model_id = persona
+        .preferred_model_id
+        .filter(|model_id| !model_id.trim().is_empty());
+    if let Some(model_id) = preferred_model_id.as_deref() {
+        if state.model_repo.get_by_id(model_id).await?.is_none() {
+            return Err(AppError::Validation {
+                field: "preferred_model_id".to_string(),
+                message: format!("Unknown model '{model_id}'"),
+            });
+        }
+    }
+
+    Ok(NewPersona {
+        name,
+        description: persona
+            .description
+            .map(|description| description.trim().to_string())
+            .filter(|description| !description.is_empty()),
+        system_prompt,
+        preferred_model_id,
+        ..persona
+    })
+}
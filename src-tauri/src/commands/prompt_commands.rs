@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tauri::State;
+
+use crate::commands::chat_commands::{forward_stream_to_window, SendMessageResponse};
+use crate::db::models::{NewSavedPrompt, SavedPrompt};
+use crate::error::AppError;
+use crate::repositories::saved_prompt_repo::render_template;
+use crate::services::generation_presets::validate_preset_name;
+use crate::services::task_router_service::validate_task_type;
+use crate::state::AppState;
+
+#[tauri::command]
+pub async fn list_saved_prompts(
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+) -> Result<Vec<SavedPrompt>, AppError> {
+    crate::log_info!("sarah.command", "list_saved_prompts invoked");
+    state.saved_prompt_repo.list_prompts(&user_id).await
+}
+
+#[tauri::command]
+pub async fn create_saved_prompt(
+    state: State<'_, Arc<AppState>>,
+    prompt: NewSavedPrompt,
+) -> Result<SavedPrompt, AppError> {
+    crate::log_info!("sarah.command", "create_saved_prompt invoked");
+    let title = require_text("title", &prompt.title)?;
+    let template = require_text("template", &prompt.template)?;
+    let task_type = prompt
+        .task_type
+        .as_deref()
+        .map(validate_task_type)
+        .transpose()?;
+    let preset = prompt
+        .preset
+        .as_deref()
+        .map(validate_preset_name)
+        .transpose()?;
+
+    state
+        .saved_prompt_repo
+        .create_prompt(NewSavedPrompt {
+            title,
+            template,
+            task_type,
+            preset,
+            ..prompt
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn update_saved_prompt(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    title: Option<String>,
+    description: Option<String>,
+    template: Option<String>,
+    task_type: Option<String>,
+    preset: Option<String>,
+) -> Result<SavedPrompt, AppError> {
+    crate::log_info!("sarah.command", "update_saved_prompt invoked");
+    let title = title
+        .map(|value| require_text("title", &value))
+        .transpose()?;
+    let template = template
+        .map(|value| require_text("template", &value))
+        .transpose()?;
+    let task_type = task_type.as_deref().map(validate_task_type).transpose()?;
+    let preset = preset.as_deref().map(validate_preset_name).transpose()?;
+
+    state
+        .saved_prompt_repo
+        .update_prompt(
+            &id,
+            title.as_deref(),
+            description.as_deref(),
+            template.as_deref(),
+            task_type.as_deref(),
+            preset.as_deref(),
+        )
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_saved_prompt(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "delete_saved_prompt invoked");
+    state.saved_prompt_repo.delete_prompt(&id).await
+}
+
+#[tauri::command]
+pub async fn run_saved_prompt(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    vars: HashMap<String, String>,
+    session_id: Option<String>,
+) -> Result<SendMessageResponse, AppError> {
+    crate::log_info!("sarah.command", "run_saved_prompt invoked");
+    let prompt = state
+        .saved_prompt_repo
+        .get_prompt(&id)
+        .await?
+        .ok_or_else(|| AppError::NotFound {
+            entity: "saved_prompt".to_string(),
+            id: id.clone(),
+        })?;
+
+    let content =
+        render_template(&prompt.template, &vars).map_err(|missing| AppError::Validation {
+            field: "vars".to_string(),
+            message: format!("Missing value for template variable '{missing}'"),
+        })?;
+
+    let session_id = match session_id {
+        Some(session_id) => session_id,
+        None => {
+            state
+                .conversation_repo
+                .create_session(&prompt.user_id, None)
+                .await?
+                .id
+        }
+    };
+
+    let stream = state
+        .conversation
+        .send_message(
+            &prompt.user_id,
+            &session_id,
+            &content,
+            &[],
+            None,
+            None,
+            prompt.task_type.as_deref(),
+            None,
+            false,
+            prompt.preset.as_deref(),
+            false,
+        )
+        .await?;
+    state.saved_prompt_repo.mark_used(&prompt.id).await?;
+
+    let mode = state
+        .hardware_service
+        .get_performance_mode(Some(&prompt.user_id))
+        .await;
+    forward_stream_to_window(
+        app,
+        window.label().to_string(),
+        session_id.clone(),
+        mode,
+        stream,
+    );
+
+    Ok(SendMessageResponse {
+        accepted: true,
+        session_id,
+    })
+}
+
+fn require_text(field: &str, value: &str) -> Result<String, AppError> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        return Err(AppError::Validation {
+            field: field.to_string(),
+            message: format!("{field} must not be empty"),
+        });
+    }
+    Ok(trimmed.to_string())
+}
@@ -1,13 +1,22 @@
 use std::sync::Arc;
 
-use tauri::State;
+use tauri::{Manager, State};
 
-use crate::db::models::RetrievedChunk;
+use crate::commands::workspace_commands::{namespace_from_name, validate_namespace};
+use crate::db::models::{
+    RagCollectionSummary, RagStats, ReindexJob, RetrievedChunk, SessionRagSettings,
+    WatchedFolderStatus,
+};
 use crate::error::AppError;
 use crate::services::background_service::BackgroundTask;
-use crate::services::rag_service::RagService;
+use crate::services::rag_service::{RagService, DEFAULT_NAMESPACE, MAX_RERANK_CANDIDATES};
 use crate::state::AppState;
 
+const MAX_COLLECTION_NAME_CHARS: usize = 64;
+/// Retrieval runs one query per turn whatever the count, but reranking more
+/// than a handful of collections mostly adds noise.
+const MAX_SESSION_COLLECTIONS: usize = 8;
+
 fn get_rag(state: &Arc<AppState>) -> Result<&Arc<RagService>, AppError> {
     state.rag.as_ref().ok_or_else(|| AppError::Validation {
         field: "rag".to_string(),
@@ -32,6 +41,34 @@ pub async fn ingest_document(
     Ok(document_id)
 }
 
+/// Fetches a web page with the shared HTTP client and indexes its readable
+/// text into `namespace` (the active workspace's when omitted).
+#[tauri::command]
+pub async fn ingest_url(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+    url: String,
+    namespace: Option<String>,
+) -> Result<String, AppError> {
+    crate::log_info!("sarah.command", "ingest_url invoked");
+    let rag = get_rag(&state)?;
+    let namespace = match namespace {
+        Some(namespace) => namespace,
+        None => rag.default_namespace(&user_id).await,
+    };
+
+    let client = app.state::<reqwest::Client>();
+    let document_id = rag
+        .ingest_url(&client, &user_id, url.trim(), &namespace)
+        .await?;
+    let _ = state
+        .background
+        .sender()
+        .send(BackgroundTask::EmbedDocument(document_id.clone()));
+    Ok(document_id)
+}
+
 #[tauri::command]
 pub async fn embed_document(
     state: State<'_, Arc<AppState>>,
@@ -52,12 +89,248 @@ pub async fn retrieve_knowledge(
 ) -> Result<Vec<RetrievedChunk>, AppError> {
     crate::log_info!("sarah.command", "retrieve_knowledge invoked");
     let rag = get_rag(&state)?;
+    let namespace = match namespace {
+        Some(namespace) => namespace,
+        None => rag.default_namespace(&user_id).await,
+    };
+
+    rag.retrieve(&user_id, &query, &namespace, limit.unwrap_or(6))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_rag_stats(
+    state: State<'_, Arc<AppState>>,
+    namespace: Option<String>,
+    user_id: Option<String>,
+) -> Result<RagStats, AppError> {
+    crate::log_info!("sarah.command", "get_rag_stats invoked");
+    let rag = get_rag(&state)?;
+    let namespace = match (namespace, user_id) {
+        (Some(namespace), _) => namespace,
+        (None, Some(user_id)) => rag.default_namespace(&user_id).await,
+        (None, None) => DEFAULT_NAMESPACE.to_string(),
+    };
+    rag.stats(&namespace).await
+}
+
+/// Keeps every supported file under `path` indexed into `namespace` (the
+/// active workspace's when omitted) as files are added, changed or deleted.
+#[tauri::command]
+pub async fn watch_folder(
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+    path: String,
+    namespace: Option<String>,
+) -> Result<WatchedFolderStatus, AppError> {
+    crate::log_info!("sarah.command", "watch_folder invoked");
+    let rag = get_rag(&state)?;
+    let folder = std::fs::canonicalize(&path)
+        .ok()
+        .filter(|folder| folder.is_dir())
+        .ok_or_else(|| AppError::Validation {
+            field: "path".to_string(),
+            message: format!("Not a folder: {path}"),
+        })?;
+    let namespace = match namespace {
+        Some(namespace) => namespace,
+        None => rag.default_namespace(&user_id).await,
+    };
+
+    state
+        .background
+        .watch_folder(&user_id, &folder.to_string_lossy(), &namespace)
+        .await
+}
+
+#[tauri::command]
+pub async fn unwatch_folder(
+    state: State<'_, Arc<AppState>>,
+    folder_id: String,
+) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "unwatch_folder invoked");
+    state.background.unwatch_folder(&folder_id).await
+}
+
+#[tauri::command]
+pub async fn list_watched_folders(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<WatchedFolderStatus>, AppError> {
+    crate::log_info!("sarah.command", "list_watched_folders invoked");
+    Ok(state.background.watched_folders())
+}
+
+/// Re-embeds every stored vector with `target_model` in the background. The
+/// new vectors replace the old ones in one step once all are computed.
+#[tauri::command]
+pub async fn reindex_documents(
+    state: State<'_, Arc<AppState>>,
+    target_model: String,
+) -> Result<ReindexJob, AppError> {
+    crate::log_info!("sarah.command", "reindex_documents invoked");
+    state.reindex.start(&target_model).await
+}
+
+#[tauri::command]
+pub async fn get_reindex_status(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Option<ReindexJob>, AppError> {
+    crate::log_info!("sarah.command", "get_reindex_status invoked");
+    state.reindex.status().await
+}
+
+#[tauri::command]
+pub async fn list_collections(
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+) -> Result<Vec<RagCollectionSummary>, AppError> {
+    crate::log_info!("sarah.command", "list_collections invoked");
+    get_rag(&state)?.list_collections(&user_id).await
+}
+
+/// `namespace` defaults to a slug of `name`, e.g. "Work docs" -> "work-docs".
+#[tauri::command]
+pub async fn create_collection(
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+    name: String,
+    namespace: Option<String>,
+    description: Option<String>,
+) -> Result<RagCollectionSummary, AppError> {
+    crate::log_info!("sarah.command", "create_collection invoked");
+    let rag = get_rag(&state)?;
+    let name = name.trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_COLLECTION_NAME_CHARS {
+        return Err(AppError::Validation {
+            field: "name".to_string(),
+            message: format!("Collection names must be 1-{MAX_COLLECTION_NAME_CHARS} characters"),
+        });
+    }
+    let namespace = match namespace.as_deref() {
+        Some(namespace) => validate_namespace(namespace)?,
+        None => namespace_from_name(&name, "collection"),
+    };
+    let description = description
+        .as_deref()
+        .map(str::trim)
+        .filter(|description| !description.is_empty());
+
+    rag.create_collection(&user_id, &name, &namespace, description)
+        .await
+}
+
+/// Deletes the collection with all of its documents. Returns how many
+/// documents were removed.
+#[tauri::command]
+pub async fn delete_collection(
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+    namespace: String,
+) -> Result<usize, AppError> {
+    crate::log_info!("sarah.command", "delete_collection invoked");
+    let rag = get_rag(&state)?;
+    if state
+        .background
+        .watched_folders()
+        .iter()
+        .any(|folder| folder.namespace == namespace)
+    {
+        return Err(AppError::Validation {
+            field: "namespace".to_string(),
+            message: "A watched folder indexes into this collection; stop watching it first"
+                .to_string(),
+        });
+    }
+    rag.delete_collection(&user_id, &namespace).await
+}
+
+#[tauri::command]
+pub async fn get_session_rag_settings(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<SessionRagSettings, AppError> {
+    crate::log_info!("sarah.command", "get_session_rag_settings invoked");
+    state
+        .conversation_repo
+        .get_session_rag_settings(&session_id)
+        .await
+}
+
+#[tauri::command]
+pub async fn set_session_rag_settings(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    settings: SessionRagSettings,
+) -> Result<SessionRagSettings, AppError> {
+    crate::log_info!("sarah.command", "set_session_rag_settings invoked");
+    if !(1..=20).contains(&settings.chunks) {
+        return Err(AppError::Validation {
+            field: "chunks".to_string(),
+            message: "chunks must be between 1 and 20".to_string(),
+        });
+    }
+    if settings.rerank_top_k < settings.chunks || settings.rerank_top_k > MAX_RERANK_CANDIDATES {
+        return Err(AppError::Validation {
+            field: "rerankTopK".to_string(),
+            message: format!(
+                "rerankTopK must be between chunks ({}) and {MAX_RERANK_CANDIDATES}",
+                settings.chunks
+            ),
+        });
+    }
+
+    let settings = SessionRagSettings {
+        collections: validate_session_collections(&state, &session_id, &settings.collections)
+            .await?,
+        ..settings
+    };
+
+    state
+        .conversation_repo
+        .set_session_rag_settings(&session_id, &settings)
+        .await?;
+    Ok(settings)
+}
+
+/// Deduplicates the session's collections and checks each one exists for the
+/// session's user.
+async fn validate_session_collections(
+    state: &Arc<AppState>,
+    session_id: &str,
+    collections: &[String],
+) -> Result<Vec<String>, AppError> {
+    if collections.is_empty() {
+        return Ok(Vec::new());
+    }
+    if collections.len() > MAX_SESSION_COLLECTIONS {
+        return Err(AppError::Validation {
+            field: "collections".to_string(),
+            message: format!("At most {MAX_SESSION_COLLECTIONS} collections per session"),
+        });
+    }
+
+    let session = state
+        .conversation_repo
+        .get_session(session_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound {
+            entity: "session".to_string(),
+            id: session_id.to_string(),
+        })?;
+    let known = get_rag(state)?.list_collections(&session.user_id).await?;
 
-    rag.retrieve(
-        &user_id,
-        &query,
-        namespace.as_deref().unwrap_or("personal"),
-        limit.unwrap_or(6),
-    )
-    .await
+    let mut validated = Vec::new();
+    for collection in collections {
+        let namespace = validate_namespace(collection)?;
+        if !known.iter().any(|summary| summary.namespace == namespace) {
+            return Err(AppError::Validation {
+                field: "collections".to_string(),
+                message: format!("Unknown collection: {namespace}"),
+            });
+        }
+        if !validated.contains(&namespace) {
+            validated.push(namespace);
+        }
+    }
+    Ok(validated)
 }
@@ -0,0 +1,163 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager, State};
+
+use crate::db::models::{
+    DatabaseBackup, DatabaseEncryptionStatus, DatabaseMaintenanceReport, StartupStatus,
+};
+use crate::error::AppError;
+use crate::services::crypto_service::CryptoService;
+use crate::services::recovery_service::{self, SafeMode};
+use crate::state::AppState;
+
+/// Works in both modes: reports the startup failure (if any) and available backups.
+#[tauri::command]
+pub async fn get_startup_status(app: AppHandle) -> Result<StartupStatus, AppError> {
+    crate::log_info!("sarah.command", "get_startup_status invoked");
+    let safe_mode = app.try_state::<Arc<SafeMode>>();
+    let (db_path, _) = database_handle(&app)?;
+    let integrity = match safe_mode.as_ref() {
+        Some(safe_mode) => safe_mode.quick_check().await,
+        None => None,
+    };
+
+    Ok(StartupStatus {
+        safe_mode: safe_mode.is_some(),
+        failure: safe_mode.as_ref().map(|safe_mode| safe_mode.failure.clone()),
+        db_path: db_path.to_string_lossy().to_string(),
+        integrity,
+        backups: recovery_service::list_backups(&db_path).await?,
+    })
+}
+
+#[tauri::command]
+pub async fn backup_database(app: AppHandle) -> Result<DatabaseBackup, AppError> {
+    crate::log_info!("sarah.command", "backup_database invoked");
+    let (db_path, pool) = database_handle(&app)?;
+    recovery_service::create_backup(&db_path, pool.as_ref()).await
+}
+
+#[tauri::command]
+pub async fn list_database_backups(app: AppHandle) -> Result<Vec<DatabaseBackup>, AppError> {
+    crate::log_info!("sarah.command", "list_database_backups invoked");
+    let (db_path, _) = database_handle(&app)?;
+    recovery_service::list_backups(&db_path).await
+}
+
+/// Swaps in a backup and relaunches, since every service holds pools on the old file.
+#[tauri::command]
+pub async fn restore_database_backup(app: AppHandle, file_name: String) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "restore_database_backup invoked");
+    let (db_path, _) = database_handle(&app)?;
+
+    if let Some(state) = app.try_state::<Arc<AppState>>() {
+        state.background.stop_all().await;
+        state.db.close().await;
+    }
+    if let Some(db) = app
+        .try_state::<Arc<SafeMode>>()
+        .and_then(|safe_mode| safe_mode.db.clone())
+    {
+        db.close().await;
+    }
+
+    recovery_service::restore_backup(&db_path, &file_name).await?;
+    app.restart();
+}
+
+/// Integrity check, WAL checkpoint and vacuum, with a size breakdown for the
+/// settings window. Runs on the write connection, so chat writes wait for it.
+#[tauri::command]
+pub async fn run_db_maintenance(
+    state: State<'_, Arc<AppState>>,
+) -> Result<DatabaseMaintenanceReport, AppError> {
+    crate::log_info!("sarah.command", "run_db_maintenance invoked");
+    state.db.run_maintenance().await
+}
+
+#[tauri::command]
+pub async fn get_database_encryption(app: AppHandle) -> Result<DatabaseEncryptionStatus, AppError> {
+    crate::log_info!("sarah.command", "get_database_encryption invoked");
+    let (db_path, _) = database_handle(&app)?;
+    Ok(DatabaseEncryptionStatus {
+        encrypted: crate::db::is_encrypted_file(&db_path),
+        supported: cfg!(feature = "sqlcipher"),
+    })
+}
+
+/// One-time migration of a plaintext database to SQLCipher. The key is created
+/// in the OS keychain, the data is exported to a sidecar file, and the app
+/// relaunches on the encrypted copy.
+#[tauri::command]
+pub async fn encrypt_database(
+    app: AppHandle,
+    remove_plaintext_backups: Option<bool>,
+) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "encrypt_database invoked");
+    let state = app.try_state::<Arc<AppState>>().ok_or_else(|| {
+        AppError::Config("The database cannot be encrypted in safe mode".to_string())
+    })?;
+    if state.db.encrypted {
+        return Err(AppError::Validation {
+            field: "database".to_string(),
+            message: "The database is already encrypted".to_string(),
+        });
+    }
+    if !cfg!(feature = "sqlcipher") {
+        return Err(AppError::Config(
+            "This build was compiled without SQLCipher support".to_string(),
+        ));
+    }
+
+    let bundle_id = app.config().identifier.clone();
+    let key = CryptoService::database_key(&bundle_id, true)?
+        .ok_or_else(|| AppError::Crypto("Failed to create the database key".to_string()))?;
+
+    let db_path = state.db.db_path.clone();
+    let mut encrypted_path = db_path.clone().into_os_string();
+    encrypted_path.push(".encrypting");
+    let encrypted_path = PathBuf::from(encrypted_path);
+    let _ = tokio::fs::remove_file(&encrypted_path).await;
+
+    // Nothing may write once the copy starts, or the write would be lost in the
+    // swap: stop background work and drain both pools first.
+    state.background.stop_all().await;
+    state.db.close().await;
+    if let Err(error) = state.db.export_encrypted(&encrypted_path, &key).await {
+        let _ = tokio::fs::remove_file(&encrypted_path).await;
+        // The pools are closed; restarting reopens the untouched plaintext file.
+        crate::log_error!("sarah.recovery", "Database encryption failed: {}", error);
+        app.restart();
+    }
+    recovery_service::swap_in_encrypted(
+        &db_path,
+        &encrypted_path,
+        remove_plaintext_backups.unwrap_or(false),
+    )
+    .await?;
+    app.restart();
+}
+
+#[tauri::command]
+pub fn restart_app(app: AppHandle) {
+    crate::log_info!("sarah.command", "restart_app invoked");
+    app.restart();
+}
+
+fn database_handle(app: &AppHandle) -> Result<(PathBuf, Option<SqlitePool>), AppError> {
+    if let Some(state) = app.try_state::<Arc<AppState>>() {
+        return Ok((
+            state.db.db_path.clone(),
+            Some(state.db.write_pool().clone()),
+        ));
+    }
+    if let Some(safe_mode) = app.try_state::<Arc<SafeMode>>() {
+        return Ok((
+            safe_mode.db_path.clone(),
+            safe_mode.db.as_ref().map(|db| db.read_pool().clone()),
+        ));
+    }
+    Ok((crate::db::database_path(app)?, None))
+}
@@ -1,21 +1,23 @@
 use std::sync::Arc;
-use std::time::Instant;
 
 use tauri::State;
-use tokio::time::Duration;
-use uuid::Uuid;
 
-use crate::commands::model_commands::{
-    ensure_catalog_seeded, run_nlp_setup_inner, start_model_download_inner,
-};
+use crate::commands::model_commands::{ensure_catalog_seeded, resolve_model, run_nlp_setup_inner};
 use crate::db::models::{
-    Message, ModelBenchmark, PerformanceSummary, RoutingDecision, RoutingPreviewRequest,
-    RuntimePolicy, RuntimePolicyPatch, SetupState, SystemProfile,
+    BenchmarkReport, Message, ModelBenchmark, NewModelBenchmark, PerformanceSummary,
+    RoutingDecision, RoutingPreviewRequest, RuntimePolicy, RuntimePolicyPatch, SetupState,
+    SystemProfile,
 };
 use crate::error::AppError;
+use crate::services::background_service::JOB_AUTO_MODEL_UPGRADE;
+use crate::services::benchmark_report;
+use crate::services::inference_queue::InferenceQueueStatus;
+use crate::services::inference_service::ModelInfo;
+use crate::services::prompt_cache::SessionCacheStats;
 use crate::services::runtime_orchestrator_service::{
     OptimizationStatsSnapshot, RuntimeProfileSnapshot, ServiceHealthSnapshot,
 };
+use crate::services::settings_watcher::SettingsWatcherStatus;
 use crate::state::AppState;
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -31,6 +33,24 @@ pub struct StartupTelemetrySnapshot {
     pub active_hardware_tier: String,
 }
 
+/// Settings merged with what the live services are actually running with.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveConfigSnapshot {
+    pub performance_mode: String,
+    pub runtime: RuntimeProfileSnapshot,
+    pub max_context: usize,
+    pub embedding_model: Option<String>,
+    pub reranker_model: Option<String>,
+    pub auto_load_model: bool,
+    /// Cache capacities are fixed when the caches are built at startup.
+    pub embed_cache_capacity: u64,
+    pub session_cache_capacity: u64,
+    pub settings_cache_capacity: u64,
+    pub loaded_model: Option<ModelInfo>,
+    pub settings_watcher: SettingsWatcherStatus,
+}
+
 #[tauri::command]
 pub async fn get_runtime_policy(
     state: State<'_, Arc<AppState>>,
@@ -73,6 +93,29 @@ pub async fn get_service_health(
     Ok(state.runtime_orchestrator.get_service_health().await)
 }
 
+#[tauri::command]
+pub async fn get_inference_queue_status(
+    state: State<'_, Arc<AppState>>,
+) -> Result<InferenceQueueStatus, AppError> {
+    crate::log_info!("sarah.command", "get_inference_queue_status invoked");
+    Ok(state.inference.queue_status())
+}
+
+/// How much of each cached session prompt was served from the KV cache.
+/// Pass `session_id` to get just that session.
+#[tauri::command]
+pub async fn session_cache_stats(
+    state: State<'_, Arc<AppState>>,
+    session_id: Option<String>,
+) -> Result<Vec<SessionCacheStats>, AppError> {
+    crate::log_info!("sarah.command", "session_cache_stats invoked");
+    let mut stats = state.inference.session_cache_stats();
+    if let Some(session_id) = session_id.as_deref() {
+        stats.retain(|entry| entry.session_id == session_id);
+    }
+    Ok(stats)
+}
+
 #[tauri::command]
 pub async fn get_optimization_stats(
     state: State<'_, Arc<AppState>>,
@@ -137,6 +180,38 @@ pub async fn get_startup_telemetry(
     })
 }
 
+#[tauri::command]
+pub async fn get_effective_config(
+    state: State<'_, Arc<AppState>>,
+    user_id: Option<String>,
+) -> Result<EffectiveConfigSnapshot, AppError> {
+    crate::log_info!("sarah.command", "get_effective_config invoked");
+    let user_id = user_id.as_deref();
+    let mode = state.hardware_service.get_performance_mode(user_id).await;
+    let tier_config = state
+        .hardware_service
+        .get_tier_config(state.tier, user_id)
+        .await;
+    let runtime = state
+        .runtime_orchestrator
+        .get_runtime_profile(user_id)
+        .await?;
+
+    Ok(EffectiveConfigSnapshot {
+        performance_mode: mode.as_str().to_string(),
+        runtime,
+        max_context: tier_config.max_context,
+        embedding_model: tier_config.embedding_model,
+        reranker_model: tier_config.reranker_model,
+        auto_load_model: tier_config.auto_load_model,
+        embed_cache_capacity: state.tier_config.embed_cache_capacity,
+        session_cache_capacity: state.tier_config.session_cache_capacity,
+        settings_cache_capacity: state.tier_config.settings_cache_capacity,
+        loaded_model: state.inference.get_active_model_info().await,
+        settings_watcher: state.settings_watcher.status(),
+    })
+}
+
 #[tauri::command]
 pub async fn get_model_routing_decision(
     state: State<'_, Arc<AppState>>,
@@ -154,6 +229,42 @@ pub async fn get_model_routing_decision(
         .await
 }
 
+#[tauri::command]
+pub async fn pin_model_for_task(
+    state: State<'_, Arc<AppState>>,
+    user_id: Option<String>,
+    task_type: String,
+    model_id: String,
+) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "pin_model_for_task invoked");
+    ensure_catalog_seeded(&state).await?;
+    let model = resolve_model(&state, &model_id).await?;
+    if model.is_downloaded != 1 {
+        return Err(AppError::Validation {
+            field: "model_id".to_string(),
+            message: format!("Model {} is not installed", model.display_name),
+        });
+    }
+
+    state
+        .task_router
+        .pin_model(user_id.as_deref(), &task_type, &model.id)
+        .await
+}
+
+#[tauri::command]
+pub async fn unpin_model_for_task(
+    state: State<'_, Arc<AppState>>,
+    user_id: Option<String>,
+    task_type: String,
+) -> Result<bool, AppError> {
+    crate::log_info!("sarah.command", "unpin_model_for_task invoked");
+    state
+        .task_router
+        .unpin_model(user_id.as_deref(), &task_type)
+        .await
+}
+
 #[tauri::command]
 pub async fn get_performance_dashboard(
     state: State<'_, Arc<AppState>>,
@@ -191,16 +302,16 @@ pub async fn get_performance_dashboard(
     .fetch_all(state.db.read_pool())
     .await?;
 
-    let (p50_latency_ms, p95_latency_ms) = if latencies.is_empty() {
-        (None, None)
-    } else {
-        latencies.sort_unstable();
-        let p50_idx = ((latencies.len() as f64) * 0.50).floor() as usize;
-        let p95_idx = ((latencies.len() as f64) * 0.95).floor() as usize;
-        let p50 = latencies[p50_idx.min(latencies.len() - 1)] as f64;
-        let p95 = latencies[p95_idx.min(latencies.len() - 1)] as f64;
-        (Some(p50), Some(p95))
-    };
+    latencies.sort_unstable();
+    let (p50_latency_ms, p95_latency_ms) = percentiles(&latencies);
+
+    let first_token_latencies = sqlx::query_scalar::<_, i64>(
+        "SELECT first_token_ms FROM perf_logs WHERE first_token_ms IS NOT NULL AND datetime(created_at) >= datetime('now', '-' || ?1 || ' hour') ORDER BY first_token_ms ASC",
+    )
+    .bind(window)
+    .fetch_all(state.db.read_pool())
+    .await?;
+    let (p50_first_token_ms, p95_first_token_ms) = percentiles(&first_token_latencies);
 
     Ok(PerformanceSummary {
         window_hours: window,
@@ -209,9 +320,23 @@ pub async fn get_performance_dashboard(
         p50_latency_ms,
         p95_latency_ms,
         avg_tokens_per_sec,
+        p50_first_token_ms,
+        p95_first_token_ms,
     })
 }
 
+fn percentiles(sorted: &[i64]) -> (Option<f64>, Option<f64>) {
+    if sorted.is_empty() {
+        return (None, None);
+    }
+    let p50_idx = ((sorted.len() as f64) * 0.50).floor() as usize;
+    let p95_idx = ((sorted.len() as f64) * 0.95).floor() as usize;
+    (
+        Some(sorted[p50_idx.min(sorted.len() - 1)] as f64),
+        Some(sorted[p95_idx.min(sorted.len() - 1)] as f64),
+    )
+}
+
 #[tauri::command]
 pub async fn run_model_microbenchmark(
     state: State<'_, Arc<AppState>>,
@@ -221,6 +346,34 @@ pub async fn run_model_microbenchmark(
     run_model_microbenchmark_inner(Arc::clone(&state), model_id.as_deref()).await
 }
 
+/// Writes a Markdown (or JSON, for a `.json` path) comparison of every model
+/// benchmarked on the current hardware profile.
+#[tauri::command]
+pub async fn export_benchmark_report(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+) -> Result<BenchmarkReport, AppError> {
+    crate::log_info!("sarah.command", "export_benchmark_report invoked");
+    let path = path.trim();
+    if path.is_empty() {
+        return Err(AppError::Validation {
+            field: "path".to_string(),
+            message: "Choose where to save the report".to_string(),
+        });
+    }
+
+    let hardware = ensure_hardware_profile(&state).await?;
+    let models = state.model_repo.benchmark_summaries(&hardware.id).await?;
+    let report = BenchmarkReport {
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        hardware,
+        models,
+    };
+
+    benchmark_report::write_report(&report, std::path::Path::new(path)).await?;
+    Ok(report)
+}
+
 #[tauri::command]
 pub async fn start_first_run_setup(
     app: tauri::AppHandle,
@@ -252,7 +405,7 @@ pub async fn start_first_run_setup(
         .await?;
 
     let setup_result = match run_nlp_setup_inner(
-        app.clone(),
+        app,
         Arc::clone(&state),
         Some(starter_model.id.clone()),
         user_id.clone(),
@@ -290,7 +443,6 @@ pub async fn start_first_run_setup(
         .await?;
 
     maybe_queue_quality_upgrade(
-        app,
         Arc::clone(&state),
         &profile,
         &setup_result.target_model_id,
@@ -393,9 +545,11 @@ async fn run_model_microbenchmark_inner(
         is_error: 0,
         error_message: None,
         parent_message_id: None,
+        is_active_variant: 1,
         edited_at: None,
         original_content: None,
         metadata: "{}".to_string(),
+        attachments: "[]".to_string(),
         position: 0,
         created_at: String::new(),
         updated_at: String::new(),
@@ -411,40 +565,28 @@ async fn run_model_microbenchmark_inner(
         generated.tokens_generated as f64 / started.elapsed().as_secs_f64().max(0.001);
 
     let stats = state.runtime_governor.current_stats();
-    let benchmark_id = Uuid::new_v4().to_string();
-
-    sqlx::query(
-        r#"
-        INSERT INTO model_benchmarks (
-          id, model_id, system_profile_id, context_tokens, prompt_tokens, output_tokens,
-          load_time_ms, first_token_ms, total_latency_ms, tokens_per_sec, memory_used_mb,
-          cpu_usage_pct, success, metadata
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, NULL, ?8, ?9, ?10, ?11, 1, '{}')
-        "#,
-    )
-    .bind(&benchmark_id)
-    .bind(&selected.id)
-    .bind(&profile.id)
-    .bind(0i64)
-    .bind((prompt.len() / 4) as i64 + 1)
-    .bind(generated.tokens_generated as i64)
-    .bind(load_time_ms)
-    .bind(total_latency_ms)
-    .bind(tokens_per_sec)
-    .bind(stats.memory_used_mb as i64)
-    .bind(stats.cpu_usage_pct as f64)
-    .execute(state.db.write_pool())
-    .await?;
+    let row = state
+        .model_repo
+        .insert_benchmark(NewModelBenchmark {
+            model_id: selected.id.clone(),
+            system_profile_id: Some(profile.id.clone()),
+            prompt_tokens: (prompt.len() / 4) as i64 + 1,
+            output_tokens: generated.tokens_generated as i64,
+            load_time_ms: Some(load_time_ms),
+            first_token_ms: generated.first_token_ms,
+            total_latency_ms,
+            tokens_per_sec: Some(tokens_per_sec),
+            memory_used_mb: Some(stats.memory_used_mb as i64),
+            cpu_usage_pct: Some(stats.cpu_usage_pct as f64),
+            metadata: "{}".to_string(),
+        })
+        .await?;
 
     let _ = state
         .model_repo
         .update_performance_metrics(&selected.id, tokens_per_sec)
         .await;
-
-    let row = sqlx::query_as::<_, ModelBenchmark>("SELECT * FROM model_benchmarks WHERE id = ?1")
-        .bind(&benchmark_id)
-        .fetch_one(state.db.read_pool())
-        .await?;
+    state.background.request_recommendation_refresh();
     Ok(row)
 }
 
@@ -466,7 +608,6 @@ fn choose_starter_bundle(profile: &SystemProfile) -> &'static str {
 }
 
 async fn maybe_queue_quality_upgrade(
-    app: tauri::AppHandle,
     state: Arc<AppState>,
     profile: &SystemProfile,
     starter_model_id: &str,
@@ -492,122 +633,22 @@ async fn maybe_queue_quality_upgrade(
         return;
     }
 
-    let target_id = target.id.clone();
-    let target_display_name = target.display_name.clone();
-
-    let job_id = Uuid::new_v4().to_string();
-    let metadata = serde_json::json!({
-        "targetModelId": target_id.clone(),
-        "targetModelName": target_display_name,
+    let payload = serde_json::json!({
+        "targetModelId": target.id,
+        "targetModelName": target.display_name,
         "starterModelId": starter_model_id,
+        "userId": user_id,
         "strategy": "auto-upgrade-idle-gated"
-    })
-    .to_string();
-
-    let _ = sqlx::query(
-        r#"
-        INSERT INTO background_job_runs (id, job_type, status, deferred_reason, metadata)
-        VALUES (?1, 'auto_model_upgrade', 'queued', NULL, ?2)
-        "#,
-    )
-    .bind(&job_id)
-    .bind(&metadata)
-    .execute(state.db.write_pool())
-    .await;
-
-    let state_cloned = Arc::clone(&state);
-    let app_cloned = app.clone();
-    let user_id_cloned = user_id.clone();
-
-    tokio::spawn(async move {
-        let started = Instant::now();
-        let mut deferred_count = 0usize;
-        let max_attempts = 24usize;
-
-        for _ in 0..max_attempts {
-            let policy = match state_cloned
-                .runtime_governor
-                .get_policy(user_id_cloned.as_deref())
-                .await
-            {
-                Ok(policy) => policy,
-                Err(_) => RuntimePolicy::default(),
-            };
-            let stats = state_cloned.runtime_governor.current_stats();
-            let pressure = state_cloned
-                .runtime_governor
-                .classify_pressure(&stats, &policy);
-
-            let can_upgrade = matches!(pressure.as_str(), "normal" | "warm");
-            if can_upgrade {
-                match start_model_download_inner(
-                    app_cloned.clone(),
-                    Arc::clone(&state_cloned),
-                    target_id.clone(),
-                )
-                .await
-                {
-                    Ok(handle) => {
-                        let _ = sqlx::query(
-                            r#"
-                            UPDATE background_job_runs
-                            SET status = 'submitted',
-                                completed_at = datetime('now','utc'),
-                                latency_ms = ?1,
-                                metadata = json_set(COALESCE(metadata, '{}'), '$.downloadStatus', ?2)
-                            WHERE id = ?3
-                            "#,
-                        )
-                        .bind(started.elapsed().as_millis() as i64)
-                        .bind(handle.status)
-                        .bind(&job_id)
-                        .execute(state_cloned.db.write_pool())
-                        .await;
-                        return;
-                    }
-                    Err(error) => {
-                        let _ = sqlx::query(
-                            r#"
-                            UPDATE background_job_runs
-                            SET status = 'failed',
-                                deferred_reason = ?1,
-                                completed_at = datetime('now','utc'),
-                                latency_ms = ?2
-                            WHERE id = ?3
-                            "#,
-                        )
-                        .bind(error.to_string())
-                        .bind(started.elapsed().as_millis() as i64)
-                        .bind(&job_id)
-                        .execute(state_cloned.db.write_pool())
-                        .await;
-                        return;
-                    }
-                }
-            }
-
-            deferred_count += 1;
-            tokio::time::sleep(Duration::from_secs(30)).await;
-        }
-
-        let _ = sqlx::query(
-            r#"
-            UPDATE background_job_runs
-            SET status = 'deferred',
-                deferred_reason = ?1,
-                completed_at = datetime('now','utc'),
-                latency_ms = ?2,
-                metadata = json_set(COALESCE(metadata, '{}'), '$.deferCount', ?3)
-            WHERE id = ?4
-            "#,
-        )
-        .bind("system remained under pressure during upgrade window")
-        .bind(started.elapsed().as_millis() as i64)
-        .bind(deferred_count as i64)
-        .bind(&job_id)
-        .execute(state_cloned.db.write_pool())
-        .await;
     });
+
+    // The dispatcher holds the download until the machine is calm and idle.
+    if let Err(error) = state
+        .background
+        .enqueue_job(JOB_AUTO_MODEL_UPGRADE, payload, None, 3)
+        .await
+    {
+        tracing::warn!("Failed to queue quality upgrade: {error}");
+    }
 }
 
 fn choose_quality_upgrade_target(profile: &SystemProfile) -> Option<&'static str> {
@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use tauri::State;
+
+use crate::db::models::{BackgroundJob, NewScheduledPrompt, ScheduledPrompt};
+use crate::error::AppError;
+use crate::services::background_service::{
+    next_local_cron_run, schedule_to_cron, JOB_SCHEDULED_PROMPT,
+};
+use crate::state::AppState;
+
+const MAX_SCHEDULED_PROMPT_NAME_CHARS: usize = 64;
+const MAX_SCHEDULED_PROMPT_CHARS: usize = 4_000;
+
+#[tauri::command]
+pub async fn list_scheduled_prompts(
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+) -> Result<Vec<ScheduledPrompt>, AppError> {
+    crate::log_info!("sarah.command", "list_scheduled_prompts invoked");
+    state.scheduled_prompt_repo.list(&user_id).await
+}
+
+/// Schedules `prompt.prompt` to run on `prompt.schedule`, which may be a
+/// phrase like "every Monday at 9am" or a cron expression.
+#[tauri::command]
+pub async fn create_scheduled_prompt(
+    state: State<'_, Arc<AppState>>,
+    prompt: NewScheduledPrompt,
+) -> Result<ScheduledPrompt, AppError> {
+    crate::log_info!("sarah.command", "create_scheduled_prompt invoked");
+    let prompt = validate_scheduled_prompt(prompt)?;
+    let cron = schedule_to_cron(&prompt.schedule)?;
+    let next_run_at = next_local_cron_run(&cron)?;
+    state
+        .scheduled_prompt_repo
+        .create(&prompt, &cron, &next_run_at)
+        .await
+}
+
+/// Replaces the name, prompt and schedule. Later runs keep using the same
+/// session.
+#[tauri::command]
+pub async fn update_scheduled_prompt(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    prompt: NewScheduledPrompt,
+) -> Result<ScheduledPrompt, AppError> {
+    crate::log_info!("sarah.command", "update_scheduled_prompt invoked");
+    let prompt = validate_scheduled_prompt(prompt)?;
+    let cron = schedule_to_cron(&prompt.schedule)?;
+    let next_run_at = next_local_cron_run(&cron)?;
+    state
+        .scheduled_prompt_repo
+        .update(&id, &prompt, &cron, &next_run_at)
+        .await
+}
+
+/// Pauses a scheduled prompt, or resumes it from its next slot.
+#[tauri::command]
+pub async fn set_scheduled_prompt_enabled(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    enabled: bool,
+) -> Result<ScheduledPrompt, AppError> {
+    crate::log_info!("sarah.command", "set_scheduled_prompt_enabled invoked");
+    let existing =
+        state
+            .scheduled_prompt_repo
+            .get(&id)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "scheduled_prompt".to_string(),
+                id: id.clone(),
+            })?;
+    let next_run_at = if enabled {
+        Some(next_local_cron_run(&existing.cron)?)
+    } else {
+        None
+    };
+    state
+        .scheduled_prompt_repo
+        .set_enabled(&id, next_run_at.as_deref())
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_scheduled_prompt(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "delete_scheduled_prompt invoked");
+    state.scheduled_prompt_repo.delete(&id).await
+}
+
+/// Queues a run right away without moving the schedule.
+#[tauri::command]
+pub async fn run_scheduled_prompt_now(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+) -> Result<BackgroundJob, AppError> {
+    crate::log_info!("sarah.command", "run_scheduled_prompt_now invoked");
+    if state.scheduled_prompt_repo.get(&id).await?.is_none() {
+        return Err(AppError::NotFound {
+            entity: "scheduled_prompt".to_string(),
+            id,
+        });
+    }
+    state
+        .background
+        .enqueue_job(
+            JOB_SCHEDULED_PROMPT,
+            serde_json::json!({ "scheduledPromptId": id }),
+            None,
+            1,
+        )
+        .await
+}
+
+fn validate_scheduled_prompt(prompt: NewScheduledPrompt) -> Result<NewScheduledPrompt, AppError> {
+    let name = prompt.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_SCHEDULED_PROMPT_NAME_CHARS {
+        return Err(AppError::Validation {
+            field: "name".to_string(),
+            message: format!("Names must be 1-{MAX_SCHEDULED_PROMPT_NAME_CHARS} characters"),
+        });
+    }
+    let text = prompt.prompt.trim().to_string();
+    if text.is_empty() || text.chars().count() > MAX_SCHEDULED_PROMPT_CHARS {
+        return Err(AppError::Validation {
+            field: "prompt".to_string(),
+            message: format!("Prompts must be 1-{MAX_SCHEDULED_PROMPT_CHARS} characters"),
+        });
+    }
+    Ok(NewScheduledPrompt {
+        name,
+        prompt: text,
+        schedule: prompt.schedule.trim().to_string(),
+        ..prompt
+    })
+}
@@ -2,8 +2,16 @@ use std::sync::Arc;
 
 use tauri::State;
 
+use crate::commands::chat_commands::export_target;
+use crate::db::models::{GenerationPreset, MemoryDecayPolicy};
 use crate::error::AppError;
 use crate::repositories::settings_repo::Setting;
+use crate::services::context_service::{
+    ASSISTANT_SETTINGS_NAMESPACE, DEFAULT_INSTRUCTIONS_KEY, MAX_DEFAULT_INSTRUCTIONS_CHARS,
+};
+use crate::services::notification_service::NotificationPreferences;
+use crate::services::retention_service::{RetentionPolicy, RetentionReport};
+use crate::services::takeout_service::TakeoutExport;
 use crate::state::AppState;
 
 #[tauri::command]
@@ -58,3 +66,217 @@ pub async fn list_settings_namespace(
         .list_namespace(user_id.as_deref(), &namespace)
         .await
 }
+
+#[tauri::command]
+pub async fn get_default_instructions(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Option<String>, AppError> {
+    crate::log_info!("sarah.command", "get_default_instructions invoked");
+    state.context.default_instructions().await
+}
+
+/// Saves the global assistant instructions; an empty value clears them.
+#[tauri::command]
+pub async fn set_default_instructions(
+    state: State<'_, Arc<AppState>>,
+    instructions: String,
+) -> Result<Option<String>, AppError> {
+    crate::log_info!("sarah.command", "set_default_instructions invoked");
+    let instructions = instructions.trim();
+    if instructions.is_empty() {
+        state
+            .settings_repo
+            .delete_setting(None, ASSISTANT_SETTINGS_NAMESPACE, DEFAULT_INSTRUCTIONS_KEY)
+            .await?;
+        return Ok(None);
+    }
+
+    if instructions.chars().count() > MAX_DEFAULT_INSTRUCTIONS_CHARS {
+        return Err(AppError::Validation {
+            field: "instructions".to_string(),
+            message: format!(
+                "Instructions must be at most {MAX_DEFAULT_INSTRUCTIONS_CHARS} characters"
+            ),
+        });
+    }
+
+    let setting = state
+        .settings_repo
+        .upsert_setting(
+            None,
+            ASSISTANT_SETTINGS_NAMESPACE,
+            DEFAULT_INSTRUCTIONS_KEY,
+            instructions,
+            "string",
+            false,
+        )
+        .await?;
+    Ok(Some(setting.value))
+}
+
+#[tauri::command]
+pub async fn get_user_instructions(
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+) -> Result<Option<String>, AppError> {
+    crate::log_info!("sarah.command", "get_user_instructions invoked");
+    state.context.user_instructions(&user_id).await
+}
+
+/// Saves instructions placed at the top of the system prompt in every one of
+/// the user's sessions; an empty value clears them.
+#[tauri::command]
+pub async fn set_user_instructions(
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+    text: String,
+) -> Result<Option<String>, AppError> {
+    crate::log_info!("sarah.command", "set_user_instructions invoked");
+    state.context.set_user_instructions(&user_id, &text).await
+}
+
+#[tauri::command]
+pub async fn get_session_user_instructions(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<bool, AppError> {
+    crate::log_info!("sarah.command", "get_session_user_instructions invoked");
+    state
+        .conversation_repo
+        .session_uses_user_instructions(&session_id)
+        .await
+}
+
+/// Turns the user's instructions off (or back on) for one session.
+#[tauri::command]
+pub async fn set_session_user_instructions(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    enabled: bool,
+) -> Result<bool, AppError> {
+    crate::log_info!("sarah.command", "set_session_user_instructions invoked");
+    state
+        .conversation_repo
+        .set_session_uses_user_instructions(&session_id, enabled)
+        .await?;
+    Ok(enabled)
+}
+
+#[tauri::command]
+pub async fn preview_system_prompt(
+    state: State<'_, Arc<AppState>>,
+    draft: Option<String>,
+    user_id: Option<String>,
+) -> Result<String, AppError> {
+    crate::log_info!("sarah.command", "preview_system_prompt invoked");
+    state
+        .context
+        .preview_system_prompt(draft.as_deref(), user_id.as_deref())
+        .await
+}
+
+#[tauri::command]
+pub async fn get_retention_policy(
+    state: State<'_, Arc<AppState>>,
+) -> Result<RetentionPolicy, AppError> {
+    crate::log_info!("sarah.command", "get_retention_policy invoked");
+    state.retention.policy().await
+}
+
+#[tauri::command]
+pub async fn set_retention_policy(
+    state: State<'_, Arc<AppState>>,
+    policy: RetentionPolicy,
+) -> Result<RetentionPolicy, AppError> {
+    crate::log_info!("sarah.command", "set_retention_policy invoked");
+    state.retention.set_policy(policy).await
+}
+
+#[tauri::command]
+pub async fn get_notification_preferences(
+    state: State<'_, Arc<AppState>>,
+) -> Result<NotificationPreferences, AppError> {
+    crate::log_info!("sarah.command", "get_notification_preferences invoked");
+    state.notifications.preferences().await
+}
+
+/// Which finished operations (downloads, setup, upgrades, recordings,
+/// scheduled prompts) raise a desktop notification.
+#[tauri::command]
+pub async fn set_notification_preferences(
+    state: State<'_, Arc<AppState>>,
+    preferences: NotificationPreferences,
+) -> Result<NotificationPreferences, AppError> {
+    crate::log_info!("sarah.command", "set_notification_preferences invoked");
+    state.notifications.set_preferences(preferences).await
+}
+
+#[tauri::command]
+pub async fn get_memory_decay_policy(
+    state: State<'_, Arc<AppState>>,
+) -> Result<MemoryDecayPolicy, AppError> {
+    crate::log_info!("sarah.command", "get_memory_decay_policy invoked");
+    state.memory.decay_policy().await
+}
+
+#[tauri::command]
+pub async fn set_memory_decay_policy(
+    state: State<'_, Arc<AppState>>,
+    policy: MemoryDecayPolicy,
+) -> Result<MemoryDecayPolicy, AppError> {
+    crate::log_info!("sarah.command", "set_memory_decay_policy invoked");
+    state.memory.set_decay_policy(policy).await
+}
+
+/// Dry run: reports what `policy` (or the saved one) would delete without deleting.
+#[tauri::command]
+pub async fn preview_retention(
+    state: State<'_, Arc<AppState>>,
+    policy: Option<RetentionPolicy>,
+) -> Result<RetentionReport, AppError> {
+    crate::log_info!("sarah.command", "preview_retention invoked");
+    state.retention.preview(policy).await
+}
+
+/// Writes a zip of everything Sarah stores (see docs/takeout-schema.md).
+#[tauri::command]
+pub async fn export_user_data(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+) -> Result<TakeoutExport, AppError> {
+    crate::log_info!("sarah.command", "export_user_data invoked");
+    let path = export_target(&path, "zip")?;
+    state.takeout.export(&path).await
+}
+
+#[tauri::command]
+pub async fn list_generation_presets(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<GenerationPreset>, AppError> {
+    crate::log_info!("sarah.command", "list_generation_presets invoked");
+    state.generation_presets.list().await
+}
+
+#[tauri::command]
+pub async fn save_generation_preset(
+    state: State<'_, Arc<AppState>>,
+    name: String,
+    temperature: f32,
+    top_p: f32,
+    top_k: i32,
+) -> Result<GenerationPreset, AppError> {
+    crate::log_info!("sarah.command", "save_generation_preset invoked");
+    state
+        .generation_presets
+        .save(&name, temperature, top_p, top_k)
+        .await
+}
+
+#[tauri::command]
+pub async fn reset_generation_preset(
+    state: State<'_, Arc<AppState>>,
+    name: String,
+) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "reset_generation_preset invoked");
+    state.generation_presets.reset(&name).await
+}
@@ -1,11 +1,33 @@
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use tauri::State;
+use sysinfo::Disks;
+use tauri::{Manager, State};
 
-use crate::db::models::{BenchmarkResult, LiveSystemStats, SystemProfile};
+use crate::db::models::{
+    BenchmarkResult, GenerationOptions, LiveSystemStats, Model, NewModelBenchmark, SystemProfile,
+};
 use crate::error::AppError;
+use crate::services::hardware_service::synthetic_embed_ms;
+use crate::services::inference_service::prompt_message;
+use crate::services::ocr::{self, OcrText};
 use crate::state::AppState;
 
+/// Tokens generated by the model benchmark.
+const BENCHMARK_OUTPUT_TOKENS: usize = 64;
+/// Gives prompt processing enough tokens to time.
+const BENCHMARK_PASSAGE: &str = "Local language models run entirely on the user's own \
+machine. Throughput depends on the processor, memory bandwidth and, when one is present, \
+the graphics card. Prompt processing evaluates every input token in large batches, so it \
+is usually many times faster than generation, which produces one token at a time and \
+re-reads the model's weights for each of them. Quantized weights shrink the model and \
+speed both phases up, at a small cost in quality. A benchmark that measures both phases \
+on the real hardware gives a far better estimate of the experience than any synthetic \
+loop, because it exercises the same kernels, caches and memory paths as a conversation.";
+const BENCHMARK_EMBED_SAMPLES: usize = 10;
+
 #[tauri::command]
 pub async fn get_hardware_profile(
     state: State<'_, Arc<AppState>>,
@@ -20,15 +42,169 @@ pub async fn get_hardware_profile(
     Ok(profile)
 }
 
+/// Measures the smallest installed model's prompt and generation throughput.
+/// Falls back to the synthetic loop when no model is installed, or always
+/// with `synthetic`.
 #[tauri::command]
 pub async fn run_hardware_benchmark(
     state: State<'_, Arc<AppState>>,
+    synthetic: Option<bool>,
 ) -> Result<BenchmarkResult, AppError> {
     crate::log_info!("sarah.command", "run_hardware_benchmark invoked");
-    let result = state.hardware_service.run_benchmark().await?;
+    let model = if synthetic.unwrap_or(false) {
+        None
+    } else {
+        smallest_installed_model(&state).await?
+    };
+    let result = match model {
+        Some(model) => run_model_benchmark(&state, model).await?,
+        None => state.hardware_service.run_benchmark().await?,
+    };
+    state.background.request_recommendation_refresh();
     Ok(result)
 }
 
+async fn smallest_installed_model(state: &AppState) -> Result<Option<Model>, AppError> {
+    let installed = state.model_repo.list_installed().await?;
+    Ok(installed
+        .into_iter()
+        .filter_map(|model| {
+            let size = std::fs::metadata(model.file_path.as_deref()?).ok()?.len();
+            Some((size, model))
+        })
+        .min_by_key(|(size, _)| *size)
+        .map(|(_, model)| model))
+}
+
+async fn run_model_benchmark(
+    state: &Arc<AppState>,
+    model: Model,
+) -> Result<BenchmarkResult, AppError> {
+    let Some(model_path) = model.file_path.clone() else {
+        return Err(AppError::Validation {
+            field: "model_id".to_string(),
+            message: "Model has no local file path".to_string(),
+        });
+    };
+    let profile = match state.hardware.read().await.clone() {
+        Some(profile) => profile,
+        None => state.hardware_service.detect_hardware().await?,
+    };
+    let mode = state.hardware_service.get_performance_mode(None).await;
+    let previous = state.inference.get_active_model_info().await;
+
+    let load_started = Instant::now();
+    state
+        .inference
+        .load_model(&model_path, &profile, mode.clone())
+        .await?;
+    let load_time_ms = load_started.elapsed().as_millis() as i64;
+
+    let user_prompt = format!(
+        "{BENCHMARK_PASSAGE}\n\nCount upward from one, writing every number as a word and \
+         separating them with commas."
+    );
+    let prompt_tokens = state.inference.count_tokens(&user_prompt).unwrap_or(0);
+    let options = GenerationOptions {
+        temperature: 0.0,
+        max_tokens: BENCHMARK_OUTPUT_TOKENS,
+        ..GenerationOptions::default()
+    };
+    let started = Instant::now();
+    let generated = state
+        .inference
+        .generate_with_options(vec![prompt_message("user", user_prompt)], &[], options)
+        .await;
+    let total_latency_ms = started.elapsed().as_millis() as i64;
+
+    // Put back the model the user had, which is usually still resident.
+    if let Some(previous) = previous.filter(|info| info.path != model_path) {
+        if let Err(error) = state
+            .inference
+            .load_model(&previous.path, &profile, mode)
+            .await
+        {
+            crate::log_warn!(
+                "sarah.benchmark",
+                "Failed to restore {} after the benchmark: {}",
+                previous.path,
+                error
+            );
+        }
+    }
+    let generated = generated?;
+
+    // The first token arrives once the whole prompt is evaluated; the rest
+    // are generation.
+    let first_token_ms = generated.first_token_ms.unwrap_or(total_latency_ms).max(1);
+    let prompt_tokens_per_sec = (prompt_tokens > 0)
+        .then(|| prompt_tokens as f64 / (first_token_ms as f64 / 1000.0));
+    let generation_ms = (total_latency_ms - first_token_ms).max(1);
+    let tokens_per_sec =
+        generated.tokens_generated.saturating_sub(1) as f64 / (generation_ms as f64 / 1000.0);
+
+    let embed_ms = match measure_embed_ms(state).await {
+        Some(embed_ms) => embed_ms,
+        None => synthetic_embed_ms(generated.tokens_generated as u64),
+    };
+
+    let stats = state.runtime_governor.current_stats();
+    state
+        .model_repo
+        .insert_benchmark(NewModelBenchmark {
+            model_id: model.id.clone(),
+            system_profile_id: Some(profile.id.clone()),
+            prompt_tokens: prompt_tokens as i64,
+            output_tokens: generated.tokens_generated as i64,
+            load_time_ms: Some(load_time_ms),
+            first_token_ms: Some(first_token_ms),
+            total_latency_ms,
+            tokens_per_sec: Some(tokens_per_sec),
+            memory_used_mb: Some(stats.memory_used_mb as i64),
+            cpu_usage_pct: Some(stats.cpu_usage_pct as f64),
+            metadata: serde_json::json!({
+                "source": "hardware_benchmark",
+                "promptTokensPerSec": prompt_tokens_per_sec,
+            })
+            .to_string(),
+        })
+        .await?;
+    state
+        .model_repo
+        .update_performance_metrics(&model.id, tokens_per_sec)
+        .await?;
+    crate::log_info!(
+        "sarah.benchmark",
+        "{}: {:.1} tok/s generation, {:.1} tok/s prompt processing",
+        model.name,
+        tokens_per_sec,
+        prompt_tokens_per_sec.unwrap_or(0.0)
+    );
+
+    state
+        .hardware_service
+        .record_benchmark(BenchmarkResult {
+            profile_id: String::new(),
+            tokens_per_sec,
+            embed_ms,
+            model_id: Some(model.id),
+            prompt_tokens_per_sec,
+        })
+        .await
+}
+
+/// Average time to embed one short text, when an embedding model is loaded.
+async fn measure_embed_ms(state: &AppState) -> Option<f64> {
+    let embedding = state.embedding.as_ref()?;
+    // Distinct texts, so no cached vector is reused.
+    let texts = (0..BENCHMARK_EMBED_SAMPLES)
+        .map(|index| format!("Benchmark sentence number {index} about local inference."))
+        .collect::<Vec<_>>();
+    let started = Instant::now();
+    embedding.embed_batch(texts).await.ok()?;
+    Some(started.elapsed().as_secs_f64() * 1000.0 / BENCHMARK_EMBED_SAMPLES as f64)
+}
+
 #[tauri::command]
 pub async fn get_system_stats(
     state: State<'_, Arc<AppState>>,
@@ -36,3 +212,289 @@ pub async fn get_system_stats(
     crate::log_info!("sarah.command", "get_system_stats invoked");
     Ok(state.hardware_service.live_stats())
 }
+
+/// Reads the text in a screenshot or other image file.
+#[tauri::command]
+pub async fn ocr_image(path: String) -> Result<OcrText, AppError> {
+    crate::log_info!("sarah.command", "ocr_image invoked");
+    ocr::recognize_text(Path::new(&path)).await
+}
+
+const SELF_TEST_DISK_FAIL_MB: u64 = 2 * 1024;
+const SELF_TEST_DISK_WARN_MB: u64 = 10 * 1024;
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestCheck {
+    pub name: String,
+    pub status: String,
+    pub detail: String,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestReport {
+    pub overall: String,
+    pub checks: Vec<SelfTestCheck>,
+    pub generated_at: String,
+}
+
+fn self_test_check(name: &str, started: Instant, outcome: (&str, String)) -> SelfTestCheck {
+    SelfTestCheck {
+        name: name.to_string(),
+        status: outcome.0.to_string(),
+        detail: outcome.1,
+        duration_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+async fn check_database(state: &AppState) -> (&'static str, String) {
+    if let Err(error) = sqlx::query("SELECT 1").execute(state.db.read_pool()).await {
+        return ("fail", format!("Read pool query failed: {error}"));
+    }
+
+    let write_probe = async {
+        sqlx::query("CREATE TEMP TABLE IF NOT EXISTS self_test_probe (value INTEGER)")
+            .execute(state.db.write_pool())
+            .await?;
+        sqlx::query("INSERT INTO self_test_probe (value) VALUES (1)")
+            .execute(state.db.write_pool())
+            .await?;
+        sqlx::query("DELETE FROM self_test_probe")
+            .execute(state.db.write_pool())
+            .await?;
+        Ok::<(), sqlx::Error>(())
+    };
+
+    match write_probe.await {
+        Ok(()) => ("pass", "Read and write pools are healthy".to_string()),
+        Err(error) => ("fail", format!("Write pool probe failed: {error}")),
+    }
+}
+
+async fn check_model_files(state: &AppState) -> (&'static str, String) {
+    let installed = match state.model_repo.list_installed().await {
+        Ok(rows) => rows,
+        Err(error) => return ("fail", format!("Failed to list installed models: {error}")),
+    };
+
+    if installed.is_empty() {
+        return ("warn", "No models are installed".to_string());
+    }
+
+    let missing = installed
+        .iter()
+        .filter(|model| {
+            model
+                .file_path
+                .as_deref()
+                .map(|path| !Path::new(path).is_file())
+                .unwrap_or(true)
+        })
+        .map(|model| model.name.clone())
+        .collect::<Vec<_>>();
+
+    if missing.is_empty() {
+        ("pass", format!("{} installed model file(s) present", installed.len()))
+    } else if missing.len() == installed.len() {
+        ("fail", format!("All model files are missing: {}", missing.join(", ")))
+    } else {
+        ("warn", format!("Missing model files: {}", missing.join(", ")))
+    }
+}
+
+fn check_nlp_engine(
+    initialized: Option<Result<(), AppError>>,
+    label: &str,
+) -> (&'static str, String) {
+    match initialized {
+        None => ("warn", format!("{label} is disabled for this device tier")),
+        Some(Ok(())) => ("pass", format!("{label} initialized")),
+        Some(Err(error)) => ("fail", format!("{label} failed to initialize: {error}")),
+    }
+}
+
+async fn check_ollama(client: &reqwest::Client) -> (&'static str, String) {
+    let request = client
+        .get("http://127.0.0.1:11434/api/tags")
+        .timeout(Duration::from_secs(3))
+        .send()
+        .await;
+
+    match request {
+        Ok(response) if response.status().is_success() => {
+            ("pass", "Ollama is reachable at 127.0.0.1:11434".to_string())
+        }
+        Ok(response) => (
+            "warn",
+            format!("Ollama responded with status {}", response.status()),
+        ),
+        Err(_) => (
+            "warn",
+            "Ollama is not reachable; local GGUF inference is unaffected".to_string(),
+        ),
+    }
+}
+
+async fn probe_binary(program: &str) -> Result<String, String> {
+    let mut command = tokio::process::Command::new(program);
+    command
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+
+    let output = tokio::time::timeout(Duration::from_secs(5), command.output())
+        .await
+        .map_err(|_| format!("{program} --version timed out"))?
+        .map_err(|error| format!("{program} not found: {error}"))?;
+
+    if !output.status.success() {
+        return Err(format!("{program} exited with {}", output.status));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+async fn check_mcp_binaries() -> (&'static str, String) {
+    let (node, npm) = if cfg!(target_os = "windows") {
+        ("node.exe", "npm.cmd")
+    } else {
+        ("node", "npm")
+    };
+
+    let mut found = Vec::new();
+    let mut missing = Vec::new();
+    for program in [node, npm] {
+        match probe_binary(program).await {
+            Ok(version) => found.push(format!("{program} {version}")),
+            Err(error) => missing.push(error),
+        }
+    }
+
+    if missing.is_empty() {
+        ("pass", found.join(", "))
+    } else {
+        (
+            "warn",
+            format!("Node-based MCP servers unavailable: {}", missing.join("; ")),
+        )
+    }
+}
+
+fn check_capture_directory() -> (&'static str, String) {
+    let directory = match crate::native_capture::get_default_capture_directory() {
+        Ok(path) => PathBuf::from(path),
+        Err(error) => return ("fail", error),
+    };
+
+    let probe = directory.join(".sarah-self-test");
+    match std::fs::write(&probe, b"ok") {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&probe);
+            (
+                "pass",
+                format!("Capture directory is writable: {}", directory.display()),
+            )
+        }
+        Err(error) => (
+            "fail",
+            format!("Capture directory is not writable ({}): {error}", directory.display()),
+        ),
+    }
+}
+
+fn check_disk_headroom(data_path: &Path) -> (&'static str, String) {
+    let disks = Disks::new_with_refreshed_list();
+    let disk = disks
+        .list()
+        .iter()
+        .filter(|disk| data_path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len());
+
+    let Some(disk) = disk else {
+        return ("warn", "Could not resolve the disk hosting app data".to_string());
+    };
+
+    let available_mb = disk.available_space() / (1024 * 1024);
+    let detail = format!(
+        "{} MB free on {}",
+        available_mb,
+        disk.mount_point().display()
+    );
+    if available_mb < SELF_TEST_DISK_FAIL_MB {
+        ("fail", detail)
+    } else if available_mb < SELF_TEST_DISK_WARN_MB {
+        ("warn", detail)
+    } else {
+        ("pass", detail)
+    }
+}
+
+#[tauri::command]
+pub async fn run_self_test(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<SelfTestReport, AppError> {
+    crate::log_info!("sarah.command", "run_self_test invoked");
+    let mut checks = Vec::new();
+
+    let started = Instant::now();
+    checks.push(self_test_check("database", started, check_database(&state).await));
+
+    let started = Instant::now();
+    checks.push(self_test_check("model_files", started, check_model_files(&state).await));
+
+    let started = Instant::now();
+    let embedding = match &state.embedding {
+        Some(service) => Some(service.ensure_initialized().await),
+        None => None,
+    };
+    checks.push(self_test_check(
+        "embedding",
+        started,
+        check_nlp_engine(embedding, "Embedding engine"),
+    ));
+
+    let started = Instant::now();
+    let reranker = match &state.reranker {
+        Some(service) => Some(service.ensure_initialized().await),
+        None => None,
+    };
+    checks.push(self_test_check(
+        "reranker",
+        started,
+        check_nlp_engine(reranker, "Reranker engine"),
+    ));
+
+    let started = Instant::now();
+    let client = app.state::<reqwest::Client>();
+    checks.push(self_test_check("ollama", started, check_ollama(&client).await));
+
+    let started = Instant::now();
+    checks.push(self_test_check("mcp_binaries", started, check_mcp_binaries().await));
+
+    let started = Instant::now();
+    checks.push(self_test_check("capture", started, check_capture_directory()));
+
+    let started = Instant::now();
+    checks.push(self_test_check(
+        "disk_headroom",
+        started,
+        check_disk_headroom(&state.db.db_path),
+    ));
+
+    let overall = if checks.iter().any(|check| check.status == "fail") {
+        "fail"
+    } else if checks.iter().any(|check| check.status == "warn") {
+        "warn"
+    } else {
+        "pass"
+    };
+
+    Ok(SelfTestReport {
+        overall: overall.to_string(),
+        checks,
+        generated_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use tauri::State;
+
+use crate::commands::model_commands::ensure_catalog_seeded;
+use crate::error::AppError;
+use crate::services::speech_service::{VoiceCaptureHandle, VoiceTranscript};
+use crate::services::tts_service::{TtsSettings, TtsVoice};
+use crate::services::wake_word_service::{WakeWordSettings, WakeWordStatus};
+use crate::state::AppState;
+
+/// Starts dictation from the microphone. Partial transcripts arrive as
+/// `voice://transcript` events until `stop_voice_capture`.
+#[tauri::command]
+pub async fn start_voice_capture(
+    state: State<'_, Arc<AppState>>,
+    model_id: Option<String>,
+    language: Option<String>,
+    device: Option<String>,
+) -> Result<VoiceCaptureHandle, AppError> {
+    crate::log_info!("sarah.command", "start_voice_capture invoked");
+    ensure_catalog_seeded(&state).await?;
+    let language = language
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty());
+    state
+        .speech
+        .start_capture(model_id.as_deref(), language, device)
+        .await
+}
+
+#[tauri::command]
+pub async fn stop_voice_capture(
+    state: State<'_, Arc<AppState>>,
+) -> Result<VoiceTranscript, AppError> {
+    crate::log_info!("sarah.command", "stop_voice_capture invoked");
+    state.speech.stop_capture().await
+}
+
+/// Reads a stored message (`message_id`) or `text` aloud, interrupting any
+/// speech in progress. `voice` and `rate` default to the user's settings.
+#[tauri::command]
+pub async fn speak_text(
+    state: State<'_, Arc<AppState>>,
+    user_id: Option<String>,
+    message_id: Option<String>,
+    text: Option<String>,
+    voice: Option<String>,
+    rate: Option<f32>,
+) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "speak_text invoked");
+    state
+        .tts
+        .speak(
+            user_id.as_deref(),
+            message_id.as_deref(),
+            text.as_deref(),
+            voice,
+            rate,
+        )
+        .await
+}
+
+#[tauri::command]
+pub async fn stop_speaking(state: State<'_, Arc<AppState>>) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "stop_speaking invoked");
+    state.tts.stop()
+}
+
+#[tauri::command]
+pub async fn list_tts_voices(state: State<'_, Arc<AppState>>) -> Result<Vec<TtsVoice>, AppError> {
+    crate::log_info!("sarah.command", "list_tts_voices invoked");
+    state.tts.voices().await
+}
+
+#[tauri::command]
+pub async fn get_tts_settings(
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+) -> Result<TtsSettings, AppError> {
+    crate::log_info!("sarah.command", "get_tts_settings invoked");
+    state.tts.settings(&user_id).await
+}
+
+#[tauri::command]
+pub async fn set_tts_settings(
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+    settings: TtsSettings,
+) -> Result<TtsSettings, AppError> {
+    crate::log_info!("sarah.command", "set_tts_settings invoked");
+    state.tts.set_settings(&user_id, settings).await
+}
+
+#[tauri::command]
+pub async fn get_wake_word_settings(
+    state: State<'_, Arc<AppState>>,
+) -> Result<WakeWordSettings, AppError> {
+    crate::log_info!("sarah.command", "get_wake_word_settings invoked");
+    state.wake_word.settings().await
+}
+
+/// Turns "Hey Sarah" on or off. The detector only listens outside
+/// Multitasking mode; the returned status says whether it is listening.
+#[tauri::command]
+pub async fn set_wake_word_settings(
+    state: State<'_, Arc<AppState>>,
+    settings: WakeWordSettings,
+) -> Result<WakeWordStatus, AppError> {
+    crate::log_info!("sarah.command", "set_wake_word_settings invoked");
+    state.wake_word.set_settings(settings).await
+}
+
+#[tauri::command]
+pub async fn get_wake_word_status(
+    state: State<'_, Arc<AppState>>,
+) -> Result<WakeWordStatus, AppError> {
+    crate::log_info!("sarah.command", "get_wake_word_status invoked");
+    Ok(state.wake_word.status().await)
+}
@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use tauri::State;
+
+use crate::db::models::{NewWebhook, Webhook};
+use crate::error::AppError;
+use crate::services::webhook_service::WebhookEvent;
+use crate::state::AppState;
+
+#[tauri::command]
+pub async fn list_webhooks(
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+) -> Result<Vec<Webhook>, AppError> {
+    crate::log_info!("sarah.command", "list_webhooks invoked");
+    state.webhooks.list(&user_id).await
+}
+
+/// The event names a webhook can subscribe to.
+#[tauri::command]
+pub fn list_webhook_events() -> Vec<&'static str> {
+    crate::log_info!("sarah.command", "list_webhook_events invoked");
+    WebhookEvent::ALL
+        .into_iter()
+        .map(WebhookEvent::as_str)
+        .collect()
+}
+
+/// Registers `webhook.url` for `webhook.events`, with a new signing secret.
+#[tauri::command]
+pub async fn create_webhook(
+    state: State<'_, Arc<AppState>>,
+    webhook: NewWebhook,
+) -> Result<Webhook, AppError> {
+    crate::log_info!("sarah.command", "create_webhook invoked");
+    state.webhooks.create(webhook).await
+}
+
+/// Replaces the name, URL and events; the secret stays.
+#[tauri::command]
+pub async fn update_webhook(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    webhook: NewWebhook,
+) -> Result<Webhook, AppError> {
+    crate::log_info!("sarah.command", "update_webhook invoked");
+    state.webhooks.update(&id, webhook).await
+}
+
+#[tauri::command]
+pub async fn set_webhook_enabled(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    enabled: bool,
+) -> Result<Webhook, AppError> {
+    crate::log_info!("sarah.command", "set_webhook_enabled invoked");
+    state.webhooks.set_enabled(&id, enabled).await
+}
+
+#[tauri::command]
+pub async fn delete_webhook(state: State<'_, Arc<AppState>>, id: String) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "delete_webhook invoked");
+    state.webhooks.delete(&id).await
+}
+
+/// The secret to check `X-Sarah-Signature-256` with on the receiving end.
+#[tauri::command]
+pub async fn get_webhook_secret(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+) -> Result<String, AppError> {
+    crate::log_info!("sarah.command", "get_webhook_secret invoked");
+    state.webhooks.secret(&id).await
+}
+
+#[tauri::command]
+pub async fn rotate_webhook_secret(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+) -> Result<String, AppError> {
+    crate::log_info!("sarah.command", "rotate_webhook_secret invoked");
+    state.webhooks.rotate_secret(&id).await
+}
+
+/// Sends a `webhook.test` event now and fails with the receiver's error, if any.
+#[tauri::command]
+pub async fn test_webhook(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+) -> Result<Webhook, AppError> {
+    crate::log_info!("sarah.command", "test_webhook invoked");
+    state.webhooks.send_test(&id).await
+}
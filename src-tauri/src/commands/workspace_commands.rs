@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tauri::State;
+
+use crate::db::models::{NewWorkspace, Session, Workspace};
+use crate::error::AppError;
+use crate::services::generation_presets::validate_preset_name;
+use crate::services::task_router_service::validate_task_type;
+use crate::state::AppState;
+
+const MAX_WORKSPACE_NAME_CHARS: usize = 64;
+const MAX_NAMESPACE_CHARS: usize = 64;
+
+#[tauri::command]
+pub async fn list_workspaces(
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+) -> Result<Vec<Workspace>, AppError> {
+    crate::log_info!("sarah.command", "list_workspaces invoked");
+    state.workspace_repo.list_workspaces(&user_id).await
+}
+
+#[tauri::command]
+pub async fn create_workspace(
+    state: State<'_, Arc<AppState>>,
+    workspace: NewWorkspace,
+) -> Result<Workspace, AppError> {
+    crate::log_info!("sarah.command", "create_workspace invoked");
+    let name = validate_workspace_name(&workspace.name)?;
+    ensure_unique_name(&state, &workspace.user_id, &name, None).await?;
+    let rag_namespace = match workspace.rag_namespace.as_deref() {
+        Some(namespace) => validate_namespace(namespace)?,
+        None => namespace_from_name(&name, "workspace"),
+    };
+    let preset = match workspace.preset.as_deref() {
+        Some(preset) => Some(validate_preset(&state, preset).await?),
+        None => None,
+    };
+    let routing_rules = validate_routing_rules(&state, &workspace.routing_rules).await?;
+
+    state
+        .workspace_repo
+        .create_workspace(NewWorkspace {
+            name,
+            rag_namespace: Some(rag_namespace),
+            preset,
+            routing_rules,
+            ..workspace
+        })
+        .await
+}
+
+/// Fields left out are unchanged; pass an empty `preset` to clear it.
+#[tauri::command]
+pub async fn update_workspace(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    name: Option<String>,
+    description: Option<String>,
+    rag_namespace: Option<String>,
+    preset: Option<String>,
+    routing_rules: Option<HashMap<String, String>>,
+) -> Result<Workspace, AppError> {
+    crate::log_info!("sarah.command", "update_workspace invoked");
+    let existing = get_workspace_or_not_found(&state, &id).await?;
+    let name = match name {
+        Some(name) => {
+            let name = validate_workspace_name(&name)?;
+            ensure_unique_name(&state, &existing.user_id, &name, Some(&id)).await?;
+            Some(name)
+        }
+        None => None,
+    };
+    let rag_namespace = rag_namespace
+        .as_deref()
+        .map(validate_namespace)
+        .transpose()?;
+    let preset = match preset.as_deref().map(str::trim) {
+        Some("") => Some(String::new()),
+        Some(preset) => Some(validate_preset(&state, preset).await?),
+        None => None,
+    };
+    let routing_rules = match routing_rules {
+        Some(rules) => Some(validate_routing_rules(&state, &rules).await?),
+        None => None,
+    };
+
+    state
+        .workspace_repo
+        .update_workspace(
+            &id,
+            name.as_deref(),
+            description.as_deref(),
+            rag_namespace.as_deref(),
+            preset.as_deref(),
+            routing_rules.as_ref(),
+        )
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_workspace(state: State<'_, Arc<AppState>>, id: String) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "delete_workspace invoked");
+    state.workspace_repo.delete_workspace(&id).await
+}
+
+#[tauri::command]
+pub async fn get_active_workspace(
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+) -> Result<Option<Workspace>, AppError> {
+    crate::log_info!("sarah.command", "get_active_workspace invoked");
+    state.workspace_repo.active_workspace(&user_id).await
+}
+
+/// Switches modes: new sessions, ingestion and retrieval follow the active
+/// workspace. `None` returns to the unscoped personal space.
+#[tauri::command]
+pub async fn set_active_workspace(
+    state: State<'_, Arc<AppState>>,
+    user_id: String,
+    workspace_id: Option<String>,
+) -> Result<Option<Workspace>, AppError> {
+    crate::log_info!("sarah.command", "set_active_workspace invoked");
+    state
+        .workspace_repo
+        .set_active_workspace(&user_id, workspace_id.as_deref())
+        .await?;
+    state.workspace_repo.active_workspace(&user_id).await
+}
+
+#[tauri::command]
+pub async fn assign_session_workspace(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    workspace_id: Option<String>,
+) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "assign_session_workspace invoked");
+    if let Some(workspace_id) = workspace_id.as_deref() {
+        let workspace = get_workspace_or_not_found(&state, workspace_id).await?;
+        let session = state
+            .conversation_repo
+            .get_session(&session_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "session".to_string(),
+                id: session_id.clone(),
+            })?;
+        if session.user_id != workspace.user_id {
+            return Err(AppError::Validation {
+                field: "workspace_id".to_string(),
+                message: "Workspace belongs to a different user".to_string(),
+            });
+        }
+    }
+
+    state
+        .conversation_repo
+        .set_session_workspace(&session_id, workspace_id.as_deref())
+        .await
+}
+
+#[tauri::command]
+pub async fn list_workspace_sessions(
+    state: State<'_, Arc<AppState>>,
+    workspace_id: String,
+    limit: Option<i64>,
+) -> Result<Vec<Session>, AppError> {
+    crate::log_info!("sarah.command", "list_workspace_sessions invoked");
+    state
+        .workspace_repo
+        .list_workspace_sessions(&workspace_id, limit.unwrap_or(50).min(100))
+        .await
+}
+
+async fn get_workspace_or_not_found(
+    state: &Arc<AppState>,
+    id: &str,
+) -> Result<Workspace, AppError> {
+    state
+        .workspace_repo
+        .get_workspace(id)
+        .await?
+        .ok_or_else(|| AppError::NotFound {
+            entity: "workspace".to_string(),
+            id: id.to_string(),
+        })
+}
+
+async fn ensure_unique_name(
+    state: &Arc<AppState>,
+    user_id: &str,
+    name: &str,
+    except_id: Option<&str>,
+) -> Result<(), AppError> {
+    let taken = state
+        .workspace_repo
+        .list_workspaces(user_id)
+        .await?
+        .iter()
+        .any(|workspace| workspace.name == name && Some(workspace.id.as_str()) != except_id);
+    if taken {
+        return Err(AppError::Validation {
+            field: "name".to_string(),
+            message: format!("A workspace named '{name}' already exists"),
+        });
+    }
+    Ok(())
+}
+
+async fn validate_preset(state: &Arc<AppState>, preset: &str) -> Result<String, AppError> {
+    let name = validate_preset_name(preset)?;
+    state.generation_presets.get(&name).await?;
+    Ok(name)
+}
+
+/// Normalizes task types and rejects rules pointing at models Sarah doesn't know.
+async fn validate_routing_rules(
+    state: &Arc<AppState>,
+    rules: &HashMap<String, String>,
+) -> Result<HashMap<String, String>, AppError> {
+    let mut validated = HashMap::with_capacity(rules.len());
+    for (task_type, model_id) in rules {
+        let task = validate_task_type(task_type)?;
+        if state.model_repo.get_by_id(model_id).await?.is_none() {
+            return Err(AppError::Validation {
+                field: "routing_rules".to_string(),
+                message: format!("Unknown model '{model_id}' for task type '{task}'"),
+            });
+        }
+        validated.insert(task, model_id.clone());
+    }
+    Ok(validated)
+}
+
+fn validate_workspace_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_WORKSPACE_NAME_CHARS {
+        return Err(AppError::Validation {
+            field: "name".to_string(),
+            message: format!("Workspace names must be 1-{MAX_WORKSPACE_NAME_CHARS} characters"),
+        });
+    }
+    Ok(name.to_string())
+}
+
+pub(crate) fn validate_namespace(namespace: &str) -> Result<String, AppError> {
+    let namespace = namespace.trim().to_ascii_lowercase();
+    let valid = !namespace.is_empty()
+        && namespace.len() <= MAX_NAMESPACE_CHARS
+        && namespace
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-');
+    if !valid {
+        return Err(AppError::Validation {
+            field: "rag_namespace".to_string(),
+            message: format!("Namespaces use 1-{MAX_NAMESPACE_CHARS} letters, digits, '-' or '_'"),
+        });
+    }
+    Ok(namespace)
+}
+
+/// "Client Work (2024)" -> "client-work-2024"; `fallback` when nothing is left.
+pub(crate) fn namespace_from_name(name: &str, fallback: &str) -> String {
+    let slug = name
+        .to_ascii_lowercase()
+        .split(|ch: char| !ch.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        fallback.to_string()
+    } else {
+        slug.chars().take(MAX_NAMESPACE_CHARS).collect()
+    }
+}
@@ -1,21 +1,31 @@
-use std::path::PathBuf;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 
-use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
-use sqlx::SqlitePool;
+use sqlx::sqlite::{
+    SqliteAutoVacuum, SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous,
+};
+use sqlx::{ConnectOptions, Connection, Row, SqlitePool};
 use tauri::{AppHandle, Manager};
 
+use crate::db::models::{DatabaseMaintenanceReport, TableRowCount};
 use crate::error::AppError;
+use crate::services::crypto_service::CryptoService;
 
 pub mod migrations;
 pub mod models;
 
+/// First bytes of every plaintext SQLite file; SQLCipher encrypts the header too.
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
 #[derive(Clone)]
 pub struct Database {
     write_pool: SqlitePool,
     read_pool: SqlitePool,
     pub db_path: PathBuf,
+    /// Whether the file is SQLCipher-encrypted and opened with the keychain key.
+    pub encrypted: bool,
 }
 
 #[derive(Clone)]
@@ -23,24 +33,27 @@ pub struct DatabaseState(pub Arc<Database>);
 
 impl Database {
     pub async fn new(app_handle: &AppHandle, max_read_connections: u32) -> Result<Self, AppError> {
-        let app_data_dir = app_handle
-            .path()
-            .app_data_dir()
-            .map_err(|e| AppError::Config(format!("Failed to resolve app data dir: {e}")))?;
-
-        tokio::fs::create_dir_all(&app_data_dir).await?;
+        let db_path = database_path(app_handle)?;
+        if let Some(app_data_dir) = db_path.parent() {
+            tokio::fs::create_dir_all(app_data_dir).await?;
+        }
 
-        let db_path = app_data_dir.join("app.db");
-        let base_options = SqliteConnectOptions::new()
+        let key = encryption_key(app_handle, &db_path)?;
+        let mut base_options = SqliteConnectOptions::new()
             .filename(&db_path)
             .create_if_missing(true)
             .journal_mode(SqliteJournalMode::Wal)
+            // Only takes effect on a new file; maintenance converts older ones.
+            .auto_vacuum(SqliteAutoVacuum::Incremental)
             .synchronous(SqliteSynchronous::Normal)
             .foreign_keys(true)
             .busy_timeout(Duration::from_secs(5))
             .pragma("cache_size", "-64000")
             .pragma("temp_store", "MEMORY")
             .pragma("mmap_size", "536870912");
+        if let Some(key) = key.as_deref() {
+            base_options = base_options.pragma("key", key_pragma(key));
+        }
 
         // Create write and read pools concurrently for faster startup
         let write_opts = base_options.clone();
@@ -68,6 +81,34 @@ impl Database {
             write_pool,
             read_pool,
             db_path,
+            encrypted: key.is_some(),
+        })
+    }
+
+    /// Opens the existing database without running migrations and without write
+    /// access. Used by safe mode after a failed startup.
+    pub async fn open_read_only(app_handle: &AppHandle) -> Result<Self, AppError> {
+        let db_path = database_path(app_handle)?;
+        let key = encryption_key(app_handle, &db_path)?;
+        let mut options = SqliteConnectOptions::new()
+            .filename(&db_path)
+            .read_only(true)
+            .busy_timeout(Duration::from_secs(5));
+        if let Some(key) = key.as_deref() {
+            options = options.pragma("key", key_pragma(key));
+        }
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(Duration::from_secs(10))
+            .connect_with(options)
+            .await?;
+
+        Ok(Self {
+            write_pool: pool.clone(),
+            read_pool: pool,
+            db_path,
+            encrypted: key.is_some(),
         })
     }
 
@@ -86,4 +127,182 @@ impl Database {
             .await;
         tracing::info!("Database PRAGMA optimize executed");
     }
+
+    /// Checks integrity, folds the WAL back into the main file and returns free
+    /// pages to the filesystem, reporting sizes before and after. A file created
+    /// before incremental auto-vacuum was enabled gets one full VACUUM to convert it.
+    pub async fn run_maintenance(&self) -> Result<DatabaseMaintenanceReport, AppError> {
+        let wal_path = {
+            let mut name = self.db_path.clone().into_os_string();
+            name.push("-wal");
+            PathBuf::from(name)
+        };
+        let wal_size = |path: &Path| std::fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+
+        let wal_bytes_before = wal_size(&wal_path);
+        let page_size = sqlx::query_scalar::<_, i64>("PRAGMA page_size")
+            .fetch_one(&self.write_pool)
+            .await?;
+        let page_count_before = sqlx::query_scalar::<_, i64>("PRAGMA page_count")
+            .fetch_one(&self.write_pool)
+            .await?;
+
+        let integrity = sqlx::query_scalar::<_, String>("PRAGMA integrity_check")
+            .fetch_all(&self.write_pool)
+            .await?;
+        let integrity_ok = integrity.len() == 1 && integrity[0] == "ok";
+
+        let auto_vacuum = sqlx::query_scalar::<_, i64>("PRAGMA auto_vacuum")
+            .fetch_one(&self.write_pool)
+            .await?;
+        // 2 is INCREMENTAL. Converting needs the whole file rewritten, which is
+        // not worth risking on a file that failed its integrity check.
+        if auto_vacuum != 2 && integrity_ok {
+            sqlx::query("PRAGMA auto_vacuum = INCREMENTAL")
+                .execute(&self.write_pool)
+                .await?;
+            sqlx::query("VACUUM").execute(&self.write_pool).await?;
+        } else if auto_vacuum == 2 {
+            sqlx::query("PRAGMA incremental_vacuum")
+                .execute(&self.write_pool)
+                .await?;
+        }
+
+        let checkpoint = sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .fetch_one(&self.write_pool)
+            .await?;
+        let checkpoint_busy = checkpoint.try_get::<i64, _>(0).unwrap_or(0) != 0;
+
+        let page_count_after = sqlx::query_scalar::<_, i64>("PRAGMA page_count")
+            .fetch_one(&self.write_pool)
+            .await?;
+        let freelist_count = sqlx::query_scalar::<_, i64>("PRAGMA freelist_count")
+            .fetch_one(&self.write_pool)
+            .await?;
+
+        let table_names = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT name FROM sqlite_master
+            WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+            ORDER BY name
+            "#,
+        )
+        .fetch_all(&self.read_pool)
+        .await?;
+        let mut tables = Vec::with_capacity(table_names.len());
+        for name in table_names {
+            let sql = format!("SELECT COUNT(*) FROM \"{}\"", name.replace('"', "\"\""));
+            let rows = sqlx::query_scalar::<_, i64>(&sql)
+                .fetch_one(&self.read_pool)
+                .await?;
+            tables.push(TableRowCount { name, rows });
+        }
+        tables.sort_by(|a, b| b.rows.cmp(&a.rows));
+
+        let wal_bytes_after = wal_size(&wal_path);
+        tracing::info!(
+            "Database maintenance: {} -> {} pages, WAL {} -> {} bytes",
+            page_count_before,
+            page_count_after,
+            wal_bytes_before,
+            wal_bytes_after
+        );
+
+        Ok(DatabaseMaintenanceReport {
+            integrity,
+            integrity_ok,
+            checkpoint_busy,
+            wal_bytes_before,
+            wal_bytes_after,
+            page_size,
+            page_count_before,
+            page_count_after,
+            freelist_count,
+            database_bytes: page_count_after * page_size,
+            tables,
+        })
+    }
+
+    /// Copies the whole database into a new SQLCipher file at `target`, encrypted
+    /// with the raw hex `key`. Call after `close`: it opens its own connection,
+    /// so no write can land between the copy and the file swap that follows.
+    pub async fn export_encrypted(&self, target: &Path, key: &str) -> Result<(), AppError> {
+        if !cfg!(feature = "sqlcipher") {
+            return Err(AppError::Config(
+                "This build was compiled without SQLCipher support".to_string(),
+            ));
+        }
+        if !self.write_pool.is_closed() || !self.read_pool.is_closed() {
+            return Err(AppError::Internal(
+                "Close the database before exporting it".to_string(),
+            ));
+        }
+
+        let mut conn = SqliteConnectOptions::new()
+            .filename(&self.db_path)
+            .connect()
+            .await?;
+        sqlx::query("ATTACH DATABASE ?1 AS encrypted KEY ?2")
+            .bind(target.to_string_lossy().to_string())
+            .bind(format!("x'{key}'"))
+            .execute(&mut conn)
+            .await?;
+        let exported = sqlx::query("SELECT sqlcipher_export('encrypted')")
+            .execute(&mut conn)
+            .await;
+        sqlx::query("DETACH DATABASE encrypted")
+            .execute(&mut conn)
+            .await?;
+        conn.close().await?;
+        exported?;
+        Ok(())
+    }
+
+    /// Closes both pools so the database file can be replaced on disk.
+    pub async fn close(&self) {
+        self.write_pool.close().await;
+        self.read_pool.close().await;
+    }
+}
+
+/// A file that exists but lacks the SQLite header is taken to be SQLCipher-encrypted.
+pub fn is_encrypted_file(path: &Path) -> bool {
+    let mut header = [0u8; 16];
+    match std::fs::File::open(path).and_then(|mut file| file.read_exact(&mut header)) {
+        Ok(()) => &header != SQLITE_HEADER,
+        Err(_) => false,
+    }
+}
+
+/// The key to open `db_path` with, or `None` for a plaintext database.
+fn encryption_key(app_handle: &AppHandle, db_path: &Path) -> Result<Option<String>, AppError> {
+    if !is_encrypted_file(db_path) {
+        return Ok(None);
+    }
+    if !cfg!(feature = "sqlcipher") {
+        return Err(AppError::Config(
+            "The database is encrypted but this build was compiled without SQLCipher".to_string(),
+        ));
+    }
+    let bundle_id = app_handle.config().identifier.clone();
+    CryptoService::database_key(&bundle_id, false)?
+        .map(Some)
+        .ok_or_else(|| {
+            AppError::Crypto(
+                "The database is encrypted but its key is not in the keychain".to_string(),
+            )
+        })
+}
+
+/// Raw keys are passed as a blob literal so SQLCipher skips key derivation.
+fn key_pragma(key: &str) -> String {
+    format!("\"x'{key}'\"")
+}
+
+pub fn database_path(app_handle: &AppHandle) -> Result<PathBuf, AppError> {
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::Config(format!("Failed to resolve app data dir: {e}")))?;
+    Ok(app_data_dir.join("app.db"))
 }
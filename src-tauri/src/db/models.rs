@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
@@ -39,8 +41,12 @@ pub struct SystemProfile {
 #[serde(rename_all = "camelCase")]
 pub struct BenchmarkResult {
     pub profile_id: String,
+    /// Generation throughput; synthetic when `model_id` is `None`.
     pub tokens_per_sec: f64,
     pub embed_ms: f64,
+    /// Installed model the figures were measured with.
+    pub model_id: Option<String>,
+    pub prompt_tokens_per_sec: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -108,6 +114,29 @@ pub struct NewModel {
     pub metadata: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportCandidate {
+    pub source: String,
+    pub label: String,
+    pub path: String,
+    pub model_count: i64,
+    pub session_count: i64,
+    pub document_count: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub source: String,
+    pub models_imported: i64,
+    pub sessions_imported: i64,
+    pub messages_imported: i64,
+    pub documents_imported: i64,
+    pub skipped: i64,
+    pub errors: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct ModelWithScore {
@@ -132,16 +161,49 @@ pub struct Session {
     pub message_count: i64,
     pub status: String,
     pub summary: Option<String>,
+    pub summarized_at: Option<String>,
     pub tags: String,
     pub pinned: i64,
     pub forked_from_session_id: Option<String>,
     pub forked_at_message_id: Option<String>,
+    pub workspace_id: Option<String>,
+    /// Persona whose prompt replaces Sarah's default identity in this session.
+    pub persona_id: Option<String>,
+    /// Rolling summary of the turns folded out of the context window, covering
+    /// messages up to `context_summary_position`.
+    pub context_summary: Option<String>,
+    pub context_summary_position: Option<i64>,
     pub metadata: String,
     pub last_message_at: Option<String>,
     pub created_at: String,
     pub updated_at: String,
 }
 
+/// Per-session footprint used by retention to pick what to remove.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStorage {
+    pub id: String,
+    pub title: Option<String>,
+    pub status: String,
+    pub pinned: i64,
+    pub last_activity_at: String,
+    pub message_count: i64,
+    /// Bytes of message text and metadata; indexes and embeddings come on top.
+    pub approx_bytes: i64,
+}
+
+/// Narrows `list_sessions`; the default lists every session that isn't deleted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SessionFilter {
+    /// Sessions must carry every one of these tags.
+    pub tags: Vec<String>,
+    pub pinned_only: bool,
+    /// `active` or `archived`.
+    pub status: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct Message {
@@ -158,15 +220,34 @@ pub struct Message {
     pub finish_reason: Option<String>,
     pub is_error: i64,
     pub error_message: Option<String>,
+    /// For assistant replies, the user message they answer. Regenerated replies
+    /// share it; only the active variant is part of the conversation.
     pub parent_message_id: Option<String>,
+    pub is_active_variant: i64,
     pub edited_at: Option<String>,
     pub original_content: Option<String>,
     pub metadata: String,
+    /// JSON array of [`MessageAttachment`].
+    pub attachments: String,
     pub position: i64,
     pub created_at: String,
     pub updated_at: String,
 }
 
+impl Message {
+    pub fn attachment_list(&self) -> Vec<MessageAttachment> {
+        serde_json::from_str(&self.attachments).unwrap_or_default()
+    }
+
+    pub fn image_paths(&self) -> Vec<String> {
+        self.attachment_list()
+            .into_iter()
+            .filter(|attachment| attachment.kind == "image")
+            .map(|attachment| attachment.path)
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct NewMessage {
@@ -177,9 +258,141 @@ pub struct NewMessage {
     pub token_count: Option<i64>,
     pub model_id: Option<String>,
     pub metadata: String,
+    pub attachments: String,
     pub position: i64,
 }
 
+/// A file sent along with a user message. Images go to the model directly when
+/// it has a vision projector; documents are ingested for retrieval.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageAttachment {
+    pub path: String,
+    /// `image` or `document`.
+    pub kind: String,
+    /// Text recognized in an image, kept for models that can't see it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ocr_text: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedPrompt {
+    pub id: String,
+    pub user_id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub template: String,
+    pub variables: String,
+    pub task_type: Option<String>,
+    pub preset: Option<String>,
+    pub use_count: i64,
+    pub last_used_at: Option<String>,
+    pub metadata: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewSavedPrompt {
+    pub user_id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub template: String,
+    pub task_type: Option<String>,
+    #[serde(default)]
+    pub preset: Option<String>,
+}
+
+/// A GGUF LoRA adapter installed for a base model. Active adapters are applied
+/// whenever that model is loaded.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct LoraAdapter {
+    pub id: String,
+    pub model_id: String,
+    pub name: String,
+    pub file_path: String,
+    pub scale: f64,
+    pub is_active: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Workspace {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// Documents ingested while the workspace is active land here, and retrieval reads from it.
+    pub rag_namespace: String,
+    /// Generation preset used when neither the request nor the session names one.
+    pub preset: Option<String>,
+    /// JSON object mapping a task type to the model id it should be routed to.
+    pub routing_rules: String,
+    pub is_active: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewWorkspace {
+    pub user_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// Defaults to a slug of the name.
+    #[serde(default)]
+    pub rag_namespace: Option<String>,
+    #[serde(default)]
+    pub preset: Option<String>,
+    #[serde(default)]
+    pub routing_rules: HashMap<String, String>,
+}
+
+/// A named system prompt preset a session can adopt.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Persona {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// Replaces Sarah's default identity line at the top of the system prompt.
+    pub system_prompt: String,
+    /// Overrides the tuned temperature; a generation preset still wins.
+    pub temperature: Option<f64>,
+    /// Used under automatic routing when installed.
+    pub preferred_model_id: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewPersona {
+    pub user_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub system_prompt: String,
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    #[serde(default)]
+    pub preferred_model_id: Option<String>,
+}
+
+/// A chat message still waiting for its history-search embedding.
+#[derive(Debug, Clone, FromRow)]
+pub struct MessageToIndex {
+    pub id: String,
+    pub user_id: String,
+    pub content: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct MessageSearchResult {
@@ -274,6 +487,81 @@ pub struct NewMemory {
     pub metadata: String,
 }
 
+/// What the Memories window asks for. Archived memories are left out unless
+/// `include_archived` is set.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryFilter {
+    pub user_id: String,
+    pub memory_type: Option<String>,
+    pub category: Option<String>,
+    pub subject: Option<String>,
+    pub pinned_only: Option<bool>,
+    pub include_archived: Option<bool>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Outcome of one deduplication pass over a user's memories.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryConsolidationReport {
+    pub user_id: String,
+    pub memories_scanned: usize,
+    pub embedded: usize,
+    pub clusters: usize,
+    pub merged: usize,
+    pub duration_ms: i64,
+}
+
+/// Shape of the memory decay curve. Each pass multiplies a memory's
+/// importance by `exp(-decay_rate * decay_multiplier * days / (1 +
+/// access_weight * access_count))`, so often-used memories fade slower.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MemoryDecayPolicy {
+    pub enabled: bool,
+    /// Scales every memory's own `decay_rate`.
+    pub decay_multiplier: f64,
+    /// How much each recorded access slows decay.
+    pub access_weight: f64,
+    /// Memories whose importance falls below this are archived.
+    pub archive_below: f64,
+}
+
+impl Default for MemoryDecayPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            decay_multiplier: 10.0,
+            access_weight: 0.5,
+            archive_below: 0.05,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryDecayReport {
+    pub decayed: usize,
+    pub archived: u64,
+    pub expired: usize,
+    pub elapsed_days: f64,
+}
+
+/// User corrections to a memory; fields left out are kept.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryPatch {
+    pub content: Option<String>,
+    pub category: Option<String>,
+    pub subject: Option<String>,
+    pub importance: Option<f64>,
+    pub is_pinned: Option<bool>,
+    pub is_archived: Option<bool>,
+    pub tags: Option<Vec<String>>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct MemoryRelation {
@@ -463,6 +751,16 @@ pub struct ModelRecommendation {
     pub created_at: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelUsageSignal {
+    pub model_id: String,
+    pub avg_tokens_per_sec: Option<f64>,
+    pub sample_count: i64,
+    pub positive_feedback: i64,
+    pub negative_feedback: i64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct PerfLog {
@@ -482,6 +780,7 @@ pub struct PerfLog {
     pub error_code: Option<String>,
     pub metadata: Option<String>,
     pub created_at: String,
+    pub first_token_ms: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -504,6 +803,47 @@ pub struct ModelBenchmark {
     pub created_at: String,
 }
 
+#[derive(Debug, Clone)]
+pub struct NewModelBenchmark {
+    pub model_id: String,
+    pub system_profile_id: Option<String>,
+    pub prompt_tokens: i64,
+    pub output_tokens: i64,
+    pub load_time_ms: Option<i64>,
+    pub first_token_ms: Option<i64>,
+    pub total_latency_ms: i64,
+    pub tokens_per_sec: Option<f64>,
+    pub memory_used_mb: Option<i64>,
+    pub cpu_usage_pct: Option<f64>,
+    pub metadata: String,
+}
+
+/// Per-model aggregate of successful benchmark runs on one hardware profile.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelBenchmarkSummary {
+    pub model_id: String,
+    pub model_name: String,
+    pub display_name: String,
+    pub parameter_count: Option<String>,
+    pub quantization: Option<String>,
+    pub runs: i64,
+    pub avg_tokens_per_sec: Option<f64>,
+    pub best_tokens_per_sec: Option<f64>,
+    pub avg_first_token_ms: Option<f64>,
+    pub avg_load_time_ms: Option<f64>,
+    pub avg_memory_used_mb: Option<f64>,
+    pub last_run_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkReport {
+    pub generated_at: String,
+    pub hardware: SystemProfile,
+    pub models: Vec<ModelBenchmarkSummary>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct SetupState {
@@ -521,7 +861,7 @@ pub struct SetupState {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct RuntimePolicy {
     pub pressure_cpu_pct: f64,
     pub pressure_memory_pct: f64,
@@ -531,6 +871,27 @@ pub struct RuntimePolicy {
     pub background_max_concurrency: usize,
     pub retrieval_candidate_limit: usize,
     pub defer_background_under_pressure: bool,
+    /// Weights of the BM25 and vector rankings in RAG's reciprocal-rank
+    /// fusion. Zero leaves that ranking out.
+    pub retrieval_bm25_weight: f64,
+    pub retrieval_vector_weight: f64,
+    /// Cap on both lanes' max tokens while on battery.
+    pub battery_max_tokens: usize,
+    /// Pauses background jobs while on battery, whatever the pressure.
+    pub battery_defer_background: bool,
+    /// Stops predictive model preloading while on battery.
+    pub battery_disable_preload: bool,
+    /// Turns the thermal limits below on or off.
+    pub thermal_protection: bool,
+    /// Above this CPU temperature (°C) inference threads are halved and
+    /// background jobs wait.
+    pub thermal_cpu_c: f64,
+    /// Above this GPU temperature (°C) the next model load offloads fewer
+    /// layers and background jobs wait.
+    pub thermal_gpu_c: f64,
+    /// Model downloads, re-indexing and memory consolidation wait until the
+    /// user has left keyboard and mouse alone this long. 0 runs them anyway.
+    pub idle_threshold_secs: u64,
 }
 
 impl Default for RuntimePolicy {
@@ -544,6 +905,15 @@ impl Default for RuntimePolicy {
             background_max_concurrency: 1,
             retrieval_candidate_limit: 36,
             defer_background_under_pressure: true,
+            retrieval_bm25_weight: 1.0,
+            retrieval_vector_weight: 1.0,
+            battery_max_tokens: 320,
+            battery_defer_background: true,
+            battery_disable_preload: true,
+            thermal_protection: true,
+            thermal_cpu_c: 90.0,
+            thermal_gpu_c: 85.0,
+            idle_threshold_secs: 120,
         }
     }
 }
@@ -559,6 +929,15 @@ pub struct RuntimePolicyPatch {
     pub background_max_concurrency: Option<usize>,
     pub retrieval_candidate_limit: Option<usize>,
     pub defer_background_under_pressure: Option<bool>,
+    pub retrieval_bm25_weight: Option<f64>,
+    pub retrieval_vector_weight: Option<f64>,
+    pub battery_max_tokens: Option<usize>,
+    pub battery_defer_background: Option<bool>,
+    pub battery_disable_preload: Option<bool>,
+    pub thermal_protection: Option<bool>,
+    pub thermal_cpu_c: Option<f64>,
+    pub thermal_gpu_c: Option<f64>,
+    pub idle_threshold_secs: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -593,6 +972,8 @@ pub struct PerformanceSummary {
     pub p50_latency_ms: Option<f64>,
     pub p95_latency_ms: Option<f64>,
     pub avg_tokens_per_sec: Option<f64>,
+    pub p50_first_token_ms: Option<f64>,
+    pub p95_first_token_ms: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -604,6 +985,250 @@ pub struct LiveSystemStats {
     pub process_count: usize,
     pub gpu_name: Option<String>,
     pub gpu_usage_pct: Option<f32>,
+    /// Running from a discharging battery rather than mains power.
+    pub on_battery: bool,
+    /// Average charge across batteries; `None` without one.
+    pub battery_pct: Option<f32>,
+    /// Hottest CPU and GPU sensors in °C; `None` where the OS exposes none.
+    pub cpu_temp_c: Option<f32>,
+    pub gpu_temp_c: Option<f32>,
+    /// Seconds since the last keyboard or mouse input; `None` where the OS
+    /// can't tell.
+    pub idle_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RagStats {
+    pub namespace: String,
+    pub document_count: i64,
+    pub indexed_document_count: i64,
+    pub pending_document_count: i64,
+    pub failed_document_count: i64,
+    pub chunk_count: i64,
+    pub embedded_chunk_count: i64,
+    pub avg_chunk_tokens: f64,
+    pub avg_chunk_chars: f64,
+    pub last_indexed_at: Option<String>,
+    pub oldest_indexed_at: Option<String>,
+    pub retrieval_count: i64,
+    pub retrieved_chunk_count: i64,
+    pub cited_chunk_count: i64,
+    pub hit_rate: f64,
+}
+
+/// A named RAG namespace the user created to group documents, e.g. "Work docs".
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct RagCollection {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub namespace: String,
+    pub description: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A namespace as shown to the user: created collections, plus namespaces that
+/// only exist because documents or workspaces use them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RagCollectionSummary {
+    pub namespace: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// `None` for namespaces that were never created as a collection.
+    pub collection_id: Option<String>,
+    pub document_count: i64,
+    pub chunk_count: i64,
+    /// Names of workspaces that ingest into and retrieve from this namespace.
+    pub workspaces: Vec<String>,
+}
+
+/// A folder whose files are kept indexed into `namespace` as they change.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchedFolder {
+    pub id: String,
+    pub user_id: String,
+    pub path: String,
+    pub namespace: String,
+    pub is_active: i64,
+    pub last_scan_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Live indexing progress of a watched folder; not persisted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchedFolderStatus {
+    pub id: String,
+    pub path: String,
+    pub namespace: String,
+    /// "scanning", "indexing", "watching" or "error".
+    pub state: String,
+    /// Changed files waiting to be re-indexed.
+    pub pending_files: usize,
+    pub indexed_files: usize,
+    pub removed_files: usize,
+    pub failed_files: usize,
+    pub last_scan_at: Option<String>,
+    pub last_event_at: Option<String>,
+    pub last_error: Option<String>,
+}
+
+/// A background job re-embedding every stored vector with `target_model`.
+/// `status` is `running`, `completed` or `failed`.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ReindexJob {
+    pub id: String,
+    pub source_model: String,
+    pub target_model: String,
+    pub status: String,
+    pub total_items: i64,
+    pub processed_items: i64,
+    pub error_message: Option<String>,
+    pub completed_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A persistent background job. `status` is `queued`, `running`, `completed`,
+/// `failed` or `cancelled`; recurring jobs (`cron` set) go back to `queued`.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct BackgroundJob {
+    pub id: String,
+    pub job_type: String,
+    pub payload: String,
+    pub cron: Option<String>,
+    pub run_at: String,
+    pub status: String,
+    pub attempts: i64,
+    pub max_attempts: i64,
+    pub last_error: Option<String>,
+    pub started_at: Option<String>,
+    pub completed_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A prompt run on a cron schedule, its replies collected in `session_id`.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledPrompt {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub prompt: String,
+    /// What the user typed, e.g. "every Monday at 9am" or a cron expression.
+    pub schedule: String,
+    /// `schedule` as a six-field cron expression, evaluated in local time.
+    pub cron: String,
+    /// Created on the first run.
+    pub session_id: Option<String>,
+    pub is_enabled: i64,
+    pub next_run_at: Option<String>,
+    pub last_run_at: Option<String>,
+    pub last_status: Option<String>,
+    pub last_error: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewScheduledPrompt {
+    pub user_id: String,
+    pub name: String,
+    pub prompt: String,
+    pub schedule: String,
+}
+
+/// A URL that receives a signed POST for each of `events`.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Webhook {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub url: String,
+    /// JSON array of event names, e.g. `["message.completed"]`.
+    pub events: String,
+    /// Encrypted signing secret; revealed through `get_webhook_secret` only.
+    #[serde(skip)]
+    pub secret: String,
+    pub is_enabled: i64,
+    pub last_delivery_at: Option<String>,
+    pub last_status: Option<String>,
+    pub last_error: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewWebhook {
+    pub user_id: String,
+    pub name: String,
+    pub url: String,
+    pub events: Vec<String>,
+}
+
+/// Per-session retrieval controls, stored under `$.rag` in the session metadata.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SessionRagSettings {
+    pub enabled: bool,
+    /// Chunks kept after reranking (before neighbour expansion).
+    pub chunks: usize,
+    /// Fused candidates handed to the reranker.
+    pub rerank_top_k: usize,
+    /// Namespaces retrieval searches; empty means the session's workspace namespace.
+    pub collections: Vec<String>,
+}
+
+impl Default for SessionRagSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            chunks: 8,
+            rerank_top_k: 15,
+            collections: Vec::new(),
+        }
+    }
+}
+
+/// Something the user wants in every turn of a session, whatever retrieval finds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum PinContextItem {
+    Document {
+        document_id: String,
+    },
+    Chunk {
+        chunk_id: String,
+    },
+    Note {
+        text: String,
+        label: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct PinnedContextItem {
+    pub id: String,
+    pub session_id: String,
+    pub kind: String,
+    pub document_id: Option<String>,
+    pub chunk_id: Option<String>,
+    pub note: Option<String>,
+    pub label: String,
+    pub created_at: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -626,6 +1251,35 @@ pub struct ToolResult {
     pub error: Option<String>,
 }
 
+/// Lifecycle update for a single tool call; `status` is awaiting_approval, started,
+/// succeeded, failed or denied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolCallEvent {
+    pub tool_call_id: String,
+    pub session_id: String,
+    pub message_id: String,
+    pub mcp_id: String,
+    pub tool_name: String,
+    pub status: String,
+    pub latency_ms: Option<i64>,
+    pub error: Option<String>,
+}
+
+/// A tool call held until the user allows or denies it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolApprovalRequest {
+    pub id: String,
+    pub tool_call_id: String,
+    pub session_id: String,
+    pub message_id: String,
+    pub mcp_id: String,
+    pub tool_name: String,
+    pub args: serde_json::Value,
+    pub created_at: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RankCandidate {
@@ -672,6 +1326,12 @@ pub struct AssembledContext {
     pub tools: Vec<Mcp>,
     pub memory_refs: Vec<Memory>,
     pub doc_refs: Vec<RetrievedChunk>,
+    /// The workspace retrieval was scoped to, if any.
+    #[serde(default)]
+    pub workspace: Option<Workspace>,
+    /// The persona the session adopted, if any.
+    #[serde(default)]
+    pub persona: Option<Persona>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -679,7 +1339,16 @@ pub struct AssembledContext {
 pub struct GenerationOptions {
     pub temperature: f32,
     pub top_p: f32,
+    /// 0 disables top-k filtering.
+    #[serde(default)]
+    pub top_k: i32,
     pub max_tokens: usize,
+    /// GBNF grammar (entry rule `root`) that sampling must follow.
+    #[serde(default)]
+    pub grammar: Option<String>,
+    /// Sampling seed; the loaded model's fixed seed when unset.
+    #[serde(default)]
+    pub seed: Option<u32>,
 }
 
 impl Default for GenerationOptions {
@@ -687,17 +1356,34 @@ impl Default for GenerationOptions {
         Self {
             temperature: 0.2,
             top_p: 0.95,
+            top_k: 40,
             max_tokens: 512,
+            grammar: None,
+            seed: None,
         }
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationPreset {
+    pub name: String,
+    pub temperature: f32,
+    pub top_p: f32,
+    pub top_k: i32,
+    #[serde(default)]
+    pub is_builtin: bool,
+    #[serde(default)]
+    pub is_customized: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GenerationResult {
     pub text: String,
     pub tokens_generated: usize,
     pub finish_reason: String,
+    pub first_token_ms: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -706,6 +1392,27 @@ pub struct MessageStreamChunk {
     pub session_id: String,
     pub token: String,
     pub done: bool,
+    /// Set on the final chunk: "stop", "length", "cancelled" or "error".
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+    /// Set on the final chunk: the retrieved documents the reply cited.
+    #[serde(default)]
+    pub citations: Vec<Citation>,
+}
+
+/// A retrieved chunk an assistant reply cited with its `[N]` marker.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Citation {
+    /// The `N` of the `[N]` marker in the reply.
+    pub marker: usize,
+    pub chunk_id: String,
+    pub document_id: String,
+    pub document_title: String,
+    pub page_number: Option<i64>,
+    pub section_title: Option<String>,
+    /// File path or URL the document was ingested from, for opening it.
+    pub source: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -715,3 +1422,120 @@ pub struct McpHealthStatus {
     pub health_status: String,
     pub last_error: Option<String>,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupFailure {
+    pub message: String,
+    /// True when initialization panicked rather than returning an error.
+    pub panicked: bool,
+    pub occurred_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseBackup {
+    pub file_name: String,
+    pub path: String,
+    pub size_bytes: u64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableRowCount {
+    pub name: String,
+    pub rows: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseMaintenanceReport {
+    /// `PRAGMA integrity_check` output; a single "ok" when the file is sound.
+    pub integrity: Vec<String>,
+    pub integrity_ok: bool,
+    /// Whether the checkpoint was blocked by a reader and the WAL kept frames.
+    pub checkpoint_busy: bool,
+    pub wal_bytes_before: u64,
+    pub wal_bytes_after: u64,
+    pub page_size: i64,
+    pub page_count_before: i64,
+    pub page_count_after: i64,
+    pub freelist_count: i64,
+    pub database_bytes: i64,
+    pub tables: Vec<TableRowCount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseEncryptionStatus {
+    pub encrypted: bool,
+    /// Whether this build links SQLCipher and can encrypt the database.
+    pub supported: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupStatus {
+    pub safe_mode: bool,
+    pub failure: Option<StartupFailure>,
+    pub db_path: String,
+    /// Result of `PRAGMA quick_check` in safe mode; `None` when the file couldn't be opened.
+    pub integrity: Option<String>,
+    pub backups: Vec<DatabaseBackup>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClarificationCandidate {
+    pub id: String,
+    /// What the id refers to for the tool, e.g. "track" or "artist".
+    pub kind: String,
+    pub title: String,
+    pub subtitle: Option<String>,
+}
+
+/// A tool intent held back until the user picks what they meant.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingClarification {
+    pub id: String,
+    pub scope: String,
+    /// The held intent, e.g. "play" or "queue".
+    pub action: String,
+    pub query: String,
+    pub question: String,
+    pub candidates: Vec<ClarificationCandidate>,
+    pub created_at: String,
+}
+
+/// A screenshot or recording kept in the capture library. `capture_type` is
+/// `screenshot` or `recording`.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Capture {
+    pub id: String,
+    pub session_id: Option<String>,
+    pub capture_type: String,
+    pub file_path: String,
+    pub mime_type: String,
+    pub thumbnail_path: Option<String>,
+    pub duration_ms: Option<i64>,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub file_size_bytes: Option<i64>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewCapture {
+    pub session_id: Option<String>,
+    pub capture_type: String,
+    pub file_path: String,
+    pub mime_type: String,
+    pub thumbnail_path: Option<String>,
+    pub duration_ms: Option<i64>,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+}
@@ -0,0 +1,259 @@
+use std::sync::{Arc, Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+use tokio_stream::StreamExt;
+
+use crate::error::AppError;
+use crate::services::notification_service::NotificationCategory;
+use crate::state::AppState;
+
+pub const DEEP_LINK_SCHEME: &str = "sarah";
+const DEEP_LINK_ASK_EVENT: &str = "sarah://deep-link-ask";
+/// Longer prompts are cut; a link is no place for a document.
+const MAX_PROMPT_CHARS: usize = 12_000;
+const SILENT_TITLE: &str = "Sarah";
+const DEEP_LINK_SETTINGS_NAMESPACE: &str = "deep_links";
+const DEEP_LINK_SETTINGS_KEY: &str = "settings";
+
+/// What links may do without the user. Everything is off by default.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DeepLinkSettings {
+    /// Lets `silent=1` links be answered without showing the overlay. When
+    /// off, such links open the overlay like any other, for the user to send.
+    pub allow_silent: bool,
+}
+
+/// A `sarah://ask?prompt=...&model=...` link, for the overlay to fill in.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeepLinkAsk {
+    pub prompt: String,
+    /// A model id or name; `None` or `auto` lets Sarah pick.
+    pub model: Option<String>,
+}
+
+/// A link that arrived before the overlay was listening, e.g. the one the
+/// app was launched with.
+fn pending_ask() -> &'static Mutex<Option<DeepLinkAsk>> {
+    static PENDING: OnceLock<Mutex<Option<DeepLinkAsk>>> = OnceLock::new();
+    PENDING.get_or_init(|| Mutex::new(None))
+}
+
+/// Handles `sarah://` links opened while the app runs, and the one it was
+/// launched with.
+pub fn register(app: &AppHandle) {
+    // Installers register the scheme; dev builds and AppImages have to do it themselves.
+    #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+    if let Err(error) = app.deep_link().register_all() {
+        crate::log_warn!(
+            "sarah.deep_link",
+            "Failed to register {}:// links: {}",
+            DEEP_LINK_SCHEME,
+            error
+        );
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            open_url(&handle, &url);
+        }
+    });
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        for url in urls {
+            open_url(app, &url);
+        }
+    }
+}
+
+fn open_url(app: &AppHandle, url: &Url) {
+    if url.scheme() != DEEP_LINK_SCHEME {
+        return;
+    }
+    match url.host_str() {
+        Some("ask") => {}
+        other => {
+            crate::log_warn!(
+                "sarah.deep_link",
+                "Ignoring unknown link action: {}",
+                other.unwrap_or_default()
+            );
+            return;
+        }
+    }
+
+    let mut prompt = String::new();
+    let mut model = None;
+    let mut silent = false;
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "prompt" | "q" => prompt = value.trim().chars().take(MAX_PROMPT_CHARS).collect(),
+            "model" => model = Some(value.trim().to_string()).filter(|model| !model.is_empty()),
+            "silent" => silent = matches!(value.as_ref(), "1" | "true" | "yes"),
+            _ => {}
+        }
+    }
+    let ask = DeepLinkAsk { prompt, model };
+    crate::log_info!(
+        "sarah.deep_link",
+        "Opened ask link (silent: {}, model: {})",
+        silent,
+        ask.model.as_deref().unwrap_or("auto")
+    );
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if silent && !ask.prompt.is_empty() && silent_allowed(&app).await {
+            run_silently(&app, ask).await;
+        } else {
+            show_in_overlay(&app, ask).await;
+        }
+    });
+}
+
+async fn silent_allowed(app: &AppHandle) -> bool {
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return false;
+    };
+    let allowed = settings(&state).await.allow_silent;
+    if !allowed {
+        crate::log_info!(
+            "sarah.deep_link",
+            "Silent links are off; showing the prompt in the overlay instead"
+        );
+    }
+    allowed
+}
+
+async fn show_in_overlay(app: &AppHandle, ask: DeepLinkAsk) {
+    if let Ok(mut pending) = pending_ask().lock() {
+        *pending = Some(ask.clone());
+    }
+    crate::overlay_position::place_main_window(app).await;
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    let _ = app.emit(DEEP_LINK_ASK_EVENT, ask);
+}
+
+/// Answers in a new chat without showing the overlay, then shows the reply
+/// as a notification. Anything that can open a link can trigger this, so
+/// tools stay off and the exchange is sent as external: it leaves no
+/// memories and fires no webhooks.
+async fn run_silently(app: &AppHandle, ask: DeepLinkAsk) {
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return;
+    };
+    let state = state.inner().clone();
+    let body = match ask_in_new_session(&state, &ask).await {
+        Ok(reply) => reply,
+        Err(error) => {
+            crate::log_warn!("sarah.deep_link", "Silent ask failed: {}", error);
+            format!("Couldn't answer: {error}")
+        }
+    };
+    crate::tray::refresh(app);
+    state
+        .notifications
+        .notify(NotificationCategory::DeepLinks, SILENT_TITLE, &body)
+        .await;
+}
+
+async fn ask_in_new_session(state: &AppState, ask: &DeepLinkAsk) -> Result<String, AppError> {
+    let user = state.user_repo.get_or_create_default_user().await?;
+    let session = state
+        .conversation_repo
+        .create_session(&user.id, None)
+        .await?;
+    let model = ask
+        .model
+        .as_deref()
+        .filter(|model| !model.eq_ignore_ascii_case("auto"));
+    let mode = if model.is_some() { "manual" } else { "auto" };
+
+    let mut stream = state
+        .conversation
+        .send_message(
+            &user.id,
+            &session.id,
+            &ask.prompt,
+            &[],
+            Some(mode),
+            model,
+            None,
+            None,
+            false,
+            None,
+            true,
+        )
+        .await?;
+    let mut reply = String::new();
+    while let Some(chunk) = stream.next().await {
+        reply.push_str(&chunk.token);
+        if chunk.done {
+            if chunk.finish_reason.as_deref() == Some("error") {
+                return Err(AppError::Inference(reply));
+            }
+            break;
+        }
+    }
+    if let Err(error) = state.conversation.title_session(&session.id).await {
+        crate::log_warn!("sarah.deep_link", "Failed to title session: {}", error);
+    }
+    Ok(reply)
+}
+
+async fn settings(state: &AppState) -> DeepLinkSettings {
+    state
+        .settings_repo
+        .get_setting(None, DEEP_LINK_SETTINGS_NAMESPACE, DEEP_LINK_SETTINGS_KEY)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|setting| serde_json::from_str(&setting.value).ok())
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub async fn get_deep_link_settings(
+    state: State<'_, Arc<AppState>>,
+) -> Result<DeepLinkSettings, AppError> {
+    crate::log_info!("sarah.command", "get_deep_link_settings invoked");
+    Ok(settings(&state).await)
+}
+
+#[tauri::command]
+pub async fn set_deep_link_settings(
+    state: State<'_, Arc<AppState>>,
+    settings: DeepLinkSettings,
+) -> Result<DeepLinkSettings, AppError> {
+    crate::log_info!("sarah.command", "set_deep_link_settings invoked");
+    let value = serde_json::to_string(&settings)
+        .map_err(|e| AppError::Internal(format!("Failed to encode link settings: {e}")))?;
+    state
+        .settings_repo
+        .upsert_setting(
+            None,
+            DEEP_LINK_SETTINGS_NAMESPACE,
+            DEEP_LINK_SETTINGS_KEY,
+            &value,
+            "json",
+            false,
+        )
+        .await?;
+    Ok(settings)
+}
+
+/// Hands over the link the overlay missed while it was loading, once.
+#[tauri::command]
+pub fn take_pending_deep_link() -> Option<DeepLinkAsk> {
+    crate::log_info!("sarah.command", "take_pending_deep_link invoked");
+    pending_ask()
+        .lock()
+        .ok()
+        .and_then(|mut pending| pending.take())
+}
@@ -9,14 +9,22 @@ use std::process::Child;
 use std::time::Duration;
 use std::sync::Mutex;
 
+mod animated_capture;
+mod audio_capture;
+pub mod cli;
+mod clipboard;
 mod commands;
 mod db;
+mod deep_link;
 mod error;
 mod logging;
 mod native_capture;
+mod overlay_position;
 mod repositories;
 mod services;
 mod state;
+mod tray;
+mod writing_assist;
 
 pub struct SpotifyMcpState(Mutex<Option<Child>>);
 
@@ -26,44 +34,102 @@ impl Default for SpotifyMcpState {
     }
 }
 use crate::commands::analytics_commands::{get_recent_perf_logs, run_analytics_aggregation};
+use crate::commands::api_commands::{
+    get_local_api_status, get_local_api_token, rotate_local_api_token, set_local_api_settings,
+};
+use crate::commands::capture_commands::{delete_capture, list_captures, reveal_capture_in_explorer};
 use crate::commands::chat_commands::{
-    archive_session, create_session, get_session_messages, list_sessions, search_conversations,
-    send_message,
+    archive_session, ask_about_screen, create_session, describe_screen, edit_message,
+    export_all_sessions, export_session, fork_session, generate_structured, get_last_session,
+    get_session_messages, list_message_variants, list_pinned_context, list_sessions,
+    pin_context_item, rate_message, regenerate_message, search_conversations,
+    select_message_variant, semantic_search_history, send_agent_message, send_message,
+    set_last_session, set_session_preset, set_session_tags, share_session, stop_generation,
+    toggle_session_pin, unpin_context_item,
 };
+use crate::commands::import_commands::{import_conversations, import_from, scan_for_importable_data};
 use crate::commands::integration_commands::{
     build_spotify_mcp, close_audio_window, emit_audio_command, open_audio_window,
     open_history_window, open_mcp_window, open_models_window, open_settings_window,
     read_spotify_config, run_spotify_oauth, run_spotify_tool, spotify_mcp_status,
     start_spotify_mcp, stop_spotify_mcp, write_spotify_config,
 };
+use crate::commands::job_commands::{cancel_job, list_background_jobs, retry_job};
 use crate::commands::local_commands::{
-    clear_local_chat_history, download_local_model, generate_local_response,
-    generate_ollama_response, get_default_user, get_local_chat_history, greet,
-    list_local_models, list_local_models_detailed, list_ollama_models,
-    list_ollama_models_detailed, pull_ollama_model,
+    answer_clarification, chat_ollama, clear_local_chat_history, delete_ollama_model,
+    download_local_model, generate_local_response, generate_ollama_response, get_default_user,
+    get_local_chat_history, greet, list_local_models, list_local_models_detailed,
+    list_ollama_models, list_ollama_models_detailed, pull_ollama_model, show_ollama_model,
 };
 use crate::commands::mcp_commands::{
-    activate_mcp, deactivate_mcp, get_mcp_stats, install_mcp, list_mcps, save_mcp_secret,
-    test_mcp_connection,
+    activate_mcp, add_filesystem_root, add_mcp_server, add_remote_mcp_server, deactivate_mcp,
+    enable_mcp_server, get_mcp_stats, get_mcp_tool_permission, get_shell_allowlist, install_mcp,
+    list_filesystem_roots, list_mcps, remove_filesystem_root, remove_mcp_server,
+    respond_tool_approval, run_tool_calls, save_mcp_secret, set_mcp_tool_permission,
+    set_shell_allowlist, test_mcp_connection,
 };
 use crate::commands::memory_commands::{
-    delete_memory, get_memories, get_memory_graph, pin_memory, search_memories, update_memory,
+    delete_memory, forget_about, get_memories, get_memory_graph, list_memories, pin_memory,
+    search_memories, update_memory,
 };
 use crate::commands::model_commands::{
     get_download_progress, get_installed_models, get_model_catalog, get_model_compatibility_score,
-    get_recommended_models, run_nlp_setup, set_default_model, start_model_download,
+    get_recommended_models, list_lora_adapters, load_lora_adapter, refresh_recommendations,
+    run_nlp_setup, set_default_model, start_model_download, unload_lora_adapter,
+};
+use crate::commands::persona_commands::{
+    create_persona, delete_persona, list_personas, set_session_persona, update_persona,
+};
+use crate::commands::prompt_commands::{
+    create_saved_prompt, delete_saved_prompt, list_saved_prompts, run_saved_prompt,
+    update_saved_prompt,
+};
+use crate::commands::rag_commands::{
+    create_collection, delete_collection, embed_document, get_rag_stats, get_reindex_status,
+    get_session_rag_settings, ingest_document, ingest_url, list_collections, list_watched_folders,
+    reindex_documents, retrieve_knowledge, set_session_rag_settings, unwatch_folder, watch_folder,
+};
+use crate::commands::recovery_commands::{
+    backup_database, encrypt_database, get_database_encryption, get_startup_status,
+    list_database_backups, restart_app, restore_database_backup, run_db_maintenance,
 };
-use crate::commands::rag_commands::{embed_document, ingest_document, retrieve_knowledge};
 use crate::commands::runtime_commands::{
+    export_benchmark_report, get_effective_config, get_inference_queue_status,
     get_model_routing_decision, get_optimization_stats, get_performance_dashboard,
     get_runtime_policy, get_runtime_profile, get_service_health, get_setup_status,
-    get_startup_telemetry, retry_setup_stage, run_model_microbenchmark, set_runtime_policy,
-    skip_quality_upgrade_for_now, start_first_run_setup,
+    get_startup_telemetry, pin_model_for_task, retry_setup_stage, run_model_microbenchmark,
+    session_cache_stats, set_runtime_policy, skip_quality_upgrade_for_now, start_first_run_setup,
+    unpin_model_for_task,
+};
+use crate::commands::schedule_commands::{
+    create_scheduled_prompt, delete_scheduled_prompt, list_scheduled_prompts,
+    run_scheduled_prompt_now, set_scheduled_prompt_enabled, update_scheduled_prompt,
+};
+use crate::commands::settings_commands::{
+    export_user_data, get_default_instructions, get_memory_decay_policy,
+    get_notification_preferences, get_retention_policy, get_session_user_instructions,
+    get_setting, get_user_instructions, list_generation_presets, list_settings_namespace,
+    preview_retention, preview_system_prompt, reset_generation_preset, save_generation_preset,
+    set_default_instructions, set_memory_decay_policy, set_notification_preferences,
+    set_retention_policy, set_session_user_instructions, set_setting, set_user_instructions,
 };
-use crate::commands::settings_commands::{get_setting, list_settings_namespace, set_setting};
 use crate::commands::system_commands::{
-    get_hardware_profile, get_system_stats, run_hardware_benchmark,
+    get_hardware_profile, get_system_stats, ocr_image, run_hardware_benchmark, run_self_test,
+};
+use crate::commands::voice_commands::{
+    get_tts_settings, get_wake_word_settings, get_wake_word_status, list_tts_voices,
+    set_tts_settings, set_wake_word_settings, speak_text, start_voice_capture, stop_speaking,
+    stop_voice_capture,
+};
+use crate::commands::webhook_commands::{
+    create_webhook, delete_webhook, get_webhook_secret, list_webhook_events, list_webhooks,
+    rotate_webhook_secret, set_webhook_enabled, test_webhook, update_webhook,
 };
+use crate::commands::workspace_commands::{
+    assign_session_workspace, create_workspace, delete_workspace, get_active_workspace,
+    list_workspace_sessions, list_workspaces, set_active_workspace, update_workspace,
+};
+use crate::services::recovery_service::SafeMode;
 use crate::state::AppState;
 
 fn init_tracing() {
@@ -90,6 +156,14 @@ pub fn run() {
         .expect("Failed to create reqwest client");
 
     tauri::Builder::default()
+        // First, so a second launch (e.g. to open a sarah:// link) hands over
+        // to the running app before anything else starts.
+        .plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        }))
         .manage(SpotifyMcpState::default())
         .manage(client)
         .setup(|app| {
@@ -101,12 +175,38 @@ pub fn run() {
                 }
             }
 
-            let state = tauri::async_runtime::block_on(AppState::initialize(&app_handle)).map_err(
-                |error| std::io::Error::new(std::io::ErrorKind::Other, error.to_string()),
-            )?;
+            let state = match tauri::async_runtime::block_on(AppState::initialize_guarded(
+                &app_handle,
+            )) {
+                Ok(state) => state,
+                Err(failure) => {
+                    // Keep the process alive with only the recovery commands usable.
+                    let safe_mode =
+                        tauri::async_runtime::block_on(SafeMode::enter(&app_handle, failure));
+                    app.manage(Arc::new(safe_mode));
+                    if let Some(window) = app.get_webview_window("main") {
+                        let _ = window.show();
+                        let _ = window.set_focus();
+                    }
+                    let _ = app.emit("backend-safe-mode", true);
+                    log_warn!("sarah", "Started in safe mode");
+                    return Ok(());
+                }
+            };
 
             app.manage(Arc::new(state));
 
+            if let Err(error) = tray::setup(&app_handle) {
+                log_warn!("sarah", "Failed to create tray icon: {}", error);
+            }
+            clipboard::register_clipboard_shortcut(&app_handle);
+            writing_assist::register_writing_shortcuts(&app_handle);
+            deep_link::register(&app_handle);
+            let overlay_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                overlay_position::place_main_window(&overlay_handle).await;
+            });
+
             if let Some(window) = app.get_webview_window("main") {
                 let _ = window.show();
                 let _ = window.set_focus();
@@ -115,62 +215,182 @@ pub fn run() {
             // Signal frontend that the backend is ready
             let _ = app.emit("backend-ready", true);
 
+            tauri::async_runtime::spawn(async move {
+                if let Err(error) =
+                    crate::commands::integration_commands::restore_previous_state(app_handle).await
+                {
+                    log_warn!("sarah", "Failed to restore previous state: {}", error);
+                }
+            });
+
             log_info!("sarah", "Application setup complete");
 
             Ok(())
         })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Moved(position) = event {
+                if window.label() == "main" {
+                    crate::overlay_position::remember_position(window.app_handle(), *position);
+                }
+            }
+            if let tauri::WindowEvent::CloseRequested { .. } = event {
+                crate::commands::integration_commands::forget_window_on_close(
+                    window.app_handle(),
+                    window.label(),
+                );
+                if window.label() == crate::commands::integration_commands::AUDIO_WINDOW_LABEL {
+                    crate::commands::integration_commands::unregister_media_keys(
+                        window.app_handle(),
+                    );
+                }
+            }
+        })
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_deep_link::init())
         .invoke_handler(tauri::generate_handler![
             greet,
             get_default_user,
             generate_local_response,
+            answer_clarification,
             list_local_models,
             list_local_models_detailed,
             download_local_model,
             get_local_chat_history,
             clear_local_chat_history,
             send_message,
+            send_agent_message,
+            describe_screen,
+            ask_about_screen,
+            regenerate_message,
+            edit_message,
+            list_message_variants,
+            select_message_variant,
+            generate_structured,
+            stop_generation,
             create_session,
+            fork_session,
             list_sessions,
+            set_last_session,
+            get_last_session,
             get_session_messages,
             archive_session,
+            set_session_tags,
+            toggle_session_pin,
+            share_session,
+            export_session,
+            export_all_sessions,
+            set_session_preset,
+            pin_context_item,
+            unpin_context_item,
+            list_pinned_context,
             search_conversations,
+            semantic_search_history,
+            rate_message,
+            list_saved_prompts,
+            create_saved_prompt,
+            update_saved_prompt,
+            delete_saved_prompt,
+            run_saved_prompt,
+            list_workspaces,
+            create_workspace,
+            update_workspace,
+            delete_workspace,
+            get_active_workspace,
+            set_active_workspace,
+            assign_session_workspace,
+            list_personas,
+            create_persona,
+            update_persona,
+            delete_persona,
+            set_session_persona,
+            list_workspace_sessions,
             get_installed_models,
             get_model_catalog,
             get_recommended_models,
+            refresh_recommendations,
             set_default_model,
             get_model_compatibility_score,
             run_nlp_setup,
+            scan_for_importable_data,
+            import_from,
+            import_conversations,
             start_model_download,
             get_download_progress,
+            load_lora_adapter,
+            unload_lora_adapter,
+            list_lora_adapters,
             get_memories,
+            list_memories,
             search_memories,
             delete_memory,
             pin_memory,
             update_memory,
             get_memory_graph,
+            forget_about,
             get_hardware_profile,
             run_hardware_benchmark,
             get_system_stats,
+            ocr_image,
+            run_self_test,
             list_mcps,
             install_mcp,
             activate_mcp,
             deactivate_mcp,
+            add_mcp_server,
+            add_remote_mcp_server,
+            remove_mcp_server,
+            enable_mcp_server,
             save_mcp_secret,
             test_mcp_connection,
             get_mcp_stats,
+            run_tool_calls,
+            respond_tool_approval,
+            get_mcp_tool_permission,
+            set_mcp_tool_permission,
+            list_filesystem_roots,
+            add_filesystem_root,
+            remove_filesystem_root,
+            get_shell_allowlist,
+            set_shell_allowlist,
             ingest_document,
+            ingest_url,
             embed_document,
             retrieve_knowledge,
+            get_rag_stats,
+            list_collections,
+            create_collection,
+            delete_collection,
+            watch_folder,
+            unwatch_folder,
+            list_watched_folders,
+            reindex_documents,
+            get_reindex_status,
+            get_startup_status,
+            backup_database,
+            list_database_backups,
+            restore_database_backup,
+            restart_app,
+            get_database_encryption,
+            encrypt_database,
+            run_db_maintenance,
+            get_session_rag_settings,
+            set_session_rag_settings,
             get_runtime_policy,
             set_runtime_policy,
             get_runtime_profile,
             get_service_health,
+            get_inference_queue_status,
+            session_cache_stats,
             get_optimization_stats,
             get_startup_telemetry,
             run_model_microbenchmark,
+            export_benchmark_report,
+            get_effective_config,
             get_model_routing_decision,
+            pin_model_for_task,
+            unpin_model_for_task,
             get_performance_dashboard,
             start_first_run_setup,
             get_setup_status,
@@ -179,8 +399,61 @@ pub fn run() {
             get_setting,
             set_setting,
             list_settings_namespace,
+            get_default_instructions,
+            set_default_instructions,
+            get_user_instructions,
+            set_user_instructions,
+            get_session_user_instructions,
+            set_session_user_instructions,
+            preview_system_prompt,
+            get_retention_policy,
+            set_retention_policy,
+            get_notification_preferences,
+            set_notification_preferences,
+            get_local_api_status,
+            set_local_api_settings,
+            get_local_api_token,
+            rotate_local_api_token,
+            preview_retention,
+            get_memory_decay_policy,
+            set_memory_decay_policy,
+            export_user_data,
+            list_generation_presets,
+            save_generation_preset,
+            reset_generation_preset,
             get_recent_perf_logs,
             run_analytics_aggregation,
+            list_captures,
+            delete_capture,
+            reveal_capture_in_explorer,
+            list_background_jobs,
+            cancel_job,
+            retry_job,
+            list_scheduled_prompts,
+            create_scheduled_prompt,
+            update_scheduled_prompt,
+            set_scheduled_prompt_enabled,
+            delete_scheduled_prompt,
+            run_scheduled_prompt_now,
+            list_webhooks,
+            list_webhook_events,
+            create_webhook,
+            update_webhook,
+            set_webhook_enabled,
+            delete_webhook,
+            get_webhook_secret,
+            rotate_webhook_secret,
+            test_webhook,
+            start_voice_capture,
+            stop_voice_capture,
+            speak_text,
+            stop_speaking,
+            list_tts_voices,
+            get_tts_settings,
+            set_tts_settings,
+            get_wake_word_settings,
+            set_wake_word_settings,
+            get_wake_word_status,
             open_history_window,
             open_settings_window,
             open_models_window,
@@ -194,17 +467,38 @@ pub fn run() {
             build_spotify_mcp,
             write_spotify_config,
             run_spotify_tool,
+            audio_capture::list_audio_devices,
+            tray::take_pending_history_focus,
+            deep_link::take_pending_deep_link,
+            deep_link::get_deep_link_settings,
+            deep_link::set_deep_link_settings,
+            overlay_position::get_overlay_position_mode,
+            overlay_position::set_overlay_position_mode,
+            overlay_position::position_overlay,
+            clipboard::read_clipboard_context,
+            clipboard::get_use_clipboard_context,
+            clipboard::set_use_clipboard_context,
+            writing_assist::rewrite_selection,
+            writing_assist::summarize_selection,
+            writing_assist::take_writing_result,
+            writing_assist::get_writing_shortcuts,
+            writing_assist::set_writing_shortcuts,
             native_capture::list_active_windows,
             native_capture::get_default_capture_directory,
+            native_capture::pause_native_screen_recording,
             native_capture::pick_capture_output_directory,
+            native_capture::resume_native_screen_recording,
             native_capture::start_native_screen_recording,
             native_capture::stop_native_screen_recording,
             native_capture::take_native_screenshot,
             native_capture::validate_capture_path,
             generate_ollama_response,
+            chat_ollama,
             list_ollama_models,
             list_ollama_models_detailed,
             pull_ollama_model,
+            delete_ollama_model,
+            show_ollama_model,
             emit_audio_command,
             read_spotify_config
 
@@ -213,14 +507,18 @@ pub fn run() {
         .expect("error while building tauri application")
         .run(|app_handle, event| {
             if let tauri::RunEvent::ExitRequested { .. } = &event {
-                let state = app_handle.state::<Arc<AppState>>();
+                let Some(state) = app_handle.try_state::<Arc<AppState>>() else {
+                    return;
+                };
                 let background = state.background.clone();
+                let local_api = state.local_api.clone();
                 let inference = state.inference.clone();
                 let db = state.db.clone();
 
                 tauri::async_runtime::block_on(async {
                     tracing::info!("App exit requested — starting graceful shutdown");
                     background.stop_all().await;
+                    local_api.stop().await;
                     inference.shutdown().await;
                     db.optimize().await;
                     tracing::info!("Graceful shutdown complete");
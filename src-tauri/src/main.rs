@@ -1,5 +1,8 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    if let Some(code) = sarah_lib::cli::try_run() {
+        std::process::exit(code);
+    }
     sarah_lib::run();
 }
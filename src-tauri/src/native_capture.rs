@@ -2,11 +2,18 @@ use std::ffi::c_void;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex, OnceLock};
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread::{self, JoinHandle};
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use rfd::FileDialog;
+use crate::animated_capture::{AnimationEncoder, AnimationOptions, RecordingFormat};
+use crate::audio_capture::{self, AudioMix, RecordingAudioOptions, MIX_CHANNELS, MIX_SAMPLE_RATE};
+use crate::db::models::NewCapture;
+use crate::services::capture_library;
+use crate::services::notification_service::NotificationCategory;
+use crate::services::webhook_service::WebhookEvent;
+use crate::state::AppState;
 use windows_capture::capture::{Context, GraphicsCaptureApiHandler};
 use windows_capture::encoder::{
     AudioSettingsBuilder, ContainerSettingsBuilder, VideoEncoder, VideoSettingsBuilder,
@@ -21,6 +28,11 @@ use windows_capture::settings::{
 };
 use windows_capture::window::Window;
 use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+const AUTO_STOPPED_EVENT: &str = "capture://auto-stopped";
+/// How often a size-limited recording stats its output file.
+const FILE_SIZE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
 
 #[derive(Clone, Copy, Debug, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -40,6 +52,8 @@ pub struct ActiveWindowSource {
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct NativeRecordingResult {
+    /// Library entry for the recording; absent if it couldn't be added.
+    pub capture_id: Option<String>,
     pub duration_ms: u64,
     pub ended_at_ms: u64,
     pub mime_type: String,
@@ -47,9 +61,41 @@ pub struct NativeRecordingResult {
     pub video_path: String,
 }
 
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AutoStopReason {
+    MaxDuration,
+    MaxFileSize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingAutoStopped {
+    pub reason: AutoStopReason,
+    pub video_path: String,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct RecordingLimits {
+    /// Recorded time, not counting pauses.
+    max_duration_ms: Option<u64>,
+    max_file_size_bytes: Option<u64>,
+}
+
+/// What a recording captures besides the picture, and how it's encoded.
+#[derive(Clone, Debug)]
+struct RecordingOptions {
+    format: RecordingFormat,
+    animation: AnimationOptions,
+    /// Ignored for GIF and WebP, which have no audio track.
+    audio: RecordingAudioOptions,
+    limits: RecordingLimits,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct NativeScreenshotResult {
+    pub capture_id: Option<String>,
     pub captured_at_ms: u64,
     pub screenshot_path: String,
 }
@@ -59,6 +105,9 @@ struct RecordingArtifacts {
     duration_ms: u64,
     ended_at_ms: u64,
     video_path: PathBuf,
+    width: u32,
+    height: u32,
+    thumbnail_path: Option<PathBuf>,
 }
 
 #[derive(Debug)]
@@ -66,6 +115,8 @@ struct NativeCaptureSession {
     join_handle: std::thread::JoinHandle<Result<RecordingArtifacts, String>>,
     started_at_ms: u64,
     stop_flag: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    paused: Arc<AtomicBool>,
+    format: RecordingFormat,
 }
 
 #[derive(Default)]
@@ -73,9 +124,88 @@ struct NativeCaptureState {
     active: Option<NativeCaptureSession>,
 }
 
+/// Handed to the capture handler; the flags are shared with the commands.
+struct RecordingFlags {
+    stop_flag: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    video_path: PathBuf,
+    thumbnail_path: PathBuf,
+    width: u32,
+    height: u32,
+    format: RecordingFormat,
+    animation: AnimationOptions,
+    audio: Option<Arc<AudioMix>>,
+    limits: RecordingLimits,
+    auto_stopped: Arc<Mutex<Option<AutoStopReason>>>,
+}
+
 struct EncoderCapture {
     encoder: Option<VideoEncoder>,
+    /// Used instead of `encoder` for GIF and WebP recordings.
+    animation: Option<AnimationEncoder>,
     stop_flag: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    paused: Arc<AtomicBool>,
+    /// Mixed system and microphone audio, when the recording includes any.
+    audio: Option<Arc<AudioMix>>,
+    video_path: PathBuf,
+    /// Written from the first recorded frame, then cleared.
+    thumbnail_path: Option<PathBuf>,
+    limits: RecordingLimits,
+    auto_stopped: Arc<Mutex<Option<AutoStopReason>>>,
+    /// Timestamp of the first frame, in 100 ns units; output time starts there.
+    first_frame: Option<i64>,
+    /// Frame timestamp at which the current pause began.
+    paused_at: Option<i64>,
+    /// Time spent paused so far, cut out of the output.
+    paused_for: i64,
+    /// Output time of the latest recorded frame.
+    last_elapsed: i64,
+    last_size_check: Instant,
+}
+
+impl EncoderCapture {
+    fn with_output(
+        flags: RecordingFlags,
+        encoder: Option<VideoEncoder>,
+        animation: Option<AnimationEncoder>,
+    ) -> Self {
+        Self {
+            encoder,
+            animation,
+            stop_flag: flags.stop_flag,
+            paused: flags.paused,
+            audio: flags.audio,
+            video_path: flags.video_path,
+            thumbnail_path: Some(flags.thumbnail_path),
+            limits: flags.limits,
+            auto_stopped: flags.auto_stopped,
+            first_frame: None,
+            paused_at: None,
+            paused_for: 0,
+            last_elapsed: 0,
+            last_size_check: Instant::now(),
+        }
+    }
+
+    fn limit_reached(&mut self, elapsed: i64) -> Option<AutoStopReason> {
+        if let Some(max_duration_ms) = self.limits.max_duration_ms {
+            if elapsed / 10_000 >= max_duration_ms as i64 {
+                return Some(AutoStopReason::MaxDuration);
+            }
+        }
+        if let Some(max_bytes) = self.limits.max_file_size_bytes {
+            if self.last_size_check.elapsed() >= FILE_SIZE_CHECK_INTERVAL {
+                self.last_size_check = Instant::now();
+                let size = fs::metadata(&self.video_path)
+                    .map(|metadata| metadata.len())
+                    .unwrap_or(0);
+                if size >= max_bytes {
+                    return Some(AutoStopReason::MaxFileSize);
+                }
+            }
+        }
+        None
+    }
 }
 
 struct ScreenshotCapture {
@@ -85,23 +215,31 @@ struct ScreenshotCapture {
 
 impl GraphicsCaptureApiHandler for EncoderCapture {
     type Error = Box<dyn std::error::Error + Send + Sync>;
-    type Flags = (std::sync::Arc<std::sync::atomic::AtomicBool>, PathBuf, u32, u32);
+    type Flags = RecordingFlags;
 
     fn new(ctx: Context<Self::Flags>) -> Result<Self, Self::Error> {
-        let (stop_flag, video_path, width, height) = ctx.flags;
-        let video_settings =
-            VideoSettingsBuilder::new(width, height).sub_type(VideoSettingsSubType::H264);
+        let flags = ctx.flags;
+        if flags.format.is_animation() {
+            let animation =
+                AnimationEncoder::new(flags.format, flags.video_path.clone(), flags.animation)?;
+            return Ok(Self::with_output(flags, None, Some(animation)));
+        }
+
+        let video_settings = VideoSettingsBuilder::new(flags.width, flags.height)
+            .sub_type(VideoSettingsSubType::H264);
+        let audio_settings = AudioSettingsBuilder::default()
+            .sample_rate(MIX_SAMPLE_RATE)
+            .channel_count(MIX_CHANNELS)
+            .bit_per_sample(16)
+            .disabled(flags.audio.is_none());
         let encoder = VideoEncoder::new(
             video_settings,
-            AudioSettingsBuilder::default().disabled(true),
+            audio_settings,
             ContainerSettingsBuilder::default(),
-            &video_path,
+            &flags.video_path,
         )?;
 
-        Ok(Self {
-            encoder: Some(encoder),
-            stop_flag,
-        })
+        Ok(Self::with_output(flags, Some(encoder), None))
     }
 
     fn on_frame_arrived(
@@ -109,14 +247,66 @@ impl GraphicsCaptureApiHandler for EncoderCapture {
         frame: &mut Frame,
         capture_control: InternalCaptureControl,
     ) -> Result<(), Self::Error> {
-        if let Some(encoder) = self.encoder.as_mut() {
-            encoder.send_frame(frame)?;
+        let timestamp = frame.timestamp().Duration;
+        let first_frame = *self.first_frame.get_or_insert(timestamp);
+        if self.paused.load(Ordering::SeqCst) {
+            self.paused_at.get_or_insert(timestamp);
+            // Audio from the pause is dropped along with the frames.
+            if let Some(mix) = self.audio.as_ref() {
+                mix.drain();
+            }
+        } else {
+            if let Some(paused_at) = self.paused_at.take() {
+                self.paused_for += timestamp - paused_at;
+            }
+            let elapsed = timestamp - first_frame - self.paused_for;
+            if let Some(encoder) = self.encoder.as_mut() {
+                if self.paused_for == 0 {
+                    encoder.send_frame(frame)?;
+                } else {
+                    // send_frame times frames from the first one, so once a
+                    // pause has been cut out the frame goes in as a buffer.
+                    let mut buffer = frame.buffer()?;
+                    encoder.send_frame_buffer(buffer.as_nopadding_buffer()?, elapsed)?;
+                }
+                if let Some((pcm, audio_timestamp)) =
+                    self.audio.as_ref().and_then(|mix| mix.drain())
+                {
+                    encoder.send_audio_buffer(&pcm, (audio_timestamp - self.paused_for).max(0))?;
+                }
+            }
+            if let Some(thumbnail_path) = self.thumbnail_path.take() {
+                let mut buffer = frame.buffer()?;
+                let (width, height) = (buffer.width(), buffer.height());
+                let bgra = buffer.as_nopadding_buffer()?;
+                if let Err(error) =
+                    capture_library::write_bgra_thumbnail(bgra, width, height, &thumbnail_path)
+                {
+                    crate::log_warn!("sarah.capture", "Recording thumbnail failed: {}", error);
+                }
+            }
+            if let Some(animation) = self.animation.as_mut() {
+                let mut buffer = frame.buffer()?;
+                let (width, height) = (buffer.width(), buffer.height());
+                animation.push(buffer.as_nopadding_buffer()?, width, height, elapsed)?;
+            }
+            self.last_elapsed = elapsed;
+
+            if let Some(reason) = self.limit_reached(elapsed) {
+                if let Ok(mut auto_stopped) = self.auto_stopped.lock() {
+                    *auto_stopped = Some(reason);
+                }
+                self.stop_flag.store(true, Ordering::SeqCst);
+            }
         }
 
         if self.stop_flag.load(std::sync::atomic::Ordering::SeqCst) {
             if let Some(encoder) = self.encoder.take() {
                 encoder.finish()?;
             }
+            if let Some(animation) = self.animation.take() {
+                animation.finish(self.last_elapsed)?;
+            }
             capture_control.stop();
         }
 
@@ -186,11 +376,15 @@ fn resolve_capture_directory(output_directory: Option<String>) -> Result<PathBuf
     Ok(base)
 }
 
-fn recording_output_path(output_directory: Option<String>) -> Result<PathBuf, String> {
+fn recording_output_path(
+    output_directory: Option<String>,
+    format: RecordingFormat,
+) -> Result<PathBuf, String> {
     let base = resolve_capture_directory(output_directory)?;
 
     let stamp = now_ms();
-    let video = base.join(format!("sarah-screen-recording-{stamp}.mp4"));
+    let extension = format.extension();
+    let video = base.join(format!("sarah-screen-recording-{stamp}.{extension}"));
     Ok(video)
 }
 
@@ -322,8 +516,39 @@ fn capture_single_screenshot(
     Ok(())
 }
 
+/// Saves the screenshot to the capture library, linked to `session_id`.
 #[tauri::command]
-pub fn take_native_screenshot(
+pub async fn take_native_screenshot(
+    app: AppHandle,
+    surface: CaptureSurface,
+    window_hwnd: Option<String>,
+    output_directory: Option<String>,
+    session_id: Option<String>,
+) -> Result<NativeScreenshotResult, String> {
+    let mut screenshot = tauri::async_runtime::spawn_blocking(move || {
+        capture_screenshot(surface, window_hwnd, output_directory)
+    })
+    .await
+    .map_err(|error| format!("Screenshot task failed: {error}"))??;
+
+    if let Some(state) = app.try_state::<Arc<AppState>>() {
+        let path = PathBuf::from(&screenshot.screenshot_path);
+        match state
+            .captures
+            .record_screenshot(&path, session_id.as_deref())
+            .await
+        {
+            Ok(capture) => screenshot.capture_id = Some(capture.id),
+            Err(error) => {
+                crate::log_warn!("sarah.capture", "Failed to add screenshot to library: {}", error);
+            }
+        }
+    }
+    Ok(screenshot)
+}
+
+/// Takes a screenshot without adding it to the capture library.
+pub fn capture_screenshot(
     surface: CaptureSurface,
     window_hwnd: Option<String>,
     output_directory: Option<String>,
@@ -341,6 +566,7 @@ pub fn take_native_screenshot(
     }
 
     Ok(NativeScreenshotResult {
+        capture_id: None,
         captured_at_ms: now_ms(),
         screenshot_path: screenshot_path.to_string_lossy().to_string(),
     })
@@ -356,14 +582,40 @@ fn compute_dimensions_for_window(window: Window) -> Result<(u32, u32), String> {
 }
 
 fn spawn_capture_thread(
+    app: AppHandle,
     surface: CaptureSurface,
     window_hwnd: Option<u64>,
     stop_flag: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
     video_path: PathBuf,
+    options: RecordingOptions,
 ) -> JoinHandle<Result<RecordingArtifacts, String>> {
     thread::spawn(move || {
+        // Device streams can't move between threads, so they live on this one
+        // until the recording ends.
+        let audio_capture = if options.audio.is_enabled() && !options.format.is_animation() {
+            Some(audio_capture::start_capture(&options.audio)?)
+        } else {
+            None
+        };
+        let mix = audio_capture.as_ref().map(|capture| capture.mix());
+        let auto_stopped = Arc::new(Mutex::new(None));
+        let thumbnail_path = capture_library::thumbnail_path(&video_path);
+        let flags = |width, height| RecordingFlags {
+            stop_flag: stop_flag.clone(),
+            paused: paused.clone(),
+            video_path: video_path.clone(),
+            thumbnail_path: thumbnail_path.clone(),
+            width,
+            height,
+            format: options.format,
+            animation: options.animation,
+            audio: mix.clone(),
+            limits: options.limits,
+            auto_stopped: auto_stopped.clone(),
+        };
         let started = Instant::now();
-        match surface {
+        let (width, height) = match surface {
             CaptureSurface::Screen => {
                 let monitor = Monitor::primary()
                     .map_err(|error| format!("Failed to access primary monitor: {error}"))?;
@@ -382,15 +634,11 @@ fn spawn_capture_thread(
                     MinimumUpdateIntervalSettings::Default,
                     DirtyRegionSettings::Default,
                     ColorFormat::Bgra8,
-                    (
-                        stop_flag.clone(),
-                        video_path.clone(),
-                        width,
-                        height,
-                    ),
+                    flags(width, height),
                 );
                 EncoderCapture::start(settings)
                     .map_err(|error| format!("Native capture failed: {error}"))?;
+                (width, height)
             }
             CaptureSurface::Window => {
                 let window = window_hwnd
@@ -410,25 +658,49 @@ fn spawn_capture_thread(
                     MinimumUpdateIntervalSettings::Default,
                     DirtyRegionSettings::Default,
                     ColorFormat::Bgra8,
-                    (
-                        stop_flag.clone(),
-                        video_path.clone(),
-                        width,
-                        height,
-                    ),
+                    flags(width, height),
                 );
                 EncoderCapture::start(settings)
                     .map_err(|error| format!("Native capture failed: {error}"))?;
+                (width, height)
             }
-        }
+        };
 
         let duration_ms = started.elapsed().as_millis() as u64;
         let ended_at_ms = now_ms();
 
+        let auto_stopped = auto_stopped.lock().ok().and_then(|reason| *reason);
+        if let Some(reason) = auto_stopped {
+            crate::log_info!("sarah.capture", "recording auto-stopped: {:?}", reason);
+            let _ = app.emit(
+                AUTO_STOPPED_EVENT,
+                RecordingAutoStopped {
+                    reason,
+                    video_path: video_path.to_string_lossy().to_string(),
+                },
+            );
+            crate::tray::refresh(&app);
+            if let Some(state) = app.try_state::<Arc<AppState>>() {
+                let notifications = state.notifications.clone();
+                let body = match reason {
+                    AutoStopReason::MaxDuration => "The recording reached its time limit.",
+                    AutoStopReason::MaxFileSize => "The recording reached its size limit.",
+                };
+                tauri::async_runtime::spawn(async move {
+                    notifications
+                        .notify(NotificationCategory::Captures, "Recording stopped", body)
+                        .await;
+                });
+            }
+        }
+
         Ok(RecordingArtifacts {
             duration_ms,
             ended_at_ms,
             video_path,
+            width,
+            height,
+            thumbnail_path: thumbnail_path.exists().then_some(thumbnail_path),
         })
     })
 }
@@ -454,10 +726,17 @@ pub fn list_active_windows() -> Result<Vec<ActiveWindowSource>, String> {
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn start_native_screen_recording(
+    app: AppHandle,
     _surface: CaptureSurface,
     _window_hwnd: Option<String>,
     output_directory: Option<String>,
+    audio: Option<RecordingAudioOptions>,
+    max_duration_ms: Option<u64>,
+    max_file_size_bytes: Option<u64>,
+    format: Option<RecordingFormat>,
+    animation: Option<AnimationOptions>,
 ) -> Result<(), String> {
     crate::log_info!("sarah.command", "start_native_screen_recording invoked");
     let mut guard = state()
@@ -477,48 +756,161 @@ pub fn start_native_screen_recording(
     fs::write(&screenshot_path, b"placeholder screenshot")
         .map_err(|error| format!("Failed to initialize placeholder screenshot: {error}"))?;
 
-    let video_path = recording_output_path(output_directory)?;
+    let format = format.unwrap_or_default();
+    let video_path = recording_output_path(output_directory, format)?;
     let started_at_ms = now_ms();
     let stop_flag = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let paused = Arc::new(AtomicBool::new(false));
     
     // Default to Screen if no surface is provided for now, or match surface enum and window_hwnd if needed
     // The upstream code had spawn_capture_thread(surface, raw_window_handle, stop_flag.clone(), video_path.clone());
     let raw_window_handle = parse_window_handle(_window_hwnd.clone())?;
     
-    let join_handle =
-        spawn_capture_thread(_surface, raw_window_handle, stop_flag.clone(), video_path.clone());
+    let options = RecordingOptions {
+        format,
+        animation: animation.unwrap_or_default(),
+        audio: audio.unwrap_or_default(),
+        limits: RecordingLimits {
+            max_duration_ms: max_duration_ms.filter(|value| *value > 0),
+            max_file_size_bytes: max_file_size_bytes.filter(|value| *value > 0),
+        },
+    };
+
+    crate::tray::refresh(&app);
+    let join_handle = spawn_capture_thread(
+        app,
+        _surface,
+        raw_window_handle,
+        stop_flag.clone(),
+        paused.clone(),
+        video_path.clone(),
+        options,
+    );
 
     guard.active = Some(NativeCaptureSession {
         join_handle,
         started_at_ms,
         stop_flag,
+        paused,
+        format,
     });
 
     Ok(())
 }
 
 #[tauri::command]
-pub fn stop_native_screen_recording() -> Result<NativeRecordingResult, String> {
-    crate::log_info!("sarah.command", "stop_native_screen_recording invoked");
-    let mut guard = state()
+pub fn pause_native_screen_recording() -> Result<(), String> {
+    crate::log_info!("sarah.command", "pause_native_screen_recording invoked");
+    set_recording_paused(true)
+}
+
+#[tauri::command]
+pub fn resume_native_screen_recording() -> Result<(), String> {
+    crate::log_info!("sarah.command", "resume_native_screen_recording invoked");
+    set_recording_paused(false)
+}
+
+/// Whether a recording is running; one that stopped at a limit doesn't count.
+pub fn is_recording() -> bool {
+    state()
+        .lock()
+        .map(|guard| {
+            guard
+                .active
+                .as_ref()
+                .is_some_and(|session| !session.join_handle.is_finished())
+        })
+        .unwrap_or(false)
+}
+
+fn set_recording_paused(paused: bool) -> Result<(), String> {
+    let guard = state()
         .lock()
         .map_err(|_| "Capture state lock was poisoned.".to_string())?;
-    if guard.active.is_none() {
-        return Err("No active screen recording to stop.".to_string());
-    }
+    let session = guard
+        .active
+        .as_ref()
+        .filter(|session| !session.join_handle.is_finished())
+        .ok_or_else(|| "No active screen recording.".to_string())?;
+    session.paused.store(paused, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Saves the recording to the capture library, linked to `session_id`.
+#[tauri::command]
+pub async fn stop_native_screen_recording(
+    app: AppHandle,
+    session_id: Option<String>,
+) -> Result<NativeRecordingResult, String> {
+    crate::log_info!("sarah.command", "stop_native_screen_recording invoked");
+    let active_session = state()
+        .lock()
+        .map_err(|_| "Capture state lock was poisoned.".to_string())?
+        .active
+        .take()
+        .ok_or_else(|| "No active screen recording to stop.".to_string())?;
 
     let ended = now_ms();
+    active_session.stop_flag.store(true, Ordering::SeqCst);
 
-    let active_session = guard.active.take().ok_or("Session not active")?;
-    let result = active_session.join_handle.join().map_err(|_| "Failed to join capture thread")??;
-    
-    let video_path = result.video_path;
     let started_at_ms = active_session.started_at_ms;
+    let mime_type = active_session.format.mime_type();
+    let join_handle = active_session.join_handle;
+    let result = tauri::async_runtime::spawn_blocking(move || join_handle.join())
+        .await
+        .map_err(|error| format!("Failed to join capture thread: {error}"))?
+        .map_err(|_| "Failed to join capture thread")??;
+    crate::tray::refresh(&app);
+
+    let video_path = result.video_path;
+    let mut capture_id = None;
+    if let Some(state) = app.try_state::<Arc<AppState>>() {
+        let capture = NewCapture {
+            session_id,
+            capture_type: "recording".to_string(),
+            file_path: video_path.to_string_lossy().to_string(),
+            mime_type: mime_type.to_string(),
+            thumbnail_path: result
+                .thumbnail_path
+                .map(|path| path.to_string_lossy().to_string()),
+            duration_ms: Some(result.duration_ms as i64),
+            width: Some(i64::from(result.width)),
+            height: Some(i64::from(result.height)),
+        };
+        match state.captures.record(capture).await {
+            Ok(capture) => {
+                // A recording linked to a chat belongs to that chat's user.
+                let owner = match capture.session_id.as_deref() {
+                    Some(session_id) => state
+                        .conversation_repo
+                        .get_session(session_id)
+                        .await
+                        .ok()
+                        .flatten()
+                        .map(|session| session.user_id),
+                    None => None,
+                };
+                state
+                    .webhooks
+                    .publish(
+                        owner.as_deref(),
+                        WebhookEvent::RecordingSaved,
+                        serde_json::to_value(&capture).unwrap_or_default(),
+                    )
+                    .await;
+                capture_id = Some(capture.id);
+            }
+            Err(error) => {
+                crate::log_warn!("sarah.capture", "Failed to add recording to library: {}", error);
+            }
+        }
+    }
 
     Ok(NativeRecordingResult {
+        capture_id,
         duration_ms: ended.saturating_sub(started_at_ms),
         ended_at_ms: ended,
-        mime_type: "video/mp4".to_string(),
+        mime_type: mime_type.to_string(),
         started_at_ms,
         video_path: video_path.to_string_lossy().to_string(),
     })
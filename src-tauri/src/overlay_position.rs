@@ -0,0 +1,249 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Monitor, PhysicalPosition, WebviewWindow};
+
+use crate::error::AppError;
+use crate::state::AppState;
+
+const OVERLAY_SETTINGS_NAMESPACE: &str = "overlay";
+const POSITION_MODE_KEY: &str = "position_mode";
+const POSITION_KEY: &str = "position";
+const MAIN_WINDOW_LABEL: &str = "main";
+/// A drag reports every step; only where it ends up is saved.
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Where the overlay appears when it's shown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverlayPositionMode {
+    /// Centered on the primary monitor.
+    #[default]
+    Center,
+    /// Centered on whichever monitor the cursor is on.
+    FollowCursor,
+    /// Wherever it was last dragged to, while that spot is still on a monitor.
+    Remembered,
+}
+
+impl OverlayPositionMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Center => "center",
+            Self::FollowCursor => "follow-cursor",
+            Self::Remembered => "remembered",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "center" => Some(Self::Center),
+            "follow-cursor" => Some(Self::FollowCursor),
+            "remembered" => Some(Self::Remembered),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct SavedPosition {
+    x: i32,
+    y: i32,
+}
+
+/// The mode as last read or set, so window moves don't hit the database.
+fn cached_mode() -> &'static Mutex<Option<OverlayPositionMode>> {
+    static MODE: OnceLock<Mutex<Option<OverlayPositionMode>>> = OnceLock::new();
+    MODE.get_or_init(|| Mutex::new(None))
+}
+
+/// Bumped on every move; a pending save only goes through if it's the latest.
+fn move_generation() -> &'static AtomicU64 {
+    static GENERATION: AtomicU64 = AtomicU64::new(0);
+    &GENERATION
+}
+
+#[tauri::command]
+pub async fn get_overlay_position_mode(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<OverlayPositionMode, AppError> {
+    crate::log_info!("sarah.command", "get_overlay_position_mode invoked");
+    position_mode(&state).await
+}
+
+/// Chooses where the overlay appears and moves it there right away.
+#[tauri::command]
+pub async fn set_overlay_position_mode(
+    app: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    mode: OverlayPositionMode,
+) -> Result<OverlayPositionMode, AppError> {
+    crate::log_info!("sarah.command", "set_overlay_position_mode invoked");
+    state
+        .settings_repo
+        .upsert_setting(
+            None,
+            OVERLAY_SETTINGS_NAMESPACE,
+            POSITION_MODE_KEY,
+            mode.as_str(),
+            "string",
+            false,
+        )
+        .await?;
+    if let Ok(mut cached) = cached_mode().lock() {
+        *cached = Some(mode);
+    }
+    if mode == OverlayPositionMode::Remembered {
+        // Start from where it is now, so switching modes doesn't make it jump.
+        if let Some(position) = app
+            .get_webview_window(MAIN_WINDOW_LABEL)
+            .and_then(|window| window.outer_position().ok())
+        {
+            save_position(&state, position).await?;
+        }
+    }
+    place_main_window(&app).await;
+    Ok(mode)
+}
+
+/// Moves the overlay to where its mode puts it; called whenever it's shown.
+#[tauri::command]
+pub async fn position_overlay(app: AppHandle) -> Result<(), AppError> {
+    crate::log_info!("sarah.command", "position_overlay invoked");
+    place_main_window(&app).await;
+    Ok(())
+}
+
+/// Places the main window according to the saved mode. Failures leave it
+/// where it is.
+pub async fn place_main_window(app: &AppHandle) {
+    let Some(window) = app.get_webview_window(MAIN_WINDOW_LABEL) else {
+        return;
+    };
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return;
+    };
+    let mode = position_mode(&state).await.unwrap_or_default();
+
+    let target = match mode {
+        OverlayPositionMode::Center => app
+            .primary_monitor()
+            .ok()
+            .flatten()
+            .and_then(|monitor| centered_on(&window, &monitor)),
+        OverlayPositionMode::FollowCursor => {
+            cursor_monitor(app).and_then(|monitor| centered_on(&window, &monitor))
+        }
+        OverlayPositionMode::Remembered => match remembered_position(app, &state).await {
+            Some(position) => Some(position),
+            None => cursor_monitor(app).and_then(|monitor| centered_on(&window, &monitor)),
+        },
+    };
+
+    if let Some(position) = target {
+        if let Err(error) = window.set_position(position) {
+            crate::log_warn!("sarah.overlay", "Failed to position overlay: {}", error);
+        }
+    }
+}
+
+/// Remembers where the overlay was dragged to, in remembered mode.
+pub fn remember_position(app: &AppHandle, position: PhysicalPosition<i32>) {
+    let remembering = cached_mode()
+        .lock()
+        .ok()
+        .and_then(|mode| *mode)
+        .is_some_and(|mode| mode == OverlayPositionMode::Remembered);
+    if !remembering {
+        return;
+    }
+
+    let generation = move_generation().fetch_add(1, Ordering::SeqCst) + 1;
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(SAVE_DEBOUNCE).await;
+        if move_generation().load(Ordering::SeqCst) != generation {
+            return;
+        }
+        let Some(state) = app.try_state::<Arc<AppState>>() else {
+            return;
+        };
+        if let Err(error) = save_position(&state, position).await {
+            crate::log_warn!(
+                "sarah.overlay",
+                "Failed to save overlay position: {}",
+                error
+            );
+        }
+    });
+}
+
+async fn position_mode(state: &AppState) -> Result<OverlayPositionMode, AppError> {
+    if let Some(mode) = cached_mode().lock().ok().and_then(|mode| *mode) {
+        return Ok(mode);
+    }
+    let mode = state
+        .settings_repo
+        .get_setting(None, OVERLAY_SETTINGS_NAMESPACE, POSITION_MODE_KEY)
+        .await?
+        .and_then(|setting| OverlayPositionMode::parse(&setting.value))
+        .unwrap_or_default();
+    if let Ok(mut cached) = cached_mode().lock() {
+        *cached = Some(mode);
+    }
+    Ok(mode)
+}
+
+async fn save_position(state: &AppState, position: PhysicalPosition<i32>) -> Result<(), AppError> {
+    let value = serde_json::to_string(&SavedPosition {
+        x: position.x,
+        y: position.y,
+    })
+    .map_err(|e| AppError::Internal(format!("Failed to encode overlay position: {e}")))?;
+    state
+        .settings_repo
+        .upsert_setting(
+            None,
+            OVERLAY_SETTINGS_NAMESPACE,
+            POSITION_KEY,
+            &value,
+            "json",
+            false,
+        )
+        .await?;
+    Ok(())
+}
+
+/// The saved spot, unless the monitor it was on has since gone away.
+async fn remembered_position(app: &AppHandle, state: &AppState) -> Option<PhysicalPosition<i32>> {
+    let saved = state
+        .settings_repo
+        .get_setting(None, OVERLAY_SETTINGS_NAMESPACE, POSITION_KEY)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|setting| serde_json::from_str::<SavedPosition>(&setting.value).ok())?;
+    app.monitor_from_point(f64::from(saved.x), f64::from(saved.y))
+        .ok()
+        .flatten()?;
+    Some(PhysicalPosition::new(saved.x, saved.y))
+}
+
+fn cursor_monitor(app: &AppHandle) -> Option<Monitor> {
+    let cursor = app.cursor_position().ok()?;
+    app.monitor_from_point(cursor.x, cursor.y)
+        .ok()
+        .flatten()
+        .or_else(|| app.primary_monitor().ok().flatten())
+}
+
+fn centered_on(window: &WebviewWindow, monitor: &Monitor) -> Option<PhysicalPosition<i32>> {
+    let size = window.outer_size().ok()?;
+    let origin = monitor.position();
+    let area = monitor.size();
+    let x = origin.x + (area.width.saturating_sub(size.width) / 2) as i32;
+    let y = origin.y + (area.height.saturating_sub(size.height) / 2) as i32;
+    Some(PhysicalPosition::new(x, y))
+}
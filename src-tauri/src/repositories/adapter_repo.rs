@@ -0,0 +1,119 @@
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::db::models::LoraAdapter;
+use crate::error::AppError;
+
+#[derive(Clone)]
+pub struct AdapterRepo {
+    read_pool: SqlitePool,
+    write_pool: SqlitePool,
+}
+
+impl AdapterRepo {
+    pub fn with_pools(read_pool: SqlitePool, write_pool: SqlitePool) -> Self {
+        Self {
+            read_pool,
+            write_pool,
+        }
+    }
+
+    /// Installs the adapter for the model, or updates its scale if it is
+    /// already installed, and marks it active.
+    pub async fn upsert_active(
+        &self,
+        model_id: &str,
+        name: &str,
+        file_path: &str,
+        scale: f64,
+    ) -> Result<LoraAdapter, AppError> {
+        sqlx::query(
+            r#"
+            INSERT INTO adapters (id, model_id, name, file_path, scale, is_active)
+            VALUES (?1, ?2, ?3, ?4, ?5, 1)
+            ON CONFLICT(model_id, file_path) DO UPDATE SET
+                scale = excluded.scale,
+                is_active = 1
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(model_id)
+        .bind(name)
+        .bind(file_path)
+        .bind(scale)
+        .execute(&self.write_pool)
+        .await?;
+
+        let row = sqlx::query_as::<_, LoraAdapter>(
+            "SELECT * FROM adapters WHERE model_id = ?1 AND file_path = ?2",
+        )
+        .bind(model_id)
+        .bind(file_path)
+        .fetch_optional(&self.read_pool)
+        .await?;
+        row.ok_or_else(|| AppError::NotFound {
+            entity: "adapter".to_string(),
+            id: file_path.to_string(),
+        })
+    }
+
+    pub async fn get_adapter(&self, id: &str) -> Result<Option<LoraAdapter>, AppError> {
+        let row = sqlx::query_as::<_, LoraAdapter>("SELECT * FROM adapters WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&self.read_pool)
+            .await?;
+        Ok(row)
+    }
+
+    pub async fn list_adapters(
+        &self,
+        model_id: Option<&str>,
+    ) -> Result<Vec<LoraAdapter>, AppError> {
+        let rows = sqlx::query_as::<_, LoraAdapter>(
+            r#"
+            SELECT * FROM adapters
+            WHERE ?1 IS NULL OR model_id = ?1
+            ORDER BY is_active DESC, name COLLATE NOCASE
+            "#,
+        )
+        .bind(model_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Active adapters of the model stored at `file_path`.
+    pub async fn active_for_model_path(
+        &self,
+        file_path: &str,
+    ) -> Result<Vec<LoraAdapter>, AppError> {
+        let rows = sqlx::query_as::<_, LoraAdapter>(
+            r#"
+            SELECT a.* FROM adapters a
+            JOIN models m ON m.id = a.model_id
+            WHERE m.file_path = ?1 AND a.is_active = 1
+            ORDER BY a.created_at
+            "#,
+        )
+        .bind(file_path)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    pub async fn set_active(&self, id: &str, active: bool) -> Result<(), AppError> {
+        let result = sqlx::query("UPDATE adapters SET is_active = ?2 WHERE id = ?1")
+            .bind(id)
+            .bind(active as i64)
+            .execute(&self.write_pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "adapter".to_string(),
+                id: id.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 use sqlx::SqlitePool;
 use uuid::Uuid;
 
-use crate::db::models::{ModelRecommendation, PerfLog};
+use crate::db::models::{ModelRecommendation, ModelUsageSignal, PerfLog};
 use crate::error::AppError;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -21,6 +21,7 @@ pub struct NewPerfLog {
     pub success: bool,
     pub error_code: Option<String>,
     pub metadata: Option<String>,
+    pub first_token_ms: Option<i64>,
 }
 
 #[derive(Clone)]
@@ -50,8 +51,8 @@ impl AnalyticsRepo {
             INSERT INTO perf_logs (
               id, event_type, session_id, model_id, mcp_id, latency_ms,
               tokens_in, tokens_out, tokens_per_sec, cpu_usage_pct, ram_usage_mb,
-              gpu_usage_pct, success, error_code, metadata
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+              gpu_usage_pct, success, error_code, metadata, first_token_ms
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
             "#,
         )
         .bind(Uuid::new_v4().to_string())
@@ -69,6 +70,7 @@ impl AnalyticsRepo {
         .bind(if entry.success { 1 } else { 0 })
         .bind(&entry.error_code)
         .bind(&entry.metadata)
+        .bind(entry.first_token_ms)
         .execute(&self.write_pool)
         .await?;
 
@@ -161,6 +163,43 @@ impl AnalyticsRepo {
         Ok(rows)
     }
 
+    pub async fn list_all_recommendations(&self) -> Result<Vec<ModelRecommendation>, AppError> {
+        let rows = sqlx::query_as::<_, ModelRecommendation>(
+            "SELECT * FROM model_recommendations ORDER BY system_profile_id, score DESC",
+        )
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    pub async fn get_model_usage_signals(
+        &self,
+        days: i64,
+    ) -> Result<Vec<ModelUsageSignal>, AppError> {
+        let rows = sqlx::query_as::<_, ModelUsageSignal>(
+            r#"
+            SELECT
+              model_id,
+              AVG(tokens_per_sec) AS avg_tokens_per_sec,
+              COUNT(tokens_per_sec) AS sample_count,
+              SUM(CASE WHEN json_extract(metadata, '$.feedback') > 0 THEN 1 ELSE 0 END) AS positive_feedback,
+              SUM(CASE WHEN json_extract(metadata, '$.feedback') < 0 THEN 1 ELSE 0 END) AS negative_feedback
+            FROM messages
+            WHERE role = 'assistant'
+              AND model_id IS NOT NULL
+              AND is_error = 0
+              AND datetime(created_at) >= datetime('now', '-' || ?1 || ' day')
+            GROUP BY model_id
+            "#,
+        )
+        .bind(days)
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        Ok(rows)
+    }
+
     pub async fn prune_old_perf_logs(&self, days: i64) -> Result<u64, AppError> {
         let result = sqlx::query(
             "DELETE FROM perf_logs WHERE datetime(created_at) < datetime('now', '-' || ?1 || ' day')",
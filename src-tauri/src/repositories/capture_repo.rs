@@ -0,0 +1,100 @@
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::db::models::{Capture, NewCapture};
+use crate::error::AppError;
+
+#[derive(Clone)]
+pub struct CaptureRepo {
+    read_pool: SqlitePool,
+    write_pool: SqlitePool,
+}
+
+impl CaptureRepo {
+    pub fn with_pools(read_pool: SqlitePool, write_pool: SqlitePool) -> Self {
+        Self {
+            read_pool,
+            write_pool,
+        }
+    }
+
+    pub async fn insert_capture(
+        &self,
+        capture: &NewCapture,
+        file_size_bytes: Option<i64>,
+    ) -> Result<Capture, AppError> {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO captures (
+              id, session_id, capture_type, file_path, mime_type, thumbnail_path,
+              duration_ms, width, height, file_size_bytes
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            "#,
+        )
+        .bind(&id)
+        .bind(&capture.session_id)
+        .bind(&capture.capture_type)
+        .bind(&capture.file_path)
+        .bind(&capture.mime_type)
+        .bind(&capture.thumbnail_path)
+        .bind(capture.duration_ms)
+        .bind(capture.width)
+        .bind(capture.height)
+        .bind(file_size_bytes)
+        .execute(&self.write_pool)
+        .await?;
+
+        let row = sqlx::query_as::<_, Capture>("SELECT * FROM captures WHERE id = ?1")
+            .bind(&id)
+            .fetch_one(&self.write_pool)
+            .await?;
+        Ok(row)
+    }
+
+    /// Newest first; filters left out match everything.
+    pub async fn list_captures(
+        &self,
+        capture_type: Option<&str>,
+        session_id: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Capture>, AppError> {
+        let rows = sqlx::query_as::<_, Capture>(
+            r#"
+            SELECT * FROM captures
+            WHERE (?1 IS NULL OR capture_type = ?1)
+              AND (?2 IS NULL OR session_id = ?2)
+            ORDER BY created_at DESC, rowid DESC
+            LIMIT ?3 OFFSET ?4
+            "#,
+        )
+        .bind(capture_type)
+        .bind(session_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    pub async fn get_capture(&self, id: &str) -> Result<Capture, AppError> {
+        sqlx::query_as::<_, Capture>("SELECT * FROM captures WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&self.read_pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "capture".to_string(),
+                id: id.to_string(),
+            })
+    }
+
+    pub async fn delete_capture(&self, id: &str) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM captures WHERE id = ?1")
+            .bind(id)
+            .execute(&self.write_pool)
+            .await?;
+        Ok(())
+    }
+}
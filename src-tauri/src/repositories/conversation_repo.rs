@@ -1,7 +1,10 @@
-use sqlx::SqlitePool;
+use sqlx::{QueryBuilder, SqlitePool};
 use uuid::Uuid;
 
-use crate::db::models::{Message, MessageSearchResult, NewMessage, NewToolCall, Session, ToolCall};
+use crate::db::models::{
+    Message, MessageSearchResult, MessageToIndex, NewMessage, NewToolCall, PinContextItem,
+    PinnedContextItem, Session, SessionFilter, SessionRagSettings, SessionStorage, ToolCall,
+};
 use crate::error::AppError;
 
 #[derive(Clone)]
@@ -48,6 +51,213 @@ impl ConversationRepo {
             })
     }
 
+    /// Creates a session tagged with the external id it was imported from.
+    pub async fn create_imported_session(
+        &self,
+        user_id: &str,
+        title: Option<&str>,
+        import_key: &str,
+        source: &str,
+    ) -> Result<Session, AppError> {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO sessions (id, user_id, title, status, tags, metadata)
+            VALUES (
+              ?1, ?2, ?3, 'active', json_array('imported', ?5),
+              json_object('importKey', ?4, 'importSource', ?5)
+            )
+            "#,
+        )
+        .bind(&id)
+        .bind(user_id)
+        .bind(title)
+        .bind(import_key)
+        .bind(source)
+        .execute(&self.write_pool)
+        .await?;
+
+        self.get_session(&id)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "session".to_string(),
+                id,
+            })
+    }
+
+    /// Puts back the original times of an imported conversation; rows are
+    /// stamped with the import time when inserted. `message_times` pairs
+    /// message ids with SQLite-format timestamps.
+    pub async fn restore_import_timestamps(
+        &self,
+        session_id: &str,
+        created_at: Option<&str>,
+        message_times: &[(String, String)],
+    ) -> Result<(), AppError> {
+        let mut tx = self.write_pool.begin().await?;
+        for (message_id, timestamp) in message_times {
+            sqlx::query("UPDATE messages SET created_at = ?1, updated_at = ?1 WHERE id = ?2")
+                .bind(timestamp)
+                .bind(message_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE sessions
+            SET created_at = COALESCE(
+                  ?1,
+                  (SELECT MIN(created_at) FROM messages WHERE session_id = ?2),
+                  created_at
+                ),
+                last_message_at = COALESCE(
+                  (SELECT MAX(created_at) FROM messages WHERE session_id = ?2),
+                  last_message_at
+                )
+            WHERE id = ?2
+            "#,
+        )
+        .bind(created_at)
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn find_session_by_import_key(
+        &self,
+        import_key: &str,
+    ) -> Result<Option<Session>, AppError> {
+        let row = sqlx::query_as::<_, Session>(
+            "SELECT * FROM sessions WHERE json_extract(metadata, '$.importKey') = ?1 LIMIT 1",
+        )
+        .bind(import_key)
+        .fetch_optional(&self.read_pool)
+        .await?;
+        Ok(row)
+    }
+
+    /// Starts a new session holding a copy of the conversation up to and
+    /// including `message_id`, linked back to where it was forked. Only the
+    /// active variant of each turn is copied.
+    pub async fn fork_session(
+        &self,
+        session_id: &str,
+        message_id: &str,
+    ) -> Result<Session, AppError> {
+        let source = self
+            .get_session(session_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "session".to_string(),
+                id: session_id.to_string(),
+            })?;
+        let fork_point = self
+            .get_message_by_id(message_id)
+            .await?
+            .filter(|message| message.session_id == session_id)
+            .ok_or_else(|| AppError::NotFound {
+                entity: "message".to_string(),
+                id: message_id.to_string(),
+            })?;
+        let messages = sqlx::query_as::<_, Message>(
+            r#"
+            SELECT * FROM messages
+            WHERE session_id = ?1 AND position <= ?2 AND is_active_variant = 1
+            ORDER BY position ASC
+            "#,
+        )
+        .bind(session_id)
+        .bind(fork_point.position)
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        let id = Uuid::new_v4().to_string();
+        let title = source
+            .title
+            .as_deref()
+            .map(|title| format!("{title} (fork)"));
+        let mut tx = self.write_pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO sessions (
+              id, user_id, title, model_id, system_prompt, status, tags, workspace_id,
+              persona_id, metadata, forked_from_session_id, forked_at_message_id
+            )
+            SELECT ?1, user_id, ?2, model_id, system_prompt, 'active', tags, workspace_id,
+                   persona_id, json_remove(metadata, '$.importKey'), id, ?3
+            FROM sessions WHERE id = ?4
+            "#,
+        )
+        .bind(&id)
+        .bind(title)
+        .bind(message_id)
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await?;
+
+        for message in &messages {
+            sqlx::query(
+                r#"
+                INSERT INTO messages (
+                  id, session_id, role, content, content_type, thinking, token_count, model_id,
+                  latency_ms, tokens_per_sec, finish_reason, is_error, error_message, edited_at,
+                  original_content, metadata, attachments, position, created_at, updated_at
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16,
+                          ?17, ?18, ?19, ?20)
+                "#,
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(&id)
+            .bind(&message.role)
+            .bind(&message.content)
+            .bind(&message.content_type)
+            .bind(&message.thinking)
+            .bind(message.token_count)
+            .bind(&message.model_id)
+            .bind(message.latency_ms)
+            .bind(message.tokens_per_sec)
+            .bind(&message.finish_reason)
+            .bind(message.is_error)
+            .bind(&message.error_message)
+            .bind(&message.edited_at)
+            .bind(&message.original_content)
+            .bind(&message.metadata)
+            .bind(&message.attachments)
+            .bind(message.position)
+            .bind(&message.created_at)
+            .bind(&message.updated_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE sessions
+            SET message_count = ?1,
+                token_count = ?2,
+                last_message_at = datetime('now','utc')
+            WHERE id = ?3
+            "#,
+        )
+        .bind(messages.len() as i64)
+        .bind(messages.iter().filter_map(|m| m.token_count).sum::<i64>())
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        self.get_session(&id)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "session".to_string(),
+                id,
+            })
+    }
+
     pub async fn get_session(&self, id: &str) -> Result<Option<Session>, AppError> {
         let row = sqlx::query_as::<_, Session>("SELECT * FROM sessions WHERE id = ?1")
             .bind(id)
@@ -56,71 +266,649 @@ impl ConversationRepo {
         Ok(row)
     }
 
-    pub async fn list_sessions(
+    /// Sessions matching `filter`, pinned ones first, then by latest activity.
+    /// `cursor` is the id of the last session of the previous page.
+    pub async fn list_sessions(
+        &self,
+        user_id: &str,
+        filter: &SessionFilter,
+        limit: i64,
+        cursor: Option<&str>,
+    ) -> Result<Vec<Session>, AppError> {
+        let mut builder = QueryBuilder::new("SELECT * FROM sessions s WHERE s.user_id = ");
+        builder.push_bind(user_id);
+        match filter.status.as_deref() {
+            Some(status) => builder.push(" AND s.status = ").push_bind(status),
+            None => builder.push(" AND s.status != 'deleted'"),
+        };
+        if filter.pinned_only {
+            builder.push(" AND s.pinned = 1");
+        }
+        for tag in &filter.tags {
+            builder
+                .push(" AND EXISTS (SELECT 1 FROM json_each(s.tags) WHERE value = ")
+                .push_bind(tag)
+                .push(")");
+        }
+        if let Some(cursor_id) = cursor {
+            builder
+                .push(
+                    r#"
+                    AND (s.pinned, COALESCE(s.last_message_at, s.created_at), s.id) < (
+                      SELECT c.pinned, COALESCE(c.last_message_at, c.created_at), c.id
+                      FROM sessions c WHERE c.id = "#,
+                )
+                .push_bind(cursor_id)
+                .push(")");
+        }
+        builder
+            .push(
+                r#"
+                ORDER BY s.pinned DESC, COALESCE(s.last_message_at, s.created_at) DESC, s.id DESC
+                LIMIT "#,
+            )
+            .push_bind(limit);
+
+        let rows = builder
+            .build_query_as::<Session>()
+            .fetch_all(&self.read_pool)
+            .await?;
+        Ok(rows)
+    }
+
+    pub async fn set_session_tags(&self, id: &str, tags: &[String]) -> Result<(), AppError> {
+        let tags_json = serde_json::to_string(tags)
+            .map_err(|e| AppError::Internal(format!("Failed to encode tags: {e}")))?;
+        let result = sqlx::query("UPDATE sessions SET tags = ?1 WHERE id = ?2")
+            .bind(tags_json)
+            .bind(id)
+            .execute(&self.write_pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "session".to_string(),
+                id: id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Flips the session's pin and returns the new state.
+    pub async fn toggle_session_pin(&self, id: &str) -> Result<bool, AppError> {
+        let pinned = sqlx::query_scalar::<_, i64>(
+            "UPDATE sessions SET pinned = 1 - pinned WHERE id = ?1 RETURNING pinned",
+        )
+        .bind(id)
+        .fetch_optional(&self.write_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound {
+            entity: "session".to_string(),
+            id: id.to_string(),
+        })?;
+        Ok(pinned == 1)
+    }
+
+    pub async fn update_session_title(&self, id: &str, title: &str) -> Result<(), AppError> {
+        sqlx::query("UPDATE sessions SET title = ?1 WHERE id = ?2")
+            .bind(title)
+            .bind(id)
+            .execute(&self.write_pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Pins a generation preset on the session, or clears it when `preset` is `None`.
+    pub async fn set_session_preset(
+        &self,
+        session_id: &str,
+        preset: Option<&str>,
+    ) -> Result<(), AppError> {
+        let result = match preset {
+            Some(name) => {
+                sqlx::query(
+                    "UPDATE sessions SET metadata = json_set(metadata, '$.generationPreset', ?1) WHERE id = ?2",
+                )
+                .bind(name)
+                .bind(session_id)
+                .execute(&self.write_pool)
+                .await?
+            }
+            None => {
+                sqlx::query(
+                    "UPDATE sessions SET metadata = json_remove(metadata, '$.generationPreset') WHERE id = ?1",
+                )
+                .bind(session_id)
+                .execute(&self.write_pool)
+                .await?
+            }
+        };
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "session".to_string(),
+                id: session_id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Moves the session into a workspace, or out of any when `workspace_id` is `None`.
+    pub async fn set_session_workspace(
+        &self,
+        session_id: &str,
+        workspace_id: Option<&str>,
+    ) -> Result<(), AppError> {
+        let result = sqlx::query("UPDATE sessions SET workspace_id = ?1 WHERE id = ?2")
+            .bind(workspace_id)
+            .bind(session_id)
+            .execute(&self.write_pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "session".to_string(),
+                id: session_id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    pub async fn get_session_preset(&self, session_id: &str) -> Result<Option<String>, AppError> {
+        let preset = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT json_extract(metadata, '$.generationPreset') FROM sessions WHERE id = ?1",
+        )
+        .bind(session_id)
+        .fetch_optional(&self.read_pool)
+        .await?;
+        Ok(preset.flatten())
+    }
+
+    pub async fn get_session_rag_settings(
+        &self,
+        session_id: &str,
+    ) -> Result<SessionRagSettings, AppError> {
+        let raw = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT json_extract(metadata, '$.rag') FROM sessions WHERE id = ?1",
+        )
+        .bind(session_id)
+        .fetch_optional(&self.read_pool)
+        .await?
+        .flatten();
+
+        Ok(raw
+            .and_then(|value| serde_json::from_str::<SessionRagSettings>(&value).ok())
+            .unwrap_or_default())
+    }
+
+    pub async fn set_session_rag_settings(
+        &self,
+        session_id: &str,
+        settings: &SessionRagSettings,
+    ) -> Result<(), AppError> {
+        let encoded = serde_json::to_string(settings).unwrap_or_else(|_| "{}".to_string());
+        let result = sqlx::query(
+            "UPDATE sessions SET metadata = json_set(metadata, '$.rag', json(?1)) WHERE id = ?2",
+        )
+        .bind(encoded)
+        .bind(session_id)
+        .execute(&self.write_pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "session".to_string(),
+                id: session_id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Whether the user's own instructions apply in this session, stored under
+    /// `$.useUserInstructions` in the session metadata. On unless turned off.
+    pub async fn session_uses_user_instructions(&self, session_id: &str) -> Result<bool, AppError> {
+        let flag = sqlx::query_scalar::<_, Option<i64>>(
+            "SELECT json_extract(metadata, '$.useUserInstructions') FROM sessions WHERE id = ?1",
+        )
+        .bind(session_id)
+        .fetch_optional(&self.read_pool)
+        .await?
+        .flatten();
+        Ok(!matches!(flag, Some(0)))
+    }
+
+    pub async fn set_session_uses_user_instructions(
+        &self,
+        session_id: &str,
+        enabled: bool,
+    ) -> Result<(), AppError> {
+        let result = sqlx::query(
+            "UPDATE sessions SET metadata = json_set(metadata, '$.useUserInstructions', json(?1)) WHERE id = ?2",
+        )
+        .bind(if enabled { "true" } else { "false" })
+        .bind(session_id)
+        .execute(&self.write_pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "session".to_string(),
+                id: session_id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    pub async fn insert_context_pin(
+        &self,
+        session_id: &str,
+        item: &PinContextItem,
+        label: &str,
+    ) -> Result<PinnedContextItem, AppError> {
+        let id = Uuid::new_v4().to_string();
+        let (kind, document_id, chunk_id, note) = match item {
+            PinContextItem::Document { document_id } => {
+                ("document", Some(document_id.as_str()), None, None)
+            }
+            PinContextItem::Chunk { chunk_id } => ("chunk", None, Some(chunk_id.as_str()), None),
+            PinContextItem::Note { text, .. } => ("note", None, None, Some(text.trim())),
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO session_context_pins (
+              id, session_id, kind, document_id, chunk_id, note, label
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+        )
+        .bind(&id)
+        .bind(session_id)
+        .bind(kind)
+        .bind(document_id)
+        .bind(chunk_id)
+        .bind(note)
+        .bind(label)
+        .execute(&self.write_pool)
+        .await?;
+
+        sqlx::query_as::<_, PinnedContextItem>("SELECT * FROM session_context_pins WHERE id = ?1")
+            .bind(&id)
+            .fetch_optional(&self.read_pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "context_pin".to_string(),
+                id,
+            })
+    }
+
+    /// Pins in the order they were added, which is the order they enter the prompt.
+    pub async fn list_context_pins(
+        &self,
+        session_id: &str,
+    ) -> Result<Vec<PinnedContextItem>, AppError> {
+        let rows = sqlx::query_as::<_, PinnedContextItem>(
+            "SELECT * FROM session_context_pins WHERE session_id = ?1 ORDER BY created_at, rowid",
+        )
+        .bind(session_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    pub async fn delete_context_pin(&self, session_id: &str, pin_id: &str) -> Result<(), AppError> {
+        let result =
+            sqlx::query("DELETE FROM session_context_pins WHERE id = ?1 AND session_id = ?2")
+                .bind(pin_id)
+                .bind(session_id)
+                .execute(&self.write_pool)
+                .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "context_pin".to_string(),
+                id: pin_id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Moves a session between `active` and `archived`; deleted sessions stay deleted.
+    pub async fn set_session_archived(&self, id: &str, archived: bool) -> Result<(), AppError> {
+        let status = if archived { "archived" } else { "active" };
+        let result =
+            sqlx::query("UPDATE sessions SET status = ?1 WHERE id = ?2 AND status != 'deleted'")
+                .bind(status)
+                .bind(id)
+                .execute(&self.write_pool)
+                .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "session".to_string(),
+                id: id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Every session with its size, least recently active first. The size counts
+    /// message text and metadata, tool call input and output, and the
+    /// messages' embeddings: what `purge_sessions` deletes.
+    pub async fn list_session_storage(&self) -> Result<Vec<SessionStorage>, AppError> {
+        let rows = sqlx::query_as::<_, SessionStorage>(
+            r#"
+            SELECT s.id, s.title, s.status, s.pinned,
+                   COALESCE(s.last_message_at, s.created_at) AS last_activity_at,
+                   (SELECT COUNT(*) FROM messages m WHERE m.session_id = s.id) AS message_count,
+                   COALESCE(
+                     (SELECT SUM(length(m.content) + length(m.metadata))
+                      FROM messages m WHERE m.session_id = s.id),
+                     0
+                   )
+                   + COALESCE(
+                     (SELECT SUM(length(t.tool_input) + COALESCE(length(t.tool_output), 0))
+                      FROM tool_calls t WHERE t.session_id = s.id),
+                     0
+                   )
+                   + COALESCE(
+                     (SELECT SUM(length(e.vector))
+                      FROM embeddings e
+                      JOIN messages m ON m.id = e.entity_id
+                      WHERE e.entity_type = 'message' AND m.session_id = s.id),
+                     0
+                   ) AS approx_bytes
+            FROM sessions s
+            ORDER BY last_activity_at ASC
+            "#,
+        )
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Bytes in use by the database file, not counting free pages.
+    pub async fn database_size_bytes(&self) -> Result<i64, AppError> {
+        let size = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT (p.page_count - f.freelist_count) * s.page_size
+            FROM pragma_page_count() p, pragma_freelist_count() f, pragma_page_size() s
+            "#,
+        )
+        .fetch_one(&self.read_pool)
+        .await?;
+        Ok(size)
+    }
+
+    /// Permanently deletes sessions with their messages, tool calls and message
+    /// embeddings. Rows elsewhere that point at them are unlinked, not deleted.
+    /// Returns the number of messages removed.
+    pub async fn purge_sessions(&self, ids: &[String]) -> Result<i64, AppError> {
+        let mut tx = self.write_pool.begin().await?;
+        let mut messages_removed = 0;
+        for id in ids {
+            sqlx::query(
+                r#"
+                DELETE FROM embeddings
+                WHERE entity_type = 'message'
+                  AND entity_id IN (SELECT id FROM messages WHERE session_id = ?1)
+                "#,
+            )
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query(
+                r#"
+                UPDATE sessions
+                SET forked_from_session_id = NULL, forked_at_message_id = NULL
+                WHERE forked_from_session_id = ?1
+                "#,
+            )
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query("UPDATE memories SET session_id = NULL WHERE session_id = ?1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("UPDATE rag_retrievals SET session_id = NULL WHERE session_id = ?1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+
+            messages_removed += sqlx::query("DELETE FROM messages WHERE session_id = ?1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected() as i64;
+            sqlx::query("DELETE FROM sessions WHERE id = ?1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(messages_removed)
+    }
+
+    /// Rewrites the database file so space freed by deletions is returned to the OS.
+    pub async fn vacuum(&self) -> Result<(), AppError> {
+        sqlx::query("VACUUM").execute(&self.write_pool).await?;
+        Ok(())
+    }
+
+    pub async fn insert_message(&self, msg: NewMessage) -> Result<Message, AppError> {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO messages (
+              id, session_id, role, content, content_type, token_count, model_id, metadata,
+              attachments, position
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            "#,
+        )
+        .bind(&id)
+        .bind(&msg.session_id)
+        .bind(&msg.role)
+        .bind(&msg.content)
+        .bind(&msg.content_type)
+        .bind(msg.token_count)
+        .bind(&msg.model_id)
+        .bind(&msg.metadata)
+        .bind(&msg.attachments)
+        .bind(msg.position)
+        .execute(&self.write_pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE sessions
+            SET message_count = message_count + 1,
+                token_count = token_count + COALESCE(?1, 0),
+                last_message_at = datetime('now','utc')
+            WHERE id = ?2
+            "#,
+        )
+        .bind(msg.token_count)
+        .bind(&msg.session_id)
+        .execute(&self.write_pool)
+        .await?;
+
+        self.get_message_by_id(&id)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "message".to_string(),
+                id,
+            })
+    }
+
+    pub async fn get_message_by_id(&self, id: &str) -> Result<Option<Message>, AppError> {
+        let row = sqlx::query_as::<_, Message>("SELECT * FROM messages WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&self.read_pool)
+            .await?;
+        Ok(row)
+    }
+
+    pub async fn get_messages(
+        &self,
+        session_id: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Message>, AppError> {
+        let rows = sqlx::query_as::<_, Message>(
+            r#"
+            SELECT * FROM messages
+            WHERE session_id = ?1 AND is_active_variant = 1
+            ORDER BY position ASC
+            LIMIT ?2 OFFSET ?3
+            "#,
+        )
+        .bind(session_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Every message of the session, including replaced variants and turns
+    /// abandoned by an edit, in conversation order.
+    pub async fn get_message_tree(&self, session_id: &str) -> Result<Vec<Message>, AppError> {
+        let rows = sqlx::query_as::<_, Message>(
+            r#"
+            SELECT * FROM messages
+            WHERE session_id = ?1
+            ORDER BY position ASC, datetime(created_at) ASC
+            "#,
+        )
+        .bind(session_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    pub async fn get_context_window(
+        &self,
+        session_id: &str,
+        max_tokens: i64,
+    ) -> Result<Vec<Message>, AppError> {
+        let mut rows = sqlx::query_as::<_, Message>(
+            r#"
+            SELECT * FROM messages
+            WHERE session_id = ?1 AND is_active_variant = 1
+            ORDER BY position DESC
+            "#,
+        )
+        .bind(session_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        let mut running_tokens: i64 = 0;
+        let mut selected = Vec::new();
+
+        for message in rows.drain(..) {
+            let tokens = message
+                .token_count
+                .unwrap_or((message.content.len() / 4) as i64 + 1);
+            if running_tokens + tokens > max_tokens {
+                break;
+            }
+            running_tokens += tokens;
+            selected.push(message);
+        }
+
+        selected.reverse();
+        Ok(selected)
+    }
+
+    /// The latest user message before `position`: the turn an assistant reply
+    /// at that position answers.
+    pub async fn prompt_before(
+        &self,
+        session_id: &str,
+        position: i64,
+    ) -> Result<Option<Message>, AppError> {
+        let row = sqlx::query_as::<_, Message>(
+            r#"
+            SELECT * FROM messages
+            WHERE session_id = ?1 AND role = 'user' AND position < ?2 AND is_active_variant = 1
+            ORDER BY position DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(session_id)
+        .bind(position)
+        .fetch_optional(&self.read_pool)
+        .await?;
+        Ok(row)
+    }
+
+    /// The active assistant reply to the user message at `position`, if the
+    /// turn was answered before the next user message.
+    pub async fn reply_after(
         &self,
-        user_id: &str,
-        limit: i64,
-        cursor: Option<&str>,
-    ) -> Result<Vec<Session>, AppError> {
-        let rows = if let Some(cursor_id) = cursor {
-            sqlx::query_as::<_, Session>(
-                r#"
-                SELECT * FROM sessions
-                WHERE user_id = ?1 AND status != 'deleted'
-                  AND datetime(created_at) < (
-                    SELECT datetime(created_at) FROM sessions WHERE id = ?2
-                  )
-                ORDER BY datetime(last_message_at) DESC, datetime(created_at) DESC
-                LIMIT ?3
-                "#,
-            )
-            .bind(user_id)
-            .bind(cursor_id)
-            .bind(limit)
-            .fetch_all(&self.read_pool)
-            .await?
-        } else {
-            sqlx::query_as::<_, Session>(
+        session_id: &str,
+        position: i64,
+    ) -> Result<Option<Message>, AppError> {
+        let row = sqlx::query_as::<_, Message>(
+            r#"
+            SELECT * FROM messages
+            WHERE session_id = ?1
+              AND role = 'assistant'
+              AND is_active_variant = 1
+              AND position > ?2
+              AND NOT EXISTS (
+                SELECT 1 FROM messages u
+                WHERE u.session_id = ?1
+                  AND u.role = 'user'
+                  AND u.position > ?2
+                  AND u.position < messages.position
+              )
+            ORDER BY position ASC
+            LIMIT 1
+            "#,
+        )
+        .bind(session_id)
+        .bind(position)
+        .fetch_optional(&self.read_pool)
+        .await?;
+        Ok(row)
+    }
+
+    /// Stores `msg` as another reply to `parent_message_id` and makes it the
+    /// active variant. `replaces` is the reply shown until now; it is adopted
+    /// into the variants if it predates them.
+    pub async fn insert_variant(
+        &self,
+        msg: NewMessage,
+        parent_message_id: &str,
+        replaces: Option<&str>,
+    ) -> Result<Message, AppError> {
+        let id = Uuid::new_v4().to_string();
+        let mut tx = self.write_pool.begin().await?;
+
+        if let Some(replaced_id) = replaces {
+            sqlx::query(
                 r#"
-                SELECT * FROM sessions
-                WHERE user_id = ?1 AND status != 'deleted'
-                ORDER BY datetime(last_message_at) DESC, datetime(created_at) DESC
-                LIMIT ?2
+                UPDATE messages SET parent_message_id = ?1
+                WHERE id = ?2 AND parent_message_id IS NULL
                 "#,
             )
-            .bind(user_id)
-            .bind(limit)
-            .fetch_all(&self.read_pool)
-            .await?
-        };
-
-        Ok(rows)
-    }
-
-    pub async fn update_session_title(&self, id: &str, title: &str) -> Result<(), AppError> {
-        sqlx::query("UPDATE sessions SET title = ?1 WHERE id = ?2")
-            .bind(title)
-            .bind(id)
-            .execute(&self.write_pool)
+            .bind(parent_message_id)
+            .bind(replaced_id)
+            .execute(&mut *tx)
             .await?;
-        Ok(())
-    }
-
-    pub async fn archive_session(&self, id: &str) -> Result<(), AppError> {
-        sqlx::query("UPDATE sessions SET status = 'archived' WHERE id = ?1")
-            .bind(id)
-            .execute(&self.write_pool)
+        }
+        sqlx::query("UPDATE messages SET is_active_variant = 0 WHERE parent_message_id = ?1")
+            .bind(parent_message_id)
+            .execute(&mut *tx)
             .await?;
-        Ok(())
-    }
 
-    pub async fn insert_message(&self, msg: NewMessage) -> Result<Message, AppError> {
-        let id = Uuid::new_v4().to_string();
         sqlx::query(
             r#"
             INSERT INTO messages (
-              id, session_id, role, content, content_type, token_count, model_id, metadata, position
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+              id, session_id, role, content, content_type, token_count, model_id, metadata,
+              attachments, position, parent_message_id
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
             "#,
         )
         .bind(&id)
@@ -131,23 +919,25 @@ impl ConversationRepo {
         .bind(msg.token_count)
         .bind(&msg.model_id)
         .bind(&msg.metadata)
+        .bind(&msg.attachments)
         .bind(msg.position)
-        .execute(&self.write_pool)
+        .bind(parent_message_id)
+        .execute(&mut *tx)
         .await?;
 
         sqlx::query(
             r#"
             UPDATE sessions
-            SET message_count = message_count + 1,
-                token_count = token_count + COALESCE(?1, 0),
+            SET token_count = token_count + COALESCE(?1, 0),
                 last_message_at = datetime('now','utc')
             WHERE id = ?2
             "#,
         )
         .bind(msg.token_count)
         .bind(&msg.session_id)
-        .execute(&self.write_pool)
+        .execute(&mut *tx)
         .await?;
+        tx.commit().await?;
 
         self.get_message_by_id(&id)
             .await?
@@ -157,60 +947,158 @@ impl ConversationRepo {
             })
     }
 
-    pub async fn get_message_by_id(&self, id: &str) -> Result<Option<Message>, AppError> {
-        let row = sqlx::query_as::<_, Message>("SELECT * FROM messages WHERE id = ?1")
-            .bind(id)
-            .fetch_optional(&self.read_pool)
+    /// Replaces a message's content, keeping the first version in
+    /// `original_content`. Everything after it belonged to the old wording, so
+    /// those messages leave the conversation (they are kept, tagged with
+    /// `supersededByEdit`) and a rolling summary that covered them is dropped.
+    pub async fn edit_message(&self, message_id: &str, content: &str) -> Result<Message, AppError> {
+        let mut tx = self.write_pool.begin().await?;
+        let target = sqlx::query_as::<_, (String, i64)>(
+            "SELECT session_id, position FROM messages WHERE id = ?1",
+        )
+        .bind(message_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some((session_id, position)) = target else {
+            return Err(AppError::NotFound {
+                entity: "message".to_string(),
+                id: message_id.to_string(),
+            });
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE messages
+            SET original_content = COALESCE(original_content, content),
+                content = ?2,
+                token_count = ?3,
+                edited_at = datetime('now','utc')
+            WHERE id = ?1
+            "#,
+        )
+        .bind(message_id)
+        .bind(content)
+        .bind((content.len() / 4) as i64 + 1)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE messages
+            SET is_active_variant = 0,
+                metadata = json_set(metadata, '$.supersededByEdit', ?3)
+            WHERE session_id = ?1 AND position > ?2 AND is_active_variant = 1
+            "#,
+        )
+        .bind(&session_id)
+        .bind(position)
+        .bind(message_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE sessions
+            SET context_summary = NULL, context_summary_position = NULL
+            WHERE id = ?1 AND context_summary_position >= ?2
+            "#,
+        )
+        .bind(&session_id)
+        .bind(position)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        self.get_message_by_id(message_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "message".to_string(),
+                id: message_id.to_string(),
+            })
+    }
+
+    /// Every reply to `parent_message_id`, oldest first.
+    pub async fn list_variants(&self, parent_message_id: &str) -> Result<Vec<Message>, AppError> {
+        let rows = sqlx::query_as::<_, Message>(
+            r#"
+            SELECT * FROM messages
+            WHERE parent_message_id = ?1
+            ORDER BY datetime(created_at), rowid
+            "#,
+        )
+        .bind(parent_message_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Makes `message_id` the active reply among its variants.
+    pub async fn select_variant(&self, message_id: &str) -> Result<(), AppError> {
+        let mut tx = self.write_pool.begin().await?;
+        sqlx::query(
+            r#"
+            UPDATE messages SET is_active_variant = 0
+            WHERE parent_message_id = (SELECT parent_message_id FROM messages WHERE id = ?1)
+            "#,
+        )
+        .bind(message_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let result = sqlx::query("UPDATE messages SET is_active_variant = 1 WHERE id = ?1")
+            .bind(message_id)
+            .execute(&mut *tx)
             .await?;
-        Ok(row)
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "message".to_string(),
+                id: message_id.to_string(),
+            });
+        }
+        tx.commit().await?;
+        Ok(())
     }
 
-    pub async fn get_messages(
+    /// The latest `limit` messages after `position`, oldest first.
+    pub async fn get_messages_after(
         &self,
         session_id: &str,
+        position: i64,
         limit: i64,
-        offset: i64,
     ) -> Result<Vec<Message>, AppError> {
-        let rows = sqlx::query_as::<_, Message>(
-            "SELECT * FROM messages WHERE session_id = ?1 ORDER BY position ASC LIMIT ?2 OFFSET ?3",
+        let mut rows = sqlx::query_as::<_, Message>(
+            r#"
+            SELECT * FROM messages
+            WHERE session_id = ?1 AND position > ?2 AND is_active_variant = 1
+            ORDER BY position DESC
+            LIMIT ?3
+            "#,
         )
         .bind(session_id)
+        .bind(position)
         .bind(limit)
-        .bind(offset)
         .fetch_all(&self.read_pool)
         .await?;
 
+        rows.reverse();
         Ok(rows)
     }
 
-    pub async fn get_context_window(
+    pub async fn update_context_summary(
         &self,
         session_id: &str,
-        max_tokens: i64,
-    ) -> Result<Vec<Message>, AppError> {
-        let mut rows = sqlx::query_as::<_, Message>(
-            "SELECT * FROM messages WHERE session_id = ?1 ORDER BY position DESC",
+        summary: &str,
+        through_position: i64,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            "UPDATE sessions SET context_summary = ?1, context_summary_position = ?2 WHERE id = ?3",
         )
+        .bind(summary)
+        .bind(through_position)
         .bind(session_id)
-        .fetch_all(&self.read_pool)
+        .execute(&self.write_pool)
         .await?;
-
-        let mut running_tokens: i64 = 0;
-        let mut selected = Vec::new();
-
-        for message in rows.drain(..) {
-            let tokens = message
-                .token_count
-                .unwrap_or((message.content.len() / 4) as i64 + 1);
-            if running_tokens + tokens > max_tokens {
-                break;
-            }
-            running_tokens += tokens;
-            selected.push(message);
-        }
-
-        selected.reverse();
-        Ok(selected)
+        Ok(())
     }
 
     pub async fn update_session_summary(
@@ -218,7 +1106,9 @@ impl ConversationRepo {
         session_id: &str,
         summary: &str,
     ) -> Result<(), AppError> {
-        sqlx::query("UPDATE sessions SET summary = ?1 WHERE id = ?2")
+        sqlx::query(
+            "UPDATE sessions SET summary = ?1, summarized_at = datetime('now','utc') WHERE id = ?2",
+        )
             .bind(summary)
             .bind(session_id)
             .execute(&self.write_pool)
@@ -226,6 +1116,157 @@ impl ConversationRepo {
         Ok(())
     }
 
+    /// Active sessions that gained messages since their last summary, have been
+    /// idle for `idle_minutes`, and were not summarized within `stale_hours`.
+    pub async fn list_sessions_needing_summary(
+        &self,
+        stale_hours: i64,
+        idle_minutes: i64,
+        limit: i64,
+    ) -> Result<Vec<Session>, AppError> {
+        let rows = sqlx::query_as::<_, Session>(
+            r#"
+            SELECT * FROM sessions
+            WHERE status = 'active'
+              AND message_count >= 2
+              AND last_message_at IS NOT NULL
+              AND datetime(last_message_at) <= datetime('now', 'utc', printf('-%d minutes', ?2))
+              AND (
+                summarized_at IS NULL
+                OR (
+                  datetime(last_message_at) > datetime(summarized_at)
+                  AND datetime(summarized_at) <= datetime('now', 'utc', printf('-%d hours', ?1))
+                )
+              )
+            ORDER BY datetime(last_message_at) DESC
+            LIMIT ?3
+            "#,
+        )
+        .bind(stale_hours)
+        .bind(idle_minutes)
+        .bind(limit)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Active, still untitled sessions with at least `min_replies` assistant
+    /// replies on their current branch, most recent first.
+    pub async fn list_sessions_needing_title(
+        &self,
+        min_replies: i64,
+        limit: i64,
+    ) -> Result<Vec<Session>, AppError> {
+        let rows = sqlx::query_as::<_, Session>(
+            r#"
+            SELECT * FROM sessions s
+            WHERE s.status = 'active'
+              AND (s.title IS NULL OR trim(s.title) = '')
+              AND (
+                SELECT COUNT(*) FROM messages m
+                WHERE m.session_id = s.id AND m.role = 'assistant' AND m.is_active_variant = 1
+              ) >= ?1
+            ORDER BY datetime(s.last_message_at) DESC
+            LIMIT ?2
+            "#,
+        )
+        .bind(min_replies)
+        .bind(limit)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    pub async fn set_message_feedback(
+        &self,
+        message_id: &str,
+        rating: i64,
+    ) -> Result<(), AppError> {
+        let result = sqlx::query(
+            "UPDATE messages SET metadata = json_set(metadata, '$.feedback', ?1) WHERE id = ?2",
+        )
+        .bind(rating)
+        .bind(message_id)
+        .execute(&self.write_pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "message".to_string(),
+                id: message_id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    pub async fn set_message_finish_reason(
+        &self,
+        message_id: &str,
+        finish_reason: &str,
+    ) -> Result<(), AppError> {
+        sqlx::query("UPDATE messages SET finish_reason = ?1 WHERE id = ?2")
+            .bind(finish_reason)
+            .bind(message_id)
+            .execute(&self.write_pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_message_attachments(
+        &self,
+        message_id: &str,
+        attachments: &str,
+    ) -> Result<(), AppError> {
+        sqlx::query("UPDATE messages SET attachments = ?1 WHERE id = ?2")
+            .bind(attachments)
+            .bind(message_id)
+            .execute(&self.write_pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Fills in a message that was inserted before its content was known.
+    pub async fn complete_message(
+        &self,
+        message_id: &str,
+        content: &str,
+        finish_reason: &str,
+        error_message: Option<&str>,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE messages
+            SET content = ?1, token_count = ?2, finish_reason = ?3,
+                is_error = ?4, error_message = ?5
+            WHERE id = ?6
+            "#,
+        )
+        .bind(content)
+        .bind((content.len() / 4) as i64 + 1)
+        .bind(finish_reason)
+        .bind(if error_message.is_some() { 1 } else { 0 })
+        .bind(error_message)
+        .bind(message_id)
+        .execute(&self.write_pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set_message_tool_calls(
+        &self,
+        message_id: &str,
+        summaries_json: &str,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            "UPDATE messages SET metadata = json_set(metadata, '$.toolCalls', json(?1)) WHERE id = ?2",
+        )
+        .bind(summaries_json)
+        .bind(message_id)
+        .execute(&self.write_pool)
+        .await?;
+        Ok(())
+    }
+
     pub async fn insert_tool_call(&self, call: NewToolCall) -> Result<ToolCall, AppError> {
         let id = Uuid::new_v4().to_string();
 
@@ -260,6 +1301,19 @@ impl ConversationRepo {
         Ok(row)
     }
 
+    pub async fn list_session_tool_calls(
+        &self,
+        session_id: &str,
+    ) -> Result<Vec<ToolCall>, AppError> {
+        let rows = sqlx::query_as::<_, ToolCall>(
+            "SELECT * FROM tool_calls WHERE session_id = ?1 ORDER BY datetime(created_at) ASC",
+        )
+        .bind(session_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
     pub async fn update_tool_call_result(
         &self,
         id: &str,
@@ -287,6 +1341,56 @@ impl ConversationRepo {
         Ok(())
     }
 
+    /// User and assistant messages without an embedding, newest first.
+    pub async fn list_unembedded_messages(
+        &self,
+        min_chars: i64,
+        limit: i64,
+    ) -> Result<Vec<MessageToIndex>, AppError> {
+        let rows = sqlx::query_as::<_, MessageToIndex>(
+            r#"
+            SELECT m.id, s.user_id, m.content
+            FROM messages m
+            JOIN sessions s ON s.id = m.session_id
+            WHERE m.role IN ('user', 'assistant')
+              AND s.status != 'deleted'
+              AND length(trim(m.content)) >= ?1
+              AND NOT EXISTS (
+                SELECT 1 FROM embeddings e
+                WHERE e.entity_type = 'message' AND e.entity_id = m.id
+              )
+            ORDER BY datetime(m.created_at) DESC
+            LIMIT ?2
+            "#,
+        )
+        .bind(min_chars)
+        .bind(limit)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Active messages among `ids`, in no particular order.
+    pub async fn get_messages_by_ids(&self, ids: &[String]) -> Result<Vec<Message>, AppError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut builder = QueryBuilder::new("SELECT * FROM messages WHERE is_active_variant = 1");
+        builder.push(" AND id IN (");
+        let mut separated = builder.separated(", ");
+        for id in ids {
+            separated.push_bind(id);
+        }
+        builder.push(")");
+
+        let rows = builder
+            .build_query_as::<Message>()
+            .fetch_all(&self.read_pool)
+            .await?;
+        Ok(rows)
+    }
+
     pub async fn search_messages(
         &self,
         user_id: &str,
@@ -1,8 +1,11 @@
-use sqlx::SqlitePool;
+use sqlx::{QueryBuilder, SqlitePool};
 use uuid::Uuid;
 
-use crate::db::models::{Chunk, ChunkResult, Document, NewChunk, NewDocument};
+use crate::db::models::{
+    Chunk, ChunkResult, Document, NewChunk, NewDocument, RagCollection, RagStats,
+};
 use crate::error::AppError;
+use crate::repositories::fts_match_query;
 
 #[derive(Clone)]
 pub struct DocumentRepo {
@@ -65,6 +68,103 @@ impl DocumentRepo {
         Ok(row)
     }
 
+    pub async fn list_documents(&self, user_id: &str) -> Result<Vec<Document>, AppError> {
+        let rows = sqlx::query_as::<_, Document>(
+            "SELECT * FROM documents WHERE user_id = ?1 AND is_deleted = 0 ORDER BY created_at",
+        )
+        .bind(user_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// The live document ingested from `file_path`, if any.
+    pub async fn find_by_file_path(
+        &self,
+        user_id: &str,
+        file_path: &str,
+    ) -> Result<Option<Document>, AppError> {
+        let row = sqlx::query_as::<_, Document>(
+            r#"
+            SELECT * FROM documents
+            WHERE user_id = ?1 AND file_path = ?2 AND is_deleted = 0
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(user_id)
+        .bind(file_path)
+        .fetch_optional(&self.read_pool)
+        .await?;
+        Ok(row)
+    }
+
+    /// The live document fetched from `source_url`, if any.
+    pub async fn find_by_source_url(
+        &self,
+        user_id: &str,
+        source_url: &str,
+    ) -> Result<Option<Document>, AppError> {
+        let row = sqlx::query_as::<_, Document>(
+            r#"
+            SELECT * FROM documents
+            WHERE user_id = ?1 AND source_url = ?2 AND is_deleted = 0
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(user_id)
+        .bind(source_url)
+        .fetch_optional(&self.read_pool)
+        .await?;
+        Ok(row)
+    }
+
+    /// Records a re-ingested source's new contents and bumps the document version.
+    pub async fn update_source(
+        &self,
+        id: &str,
+        checksum: &str,
+        file_size_bytes: i64,
+        namespace: &str,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE documents
+            SET checksum = ?1, file_size_bytes = ?2, namespace = ?3, version = version + 1,
+                index_status = 'pending'
+            WHERE id = ?4
+            "#,
+        )
+        .bind(checksum)
+        .bind(file_size_bytes)
+        .bind(namespace)
+        .bind(id)
+        .execute(&self.write_pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn soft_delete_document(&self, id: &str) -> Result<(), AppError> {
+        sqlx::query("UPDATE documents SET is_deleted = 1, index_status = 'deleted' WHERE id = ?1")
+            .bind(id)
+            .execute(&self.write_pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Deletes every chunk of the document and returns their ids so the
+    /// caller can drop the matching embeddings.
+    pub async fn delete_chunks(&self, document_id: &str) -> Result<Vec<String>, AppError> {
+        let ids = sqlx::query_scalar::<_, String>(
+            "DELETE FROM document_chunks WHERE document_id = ?1 RETURNING id",
+        )
+        .bind(document_id)
+        .fetch_all(&self.write_pool)
+        .await?;
+        Ok(ids)
+    }
+
     pub async fn update_index_status(
         &self,
         id: &str,
@@ -139,30 +239,38 @@ impl DocumentRepo {
         &self,
         user_id: &str,
         query: &str,
-        namespace: &str,
+        namespaces: &[String],
         limit: i64,
     ) -> Result<Vec<ChunkResult>, AppError> {
-        let rows = sqlx::query_as::<_, ChunkResult>(
+        let match_query = fts_match_query(query);
+        if namespaces.is_empty() || match_query.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut builder = QueryBuilder::new(
             r#"
             SELECT c.id, c.document_id, c.chunk_index, c.content, c.section_title,
                    bm25(chunks_fts) AS score
             FROM chunks_fts
             JOIN document_chunks c ON c.id = chunks_fts.chunk_id
             JOIN documents d ON d.id = c.document_id
-            WHERE c.user_id = ?1
-              AND d.namespace = ?2
-              AND chunks_fts MATCH ?3
-            ORDER BY score
-            LIMIT ?4
-            "#,
-        )
-        .bind(user_id)
-        .bind(namespace)
-        .bind(query)
-        .bind(limit)
-        .fetch_all(&self.read_pool)
-        .await?;
+            WHERE c.user_id = "#,
+        );
+        builder.push_bind(user_id).push(" AND d.namespace IN (");
+        let mut separated = builder.separated(", ");
+        for namespace in namespaces {
+            separated.push_bind(namespace);
+        }
+        builder
+            .push(") AND chunks_fts MATCH ")
+            .push_bind(match_query)
+            .push(" ORDER BY score LIMIT ")
+            .push_bind(limit);
 
+        let rows = builder
+            .build_query_as::<ChunkResult>()
+            .fetch_all(&self.read_pool)
+            .await?;
         Ok(rows)
     }
 
@@ -221,4 +329,228 @@ impl DocumentRepo {
 
         Ok(rows)
     }
+
+    /// Keeps the ids of chunks whose live document is in one of `namespaces`,
+    /// in input order.
+    pub async fn filter_chunk_ids_in_namespaces(
+        &self,
+        user_id: &str,
+        namespaces: &[String],
+        chunk_ids: &[String],
+    ) -> Result<Vec<String>, AppError> {
+        if chunk_ids.is_empty() || namespaces.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut builder = QueryBuilder::new(
+            "SELECT c.id FROM document_chunks c JOIN documents d ON d.id = c.document_id WHERE c.user_id = ",
+        );
+        builder.push_bind(user_id).push(" AND d.namespace IN (");
+        let mut separated = builder.separated(", ");
+        for namespace in namespaces {
+            separated.push_bind(namespace);
+        }
+        builder.push(") AND d.is_deleted = 0 AND c.id IN (");
+        let mut separated = builder.separated(", ");
+        for chunk_id in chunk_ids {
+            separated.push_bind(chunk_id);
+        }
+        builder.push(")");
+
+        let matching = builder
+            .build_query_scalar::<String>()
+            .fetch_all(&self.read_pool)
+            .await?
+            .into_iter()
+            .collect::<std::collections::HashSet<_>>();
+        Ok(chunk_ids
+            .iter()
+            .filter(|id| matching.contains(*id))
+            .cloned()
+            .collect())
+    }
+
+    pub async fn create_collection(
+        &self,
+        user_id: &str,
+        name: &str,
+        namespace: &str,
+        description: Option<&str>,
+    ) -> Result<RagCollection, AppError> {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO rag_collections (id, user_id, name, namespace, description)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+        )
+        .bind(&id)
+        .bind(user_id)
+        .bind(name)
+        .bind(namespace)
+        .bind(description)
+        .execute(&self.write_pool)
+        .await?;
+
+        let row = sqlx::query_as::<_, RagCollection>("SELECT * FROM rag_collections WHERE id = ?1")
+            .bind(&id)
+            .fetch_one(&self.write_pool)
+            .await?;
+        Ok(row)
+    }
+
+    pub async fn list_collections(&self, user_id: &str) -> Result<Vec<RagCollection>, AppError> {
+        let rows = sqlx::query_as::<_, RagCollection>(
+            "SELECT * FROM rag_collections WHERE user_id = ?1 ORDER BY name COLLATE NOCASE",
+        )
+        .bind(user_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    pub async fn delete_collection(&self, user_id: &str, namespace: &str) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM rag_collections WHERE user_id = ?1 AND namespace = ?2")
+            .bind(user_id)
+            .bind(namespace)
+            .execute(&self.write_pool)
+            .await?;
+        Ok(())
+    }
+
+    /// `(namespace, live documents, chunks)` for every namespace the user has documents in.
+    pub async fn namespace_counts(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<(String, i64, i64)>, AppError> {
+        let rows = sqlx::query_as::<_, (String, i64, i64)>(
+            r#"
+            SELECT namespace, COUNT(*), COALESCE(SUM(chunk_count), 0)
+            FROM documents
+            WHERE user_id = ?1 AND is_deleted = 0
+            GROUP BY namespace
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    pub async fn list_document_ids_in_namespace(
+        &self,
+        user_id: &str,
+        namespace: &str,
+    ) -> Result<Vec<String>, AppError> {
+        let rows = sqlx::query_scalar::<_, String>(
+            "SELECT id FROM documents WHERE user_id = ?1 AND namespace = ?2 AND is_deleted = 0",
+        )
+        .bind(user_id)
+        .bind(namespace)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Records that an answer cited `chunk_id`, tagged with the namespace of its document.
+    pub async fn record_citation(
+        &self,
+        chunk_id: &str,
+        session_id: Option<&str>,
+        message_id: Option<&str>,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            INSERT INTO rag_citations (id, chunk_id, document_id, namespace, session_id, message_id)
+            SELECT ?1, c.id, c.document_id, d.namespace, ?3, ?4
+            FROM document_chunks c
+            JOIN documents d ON d.id = c.document_id
+            WHERE c.id = ?2
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(chunk_id)
+        .bind(session_id)
+        .bind(message_id)
+        .execute(&self.write_pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn namespace_stats(&self, namespace: &str) -> Result<RagStats, AppError> {
+        let (document_count, indexed, pending, failed, last_indexed_at, oldest_indexed_at) =
+            sqlx::query_as::<_, (i64, i64, i64, i64, Option<String>, Option<String>)>(
+                r#"
+                SELECT COUNT(*),
+                       COALESCE(SUM(index_status = 'indexed'), 0),
+                       COALESCE(SUM(index_status NOT IN ('indexed', 'failed')), 0),
+                       COALESCE(SUM(index_status = 'failed'), 0),
+                       MAX(last_indexed_at),
+                       MIN(last_indexed_at)
+                FROM documents
+                WHERE namespace = ?1 AND is_deleted = 0
+                "#,
+            )
+            .bind(namespace)
+            .fetch_one(&self.read_pool)
+            .await?;
+
+        let (chunk_count, embedded_chunk_count, avg_chunk_tokens, avg_chunk_chars) =
+            sqlx::query_as::<_, (i64, i64, f64, f64)>(
+                r#"
+                SELECT COUNT(*),
+                       COALESCE(SUM(e.id IS NOT NULL), 0),
+                       COALESCE(AVG(c.token_count), 0.0),
+                       COALESCE(AVG(length(c.content)), 0.0)
+                FROM document_chunks c
+                JOIN documents d ON d.id = c.document_id
+                LEFT JOIN embeddings e ON e.entity_type = 'chunk' AND e.entity_id = c.id
+                WHERE d.namespace = ?1 AND d.is_deleted = 0
+                "#,
+            )
+            .bind(namespace)
+            .fetch_one(&self.read_pool)
+            .await?;
+
+        let (retrieval_count, retrieved_chunk_count) = sqlx::query_as::<_, (i64, i64)>(
+            r#"
+            SELECT COUNT(*), COALESCE(SUM(json_array_length(reranked_chunk_ids)), 0)
+            FROM rag_retrievals
+            WHERE namespace = ?1
+            "#,
+        )
+        .bind(namespace)
+        .fetch_one(&self.read_pool)
+        .await?;
+
+        let cited_chunk_count =
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM rag_citations WHERE namespace = ?1")
+                .bind(namespace)
+                .fetch_one(&self.read_pool)
+                .await?;
+
+        let hit_rate = if retrieved_chunk_count > 0 {
+            (cited_chunk_count as f64 / retrieved_chunk_count as f64).min(1.0)
+        } else {
+            0.0
+        };
+
+        Ok(RagStats {
+            namespace: namespace.to_string(),
+            document_count,
+            indexed_document_count: indexed,
+            pending_document_count: pending,
+            failed_document_count: failed,
+            chunk_count,
+            embedded_chunk_count,
+            avg_chunk_tokens,
+            avg_chunk_chars,
+            last_indexed_at,
+            oldest_indexed_at,
+            retrieval_count,
+            retrieved_chunk_count,
+            cited_chunk_count,
+            hit_rate,
+        })
+    }
 }
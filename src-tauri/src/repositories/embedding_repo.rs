@@ -3,12 +3,15 @@ use uuid::Uuid;
 
 use crate::db::models::EmbeddingRow;
 use crate::error::AppError;
+use crate::repositories::vector_index::VectorIndex;
 use crate::repositories::{blob_to_vector, vector_to_blob};
 
 #[derive(Clone)]
 pub struct EmbeddingRepo {
     read_pool: SqlitePool,
     write_pool: SqlitePool,
+    /// Shared by every clone, so all writers keep the same index current.
+    index: VectorIndex,
 }
 
 impl EmbeddingRepo {
@@ -16,6 +19,7 @@ impl EmbeddingRepo {
         Self {
             read_pool: pool.clone(),
             write_pool: pool,
+            index: VectorIndex::default(),
         }
     }
 
@@ -23,6 +27,7 @@ impl EmbeddingRepo {
         Self {
             read_pool,
             write_pool,
+            index: VectorIndex::default(),
         }
     }
 
@@ -65,6 +70,9 @@ impl EmbeddingRepo {
         .bind(norm)
         .execute(&self.write_pool)
         .await?;
+        self.index
+            .upsert(namespace, user_id, entity_type, entity_id, &vector)
+            .await;
 
         let row = sqlx::query_scalar::<_, String>(
             "SELECT id FROM embeddings WHERE entity_type = ?1 AND entity_id = ?2",
@@ -147,10 +155,34 @@ impl EmbeddingRepo {
             .bind(entity_id)
             .execute(&self.write_pool)
             .await?;
+        self.index.remove(entity_type, entity_id).await;
 
         Ok(())
     }
 
+    /// The `k` entities whose vectors are closest to `query` by cosine
+    /// similarity, best first, from the in-memory index. The first search of a
+    /// namespace, user and entity type builds its index from the table.
+    pub async fn search_nearest(
+        &self,
+        namespace: &str,
+        user_id: &str,
+        entity_type: &str,
+        query: &[f32],
+        k: usize,
+    ) -> Result<Vec<(String, f32)>, AppError> {
+        self.index
+            .search_or_load(
+                namespace,
+                user_id,
+                entity_type,
+                query,
+                k,
+                self.get_embeddings_by_namespace(namespace, user_id),
+            )
+            .await
+    }
+
     pub async fn count_embeddings_by_namespace(&self, namespace: &str) -> Result<i64, AppError> {
         let count =
             sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM embeddings WHERE namespace = ?1")
@@ -161,6 +193,12 @@ impl EmbeddingRepo {
         Ok(count)
     }
 
+    /// Forgets the in-memory index after vectors were rewritten outside this
+    /// repo, e.g. by a re-index swap.
+    pub async fn reset_index(&self) {
+        self.index.clear().await;
+    }
+
     fn l2_norm(vector: &[f32]) -> f32 {
         vector.iter().map(|v| v * v).sum::<f32>().sqrt()
     }
@@ -0,0 +1,277 @@
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::db::models::BackgroundJob;
+use crate::error::AppError;
+
+/// Timestamps are kept in SQLite's `datetime()` format so `run_at` compares
+/// as text against `datetime('now','utc')`.
+pub const JOB_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Clone)]
+pub struct JobRepo {
+    read_pool: SqlitePool,
+    write_pool: SqlitePool,
+}
+
+impl JobRepo {
+    pub fn with_pools(read_pool: SqlitePool, write_pool: SqlitePool) -> Self {
+        Self {
+            read_pool,
+            write_pool,
+        }
+    }
+
+    /// Queues a job due at `run_at`, or right away without one.
+    pub async fn enqueue(
+        &self,
+        job_type: &str,
+        payload: &str,
+        cron: Option<&str>,
+        run_at: Option<&str>,
+        max_attempts: i64,
+    ) -> Result<BackgroundJob, AppError> {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO jobs (id, job_type, payload, cron, run_at, max_attempts)
+            VALUES (?1, ?2, ?3, ?4, COALESCE(?5, datetime('now','utc')), ?6)
+            "#,
+        )
+        .bind(&id)
+        .bind(job_type)
+        .bind(payload)
+        .bind(cron)
+        .bind(run_at)
+        .bind(max_attempts.max(1))
+        .execute(&self.write_pool)
+        .await?;
+        self.get_required(&id).await
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<BackgroundJob>, AppError> {
+        let row = sqlx::query_as::<_, BackgroundJob>("SELECT * FROM jobs WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&self.write_pool)
+            .await?;
+        Ok(row)
+    }
+
+    async fn get_required(&self, id: &str) -> Result<BackgroundJob, AppError> {
+        self.get(id).await?.ok_or_else(|| AppError::NotFound {
+            entity: "job".to_string(),
+            id: id.to_string(),
+        })
+    }
+
+    /// The queued or running job of `job_type`, if there is one.
+    pub async fn active_of_type(&self, job_type: &str) -> Result<Option<BackgroundJob>, AppError> {
+        let row = sqlx::query_as::<_, BackgroundJob>(
+            r#"
+            SELECT * FROM jobs
+            WHERE job_type = ?1 AND status IN ('queued', 'running')
+            ORDER BY run_at LIMIT 1
+            "#,
+        )
+        .bind(job_type)
+        .fetch_optional(&self.read_pool)
+        .await?;
+        Ok(row)
+    }
+
+    pub async fn list(
+        &self,
+        status: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<BackgroundJob>, AppError> {
+        let rows = sqlx::query_as::<_, BackgroundJob>(
+            r#"
+            SELECT * FROM jobs
+            WHERE ?1 IS NULL OR status = ?1
+            ORDER BY
+              CASE status WHEN 'running' THEN 0 WHEN 'queued' THEN 1 ELSE 2 END,
+              COALESCE(completed_at, run_at) DESC
+            LIMIT ?2
+            "#,
+        )
+        .bind(status)
+        .bind(limit)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Marks up to `limit` due jobs running and returns them, oldest first.
+    pub async fn claim_due(&self, limit: i64) -> Result<Vec<BackgroundJob>, AppError> {
+        let rows = sqlx::query_as::<_, BackgroundJob>(
+            r#"
+            UPDATE jobs
+            SET status = 'running',
+                attempts = attempts + 1,
+                started_at = datetime('now','utc')
+            WHERE id IN (
+              SELECT id FROM jobs
+              WHERE status = 'queued' AND run_at <= datetime('now','utc')
+              ORDER BY run_at
+              LIMIT ?1
+            )
+            RETURNING *
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.write_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Puts a claimed job back without counting the attempt, for jobs that
+    /// were due but shouldn't run yet.
+    pub async fn defer(&self, id: &str, run_at: &str, reason: &str) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE jobs
+            SET status = 'queued', attempts = MAX(attempts - 1, 0), run_at = ?1, last_error = ?2
+            WHERE id = ?3 AND status = 'running'
+            "#,
+        )
+        .bind(run_at)
+        .bind(reason)
+        .bind(id)
+        .execute(&self.write_pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Finishes a run. A recurring job is queued again for `next_run_at`.
+    pub async fn complete(&self, id: &str, next_run_at: Option<&str>) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE jobs
+            SET status = CASE WHEN ?1 IS NULL THEN 'completed' ELSE 'queued' END,
+                run_at = COALESCE(?1, run_at),
+                attempts = CASE WHEN ?1 IS NULL THEN attempts ELSE 0 END,
+                last_error = NULL,
+                completed_at = datetime('now','utc')
+            WHERE id = ?2 AND status = 'running'
+            "#,
+        )
+        .bind(next_run_at)
+        .bind(id)
+        .execute(&self.write_pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Records a failed run. With `retry_at` the job is queued again then;
+    /// without, it stays failed until retried by hand.
+    pub async fn fail(
+        &self,
+        id: &str,
+        error: &str,
+        retry_at: Option<&str>,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE jobs
+            SET status = CASE WHEN ?1 IS NULL THEN 'failed' ELSE 'queued' END,
+                run_at = COALESCE(?1, run_at),
+                last_error = ?2,
+                completed_at = CASE WHEN ?1 IS NULL THEN datetime('now','utc') ELSE NULL END
+            WHERE id = ?3 AND status = 'running'
+            "#,
+        )
+        .bind(retry_at)
+        .bind(error)
+        .bind(id)
+        .execute(&self.write_pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Cancels a queued or running job. A running one finishes its current
+    /// run, but its outcome is not recorded.
+    pub async fn cancel(&self, id: &str) -> Result<BackgroundJob, AppError> {
+        sqlx::query(
+            r#"
+            UPDATE jobs
+            SET status = 'cancelled', completed_at = datetime('now','utc')
+            WHERE id = ?1 AND status IN ('queued', 'running')
+            "#,
+        )
+        .bind(id)
+        .execute(&self.write_pool)
+        .await?;
+        self.get_required(id).await
+    }
+
+    /// Queues a failed or cancelled job to run now, with its attempts reset.
+    pub async fn retry(&self, id: &str) -> Result<BackgroundJob, AppError> {
+        sqlx::query(
+            r#"
+            UPDATE jobs
+            SET status = 'queued',
+                attempts = 0,
+                run_at = datetime('now','utc'),
+                last_error = NULL,
+                completed_at = NULL
+            WHERE id = ?1 AND status IN ('failed', 'cancelled')
+            "#,
+        )
+        .bind(id)
+        .execute(&self.write_pool)
+        .await?;
+        self.get_required(id).await
+    }
+
+    /// Deletes one-off jobs that finished, failed or were cancelled before
+    /// `older_than` (in `JOB_TIME_FORMAT`). Returns how many were removed.
+    pub async fn prune_finished(&self, older_than: &str) -> Result<u64, AppError> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM jobs
+            WHERE status IN ('completed', 'failed', 'cancelled')
+              AND completed_at IS NOT NULL
+              AND completed_at < ?1
+            "#,
+        )
+        .bind(older_than)
+        .execute(&self.write_pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Deletes the jobs of `job_type` whose JSON payload has `field` set to
+    /// `value`, except one running right now.
+    pub async fn delete_by_payload(
+        &self,
+        job_type: &str,
+        field: &str,
+        value: &str,
+    ) -> Result<u64, AppError> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM jobs
+            WHERE job_type = ?1
+              AND status <> 'running'
+              AND json_extract(payload, '$.' || ?2) = ?3
+            "#,
+        )
+        .bind(job_type)
+        .bind(field)
+        .bind(value)
+        .execute(&self.write_pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Jobs left running by a previous process are queued again; their
+    /// attempt still counts.
+    pub async fn requeue_interrupted(&self) -> Result<u64, AppError> {
+        let result = sqlx::query(
+            "UPDATE jobs SET status = 'queued', last_error = 'Interrupted by shutdown' WHERE status = 'running'",
+        )
+        .execute(&self.write_pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
 use sqlx::SqlitePool;
 use uuid::Uuid;
 
@@ -62,6 +65,113 @@ impl McpRepo {
         Ok(row)
     }
 
+    /// Registers a user-defined stdio server. It starts installed but inactive;
+    /// tool schemas are filled in on first connect.
+    pub async fn create_custom_mcp(
+        &self,
+        name: &str,
+        command: &str,
+        args: &[String],
+        env: &HashMap<String, String>,
+    ) -> Result<Mcp, AppError> {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO mcps (id, name, display_name, category, mcp_type, command, args, env_vars, is_installed)
+            VALUES (?1, ?2, ?2, 'custom', 'stdio', ?3, ?4, ?5, 1)
+            "#,
+        )
+        .bind(&id)
+        .bind(name)
+        .bind(command)
+        .bind(serde_json::to_string(args).unwrap_or_else(|_| "[]".to_string()))
+        .bind(serde_json::to_string(env).unwrap_or_else(|_| "{}".to_string()))
+        .execute(&self.write_pool)
+        .await?;
+
+        self.get_mcp(&id).await?.ok_or_else(|| AppError::NotFound {
+            entity: "mcp".to_string(),
+            id,
+        })
+    }
+
+    /// Registers a remote server reached over HTTP. Only the names of its
+    /// request headers, such as `Authorization`, go in `metadata.headerNames`;
+    /// their values are stored encrypted in `mcp_secrets`.
+    pub async fn create_remote_mcp(
+        &self,
+        name: &str,
+        url: &str,
+        header_names: &[String],
+    ) -> Result<Mcp, AppError> {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO mcps (id, name, display_name, category, mcp_type, url, metadata, is_installed)
+            VALUES (?1, ?2, ?2, 'custom', 'http', ?3, ?4, 1)
+            "#,
+        )
+        .bind(&id)
+        .bind(name)
+        .bind(url)
+        .bind(serde_json::json!({ "headerNames": header_names }).to_string())
+        .execute(&self.write_pool)
+        .await?;
+
+        self.get_mcp(&id).await?.ok_or_else(|| AppError::NotFound {
+            entity: "mcp".to_string(),
+            id,
+        })
+    }
+
+    pub async fn get_mcp_by_name(&self, name: &str) -> Result<Option<Mcp>, AppError> {
+        let row = sqlx::query_as::<_, Mcp>("SELECT * FROM mcps WHERE name = ?1")
+            .bind(name)
+            .fetch_optional(&self.read_pool)
+            .await?;
+        Ok(row)
+    }
+
+    /// Builtin MCPs are never deleted; secrets and stats cascade with the row.
+    pub async fn delete_mcp(&self, mcp_id: &str) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM mcps WHERE id = ?1 AND is_builtin = 0")
+            .bind(mcp_id)
+            .execute(&self.write_pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "mcp".to_string(),
+                id: mcp_id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    pub async fn update_schemas(
+        &self,
+        mcp_id: &str,
+        tool_schemas: &[Value],
+        resource_schemas: &[Value],
+    ) -> Result<(), AppError> {
+        sqlx::query("UPDATE mcps SET tool_schemas = ?1, resource_schemas = ?2 WHERE id = ?3")
+            .bind(serde_json::to_string(tool_schemas).unwrap_or_else(|_| "[]".to_string()))
+            .bind(serde_json::to_string(resource_schemas).unwrap_or_else(|_| "[]".to_string()))
+            .bind(mcp_id)
+            .execute(&self.write_pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_metadata(&self, mcp_id: &str, metadata: &Value) -> Result<(), AppError> {
+        sqlx::query("UPDATE mcps SET metadata = ?1 WHERE id = ?2")
+            .bind(metadata.to_string())
+            .bind(mcp_id)
+            .execute(&self.write_pool)
+            .await?;
+        Ok(())
+    }
+
     pub async fn install_mcp(&self, mcp_id: &str) -> Result<(), AppError> {
         sqlx::query("UPDATE mcps SET is_installed = 1 WHERE id = ?1")
             .bind(mcp_id)
@@ -1,8 +1,11 @@
-use sqlx::SqlitePool;
+use sqlx::{QueryBuilder, SqlitePool};
 use uuid::Uuid;
 
-use crate::db::models::{Memory, MemoryGraph, MemoryRelation, NewMemory};
+use crate::db::models::{
+    Memory, MemoryFilter, MemoryGraph, MemoryPatch, MemoryRelation, NewMemory,
+};
 use crate::error::AppError;
+use crate::repositories::fts_match_query;
 
 #[derive(Clone)]
 pub struct MemoryRepo {
@@ -25,7 +28,9 @@ impl MemoryRepo {
         }
     }
 
-    pub async fn upsert_memory(&self, memory: NewMemory) -> Result<Memory, AppError> {
+    /// Returns the user's memory with the same text if there is one, else the
+    /// inserted memory; the flag says whether it was inserted.
+    pub async fn upsert_memory(&self, memory: NewMemory) -> Result<(Memory, bool), AppError> {
         if let Some(existing) = sqlx::query_as::<_, Memory>(
             "SELECT * FROM memories WHERE user_id = ?1 AND content = ?2 LIMIT 1",
         )
@@ -34,7 +39,7 @@ impl MemoryRepo {
         .fetch_optional(&self.read_pool)
         .await?
         {
-            return Ok(existing);
+            return Ok((existing, false));
         }
 
         let id = Uuid::new_v4().to_string();
@@ -69,12 +74,14 @@ impl MemoryRepo {
         .execute(&self.write_pool)
         .await?;
 
-        self.get_memory(&id)
+        let inserted = self
+            .get_memory(&id)
             .await?
             .ok_or_else(|| AppError::NotFound {
                 entity: "memory".to_string(),
                 id,
-            })
+            })?;
+        Ok((inserted, true))
     }
 
     pub async fn get_memory(&self, id: &str) -> Result<Option<Memory>, AppError> {
@@ -113,12 +120,52 @@ impl MemoryRepo {
         Ok(rows)
     }
 
+    /// Memories matching `filter`, pinned first and then by importance.
+    pub async fn list_memories(&self, filter: &MemoryFilter) -> Result<Vec<Memory>, AppError> {
+        let mut builder = QueryBuilder::new("SELECT * FROM memories WHERE user_id = ");
+        builder.push_bind(&filter.user_id);
+        if !filter.include_archived.unwrap_or(false) {
+            builder.push(" AND is_archived = 0");
+        }
+        if filter.pinned_only.unwrap_or(false) {
+            builder.push(" AND is_pinned = 1");
+        }
+        if let Some(kind) = &filter.memory_type {
+            builder.push(" AND memory_type = ").push_bind(kind);
+        }
+        if let Some(category) = &filter.category {
+            builder.push(" AND category = ").push_bind(category);
+        }
+        if let Some(subject) = &filter.subject {
+            builder
+                .push(" AND subject = ")
+                .push_bind(subject)
+                .push(" COLLATE NOCASE");
+        }
+        builder
+            .push(" ORDER BY is_pinned DESC, importance DESC, created_at DESC LIMIT ")
+            .push_bind(filter.limit.unwrap_or(100).clamp(1, 500))
+            .push(" OFFSET ")
+            .push_bind(filter.offset.unwrap_or(0).max(0));
+
+        let rows = builder
+            .build_query_as::<Memory>()
+            .fetch_all(&self.read_pool)
+            .await?;
+        Ok(rows)
+    }
+
     pub async fn search_memories_text(
         &self,
         user_id: &str,
         query: &str,
         limit: i64,
     ) -> Result<Vec<Memory>, AppError> {
+        let match_query = fts_match_query(query);
+        if match_query.is_empty() {
+            return Ok(Vec::new());
+        }
+
         let rows = sqlx::query_as::<_, Memory>(
             r#"
             SELECT m.*
@@ -130,7 +177,7 @@ impl MemoryRepo {
             "#,
         )
         .bind(user_id)
-        .bind(query)
+        .bind(match_query)
         .bind(limit)
         .fetch_all(&self.read_pool)
         .await?;
@@ -138,6 +185,185 @@ impl MemoryRepo {
         Ok(rows)
     }
 
+    /// Memories whose subject or object is `subject`, or whose text mentions
+    /// it as a phrase.
+    pub async fn memories_about(
+        &self,
+        user_id: &str,
+        subject: &str,
+    ) -> Result<Vec<Memory>, AppError> {
+        let terms: Vec<&str> = subject
+            .split(|ch: char| !ch.is_alphanumeric())
+            .filter(|term| !term.is_empty())
+            .collect();
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+        let phrase = format!("\"{}\"", terms.join(" "));
+
+        let rows = sqlx::query_as::<_, Memory>(
+            r#"
+            SELECT * FROM memories
+            WHERE user_id = ?1
+              AND (subject = ?2 COLLATE NOCASE
+                   OR object = ?2 COLLATE NOCASE
+                   OR id IN (
+                       SELECT memory_id FROM memories_fts
+                       WHERE user_id = ?1 AND memories_fts MATCH ?3
+                   ))
+            "#,
+        )
+        .bind(user_id)
+        .bind(subject.trim())
+        .bind(phrase)
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Applies a user's correction. Edited text counts as verified.
+    pub async fn update_memory(&self, id: &str, patch: &MemoryPatch) -> Result<Memory, AppError> {
+        let tags = patch
+            .tags
+            .as_ref()
+            .map(|tags| serde_json::to_string(tags).unwrap_or_else(|_| "[]".to_string()));
+
+        let result = sqlx::query(
+            r#"
+            UPDATE memories
+            SET content = COALESCE(?2, content),
+                is_verified = CASE WHEN ?2 IS NULL THEN is_verified ELSE 1 END,
+                category = COALESCE(?3, category),
+                subject = COALESCE(?4, subject),
+                importance = COALESCE(?5, importance),
+                is_pinned = COALESCE(?6, is_pinned),
+                is_archived = COALESCE(?7, is_archived),
+                tags = COALESCE(?8, tags),
+                updated_at = datetime('now','utc')
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(&patch.content)
+        .bind(&patch.category)
+        .bind(&patch.subject)
+        .bind(patch.importance.map(|value| value.clamp(0.0, 1.0)))
+        .bind(patch.is_pinned.map(i64::from))
+        .bind(patch.is_archived.map(i64::from))
+        .bind(tags)
+        .execute(&self.write_pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "memory".to_string(),
+                id: id.to_string(),
+            });
+        }
+
+        self.get_memory(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "memory".to_string(),
+                id: id.to_string(),
+            })
+    }
+
+    /// Folds `duplicate_ids` into `keeper_id`: access counts are summed, the
+    /// keeper takes the highest importance and any pin or verification, and
+    /// relations are repointed before the duplicates are deleted.
+    pub async fn merge_memories(
+        &self,
+        keeper_id: &str,
+        duplicate_ids: &[String],
+    ) -> Result<(), AppError> {
+        let mut tx = self.write_pool.begin().await?;
+        for duplicate_id in duplicate_ids {
+            sqlx::query(
+                r#"
+                UPDATE memories
+                SET access_count = memories.access_count + dup.access_count,
+                    importance = MAX(memories.importance, dup.importance),
+                    is_pinned = MAX(memories.is_pinned, dup.is_pinned),
+                    is_verified = MAX(memories.is_verified, dup.is_verified),
+                    last_accessed_at = NULLIF(MAX(
+                        COALESCE(memories.last_accessed_at, ''),
+                        COALESCE(dup.last_accessed_at, '')
+                    ), ''),
+                    updated_at = datetime('now','utc')
+                FROM (SELECT access_count, importance, is_pinned, is_verified, last_accessed_at
+                      FROM memories WHERE id = ?2) AS dup
+                WHERE memories.id = ?1
+                "#,
+            )
+            .bind(keeper_id)
+            .bind(duplicate_id)
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query(
+                "UPDATE memory_relations SET source_memory_id = ?1 WHERE source_memory_id = ?2",
+            )
+            .bind(keeper_id)
+            .bind(duplicate_id)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query(
+                "UPDATE memory_relations SET target_memory_id = ?1 WHERE target_memory_id = ?2",
+            )
+            .bind(keeper_id)
+            .bind(duplicate_id)
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query("DELETE FROM memories WHERE id = ?1")
+                .bind(duplicate_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        // Repointing can leave self-loops and repeats of an existing edge.
+        sqlx::query(
+            r#"
+            DELETE FROM memory_relations
+            WHERE (source_memory_id = ?1 OR target_memory_id = ?1)
+              AND (source_memory_id = target_memory_id
+                   OR rowid NOT IN (
+                       SELECT MIN(rowid) FROM memory_relations
+                       WHERE source_memory_id = ?1 OR target_memory_id = ?1
+                       GROUP BY source_memory_id, target_memory_id, relation_type
+                   ))
+            "#,
+        )
+        .bind(keeper_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn set_pinned(&self, id: &str, pinned: bool) -> Result<(), AppError> {
+        sqlx::query("UPDATE memories SET is_pinned = ?1 WHERE id = ?2")
+            .bind(i64::from(pinned))
+            .bind(id)
+            .execute(&self.write_pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Every memory the user has, archived ones included, oldest first.
+    pub async fn list_all_memories(&self, user_id: &str) -> Result<Vec<Memory>, AppError> {
+        let rows = sqlx::query_as::<_, Memory>(
+            "SELECT * FROM memories WHERE user_id = ?1 ORDER BY created_at",
+        )
+        .bind(user_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
     pub async fn get_memories_by_importance(
         &self,
         user_id: &str,
@@ -163,26 +389,70 @@ impl MemoryRepo {
         Ok(())
     }
 
-    pub async fn apply_time_decay(&self, user_id: &str) -> Result<u64, AppError> {
+    /// `(id, importance, decay_rate, access_count)` for memories that can
+    /// still fade: not archived and not pinned.
+    pub async fn decay_candidates(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<(String, f64, f64, i64)>, AppError> {
+        let rows = sqlx::query_as::<_, (String, f64, f64, i64)>(
+            r#"
+            SELECT id, importance, decay_rate, access_count
+            FROM memories
+            WHERE user_id = ?1 AND is_archived = 0 AND is_pinned = 0
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    pub async fn set_importances(&self, updates: &[(String, f64)]) -> Result<(), AppError> {
+        let mut tx = self.write_pool.begin().await?;
+        for (id, importance) in updates {
+            sqlx::query("UPDATE memories SET importance = ?1 WHERE id = ?2")
+                .bind(importance)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Archives unpinned memories whose importance has fallen below `threshold`.
+    pub async fn archive_below(&self, user_id: &str, threshold: f64) -> Result<u64, AppError> {
         let result = sqlx::query(
             r#"
             UPDATE memories
-            SET importance = MAX(
-                0.0,
-                importance * (
-                    1 - (decay_rate * COALESCE((julianday('now') - julianday(last_accessed_at)), (julianday('now') - julianday(created_at))))
-                )
-            )
-            WHERE user_id = ?1
+            SET is_archived = 1, updated_at = datetime('now','utc')
+            WHERE user_id = ?1 AND is_archived = 0 AND is_pinned = 0 AND importance < ?2
             "#,
         )
         .bind(user_id)
+        .bind(threshold)
         .execute(&self.write_pool)
         .await?;
-
         Ok(result.rows_affected())
     }
 
+    /// Deletes memories past their `expires_at` and returns their ids.
+    pub async fn delete_expired(&self, user_id: &str) -> Result<Vec<String>, AppError> {
+        let ids = sqlx::query_scalar::<_, String>(
+            r#"
+            DELETE FROM memories
+            WHERE user_id = ?1 AND expires_at IS NOT NULL
+              AND datetime(expires_at) <= datetime('now','utc')
+            RETURNING id
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.write_pool)
+        .await?;
+        Ok(ids)
+    }
+
     pub async fn delete_memory(&self, id: &str) -> Result<(), AppError> {
         sqlx::query("DELETE FROM memories WHERE id = ?1")
             .bind(id)
@@ -191,47 +461,109 @@ impl MemoryRepo {
         Ok(())
     }
 
-    pub async fn get_memory_graph(
+    /// Relates two memories unless they already are, in either direction.
+    /// Returns whether a relation was added.
+    pub async fn link_memories(
         &self,
         user_id: &str,
-        memory_id: &str,
-        depth: i64,
-    ) -> Result<MemoryGraph, AppError> {
-        let nodes = sqlx::query_as::<_, Memory>(
+        source_memory_id: &str,
+        target_memory_id: &str,
+        relation_type: &str,
+        strength: f64,
+    ) -> Result<bool, AppError> {
+        let result = sqlx::query(
             r#"
-            WITH RECURSIVE graph(memory_id, level) AS (
-                SELECT ?2 AS memory_id, 0 AS level
-                UNION ALL
-                SELECT r.target_memory_id, g.level + 1
-                FROM memory_relations r
-                JOIN graph g ON r.source_memory_id = g.memory_id
-                WHERE g.level < ?3 AND r.user_id = ?1
-            )
-            SELECT m.*
-            FROM memories m
-            JOIN graph g ON g.memory_id = m.id
-            WHERE m.user_id = ?1
+            INSERT INTO memory_relations (id, user_id, source_memory_id, target_memory_id, relation_type, strength)
+            SELECT ?1, ?2, ?3, ?4, ?5, ?6
+            WHERE ?3 != ?4
+              AND NOT EXISTS (
+                  SELECT 1 FROM memory_relations
+                  WHERE relation_type = ?5
+                    AND ((source_memory_id = ?3 AND target_memory_id = ?4)
+                         OR (source_memory_id = ?4 AND target_memory_id = ?3))
+              )
             "#,
         )
+        .bind(Uuid::new_v4().to_string())
         .bind(user_id)
-        .bind(memory_id)
-        .bind(depth)
-        .fetch_all(&self.read_pool)
+        .bind(source_memory_id)
+        .bind(target_memory_id)
+        .bind(relation_type)
+        .bind(strength.clamp(0.0, 1.0))
+        .execute(&self.write_pool)
         .await?;
+        Ok(result.rows_affected() > 0)
+    }
 
-        let edges = sqlx::query_as::<_, MemoryRelation>(
-            r#"
-            SELECT * FROM memory_relations
-            WHERE user_id = ?1
-              AND (source_memory_id IN (SELECT id FROM memories WHERE user_id = ?1)
-                   OR target_memory_id IN (SELECT id FROM memories WHERE user_id = ?1))
-              AND (source_memory_id = ?2 OR target_memory_id = ?2)
-            "#,
-        )
-        .bind(user_id)
-        .bind(memory_id)
-        .fetch_all(&self.read_pool)
-        .await?;
+    /// Walks relations breadth-first from `memory_id`, following edges in
+    /// both directions, up to `depth` hops or `max_nodes` memories. Edges are
+    /// those between the memories reached.
+    pub async fn get_memory_graph(
+        &self,
+        user_id: &str,
+        memory_id: &str,
+        depth: i64,
+        max_nodes: usize,
+    ) -> Result<MemoryGraph, AppError> {
+        let mut visited: Vec<String> = vec![memory_id.to_string()];
+        let mut frontier = visited.clone();
+        let mut edges: Vec<MemoryRelation> = Vec::new();
+
+        for _ in 0..depth.max(0) {
+            if frontier.is_empty() || visited.len() >= max_nodes {
+                break;
+            }
+
+            let mut builder = QueryBuilder::new("SELECT * FROM memory_relations WHERE user_id = ");
+            builder
+                .push_bind(user_id)
+                .push(" AND (source_memory_id IN (");
+            let mut separated = builder.separated(", ");
+            for id in &frontier {
+                separated.push_bind(id);
+            }
+            builder.push(") OR target_memory_id IN (");
+            let mut separated = builder.separated(", ");
+            for id in &frontier {
+                separated.push_bind(id);
+            }
+            builder.push(")) ORDER BY strength DESC");
+            let relations = builder
+                .build_query_as::<MemoryRelation>()
+                .fetch_all(&self.read_pool)
+                .await?;
+
+            let mut next = Vec::new();
+            for relation in relations {
+                if edges.iter().any(|edge| edge.id == relation.id) {
+                    continue;
+                }
+                for neighbour in [&relation.source_memory_id, &relation.target_memory_id] {
+                    if !visited.contains(neighbour) && visited.len() < max_nodes {
+                        visited.push(neighbour.clone());
+                        next.push(neighbour.clone());
+                    }
+                }
+                if visited.contains(&relation.source_memory_id)
+                    && visited.contains(&relation.target_memory_id)
+                {
+                    edges.push(relation);
+                }
+            }
+            frontier = next;
+        }
+
+        let mut builder = QueryBuilder::new("SELECT * FROM memories WHERE user_id = ");
+        builder.push_bind(user_id).push(" AND id IN (");
+        let mut separated = builder.separated(", ");
+        for id in &visited {
+            separated.push_bind(id);
+        }
+        builder.push(")");
+        let nodes = builder
+            .build_query_as::<Memory>()
+            .fetch_all(&self.read_pool)
+            .await?;
 
         Ok(MemoryGraph {
             root_memory_id: memory_id.to_string(),
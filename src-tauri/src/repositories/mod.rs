@@ -1,13 +1,24 @@
+pub mod adapter_repo;
 pub mod analytics_repo;
+pub mod capture_repo;
 pub mod conversation_repo;
 pub mod document_repo;
 pub mod embedding_repo;
+pub mod job_repo;
 pub mod mcp_repo;
 pub mod memory_repo;
 pub mod model_repo;
+pub mod persona_repo;
+pub mod reindex_repo;
+pub mod saved_prompt_repo;
+pub mod scheduled_prompt_repo;
 pub mod settings_repo;
 pub mod system_repo;
 pub mod user_repo;
+pub mod vector_index;
+pub mod watched_folder_repo;
+pub mod webhook_repo;
+pub mod workspace_repo;
 
 pub fn vector_to_blob(vector: &[f32]) -> Vec<u8> {
     let mut bytes = Vec::with_capacity(vector.len() * 4);
@@ -23,9 +34,21 @@ pub fn blob_to_vector(blob: &[u8]) -> Vec<f32> {
         .collect()
 }
 
+/// Turns free text into an FTS5 query matching any of its words. Each term is
+/// quoted, so punctuation and words like `NOT` or `NEAR` aren't read as query
+/// syntax.
+pub fn fts_match_query(query: &str) -> String {
+    query
+        .split(|ch: char| !ch.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(|term| format!("\"{term}\""))
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{blob_to_vector, vector_to_blob};
+    use super::{blob_to_vector, fts_match_query, vector_to_blob};
 
     #[test]
     fn vector_blob_roundtrip() {
@@ -34,4 +57,75 @@ mod tests {
         let restored = blob_to_vector(&blob);
         assert_eq!(source, restored);
     }
+
+    #[test]
+    fn fts_query_quotes_each_word() {
+        assert_eq!(fts_match_query("rust sqlite"), "\"rust\" OR \"sqlite\"");
+        assert_eq!(fts_match_query("  spaced\tout\n"), "\"spaced\" OR \"out\"");
+        assert_eq!(fts_match_query("café über"), "\"café\" OR \"über\"");
+    }
+
+    #[test]
+    fn fts_query_neutralises_operators() {
+        assert_eq!(
+            fts_match_query("cats AND dogs OR NOT birds"),
+            "\"cats\" OR \"AND\" OR \"dogs\" OR \"OR\" OR \"NOT\" OR \"birds\""
+        );
+        assert_eq!(
+            fts_match_query("NEAR(a b, 2)"),
+            "\"NEAR\" OR \"a\" OR \"b\" OR \"2\""
+        );
+        assert_eq!(
+            fts_match_query("pref* -excluded"),
+            "\"pref\" OR \"excluded\""
+        );
+        assert_eq!(
+            fts_match_query("say \"hi\" don't"),
+            "\"say\" OR \"hi\" OR \"don\" OR \"t\""
+        );
+        assert_eq!(
+            fts_match_query("title:secret ^start"),
+            "\"title\" OR \"secret\" OR \"start\""
+        );
+    }
+
+    #[test]
+    fn fts_query_is_empty_without_words() {
+        assert_eq!(fts_match_query(""), "");
+        assert_eq!(fts_match_query("   "), "");
+        assert_eq!(fts_match_query("\"*-()^:"), "");
+    }
+
+    #[tokio::test]
+    async fn fts_query_is_accepted_by_sqlite() {
+        // One connection: each in-memory connection is its own database.
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::query("CREATE VIRTUAL TABLE notes USING fts5(body)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO notes (body) VALUES ('rust and sqlite'), ('c++ notes')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        for (query, expected) in [
+            ("rust AND", 1),
+            ("NEAR(c, notes)", 1),
+            ("\"unbalanced -c++ *", 1),
+            ("NOT", 0),
+        ] {
+            let count =
+                sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM notes WHERE notes MATCH ?1")
+                    .bind(fts_match_query(query))
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap();
+            assert_eq!(count, expected, "{query}");
+        }
+    }
 }
@@ -1,7 +1,9 @@
 use sqlx::SqlitePool;
 use uuid::Uuid;
 
-use crate::db::models::{Model, ModelWithScore, NewModel};
+use crate::db::models::{
+    Model, ModelBenchmark, ModelBenchmarkSummary, ModelWithScore, NewModel, NewModelBenchmark,
+};
 use crate::error::AppError;
 
 #[derive(Clone)]
@@ -188,7 +190,7 @@ impl ModelRepo {
         let rows = sqlx::query_as::<_, Model>(
             r#"
             SELECT * FROM models
-            WHERE min_ram_mb <= ?1 AND min_vram_mb <= ?2
+            WHERE min_ram_mb <= ?1 AND min_vram_mb <= ?2 AND category = 'chat'
             ORDER BY compatibility_score DESC, is_recommended DESC
             "#,
         )
@@ -251,9 +253,89 @@ impl ModelRepo {
         Ok(rows)
     }
 
+    /// Returns the recorded `(layers, vram_mb)` GPU offload that last loaded this file.
+    pub async fn get_gpu_offload(&self, file_path: &str) -> Result<Option<(i64, i64)>, AppError> {
+        let row = sqlx::query_as::<_, (Option<i64>, Option<i64>)>(
+            r#"
+            SELECT json_extract(metadata, '$.gpuOffload.layers'),
+                   json_extract(metadata, '$.gpuOffload.vramMb')
+            FROM models
+            WHERE file_path = ?1
+            LIMIT 1
+            "#,
+        )
+        .bind(file_path)
+        .fetch_optional(&self.read_pool)
+        .await?;
+
+        Ok(row.and_then(|(layers, vram_mb)| Some((layers?, vram_mb.unwrap_or(0)))))
+    }
+
+    pub async fn record_gpu_offload(
+        &self,
+        file_path: &str,
+        layers: i64,
+        vram_mb: i64,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE models
+            SET metadata = json_set(
+              metadata,
+              '$.gpuOffload',
+              json_object('layers', ?2, 'vramMb', ?3, 'recordedAt', datetime('now','utc'))
+            )
+            WHERE file_path = ?1
+            "#,
+        )
+        .bind(file_path)
+        .bind(layers)
+        .bind(vram_mb)
+        .execute(&self.write_pool)
+        .await?;
+        Ok(())
+    }
+
+    /// The downloaded multimodal projector (mmproj) of the model at `file_path`.
+    pub async fn get_vision_projector(&self, file_path: &str) -> Result<Option<String>, AppError> {
+        let row = sqlx::query_scalar::<_, Option<String>>(
+            r#"
+            SELECT json_extract(metadata, '$.visionProjector.path')
+            FROM models
+            WHERE file_path = ?1
+            LIMIT 1
+            "#,
+        )
+        .bind(file_path)
+        .fetch_optional(&self.read_pool)
+        .await?;
+
+        Ok(row.flatten())
+    }
+
+    pub async fn record_vision_projector(
+        &self,
+        model_id: &str,
+        projector_path: &str,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE models
+            SET metadata = json_set(metadata, '$.visionProjector.path', ?2)
+            WHERE id = ?1
+            "#,
+        )
+        .bind(model_id)
+        .bind(projector_path)
+        .execute(&self.write_pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Downloaded chat models; speech models are listed by category instead.
     pub async fn list_installed(&self) -> Result<Vec<Model>, AppError> {
         let rows = sqlx::query_as::<_, Model>(
-            "SELECT * FROM models WHERE is_downloaded = 1 ORDER BY is_default DESC, display_name ASC",
+            "SELECT * FROM models WHERE is_downloaded = 1 AND category = 'chat' ORDER BY is_default DESC, display_name ASC",
         )
         .fetch_all(&self.read_pool)
         .await?;
@@ -268,4 +350,73 @@ impl ModelRepo {
         .await?;
         Ok(rows)
     }
+
+    pub async fn insert_benchmark(
+        &self,
+        entry: NewModelBenchmark,
+    ) -> Result<ModelBenchmark, AppError> {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO model_benchmarks (
+              id, model_id, system_profile_id, context_tokens, prompt_tokens, output_tokens,
+              load_time_ms, first_token_ms, total_latency_ms, tokens_per_sec, memory_used_mb,
+              cpu_usage_pct, success, metadata
+            ) VALUES (?1, ?2, ?3, 0, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, 1, ?12)
+            "#,
+        )
+        .bind(&id)
+        .bind(&entry.model_id)
+        .bind(&entry.system_profile_id)
+        .bind(entry.prompt_tokens)
+        .bind(entry.output_tokens)
+        .bind(entry.load_time_ms)
+        .bind(entry.first_token_ms)
+        .bind(entry.total_latency_ms)
+        .bind(entry.tokens_per_sec)
+        .bind(entry.memory_used_mb)
+        .bind(entry.cpu_usage_pct)
+        .bind(&entry.metadata)
+        .execute(&self.write_pool)
+        .await?;
+
+        let row =
+            sqlx::query_as::<_, ModelBenchmark>("SELECT * FROM model_benchmarks WHERE id = ?1")
+                .bind(&id)
+                .fetch_one(&self.write_pool)
+                .await?;
+        Ok(row)
+    }
+
+    pub async fn benchmark_summaries(
+        &self,
+        system_profile_id: &str,
+    ) -> Result<Vec<ModelBenchmarkSummary>, AppError> {
+        let rows = sqlx::query_as::<_, ModelBenchmarkSummary>(
+            r#"
+            SELECT
+              b.model_id,
+              m.name AS model_name,
+              m.display_name,
+              m.parameter_count,
+              m.quantization,
+              COUNT(*) AS runs,
+              AVG(b.tokens_per_sec) AS avg_tokens_per_sec,
+              MAX(b.tokens_per_sec) AS best_tokens_per_sec,
+              AVG(b.first_token_ms) AS avg_first_token_ms,
+              AVG(b.load_time_ms) AS avg_load_time_ms,
+              AVG(b.memory_used_mb) AS avg_memory_used_mb,
+              MAX(b.created_at) AS last_run_at
+            FROM model_benchmarks b
+            JOIN models m ON m.id = b.model_id
+            WHERE b.system_profile_id = ?1 AND b.success = 1
+            GROUP BY b.model_id
+            ORDER BY avg_tokens_per_sec DESC
+            "#,
+        )
+        .bind(system_profile_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
 }
@@ -0,0 +1,156 @@
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::db::models::{NewPersona, Persona};
+use crate::error::AppError;
+
+#[derive(Clone)]
+pub struct PersonaRepo {
+    read_pool: SqlitePool,
+    write_pool: SqlitePool,
+}
+
+impl PersonaRepo {
+    pub fn with_pools(read_pool: SqlitePool, write_pool: SqlitePool) -> Self {
+        Self {
+            read_pool,
+            write_pool,
+        }
+    }
+
+    pub async fn create_persona(&self, persona: NewPersona) -> Result<Persona, AppError> {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO personas (id, user_id, name, description, system_prompt, temperature, preferred_model_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+        )
+        .bind(&id)
+        .bind(&persona.user_id)
+        .bind(&persona.name)
+        .bind(&persona.description)
+        .bind(&persona.system_prompt)
+        .bind(persona.temperature)
+        .bind(&persona.preferred_model_id)
+        .execute(&self.write_pool)
+        .await?;
+
+        self.get_persona(&id)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "persona".to_string(),
+                id,
+            })
+    }
+
+    pub async fn get_persona(&self, id: &str) -> Result<Option<Persona>, AppError> {
+        let row = sqlx::query_as::<_, Persona>("SELECT * FROM personas WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&self.read_pool)
+            .await?;
+        Ok(row)
+    }
+
+    pub async fn list_personas(&self, user_id: &str) -> Result<Vec<Persona>, AppError> {
+        let rows = sqlx::query_as::<_, Persona>(
+            "SELECT * FROM personas WHERE user_id = ?1 ORDER BY name COLLATE NOCASE",
+        )
+        .bind(user_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Replaces every editable field; the owner is unchanged.
+    pub async fn update_persona(
+        &self,
+        id: &str,
+        persona: &NewPersona,
+    ) -> Result<Persona, AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE personas
+            SET name = ?2,
+                description = ?3,
+                system_prompt = ?4,
+                temperature = ?5,
+                preferred_model_id = ?6
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(&persona.name)
+        .bind(&persona.description)
+        .bind(&persona.system_prompt)
+        .bind(persona.temperature)
+        .bind(&persona.preferred_model_id)
+        .execute(&self.write_pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "persona".to_string(),
+                id: id.to_string(),
+            });
+        }
+
+        self.get_persona(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "persona".to_string(),
+                id: id.to_string(),
+            })
+    }
+
+    /// Sessions using the persona are kept and fall back to the default prompt.
+    pub async fn delete_persona(&self, id: &str) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM personas WHERE id = ?1")
+            .bind(id)
+            .execute(&self.write_pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "persona".to_string(),
+                id: id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    pub async fn persona_for_session(&self, session_id: &str) -> Result<Option<Persona>, AppError> {
+        let row = sqlx::query_as::<_, Persona>(
+            r#"
+            SELECT p.* FROM personas p
+            JOIN sessions s ON s.persona_id = p.id
+            WHERE s.id = ?1
+            "#,
+        )
+        .bind(session_id)
+        .fetch_optional(&self.read_pool)
+        .await?;
+        Ok(row)
+    }
+
+    /// Gives the session `persona_id`, or none when it is `None`.
+    pub async fn set_session_persona(
+        &self,
+        session_id: &str,
+        persona_id: Option<&str>,
+    ) -> Result<(), AppError> {
+        let result = sqlx::query("UPDATE sessions SET persona_id = ?1 WHERE id = ?2")
+            .bind(persona_id)
+            .bind(session_id)
+            .execute(&self.write_pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "session".to_string(),
+                id: session_id.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
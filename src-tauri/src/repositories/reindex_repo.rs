@@ -0,0 +1,280 @@
+use sqlx::{FromRow, QueryBuilder, SqlitePool};
+use uuid::Uuid;
+
+use crate::db::models::ReindexJob;
+use crate::error::AppError;
+use crate::repositories::vector_to_blob;
+
+/// A stored embedding still waiting for its target-model vector, with the text
+/// it was computed from.
+#[derive(Debug, Clone, FromRow)]
+pub struct ReindexItem {
+    pub embedding_id: String,
+    pub text: String,
+}
+
+#[derive(Clone)]
+pub struct ReindexRepo {
+    read_pool: SqlitePool,
+    write_pool: SqlitePool,
+}
+
+impl ReindexRepo {
+    pub fn with_pools(read_pool: SqlitePool, write_pool: SqlitePool) -> Self {
+        Self {
+            read_pool,
+            write_pool,
+        }
+    }
+
+    pub async fn create_job(
+        &self,
+        source_model: &str,
+        target_model: &str,
+        total_items: i64,
+    ) -> Result<ReindexJob, AppError> {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO reindex_jobs (id, source_model, target_model, total_items)
+            VALUES (?1, ?2, ?3, ?4)
+            "#,
+        )
+        .bind(&id)
+        .bind(source_model)
+        .bind(target_model)
+        .bind(total_items)
+        .execute(&self.write_pool)
+        .await?;
+
+        let job = sqlx::query_as::<_, ReindexJob>("SELECT * FROM reindex_jobs WHERE id = ?1")
+            .bind(&id)
+            .fetch_one(&self.write_pool)
+            .await?;
+        Ok(job)
+    }
+
+    pub async fn latest_job(&self) -> Result<Option<ReindexJob>, AppError> {
+        let row = sqlx::query_as::<_, ReindexJob>(
+            "SELECT * FROM reindex_jobs ORDER BY created_at DESC, rowid DESC LIMIT 1",
+        )
+        .fetch_optional(&self.read_pool)
+        .await?;
+        Ok(row)
+    }
+
+    pub async fn running_job(&self) -> Result<Option<ReindexJob>, AppError> {
+        let row = sqlx::query_as::<_, ReindexJob>(
+            "SELECT * FROM reindex_jobs WHERE status = 'running' ORDER BY created_at LIMIT 1",
+        )
+        .fetch_optional(&self.read_pool)
+        .await?;
+        Ok(row)
+    }
+
+    /// Embeddings whose source text still exists: document chunks, memories
+    /// and (truncated like history indexing does) messages.
+    pub async fn count_items(&self, max_message_chars: usize) -> Result<i64, AppError> {
+        let mut builder = QueryBuilder::new("SELECT COUNT(*) FROM (");
+        push_item_select(&mut builder, max_message_chars);
+        builder.push(")");
+
+        let count = builder
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.read_pool)
+            .await?;
+        Ok(count)
+    }
+
+    /// The next `limit` items this job hasn't staged a vector for. Rows added
+    /// while the job runs show up here too, so nothing is left behind.
+    pub async fn list_pending_items(
+        &self,
+        job_id: &str,
+        max_message_chars: usize,
+        limit: i64,
+    ) -> Result<Vec<ReindexItem>, AppError> {
+        let mut builder = QueryBuilder::new("SELECT embedding_id, text FROM (");
+        push_item_select(&mut builder, max_message_chars);
+        builder
+            .push(
+                ") WHERE embedding_id NOT IN (SELECT embedding_id FROM reindex_staged_vectors WHERE job_id = ",
+            )
+            .push_bind(job_id)
+            .push(") ORDER BY embedding_id LIMIT ")
+            .push_bind(limit);
+
+        let rows = builder
+            .build_query_as::<ReindexItem>()
+            .fetch_all(&self.read_pool)
+            .await?;
+        Ok(rows)
+    }
+
+    /// Stores one batch of target-model vectors and returns how many the job
+    /// has staged so far.
+    pub async fn stage_vectors(
+        &self,
+        job_id: &str,
+        vectors: &[(String, Vec<f32>)],
+    ) -> Result<i64, AppError> {
+        let mut tx = self.write_pool.begin().await?;
+        for (embedding_id, vector) in vectors {
+            sqlx::query(
+                r#"
+                INSERT OR REPLACE INTO reindex_staged_vectors (job_id, embedding_id, vector, dimensions, norm)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                "#,
+            )
+            .bind(job_id)
+            .bind(embedding_id)
+            .bind(vector_to_blob(vector))
+            .bind(vector.len() as i64)
+            .bind(vector.iter().map(|v| v * v).sum::<f32>().sqrt())
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let processed = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM reindex_staged_vectors WHERE job_id = ?1",
+        )
+        .bind(job_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"
+            UPDATE reindex_jobs
+            SET processed_items = ?2, total_items = MAX(total_items, ?2)
+            WHERE id = ?1
+            "#,
+        )
+        .bind(job_id)
+        .bind(processed)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(processed)
+    }
+
+    /// Replaces every live vector with its staged one in a single transaction,
+    /// so searches never see a mix of models. Returns `false` without touching
+    /// anything when items were added since the last batch; they need staging
+    /// first. Embeddings whose source is gone are dropped.
+    pub async fn swap_in(
+        &self,
+        job_id: &str,
+        target_model: &str,
+        max_message_chars: usize,
+    ) -> Result<bool, AppError> {
+        let mut tx = self.write_pool.begin().await?;
+        // Re-checked on the write connection, so no embedding can be written
+        // between this check and the delete below.
+        let mut builder = QueryBuilder::new("SELECT COUNT(*) FROM (");
+        push_item_select(&mut builder, max_message_chars);
+        builder
+            .push(
+                ") WHERE embedding_id NOT IN (SELECT embedding_id FROM reindex_staged_vectors WHERE job_id = ",
+            )
+            .push_bind(job_id)
+            .push(")");
+        let pending = builder
+            .build_query_scalar::<i64>()
+            .fetch_one(&mut *tx)
+            .await?;
+        if pending > 0 {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE embeddings
+            SET vector = s.vector,
+                dimensions = s.dimensions,
+                norm = s.norm,
+                model_name = ?2,
+                updated_at = datetime('now','utc')
+            FROM reindex_staged_vectors s
+            WHERE s.job_id = ?1 AND s.embedding_id = embeddings.id
+            "#,
+        )
+        .bind(job_id)
+        .bind(target_model)
+        .execute(&mut *tx)
+        .await?;
+        let mut builder = QueryBuilder::new(
+            "DELETE FROM embeddings WHERE id NOT IN (SELECT embedding_id FROM reindex_staged_vectors WHERE job_id = ",
+        );
+        builder
+            .push_bind(job_id)
+            .push(") AND id NOT IN (SELECT embedding_id FROM (");
+        push_item_select(&mut builder, max_message_chars);
+        builder.push("))");
+        builder.build().execute(&mut *tx).await?;
+        sqlx::query("DELETE FROM reindex_staged_vectors WHERE job_id = ?1")
+            .bind(job_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            r#"
+            UPDATE reindex_jobs
+            SET status = 'completed', completed_at = datetime('now','utc')
+            WHERE id = ?1
+            "#,
+        )
+        .bind(job_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Marks the job failed and drops its staged vectors; the live ones were
+    /// never touched.
+    pub async fn fail_job(&self, job_id: &str, error: &str) -> Result<(), AppError> {
+        let mut tx = self.write_pool.begin().await?;
+        sqlx::query("DELETE FROM reindex_staged_vectors WHERE job_id = ?1")
+            .bind(job_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            r#"
+            UPDATE reindex_jobs
+            SET status = 'failed', error_message = ?2, completed_at = datetime('now','utc')
+            WHERE id = ?1
+            "#,
+        )
+        .bind(job_id)
+        .bind(error)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_job(&self, job_id: &str) -> Result<Option<ReindexJob>, AppError> {
+        let row = sqlx::query_as::<_, ReindexJob>("SELECT * FROM reindex_jobs WHERE id = ?1")
+            .bind(job_id)
+            .fetch_optional(&self.read_pool)
+            .await?;
+        Ok(row)
+    }
+}
+
+fn push_item_select(builder: &mut QueryBuilder<'_, sqlx::Sqlite>, max_message_chars: usize) {
+    builder
+        .push(
+            r#"
+            SELECT e.id AS embedding_id,
+                   COALESCE(c.content, m.content, substr(msg.content, 1, "#,
+        )
+        .push_bind(max_message_chars as i64)
+        .push(
+            r#")) AS text
+            FROM embeddings e
+            LEFT JOIN document_chunks c ON e.entity_type = 'chunk' AND c.id = e.entity_id
+            LEFT JOIN memories m ON e.entity_type = 'memory' AND m.id = e.entity_id
+            LEFT JOIN messages msg ON e.entity_type = 'message' AND msg.id = e.entity_id
+            WHERE COALESCE(c.content, m.content, msg.content) IS NOT NULL
+            "#,
+        );
+}
@@ -0,0 +1,291 @@
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::db::models::{NewSavedPrompt, SavedPrompt};
+use crate::error::AppError;
+
+#[derive(Clone)]
+pub struct SavedPromptRepo {
+    read_pool: SqlitePool,
+    write_pool: SqlitePool,
+}
+
+impl SavedPromptRepo {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self {
+            read_pool: pool.clone(),
+            write_pool: pool,
+        }
+    }
+
+    pub fn with_pools(read_pool: SqlitePool, write_pool: SqlitePool) -> Self {
+        Self {
+            read_pool,
+            write_pool,
+        }
+    }
+
+    pub async fn create_prompt(&self, prompt: NewSavedPrompt) -> Result<SavedPrompt, AppError> {
+        let id = Uuid::new_v4().to_string();
+        let variables = encode_variables(&prompt.template);
+        sqlx::query(
+            r#"
+            INSERT INTO saved_prompts (id, user_id, title, description, template, variables, task_type, preset)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "#,
+        )
+        .bind(&id)
+        .bind(&prompt.user_id)
+        .bind(&prompt.title)
+        .bind(&prompt.description)
+        .bind(&prompt.template)
+        .bind(&variables)
+        .bind(&prompt.task_type)
+        .bind(&prompt.preset)
+        .execute(&self.write_pool)
+        .await?;
+
+        self.get_prompt(&id)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "saved_prompt".to_string(),
+                id,
+            })
+    }
+
+    pub async fn get_prompt(&self, id: &str) -> Result<Option<SavedPrompt>, AppError> {
+        let row = sqlx::query_as::<_, SavedPrompt>("SELECT * FROM saved_prompts WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&self.read_pool)
+            .await?;
+        Ok(row)
+    }
+
+    pub async fn list_prompts(&self, user_id: &str) -> Result<Vec<SavedPrompt>, AppError> {
+        let rows = sqlx::query_as::<_, SavedPrompt>(
+            r#"
+            SELECT * FROM saved_prompts
+            WHERE user_id = ?1
+            ORDER BY datetime(COALESCE(last_used_at, created_at)) DESC
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    pub async fn update_prompt(
+        &self,
+        id: &str,
+        title: Option<&str>,
+        description: Option<&str>,
+        template: Option<&str>,
+        task_type: Option<&str>,
+        preset: Option<&str>,
+    ) -> Result<SavedPrompt, AppError> {
+        let variables = template.map(encode_variables);
+        let result = sqlx::query(
+            r#"
+            UPDATE saved_prompts
+            SET title = COALESCE(?2, title),
+                description = COALESCE(?3, description),
+                template = COALESCE(?4, template),
+                variables = COALESCE(?5, variables),
+                task_type = COALESCE(?6, task_type),
+                preset = COALESCE(?7, preset)
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(title)
+        .bind(description)
+        .bind(template)
+        .bind(variables)
+        .bind(task_type)
+        .bind(preset)
+        .execute(&self.write_pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "saved_prompt".to_string(),
+                id: id.to_string(),
+            });
+        }
+
+        self.get_prompt(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "saved_prompt".to_string(),
+                id: id.to_string(),
+            })
+    }
+
+    pub async fn delete_prompt(&self, id: &str) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM saved_prompts WHERE id = ?1")
+            .bind(id)
+            .execute(&self.write_pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn mark_used(&self, id: &str) -> Result<(), AppError> {
+        sqlx::query(
+            "UPDATE saved_prompts SET use_count = use_count + 1, last_used_at = datetime('now','utc') WHERE id = ?1",
+        )
+        .bind(id)
+        .execute(&self.write_pool)
+        .await?;
+        Ok(())
+    }
+}
+
+/// Collects the distinct `{name}` placeholders of a template in order of first appearance.
+pub fn template_variables(template: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for part in template_parts(template) {
+        if let TemplatePart::Variable(name) = part {
+            if !names.iter().any(|existing| existing == name) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names
+}
+
+/// Substitutes `{name}` placeholders from `values`, reporting the first one left unfilled.
+/// `{{` and `}}` stand for literal braces, so code and JSON can be written as is
+/// or escaped where they would look like a placeholder.
+pub fn render_template(
+    template: &str,
+    values: &std::collections::HashMap<String, String>,
+) -> Result<String, String> {
+    let mut rendered = String::with_capacity(template.len());
+    for part in template_parts(template) {
+        match part {
+            TemplatePart::Text(text) => rendered.push_str(text),
+            TemplatePart::Variable(name) => match values.get(name) {
+                Some(value) => rendered.push_str(value),
+                None => return Err(name.to_string()),
+            },
+        }
+    }
+    Ok(rendered)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum TemplatePart<'a> {
+    Text(&'a str),
+    Variable(&'a str),
+}
+
+/// Splits a template into literal text and `{name}` placeholders. Braces that
+/// don't enclose a variable name stay literal text.
+fn template_parts(template: &str) -> Vec<TemplatePart<'_>> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(index) = rest.find(['{', '}']) {
+        if index > 0 {
+            parts.push(TemplatePart::Text(&rest[..index]));
+        }
+        rest = &rest[index..];
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            parts.push(TemplatePart::Text(&rest[..1]));
+            rest = &rest[2..];
+            continue;
+        }
+        if rest.starts_with('{') {
+            if let Some(end) = rest[1..].find(['{', '}']) {
+                let name = rest[1..end + 1].trim();
+                if rest[end + 1..].starts_with('}') && is_variable_name(name) {
+                    parts.push(TemplatePart::Variable(name));
+                    rest = &rest[end + 2..];
+                    continue;
+                }
+            }
+        }
+        parts.push(TemplatePart::Text(&rest[..1]));
+        rest = &rest[1..];
+    }
+    if !rest.is_empty() {
+        parts.push(TemplatePart::Text(rest));
+    }
+    parts
+}
+
+fn encode_variables(template: &str) -> String {
+    serde_json::to_string(&template_variables(template)).unwrap_or_else(|_| "[]".to_string())
+}
+
+fn is_variable_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{render_template, template_variables};
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn renders_variables() {
+        let rendered = render_template(
+            "Translate { text } to {language}.",
+            &values(&[("text", "hola"), ("language", "English")]),
+        );
+        assert_eq!(rendered.as_deref(), Ok("Translate hola to English."));
+    }
+
+    #[test]
+    fn reports_missing_variable() {
+        let rendered = render_template("Hi {name}", &HashMap::new());
+        assert_eq!(rendered, Err("name".to_string()));
+    }
+
+    #[test]
+    fn leaves_code_and_json_braces_alone() {
+        let template = r#"Fix {code}: fn main() { run(); } and {"a": 1}"#;
+        assert_eq!(template_variables(template), vec!["code"]);
+        let rendered = render_template(template, &values(&[("code", "x")]));
+        assert_eq!(
+            rendered.as_deref(),
+            Ok(r#"Fix x: fn main() { run(); } and {"a": 1}"#)
+        );
+    }
+
+    #[test]
+    fn doubled_braces_are_literal() {
+        let template = "Use {{name}} in {lang}, not }}";
+        assert_eq!(template_variables(template), vec!["lang"]);
+        let rendered = render_template(template, &values(&[("lang", "Rust")]));
+        assert_eq!(rendered.as_deref(), Ok("Use {name} in Rust, not }"));
+    }
+
+    #[test]
+    fn unclosed_brace_is_literal() {
+        assert!(template_variables("open {name").is_empty());
+        assert_eq!(
+            render_template("open {name", &HashMap::new()).as_deref(),
+            Ok("open {name")
+        );
+    }
+
+    #[test]
+    fn variables_are_distinct_in_order() {
+        assert_eq!(
+            template_variables("{b} {a} {b} {{c}}"),
+            vec!["b".to_string(), "a".to_string()]
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::db::models::{NewScheduledPrompt, ScheduledPrompt};
+use crate::error::AppError;
+
+#[derive(Clone)]
+pub struct ScheduledPromptRepo {
+    read_pool: SqlitePool,
+    write_pool: SqlitePool,
+}
+
+impl ScheduledPromptRepo {
+    pub fn with_pools(read_pool: SqlitePool, write_pool: SqlitePool) -> Self {
+        Self {
+            read_pool,
+            write_pool,
+        }
+    }
+
+    pub async fn create(
+        &self,
+        prompt: &NewScheduledPrompt,
+        cron: &str,
+        next_run_at: &str,
+    ) -> Result<ScheduledPrompt, AppError> {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO scheduled_prompts (id, user_id, name, prompt, schedule, cron, next_run_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+        )
+        .bind(&id)
+        .bind(&prompt.user_id)
+        .bind(&prompt.name)
+        .bind(&prompt.prompt)
+        .bind(&prompt.schedule)
+        .bind(cron)
+        .bind(next_run_at)
+        .execute(&self.write_pool)
+        .await?;
+        self.get_required(&id).await
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<ScheduledPrompt>, AppError> {
+        let row =
+            sqlx::query_as::<_, ScheduledPrompt>("SELECT * FROM scheduled_prompts WHERE id = ?1")
+                .bind(id)
+                .fetch_optional(&self.write_pool)
+                .await?;
+        Ok(row)
+    }
+
+    async fn get_required(&self, id: &str) -> Result<ScheduledPrompt, AppError> {
+        self.get(id).await?.ok_or_else(|| AppError::NotFound {
+            entity: "scheduled_prompt".to_string(),
+            id: id.to_string(),
+        })
+    }
+
+    pub async fn list(&self, user_id: &str) -> Result<Vec<ScheduledPrompt>, AppError> {
+        let rows = sqlx::query_as::<_, ScheduledPrompt>(
+            r#"
+            SELECT * FROM scheduled_prompts
+            WHERE user_id = ?1
+            ORDER BY is_enabled DESC, next_run_at, name COLLATE NOCASE
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Replaces the name, prompt and schedule; the session and run history stay.
+    pub async fn update(
+        &self,
+        id: &str,
+        prompt: &NewScheduledPrompt,
+        cron: &str,
+        next_run_at: &str,
+    ) -> Result<ScheduledPrompt, AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE scheduled_prompts
+            SET name = ?2,
+                prompt = ?3,
+                schedule = ?4,
+                cron = ?5,
+                next_run_at = CASE WHEN is_enabled = 1 THEN ?6 ELSE NULL END
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(&prompt.name)
+        .bind(&prompt.prompt)
+        .bind(&prompt.schedule)
+        .bind(cron)
+        .bind(next_run_at)
+        .execute(&self.write_pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "scheduled_prompt".to_string(),
+                id: id.to_string(),
+            });
+        }
+        self.get_required(id).await
+    }
+
+    /// Pauses the prompt, or resumes it from `next_run_at`.
+    pub async fn set_enabled(
+        &self,
+        id: &str,
+        next_run_at: Option<&str>,
+    ) -> Result<ScheduledPrompt, AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE scheduled_prompts
+            SET is_enabled = ?2 IS NOT NULL, next_run_at = ?2
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(next_run_at)
+        .execute(&self.write_pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "scheduled_prompt".to_string(),
+                id: id.to_string(),
+            });
+        }
+        self.get_required(id).await
+    }
+
+    /// The session keeps the replies already delivered.
+    pub async fn delete(&self, id: &str) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM scheduled_prompts WHERE id = ?1")
+            .bind(id)
+            .execute(&self.write_pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "scheduled_prompt".to_string(),
+                id: id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Enabled prompts whose next run has come, oldest first.
+    pub async fn list_due(&self, limit: i64) -> Result<Vec<ScheduledPrompt>, AppError> {
+        let rows = sqlx::query_as::<_, ScheduledPrompt>(
+            r#"
+            SELECT * FROM scheduled_prompts
+            WHERE is_enabled = 1
+              AND next_run_at IS NOT NULL
+              AND next_run_at <= datetime('now','utc')
+            ORDER BY next_run_at
+            LIMIT ?1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.write_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    pub async fn set_next_run(&self, id: &str, next_run_at: Option<&str>) -> Result<(), AppError> {
+        sqlx::query("UPDATE scheduled_prompts SET next_run_at = ?2 WHERE id = ?1")
+            .bind(id)
+            .bind(next_run_at)
+            .execute(&self.write_pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_session(&self, id: &str, session_id: &str) -> Result<(), AppError> {
+        sqlx::query("UPDATE scheduled_prompts SET session_id = ?2 WHERE id = ?1")
+            .bind(id)
+            .bind(session_id)
+            .execute(&self.write_pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Records how the latest run went; `error` is `None` on success.
+    pub async fn record_run(&self, id: &str, error: Option<&str>) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE scheduled_prompts
+            SET last_run_at = datetime('now','utc'),
+                last_status = CASE WHEN ?2 IS NULL THEN 'succeeded' ELSE 'failed' END,
+                last_error = ?2
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(error)
+        .execute(&self.write_pool)
+        .await?;
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 use sqlx::SqlitePool;
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
 use crate::error::AppError;
@@ -18,27 +19,52 @@ pub struct Setting {
     pub updated_at: String,
 }
 
+/// Published after every successful write; `value` is `None` when the key was deleted.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingChange {
+    pub user_id: Option<String>,
+    pub namespace: String,
+    pub key: String,
+    pub value: Option<String>,
+}
+
+/// Enough to absorb a dragged slider before slow subscribers start lagging.
+const CHANGE_CHANNEL_CAPACITY: usize = 256;
+
 #[derive(Clone)]
 pub struct SettingsRepo {
     read_pool: SqlitePool,
     write_pool: SqlitePool,
+    changes: broadcast::Sender<SettingChange>,
 }
 
 impl SettingsRepo {
     pub fn new(pool: SqlitePool) -> Self {
-        Self {
-            read_pool: pool.clone(),
-            write_pool: pool,
-        }
+        Self::with_pools(pool.clone(), pool)
     }
 
     pub fn with_pools(read_pool: SqlitePool, write_pool: SqlitePool) -> Self {
+        let (changes, _) = broadcast::channel(CHANGE_CHANNEL_CAPACITY);
         Self {
             read_pool,
             write_pool,
+            changes,
         }
     }
 
+    /// Change notifications from this repo and every clone of it.
+    pub fn subscribe(&self) -> broadcast::Receiver<SettingChange> {
+        self.changes.subscribe()
+    }
+
+    /// Announces a change to config stored outside the settings table (e.g. the
+    /// runtime policy) so subscribers treat it like any other setting.
+    pub fn publish(&self, change: SettingChange) {
+        // No subscribers yet is fine; they read current values when they start.
+        let _ = self.changes.send(change);
+    }
+
     pub async fn upsert_setting(
         &self,
         user_id: Option<&str>,
@@ -49,30 +75,59 @@ impl SettingsRepo {
         is_encrypted: bool,
     ) -> Result<Setting, AppError> {
         let id = Uuid::new_v4().to_string();
-        sqlx::query(
-            r#"
-            INSERT INTO settings (id, user_id, namespace, key, value, value_type, is_encrypted)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
-            ON CONFLICT(user_id, namespace, key)
-            DO UPDATE SET value = excluded.value, value_type = excluded.value_type, is_encrypted = excluded.is_encrypted
-            "#,
-        )
-        .bind(&id)
-        .bind(user_id)
-        .bind(namespace)
-        .bind(key)
-        .bind(value)
-        .bind(value_type)
-        .bind(if is_encrypted { 1 } else { 0 })
-        .execute(&self.write_pool)
-        .await?;
+        if user_id.is_some() {
+            sqlx::query(
+                r#"
+                INSERT INTO settings (id, user_id, namespace, key, value, value_type, is_encrypted)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                ON CONFLICT(user_id, namespace, key)
+                DO UPDATE SET value = excluded.value, value_type = excluded.value_type, is_encrypted = excluded.is_encrypted
+                "#,
+            )
+            .bind(&id)
+            .bind(user_id)
+            .bind(namespace)
+            .bind(key)
+            .bind(value)
+            .bind(value_type)
+            .bind(if is_encrypted { 1 } else { 0 })
+            .execute(&self.write_pool)
+            .await?;
+        } else {
+            // NULL user ids never collide in the composite key, so global rows use the partial index.
+            sqlx::query(
+                r#"
+                INSERT INTO settings (id, user_id, namespace, key, value, value_type, is_encrypted)
+                VALUES (?1, NULL, ?2, ?3, ?4, ?5, ?6)
+                ON CONFLICT(namespace, key) WHERE user_id IS NULL
+                DO UPDATE SET value = excluded.value, value_type = excluded.value_type, is_encrypted = excluded.is_encrypted
+                "#,
+            )
+            .bind(&id)
+            .bind(namespace)
+            .bind(key)
+            .bind(value)
+            .bind(value_type)
+            .bind(if is_encrypted { 1 } else { 0 })
+            .execute(&self.write_pool)
+            .await?;
+        }
 
-        self.get_setting(user_id, namespace, key)
+        let setting = self
+            .get_setting(user_id, namespace, key)
             .await?
             .ok_or_else(|| AppError::NotFound {
                 entity: "setting".to_string(),
                 id: format!("{namespace}:{key}"),
-            })
+            })?;
+
+        self.publish(SettingChange {
+            user_id: user_id.map(str::to_string),
+            namespace: namespace.to_string(),
+            key: key.to_string(),
+            value: Some(setting.value.clone()),
+        });
+        Ok(setting)
     }
 
     pub async fn get_setting(
@@ -127,4 +182,52 @@ impl SettingsRepo {
 
         Ok(rows)
     }
+
+    /// Global settings plus the user's own, across every namespace.
+    pub async fn list_for_user(&self, user_id: &str) -> Result<Vec<Setting>, AppError> {
+        let rows = sqlx::query_as::<_, Setting>(
+            r#"
+            SELECT * FROM settings
+            WHERE user_id IS NULL OR user_id = ?1
+            ORDER BY namespace, key, user_id
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    pub async fn delete_setting(
+        &self,
+        user_id: Option<&str>,
+        namespace: &str,
+        key: &str,
+    ) -> Result<bool, AppError> {
+        let result = if user_id.is_some() {
+            sqlx::query("DELETE FROM settings WHERE user_id = ?1 AND namespace = ?2 AND key = ?3")
+                .bind(user_id)
+                .bind(namespace)
+                .bind(key)
+                .execute(&self.write_pool)
+                .await?
+        } else {
+            sqlx::query("DELETE FROM settings WHERE user_id IS NULL AND namespace = ?1 AND key = ?2")
+                .bind(namespace)
+                .bind(key)
+                .execute(&self.write_pool)
+                .await?
+        };
+
+        let deleted = result.rows_affected() > 0;
+        if deleted {
+            self.publish(SettingChange {
+                user_id: user_id.map(str::to_string),
+                namespace: namespace.to_string(),
+                key: key.to_string(),
+                value: None,
+            });
+        }
+        Ok(deleted)
+    }
 }
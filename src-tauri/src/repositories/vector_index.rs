@@ -0,0 +1,555 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use tokio::sync::RwLock;
+
+use crate::db::models::EmbeddingRow;
+use crate::error::AppError;
+use crate::repositories::blob_to_vector;
+
+/// Links kept per node on the upper layers; layer 0 keeps twice as many.
+const MAX_NEIGHBORS: usize = 16;
+const MAX_LEVEL: usize = 12;
+const EF_CONSTRUCTION: usize = 100;
+const MIN_EF_SEARCH: usize = 64;
+/// Up to this many vectors an exact scan takes about a millisecond and never
+/// misses, so the graph is only walked for larger partitions.
+const EXACT_SCAN_LIMIT: usize = 2_000;
+/// Removed nodes keep routing searches until they outnumber live ones.
+const MIN_DEAD_BEFORE_COMPACTION: usize = 1_000;
+
+/// (namespace, user_id, entity_type)
+type PartitionKey = (String, String, String);
+
+/// In-memory HNSW graphs over the `embeddings` table, one per namespace, user
+/// and entity type, so nearest-neighbour search doesn't decode every blob.
+///
+/// A partition is built from the table the first time it is searched and is
+/// then kept current by `EmbeddingRepo` writes. Code that deletes or rewrites
+/// rows with plain SQL (retention purges, re-index swaps) must call
+/// `EmbeddingRepo::reset_index` afterwards.
+#[derive(Clone, Default)]
+pub struct VectorIndex {
+    partitions: Arc<RwLock<HashMap<PartitionKey, HnswGraph>>>,
+}
+
+impl VectorIndex {
+    /// The `k` entities most similar to `query` by cosine similarity, best first.
+    /// `load` supplies the partition's rows if it hasn't been built yet; it runs
+    /// under the write lock so no concurrent write is lost.
+    pub async fn search_or_load<F>(
+        &self,
+        namespace: &str,
+        user_id: &str,
+        entity_type: &str,
+        query: &[f32],
+        k: usize,
+        load: F,
+    ) -> Result<Vec<(String, f32)>, AppError>
+    where
+        F: Future<Output = Result<Vec<EmbeddingRow>, AppError>>,
+    {
+        let key = partition_key(namespace, user_id, entity_type);
+        {
+            let partitions = self.partitions.read().await;
+            // A partition built for other dimensions predates a model change.
+            if let Some(graph) = partitions.get(&key).filter(|g| g.dims == query.len()) {
+                return Ok(graph.search(query, k));
+            }
+        }
+
+        let mut partitions = self.partitions.write().await;
+        let current = partitions
+            .get(&key)
+            .is_some_and(|graph| graph.dims == query.len());
+        if !current {
+            let rows = load.await?;
+            let mut graph = HnswGraph::new(query.len());
+            for row in rows.iter().filter(|row| row.entity_type == entity_type) {
+                graph.insert(&row.entity_id, &blob_to_vector(&row.vector));
+            }
+            partitions.insert(key.clone(), graph);
+        }
+        Ok(partitions
+            .get(&key)
+            .map(|graph| graph.search(query, k))
+            .unwrap_or_default())
+    }
+
+    /// Mirrors an upsert. Partitions that were never searched are skipped;
+    /// they read the row from the table when they are built.
+    pub async fn upsert(
+        &self,
+        namespace: &str,
+        user_id: &str,
+        entity_type: &str,
+        entity_id: &str,
+        vector: &[f32],
+    ) {
+        let key = partition_key(namespace, user_id, entity_type);
+        let mut partitions = self.partitions.write().await;
+        // An upsert can move an entity to another namespace or user.
+        for (other_key, graph) in partitions.iter_mut() {
+            if other_key.2 == entity_type && *other_key != key {
+                graph.remove(entity_id);
+            }
+        }
+        if let Some(graph) = partitions.get_mut(&key) {
+            graph.insert(entity_id, vector);
+            if graph.needs_compaction() {
+                *graph = graph.compacted();
+            }
+        }
+    }
+
+    /// Drops every partition; each is rebuilt from the table on its next search.
+    pub async fn clear(&self) {
+        self.partitions.write().await.clear();
+    }
+
+    pub async fn remove(&self, entity_type: &str, entity_id: &str) {
+        let mut partitions = self.partitions.write().await;
+        for (key, graph) in partitions.iter_mut() {
+            if key.2 == entity_type {
+                graph.remove(entity_id);
+                if graph.needs_compaction() {
+                    *graph = graph.compacted();
+                }
+            }
+        }
+    }
+}
+
+fn partition_key(namespace: &str, user_id: &str, entity_type: &str) -> PartitionKey {
+    (
+        namespace.to_string(),
+        user_id.to_string(),
+        entity_type.to_string(),
+    )
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Scored {
+    similarity: f32,
+    node: u32,
+}
+
+impl Eq for Scored {}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.similarity
+            .total_cmp(&other.similarity)
+            .then(self.node.cmp(&other.node))
+    }
+}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Hierarchical navigable small world graph over unit vectors, so cosine
+/// similarity is a dot product. Removal only tombstones a node.
+struct HnswGraph {
+    dims: usize,
+    entity_ids: Vec<String>,
+    vectors: Vec<Vec<f32>>,
+    /// `links[node][layer]`; a node has one list per layer it lives on.
+    links: Vec<Vec<Vec<u32>>>,
+    deleted: Vec<bool>,
+    node_by_entity: HashMap<String, u32>,
+    entry_point: Option<u32>,
+    top_layer: usize,
+}
+
+impl HnswGraph {
+    fn new(dims: usize) -> Self {
+        Self {
+            dims,
+            entity_ids: Vec::new(),
+            vectors: Vec::new(),
+            links: Vec::new(),
+            deleted: Vec::new(),
+            node_by_entity: HashMap::new(),
+            entry_point: None,
+            top_layer: 0,
+        }
+    }
+
+    fn insert(&mut self, entity_id: &str, vector: &[f32]) {
+        if vector.len() != self.dims {
+            return;
+        }
+        let Some(vector) = normalized(vector) else {
+            return;
+        };
+        self.remove(entity_id);
+
+        let node = self.vectors.len() as u32;
+        let level = random_level(entity_id);
+        self.entity_ids.push(entity_id.to_string());
+        self.vectors.push(vector);
+        self.links.push(vec![Vec::new(); level + 1]);
+        self.deleted.push(false);
+        self.node_by_entity.insert(entity_id.to_string(), node);
+
+        let Some(mut entry) = self.entry_point else {
+            self.entry_point = Some(node);
+            self.top_layer = level;
+            return;
+        };
+
+        let query = self.vectors[node as usize].clone();
+        for layer in (level + 1..=self.top_layer).rev() {
+            entry = self.greedy_closest(&query, entry, layer);
+        }
+        for layer in (0..=level.min(self.top_layer)).rev() {
+            let candidates = self.search_layer(&query, entry, EF_CONSTRUCTION, layer);
+            let limit = max_neighbors(layer);
+            let neighbors = candidates
+                .iter()
+                .take(limit)
+                .map(|scored| scored.node)
+                .collect::<Vec<_>>();
+            for &neighbor in &neighbors {
+                self.links[neighbor as usize][layer].push(node);
+                if self.links[neighbor as usize][layer].len() > limit {
+                    self.prune(neighbor, layer, limit);
+                }
+            }
+            self.links[node as usize][layer] = neighbors;
+            if let Some(best) = candidates.first() {
+                entry = best.node;
+            }
+        }
+
+        if level > self.top_layer {
+            self.entry_point = Some(node);
+            self.top_layer = level;
+        }
+    }
+
+    fn remove(&mut self, entity_id: &str) {
+        if let Some(node) = self.node_by_entity.remove(entity_id) {
+            self.deleted[node as usize] = true;
+        }
+    }
+
+    fn needs_compaction(&self) -> bool {
+        let dead = self.vectors.len() - self.node_by_entity.len();
+        dead >= MIN_DEAD_BEFORE_COMPACTION && dead > self.node_by_entity.len()
+    }
+
+    fn compacted(&self) -> Self {
+        let mut graph = Self::new(self.dims);
+        for (entity_id, &node) in &self.node_by_entity {
+            graph.insert(entity_id, &self.vectors[node as usize]);
+        }
+        graph
+    }
+
+    fn search(&self, query: &[f32], k: usize) -> Vec<(String, f32)> {
+        if query.len() != self.dims || k == 0 {
+            return Vec::new();
+        }
+        let Some(query) = normalized(query) else {
+            return Vec::new();
+        };
+
+        let mut hits = if self.node_by_entity.len() <= EXACT_SCAN_LIMIT {
+            self.node_by_entity
+                .values()
+                .map(|&node| Scored {
+                    similarity: dot(&query, &self.vectors[node as usize]),
+                    node,
+                })
+                .collect::<Vec<_>>()
+        } else {
+            let Some(mut entry) = self.entry_point else {
+                return Vec::new();
+            };
+            for layer in (1..=self.top_layer).rev() {
+                entry = self.greedy_closest(&query, entry, layer);
+            }
+            let ef = (k * 2).max(MIN_EF_SEARCH);
+            self.search_layer(&query, entry, ef, 0)
+                .into_iter()
+                .filter(|scored| !self.deleted[scored.node as usize])
+                .collect()
+        };
+
+        hits.sort_by(|a, b| b.cmp(a));
+        hits.truncate(k);
+        hits.into_iter()
+            .map(|scored| {
+                (
+                    self.entity_ids[scored.node as usize].clone(),
+                    scored.similarity,
+                )
+            })
+            .collect()
+    }
+
+    fn greedy_closest(&self, query: &[f32], mut current: u32, layer: usize) -> u32 {
+        let mut best = dot(query, &self.vectors[current as usize]);
+        loop {
+            let mut improved = false;
+            for &neighbor in &self.links[current as usize][layer] {
+                let similarity = dot(query, &self.vectors[neighbor as usize]);
+                if similarity > best {
+                    best = similarity;
+                    current = neighbor;
+                    improved = true;
+                }
+            }
+            if !improved {
+                return current;
+            }
+        }
+    }
+
+    /// Best-first search of one layer; up to `ef` nodes, most similar first.
+    fn search_layer(&self, query: &[f32], entry: u32, ef: usize, layer: usize) -> Vec<Scored> {
+        let first = Scored {
+            similarity: dot(query, &self.vectors[entry as usize]),
+            node: entry,
+        };
+        let mut visited = HashSet::from([entry]);
+        let mut candidates = BinaryHeap::from([first]);
+        let mut found = BinaryHeap::from([Reverse(first)]);
+
+        while let Some(candidate) = candidates.pop() {
+            let worst = found.peek().map_or(f32::MIN, |Reverse(s)| s.similarity);
+            if found.len() >= ef && candidate.similarity < worst {
+                break;
+            }
+            for &neighbor in &self.links[candidate.node as usize][layer] {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+                let scored = Scored {
+                    similarity: dot(query, &self.vectors[neighbor as usize]),
+                    node: neighbor,
+                };
+                let worst = found.peek().map_or(f32::MIN, |Reverse(s)| s.similarity);
+                if found.len() < ef || scored.similarity > worst {
+                    candidates.push(scored);
+                    found.push(Reverse(scored));
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+
+        let mut found = found
+            .into_iter()
+            .map(|Reverse(scored)| scored)
+            .collect::<Vec<_>>();
+        found.sort_by(|a, b| b.cmp(a));
+        found
+    }
+
+    fn prune(&mut self, node: u32, layer: usize, limit: usize) {
+        let base = &self.vectors[node as usize];
+        let mut scored = self.links[node as usize][layer]
+            .iter()
+            .map(|&neighbor| Scored {
+                similarity: dot(base, &self.vectors[neighbor as usize]),
+                node: neighbor,
+            })
+            .collect::<Vec<_>>();
+        scored.sort_by(|a, b| b.cmp(a));
+        scored.truncate(limit);
+        self.links[node as usize][layer] = scored.into_iter().map(|s| s.node).collect();
+    }
+}
+
+fn max_neighbors(layer: usize) -> usize {
+    if layer == 0 {
+        MAX_NEIGHBORS * 2
+    } else {
+        MAX_NEIGHBORS
+    }
+}
+
+/// Exponentially distributed layer, derived from the id so rebuilding a
+/// partition gives the same graph shape.
+fn random_level(entity_id: &str) -> usize {
+    let mut hasher = DefaultHasher::new();
+    entity_id.hash(&mut hasher);
+    let unit = (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64;
+    let level = -(1.0 - unit).ln() / (MAX_NEIGHBORS as f64).ln();
+    (level as usize).min(MAX_LEVEL)
+}
+
+fn normalized(vector: &[f32]) -> Option<Vec<f32>> {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm == 0.0 || !norm.is_finite() {
+        return None;
+    }
+    Some(vector.iter().map(|v| v / norm).collect())
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dot, normalized, HnswGraph, VectorIndex, EXACT_SCAN_LIMIT};
+
+    const DIMS: usize = 16;
+
+    /// Deterministic vectors, so a failing recall run can be reproduced.
+    fn vectors(count: usize, seed: u64) -> Vec<Vec<f32>> {
+        let mut state = seed;
+        (0..count)
+            .map(|_| {
+                (0..DIMS)
+                    .map(|_| {
+                        state = state
+                            .wrapping_mul(6_364_136_223_846_793_005)
+                            .wrapping_add(1_442_695_040_888_963_407);
+                        ((state >> 33) as f32 / (1u64 << 31) as f32) - 0.5
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn brute_force(entries: &[(String, Vec<f32>)], query: &[f32], k: usize) -> Vec<String> {
+        let query = normalized(query).unwrap();
+        let mut scored = entries
+            .iter()
+            .map(|(id, vector)| (id.clone(), dot(&query, &normalized(vector).unwrap())))
+            .collect::<Vec<_>>();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.into_iter().take(k).map(|(id, _)| id).collect()
+    }
+
+    fn recall(graph: &HnswGraph, entries: &[(String, Vec<f32>)], queries: &[Vec<f32>]) -> f32 {
+        let k = 10;
+        let mut found = 0;
+        for query in queries {
+            let expected = brute_force(entries, query, k);
+            let hits = graph.search(query, k);
+            found += hits.iter().filter(|(id, _)| expected.contains(id)).count();
+        }
+        found as f32 / (queries.len() * k) as f32
+    }
+
+    fn build(count: usize) -> (HnswGraph, Vec<(String, Vec<f32>)>) {
+        let entries = vectors(count, 7)
+            .into_iter()
+            .enumerate()
+            .map(|(i, vector)| (format!("e{i}"), vector))
+            .collect::<Vec<_>>();
+        let mut graph = HnswGraph::new(DIMS);
+        for (id, vector) in &entries {
+            graph.insert(id, vector);
+        }
+        (graph, entries)
+    }
+
+    #[test]
+    fn small_partitions_match_brute_force_exactly() {
+        let (graph, entries) = build(500);
+        let queries = vectors(20, 99);
+        assert_eq!(recall(&graph, &entries, &queries), 1.0);
+    }
+
+    #[test]
+    fn graph_search_recall_matches_brute_force() {
+        let (graph, entries) = build(EXACT_SCAN_LIMIT + 200);
+        let queries = vectors(40, 99);
+        let recall = recall(&graph, &entries, &queries);
+        assert!(recall >= 0.9, "recall@10 was {recall}");
+    }
+
+    #[test]
+    fn removed_entities_are_never_returned() {
+        // Enough stay live that the graph, not the exact scan, is searched.
+        let (mut graph, mut entries) = build(EXACT_SCAN_LIMIT + 600);
+        let removed = entries
+            .iter()
+            .step_by(6)
+            .map(|(id, _)| id.clone())
+            .collect::<Vec<_>>();
+        for id in &removed {
+            graph.remove(id);
+        }
+        entries.retain(|(id, _)| !removed.contains(id));
+        assert!(entries.len() > EXACT_SCAN_LIMIT);
+
+        let queries = vectors(40, 99);
+        for query in &queries {
+            for (id, _) in graph.search(query, 10) {
+                assert!(!removed.contains(&id), "{id} was removed");
+            }
+        }
+        let recall = recall(&graph, &entries, &queries);
+        assert!(recall >= 0.9, "recall@10 after removal was {recall}");
+    }
+
+    #[test]
+    fn reinserting_an_entity_replaces_its_vector() {
+        let (mut graph, _) = build(100);
+        let target = vectors(1, 12_345).remove(0);
+        graph.insert("e5", &target);
+        let hits = graph.search(&target, 1);
+        assert_eq!(hits[0].0, "e5");
+        assert!((hits[0].1 - 1.0).abs() < 1e-5);
+        assert_eq!(graph.search(&target, 200).len(), 100);
+    }
+
+    #[test]
+    fn zero_and_mismatched_vectors_are_ignored() {
+        let mut graph = HnswGraph::new(DIMS);
+        graph.insert("zero", &[0.0; DIMS]);
+        graph.insert("short", &[1.0; 3]);
+        assert!(graph.search(&[1.0; DIMS], 5).is_empty());
+        assert!(graph.search(&[1.0; 3], 5).is_empty());
+    }
+
+    #[tokio::test]
+    async fn index_tracks_upserts_and_removals() {
+        let index = VectorIndex::default();
+        let query = vec![1.0; DIMS];
+        let empty = index
+            .search_or_load("docs", "u1", "chunk", &query, 5, async { Ok(Vec::new()) })
+            .await
+            .unwrap();
+        assert!(empty.is_empty());
+
+        index.upsert("docs", "u1", "chunk", "a", &query).await;
+        index.upsert("docs", "u2", "chunk", "b", &query).await;
+        let hits = index
+            .search_or_load("docs", "u1", "chunk", &query, 5, async { Ok(Vec::new()) })
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].0, "a");
+
+        index.remove("chunk", "a").await;
+        let hits = index
+            .search_or_load("docs", "u1", "chunk", &query, 5, async { Ok(Vec::new()) })
+            .await
+            .unwrap();
+        assert!(hits.is_empty());
+
+        index.upsert("docs", "u1", "chunk", "c", &query).await;
+        index.clear().await;
+        let reloaded = index
+            .search_or_load("docs", "u1", "chunk", &query, 5, async { Ok(Vec::new()) })
+            .await
+            .unwrap();
+        assert!(reloaded.is_empty());
+    }
+}
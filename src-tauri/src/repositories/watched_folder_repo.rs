@@ -0,0 +1,86 @@
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::db::models::WatchedFolder;
+use crate::error::AppError;
+
+#[derive(Clone)]
+pub struct WatchedFolderRepo {
+    read_pool: SqlitePool,
+    write_pool: SqlitePool,
+}
+
+impl WatchedFolderRepo {
+    pub fn with_pools(read_pool: SqlitePool, write_pool: SqlitePool) -> Self {
+        Self {
+            read_pool,
+            write_pool,
+        }
+    }
+
+    /// Watching a folder again re-activates it and moves it to `namespace`.
+    pub async fn upsert_folder(
+        &self,
+        user_id: &str,
+        path: &str,
+        namespace: &str,
+    ) -> Result<WatchedFolder, AppError> {
+        sqlx::query(
+            r#"
+            INSERT INTO watched_folders (id, user_id, path, namespace)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(user_id, path) DO UPDATE SET
+              namespace = excluded.namespace,
+              is_active = 1
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(user_id)
+        .bind(path)
+        .bind(namespace)
+        .execute(&self.write_pool)
+        .await?;
+
+        let row = sqlx::query_as::<_, WatchedFolder>(
+            "SELECT * FROM watched_folders WHERE user_id = ?1 AND path = ?2",
+        )
+        .bind(user_id)
+        .bind(path)
+        .fetch_one(&self.write_pool)
+        .await?;
+        Ok(row)
+    }
+
+    pub async fn list_active_folders(&self) -> Result<Vec<WatchedFolder>, AppError> {
+        let rows = sqlx::query_as::<_, WatchedFolder>(
+            "SELECT * FROM watched_folders WHERE is_active = 1 ORDER BY created_at",
+        )
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    pub async fn deactivate_folder(&self, id: &str) -> Result<(), AppError> {
+        let result = sqlx::query("UPDATE watched_folders SET is_active = 0 WHERE id = ?1")
+            .bind(id)
+            .execute(&self.write_pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "watched_folder".to_string(),
+                id: id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    pub async fn mark_scanned(&self, id: &str) -> Result<(), AppError> {
+        sqlx::query(
+            "UPDATE watched_folders SET last_scan_at = datetime('now','utc') WHERE id = ?1",
+        )
+        .bind(id)
+        .execute(&self.write_pool)
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::db::models::{NewWebhook, Webhook};
+use crate::error::AppError;
+
+#[derive(Clone)]
+pub struct WebhookRepo {
+    read_pool: SqlitePool,
+    write_pool: SqlitePool,
+}
+
+impl WebhookRepo {
+    pub fn with_pools(read_pool: SqlitePool, write_pool: SqlitePool) -> Self {
+        Self {
+            read_pool,
+            write_pool,
+        }
+    }
+
+    /// `events` is the JSON array to store; `secret` is already encrypted.
+    pub async fn create(
+        &self,
+        webhook: &NewWebhook,
+        events: &str,
+        secret: &str,
+    ) -> Result<Webhook, AppError> {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO webhooks (id, user_id, name, url, events, secret)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+        )
+        .bind(&id)
+        .bind(&webhook.user_id)
+        .bind(&webhook.name)
+        .bind(&webhook.url)
+        .bind(events)
+        .bind(secret)
+        .execute(&self.write_pool)
+        .await?;
+        self.get_required(&id).await
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<Webhook>, AppError> {
+        let row = sqlx::query_as::<_, Webhook>("SELECT * FROM webhooks WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&self.write_pool)
+            .await?;
+        Ok(row)
+    }
+
+    pub async fn get_required(&self, id: &str) -> Result<Webhook, AppError> {
+        self.get(id).await?.ok_or_else(|| AppError::NotFound {
+            entity: "webhook".to_string(),
+            id: id.to_string(),
+        })
+    }
+
+    pub async fn list(&self, user_id: &str) -> Result<Vec<Webhook>, AppError> {
+        let rows = sqlx::query_as::<_, Webhook>(
+            r#"
+            SELECT * FROM webhooks
+            WHERE user_id = ?1
+            ORDER BY is_enabled DESC, name COLLATE NOCASE
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Enabled webhooks of `user_id`, or of every user when `None`; callers
+    /// filter by event.
+    pub async fn list_enabled(&self, user_id: Option<&str>) -> Result<Vec<Webhook>, AppError> {
+        let rows = sqlx::query_as::<_, Webhook>(
+            "SELECT * FROM webhooks WHERE is_enabled = 1 AND (?1 IS NULL OR user_id = ?1)",
+        )
+        .bind(user_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Replaces the name, URL and events; the secret and delivery history stay.
+    pub async fn update(
+        &self,
+        id: &str,
+        webhook: &NewWebhook,
+        events: &str,
+    ) -> Result<Webhook, AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE webhooks
+            SET name = ?2, url = ?3, events = ?4
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(&webhook.name)
+        .bind(&webhook.url)
+        .bind(events)
+        .execute(&self.write_pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "webhook".to_string(),
+                id: id.to_string(),
+            });
+        }
+        self.get_required(id).await
+    }
+
+    pub async fn set_enabled(&self, id: &str, enabled: bool) -> Result<Webhook, AppError> {
+        let result = sqlx::query("UPDATE webhooks SET is_enabled = ?2 WHERE id = ?1")
+            .bind(id)
+            .bind(enabled)
+            .execute(&self.write_pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "webhook".to_string(),
+                id: id.to_string(),
+            });
+        }
+        self.get_required(id).await
+    }
+
+    /// `secret` is already encrypted.
+    pub async fn set_secret(&self, id: &str, secret: &str) -> Result<(), AppError> {
+        let result = sqlx::query("UPDATE webhooks SET secret = ?2 WHERE id = ?1")
+            .bind(id)
+            .bind(secret)
+            .execute(&self.write_pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "webhook".to_string(),
+                id: id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Deliveries already queued are dropped when they come up.
+    pub async fn delete(&self, id: &str) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM webhooks WHERE id = ?1")
+            .bind(id)
+            .execute(&self.write_pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "webhook".to_string(),
+                id: id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Records how the latest delivery attempt went; `error` is `None` on success.
+    pub async fn record_delivery(&self, id: &str, error: Option<&str>) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE webhooks
+            SET last_delivery_at = datetime('now','utc'),
+                last_status = CASE WHEN ?2 IS NULL THEN 'succeeded' ELSE 'failed' END,
+                last_error = ?2
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(error)
+        .execute(&self.write_pool)
+        .await?;
+        Ok(())
+    }
+}
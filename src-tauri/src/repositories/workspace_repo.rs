@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::db::models::{NewWorkspace, Session, Workspace};
+use crate::error::AppError;
+use crate::services::rag_service::DEFAULT_NAMESPACE;
+
+#[derive(Clone)]
+pub struct WorkspaceRepo {
+    read_pool: SqlitePool,
+    write_pool: SqlitePool,
+}
+
+impl WorkspaceRepo {
+    pub fn with_pools(read_pool: SqlitePool, write_pool: SqlitePool) -> Self {
+        Self {
+            read_pool,
+            write_pool,
+        }
+    }
+
+    /// Expects `workspace.rag_namespace` to be resolved already.
+    pub async fn create_workspace(&self, workspace: NewWorkspace) -> Result<Workspace, AppError> {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO workspaces (id, user_id, name, description, rag_namespace, preset, routing_rules)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+        )
+        .bind(&id)
+        .bind(&workspace.user_id)
+        .bind(&workspace.name)
+        .bind(&workspace.description)
+        .bind(workspace.rag_namespace.as_deref().unwrap_or(DEFAULT_NAMESPACE))
+        .bind(&workspace.preset)
+        .bind(encode_routing_rules(&workspace.routing_rules))
+        .execute(&self.write_pool)
+        .await?;
+
+        self.get_workspace(&id)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "workspace".to_string(),
+                id,
+            })
+    }
+
+    pub async fn get_workspace(&self, id: &str) -> Result<Option<Workspace>, AppError> {
+        let row = sqlx::query_as::<_, Workspace>("SELECT * FROM workspaces WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&self.read_pool)
+            .await?;
+        Ok(row)
+    }
+
+    pub async fn list_workspaces(&self, user_id: &str) -> Result<Vec<Workspace>, AppError> {
+        let rows = sqlx::query_as::<_, Workspace>(
+            "SELECT * FROM workspaces WHERE user_id = ?1 ORDER BY is_active DESC, name COLLATE NOCASE",
+        )
+        .bind(user_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// `None` leaves a field unchanged; an empty `preset` clears it.
+    pub async fn update_workspace(
+        &self,
+        id: &str,
+        name: Option<&str>,
+        description: Option<&str>,
+        rag_namespace: Option<&str>,
+        preset: Option<&str>,
+        routing_rules: Option<&HashMap<String, String>>,
+    ) -> Result<Workspace, AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE workspaces
+            SET name = COALESCE(?2, name),
+                description = COALESCE(?3, description),
+                rag_namespace = COALESCE(?4, rag_namespace),
+                preset = CASE WHEN ?5 IS NULL THEN preset ELSE NULLIF(?5, '') END,
+                routing_rules = COALESCE(?6, routing_rules)
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(name)
+        .bind(description)
+        .bind(rag_namespace)
+        .bind(preset)
+        .bind(routing_rules.map(encode_routing_rules))
+        .execute(&self.write_pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "workspace".to_string(),
+                id: id.to_string(),
+            });
+        }
+
+        self.get_workspace(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "workspace".to_string(),
+                id: id.to_string(),
+            })
+    }
+
+    /// Sessions in the workspace are kept and simply become unassigned.
+    pub async fn delete_workspace(&self, id: &str) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM workspaces WHERE id = ?1")
+            .bind(id)
+            .execute(&self.write_pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound {
+                entity: "workspace".to_string(),
+                id: id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    pub async fn active_workspace(&self, user_id: &str) -> Result<Option<Workspace>, AppError> {
+        let row = sqlx::query_as::<_, Workspace>(
+            "SELECT * FROM workspaces WHERE user_id = ?1 AND is_active = 1 LIMIT 1",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.read_pool)
+        .await?;
+        Ok(row)
+    }
+
+    /// Makes `workspace_id` the user's only active workspace, or leaves none
+    /// active when it is `None`.
+    pub async fn set_active_workspace(
+        &self,
+        user_id: &str,
+        workspace_id: Option<&str>,
+    ) -> Result<(), AppError> {
+        let mut tx = self.write_pool.begin().await?;
+        sqlx::query("UPDATE workspaces SET is_active = 0 WHERE user_id = ?1 AND is_active = 1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        if let Some(id) = workspace_id {
+            let result =
+                sqlx::query("UPDATE workspaces SET is_active = 1 WHERE id = ?1 AND user_id = ?2")
+                    .bind(id)
+                    .bind(user_id)
+                    .execute(&mut *tx)
+                    .await?;
+            if result.rows_affected() == 0 {
+                return Err(AppError::NotFound {
+                    entity: "workspace".to_string(),
+                    id: id.to_string(),
+                });
+            }
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// The workspace a session belongs to, falling back to the user's active
+    /// one for unassigned sessions or when there is no session at all.
+    pub async fn workspace_for_session(
+        &self,
+        user_id: &str,
+        session_id: Option<&str>,
+    ) -> Result<Option<Workspace>, AppError> {
+        if let Some(session_id) = session_id {
+            let assigned = sqlx::query_as::<_, Workspace>(
+                r#"
+                SELECT w.* FROM workspaces w
+                JOIN sessions s ON s.workspace_id = w.id
+                WHERE s.id = ?1
+                "#,
+            )
+            .bind(session_id)
+            .fetch_optional(&self.read_pool)
+            .await?;
+            if assigned.is_some() {
+                return Ok(assigned);
+            }
+        }
+        self.active_workspace(user_id).await
+    }
+
+    pub async fn list_workspace_sessions(
+        &self,
+        workspace_id: &str,
+        limit: i64,
+    ) -> Result<Vec<Session>, AppError> {
+        let rows = sqlx::query_as::<_, Session>(
+            r#"
+            SELECT * FROM sessions
+            WHERE workspace_id = ?1 AND status != 'deleted'
+            ORDER BY datetime(last_message_at) DESC, datetime(created_at) DESC
+            LIMIT ?2
+            "#,
+        )
+        .bind(workspace_id)
+        .bind(limit)
+        .fetch_all(&self.read_pool)
+        .await?;
+        Ok(rows)
+    }
+}
+
+pub fn decode_routing_rules(raw: &str) -> HashMap<String, String> {
+    serde_json::from_str(raw).unwrap_or_default()
+}
+
+fn encode_routing_rules(rules: &HashMap<String, String>) -> String {
+    serde_json::to_string(rules).unwrap_or_else(|_| "{}".to_string())
+}
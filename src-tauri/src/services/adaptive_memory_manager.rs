@@ -5,8 +5,20 @@ use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 use tokio::time::interval;
 
-use crate::services::hardware_service::HardwareService;
+use crate::services::embedding_service::EmbeddingService;
+use crate::services::hardware_service::{DeviceTier, HardwareService};
 use crate::services::inference_service::InferenceService;
+use crate::services::mcp_service::McpService;
+use crate::services::reranker_service::RerankerService;
+use crate::state::AppCache;
+
+// Resident-size estimates for subsystems that don't report their own footprint.
+const EMBEDDING_SESSION_MB: u64 = 130;
+const RERANKER_SESSION_MB: u64 = 1_100;
+const MCP_CHILD_MB: u64 = 80;
+const KV_CACHE_MB_PER_1K_CTX: u64 = 64;
+const VECTOR_ENTRY_BYTES: u64 = 1_600;
+const CACHE_ENTRY_BYTES: u64 = 4_096;
 
 #[derive(Clone)]
 pub struct AdaptiveMemoryManager {
@@ -21,6 +33,9 @@ pub struct AdaptiveMemoryManager {
     unload_cooldown_secs: u64,
     total_unloads: Arc<AtomicU64>,
     total_loads: Arc<AtomicU64>,
+    budget: Option<MemoryBudget>,
+    last_usage: Arc<std::sync::Mutex<Option<MemoryUsage>>>,
+    total_evictions: Arc<AtomicU64>,
 }
 
 impl AdaptiveMemoryManager {
@@ -37,6 +52,9 @@ impl AdaptiveMemoryManager {
             unload_cooldown_secs: 30, // Drop from 120s down to 30s for aggressive pruning
             total_unloads: Arc::new(AtomicU64::new(0)),
             total_loads: Arc::new(AtomicU64::new(0)),
+            budget: None,
+            last_usage: Arc::new(std::sync::Mutex::new(None)),
+            total_evictions: Arc::new(AtomicU64::new(0)),
         }
     }
 
@@ -60,9 +78,17 @@ impl AdaptiveMemoryManager {
             unload_cooldown_secs: 90,
             total_unloads: Arc::new(AtomicU64::new(0)),
             total_loads: Arc::new(AtomicU64::new(0)),
+            budget: None,
+            last_usage: Arc::new(std::sync::Mutex::new(None)),
+            total_evictions: Arc::new(AtomicU64::new(0)),
         }
     }
 
+    pub fn with_budget(mut self, budget: MemoryBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
     pub fn set_enabled(&self, enabled: bool) {
         self.enabled.store(enabled, Ordering::Relaxed);
     }
@@ -89,6 +115,33 @@ impl AdaptiveMemoryManager {
         let is_low_pressure =
             memory_pct <= self.memory_threshold_low && cpu_pct <= self.cpu_threshold_low;
 
+        if let Some(budget) = self.budget.as_ref() {
+            let usage = budget.measure(&self.inference).await;
+            if let Ok(mut last) = self.last_usage.lock() {
+                *last = Some(usage.clone());
+            }
+
+            let over_budget = usage.total_mb > usage.cap_mb;
+            if over_budget || memory_pct >= self.memory_threshold_high {
+                let target_mb = if over_budget {
+                    usage.cap_mb * 9 / 10
+                } else {
+                    usage.total_mb * 3 / 4
+                };
+                let (mut evicted, remaining_mb) = budget.evict_until(&usage, target_mb);
+                if remaining_mb > target_mb && usage.model_mb > 0 {
+                    if let MemoryAction::Unloaded(path) = self.maybe_unload_model().await {
+                        evicted.push(format!("model ({path})"));
+                    }
+                }
+                if !evicted.is_empty() {
+                    self.total_evictions
+                        .fetch_add(evicted.len() as u64, Ordering::Relaxed);
+                    return MemoryAction::Evicted(evicted);
+                }
+            }
+        }
+
         if is_high_pressure {
             return self.maybe_unload_model().await;
         }
@@ -107,18 +160,15 @@ impl AdaptiveMemoryManager {
             }
         }
 
-        let active_model = self.inference.get_active_model_info().await;
-        if let Some(model) = active_model {
+        // Resident models go least recently used first; the active one last.
+        if let Some(path) = self.inference.evict_least_recent_model() {
             tracing::info!(
-                "Adaptive memory manager unloading model under pressure: {}",
-                model.path
+                "Adaptive memory manager unloaded model under pressure: {}",
+                path
             );
-
-            if self.inference.unload_model().await.is_ok() {
-                *last_unload = Some(Instant::now());
-                self.total_unloads.fetch_add(1, Ordering::Relaxed);
-                return MemoryAction::Unloaded(model.path);
-            }
+            *last_unload = Some(Instant::now());
+            self.total_unloads.fetch_add(1, Ordering::Relaxed);
+            return MemoryAction::Unloaded(path);
         }
 
         MemoryAction::None
@@ -154,6 +204,12 @@ impl AdaptiveMemoryManager {
                     MemoryAction::Loaded(name) => {
                         tracing::info!("Adaptive memory monitor loaded model: {}", name);
                     }
+                    MemoryAction::Evicted(subsystems) => {
+                        tracing::info!(
+                            "Adaptive memory monitor evicted to stay within budget: {}",
+                            subsystems.join(", ")
+                        );
+                    }
                     MemoryAction::None => {}
                 }
             }
@@ -169,6 +225,8 @@ impl AdaptiveMemoryManager {
             memory_threshold_low: self.memory_threshold_low,
             cpu_threshold_high: self.cpu_threshold_high,
             cpu_threshold_low: self.cpu_threshold_low,
+            total_evictions: self.total_evictions.load(Ordering::Relaxed),
+            budget: self.last_usage.lock().ok().and_then(|usage| usage.clone()),
         }
     }
 }
@@ -179,6 +237,7 @@ pub enum MemoryAction {
     None,
     Unloaded(String),
     Loaded(String),
+    Evicted(Vec<String>),
 }
 
 #[derive(Clone, Debug, serde::Serialize)]
@@ -191,4 +250,158 @@ pub struct MemoryManagerStats {
     pub memory_threshold_low: f64,
     pub cpu_threshold_high: f64,
     pub cpu_threshold_low: f64,
+    pub total_evictions: u64,
+    pub budget: Option<MemoryUsage>,
+}
+
+/// Estimated resident memory per subsystem against the tier-derived cap.
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryUsage {
+    pub model_mb: u64,
+    pub caches_mb: u64,
+    pub embedding_mb: u64,
+    pub reranker_mb: u64,
+    pub mcp_mb: u64,
+    pub total_mb: u64,
+    pub cap_mb: u64,
+}
+
+/// Shared view over the memory-hungry subsystems so evictions can be targeted
+/// instead of always dropping the chat model first.
+#[derive(Clone)]
+pub struct MemoryBudget {
+    cap_mb: u64,
+    cache: Arc<AppCache>,
+    embedding: Option<Arc<EmbeddingService>>,
+    reranker: Option<Arc<RerankerService>>,
+    mcp: Arc<McpService>,
+}
+
+impl MemoryBudget {
+    pub fn new(
+        tier: DeviceTier,
+        total_ram_mb: u64,
+        cache: Arc<AppCache>,
+        embedding: Option<Arc<EmbeddingService>>,
+        reranker: Option<Arc<RerankerService>>,
+        mcp: Arc<McpService>,
+    ) -> Self {
+        let share = match tier {
+            DeviceTier::Ultra => 0.60,
+            DeviceTier::High => 0.50,
+            DeviceTier::Medium => 0.40,
+            DeviceTier::Low => 0.30,
+            DeviceTier::Minimal => 0.25,
+            DeviceTier::Potato => 0.20,
+        };
+
+        Self {
+            cap_mb: ((total_ram_mb as f64) * share).round() as u64,
+            cache,
+            embedding,
+            reranker,
+            mcp,
+        }
+    }
+
+    pub fn cap_mb(&self) -> u64 {
+        self.cap_mb
+    }
+
+    pub async fn measure(&self, inference: &InferenceService) -> MemoryUsage {
+        let model_mb = inference
+            .resident_models()
+            .iter()
+            .map(|info| {
+                let file_mb = std::fs::metadata(&info.path)
+                    .map(|meta| meta.len() / (1024 * 1024))
+                    .unwrap_or(0);
+                file_mb + (info.context_length as u64 / 1024) * KV_CACHE_MB_PER_1K_CTX
+            })
+            .sum();
+
+        let vector_entries = self.cache.text_embeddings.entry_count()
+            + self
+                .embedding
+                .as_ref()
+                .map(|service| service.cached_vectors())
+                .unwrap_or(0);
+        let other_entries = self.cache.hardware_profile.entry_count()
+            + self.cache.model_list.entry_count()
+            + self.cache.user_settings.entry_count()
+            + self.cache.session_metadata.entry_count()
+            + self.cache.recent_memories.entry_count()
+            + self.cache.mcp_tool_schemas.entry_count();
+        let caches_mb = (vector_entries * VECTOR_ENTRY_BYTES + other_entries * CACHE_ENTRY_BYTES)
+            / (1024 * 1024);
+
+        let embedding_mb = match self.embedding.as_ref() {
+            Some(service) if service.is_loaded() => EMBEDDING_SESSION_MB,
+            _ => 0,
+        };
+        let reranker_mb = match self.reranker.as_ref() {
+            Some(service) if service.is_loaded() => RERANKER_SESSION_MB,
+            _ => 0,
+        };
+        let mcp_mb = self.mcp.connection_count() as u64 * MCP_CHILD_MB;
+
+        MemoryUsage {
+            model_mb,
+            caches_mb,
+            embedding_mb,
+            reranker_mb,
+            mcp_mb,
+            total_mb: model_mb + caches_mb + embedding_mb + reranker_mb + mcp_mb,
+            cap_mb: self.cap_mb,
+        }
+    }
+
+    /// Frees the cheapest-to-rebuild subsystems first and stops once the estimate
+    /// drops to `target_mb`; returns what was evicted and the remaining estimate so the
+    /// caller can decide whether the chat model has to go too.
+    fn evict_until(&self, usage: &MemoryUsage, target_mb: u64) -> (Vec<String>, u64) {
+        let mut remaining = usage.total_mb;
+        let mut evicted = Vec::new();
+
+        if remaining > target_mb && usage.caches_mb > 0 {
+            self.cache.text_embeddings.invalidate_all();
+            self.cache.recent_memories.invalidate_all();
+            self.cache.session_metadata.invalidate_all();
+            if let Some(service) = self.embedding.as_ref() {
+                service.clear_cache();
+            }
+            remaining = remaining.saturating_sub(usage.caches_mb);
+            evicted.push("caches".to_string());
+        }
+
+        if remaining > target_mb && usage.mcp_mb > 0 {
+            let mcp = Arc::clone(&self.mcp);
+            tokio::spawn(async move {
+                let _ = mcp.cleanup_idle_connections(Duration::from_secs(60)).await;
+            });
+            remaining = remaining.saturating_sub(usage.mcp_mb);
+            evicted.push("mcp".to_string());
+        }
+
+        if remaining > target_mb {
+            if let Some(service) = self.reranker.as_ref() {
+                if service.unload() {
+                    remaining = remaining.saturating_sub(usage.reranker_mb);
+                    evicted.push("reranker".to_string());
+                }
+            }
+        }
+
+        if remaining > target_mb {
+            if let Some(service) = self.embedding.as_ref() {
+                if service.unload() {
+                    remaining = remaining.saturating_sub(usage.embedding_mb);
+                    evicted.push("embedding".to_string());
+                }
+            }
+        }
+
+        (evicted, remaining)
+    }
 }
@@ -15,6 +15,7 @@ impl AnalyticsService {
         Self { repo }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn log_inference(
         &self,
         session_id: Option<String>,
@@ -23,6 +24,7 @@ impl AnalyticsService {
         tokens_in: Option<i64>,
         tokens_out: Option<i64>,
         tokens_per_sec: Option<f64>,
+        first_token_ms: Option<i64>,
         success: bool,
         error_code: Option<String>,
     ) -> Result<(), AppError> {
@@ -51,6 +53,7 @@ impl AnalyticsService {
                 success,
                 error_code,
                 metadata: None,
+                first_token_ms,
             })
             .await
     }
@@ -87,6 +90,7 @@ impl AnalyticsService {
                 success,
                 error_code: None,
                 metadata,
+                first_token_ms: None,
             })
             .await
     }
@@ -1,33 +1,327 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
-use tauri::Emitter;
+use notify::{RecursiveMode, Watcher};
+use tauri::{Emitter, Manager};
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
 
+use crate::db::models::{BackgroundJob, WatchedFolder, WatchedFolderStatus};
 use crate::error::AppError;
 use crate::repositories::conversation_repo::ConversationRepo;
+use crate::repositories::job_repo::{JobRepo, JOB_TIME_FORMAT};
+use crate::repositories::scheduled_prompt_repo::ScheduledPromptRepo;
 use crate::repositories::system_repo::SystemRepo;
+use crate::repositories::watched_folder_repo::WatchedFolderRepo;
 use crate::services::analytics_service::AnalyticsService;
-use crate::services::conversation_service::ConversationService;
+use crate::services::conversation_service::{ConversationService, DEFAULT_MAX_TOOL_ROUNDS};
 use crate::services::hardware_service::HardwareService;
+use crate::services::history_search::HistorySearchService;
 use crate::services::mcp_service::McpService;
 use crate::services::memory_service::MemoryService;
-use crate::services::rag_service::RagService;
+use crate::services::notification_service::{NotificationCategory, NotificationService};
+use crate::services::rag_service::{self, RagService};
 use crate::services::recommendation_service::RecommendationService;
+use crate::services::retention_service::RetentionService;
+use crate::services::webhook_service::{WebhookService, JOB_WEBHOOK_DELIVERY};
+
+/// Sessions are re-summarized at most this often once they gain new messages.
+const SUMMARY_STALE_HOURS: i64 = 6;
+/// A session must be quiet this long before it is summarized.
+const SUMMARY_IDLE_MINUTES: i64 = 20;
+const SUMMARY_BATCH_SIZE: i64 = 10;
+/// Sessions are titled once their second exchange is stored.
+const TITLE_MIN_REPLIES: i64 = 2;
+const TITLE_BATCH_SIZE: i64 = 5;
+const TITLE_INTERVAL: Duration = Duration::from_secs(30);
+const SESSION_TITLE_UPDATED_EVENT: &str = "session:title-updated";
+/// Messages embedded per tick for history search; catches imports and older chats.
+const HISTORY_INDEX_BATCH_SIZE: i64 = 64;
+const HISTORY_INDEX_INTERVAL: Duration = Duration::from_secs(60 * 5);
+/// Memory consolidation checks this often for the user being away, and runs
+/// at most once per `MEMORY_CONSOLIDATION_MIN_GAP`.
+const MEMORY_CONSOLIDATION_INTERVAL: Duration = Duration::from_secs(60 * 5);
+const MEMORY_CONSOLIDATION_MIN_GAP: Duration = Duration::from_secs(60 * 60 * 12);
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60 * 6);
+const RETENTION_APPLIED_EVENT: &str = "retention:applied";
+/// How often the dispatcher looks for due jobs.
+const JOB_DISPATCH_INTERVAL: Duration = Duration::from_secs(15);
+const JOB_CLAIM_BATCH: i64 = 4;
+/// A failed run is retried after this, doubling per attempt.
+const JOB_RETRY_BASE: Duration = Duration::from_secs(60);
+/// Jobs that are due but held back by load or activity look again after this.
+const JOB_DEFER_DELAY: Duration = Duration::from_secs(30);
+/// Finished and failed jobs stay listed this long, then are deleted.
+const JOB_HISTORY_DAYS: i64 = 7;
+const JOB_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const JOBS_CHANGED_EVENT: &str = "jobs:changed";
+pub const JOB_AUTO_MODEL_UPGRADE: &str = "auto_model_upgrade";
+pub const JOB_REFRESH_RECOMMENDATIONS: &str = "refresh_recommendations";
+pub const JOB_SCHEDULED_PROMPT: &str = "scheduled_prompt";
+const SCHEDULED_PROMPT_BATCH: i64 = 8;
+/// A failed run is tried once more; its prompt is then sent a second time.
+const SCHEDULED_PROMPT_ATTEMPTS: i64 = 2;
+const SCHEDULED_PROMPT_RAN_EVENT: &str = "scheduled-prompts:ran";
+/// Sundays at 03:00 UTC (seconds, minutes, hours, day, month, weekday).
+const RECOMMENDATION_REFRESH_CRON: &str = "0 0 3 * * Sun";
+/// A crashed server waits at most one tick before its first restart attempt.
+const MCP_HEALTH_INTERVAL: Duration = Duration::from_secs(30);
+const MCP_HEALTH_CHANGED_EVENT: &str = "mcp://health-changed";
+/// Editors often save as write-temp-then-rename; events for a file within this
+/// window of the first one are synced once.
+const FOLDER_WATCH_DEBOUNCE: Duration = Duration::from_millis(1500);
+const FOLDER_WATCH_STATUS_EVENT: &str = "rag:watch-status";
+/// Dependency and build directories are never indexed from a watched folder.
+const FOLDER_WATCH_SKIPPED_DIRS: &[&str] = &["node_modules", "target", "__pycache__", "venv"];
+
+enum JobOutcome {
+    Done,
+    /// Due but held back; queued again without using up an attempt.
+    Deferred(String),
+}
+
+fn parse_cron(cron: &str) -> Result<cron::Schedule, AppError> {
+    cron::Schedule::from_str(cron).map_err(|error| AppError::Validation {
+        field: "cron".to_string(),
+        message: format!("Invalid cron expression '{cron}': {error}"),
+    })
+}
+
+/// When a cron expression next fires, in `JOB_TIME_FORMAT`.
+fn next_cron_run(cron: &str) -> Result<String, AppError> {
+    parse_cron(cron)?
+        .upcoming(chrono::Utc)
+        .next()
+        .map(|next| next.format(JOB_TIME_FORMAT).to_string())
+        .ok_or_else(|| AppError::Validation {
+            field: "cron".to_string(),
+            message: format!("Cron expression '{cron}' never fires"),
+        })
+}
+
+/// Like `next_cron_run`, but the expression is read in the machine's local
+/// time so "9am" means the user's 9am. The result is still UTC.
+pub fn next_local_cron_run(cron: &str) -> Result<String, AppError> {
+    parse_cron(cron)?
+        .upcoming(chrono::Local)
+        .next()
+        .map(|next| {
+            next.with_timezone(&chrono::Utc)
+                .format(JOB_TIME_FORMAT)
+                .to_string()
+        })
+        .ok_or_else(|| AppError::Validation {
+            field: "cron".to_string(),
+            message: format!("Cron expression '{cron}' never fires"),
+        })
+}
+
+const WEEKDAYS: [(&str, &str); 7] = [
+    ("monday", "Mon"),
+    ("tuesday", "Tue"),
+    ("wednesday", "Wed"),
+    ("thursday", "Thu"),
+    ("friday", "Fri"),
+    ("saturday", "Sat"),
+    ("sunday", "Sun"),
+];
+
+/// Turns a schedule as a user would say it ("every Monday at 9am",
+/// "weekdays 8:30", "daily", "hourly") into a six-field cron expression.
+/// Six-field cron expressions pass through; five-field crontab ones get a
+/// zero seconds field and their weekdays spelled out.
+pub fn schedule_to_cron(schedule: &str) -> Result<String, AppError> {
+    let schedule = schedule.trim();
+    let fields = schedule.split_whitespace().collect::<Vec<_>>();
+    if fields.len() >= 5 && schedule.starts_with(|c: char| c.is_ascii_digit() || c == '*') {
+        let cron = if fields.len() == 5 {
+            let weekdays = crontab_weekdays(fields[4]).ok_or_else(|| AppError::Validation {
+                field: "schedule".to_string(),
+                message: format!("Invalid day of week '{}' in '{schedule}'", fields[4]),
+            })?;
+            format!("0 {} {weekdays}", fields[..4].join(" "))
+        } else {
+            schedule.to_string()
+        };
+        parse_cron(&cron)?;
+        return Ok(cron);
+    }
+
+    let not_understood = || AppError::Validation {
+        field: "schedule".to_string(),
+        message: format!(
+            "Couldn't understand '{schedule}'; try \"every Monday at 9am\" or a cron expression"
+        ),
+    };
+    let lower = schedule.to_lowercase().replace(',', " ");
+    let mut days: Vec<&str> = Vec::new();
+    let mut every_day = false;
+    let mut hourly = false;
+    let mut time: Option<(u32, u32)> = None;
+    for token in lower.split_whitespace() {
+        match token {
+            "every" | "each" | "at" | "on" | "and" => {}
+            "day" | "days" | "daily" => every_day = true,
+            "hour" | "hourly" => hourly = true,
+            "weekday" | "weekdays" => days.push("Mon-Fri"),
+            "weekend" | "weekends" => days.extend(["Sat", "Sun"]),
+            "noon" => time = Some((12, 0)),
+            "midnight" => time = Some((0, 0)),
+            "am" | "pm" => {
+                let (hour, minute) = time.ok_or_else(not_understood)?;
+                time = Some((to_24_hour(hour, token).ok_or_else(not_understood)?, minute));
+            }
+            _ => {
+                let day = token.trim_end_matches('s');
+                if let Some((_, abbrev)) = WEEKDAYS
+                    .iter()
+                    .find(|(name, _)| day.len() >= 3 && name.starts_with(day))
+                {
+                    days.push(*abbrev);
+                } else {
+                    time = Some(parse_clock(token).ok_or_else(not_understood)?);
+                }
+            }
+        }
+    }
+
+    let weekdays = if days.is_empty() {
+        "*".to_string()
+    } else {
+        days.join(",")
+    };
+    let cron = if hourly {
+        if time.is_some() {
+            return Err(not_understood());
+        }
+        format!("0 0 * * * {weekdays}")
+    } else {
+        if days.is_empty() && !every_day && time.is_none() {
+            return Err(not_understood());
+        }
+        // A day without a time means the morning.
+        let (hour, minute) = time.unwrap_or((9, 0));
+        format!("0 {minute} {hour} * * {weekdays}")
+    };
+    parse_cron(&cron)?;
+    Ok(cron)
+}
+
+/// Crontab numbers weekdays 0-7 from Sunday (both 0 and 7 are Sunday), the
+/// `cron` crate 1-7, so numbers would fire a day late. Names mean the same in
+/// both, so the field is rewritten as the list of days it selects.
+fn crontab_weekdays(field: &str) -> Option<String> {
+    if field.starts_with('*') || field == "?" {
+        return Some(field.to_string());
+    }
+    // Indexed from Sunday, as crontab counts.
+    let mut selected = [false; 7];
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, step.parse::<usize>().ok().filter(|&s| s > 0)?),
+            None => (item, 1),
+        };
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => {
+                let start = crontab_weekday(start)?;
+                // "Fri-Sun" runs to the Sunday that ends the week.
+                let end = match crontab_weekday(end)? {
+                    0 if start > 0 => 7,
+                    end => end,
+                };
+                (start, end)
+            }
+            None => {
+                let day = crontab_weekday(range)?;
+                (day, if step > 1 { 6 } else { day })
+            }
+        };
+        if start > end {
+            return None;
+        }
+        for day in (start..=end).step_by(step) {
+            selected[day % 7] = true;
+        }
+    }
+    Some(
+        WEEKDAYS
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| selected[(index + 1) % 7])
+            .map(|(_, (_, abbrev))| *abbrev)
+            .collect::<Vec<_>>()
+            .join(","),
+    )
+}
+
+/// A crontab weekday, 0-7 or a name, counted from Sunday.
+fn crontab_weekday(token: &str) -> Option<usize> {
+    if let Ok(day) = token.parse::<usize>() {
+        return (day <= 7).then_some(day);
+    }
+    let token = token.to_lowercase();
+    WEEKDAYS
+        .iter()
+        .position(|(name, _)| token.len() >= 3 && name.starts_with(&token))
+        .map(|index| (index + 1) % 7)
+}
+
+/// "9", "9am", "9:30pm" or "21:30" as (hour, minute).
+fn parse_clock(token: &str) -> Option<(u32, u32)> {
+    let (clock, meridiem) = match token
+        .strip_suffix("am")
+        .or_else(|| token.strip_suffix("pm"))
+    {
+        Some(clock) => (clock, Some(&token[clock.len()..])),
+        None => (token, None),
+    };
+    let (hour, minute) = clock.split_once(':').unwrap_or((clock, "0"));
+    let hour: u32 = hour.parse().ok()?;
+    let minute: u32 = minute.parse().ok()?;
+    if hour > 23 || minute > 59 {
+        return None;
+    }
+    match meridiem {
+        Some(meridiem) => Some((to_24_hour(hour, meridiem)?, minute)),
+        None => Some((hour, minute)),
+    }
+}
+
+fn to_24_hour(hour: u32, meridiem: &str) -> Option<u32> {
+    match (hour, meridiem) {
+        (1..=11, "am") => Some(hour),
+        (12, "am") => Some(0),
+        (1..=11, "pm") => Some(hour + 12),
+        (12, "pm") => Some(12),
+        _ => None,
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum BackgroundTask {
     EmbedDocument(String),
     SummarizeSession(String),
+    TitleSession(String),
     RefreshRecommendations,
 }
 
+struct FolderWatch {
+    /// Dropping the watcher ends the OS subscription.
+    _watcher: notify::RecommendedWatcher,
+    token: CancellationToken,
+}
+
 #[derive(Clone)]
 pub struct BackgroundService {
     app_handle: tauri::AppHandle,
+    job_repo: JobRepo,
+    scheduled_prompt_repo: ScheduledPromptRepo,
+    notifications: NotificationService,
+    webhooks: WebhookService,
     mcp_service: McpService,
     memory_service: MemoryService,
     rag_service: Option<Arc<RagService>>,
@@ -37,6 +331,11 @@ pub struct BackgroundService {
     hardware_service: HardwareService,
     conversation_repo: ConversationRepo,
     system_repo: SystemRepo,
+    history_search: HistorySearchService,
+    retention: RetentionService,
+    watched_folder_repo: WatchedFolderRepo,
+    folder_watches: Arc<Mutex<HashMap<String, FolderWatch>>>,
+    folder_status: Arc<std::sync::Mutex<HashMap<String, WatchedFolderStatus>>>,
     tasks: Arc<Mutex<HashMap<String, JoinHandle<()>>>>,
     queue_tx: flume::Sender<BackgroundTask>,
     queue_rx: flume::Receiver<BackgroundTask>,
@@ -56,12 +355,23 @@ impl BackgroundService {
         hardware_service: HardwareService,
         conversation_repo: ConversationRepo,
         system_repo: SystemRepo,
+        history_search: HistorySearchService,
+        retention: RetentionService,
+        watched_folder_repo: WatchedFolderRepo,
+        job_repo: JobRepo,
+        scheduled_prompt_repo: ScheduledPromptRepo,
+        notifications: NotificationService,
+        webhooks: WebhookService,
         enabled: bool,
     ) -> Self {
         let (queue_tx, queue_rx) = flume::bounded(256);
 
         Self {
             app_handle,
+            job_repo,
+            scheduled_prompt_repo,
+            notifications,
+            webhooks,
             mcp_service,
             memory_service,
             rag_service,
@@ -71,6 +381,11 @@ impl BackgroundService {
             hardware_service,
             conversation_repo,
             system_repo,
+            history_search,
+            retention,
+            watched_folder_repo,
+            folder_watches: Arc::new(Mutex::new(HashMap::new())),
+            folder_status: Arc::new(std::sync::Mutex::new(HashMap::new())),
             tasks: Arc::new(Mutex::new(HashMap::new())),
             queue_tx,
             queue_rx,
@@ -83,8 +398,20 @@ impl BackgroundService {
         self.queue_tx.clone()
     }
 
+    /// Queues a recommendation recompute; duplicates are harmless and a full queue drops it.
+    pub fn request_recommendation_refresh(&self) {
+        let _ = self
+            .queue_tx
+            .try_send(BackgroundTask::RefreshRecommendations);
+    }
+
     pub async fn start_critical_tasks(&self) -> Result<(), AppError> {
         self.start_mcp_health_check_job().await;
+        // Jobs are ones the app or user asked for, so they run on every tier.
+        self.start_job_dispatcher().await;
+        // Folders are watched even on tiers without background jobs: the user
+        // asked for them explicitly.
+        self.resume_folder_watches().await;
 
         if self.enabled {
             self.start_worker().await;
@@ -112,10 +439,19 @@ impl BackgroundService {
     async fn start_secondary_tasks(&self) {
         self.start_session_summary_job().await;
         self.start_memory_decay_job().await;
+        self.start_memory_consolidation_job().await;
+        self.start_history_index_job().await;
+        self.start_session_title_job().await;
+        self.start_retention_job().await;
     }
 
     async fn start_background_tasks(&self) {
-        self.start_model_refresh_job().await;
+        if let Err(error) = self
+            .schedule_recurring(JOB_REFRESH_RECOMMENDATIONS, RECOMMENDATION_REFRESH_CRON)
+            .await
+        {
+            tracing::warn!("Failed to schedule recommendation refresh: {error}");
+        }
         self.start_analytics_aggregation_job().await;
     }
 
@@ -127,6 +463,7 @@ impl BackgroundService {
         let system_repo = self.system_repo.clone();
         let hardware = self.hardware_service.clone();
         let token = self.cancel_token.clone();
+        let app_handle = self.app_handle.clone();
 
         let handle = tokio::spawn(async move {
             loop {
@@ -147,7 +484,26 @@ impl BackgroundService {
                                     if is_pressure_high(&hardware) {
                                         continue;
                                     }
-                                    let _ = conv.summarize_session(&session_id).await;
+                                    if let Ok(true) = conv.summarize_session(&session_id).await {
+                                        let _ = app_handle.emit(
+                                            "session:summarized",
+                                            serde_json::json!({ "sessionId": session_id }),
+                                        );
+                                    }
+                                }
+                                BackgroundTask::TitleSession(session_id) => {
+                                    if is_pressure_high(&hardware) {
+                                        continue;
+                                    }
+                                    if let Ok(Some(title)) = conv.title_session(&session_id).await {
+                                        let _ = app_handle.emit(
+                                            SESSION_TITLE_UPDATED_EVENT,
+                                            serde_json::json!({
+                                                "sessionId": session_id,
+                                                "title": title,
+                                            }),
+                                        );
+                                    }
                                 }
                                 BackgroundTask::RefreshRecommendations => {
                                     if is_pressure_high(&hardware) {
@@ -155,7 +511,15 @@ impl BackgroundService {
                                     }
                                     if let Ok(Some(profile)) = system_repo.get_current_profile().await {
                                         let mode = hardware.get_performance_mode(None).await;
-                                        let _ = rec.recompute(&profile, mode).await;
+                                        if let Ok(recs) = rec.recompute(&profile, mode).await {
+                                            let _ = app_handle.emit(
+                                                "recommendations:updated",
+                                                serde_json::json!({
+                                                    "systemProfileId": profile.id,
+                                                    "count": recs.len(),
+                                                }),
+                                            );
+                                        }
                                     }
                                 }
                             },
@@ -169,6 +533,121 @@ impl BackgroundService {
         self.tasks.lock().await.insert("worker".to_string(), handle);
     }
 
+    /// Saves `path` as a watched folder and starts keeping it indexed into
+    /// `namespace`, beginning with a scan of what is already there.
+    pub async fn watch_folder(
+        &self,
+        user_id: &str,
+        path: &str,
+        namespace: &str,
+    ) -> Result<WatchedFolderStatus, AppError> {
+        let rag = self
+            .rag_service
+            .clone()
+            .ok_or_else(|| AppError::Validation {
+                field: "rag".to_string(),
+                message: "RAG service is not available".to_string(),
+            })?;
+        let folder = self
+            .watched_folder_repo
+            .upsert_folder(user_id, path, namespace)
+            .await?;
+        self.start_folder_watch(rag, folder).await
+    }
+
+    /// Stops watching; documents already indexed from the folder are kept.
+    pub async fn unwatch_folder(&self, folder_id: &str) -> Result<(), AppError> {
+        self.watched_folder_repo
+            .deactivate_folder(folder_id)
+            .await?;
+        if let Some(watch) = self.folder_watches.lock().await.remove(folder_id) {
+            watch.token.cancel();
+        }
+        if let Ok(mut statuses) = self.folder_status.lock() {
+            statuses.remove(folder_id);
+        }
+        Ok(())
+    }
+
+    pub fn watched_folders(&self) -> Vec<WatchedFolderStatus> {
+        let mut statuses = self
+            .folder_status
+            .lock()
+            .map(|statuses| statuses.values().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        statuses.sort_by(|a, b| a.path.cmp(&b.path));
+        statuses
+    }
+
+    async fn resume_folder_watches(&self) {
+        let Some(rag) = self.rag_service.clone() else {
+            return;
+        };
+        let folders = match self.watched_folder_repo.list_active_folders().await {
+            Ok(folders) => folders,
+            Err(error) => {
+                tracing::warn!("Failed to load watched folders: {error}");
+                return;
+            }
+        };
+        for folder in folders {
+            let path = folder.path.clone();
+            if let Err(error) = self.start_folder_watch(rag.clone(), folder).await {
+                tracing::warn!("Failed to resume watching {path}: {error}");
+            }
+        }
+    }
+
+    async fn start_folder_watch(
+        &self,
+        rag: Arc<RagService>,
+        folder: WatchedFolder,
+    ) -> Result<WatchedFolderStatus, AppError> {
+        let (tx, rx) = flume::unbounded::<PathBuf>();
+        let mut watcher =
+            notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+                if let Ok(event) = result {
+                    if !event.kind.is_access() {
+                        for path in event.paths {
+                            let _ = tx.send(path);
+                        }
+                    }
+                }
+            })
+            .map_err(|e| AppError::Io(format!("Failed to start folder watcher: {e}")))?;
+        watcher
+            .watch(Path::new(&folder.path), RecursiveMode::Recursive)
+            .map_err(|e| AppError::Io(format!("Failed to watch {}: {e}", folder.path)))?;
+
+        let token = self.cancel_token.child_token();
+        let previous = self.folder_watches.lock().await.insert(
+            folder.id.clone(),
+            FolderWatch {
+                _watcher: watcher,
+                token: token.clone(),
+            },
+        );
+        if let Some(previous) = previous {
+            previous.token.cancel();
+        }
+
+        let indexer = FolderIndexer {
+            rag,
+            queue_tx: self.queue_tx.clone(),
+            repo: self.watched_folder_repo.clone(),
+            statuses: self.folder_status.clone(),
+            app_handle: self.app_handle.clone(),
+            folder,
+        };
+        let status = indexer.reset_status();
+        let task_name = format!("folder_watch:{}", status.id);
+        let handle = tokio::spawn(indexer.run(rx, token));
+        if let Some(previous) = self.tasks.lock().await.insert(task_name, handle) {
+            previous.abort();
+        }
+        Ok(status)
+    }
+
     async fn start_memory_decay_job(&self) {
         let memory_service = self.memory_service.clone();
         let token = self.cancel_token.clone();
@@ -182,7 +661,15 @@ impl BackgroundService {
                         break;
                     }
                     _ = ticker.tick() => {
-                        let _ = memory_service.apply_decay_job("default").await;
+                        match memory_service.apply_decay_job("default").await {
+                            Ok(report) => tracing::info!(
+                                "Memory decay: {} faded, {} archived, {} expired",
+                                report.decayed,
+                                report.archived,
+                                report.expired
+                            ),
+                            Err(error) => tracing::warn!("Memory decay failed: {error}"),
+                        }
                     }
                 }
             }
@@ -194,13 +681,182 @@ impl BackgroundService {
             .insert("memory_decay".to_string(), handle);
     }
 
+    /// Merges near-duplicate memories, only while the user is away: it embeds
+    /// and compares hundreds of them.
+    async fn start_memory_consolidation_job(&self) {
+        let memory_service = self.memory_service.clone();
+        let analytics = self.analytics_service.clone();
+        let hardware = self.hardware_service.clone();
+        let token = self.cancel_token.clone();
+
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(MEMORY_CONSOLIDATION_INTERVAL);
+            let mut last_run: Option<std::time::Instant> = None;
+            loop {
+                tokio::select! {
+                    _ = token.cancelled() => {
+                        tracing::info!("Memory consolidation job shutting down");
+                        break;
+                    }
+                    _ = ticker.tick() => {
+                        if last_run.is_some_and(|at| at.elapsed() < MEMORY_CONSOLIDATION_MIN_GAP) {
+                            continue;
+                        }
+                        if hardware.user_active() || is_pressure_high(&hardware) {
+                            continue;
+                        }
+                        last_run = Some(std::time::Instant::now());
+                        match memory_service.consolidate_memories("default").await {
+                            Ok(report) => {
+                                if report.merged > 0 {
+                                    tracing::info!(
+                                        "Merged {} duplicate memories into {} clusters",
+                                        report.merged,
+                                        report.clusters
+                                    );
+                                }
+                                let metadata = serde_json::to_string(&report).ok();
+                                let _ = analytics
+                                    .log_event(
+                                        "memory_consolidation",
+                                        report.duration_ms,
+                                        true,
+                                        metadata,
+                                    )
+                                    .await;
+                            }
+                            Err(error) => {
+                                tracing::warn!("Memory consolidation failed: {error}");
+                                let _ = analytics
+                                    .log_event("memory_consolidation", 0, false, None)
+                                    .await;
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        self.tasks
+            .lock()
+            .await
+            .insert("memory_consolidation".to_string(), handle);
+    }
+
+    async fn start_retention_job(&self) {
+        let retention = self.retention.clone();
+        let hardware = self.hardware_service.clone();
+        let app_handle = self.app_handle.clone();
+        let token = self.cancel_token.clone();
+
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(RETENTION_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = token.cancelled() => {
+                        tracing::info!("Retention job shutting down");
+                        break;
+                    }
+                    _ = ticker.tick() => {
+                        if is_pressure_high(&hardware) {
+                            continue;
+                        }
+                        match retention.run().await {
+                            Ok(Some(report)) if !report.sessions.is_empty() => {
+                                let _ = app_handle.emit(RETENTION_APPLIED_EVENT, &report);
+                            }
+                            Ok(_) => {}
+                            Err(error) => tracing::warn!("Retention cleanup failed: {error}"),
+                        }
+                    }
+                }
+            }
+        });
+
+        self.tasks
+            .lock()
+            .await
+            .insert("retention".to_string(), handle);
+    }
+
+    async fn start_session_title_job(&self) {
+        let repo = self.conversation_repo.clone();
+        let hardware = self.hardware_service.clone();
+        let tx = self.queue_tx.clone();
+        let token = self.cancel_token.clone();
+
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(TITLE_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = token.cancelled() => {
+                        tracing::info!("Session title job shutting down");
+                        break;
+                    }
+                    _ = ticker.tick() => {
+                        if is_pressure_high(&hardware) {
+                            continue;
+                        }
+                        if let Ok(sessions) = repo
+                            .list_sessions_needing_title(TITLE_MIN_REPLIES, TITLE_BATCH_SIZE)
+                            .await
+                        {
+                            for session in sessions {
+                                let _ = tx.try_send(BackgroundTask::TitleSession(session.id));
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        self.tasks
+            .lock()
+            .await
+            .insert("session_title".to_string(), handle);
+    }
+
+    async fn start_history_index_job(&self) {
+        let history_search = self.history_search.clone();
+        let hardware = self.hardware_service.clone();
+        let token = self.cancel_token.clone();
+
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(HISTORY_INDEX_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = token.cancelled() => {
+                        tracing::info!("History index job shutting down");
+                        break;
+                    }
+                    _ = ticker.tick() => {
+                        if is_pressure_high(&hardware) {
+                            continue;
+                        }
+                        if let Err(error) = history_search
+                            .index_pending(HISTORY_INDEX_BATCH_SIZE)
+                            .await
+                        {
+                            tracing::debug!("History indexing skipped: {error}");
+                        }
+                    }
+                }
+            }
+        });
+
+        self.tasks
+            .lock()
+            .await
+            .insert("history_index".to_string(), handle);
+    }
+
     async fn start_mcp_health_check_job(&self) {
         let mcp_service = self.mcp_service.clone();
         let app_handle = self.app_handle.clone();
         let token = self.cancel_token.clone();
 
         let handle = tokio::spawn(async move {
-            let mut ticker = tokio::time::interval(Duration::from_secs(120));
+            let mut ticker = tokio::time::interval(MCP_HEALTH_INTERVAL);
             loop {
                 tokio::select! {
                     _ = token.cancelled() => {
@@ -208,8 +864,14 @@ impl BackgroundService {
                         break;
                     }
                     _ = ticker.tick() => {
-                        if let Ok(statuses) = mcp_service.health_check_all().await {
-                            let _ = app_handle.emit("mcp:health_changed", statuses);
+                        match mcp_service.probe_health().await {
+                            Ok(changed) if !changed.is_empty() => {
+                                let _ = app_handle.emit(MCP_HEALTH_CHANGED_EVENT, changed);
+                            }
+                            Ok(_) => {}
+                            Err(error) => {
+                                crate::log_warn!("sarah.mcp", "MCP health probe failed: {}", error);
+                            }
                         }
                         let _ = mcp_service
                             .cleanup_idle_connections(Duration::from_secs(300))
@@ -225,20 +887,126 @@ impl BackgroundService {
             .insert("mcp_health".to_string(), handle);
     }
 
-    async fn start_model_refresh_job(&self) {
-        let tx = self.queue_tx.clone();
-        let token = self.cancel_token.clone();
+    /// Queues a job to run once, after `delay` when given.
+    pub async fn enqueue_job(
+        &self,
+        job_type: &str,
+        payload: serde_json::Value,
+        delay: Option<Duration>,
+        max_attempts: i64,
+    ) -> Result<BackgroundJob, AppError> {
+        let run_at = delay.map(|delay| {
+            (chrono::Utc::now() + chrono::Duration::from_std(delay).unwrap_or_default())
+                .format(JOB_TIME_FORMAT)
+                .to_string()
+        });
+        let job = self
+            .job_repo
+            .enqueue(
+                job_type,
+                &payload.to_string(),
+                None,
+                run_at.as_deref(),
+                max_attempts,
+            )
+            .await?;
+        self.notify_jobs_changed();
+        Ok(job)
+    }
+
+    /// Keeps one job of `job_type` on a cron schedule; runs it now the first
+    /// time. Does nothing if one is already queued.
+    pub async fn schedule_recurring(
+        &self,
+        job_type: &str,
+        cron: &str,
+    ) -> Result<BackgroundJob, AppError> {
+        next_cron_run(cron)?;
+        if let Some(job) = self.job_repo.active_of_type(job_type).await? {
+            return Ok(job);
+        }
+        let job = self
+            .job_repo
+            .enqueue(job_type, "{}", Some(cron), None, 3)
+            .await?;
+        self.notify_jobs_changed();
+        Ok(job)
+    }
+
+    pub async fn list_jobs(
+        &self,
+        status: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<BackgroundJob>, AppError> {
+        self.job_repo.list(status, limit.clamp(1, 500)).await
+    }
+
+    pub async fn cancel_job(&self, id: &str) -> Result<BackgroundJob, AppError> {
+        let job = self.job_repo.cancel(id).await?;
+        self.notify_jobs_changed();
+        Ok(job)
+    }
+
+    pub async fn retry_job(&self, id: &str) -> Result<BackgroundJob, AppError> {
+        let job = self.job_repo.retry(id).await?;
+        self.notify_jobs_changed();
+        Ok(job)
+    }
+
+    fn notify_jobs_changed(&self) {
+        let _ = self.app_handle.emit(JOBS_CHANGED_EVENT, ());
+    }
+
+    async fn start_job_dispatcher(&self) {
+        match self.job_repo.requeue_interrupted().await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Re-queued {count} job(s) interrupted by shutdown"),
+            Err(error) => tracing::warn!("Failed to re-queue interrupted jobs: {error}"),
+        }
 
+        let service = self.clone();
+        let token = self.cancel_token.clone();
         let handle = tokio::spawn(async move {
-            let mut ticker = tokio::time::interval(Duration::from_secs(60 * 60 * 24 * 7));
+            let mut ticker = tokio::time::interval(JOB_DISPATCH_INTERVAL);
+            let mut prune_ticker = tokio::time::interval(JOB_PRUNE_INTERVAL);
             loop {
                 tokio::select! {
                     _ = token.cancelled() => {
-                        tracing::info!("Model refresh job shutting down");
+                        tracing::info!("Job dispatcher shutting down");
                         break;
                     }
+                    _ = prune_ticker.tick() => {
+                        service.prune_finished_jobs().await;
+                    }
                     _ = ticker.tick() => {
-                        let _ = tx.send(BackgroundTask::RefreshRecommendations);
+                        if let Err(error) = service.queue_due_scheduled_prompts().await {
+                            tracing::warn!("Failed to queue scheduled prompts: {error}");
+                        }
+                        let jobs = match service.job_repo.claim_due(JOB_CLAIM_BATCH).await {
+                            Ok(jobs) => jobs,
+                            Err(error) => {
+                                tracing::warn!("Failed to claim due jobs: {error}");
+                                continue;
+                            }
+                        };
+                        if jobs.is_empty() {
+                            continue;
+                        }
+                        for job in jobs {
+                            // A scheduled prompt can sit on a tool approval
+                            // for minutes; run it on its own task so webhook
+                            // deliveries and other due jobs keep going.
+                            if job.job_type == JOB_SCHEDULED_PROMPT {
+                                let service = service.clone();
+                                tokio::spawn(async move {
+                                    service.dispatch(job).await;
+                                    service.notify_jobs_changed();
+                                });
+                            } else {
+                                service.dispatch(job).await;
+                            }
+                        }
+                        service.notify_jobs_changed();
                     }
                 }
             }
@@ -247,11 +1015,252 @@ impl BackgroundService {
         self.tasks
             .lock()
             .await
-            .insert("model_refresh".to_string(), handle);
+            .insert("job_dispatcher".to_string(), handle);
+    }
+
+    /// Webhook deliveries and other one-off jobs would otherwise pile up in
+    /// the jobs table forever.
+    async fn prune_finished_jobs(&self) {
+        let cutoff = (chrono::Utc::now() - chrono::Duration::days(JOB_HISTORY_DAYS))
+            .format(JOB_TIME_FORMAT)
+            .to_string();
+        match self.job_repo.prune_finished(&cutoff).await {
+            Ok(0) => {}
+            Ok(count) => {
+                tracing::info!("Deleted {count} finished job(s)");
+                self.notify_jobs_changed();
+            }
+            Err(error) => tracing::warn!("Failed to delete finished jobs: {error}"),
+        }
+    }
+
+    /// Runs one claimed job and records how it went.
+    async fn dispatch(&self, job: BackgroundJob) {
+        let outcome = match job.job_type.as_str() {
+            JOB_AUTO_MODEL_UPGRADE => self.run_auto_model_upgrade(&job).await,
+            JOB_SCHEDULED_PROMPT => self.run_scheduled_prompt(&job).await,
+            JOB_WEBHOOK_DELIVERY => self.webhooks.deliver(&job).await.map(|_| JobOutcome::Done),
+            JOB_REFRESH_RECOMMENDATIONS => {
+                let _ = self
+                    .queue_tx
+                    .try_send(BackgroundTask::RefreshRecommendations);
+                Ok(JobOutcome::Done)
+            }
+            other => Err(AppError::Validation {
+                field: "job_type".to_string(),
+                message: format!("Unknown job type: {other}"),
+            }),
+        };
+
+        let recorded = match outcome {
+            Ok(JobOutcome::Done) => {
+                let next_run_at = job
+                    .cron
+                    .as_deref()
+                    .and_then(|cron| next_cron_run(cron).ok());
+                self.job_repo
+                    .complete(&job.id, next_run_at.as_deref())
+                    .await
+            }
+            Ok(JobOutcome::Deferred(reason)) => {
+                let run_at = (chrono::Utc::now()
+                    + chrono::Duration::from_std(JOB_DEFER_DELAY).unwrap_or_default())
+                .format(JOB_TIME_FORMAT)
+                .to_string();
+                self.job_repo.defer(&job.id, &run_at, &reason).await
+            }
+            Err(error) => {
+                tracing::warn!("Job {} ({}) failed: {error}", job.id, job.job_type);
+                let retry_at = (job.attempts < job.max_attempts).then(|| {
+                    let backoff = JOB_RETRY_BASE * 2u32.pow((job.attempts.max(1) - 1) as u32);
+                    (chrono::Utc::now() + chrono::Duration::from_std(backoff).unwrap_or_default())
+                        .format(JOB_TIME_FORMAT)
+                        .to_string()
+                });
+                self.job_repo
+                    .fail(&job.id, &error.to_string(), retry_at.as_deref())
+                    .await
+            }
+        };
+        if let Err(error) = recorded {
+            tracing::warn!("Failed to record job {} outcome: {error}", job.id);
+        }
+    }
+
+    /// Downloads the quality model picked at setup, once the machine is calm
+    /// and the user has stepped away.
+    async fn run_auto_model_upgrade(&self, job: &BackgroundJob) -> Result<JobOutcome, AppError> {
+        let payload: serde_json::Value =
+            serde_json::from_str(&job.payload).unwrap_or(serde_json::Value::Null);
+        let target_id = payload
+            .get("targetModelId")
+            .and_then(|value| value.as_str())
+            .ok_or_else(|| AppError::Validation {
+                field: "payload".to_string(),
+                message: "Upgrade job has no targetModelId".to_string(),
+            })?
+            .to_string();
+        let user_id = payload.get("userId").and_then(|value| value.as_str());
+
+        let Some(state) = self.app_handle.try_state::<Arc<crate::state::AppState>>() else {
+            return Ok(JobOutcome::Deferred("App is still starting".to_string()));
+        };
+        let state = Arc::clone(&state);
+        let policy = state
+            .runtime_governor
+            .get_policy(user_id)
+            .await
+            .unwrap_or_default();
+        let stats = state.runtime_governor.current_stats();
+        let pressure = state.runtime_governor.classify_pressure(&stats, &policy);
+        if !matches!(pressure.as_str(), "normal" | "warm") {
+            return Ok(JobOutcome::Deferred(format!(
+                "System pressure is {pressure}"
+            )));
+        }
+        // A multi-gigabyte download waits for the user to step away.
+        if !state.runtime_governor.is_user_idle(&stats, &policy) {
+            return Ok(JobOutcome::Deferred("User is active".to_string()));
+        }
+
+        let target_name = payload
+            .get("targetModelName")
+            .and_then(|value| value.as_str())
+            .unwrap_or(&target_id)
+            .to_string();
+        crate::commands::model_commands::start_model_download_inner(
+            self.app_handle.clone(),
+            state,
+            target_id,
+        )
+        .await?;
+        self.notifications
+            .notify(
+                NotificationCategory::ModelUpgrades,
+                "Upgrading your model",
+                &format!("Downloading {target_name} in the background."),
+            )
+            .await;
+        Ok(JobOutcome::Done)
+    }
+
+    /// Queues a run for each scheduled prompt that has come due and moves it
+    /// on to its next slot. Slots missed while the app was closed run once.
+    async fn queue_due_scheduled_prompts(&self) -> Result<(), AppError> {
+        let due = self
+            .scheduled_prompt_repo
+            .list_due(SCHEDULED_PROMPT_BATCH)
+            .await?;
+        for prompt in due {
+            let next_run_at = next_local_cron_run(&prompt.cron).ok();
+            self.scheduled_prompt_repo
+                .set_next_run(&prompt.id, next_run_at.as_deref())
+                .await?;
+            self.job_repo
+                .enqueue(
+                    JOB_SCHEDULED_PROMPT,
+                    &serde_json::json!({ "scheduledPromptId": prompt.id }).to_string(),
+                    None,
+                    None,
+                    SCHEDULED_PROMPT_ATTEMPTS,
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Sends a scheduled prompt into its own session, tools on offer, and
+    /// shows the reply as a desktop notification.
+    async fn run_scheduled_prompt(&self, job: &BackgroundJob) -> Result<JobOutcome, AppError> {
+        let payload: serde_json::Value =
+            serde_json::from_str(&job.payload).unwrap_or(serde_json::Value::Null);
+        let id = payload
+            .get("scheduledPromptId")
+            .and_then(|value| value.as_str())
+            .ok_or_else(|| AppError::Validation {
+                field: "payload".to_string(),
+                message: "Scheduled prompt job has no scheduledPromptId".to_string(),
+            })?;
+        // Deleted since the run was queued.
+        let Some(scheduled) = self.scheduled_prompt_repo.get(id).await? else {
+            return Ok(JobOutcome::Done);
+        };
+
+        let session_id = match scheduled.session_id.clone() {
+            Some(session_id) => session_id,
+            None => {
+                let session = self
+                    .conversation_repo
+                    .create_session(&scheduled.user_id, None)
+                    .await?;
+                self.conversation_repo
+                    .update_session_title(&session.id, &scheduled.name)
+                    .await?;
+                self.scheduled_prompt_repo
+                    .set_session(&scheduled.id, &session.id)
+                    .await?;
+                session.id
+            }
+        };
+
+        let app = self.app_handle.clone();
+        let reply = self
+            .conversation_service
+            .send_agent_message(
+                &scheduled.user_id,
+                &session_id,
+                &scheduled.prompt,
+                DEFAULT_MAX_TOOL_ROUNDS,
+                |_| {},
+                |request| {
+                    let _ = app.emit(crate::commands::mcp_commands::TOOL_APPROVAL_EVENT, request);
+                },
+            )
+            .await;
+        let reply = match reply {
+            Ok(reply) => reply,
+            Err(error) => {
+                let _ = self
+                    .scheduled_prompt_repo
+                    .record_run(&scheduled.id, Some(&error.to_string()))
+                    .await;
+                if job.attempts >= job.max_attempts {
+                    self.notifications
+                        .notify(
+                            NotificationCategory::ScheduledPrompts,
+                            &scheduled.name,
+                            &format!("Couldn't run this scheduled prompt: {error}"),
+                        )
+                        .await;
+                }
+                return Err(error);
+            }
+        };
+
+        self.scheduled_prompt_repo
+            .record_run(&scheduled.id, None)
+            .await?;
+        self.notifications
+            .notify(
+                NotificationCategory::ScheduledPrompts,
+                &scheduled.name,
+                &reply.content,
+            )
+            .await;
+        let _ = self.app_handle.emit(
+            SCHEDULED_PROMPT_RAN_EVENT,
+            serde_json::json!({
+                "scheduledPromptId": scheduled.id,
+                "sessionId": session_id,
+                "messageId": reply.id,
+            }),
+        );
+        Ok(JobOutcome::Done)
     }
 
     async fn start_session_summary_job(&self) {
         let repo = self.conversation_repo.clone();
+        let hardware = self.hardware_service.clone();
         let tx = self.queue_tx.clone();
         let token = self.cancel_token.clone();
 
@@ -264,11 +1273,19 @@ impl BackgroundService {
                         break;
                     }
                     _ = ticker.tick() => {
-                        if let Ok(sessions) = repo.list_sessions("default", 200, None).await {
+                        if is_pressure_high(&hardware) {
+                            continue;
+                        }
+                        if let Ok(sessions) = repo
+                            .list_sessions_needing_summary(
+                                SUMMARY_STALE_HOURS,
+                                SUMMARY_IDLE_MINUTES,
+                                SUMMARY_BATCH_SIZE,
+                            )
+                            .await
+                        {
                             for session in sessions {
-                                if session.message_count >= 20 {
-                                    let _ = tx.send(BackgroundTask::SummarizeSession(session.id));
-                                }
+                                let _ = tx.try_send(BackgroundTask::SummarizeSession(session.id));
                             }
                         }
                     }
@@ -328,6 +1345,10 @@ impl BackgroundService {
 }
 
 fn is_pressure_high(hardware: &HardwareService) -> bool {
+    // Held back by the governor's battery or thermal policy.
+    if hardware.background_paused() {
+        return true;
+    }
     let stats = hardware.live_stats();
     if stats.memory_total_mb == 0 {
         return stats.cpu_usage_pct >= 88.0;
@@ -335,3 +1356,367 @@ fn is_pressure_high(hardware: &HardwareService) -> bool {
     let memory_pct = (stats.memory_used_mb as f64 / stats.memory_total_mb as f64) * 100.0;
     stats.cpu_usage_pct >= 88.0 || memory_pct >= 88.0
 }
+
+/// Keeps one watched folder's documents in step with its files.
+struct FolderIndexer {
+    rag: Arc<RagService>,
+    queue_tx: flume::Sender<BackgroundTask>,
+    repo: WatchedFolderRepo,
+    statuses: Arc<std::sync::Mutex<HashMap<String, WatchedFolderStatus>>>,
+    app_handle: tauri::AppHandle,
+    folder: WatchedFolder,
+}
+
+impl FolderIndexer {
+    async fn run(self, rx: flume::Receiver<PathBuf>, token: CancellationToken) {
+        tokio::select! {
+            _ = token.cancelled() => return,
+            _ = self.scan() => {}
+        }
+
+        loop {
+            let first = tokio::select! {
+                _ = token.cancelled() => break,
+                path = rx.recv_async() => match path {
+                    Ok(path) => path,
+                    Err(_) => break,
+                },
+            };
+
+            let mut pending = HashSet::from([first]);
+            let window = tokio::time::sleep(FOLDER_WATCH_DEBOUNCE);
+            tokio::pin!(window);
+            loop {
+                tokio::select! {
+                    _ = &mut window => break,
+                    path = rx.recv_async() => match path {
+                        Ok(path) => {
+                            pending.insert(path);
+                        }
+                        Err(_) => break,
+                    },
+                }
+            }
+
+            let now = chrono::Utc::now().to_rfc3339();
+            self.update_status(|status| {
+                status.state = "indexing".to_string();
+                status.pending_files = pending.len();
+                status.last_event_at = Some(now);
+            });
+            for path in pending {
+                if token.is_cancelled() {
+                    return;
+                }
+                self.sync_path(&path).await;
+                self.update_status(|status| {
+                    status.pending_files = status.pending_files.saturating_sub(1);
+                });
+            }
+            self.update_status(|status| status.state = "watching".to_string());
+        }
+    }
+
+    /// Indexes new and changed files, and drops documents whose files were
+    /// deleted while nothing was watching.
+    async fn scan(&self) {
+        let root = PathBuf::from(&self.folder.path);
+        if !root.is_dir() {
+            self.update_status(|status| {
+                status.state = "error".to_string();
+                status.last_error = Some("Folder not found".to_string());
+            });
+            return;
+        }
+
+        let files = tokio::task::spawn_blocking({
+            let root = root.clone();
+            move || collect_files(&root)
+        })
+        .await
+        .unwrap_or_default();
+        self.update_status(|status| {
+            status.state = "scanning".to_string();
+            status.pending_files = files.len();
+        });
+        for path in &files {
+            self.sync_path(path).await;
+            self.update_status(|status| {
+                status.pending_files = status.pending_files.saturating_sub(1);
+            });
+        }
+
+        match self
+            .rag
+            .indexed_files_under(&self.folder.user_id, &root)
+            .await
+        {
+            Ok(indexed) => {
+                for path in indexed.iter().filter(|path| !path.exists()) {
+                    self.sync_path(path).await;
+                }
+            }
+            Err(error) => tracing::warn!("Failed to list indexed files: {error}"),
+        }
+
+        if let Err(error) = self.repo.mark_scanned(&self.folder.id).await {
+            tracing::warn!("Failed to record folder scan: {error}");
+        }
+        let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        self.update_status(|status| {
+            status.state = "watching".to_string();
+            status.last_scan_at = Some(now);
+        });
+    }
+
+    async fn sync_path(&self, path: &Path) {
+        if is_ignored_path(Path::new(&self.folder.path), path) || path.is_dir() {
+            return;
+        }
+
+        let user_id = &self.folder.user_id;
+        let result = if path.is_file() {
+            if !rag_service::is_supported_file(path) {
+                return;
+            }
+            match self
+                .rag
+                .sync_file(user_id, path, &self.folder.namespace)
+                .await
+            {
+                Ok(Some(document_id)) => {
+                    let _ = self
+                        .queue_tx
+                        .send_async(BackgroundTask::EmbedDocument(document_id))
+                        .await;
+                    self.update_status(|status| status.indexed_files += 1);
+                    Ok(())
+                }
+                Ok(None) => Ok(()),
+                Err(error) => Err(error),
+            }
+        } else {
+            // A deleted directory arrives as one event for the directory itself.
+            self.remove_under(path).await
+        };
+
+        if let Err(error) = result {
+            tracing::warn!("Failed to index {}: {error}", path.display());
+            let message = format!("{}: {error}", path.display());
+            self.update_status(|status| {
+                status.failed_files += 1;
+                status.last_error = Some(message);
+            });
+        }
+    }
+
+    async fn remove_under(&self, path: &Path) -> Result<(), AppError> {
+        let user_id = &self.folder.user_id;
+        for file_path in self.rag.indexed_files_under(user_id, path).await? {
+            if !file_path.exists() && self.rag.remove_file(user_id, &file_path).await? {
+                self.update_status(|status| status.removed_files += 1);
+            }
+        }
+        Ok(())
+    }
+
+    fn reset_status(&self) -> WatchedFolderStatus {
+        let status = WatchedFolderStatus {
+            id: self.folder.id.clone(),
+            path: self.folder.path.clone(),
+            namespace: self.folder.namespace.clone(),
+            state: "scanning".to_string(),
+            pending_files: 0,
+            indexed_files: 0,
+            removed_files: 0,
+            failed_files: 0,
+            last_scan_at: self.folder.last_scan_at.clone(),
+            last_event_at: None,
+            last_error: None,
+        };
+        if let Ok(mut statuses) = self.statuses.lock() {
+            statuses.insert(status.id.clone(), status.clone());
+        }
+        status
+    }
+
+    fn update_status(&self, apply: impl FnOnce(&mut WatchedFolderStatus)) {
+        let status = {
+            let Ok(mut statuses) = self.statuses.lock() else {
+                return;
+            };
+            // Unwatched in the meantime.
+            let Some(status) = statuses.get_mut(&self.folder.id) else {
+                return;
+            };
+            apply(status);
+            status.clone()
+        };
+        let _ = self.app_handle.emit(FOLDER_WATCH_STATUS_EVENT, &status);
+    }
+}
+
+/// Supported files below `root`, skipping hidden entries and dependency directories.
+fn collect_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if is_ignored_path(root, &path) {
+                continue;
+            }
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => {
+                    let skipped = path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| FOLDER_WATCH_SKIPPED_DIRS.contains(&name));
+                    if !skipped {
+                        dirs.push(path);
+                    }
+                }
+                Ok(file_type) if file_type.is_file() => {
+                    if rag_service::is_supported_file(&path) {
+                        files.push(path);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Hidden files and directories inside the watched folder, editor backups and
+/// Office lock files.
+fn is_ignored_path(root: &Path, path: &Path) -> bool {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let hidden = relative.components().any(|component| {
+        component
+            .as_os_str()
+            .to_str()
+            .is_some_and(|name| name.starts_with('.'))
+    });
+    hidden
+        || path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| {
+                name.starts_with("~$")
+                    || name.ends_with('~')
+                    || name.ends_with(".tmp")
+                    || name.ends_with(".swp")
+            })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, Weekday};
+
+    use super::{parse_clock, parse_cron, schedule_to_cron};
+
+    fn next_weekday(cron: &str) -> Weekday {
+        parse_cron(cron)
+            .unwrap()
+            .upcoming(chrono::Utc)
+            .next()
+            .unwrap()
+            .weekday()
+    }
+
+    #[test]
+    fn phrases_become_cron() {
+        let cases = [
+            ("every Monday at 9am", "0 0 9 * * Mon"),
+            ("weekdays 8:30", "0 30 8 * * Mon-Fri"),
+            ("weekends at noon", "0 0 12 * * Sat,Sun"),
+            ("every tue and thu at 6:15pm", "0 15 18 * * Tue,Thu"),
+            ("daily", "0 0 9 * * *"),
+            ("every day at midnight", "0 0 0 * * *"),
+            ("hourly", "0 0 * * * *"),
+            ("Fridays", "0 0 9 * * Fri"),
+        ];
+        for (schedule, expected) in cases {
+            assert_eq!(schedule_to_cron(schedule).unwrap(), expected, "{schedule}");
+        }
+    }
+
+    #[test]
+    fn six_field_cron_passes_through() {
+        assert_eq!(schedule_to_cron("0 0 3 * * Sun").unwrap(), "0 0 3 * * Sun");
+        assert_eq!(
+            schedule_to_cron(" 0 */5 * * * * ").unwrap(),
+            "0 */5 * * * *"
+        );
+    }
+
+    #[test]
+    fn crontab_weekdays_count_from_sunday() {
+        let cases = [
+            ("30 9 * * *", "0 30 9 * * *"),
+            ("0 8 * * 0", "0 0 8 * * Sun"),
+            ("0 8 * * 7", "0 0 8 * * Sun"),
+            ("0 8 * * 1", "0 0 8 * * Mon"),
+            ("0 8 * * 6", "0 0 8 * * Sat"),
+            ("30 9 * * 1-5", "0 30 9 * * Mon,Tue,Wed,Thu,Fri"),
+            ("0 8 * * 5-7", "0 0 8 * * Fri,Sat,Sun"),
+            ("0 8 * * fri-sun", "0 0 8 * * Fri,Sat,Sun"),
+            ("0 8 * * 0,3", "0 0 8 * * Wed,Sun"),
+            ("0 8 * * 1-5/2", "0 0 8 * * Mon,Wed,Fri"),
+            ("0 8 * * */2", "0 0 8 * * */2"),
+        ];
+        for (schedule, expected) in cases {
+            assert_eq!(schedule_to_cron(schedule).unwrap(), expected, "{schedule}");
+        }
+
+        for (day, weekday) in [
+            (0, Weekday::Sun),
+            (1, Weekday::Mon),
+            (3, Weekday::Wed),
+            (6, Weekday::Sat),
+            (7, Weekday::Sun),
+        ] {
+            let cron = schedule_to_cron(&format!("0 8 * * {day}")).unwrap();
+            assert_eq!(next_weekday(&cron), weekday, "{day}");
+        }
+    }
+
+    #[test]
+    fn bad_schedules_are_refused() {
+        for schedule in [
+            "",
+            "sometime soon",
+            "hourly at 9am",
+            "every monday at 25:00",
+            "0 8 * * 8",
+            "0 8 * * 5-2",
+            "0 8 * * 1/0",
+            "0 8 * * someday",
+        ] {
+            assert!(schedule_to_cron(schedule).is_err(), "{schedule}");
+        }
+    }
+
+    #[test]
+    fn clock_times_parse() {
+        assert_eq!(parse_clock("9"), Some((9, 0)));
+        assert_eq!(parse_clock("9am"), Some((9, 0)));
+        assert_eq!(parse_clock("12am"), Some((0, 0)));
+        assert_eq!(parse_clock("12pm"), Some((12, 0)));
+        assert_eq!(parse_clock("9:30pm"), Some((21, 30)));
+        assert_eq!(parse_clock("21:30"), Some((21, 30)));
+        assert_eq!(parse_clock("0:05"), Some((0, 5)));
+    }
+
+    #[test]
+    fn bad_clock_times_are_refused() {
+        for token in ["24", "9:60", "13pm", "0am", "9:", ":30", "nine", "9:30:00"] {
+            assert_eq!(parse_clock(token), None, "{token}");
+        }
+    }
+}
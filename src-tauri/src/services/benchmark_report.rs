@@ -0,0 +1,91 @@
+use std::path::Path;
+
+use crate::db::models::{BenchmarkReport, SystemProfile};
+use crate::error::AppError;
+
+/// Writes the report as JSON when `path` ends in `.json`, Markdown otherwise.
+pub async fn write_report(report: &BenchmarkReport, path: &Path) -> Result<(), AppError> {
+    let is_json = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+
+    let body = if is_json {
+        serde_json::to_string_pretty(report)
+            .map_err(|e| AppError::Internal(format!("Failed to encode benchmark report: {e}")))?
+    } else {
+        render_markdown(report)
+    };
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, body).await?;
+    Ok(())
+}
+
+/// Shareable summary: hardware first, then one row per model, fastest first.
+pub fn render_markdown(report: &BenchmarkReport) -> String {
+    let mut out = String::new();
+    out.push_str("# Sarah model benchmark report\n\n");
+    out.push_str(&format!("Generated {}\n\n", report.generated_at));
+    out.push_str("## Hardware\n\n");
+    out.push_str(&hardware_lines(&report.hardware));
+
+    out.push_str("\n## Models\n\n");
+    if report.models.is_empty() {
+        out.push_str("No successful benchmark runs on this hardware yet.\n");
+        return out;
+    }
+
+    out.push_str(
+        "| Model | Params | Quant | Runs | Tokens/sec (avg) | Tokens/sec (best) | TTFT (ms) | Load (ms) | RAM in use (MB) |\n",
+    );
+    out.push_str("|---|---|---|---:|---:|---:|---:|---:|---:|\n");
+    for row in &report.models {
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} | {} | {} | {} |\n",
+            row.display_name.replace('|', "/"),
+            row.parameter_count.as_deref().unwrap_or("-"),
+            row.quantization.as_deref().unwrap_or("-"),
+            row.runs,
+            fmt_number(row.avg_tokens_per_sec, 1),
+            fmt_number(row.best_tokens_per_sec, 1),
+            fmt_number(row.avg_first_token_ms, 0),
+            fmt_number(row.avg_load_time_ms, 0),
+            fmt_number(row.avg_memory_used_mb, 0),
+        ));
+    }
+    out.push_str(
+        "\nTTFT is time to first token. RAM in use is system memory during the run, not the model alone.\n",
+    );
+    out
+}
+
+fn hardware_lines(profile: &SystemProfile) -> String {
+    let gpu = match (&profile.gpu_name, profile.gpu_vram_mb) {
+        (Some(name), Some(vram)) => format!("{name} ({vram} MB VRAM)"),
+        (Some(name), None) => name.clone(),
+        _ => "none detected".to_string(),
+    };
+    let backend = profile.gpu_backend.as_deref().unwrap_or("cpu");
+
+    format!(
+        "- CPU: {} ({} cores / {} threads)\n- RAM: {} MB\n- GPU: {}\n- Backend: {}\n- OS: {} {} ({})\n",
+        profile.cpu_brand,
+        profile.cpu_cores,
+        profile.cpu_threads,
+        profile.total_ram_mb,
+        gpu,
+        backend,
+        profile.os_name,
+        profile.os_version,
+        profile.os_arch,
+    )
+}
+
+fn fmt_number(value: Option<f64>, decimals: usize) -> String {
+    value
+        .map(|v| format!("{v:.decimals$}"))
+        .unwrap_or_else(|| "-".to_string())
+}
@@ -0,0 +1,162 @@
+use std::path::{Path, PathBuf};
+
+use image::imageops::FilterType;
+use image::RgbaImage;
+
+use crate::db::models::{Capture, NewCapture};
+use crate::error::AppError;
+use crate::repositories::capture_repo::CaptureRepo;
+
+/// Longest side of a library thumbnail, in pixels.
+const THUMBNAIL_SIZE: u32 = 320;
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+/// The history of screenshots and recordings. Rows point at files on disk;
+/// deleting a capture removes both.
+#[derive(Clone)]
+pub struct CaptureLibrary {
+    repo: CaptureRepo,
+}
+
+impl CaptureLibrary {
+    pub fn new(repo: CaptureRepo) -> Self {
+        Self { repo }
+    }
+
+    /// Adds a capture whose file is already written.
+    pub async fn record(&self, capture: NewCapture) -> Result<Capture, AppError> {
+        let file_size_bytes = tokio::fs::metadata(&capture.file_path)
+            .await
+            .ok()
+            .map(|metadata| metadata.len() as i64);
+        self.repo.insert_capture(&capture, file_size_bytes).await
+    }
+
+    /// Adds a PNG screenshot, generating its thumbnail first.
+    pub async fn record_screenshot(
+        &self,
+        path: &Path,
+        session_id: Option<&str>,
+    ) -> Result<Capture, AppError> {
+        let source = path.to_path_buf();
+        let (thumbnail, width, height) =
+            tokio::task::spawn_blocking(move || -> Result<_, AppError> {
+                let image = image::open(&source)
+                    .map_err(|error| AppError::Io(format!("Failed to read screenshot: {error}")))?
+                    .to_rgba8();
+                let thumbnail = thumbnail_path(&source);
+                write_thumbnail(&image, &thumbnail)?;
+                Ok((thumbnail, image.width(), image.height()))
+            })
+            .await
+            .map_err(|error| AppError::Internal(error.to_string()))??;
+
+        self.record(NewCapture {
+            session_id: session_id.map(str::to_string),
+            capture_type: "screenshot".to_string(),
+            file_path: path.to_string_lossy().to_string(),
+            mime_type: "image/png".to_string(),
+            thumbnail_path: Some(thumbnail.to_string_lossy().to_string()),
+            duration_ms: None,
+            width: Some(i64::from(width)),
+            height: Some(i64::from(height)),
+        })
+        .await
+    }
+
+    pub async fn list(
+        &self,
+        capture_type: Option<&str>,
+        session_id: Option<&str>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> Result<Vec<Capture>, AppError> {
+        if let Some(capture_type) = capture_type {
+            if !matches!(capture_type, "screenshot" | "recording") {
+                return Err(AppError::Validation {
+                    field: "capture_type".to_string(),
+                    message: "Expected `screenshot` or `recording`".to_string(),
+                });
+            }
+        }
+        let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+        let offset = offset.unwrap_or(0).max(0);
+        self.repo
+            .list_captures(capture_type, session_id, limit, offset)
+            .await
+    }
+
+    /// Removes the capture and its files; files already gone are ignored.
+    pub async fn delete(&self, id: &str) -> Result<(), AppError> {
+        let capture = self.repo.get_capture(id).await?;
+        for path in std::iter::once(&capture.file_path).chain(&capture.thumbnail_path) {
+            match tokio::fs::remove_file(path).await {
+                Ok(()) => {}
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+                Err(error) => {
+                    return Err(AppError::Io(format!("Failed to delete {path}: {error}")));
+                }
+            }
+        }
+        self.repo.delete_capture(id).await
+    }
+
+    /// Shows the capture's file selected in the system file manager.
+    pub async fn reveal(&self, id: &str) -> Result<(), AppError> {
+        let capture = self.repo.get_capture(id).await?;
+        if !tokio::fs::try_exists(&capture.file_path)
+            .await
+            .unwrap_or(false)
+        {
+            return Err(AppError::NotFound {
+                entity: "capture file".to_string(),
+                id: capture.file_path,
+            });
+        }
+        tauri_plugin_opener::reveal_item_in_dir(&capture.file_path)
+            .map_err(|error| AppError::Io(format!("Failed to reveal capture: {error}")))
+    }
+}
+
+/// Where a capture's thumbnail lives: next to it, as `<name>.thumb.png`.
+pub fn thumbnail_path(capture_path: &Path) -> PathBuf {
+    let stem = capture_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "capture".to_string());
+    capture_path.with_file_name(format!("{stem}.thumb.png"))
+}
+
+/// Writes a thumbnail of a captured BGRA frame.
+pub fn write_bgra_thumbnail(
+    bgra: &[u8],
+    width: u32,
+    height: u32,
+    path: &Path,
+) -> Result<(), AppError> {
+    let mut rgba = bgra.to_vec();
+    for pixel in rgba.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+    }
+    let image = RgbaImage::from_raw(width, height, rgba)
+        .ok_or_else(|| AppError::Internal("Captured frame had an unexpected size".to_string()))?;
+    write_thumbnail(&image, path)
+}
+
+fn write_thumbnail(image: &RgbaImage, path: &Path) -> Result<(), AppError> {
+    let scale = THUMBNAIL_SIZE as f32 / image.width().max(image.height()).max(1) as f32;
+    let thumbnail = if scale < 1.0 {
+        image::imageops::resize(
+            image,
+            ((image.width() as f32 * scale) as u32).max(1),
+            ((image.height() as f32 * scale) as u32).max(1),
+            FilterType::Triangle,
+        )
+    } else {
+        image.clone()
+    };
+    thumbnail
+        .save(path)
+        .map_err(|error| AppError::Io(format!("Failed to write thumbnail: {error}")))
+}
@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::Message;
+
+/// Prompt layouts for the model families Sarah runs. llama.cpp only exposes the
+/// GGUF's Jinja template as text, so the template is matched to one of these
+/// instead of being evaluated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatTemplate {
+    Llama3,
+    ChatMl,
+    Gemma,
+    Phi3,
+    Mistral,
+}
+
+impl ChatTemplate {
+    /// Picks the layout from the GGUF `tokenizer.chat_template`, then
+    /// `general.architecture`, then the file name. Unknown models get Llama 3,
+    /// the layout every model used before templates were detected.
+    pub fn detect(
+        chat_template: Option<&str>,
+        architecture: Option<&str>,
+        model_path: &str,
+    ) -> Self {
+        chat_template
+            .and_then(Self::from_template_text)
+            .or_else(|| architecture.and_then(Self::for_family))
+            .or_else(|| {
+                let file_name = std::path::Path::new(model_path)
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or(model_path);
+                Self::for_family(file_name)
+            })
+            .unwrap_or(ChatTemplate::Llama3)
+    }
+
+    /// Recognizes a Jinja chat template by the role markers it emits.
+    fn from_template_text(template: &str) -> Option<Self> {
+        if template.contains("<|start_header_id|>") {
+            Some(ChatTemplate::Llama3)
+        } else if template.contains("<|im_start|>") {
+            Some(ChatTemplate::ChatMl)
+        } else if template.contains("<start_of_turn>") {
+            Some(ChatTemplate::Gemma)
+        } else if template.contains("<|assistant|>") && template.contains("<|end|>") {
+            Some(ChatTemplate::Phi3)
+        } else if template.contains("[INST]") {
+            Some(ChatTemplate::Mistral)
+        } else {
+            None
+        }
+    }
+
+    /// Registry of family names (GGUF architectures or catalog families).
+    pub fn for_family(family: &str) -> Option<Self> {
+        let family = family.to_ascii_lowercase();
+        if family.contains("qwen") || family.contains("smollm") {
+            Some(ChatTemplate::ChatMl)
+        } else if family.contains("gemma") {
+            Some(ChatTemplate::Gemma)
+        } else if family.contains("phi") {
+            Some(ChatTemplate::Phi3)
+        } else if family.contains("mistral") || family.contains("mixtral") {
+            Some(ChatTemplate::Mistral)
+        } else if family.contains("llama") {
+            Some(ChatTemplate::Llama3)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChatTemplate::Llama3 => "llama3",
+            ChatTemplate::ChatMl => "chatml",
+            ChatTemplate::Gemma => "gemma",
+            ChatTemplate::Phi3 => "phi3",
+            ChatTemplate::Mistral => "mistral",
+        }
+    }
+
+    /// Renders the conversation and opens the assistant turn.
+    pub fn render(&self, messages: &[Message]) -> String {
+        match self {
+            ChatTemplate::Llama3 => render_llama3(messages),
+            ChatTemplate::ChatMl => render_chatml(messages),
+            ChatTemplate::Gemma => render_gemma(messages),
+            ChatTemplate::Phi3 => render_phi3(messages),
+            ChatTemplate::Mistral => render_mistral(messages),
+        }
+    }
+}
+
+fn render_llama3(messages: &[Message]) -> String {
+    let mut prompt = String::from("<|begin_of_text|>");
+    for message in messages {
+        let role = match message.role.as_str() {
+            "assistant" => "assistant",
+            "system" => "system",
+            // Llama 3.1 reads tool results from the ipython role.
+            "tool" => "ipython",
+            _ => "user",
+        };
+        prompt.push_str(&format!(
+            "<|start_header_id|>{role}<|end_header_id|>\n\n{}<|eot_id|>",
+            message.content.trim()
+        ));
+    }
+    prompt.push_str("<|start_header_id|>assistant<|end_header_id|>\n\n");
+    prompt
+}
+
+fn render_chatml(messages: &[Message]) -> String {
+    let mut prompt = String::new();
+    for message in messages {
+        let (role, content) = match message.role.as_str() {
+            "assistant" => ("assistant", message.content.trim().to_string()),
+            "system" => ("system", message.content.trim().to_string()),
+            // Qwen expects tool output as a user turn wrapped in <tool_response>.
+            "tool" => (
+                "user",
+                format!(
+                    "<tool_response>\n{}\n</tool_response>",
+                    message.content.trim()
+                ),
+            ),
+            _ => ("user", message.content.trim().to_string()),
+        };
+        prompt.push_str(&format!("<|im_start|>{role}\n{content}<|im_end|>\n"));
+    }
+    prompt.push_str("<|im_start|>assistant\n");
+    prompt
+}
+
+/// Gemma has no system role; system text is folded into the next user turn.
+fn render_gemma(messages: &[Message]) -> String {
+    let mut prompt = String::new();
+    let mut pending_system = Vec::new();
+    for message in messages {
+        let content = message.content.trim();
+        match message.role.as_str() {
+            "system" => pending_system.push(content),
+            "assistant" => {
+                prompt.push_str(&format!("<start_of_turn>model\n{content}<end_of_turn>\n"));
+            }
+            _ => {
+                let content = with_system_prefix(&mut pending_system, content);
+                prompt.push_str(&format!("<start_of_turn>user\n{content}<end_of_turn>\n"));
+            }
+        }
+    }
+    if !pending_system.is_empty() {
+        let content = with_system_prefix(&mut pending_system, "");
+        prompt.push_str(&format!("<start_of_turn>user\n{content}<end_of_turn>\n"));
+    }
+    prompt.push_str("<start_of_turn>model\n");
+    prompt
+}
+
+fn render_phi3(messages: &[Message]) -> String {
+    let mut prompt = String::new();
+    for message in messages {
+        let role = match message.role.as_str() {
+            "assistant" => "assistant",
+            "system" => "system",
+            _ => "user",
+        };
+        prompt.push_str(&format!("<|{role}|>\n{}<|end|>\n", message.content.trim()));
+    }
+    prompt.push_str("<|assistant|>\n");
+    prompt
+}
+
+/// Mistral instruct has no system role either; system text leads the next
+/// `[INST]` block. The BOS token is added by the tokenizer.
+fn render_mistral(messages: &[Message]) -> String {
+    let mut prompt = String::new();
+    let mut pending_system = Vec::new();
+    for message in messages {
+        let content = message.content.trim();
+        match message.role.as_str() {
+            "system" => pending_system.push(content),
+            "assistant" => prompt.push_str(&format!("{content}</s>")),
+            _ => {
+                let content = with_system_prefix(&mut pending_system, content);
+                prompt.push_str(&format!("[INST] {content} [/INST]"));
+            }
+        }
+    }
+    if !pending_system.is_empty() {
+        let content = with_system_prefix(&mut pending_system, "");
+        prompt.push_str(&format!("[INST] {content} [/INST]"));
+    }
+    prompt
+}
+
+fn with_system_prefix<'a>(pending_system: &mut Vec<&'a str>, content: &'a str) -> String {
+    if pending_system.is_empty() {
+        return content.to_string();
+    }
+    let mut parts = std::mem::take(pending_system);
+    if !content.is_empty() {
+        parts.push(content);
+    }
+    parts.join("\n\n")
+}
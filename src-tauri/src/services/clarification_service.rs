@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::db::models::{ClarificationCandidate, PendingClarification};
+
+/// A question left unanswered this long no longer applies to what the user types next.
+const CLARIFICATION_TTL: Duration = Duration::from_secs(5 * 60);
+
+const ORDINALS: &[&str] = &["first", "second", "third", "fourth", "fifth"];
+const CANCEL_REPLIES: &[&str] = &[
+    "cancel",
+    "never mind",
+    "nevermind",
+    "forget it",
+    "no",
+    "none",
+    "neither",
+];
+
+#[derive(Debug, Clone)]
+pub enum ClarificationOutcome {
+    Chosen {
+        pending: PendingClarification,
+        candidate: ClarificationCandidate,
+    },
+    Cancelled,
+    /// The reply wasn't an answer; the question is dropped and the reply handled normally.
+    Unrelated,
+}
+
+/// Holds at most one pending clarification per scope (a session, or the
+/// quick-prompt window) and matches the user's next reply against it.
+#[derive(Clone, Default)]
+pub struct ClarificationService {
+    pending: Arc<Mutex<HashMap<String, (PendingClarification, Instant)>>>,
+}
+
+impl ClarificationService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks a question, replacing anything already pending in the same scope.
+    pub fn hold(
+        &self,
+        scope: &str,
+        action: &str,
+        query: &str,
+        question: String,
+        candidates: Vec<ClarificationCandidate>,
+    ) -> PendingClarification {
+        let pending = PendingClarification {
+            id: uuid::Uuid::new_v4().to_string(),
+            scope: scope.to_string(),
+            action: action.to_string(),
+            query: query.to_string(),
+            question,
+            candidates,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        if let Ok(mut map) = self.pending.lock() {
+            map.insert(scope.to_string(), (pending.clone(), Instant::now()));
+        }
+        pending
+    }
+
+    pub fn get(&self, scope: &str) -> Option<PendingClarification> {
+        let mut map = self.pending.lock().ok()?;
+        match map.get(scope) {
+            Some((_, asked_at)) if asked_at.elapsed() > CLARIFICATION_TTL => {
+                map.remove(scope);
+                None
+            }
+            Some((pending, _)) => Some(pending.clone()),
+            None => None,
+        }
+    }
+
+    /// Matches a typed reply. Returns `None` when nothing is pending in `scope`.
+    pub fn answer(&self, scope: &str, reply: &str) -> Option<ClarificationOutcome> {
+        let pending = self.get(scope)?;
+        self.clear(scope);
+
+        let reply = normalize(reply);
+        if CANCEL_REPLIES.contains(&reply.as_str()) {
+            return Some(ClarificationOutcome::Cancelled);
+        }
+
+        Some(match pick_candidate(&reply, &pending.candidates) {
+            Some(index) => ClarificationOutcome::Chosen {
+                candidate: pending.candidates[index].clone(),
+                pending,
+            },
+            None => ClarificationOutcome::Unrelated,
+        })
+    }
+
+    /// Resolves a pick made from the candidate list (e.g. a button) by id.
+    pub fn choose(&self, clarification_id: &str, index: usize) -> Option<ClarificationOutcome> {
+        let pending =
+            self.take_by_id(clarification_id, |pending| index < pending.candidates.len())?;
+        let candidate = pending.candidates[index].clone();
+        Some(ClarificationOutcome::Chosen { pending, candidate })
+    }
+
+    pub fn cancel(&self, clarification_id: &str) -> bool {
+        self.take_by_id(clarification_id, |_| true).is_some()
+    }
+
+    pub fn clear(&self, scope: &str) {
+        if let Ok(mut map) = self.pending.lock() {
+            map.remove(scope);
+        }
+    }
+
+    /// Removes the clarification only if `accept` agrees, so a bad pick leaves it pending.
+    fn take_by_id(
+        &self,
+        clarification_id: &str,
+        accept: impl Fn(&PendingClarification) -> bool,
+    ) -> Option<PendingClarification> {
+        let mut map = self.pending.lock().ok()?;
+        let scope = map
+            .iter()
+            .find(|(_, (pending, asked_at))| {
+                pending.id == clarification_id
+                    && asked_at.elapsed() <= CLARIFICATION_TTL
+                    && accept(pending)
+            })
+            .map(|(scope, _)| scope.clone())?;
+        map.remove(&scope).map(|(pending, _)| pending)
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .split(|ch: char| !ch.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Accepts "2", "the second one", "the artist", "the song", or (part of) a title.
+fn pick_candidate(reply: &str, candidates: &[ClarificationCandidate]) -> Option<usize> {
+    if reply.is_empty() {
+        return None;
+    }
+    let words = reply.split_whitespace().collect::<Vec<_>>();
+
+    if let Some(number) = words.iter().find_map(|word| word.parse::<usize>().ok()) {
+        return number
+            .checked_sub(1)
+            .filter(|index| *index < candidates.len());
+    }
+    if let Some(index) = ORDINALS.iter().position(|ordinal| words.contains(ordinal)) {
+        return (index < candidates.len()).then_some(index);
+    }
+
+    let title_match = candidates.iter().position(|candidate| {
+        let title = normalize(&candidate.title);
+        !title.is_empty() && (reply == title || (reply.len() >= 3 && title.contains(reply)))
+    });
+    if title_match.is_some() {
+        return title_match;
+    }
+
+    // "the artist" / "the song" only decide it when exactly one candidate is of that kind.
+    let kind = if words.contains(&"artist") || words.contains(&"band") {
+        "artist"
+    } else if words.contains(&"song") || words.contains(&"track") {
+        "track"
+    } else {
+        return None;
+    };
+    let mut of_kind = candidates
+        .iter()
+        .enumerate()
+        .filter(|(_, candidate)| candidate.kind == kind);
+    match (of_kind.next(), of_kind.next()) {
+        (Some((index, _)), None) => Some(index),
+        _ => None,
+    }
+}
@@ -1,13 +1,55 @@
 use std::sync::Arc;
 
-use crate::db::models::{AssembledContext, Mcp, Message};
+use crate::db::models::{AssembledContext, Chunk, GenerationOptions, Mcp, Message, RetrievedChunk};
 use crate::error::AppError;
 use crate::repositories::conversation_repo::ConversationRepo;
+use crate::repositories::document_repo::DocumentRepo;
 use crate::repositories::model_repo::ModelRepo;
+use crate::repositories::persona_repo::PersonaRepo;
+use crate::repositories::settings_repo::SettingsRepo;
+use crate::repositories::workspace_repo::WorkspaceRepo;
+use crate::services::inference_service::{prompt_message, InferenceService};
 use crate::services::intent_service::IntentService;
+use crate::services::language_detector::{prefer_language, DetectedLanguage};
 use crate::services::mcp_service::McpService;
 use crate::services::memory_service::MemoryService;
-use crate::services::rag_service::RagService;
+use crate::services::rag_service::{RagService, DEFAULT_NAMESPACE};
+
+pub const ASSISTANT_SETTINGS_NAMESPACE: &str = "assistant";
+pub const DEFAULT_INSTRUCTIONS_KEY: &str = "default_instructions";
+pub const MAX_DEFAULT_INSTRUCTIONS_CHARS: usize = 4000;
+pub const USER_INSTRUCTIONS_KEY: &str = "user_instructions";
+/// Per-user instructions ride along with every turn, so they are kept small.
+pub const MAX_USER_INSTRUCTIONS_TOKENS: usize = 400;
+
+const SARAH_IDENTITY: &str = "You are Sarah, a highly capable local AI assistant.";
+
+/// Share of the ~3500-token window pinned items may take, leaving room for
+/// retrieval and history.
+const PINNED_CONTEXT_TOKEN_BUDGET: usize = 1200;
+
+/// Window assumed when no model is loaded to measure against.
+const FALLBACK_CONTEXT_TOKENS: usize = 3500;
+/// Kept free for the reply when fitting history into the window.
+const REPLY_RESERVE_TOKENS: usize = 1024;
+/// Role markers the chat template wraps around each message.
+const MESSAGE_OVERHEAD_TOKENS: usize = 8;
+/// History read per turn; anything older is covered by the rolling summary.
+const MAX_HISTORY_MESSAGES: i64 = 200;
+/// Turns older than the latest this many are folded into the summary even
+/// when they would fit.
+const MAX_VERBATIM_MESSAGES: usize = 24;
+/// The latest turns always stay verbatim.
+const MIN_VERBATIM_MESSAGES: usize = 4;
+const MAX_SUMMARY_TOKENS: usize = 256;
+/// Per-message cap on what the summarizer reads.
+const SUMMARY_SOURCE_CHARS: usize = 800;
+/// Longest bracket content read as a citation, e.g. "Doc 12" or "1, 2, 3".
+const MAX_CITATION_MARKER_CHARS: usize = 16;
+
+const SUMMARY_INSTRUCTIONS: &str = "Update the running summary of a conversation with the \
+new turns below. Keep names, facts, decisions and open questions; drop small talk. Reply \
+with the summary only, in at most 150 words.";
 
 #[derive(Clone)]
 pub struct ContextService {
@@ -16,7 +58,12 @@ pub struct ContextService {
     intent_service: IntentService,
     mcp_service: McpService,
     conversation_repo: ConversationRepo,
+    document_repo: DocumentRepo,
+    workspace_repo: WorkspaceRepo,
+    persona_repo: PersonaRepo,
     model_repo: ModelRepo,
+    settings_repo: SettingsRepo,
+    inference: Option<Arc<InferenceService>>,
 }
 
 impl ContextService {
@@ -26,7 +73,11 @@ impl ContextService {
         intent_service: IntentService,
         mcp_service: McpService,
         conversation_repo: ConversationRepo,
+        document_repo: DocumentRepo,
+        workspace_repo: WorkspaceRepo,
+        persona_repo: PersonaRepo,
         model_repo: ModelRepo,
+        settings_repo: SettingsRepo,
     ) -> Self {
         Self {
             memory_service,
@@ -34,36 +85,416 @@ impl ContextService {
             intent_service,
             mcp_service,
             conversation_repo,
+            document_repo,
+            workspace_repo,
+            persona_repo,
             model_repo,
+            settings_repo,
+            inference: None,
         }
     }
 
+    /// Counts tokens with the loaded model's tokenizer and summarizes history
+    /// that no longer fits its window.
+    pub fn with_inference(mut self, inference: Arc<InferenceService>) -> Self {
+        self.inference = Some(inference);
+        self
+    }
+
+    /// The user's global assistant instructions (tone, language, formatting), if set.
+    pub async fn default_instructions(&self) -> Result<Option<String>, AppError> {
+        Ok(self
+            .settings_repo
+            .get_setting(None, ASSISTANT_SETTINGS_NAMESPACE, DEFAULT_INSTRUCTIONS_KEY)
+            .await?
+            .map(|setting| setting.value.trim().to_string())
+            .filter(|value| !value.is_empty()))
+    }
+
+    /// Instructions `user_id` wrote for every conversation ("always respond in
+    /// Spanish"), if set.
+    pub async fn user_instructions(&self, user_id: &str) -> Result<Option<String>, AppError> {
+        Ok(self
+            .settings_repo
+            .get_setting(
+                Some(user_id),
+                ASSISTANT_SETTINGS_NAMESPACE,
+                USER_INSTRUCTIONS_KEY,
+            )
+            .await?
+            .map(|setting| setting.value.trim().to_string())
+            .filter(|value| !value.is_empty()))
+    }
+
+    /// Saves `user_id`'s instructions; an empty value clears them. Rejects text
+    /// over `MAX_USER_INSTRUCTIONS_TOKENS` by the loaded model's tokenizer.
+    pub async fn set_user_instructions(
+        &self,
+        user_id: &str,
+        text: &str,
+    ) -> Result<Option<String>, AppError> {
+        let text = text.trim();
+        if text.is_empty() {
+            self.settings_repo
+                .delete_setting(
+                    Some(user_id),
+                    ASSISTANT_SETTINGS_NAMESPACE,
+                    USER_INSTRUCTIONS_KEY,
+                )
+                .await?;
+            return Ok(None);
+        }
+
+        let tokens = self.count_tokens(text);
+        if tokens > MAX_USER_INSTRUCTIONS_TOKENS {
+            return Err(AppError::Validation {
+                field: "text".to_string(),
+                message: format!(
+                    "Instructions use {tokens} tokens; the limit is {MAX_USER_INSTRUCTIONS_TOKENS}"
+                ),
+            });
+        }
+
+        let setting = self
+            .settings_repo
+            .upsert_setting(
+                Some(user_id),
+                ASSISTANT_SETTINGS_NAMESPACE,
+                USER_INSTRUCTIONS_KEY,
+                text,
+                "string",
+                false,
+            )
+            .await?;
+        Ok(Some(setting.value))
+    }
+
+    /// Shows the system prompt exactly as it is assembled, with the per-turn blocks
+    /// left as placeholders. `draft` previews unsaved instructions; `user_id`
+    /// adds that user's own instructions.
+    pub async fn preview_system_prompt(
+        &self,
+        draft: Option<&str>,
+        user_id: Option<&str>,
+    ) -> Result<String, AppError> {
+        let instructions = match draft {
+            Some(draft) => Some(draft.trim().to_string()).filter(|value| !value.is_empty()),
+            None => self.default_instructions().await?,
+        };
+        let user_instructions = match user_id {
+            Some(user_id) => self.user_instructions(user_id).await?,
+            None => None,
+        };
+        let model_line = self.active_model_line().await?;
+
+        Ok(compose_system_prompt(
+            None,
+            user_instructions.as_deref(),
+            instructions.as_deref(),
+            None,
+            &model_line,
+            "(memories relevant to each message)",
+            "(documents retrieved for each message)",
+            "(tools routed for each message)",
+        ))
+    }
+
+    async fn active_model_line(&self) -> Result<String, AppError> {
+        let mut installed_models = self.model_repo.list_installed().await?;
+        let active_model = installed_models
+            .iter()
+            .find(|model| model.is_default == 1)
+            .cloned()
+            .or_else(|| installed_models.pop());
+
+        Ok(active_model
+            .map(|m| format!("Active model: {} ({})", m.display_name, m.name))
+            .unwrap_or_else(|| "Active model: none selected".to_string()))
+    }
+
+    /// Renders the session's pinned items in pin order, cut off once the pinned
+    /// budget is spent. Also returns the chunk ids covered so retrieval can skip them.
+    async fn pinned_context(&self, session_id: &str) -> (Vec<String>, Vec<String>) {
+        let pins = match self.conversation_repo.list_context_pins(session_id).await {
+            Ok(pins) => pins,
+            Err(error) => {
+                crate::log_warn!(
+                    "sarah.context",
+                    "Failed to load pinned context for {}: {}",
+                    session_id,
+                    error
+                );
+                return (Vec::new(), Vec::new());
+            }
+        };
+
+        let mut remaining = PINNED_CONTEXT_TOKEN_BUDGET * 4;
+        let mut lines = Vec::new();
+        let mut chunk_ids = Vec::new();
+        'pins: for pin in pins {
+            let parts = match (pin.kind.as_str(), &pin.document_id, &pin.chunk_id) {
+                ("document", Some(document_id), _) => self
+                    .document_repo
+                    .get_chunks_by_document(document_id)
+                    .await
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|chunk| (Some(chunk.id), chunk.content))
+                    .collect::<Vec<_>>(),
+                ("chunk", _, Some(chunk_id)) => self
+                    .document_repo
+                    .get_chunk(chunk_id)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|chunk| vec![(Some(chunk.id), chunk.content)])
+                    .unwrap_or_default(),
+                _ => pin
+                    .note
+                    .clone()
+                    .map(|note| vec![(None, note)])
+                    .unwrap_or_default(),
+            };
+
+            for (chunk_id, content) in parts {
+                let line = format!("[Pinned: {}] {}", pin.label, content.trim());
+                // A sliver of a chunk is noise; stop rather than pin a few words.
+                if line.len() > remaining && remaining < 200 {
+                    break 'pins;
+                }
+                let line = clip_to_bytes(&line, remaining);
+                remaining -= line.len();
+                lines.push(line.to_string());
+                if let Some(chunk_id) = chunk_id {
+                    chunk_ids.push(chunk_id);
+                }
+            }
+        }
+        (lines, chunk_ids)
+    }
+
+    /// The session's rolling summary and the messages it doesn't cover yet.
+    async fn unsummarized_history(
+        &self,
+        session_id: &str,
+    ) -> Result<(Option<String>, Vec<Message>), AppError> {
+        let session = self.conversation_repo.get_session(session_id).await?;
+        let (summary, position) = session
+            .and_then(|session| {
+                session
+                    .context_summary
+                    .zip(session.context_summary_position)
+            })
+            .map_or((None, -1), |(summary, position)| (Some(summary), position));
+        let messages = self
+            .conversation_repo
+            .get_messages_after(session_id, position, MAX_HISTORY_MESSAGES)
+            .await?;
+        Ok((summary, messages))
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        self.inference
+            .as_ref()
+            .and_then(|inference| inference.count_tokens(text))
+            .unwrap_or(text.len() / 4 + 1)
+    }
+
+    /// Folds the oldest messages into the rolling summary until the system
+    /// prompt, summary and remaining history fit the model's window with room
+    /// for the reply. Returns the summary to show and the token budget.
+    async fn fit_history(
+        &self,
+        session_id: &str,
+        system_prompt: &str,
+        messages: &mut Vec<Message>,
+        summary: Option<String>,
+    ) -> (Option<String>, usize) {
+        let window = self
+            .inference
+            .as_ref()
+            .and_then(|inference| inference.context_window())
+            .unwrap_or(FALLBACK_CONTEXT_TOKENS);
+        let budget = window.saturating_sub(REPLY_RESERVE_TOKENS);
+
+        let costs = messages
+            .iter()
+            .map(|message| self.count_tokens(&message.content) + MESSAGE_OVERHEAD_TOKENS)
+            .collect::<Vec<_>>();
+        let mut total =
+            self.count_tokens(system_prompt) + MAX_SUMMARY_TOKENS + costs.iter().sum::<usize>();
+        if total <= budget && messages.len() <= MAX_VERBATIM_MESSAGES {
+            return (summary, budget);
+        }
+
+        // Fold well past the limit so the next few turns fit without another
+        // summarization pass.
+        let target = budget * 3 / 4;
+        let mut fold = 0;
+        while (total > target || messages.len() - fold > MAX_VERBATIM_MESSAGES / 2)
+            && messages.len() - fold > MIN_VERBATIM_MESSAGES
+        {
+            total -= costs[fold];
+            fold += 1;
+        }
+        if fold == 0 {
+            return (summary, budget);
+        }
+
+        let folded = messages.drain(..fold).collect::<Vec<_>>();
+        match self
+            .summarize_history(summary.as_deref(), &folded, window)
+            .await
+        {
+            Ok(updated) => {
+                let through = folded.last().map_or(-1, |message| message.position);
+                if let Err(error) = self
+                    .conversation_repo
+                    .update_context_summary(session_id, &updated, through)
+                    .await
+                {
+                    crate::log_warn!(
+                        "sarah.context",
+                        "Failed to store the context summary for {}: {}",
+                        session_id,
+                        error
+                    );
+                }
+                (Some(updated), budget)
+            }
+            Err(error) => {
+                // The folded turns are left out of this reply only; the next turn
+                // tries to summarize them again.
+                crate::log_warn!(
+                    "sarah.context",
+                    "Dropped {} turns from {} without a summary: {}",
+                    folded.len(),
+                    session_id,
+                    error
+                );
+                (summary, budget)
+            }
+        }
+    }
+
+    async fn summarize_history(
+        &self,
+        previous: Option<&str>,
+        folded: &[Message],
+        window: usize,
+    ) -> Result<String, AppError> {
+        let inference = self
+            .inference
+            .as_ref()
+            .ok_or_else(|| AppError::Inference("No model to summarize history".to_string()))?;
+
+        // Newest turns first so a long backlog loses its oldest lines, not its latest.
+        let mut remaining = window.saturating_sub(MAX_SUMMARY_TOKENS + 512) * 3;
+        let mut lines = Vec::new();
+        for message in folded.iter().rev() {
+            let content = message
+                .content
+                .chars()
+                .take(SUMMARY_SOURCE_CHARS)
+                .collect::<String>();
+            let line = format!("{}: {}", message.role, content.trim());
+            if line.len() > remaining {
+                break;
+            }
+            remaining -= line.len();
+            lines.push(line);
+        }
+        lines.reverse();
+
+        let previous = previous
+            .map(|summary| format!("Summary so far:\n{summary}\n\n"))
+            .unwrap_or_default();
+        let prompt = format!(
+            "{SUMMARY_INSTRUCTIONS}\n\n{previous}New turns:\n{}",
+            lines.join("\n")
+        );
+        let options = GenerationOptions {
+            temperature: 0.2,
+            max_tokens: MAX_SUMMARY_TOKENS,
+            ..GenerationOptions::default()
+        };
+        let result = inference
+            .generate_with_options(vec![prompt_message("user", prompt)], &[], options)
+            .await?;
+
+        let summary = result.text.trim();
+        if summary.is_empty() {
+            return Err(AppError::Inference(
+                "The summary came back empty".to_string(),
+            ));
+        }
+        Ok(summary.to_string())
+    }
+
     pub async fn build_context(
         &self,
         user_id: &str,
         session_id: &str,
         query: &str,
+        language: Option<&DetectedLanguage>,
     ) -> Result<AssembledContext, AppError> {
         let memory_fut = self.memory_service.retrieve_relevant(user_id, query, 10);
+        let rag_settings = self
+            .conversation_repo
+            .get_session_rag_settings(session_id)
+            .await
+            .unwrap_or_default();
+        let workspace = self
+            .workspace_repo
+            .workspace_for_session(user_id, Some(session_id))
+            .await
+            .unwrap_or(None);
+        let persona = self
+            .persona_repo
+            .persona_for_session(session_id)
+            .await
+            .unwrap_or(None);
+        let namespaces = if rag_settings.collections.is_empty() {
+            vec![workspace
+                .as_ref()
+                .map(|workspace| workspace.rag_namespace.clone())
+                .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string())]
+        } else {
+            rag_settings.collections.clone()
+        };
 
         let rag_fut = async {
-            if let Some(rag) = self.rag_service.as_ref() {
-                rag.retrieve(user_id, query, "personal", 8).await.ok()
-            } else {
-                Some(Vec::new())
+            match self.rag_service.as_ref() {
+                Some(rag) if rag_settings.enabled => rag
+                    .retrieve_with_rerank(
+                        user_id,
+                        query,
+                        &namespaces,
+                        rag_settings.chunks,
+                        rag_settings.rerank_top_k,
+                    )
+                    .await
+                    .ok(),
+                _ => Some(Vec::new()),
             }
         };
 
         let intent_fut = self.intent_service.classify_intent(query);
-        let conv_fut = self.conversation_repo.get_context_window(session_id, 2000);
+        let conv_fut = self.unsummarized_history(session_id);
+        let pinned_fut = self.pinned_context(session_id);
 
-        let (memories, docs, intent, messages) =
-            tokio::join!(memory_fut, rag_fut, intent_fut, conv_fut);
+        let (memories, docs, intent, messages, (pinned_lines, pinned_chunk_ids)) =
+            tokio::join!(memory_fut, rag_fut, intent_fut, conv_fut, pinned_fut);
 
-        let memories = memories?;
-        let docs = docs.unwrap_or_default();
+        let mut memories = memories?;
+        let mut docs = docs.unwrap_or_default();
+        docs.retain(|row| !pinned_chunk_ids.contains(&row.chunk.id));
+        if let Some(language) = language {
+            prefer_language(&mut memories, language.code, |m| m.content.as_str());
+            prefer_language(&mut docs, language.code, |row| row.chunk.content.as_str());
+        }
         let intent = intent?;
-        let mut messages = messages?;
+        let (summary, mut messages) = messages?;
 
         let mcp_ids = self
             .mcp_service
@@ -112,20 +543,19 @@ impl ContextService {
             })
             .collect();
 
-        if messages.len() > 24 {
-            messages = messages.split_off(messages.len().saturating_sub(24));
-        }
-
-        let mut installed_models = self.model_repo.list_installed().await?;
-        let active_model = installed_models
-            .iter()
-            .find(|model| model.is_default == 1)
-            .cloned()
-            .or_else(|| installed_models.pop());
-
-        let model_line = active_model
-            .map(|m| format!("Active model: {} ({})", m.display_name, m.name))
-            .unwrap_or_else(|| "Active model: none selected".to_string());
+        let model_line = self.active_model_line().await?;
+        let instructions = self.default_instructions().await.unwrap_or_default();
+        // A session can opt out, e.g. to write in another language than usual.
+        let user_instructions = if self
+            .conversation_repo
+            .session_uses_user_instructions(session_id)
+            .await
+            .unwrap_or(true)
+        {
+            self.user_instructions(user_id).await.unwrap_or_default()
+        } else {
+            None
+        };
 
         let memory_block = if memories.is_empty() {
             "(none)".to_string()
@@ -143,14 +573,24 @@ impl ContextService {
                 .join("\n")
         };
 
-        let doc_block = if docs.is_empty() {
+        // Pinned items lead so they survive even when retrieval finds plenty.
+        let mut doc_lines = pinned_lines;
+        if rag_settings.enabled {
+            doc_lines.extend(docs.iter().enumerate().map(|(idx, row)| {
+                format!(
+                    "[{}]{} {}",
+                    idx + 1,
+                    chunk_location(&row.chunk),
+                    row.chunk.content
+                )
+            }));
+        } else {
+            doc_lines.push("(document retrieval is off for this conversation)".to_string());
+        }
+        let doc_block = if doc_lines.is_empty() {
             "(none)".to_string()
         } else {
-            docs.iter()
-                .enumerate()
-                .map(|(idx, row)| format!("[Doc {}] {}", idx + 1, row.chunk.content))
-                .collect::<Vec<_>>()
-                .join("\n")
+            doc_lines.join("\n")
         };
 
         let tool_block = if tools.is_empty() {
@@ -163,12 +603,32 @@ impl ContextService {
                 .join(", ")
         };
 
-        let mut system_prompt = format!(
-            "You are Sarah, a highly capable local AI assistant.\n\n{}\n\nUSER MEMORY:\n{}\n\nRELEVANT KNOWLEDGE:\n{}\n\nACTIVE TOOLS: {}\n\nGUIDELINES:\n- Personalize using memory facts\n- Cite sources as [Doc N] or [Memory: subject]\n- Extract new facts to memory when user shares information\n- Be concise, intelligent, and premium quality",
-            model_line, memory_block, doc_block, tool_block
+        // Small local models drift back to English mid-answer without an explicit nudge.
+        let language_hint = language
+            .filter(|detected| !detected.is_english())
+            .map(|detected| detected.name);
+
+        let mut system_prompt = compose_system_prompt(
+            persona
+                .as_ref()
+                .map(|persona| persona.system_prompt.as_str()),
+            user_instructions.as_deref(),
+            instructions.as_deref(),
+            language_hint,
+            &model_line,
+            &memory_block,
+            &doc_block,
+            &tool_block,
         );
 
-        trim_context(&mut system_prompt, &mut messages, 3500);
+        let (summary, budget) = self
+            .fit_history(session_id, &system_prompt, &mut messages, summary)
+            .await;
+        if let Some(summary) = summary {
+            system_prompt.push_str(&format!("\n\nEARLIER IN THIS CONVERSATION:\n{summary}"));
+        }
+
+        trim_context(&mut system_prompt, &mut messages, budget);
 
         Ok(AssembledContext {
             system_prompt,
@@ -176,10 +636,125 @@ impl ContextService {
             tools,
             memory_refs: memories,
             doc_refs: docs,
+            workspace,
+            persona,
         })
     }
 }
 
+/// Builds the system prompt: the user's own instructions on top, then Sarah's
+/// identity (or the session persona's prompt), the global instructions and
+/// reply language, then the per-turn context blocks.
+pub fn compose_system_prompt(
+    persona_prompt: Option<&str>,
+    user_instructions: Option<&str>,
+    instructions: Option<&str>,
+    language_hint: Option<&str>,
+    model_line: &str,
+    memory_block: &str,
+    doc_block: &str,
+    tool_block: &str,
+) -> String {
+    let user_block = user_instructions
+        .map(|text| format!("MY INSTRUCTIONS (always follow these):\n{text}\n\n"))
+        .unwrap_or_default();
+    let instructions_block = instructions
+        .map(|text| format!("\n\nUSER INSTRUCTIONS (apply to every reply):\n{text}"))
+        .unwrap_or_default();
+    let language_block = language_hint
+        .map(|language| {
+            format!("\n\nLANGUAGE: The user is writing in {language}. Reply only in {language} unless asked to switch.")
+        })
+        .unwrap_or_default();
+
+    format!(
+        "{}{}{}{}\n\n{}\n\nUSER MEMORY:\n{}\n\nRELEVANT KNOWLEDGE:\n{}\n\nACTIVE TOOLS: {}\n\nGUIDELINES:\n- Personalize using memory facts\n- When you use a RELEVANT KNOWLEDGE entry, cite its number right after the claim, like [1] or [2][3]; never invent numbers\n- Cite memories as [Memory: subject]\n- Extract new facts to memory when user shares information\n- Be concise, intelligent, and premium quality",
+        user_block, persona_prompt.unwrap_or(SARAH_IDENTITY), instructions_block, language_block, model_line, memory_block, doc_block, tool_block
+    )
+}
+
+/// Rough token count of everything the model will see for this turn.
+pub fn estimate_context_tokens(context: &AssembledContext) -> usize {
+    context.system_prompt.len() / 4
+        + context
+            .messages
+            .iter()
+            .map(|m| m.content.len() / 4)
+            .sum::<usize>()
+}
+
+/// Maps the `[N]` markers in a reply (also `[1, 2]` and the older `[Doc N]`)
+/// back to the chunks they point at, in order of first citation. Numbers with
+/// no matching chunk are ignored.
+pub fn cited_refs<'a>(
+    reply: &str,
+    doc_refs: &'a [RetrievedChunk],
+) -> Vec<(usize, &'a RetrievedChunk)> {
+    let mut cited: Vec<(usize, &RetrievedChunk)> = Vec::new();
+    for part in reply.split('[').skip(1) {
+        let Some(end) = part
+            .find(']')
+            .filter(|end| *end <= MAX_CITATION_MARKER_CHARS)
+        else {
+            continue;
+        };
+        let inner = part[..end].trim();
+        let inner = inner.strip_prefix("Doc").unwrap_or(inner);
+        for number in inner.split(',') {
+            let Ok(marker) = number.trim().parse::<usize>() else {
+                continue;
+            };
+            let Some(doc) = marker.checked_sub(1).and_then(|idx| doc_refs.get(idx)) else {
+                continue;
+            };
+            if !cited.iter().any(|(seen, _)| *seen == marker) {
+                cited.push((marker, doc));
+            }
+        }
+    }
+    cited
+}
+
+/// Shrinks an assembled context so it fits a smaller model window.
+pub fn compress_context(context: &mut AssembledContext, max_tokens: usize) {
+    trim_context(
+        &mut context.system_prompt,
+        &mut context.messages,
+        max_tokens,
+    );
+}
+
+/// " (p. 4, Results)" for chunks whose source had pages or headings.
+fn chunk_location(chunk: &Chunk) -> String {
+    let mut parts = Vec::new();
+    if let Some(page) = chunk.page_number {
+        parts.push(format!("p. {page}"));
+    }
+    if let Some(section) = chunk
+        .section_title
+        .as_deref()
+        .filter(|section| !section.trim().is_empty())
+    {
+        parts.push(section.to_string());
+    }
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", parts.join(", "))
+    }
+}
+
+fn clip_to_bytes(text: &str, max_bytes: usize) -> &str {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
 fn trim_context(system_prompt: &mut String, messages: &mut Vec<Message>, max_tokens: usize) {
     let estimate_tokens = |text: &str| text.len() / 4;
 
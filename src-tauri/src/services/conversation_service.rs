@@ -1,26 +1,49 @@
+use std::path::Path;
 use std::sync::Arc;
 
 use tokio_stream::wrappers::ReceiverStream;
 use tokio_stream::StreamExt;
 
 use crate::db::models::{
-    GenerationOptions, Message, MessageStreamChunk, Model, NewMessage, NewToolCall,
-    RoutingDecision, SystemProfile, ToolResult,
+    Citation, GenerationOptions, Message, MessageAttachment, MessageStreamChunk, Model,
+    NewMessage, NewToolCall, RoutingDecision, SystemProfile, ToolApprovalRequest, ToolCallEvent,
+    ToolResult,
 };
 use crate::error::AppError;
 use crate::repositories::conversation_repo::ConversationRepo;
 use crate::repositories::model_repo::ModelRepo;
 use crate::repositories::system_repo::SystemRepo;
 use crate::services::analytics_service::AnalyticsService;
-use crate::services::context_service::ContextService;
-use crate::services::inference_service::InferenceService;
+use crate::services::context_service::{
+    cited_refs, compress_context, estimate_context_tokens, ContextService,
+};
+use crate::services::generation_presets::{apply_preset, GenerationPresetService};
+use crate::services::inference_service::{parse_tool_calls, InferenceService};
+use crate::services::language_detector::detect_language;
 use crate::services::mcp_service::McpService;
 use crate::services::memory_service::MemoryService;
+use crate::services::ocr;
 use crate::services::rag_service::RagService;
 use crate::services::runtime_governor_service::RuntimeGovernorService;
 use crate::services::runtime_orchestrator_service::RuntimeOrchestratorService;
 use crate::services::task_router_service::TaskRouterService;
+use crate::services::tool_approval_service::{ToolApprovalService, ToolPermission};
+use crate::services::webhook_service::{WebhookEvent, WebhookService};
 use crate::services::hardware_service::HardwareService;
+use crate::services::history_search::HistorySearchService;
+
+pub const DEFAULT_MAX_TOOL_ROUNDS: usize = 4;
+pub const MAX_TOOL_ROUNDS: usize = 8;
+const MAX_TOOL_RESULT_CHARS: usize = 4_000;
+/// Messages shown to the model when naming a session: the first two exchanges.
+const TITLE_SOURCE_MESSAGES: usize = 4;
+const TITLE_SOURCE_CHARS: usize = 400;
+const MAX_TITLE_CHARS: usize = 80;
+/// Recognized text kept per image when the model can't look at it.
+const MAX_IMAGE_TEXT_CHARS: usize = 4_000;
+const OLLAMA_BASE_URL: &str = "http://127.0.0.1:11434";
+/// Titling is best effort; a slow or absent Ollama must not hold up the queue.
+const OLLAMA_TITLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -30,6 +53,15 @@ pub struct ToolCallRequest {
     pub args: serde_json::Value,
 }
 
+/// Final answer of a tool-calling turn and every tool that ran along the way.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolLoopResult {
+    pub text: String,
+    pub rounds: usize,
+    pub tool_results: Vec<ToolResult>,
+}
+
 #[derive(Clone)]
 pub struct ConversationService {
     conversation_repo: ConversationRepo,
@@ -45,6 +77,11 @@ pub struct ConversationService {
     runtime_orchestrator: Arc<RuntimeOrchestratorService>,
     system_repo: SystemRepo,
     hardware_service: Arc<HardwareService>,
+    presets: GenerationPresetService,
+    tool_approvals: ToolApprovalService,
+    history_search: HistorySearchService,
+    webhooks: WebhookService,
+    http: reqwest::Client,
 }
 
 impl ConversationService {
@@ -62,6 +99,10 @@ impl ConversationService {
         runtime_orchestrator: Arc<RuntimeOrchestratorService>,
         system_repo: SystemRepo,
         hardware_service: Arc<HardwareService>,
+        presets: GenerationPresetService,
+        tool_approvals: ToolApprovalService,
+        history_search: HistorySearchService,
+        webhooks: WebhookService,
     ) -> Self {
         Self {
             conversation_repo,
@@ -77,6 +118,14 @@ impl ConversationService {
             runtime_orchestrator,
             system_repo,
             hardware_service,
+            presets,
+            tool_approvals,
+            history_search,
+            webhooks,
+            http: reqwest::Client::builder()
+                .timeout(OLLAMA_TITLE_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
         }
     }
 
@@ -87,7 +136,11 @@ impl ConversationService {
         }
     }
 
-    async fn resolve_selected_model(&self, selected_model: &str) -> Result<Option<Model>, AppError> {
+    /// Looks a model up by id, then by name.
+    pub(crate) async fn resolve_selected_model(
+        &self,
+        selected_model: &str,
+    ) -> Result<Option<Model>, AppError> {
         let normalized = selected_model.trim();
         if normalized.is_empty() {
             return Ok(None);
@@ -100,7 +153,8 @@ impl ConversationService {
         self.model_repo.get_by_name(normalized).await
     }
 
-    async fn resolve_target_model_for_routing(
+    /// The routed model, or the default when routing found none.
+    pub(crate) async fn resolve_target_model_for_routing(
         &self,
         routing: &RoutingDecision,
     ) -> Result<Option<Model>, AppError> {
@@ -156,6 +210,12 @@ impl ConversationService {
             }))
     }
 
+    /// Loads `model` for callers outside a chat turn, such as the local API.
+    pub(crate) async fn ensure_model_ready(&self, model: &Model) -> Result<(), AppError> {
+        let profile = self.active_or_default_profile().await?;
+        self.ensure_model_loaded(model, &profile).await
+    }
+
     async fn ensure_model_loaded(
         &self,
         model: &Model,
@@ -187,6 +247,52 @@ impl ConversationService {
             .await
     }
 
+    /// A model without a vision projector can't see image attachments, so the
+    /// text recognized in them is added to their messages instead. Each image
+    /// is recognized once; the text is kept on its attachment.
+    async fn fold_image_text(&self, messages: &mut [Message]) {
+        if self.inference_service.supports_vision() {
+            return;
+        }
+        for message in messages.iter_mut() {
+            let mut attachments = message.attachment_list();
+            let mut recognized = false;
+            for attachment in attachments
+                .iter_mut()
+                .filter(|a| a.kind == "image" && a.ocr_text.is_none())
+            {
+                let text = match ocr::recognize_text(Path::new(&attachment.path)).await {
+                    Ok(result) => result.text,
+                    Err(error) => {
+                        crate::log_warn!(
+                            "sarah.conversation",
+                            "Could not read text from {}: {}",
+                            attachment.path,
+                            error
+                        );
+                        String::new()
+                    }
+                };
+                attachment.ocr_text = Some(text);
+                recognized = true;
+            }
+            if recognized {
+                if let Ok(json) = serde_json::to_string(&attachments) {
+                    let _ = self
+                        .conversation_repo
+                        .set_message_attachments(&message.id, &json)
+                        .await;
+                    message.attachments = json;
+                }
+            }
+            for attachment in attachments.iter().filter(|a| a.kind == "image") {
+                message.content.push_str(&image_text_block(attachment));
+            }
+        }
+    }
+
+    /// `external` marks a prompt that came from outside the app, such as a
+    /// link: no memories are extracted from the exchange and no webhooks fire.
     pub async fn send_message(
         &self,
         user_id: &str,
@@ -198,7 +304,8 @@ impl ConversationService {
         task_type: Option<&str>,
         qos: Option<&str>,
         allow_background_defer: bool,
-        app_handle: Option<tauri::AppHandle>,
+        preset: Option<&str>,
+        external: bool,
     ) -> Result<ReceiverStream<MessageStreamChunk>, AppError> {
         let existing = self
             .conversation_repo
@@ -206,6 +313,23 @@ impl ConversationService {
             .await
             .unwrap_or_default();
         let position = existing.last().map(|m| m.position + 1).unwrap_or(0);
+        let language = detect_language(content);
+        let user_metadata = language
+            .map(|detected| serde_json::json!({ "language": detected.code }).to_string())
+            .unwrap_or_else(|| "{}".to_string());
+        // Images go to the model; other files are ingested for retrieval.
+        let message_attachments = attachments
+            .iter()
+            .map(|path| MessageAttachment {
+                path: path.clone(),
+                kind: if ocr::is_image(Path::new(path)) {
+                    "image".to_string()
+                } else {
+                    "document".to_string()
+                },
+                ocr_text: None,
+            })
+            .collect::<Vec<_>>();
 
         let user_message = self
             .conversation_repo
@@ -216,21 +340,24 @@ impl ConversationService {
                 content_type: "text".to_string(),
                 token_count: Some((content.len() / 4) as i64 + 1),
                 model_id: None,
-                metadata: "{}".to_string(),
+                metadata: user_metadata,
+                attachments: serde_json::to_string(&message_attachments)
+                    .unwrap_or_else(|_| "[]".to_string()),
                 position,
             })
             .await?;
 
-        for path in attachments {
+        for attachment in message_attachments.iter().filter(|a| a.kind == "document") {
             if let Some(rag) = self.rag_service.as_ref() {
-                let _ = rag.ingest_document(user_id, path).await;
+                let _ = rag.ingest_document(user_id, &attachment.path).await;
             }
         }
 
-        let context = self
+        let mut context = self
             .context_service
-            .build_context(user_id, session_id, content)
+            .build_context(user_id, session_id, content, language.as_ref())
             .await?;
+        let context_tokens = estimate_context_tokens(&context);
 
         let orchestrated = self
             .runtime_orchestrator
@@ -246,6 +373,7 @@ impl ConversationService {
                 Some(orchestrated.task_type.as_str()),
                 Some(orchestrated.qos.as_str()),
                 orchestrated.defer_background,
+                Some(context_tokens),
             )
             .await?;
 
@@ -286,6 +414,23 @@ impl ConversationService {
             }
         }
 
+        // The session persona's model stands in for automatic routing when installed.
+        if !manual_mode {
+            if let Some(persona) = context.persona.as_ref() {
+                if let Some(model_id) = persona.preferred_model_id.as_deref() {
+                    match self.model_repo.get_by_id(model_id).await? {
+                        Some(model) if model.is_downloaded == 1 => {
+                            routing.selected_model_id = Some(model.id.clone());
+                            routing.selected_model_name = Some(model.display_name.clone());
+                            routing.reason =
+                                format!("{}; persona_model={}", routing.reason, model.name);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
         let profile = self.active_or_default_profile().await?;
         let mut target_model = self.resolve_target_model_for_routing(&routing).await?;
 
@@ -348,6 +493,54 @@ impl ConversationService {
             &pressure,
             orchestrated.defer_background,
         );
+        if let Some(temperature) = context.persona.as_ref().and_then(|p| p.temperature) {
+            tuned_options.temperature = temperature as f32;
+        }
+
+        // An explicit preset wins over the one pinned on the session, which wins
+        // over the workspace preset.
+        let preset_name = match preset {
+            Some(name) => Some(name.to_string()),
+            None => self.conversation_repo.get_session_preset(session_id).await?,
+        };
+        let active_preset = match preset_name {
+            Some(name) => Some(self.presets.get(&name).await?),
+            // A workspace preset that has since been deleted just falls back to defaults.
+            None => match context.workspace.as_ref().and_then(|w| w.preset.as_deref()) {
+                Some(preset) => self.presets.get(preset).await.ok(),
+                None => None,
+            },
+        };
+        if let Some(active) = active_preset.as_ref() {
+            tuned_options = apply_preset(tuned_options, active);
+        }
+        let assistant_metadata = match active_preset.as_ref() {
+            Some(active) => serde_json::json!({
+                "generationPreset": {
+                    "name": active.name,
+                    "temperature": active.temperature,
+                    "topP": active.top_p,
+                    "topK": active.top_k,
+                }
+            })
+            .to_string(),
+            None => "{}".to_string(),
+        };
+
+        self.fold_image_text(&mut context.messages).await;
+        if let Some(model) = target_model.as_ref() {
+            let window = model.context_length.max(0) as usize;
+            if window > 0 && context_tokens + tuned_options.max_tokens > window {
+                let budget = window.saturating_sub(tuned_options.max_tokens).max(512);
+                compress_context(&mut context, budget);
+                routing.reason = format!(
+                    "{}; context_compressed={}->{}",
+                    routing.reason,
+                    context_tokens,
+                    estimate_context_tokens(&context)
+                );
+            }
+        }
 
         let mut inference_stream = match self
             .inference_service
@@ -355,7 +548,6 @@ impl ConversationService {
                 session_id,
                 context.messages.clone(),
                 tuned_options.clone(),
-                app_handle.clone(),
             )
             .await
         {
@@ -392,7 +584,6 @@ impl ConversationService {
                             session_id,
                             context.messages.clone(),
                             tuned_options,
-                            app_handle,
                         )
                         .await?
                 } else {
@@ -412,10 +603,16 @@ impl ConversationService {
         let content_len_estimate = (content.len() / 4) as i64 + 1;
         let selected_model_id = routing.selected_model_id.clone();
         let fallback_notice_for_stream = fallback_notice.clone();
+        let rag_service = self.rag_service.clone();
+        let history_search = self.history_search.clone();
+        let webhooks = self.webhooks.clone();
+        let doc_refs = std::mem::take(&mut context.doc_refs);
 
         tokio::spawn(async move {
             let started = std::time::Instant::now();
+            let mut first_token_ms = None;
             let mut full_text = String::new();
+            let mut finish_reason = None;
 
             if let Some(notice) = fallback_notice_for_stream {
                 let notice_token = format!("{notice}\n\n");
@@ -425,6 +622,8 @@ impl ConversationService {
                         session_id: session_id_owned.clone(),
                         token: notice_token,
                         done: false,
+                        finish_reason: None,
+                        citations: Vec::new(),
                     })
                     .await
                     .is_err()
@@ -433,9 +632,19 @@ impl ConversationService {
                 }
             }
 
-            while let Some(chunk) = inference_stream.next().await {
+            let mut citations = Vec::new();
+            while let Some(mut chunk) = inference_stream.next().await {
                 if !chunk.done {
+                    if first_token_ms.is_none() && !chunk.token.is_empty() {
+                        first_token_ms = Some(started.elapsed().as_millis() as i64);
+                    }
                     full_text.push_str(&chunk.token);
+                } else {
+                    finish_reason = chunk.finish_reason.clone();
+                    if let Some(rag) = rag_service.as_ref() {
+                        citations = rag.citations(&cited_refs(&full_text, &doc_refs)).await;
+                        chunk.citations = citations.clone();
+                    }
                 }
                 if tx.send(chunk.clone()).await.is_err() {
                     break;
@@ -460,17 +669,54 @@ impl ConversationService {
                         content_type: "markdown".to_string(),
                         token_count: Some((full_text.len() / 4) as i64 + 1),
                         model_id: selected_model_id.clone(),
-                        metadata: "{}".to_string(),
+                        metadata: with_citations(&assistant_metadata, &citations),
+                        attachments: "[]".to_string(),
                         position: next_position,
                     })
                     .await;
 
                 if let Ok(assistant_message) = assistant {
-                    let paired = vec![user_message.clone(), assistant_message.clone()];
-                    if let Ok(extracted) =
-                        memory_service.extract_batch(&paired, &user_id_owned).await
-                    {
-                        let _ = memory_service.persist_extracted(extracted).await;
+                    if let Some(reason) = finish_reason.as_deref() {
+                        let _ = conversation_repo
+                            .set_message_finish_reason(&assistant_message.id, reason)
+                            .await;
+                    }
+                    if let Some(rag) = rag_service.as_ref() {
+                        let cited = citations
+                            .iter()
+                            .map(|citation| citation.chunk_id.clone())
+                            .collect::<Vec<_>>();
+                        if !cited.is_empty() {
+                            let _ = rag
+                                .record_citations(&session_id_owned, &assistant_message.id, &cited)
+                                .await;
+                        }
+                    }
+
+                    for message in [&user_message, &assistant_message] {
+                        let _ = history_search
+                            .index_message(&user_id_owned, &message.id, &message.content)
+                            .await;
+                    }
+
+                    if !external {
+                        webhooks
+                            .publish(
+                                Some(&user_id_owned),
+                                WebhookEvent::MessageCompleted,
+                                completed_message_event(
+                                    &assistant_message,
+                                    finish_reason.as_deref(),
+                                ),
+                            )
+                            .await;
+
+                        let paired = vec![user_message.clone(), assistant_message.clone()];
+                        if let Ok(extracted) =
+                            memory_service.extract_batch(&paired, &user_id_owned).await
+                        {
+                            let _ = memory_service.persist_extracted(extracted).await;
+                        }
                     }
                 }
 
@@ -486,6 +732,7 @@ impl ConversationService {
                             (full_text.split_whitespace().count() as f64)
                                 / (started.elapsed().as_secs_f64().max(0.001)),
                         ),
+                        first_token_ms,
                         true,
                         None,
                     )
@@ -496,14 +743,317 @@ impl ConversationService {
         Ok(ReceiverStream::new(rx))
     }
 
+    /// Answers a user turn again and stores the result as a new variant of its
+    /// reply. `message_id` is either the reply being replaced or the user
+    /// message itself. Earlier variants are kept and can be selected again.
+    pub async fn regenerate_message(
+        &self,
+        session_id: &str,
+        message_id: &str,
+        model_override: Option<&str>,
+    ) -> Result<ReceiverStream<MessageStreamChunk>, AppError> {
+        let session = self
+            .conversation_repo
+            .get_session(session_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "session".to_string(),
+                id: session_id.to_string(),
+            })?;
+        let message = self
+            .conversation_repo
+            .get_message_by_id(message_id)
+            .await?
+            .filter(|message| message.session_id == session_id)
+            .ok_or_else(|| AppError::NotFound {
+                entity: "message".to_string(),
+                id: message_id.to_string(),
+            })?;
+
+        let (prompt, replaced) = match message.role.as_str() {
+            "user" => {
+                let reply = self
+                    .conversation_repo
+                    .reply_after(session_id, message.position)
+                    .await?;
+                (message, reply)
+            }
+            "assistant" => {
+                let prompt = match message.parent_message_id.as_deref() {
+                    Some(parent_id) => self.conversation_repo.get_message_by_id(parent_id).await?,
+                    None => {
+                        self.conversation_repo
+                            .prompt_before(session_id, message.position)
+                            .await?
+                    }
+                };
+                let prompt = prompt.ok_or_else(|| AppError::Validation {
+                    field: "message_id".to_string(),
+                    message: "This reply has no user message to answer again".to_string(),
+                })?;
+                (prompt, Some(message))
+            }
+            _ => {
+                return Err(AppError::Validation {
+                    field: "message_id".to_string(),
+                    message: "Only user messages and assistant replies can be regenerated"
+                        .to_string(),
+                })
+            }
+        };
+
+        // An override wins, then the model that wrote the reply, then the session's model.
+        let target_model = match model_override.map(str::trim).filter(|m| !m.is_empty()) {
+            Some(requested) => match self.resolve_selected_model(requested).await? {
+                Some(model) if model.is_downloaded == 1 => Some(model),
+                _ => {
+                    return Err(AppError::Validation {
+                        field: "model_override".to_string(),
+                        message: format!("Model '{requested}' is not installed"),
+                    })
+                }
+            },
+            None => {
+                let previous = replaced
+                    .as_ref()
+                    .and_then(|reply| reply.model_id.clone())
+                    .or_else(|| session.model_id.clone());
+                let previous = match previous {
+                    Some(model_id) => self
+                        .model_repo
+                        .get_by_id(&model_id)
+                        .await?
+                        .filter(|model| model.is_downloaded == 1),
+                    None => None,
+                };
+                match previous {
+                    Some(model) => Some(model),
+                    None => {
+                        let installed = self.model_repo.list_installed().await?;
+                        installed
+                            .iter()
+                            .find(|m| m.is_default == 1)
+                            .cloned()
+                            .or_else(|| installed.first().cloned())
+                    }
+                }
+            }
+        };
+        if let Some(model) = target_model.as_ref() {
+            let profile = self.active_or_default_profile().await?;
+            self.ensure_model_loaded(model, &profile).await?;
+        }
+
+        let language = detect_language(&prompt.content);
+        let mut context = self
+            .context_service
+            .build_context(
+                &session.user_id,
+                session_id,
+                &prompt.content,
+                language.as_ref(),
+            )
+            .await?;
+        // Later turns weren't there when this one was first answered.
+        context
+            .messages
+            .retain(|message| message.position <= prompt.position);
+
+        let policy = self
+            .runtime_governor
+            .get_policy(Some(&session.user_id))
+            .await?;
+        let pressure = self
+            .runtime_governor
+            .classify_pressure(&self.runtime_governor.current_stats(), &policy);
+        let mut options = self.runtime_governor.tune_generation(
+            GenerationOptions::default(),
+            &policy,
+            "balanced",
+            &pressure,
+            false,
+        );
+        if let Some(temperature) = context.persona.as_ref().and_then(|p| p.temperature) {
+            options.temperature = temperature as f32;
+        }
+        if let Some(name) = self
+            .conversation_repo
+            .get_session_preset(session_id)
+            .await?
+        {
+            options = apply_preset(options, &self.presets.get(&name).await?);
+        }
+        // A fresh seed so the new variant doesn't repeat the previous one.
+        options.seed = Some(uuid::Uuid::new_v4().as_u128() as u32);
+
+        self.fold_image_text(&mut context.messages).await;
+        if let Some(model) = target_model.as_ref() {
+            let window = model.context_length.max(0) as usize;
+            let context_tokens = estimate_context_tokens(&context);
+            if window > 0 && context_tokens + options.max_tokens > window {
+                compress_context(
+                    &mut context,
+                    window.saturating_sub(options.max_tokens).max(512),
+                );
+            }
+        }
+
+        let mut inference_stream = self
+            .inference_service
+            .generate_stream(session_id, context.messages.clone(), options)
+            .await?;
+
+        let (tx, rx) = tokio::sync::mpsc::channel::<MessageStreamChunk>(256);
+        let conversation_repo = self.conversation_repo.clone();
+        let analytics_service = self.analytics_service.clone();
+        let session_id_owned = session_id.to_string();
+        let model_id = target_model.map(|model| model.id);
+        let prompt_tokens = prompt.token_count;
+        let position = replaced
+            .as_ref()
+            .map(|reply| reply.position)
+            .unwrap_or(prompt.position + 1);
+        let metadata = serde_json::json!({ "regeneratedFrom": message_id }).to_string();
+        let rag_service = self.rag_service.clone();
+        let webhooks = self.webhooks.clone();
+        let user_id_owned = session.user_id.clone();
+        let doc_refs = std::mem::take(&mut context.doc_refs);
+
+        tokio::spawn(async move {
+            let started = std::time::Instant::now();
+            let mut first_token_ms = None;
+            let mut full_text = String::new();
+            let mut finish_reason = None;
+            let mut citations = Vec::new();
+
+            while let Some(mut chunk) = inference_stream.next().await {
+                if !chunk.done {
+                    if first_token_ms.is_none() && !chunk.token.is_empty() {
+                        first_token_ms = Some(started.elapsed().as_millis() as i64);
+                    }
+                    full_text.push_str(&chunk.token);
+                } else {
+                    finish_reason = chunk.finish_reason.clone();
+                    if let Some(rag) = rag_service.as_ref() {
+                        citations = rag.citations(&cited_refs(&full_text, &doc_refs)).await;
+                        chunk.citations = citations.clone();
+                    }
+                }
+                if tx.send(chunk.clone()).await.is_err() {
+                    break;
+                }
+            }
+
+            if full_text.trim().is_empty() {
+                return;
+            }
+            let tokens_out = (full_text.len() / 4) as i64 + 1;
+            let variant = conversation_repo
+                .insert_variant(
+                    NewMessage {
+                        session_id: session_id_owned.clone(),
+                        role: "assistant".to_string(),
+                        content: full_text.clone(),
+                        content_type: "markdown".to_string(),
+                        token_count: Some(tokens_out),
+                        model_id: model_id.clone(),
+                        metadata: with_citations(&metadata, &citations),
+                        attachments: "[]".to_string(),
+                        position,
+                    },
+                    &prompt.id,
+                    replaced.as_ref().map(|reply| reply.id.as_str()),
+                )
+                .await;
+            match variant {
+                Ok(variant) => {
+                    if let Some(reason) = finish_reason.as_deref() {
+                        let _ = conversation_repo
+                            .set_message_finish_reason(&variant.id, reason)
+                            .await;
+                    }
+                    if let Some(rag) = rag_service.as_ref() {
+                        let cited = citations
+                            .iter()
+                            .map(|citation| citation.chunk_id.clone())
+                            .collect::<Vec<_>>();
+                        if !cited.is_empty() {
+                            let _ = rag
+                                .record_citations(&session_id_owned, &variant.id, &cited)
+                                .await;
+                        }
+                    }
+                    webhooks
+                        .publish(
+                            Some(&user_id_owned),
+                            WebhookEvent::MessageCompleted,
+                            completed_message_event(&variant, finish_reason.as_deref()),
+                        )
+                        .await;
+                }
+                Err(error) => {
+                    crate::log_warn!(
+                        "sarah.conversation",
+                        "Failed to store regenerated reply in {}: {}",
+                        session_id_owned,
+                        error
+                    );
+                }
+            }
+
+            let _ = analytics_service
+                .log_inference(
+                    Some(session_id_owned),
+                    model_id,
+                    started.elapsed().as_millis() as i64,
+                    prompt_tokens,
+                    Some(tokens_out),
+                    Some(
+                        (full_text.split_whitespace().count() as f64)
+                            / (started.elapsed().as_secs_f64().max(0.001)),
+                    ),
+                    first_token_ms,
+                    true,
+                    None,
+                )
+                .await;
+        });
+
+        Ok(ReceiverStream::new(rx))
+    }
+
+    /// All replies to the same user turn as `message_id`, oldest first.
+    pub async fn message_variants(&self, message_id: &str) -> Result<Vec<Message>, AppError> {
+        let message = self
+            .conversation_repo
+            .get_message_by_id(message_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "message".to_string(),
+                id: message_id.to_string(),
+            })?;
+        match message.parent_message_id.as_deref() {
+            Some(parent_id) => self.conversation_repo.list_variants(parent_id).await,
+            None => Ok(vec![message]),
+        }
+    }
+
+    /// Runs tool calls in order, reporting each transition through `on_event` and
+    /// recording the final outcomes under `toolCalls` in the message metadata.
+    /// Calls whose tool policy is "ask" wait for the user via `on_approval`;
+    /// a denied call stops the batch like a failed one.
     pub async fn process_tool_calls(
         &self,
         tool_calls: Vec<ToolCallRequest>,
         session_id: &str,
         message_id: &str,
         user_id: &str,
+        on_event: impl Fn(&ToolCallEvent) + Send + Sync,
+        on_approval: impl Fn(&ToolApprovalRequest) + Send + Sync,
     ) -> Result<Vec<ToolResult>, AppError> {
         let mut results = Vec::new();
+        let mut summaries = Vec::new();
+        let mut failure = None;
 
         for call in tool_calls {
             let row = self
@@ -517,6 +1067,63 @@ impl ConversationService {
                 })
                 .await?;
 
+            let mut event = ToolCallEvent {
+                tool_call_id: row.id.clone(),
+                session_id: session_id.to_string(),
+                message_id: message_id.to_string(),
+                mcp_id: call.mcp_id.clone(),
+                tool_name: call.tool_name.clone(),
+                status: "started".to_string(),
+                latency_ms: None,
+                error: None,
+            };
+
+            let approved = match self
+                .tool_approvals
+                .tool_policy(user_id, &call.mcp_id, &call.tool_name)
+                .await?
+            {
+                ToolPermission::AlwaysAllow => true,
+                ToolPermission::Deny => false,
+                ToolPermission::Ask => {
+                    event.status = "awaiting_approval".to_string();
+                    on_event(&event);
+                    let request = ToolApprovalRequest {
+                        id: uuid::Uuid::new_v4().to_string(),
+                        tool_call_id: row.id.clone(),
+                        session_id: session_id.to_string(),
+                        message_id: message_id.to_string(),
+                        mcp_id: call.mcp_id.clone(),
+                        tool_name: call.tool_name.clone(),
+                        args: call.args.clone(),
+                        created_at: chrono::Utc::now().to_rfc3339(),
+                    };
+                    self.tool_approvals
+                        .await_approval(&request, &on_approval)
+                        .await
+                }
+            };
+            if !approved {
+                let _ = self
+                    .conversation_repo
+                    .update_tool_call_result(&row.id, None, "denied", 0)
+                    .await;
+                let err = AppError::McpError {
+                    mcp_id: call.mcp_id.clone(),
+                    message: format!("Tool '{}' was not approved", call.tool_name),
+                };
+                event.status = "denied".to_string();
+                event.error = Some(err.to_string());
+                on_event(&event);
+                summaries.push(event);
+                failure = Some(err);
+                break;
+            }
+
+            event.status = "started".to_string();
+            on_event(&event);
+
+            let started = std::time::Instant::now();
             match self
                 .mcp_service
                 .call_tool(&call.mcp_id, &call.tool_name, call.args.clone(), user_id)
@@ -531,61 +1138,594 @@ impl ConversationService {
                             result.latency_ms,
                         )
                         .await?;
+                    event.status = "succeeded".to_string();
+                    event.latency_ms = Some(result.latency_ms);
+                    on_event(&event);
+                    summaries.push(event);
                     results.push(result);
                 }
                 Err(err) => {
+                    let latency_ms = started.elapsed().as_millis() as i64;
                     let _ = self
                         .conversation_repo
-                        .update_tool_call_result(&row.id, None, "error", 0)
+                        .update_tool_call_result(&row.id, None, "error", latency_ms)
                         .await;
-                    return Err(err);
+                    event.status = "failed".to_string();
+                    event.latency_ms = Some(latency_ms);
+                    event.error = Some(err.to_string());
+                    on_event(&event);
+                    summaries.push(event);
+                    failure = Some(err);
+                    break;
                 }
             }
         }
 
-        Ok(results)
+        if let Ok(encoded) = serde_json::to_string(&summaries) {
+            let _ = self
+                .conversation_repo
+                .set_message_tool_calls(message_id, &encoded)
+                .await;
+        }
+
+        match failure {
+            Some(err) => Err(err),
+            None => Ok(results),
+        }
     }
 
-    pub async fn generate_session_title(&self, messages: &[Message]) -> Result<String, AppError> {
-        let content = messages
+    /// Non-streaming turn in which the model may call MCP tools before it
+    /// answers. The answer replaces an assistant placeholder that the tool
+    /// calls hang off.
+    pub async fn send_agent_message(
+        &self,
+        user_id: &str,
+        session_id: &str,
+        content: &str,
+        max_rounds: usize,
+        on_event: impl Fn(&ToolCallEvent) + Send + Sync,
+        on_approval: impl Fn(&ToolApprovalRequest) + Send + Sync,
+    ) -> Result<Message, AppError> {
+        let existing = self
+            .conversation_repo
+            .get_messages(session_id, 1_000, 0)
+            .await
+            .unwrap_or_default();
+        let position = existing.last().map(|m| m.position + 1).unwrap_or(0);
+        self.conversation_repo
+            .insert_message(NewMessage {
+                session_id: session_id.to_string(),
+                role: "user".to_string(),
+                content: content.to_string(),
+                content_type: "text".to_string(),
+                token_count: Some((content.len() / 4) as i64 + 1),
+                model_id: None,
+                metadata: "{}".to_string(),
+                attachments: "[]".to_string(),
+                position,
+            })
+            .await?;
+
+        let context = self
+            .context_service
+            .build_context(user_id, session_id, content, None)
+            .await?;
+        let routing = self
+            .task_router
+            .route(
+                user_id,
+                Some(session_id),
+                content,
+                None,
+                None,
+                false,
+                Some(estimate_context_tokens(&context)),
+            )
+            .await?;
+        if let Some(model) = self.resolve_target_model_for_routing(&routing).await? {
+            let profile = self.active_or_default_profile().await?;
+            self.ensure_model_loaded(&model, &profile).await?;
+        }
+
+        let assistant = self
+            .conversation_repo
+            .insert_message(NewMessage {
+                session_id: session_id.to_string(),
+                role: "assistant".to_string(),
+                content: String::new(),
+                content_type: "text".to_string(),
+                token_count: None,
+                model_id: routing.selected_model_id.clone(),
+                metadata: "{}".to_string(),
+                attachments: "[]".to_string(),
+                position: position + 1,
+            })
+            .await?;
+
+        let outcome = self
+            .run_tool_loop(
+                context.messages,
+                session_id,
+                &assistant.id,
+                user_id,
+                max_rounds,
+                on_event,
+                on_approval,
+            )
+            .await;
+        match outcome {
+            Ok(result) => {
+                self.conversation_repo
+                    .complete_message(&assistant.id, &result.text, "stop", None)
+                    .await?;
+            }
+            Err(error) => {
+                let message = error.to_string();
+                let _ = self
+                    .conversation_repo
+                    .complete_message(
+                        &assistant.id,
+                        &format!("[inference error] {message}"),
+                        "error",
+                        Some(&message),
+                    )
+                    .await;
+                return Err(error);
+            }
+        }
+
+        let reply = self
+            .conversation_repo
+            .get_message_by_id(&assistant.id)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "message".to_string(),
+                id: assistant.id.clone(),
+            })?;
+        self.webhooks
+            .publish(
+                Some(user_id),
+                WebhookEvent::MessageCompleted,
+                completed_message_event(&reply, Some("stop")),
+            )
+            .await;
+        Ok(reply)
+    }
+
+    /// Generates with the active MCP tools on offer, runs the calls the model
+    /// makes and feeds their results back, for at most `max_rounds` rounds. If
+    /// the model still wants tools after that, it is told to answer without them.
+    pub async fn run_tool_loop(
+        &self,
+        mut messages: Vec<Message>,
+        session_id: &str,
+        message_id: &str,
+        user_id: &str,
+        max_rounds: usize,
+        on_event: impl Fn(&ToolCallEvent) + Send + Sync,
+        on_approval: impl Fn(&ToolApprovalRequest) + Send + Sync,
+    ) -> Result<ToolLoopResult, AppError> {
+        let tools = self.mcp_service.active_tools().await?;
+        let schemas = tools
             .iter()
-            .take(2)
-            .map(|m| m.content.as_str())
-            .collect::<Vec<_>>()
-            .join(" ");
+            .map(|tool| tool.schema.to_string())
+            .collect::<Vec<_>>();
+        let mut tool_results = Vec::new();
+
+        for round in 0..max_rounds {
+            let generated = self
+                .inference_service
+                .generate_with_tools(messages.clone(), &schemas)
+                .await?;
+            let calls = parse_tool_calls(&generated.text);
+            if calls.is_empty() {
+                return Ok(ToolLoopResult {
+                    text: generated.text.trim().to_string(),
+                    rounds: round,
+                    tool_results,
+                });
+            }
 
-        let title = content
-            .split_whitespace()
-            .take(5)
+            let mut feedback = Vec::new();
+            let mut requests = Vec::new();
+            for call in calls {
+                match tools.iter().find(|tool| tool.name == call.name) {
+                    Some(tool) => requests.push(ToolCallRequest {
+                        mcp_id: tool.mcp_id.clone(),
+                        tool_name: call.name,
+                        args: call.arguments,
+                    }),
+                    None => feedback.push(format!("{}: error: no such tool", call.name)),
+                }
+            }
+            if !requests.is_empty() {
+                match self
+                    .process_tool_calls(
+                        requests,
+                        session_id,
+                        message_id,
+                        user_id,
+                        &on_event,
+                        &on_approval,
+                    )
+                    .await
+                {
+                    Ok(results) => {
+                        for result in results {
+                            let output = truncate_chars(&result.output, MAX_TOOL_RESULT_CHARS);
+                            feedback.push(format!("{}: {output}", result.tool_name));
+                            tool_results.push(result);
+                        }
+                    }
+                    // Denials and failures are reported to the model so it can recover.
+                    Err(error) => feedback.push(format!("error: {error}")),
+                }
+            }
+
+            messages.push(transient_message(session_id, "assistant", generated.text));
+            messages.push(transient_message(session_id, "tool", feedback.join("\n\n")));
+        }
+
+        messages.push(transient_message(
+            session_id,
+            "system",
+            "No more tool calls are available. Answer the user with what you have.".to_string(),
+        ));
+        let generated = self
+            .inference_service
+            .generate_with_tools(messages, &[])
+            .await?;
+        Ok(ToolLoopResult {
+            text: generated.text.trim().to_string(),
+            rounds: max_rounds,
+            tool_results,
+        })
+    }
+
+    /// Names the conversation with the loaded model, or Ollama when no local model
+    /// is loaded, falling back to its opening words when neither answers.
+    pub async fn generate_session_title(&self, messages: &[Message]) -> Result<String, AppError> {
+        let transcript = messages
+            .iter()
+            .filter(|m| m.role == "user" || m.role == "assistant")
+            .take(TITLE_SOURCE_MESSAGES)
+            .map(|m| {
+                let content: String = m.content.chars().take(TITLE_SOURCE_CHARS).collect();
+                format!("{}: {}", m.role, content)
+            })
             .collect::<Vec<_>>()
-            .join(" ");
+            .join("\n");
+        if transcript.is_empty() {
+            return Ok(heuristic_title(messages));
+        }
+        let instruction = format!(
+            "Write a title of at most 6 words for the conversation below. Reply with the title only, without quotes.\n\n{transcript}"
+        );
 
-        Ok(if title.is_empty() {
-            "New Conversation".to_string()
+        let reply = if self.inference_service.is_loaded().await {
+            let prompt = transient_message(&messages[0].session_id, "user", instruction);
+            let options = GenerationOptions {
+                temperature: 0.2,
+                max_tokens: 24,
+                ..GenerationOptions::default()
+            };
+            let label = format!("title:{}", messages[0].session_id);
+            match self
+                .inference_service
+                .generate_background(&label, vec![prompt], options)
+                .await
+            {
+                Ok(result) => Some(result.text),
+                Err(error) => {
+                    tracing::debug!("Local title generation failed: {error}");
+                    None
+                }
+            }
         } else {
-            title
-        })
+            self.ollama_title(&instruction).await
+        };
+
+        Ok(reply
+            .as_deref()
+            .and_then(clean_title)
+            .unwrap_or_else(|| heuristic_title(messages)))
     }
 
-    pub async fn summarize_session(&self, session_id: &str) -> Result<(), AppError> {
+    /// Asks the first model installed in a local Ollama; `None` when it isn't running.
+    async fn ollama_title(&self, instruction: &str) -> Option<String> {
+        let tags = self
+            .http
+            .get(format!("{OLLAMA_BASE_URL}/api/tags"))
+            .send()
+            .await
+            .ok()?
+            .json::<serde_json::Value>()
+            .await
+            .ok()?;
+        let model = tags
+            .pointer("/models/0/name")
+            .and_then(serde_json::Value::as_str)?;
+
+        let response = self
+            .http
+            .post(format!("{OLLAMA_BASE_URL}/api/generate"))
+            .json(&serde_json::json!({
+                "model": model,
+                "prompt": instruction,
+                "stream": false,
+                "options": { "temperature": 0.2, "num_predict": 24 },
+            }))
+            .send()
+            .await
+            .ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+        let payload = response.json::<serde_json::Value>().await.ok()?;
+        payload
+            .get("response")
+            .and_then(serde_json::Value::as_str)
+            .map(str::to_string)
+    }
+
+    /// Titles a session that has none yet. Returns the new title, or `None` when the
+    /// session was titled meanwhile.
+    pub async fn title_session(&self, session_id: &str) -> Result<Option<String>, AppError> {
+        let session = self
+            .conversation_repo
+            .get_session(session_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "session".to_string(),
+                id: session_id.to_string(),
+            })?;
+        if session
+            .title
+            .as_deref()
+            .is_some_and(|title| !title.trim().is_empty())
+        {
+            return Ok(None);
+        }
+
+        let messages = self
+            .conversation_repo
+            .get_messages(session_id, TITLE_SOURCE_MESSAGES as i64 * 2, 0)
+            .await?;
+        if messages.is_empty() {
+            return Ok(None);
+        }
+
+        let title = self.generate_session_title(&messages).await?;
+        self.conversation_repo
+            .update_session_title(session_id, &title)
+            .await?;
+        Ok(Some(title))
+    }
+
+    /// Refreshes the session summary (and a missing title) with the loaded model on the
+    /// background lane. Returns `false` when skipped because the machine is under pressure.
+    pub async fn summarize_session(&self, session_id: &str) -> Result<bool, AppError> {
+        let session = self
+            .conversation_repo
+            .get_session(session_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "session".to_string(),
+                id: session_id.to_string(),
+            })?;
         let messages = self
             .conversation_repo
             .get_messages(session_id, 500, 0)
             .await?;
         if messages.is_empty() {
-            return Ok(());
+            return Ok(false);
         }
 
-        let summary = messages
+        let policy = self.runtime_governor.get_policy(Some(&session.user_id)).await?;
+        let pressure = self
+            .runtime_governor
+            .classify_pressure(&self.runtime_governor.current_stats(), &policy);
+        if pressure == "high" || pressure == "critical" {
+            return Ok(false);
+        }
+
+        let transcript = messages
             .iter()
             .rev()
-            .take(8)
-            .map(|m| format!("{}: {}", m.role, m.content))
+            .take(24)
+            .rev()
+            .map(|m| {
+                let content: String = m.content.chars().take(600).collect();
+                format!("{}: {}", m.role, content)
+            })
             .collect::<Vec<_>>()
             .join("\n");
 
+        let mut prompt = messages[messages.len() - 1].clone();
+        prompt.role = "user".to_string();
+        prompt.content = format!(
+            "Summarize the conversation below for a history list.\nReply with exactly two lines:\nTITLE: <at most 8 words>\nSUMMARY: <two or three sentences>\n\n{transcript}"
+        );
+
+        let options = self.runtime_governor.tune_generation(
+            GenerationOptions {
+                temperature: 0.2,
+                max_tokens: 192,
+                ..GenerationOptions::default()
+            },
+            &policy,
+            "fast",
+            &pressure,
+            true,
+        );
+
+        let (title, summary) = match self
+            .inference_service
+            .generate_background(&format!("summarize:{session_id}"), vec![prompt], options)
+            .await
+        {
+            Ok(result) => parse_summary_reply(&result.text),
+            Err(error) => {
+                tracing::debug!("LLM summary unavailable for {session_id}: {error}");
+                (None, None)
+            }
+        };
+
+        let summary = summary.unwrap_or_else(|| {
+            messages
+                .iter()
+                .rev()
+                .take(8)
+                .map(|m| format!("{}: {}", m.role, m.content))
+                .collect::<Vec<_>>()
+                .join("\n")
+        });
         self.conversation_repo
             .update_session_summary(session_id, &summary)
-            .await
+            .await?;
+
+        let has_title = session
+            .title
+            .as_deref()
+            .map(|value| !value.trim().is_empty())
+            .unwrap_or(false);
+        if !has_title {
+            let title = match title {
+                Some(title) => title,
+                None => heuristic_title(&messages),
+            };
+            self.conversation_repo
+                .update_session_title(session_id, &title)
+                .await?;
+        }
+
+        Ok(true)
+    }
+}
+
+/// First line of a model reply, without a "Title:" prefix, quotes or a closing period.
+fn clean_title(reply: &str) -> Option<String> {
+    let line = reply.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let line = line
+        .strip_prefix("Title:")
+        .or_else(|| line.strip_prefix("TITLE:"))
+        .unwrap_or(line);
+    let title = line
+        .trim()
+        .trim_matches(|ch: char| matches!(ch, '"' | '\'' | '*' | '#' | '`'))
+        .trim_end_matches('.')
+        .trim();
+    (!title.is_empty()).then(|| title.chars().take(MAX_TITLE_CHARS).collect())
+}
+
+fn heuristic_title(messages: &[Message]) -> String {
+    let title = messages
+        .iter()
+        .take(2)
+        .flat_map(|m| m.content.split_whitespace())
+        .take(5)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if title.is_empty() {
+        "New Conversation".to_string()
+    } else {
+        title
+    }
+}
+
+fn parse_summary_reply(text: &str) -> (Option<String>, Option<String>) {
+    let mut title = None;
+    let mut summary = None;
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(value) = line.strip_prefix("TITLE:") {
+            let value = value.trim().trim_matches('"');
+            if !value.is_empty() {
+                title = Some(value.chars().take(80).collect());
+            }
+        } else if let Some(value) = line.strip_prefix("SUMMARY:") {
+            let value = value.trim();
+            if !value.is_empty() {
+                summary = Some(value.to_string());
+            }
+        }
+    }
+    (title, summary)
+}
+
+/// The `message.completed` webhook payload for an assistant reply.
+fn completed_message_event(message: &Message, finish_reason: Option<&str>) -> serde_json::Value {
+    serde_json::json!({
+        "sessionId": message.session_id,
+        "messageId": message.id,
+        "modelId": message.model_id,
+        "content": message.content,
+        "finishReason": finish_reason,
+    })
+}
+
+/// Adds the reply's citations to its stored metadata so the UI can rebuild the
+/// source list when the session is reopened.
+fn with_citations(metadata: &str, citations: &[Citation]) -> String {
+    if citations.is_empty() {
+        return metadata.to_string();
+    }
+    let mut value = serde_json::from_str::<serde_json::Value>(metadata)
+        .ok()
+        .filter(|value| value.is_object())
+        .unwrap_or_else(|| serde_json::json!({}));
+    value["citations"] = serde_json::json!(citations);
+    value.to_string()
+}
+
+/// Stands in for an image the model can't see.
+fn image_text_block(attachment: &MessageAttachment) -> String {
+    let name = Path::new(&attachment.path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(&attachment.path);
+    match attachment.ocr_text.as_deref().map(str::trim) {
+        Some(text) if !text.is_empty() => format!(
+            "\n\n[Attached image {name}, text recognized in it:]\n{}",
+            truncate_chars(text, MAX_IMAGE_TEXT_CHARS)
+        ),
+        _ => format!("\n\n[Attached image {name}, no readable text]"),
+    }
+}
+
+/// A prompt-only message that is never stored.
+fn transient_message(session_id: &str, role: &str, content: String) -> Message {
+    let now = chrono::Utc::now().to_rfc3339();
+    Message {
+        id: uuid::Uuid::new_v4().to_string(),
+        session_id: session_id.to_string(),
+        role: role.to_string(),
+        content,
+        content_type: "text".to_string(),
+        thinking: None,
+        token_count: None,
+        model_id: None,
+        latency_ms: None,
+        tokens_per_sec: None,
+        finish_reason: None,
+        is_error: 0,
+        error_message: None,
+        parent_message_id: None,
+        is_active_variant: 1,
+        edited_at: None,
+        original_content: None,
+        metadata: "{}".to_string(),
+        attachments: "[]".to_string(),
+        position: 0,
+        created_at: now.clone(),
+        updated_at: now,
+    }
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
     }
 }
@@ -8,6 +8,7 @@ use crate::error::AppError;
 
 const MASTER_KEY_BYTES: usize = 32;
 const NONCE_BYTES: usize = 12;
+const DATABASE_KEY_BYTES: usize = 32;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -80,6 +81,38 @@ impl CryptoService {
         })
     }
 
+    /// Raw SQLCipher key for app.db as hex, in its own keychain entry because the
+    /// database is opened before this service exists. `None` if there is no key
+    /// yet and `create` is false.
+    pub fn database_key(app_bundle_id: &str, create: bool) -> Result<Option<String>, AppError> {
+        let entry = keyring::Entry::new(&format!("{app_bundle_id}:database_key"), "local-user")?;
+        match entry.get_password() {
+            Ok(key) => {
+                let valid = key.len() == DATABASE_KEY_BYTES * 2
+                    && key.chars().all(|ch| ch.is_ascii_hexdigit());
+                if !valid {
+                    return Err(AppError::Crypto(
+                        "Stored database key has invalid format".to_string(),
+                    ));
+                }
+                Ok(Some(key))
+            }
+            Err(keyring::Error::NoEntry) if create => {
+                let mut bytes = [0u8; DATABASE_KEY_BYTES];
+                OsRng.fill_bytes(&mut bytes);
+                let key = bytes
+                    .iter()
+                    .map(|byte| format!("{byte:02x}"))
+                    .collect::<String>();
+                bytes.zeroize();
+                entry.set_password(&key)?;
+                Ok(Some(key))
+            }
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
     pub fn encrypt(&self, plaintext: &[u8]) -> Result<EncryptedData, AppError> {
         let cipher = Aes256Gcm::new_from_slice(&self.master_key)
             .map_err(|_| AppError::Crypto("Failed to initialize AES-256-GCM cipher".to_string()))?;
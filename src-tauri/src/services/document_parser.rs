@@ -0,0 +1,936 @@
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+use std::path::Path;
+
+use crate::error::AppError;
+
+const MAX_HEADING_CHARS: usize = 80;
+const MAX_HEADING_WORDS: usize = 12;
+/// Deeper numbering ("1.2.3.4.5") is almost always a list or a version string.
+const MAX_NUMBERED_DEPTH: usize = 4;
+/// Small definitions are packed together until a block has this many words.
+const MIN_CODE_BLOCK_WORDS: usize = 60;
+/// Definitions nested deeper than this (e.g. closures, inner helpers) don't
+/// start a block; methods directly inside a class or impl still do.
+const MAX_DEFINITION_INDENT: usize = 4;
+
+/// Words that open a function, type or module in the `code_language` languages,
+/// after any modifiers.
+const DEFINITION_KEYWORDS: &[&str] = &[
+    "fn",
+    "fun",
+    "func",
+    "function",
+    "def",
+    "class",
+    "struct",
+    "enum",
+    "trait",
+    "impl",
+    "interface",
+    "type",
+    "mod",
+    "module",
+    "object",
+    "namespace",
+    "record",
+    "protocol",
+    "extension",
+];
+
+const DEFINITION_MODIFIERS: &[&str] = &[
+    "pub",
+    "pub(crate)",
+    "pub(super)",
+    "export",
+    "default",
+    "async",
+    "static",
+    "public",
+    "private",
+    "protected",
+    "internal",
+    "abstract",
+    "final",
+    "sealed",
+    "override",
+    "open",
+    "data",
+    "inline",
+    "unsafe",
+    "extern",
+    "const",
+    "virtual",
+    "partial",
+];
+
+/// A run of text from one page and section of a document. Chunks never cross
+/// block boundaries, so each chunk has exactly one page and section.
+#[derive(Debug, Clone)]
+pub struct TextBlock {
+    pub text: String,
+    pub page_number: Option<i64>,
+    pub section_title: Option<String>,
+    /// Enclosing headings joined with " > ", outermost first.
+    pub heading_path: Option<String>,
+    /// Programming language for source files; their line breaks are kept.
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HtmlDocument {
+    /// Contents of `<title>`, whitespace collapsed.
+    pub title: Option<String>,
+    pub blocks: Vec<TextBlock>,
+}
+
+/// Extracts a PDF page by page, starting a new block at every heading. PDFs
+/// carry no reliable structure, so headings are guessed from numbering
+/// ("2.1 Results"), chapter words and all-caps lines.
+pub fn parse_pdf(path: &Path) -> Result<Vec<TextBlock>, AppError> {
+    let pages = pdf_extract::extract_text_by_pages(path)
+        .map_err(|e| AppError::Io(format!("Failed to read PDF: {e}")))?;
+
+    let mut outline = Outline::default();
+    let mut blocks = Vec::new();
+    for (index, page) in pages.iter().enumerate() {
+        let mut builder = BlockBuilder::new(Some(index as i64 + 1));
+        for line in page.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            match pdf_heading_level(line) {
+                Some(level) => builder.heading(&mut blocks, &mut outline, level, line),
+                None => builder.body(line),
+            }
+        }
+        builder.finish(&mut blocks, &outline);
+    }
+    Ok(blocks)
+}
+
+/// Splits Markdown at ATX headings (`#` to `######`), ignoring fenced code.
+pub fn parse_markdown(text: &str) -> Vec<TextBlock> {
+    let mut outline = Outline::default();
+    let mut blocks = Vec::new();
+    let mut builder = BlockBuilder::new(None);
+    let mut in_fence = false;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        let heading = if in_fence {
+            None
+        } else {
+            markdown_heading(trimmed)
+        };
+        match heading {
+            Some((level, title)) => builder.heading(&mut blocks, &mut outline, level, title),
+            None if trimmed.is_empty() => {}
+            None => builder.body(trimmed),
+        }
+    }
+    builder.finish(&mut blocks, &outline);
+    blocks
+}
+
+/// Reads `word/document.xml`; paragraphs styled "Heading N" or "Title" start sections.
+pub fn parse_docx(path: &Path) -> Result<Vec<TextBlock>, AppError> {
+    let mut archive = open_zip(path)?;
+    let xml = read_zip_entry(&mut archive, "word/document.xml")?;
+
+    let mut outline = Outline::default();
+    let mut blocks = Vec::new();
+    let mut builder = BlockBuilder::new(None);
+    let mut paragraph = String::new();
+    let mut heading_level = None;
+    let mut in_text = false;
+    for token in xml_tokens(&xml) {
+        match token {
+            XmlToken::Open {
+                name,
+                tag,
+                self_closing,
+            } => match local_name(name) {
+                "pStyle" => {
+                    heading_level = attribute(tag, "w:val")
+                        .and_then(docx_heading_level)
+                        .or(heading_level);
+                }
+                "outlineLvl" => {
+                    heading_level = attribute(tag, "w:val")
+                        .and_then(|level| level.parse::<usize>().ok())
+                        .map(|level| level + 1)
+                        .or(heading_level);
+                }
+                "t" if !self_closing => in_text = true,
+                "tab" => paragraph.push(' '),
+                "br" | "cr" => paragraph.push('\n'),
+                _ => {}
+            },
+            XmlToken::Close(name) => match local_name(name) {
+                "t" => in_text = false,
+                "p" => {
+                    push_paragraph(
+                        &mut builder,
+                        &mut blocks,
+                        &mut outline,
+                        heading_level.take(),
+                        &mut paragraph,
+                    );
+                }
+                _ => {}
+            },
+            XmlToken::Text(text) if in_text => paragraph.push_str(&decode_entities(text)),
+            XmlToken::Text(_) => {}
+        }
+    }
+    builder.finish(&mut blocks, &outline);
+    Ok(blocks)
+}
+
+/// Reads the chapters in spine order; `h1`-`h6` start sections across the book.
+pub fn parse_epub(path: &Path) -> Result<Vec<TextBlock>, AppError> {
+    let mut archive = open_zip(path)?;
+    let container = read_zip_entry(&mut archive, "META-INF/container.xml")?;
+    let package_path = xml_tokens(&container)
+        .into_iter()
+        .find_map(|token| match token {
+            XmlToken::Open { name, tag, .. } if local_name(name) == "rootfile" => {
+                attribute(tag, "full-path").map(str::to_string)
+            }
+            _ => None,
+        })
+        .ok_or_else(|| AppError::Io("EPUB has no package document".to_string()))?;
+    let package = read_zip_entry(&mut archive, &package_path)?;
+    let base = package_path.rsplit_once('/').map_or("", |(dir, _)| dir);
+
+    let mut hrefs = HashMap::new();
+    let mut spine = Vec::new();
+    for token in xml_tokens(&package) {
+        let XmlToken::Open { name, tag, .. } = token else {
+            continue;
+        };
+        match local_name(name) {
+            "item" => {
+                if let (Some(id), Some(href)) = (attribute(tag, "id"), attribute(tag, "href")) {
+                    hrefs.insert(id.to_string(), resolve_href(base, href));
+                }
+            }
+            "itemref" => {
+                if let Some(idref) = attribute(tag, "idref") {
+                    spine.push(idref.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut outline = Outline::default();
+    let mut blocks = Vec::new();
+    for idref in spine {
+        let Some(href) = hrefs.get(&idref) else {
+            continue;
+        };
+        // One unreadable chapter shouldn't lose the rest of the book.
+        if let Ok(xhtml) = read_zip_entry(&mut archive, href) {
+            parse_xhtml(&xhtml, &mut outline, &mut blocks);
+        }
+    }
+    Ok(blocks)
+}
+
+/// Readable text of a web page. Navigation, footers, forms and scripts are
+/// dropped, and only `<main>` (or the first `<article>`) is kept when the page
+/// has one.
+pub fn parse_html(html: &str) -> HtmlDocument {
+    let tokens = xml_tokens(html);
+    let mut outline = Outline::default();
+    let mut blocks = Vec::new();
+    walk_html(
+        main_content(&tokens),
+        is_boilerplate_element,
+        &mut outline,
+        &mut blocks,
+    );
+    HtmlDocument {
+        title: page_title(&tokens),
+        blocks,
+    }
+}
+
+/// Language name for source files the code chunker understands.
+pub fn code_language(extension: &str) -> Option<&'static str> {
+    let language = match extension.to_ascii_lowercase().as_str() {
+        "rs" => "rust",
+        "py" | "pyi" => "python",
+        "js" | "mjs" | "cjs" | "jsx" => "javascript",
+        "ts" | "mts" | "cts" | "tsx" => "typescript",
+        "go" => "go",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "swift" => "swift",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" => "cpp",
+        "cs" => "csharp",
+        "rb" => "ruby",
+        "php" => "php",
+        "scala" => "scala",
+        "lua" => "lua",
+        _ => return None,
+    };
+    Some(language)
+}
+
+/// Splits source at top-level definitions (and methods one level in), with
+/// their doc comments and attributes, packing small ones together. Each block
+/// is titled with the signature of its first definition.
+pub fn parse_code(text: &str, language: &str) -> Vec<TextBlock> {
+    let lines = text.lines().collect::<Vec<_>>();
+    let mut starts = vec![0];
+    for (index, line) in lines.iter().enumerate().skip(1) {
+        if !is_definition(line) {
+            continue;
+        }
+        let mut start = index;
+        while start > 0 && is_definition_preamble(lines[start - 1]) {
+            start -= 1;
+        }
+        if start > starts.last().copied().unwrap_or(0) {
+            starts.push(start);
+        }
+    }
+    starts.push(lines.len());
+
+    let mut blocks = Vec::new();
+    let mut text = String::new();
+    let mut title = None;
+    let mut words = 0;
+    for window in starts.windows(2) {
+        let segment = &lines[window[0]..window[1]];
+        if words >= MIN_CODE_BLOCK_WORDS {
+            blocks.push(code_block(
+                std::mem::take(&mut text),
+                title.take(),
+                language,
+            ));
+            words = 0;
+        }
+        if title.is_none() {
+            title = segment
+                .iter()
+                .find(|line| is_definition(line))
+                .map(|line| signature(line));
+        }
+        for line in segment {
+            text.push_str(line);
+            text.push('\n');
+            words += line.split_whitespace().count();
+        }
+    }
+    if !text.trim().is_empty() {
+        blocks.push(code_block(text, title, language));
+    }
+    blocks
+}
+
+/// Formats without structure become one block.
+pub fn plain_text(text: String) -> Vec<TextBlock> {
+    vec![TextBlock {
+        text,
+        page_number: None,
+        section_title: None,
+        heading_path: None,
+        language: None,
+    }]
+}
+
+/// Open headings, outermost first, with their levels.
+#[derive(Default)]
+struct Outline {
+    headings: Vec<(usize, String)>,
+}
+
+impl Outline {
+    fn enter(&mut self, level: usize, title: &str) {
+        while self.headings.last().is_some_and(|(open, _)| *open >= level) {
+            self.headings.pop();
+        }
+        self.headings.push((level, title.to_string()));
+    }
+
+    fn section_title(&self) -> Option<String> {
+        self.headings.last().map(|(_, title)| title.clone())
+    }
+
+    fn heading_path(&self) -> Option<String> {
+        if self.headings.is_empty() {
+            return None;
+        }
+        Some(
+            self.headings
+                .iter()
+                .map(|(_, title)| title.as_str())
+                .collect::<Vec<_>>()
+                .join(" > "),
+        )
+    }
+}
+
+struct BlockBuilder {
+    page_number: Option<i64>,
+    text: String,
+    has_body: bool,
+}
+
+impl BlockBuilder {
+    fn new(page_number: Option<i64>) -> Self {
+        Self {
+            page_number,
+            text: String::new(),
+            has_body: false,
+        }
+    }
+
+    /// Headings stay in the text so they are searchable. A heading directly
+    /// under another joins its block instead of leaving a heading-only chunk.
+    fn heading(
+        &mut self,
+        blocks: &mut Vec<TextBlock>,
+        outline: &mut Outline,
+        level: usize,
+        title: &str,
+    ) {
+        if self.has_body {
+            self.flush(blocks, outline);
+        }
+        outline.enter(level, title);
+        self.push_line(title);
+    }
+
+    fn body(&mut self, line: &str) {
+        self.has_body = true;
+        self.push_line(line);
+    }
+
+    fn finish(mut self, blocks: &mut Vec<TextBlock>, outline: &Outline) {
+        self.flush(blocks, outline);
+    }
+
+    fn push_line(&mut self, line: &str) {
+        self.text.push_str(line);
+        self.text.push('\n');
+    }
+
+    fn flush(&mut self, blocks: &mut Vec<TextBlock>, outline: &Outline) {
+        if self.text.trim().is_empty() {
+            return;
+        }
+        blocks.push(TextBlock {
+            text: std::mem::take(&mut self.text),
+            page_number: self.page_number,
+            section_title: outline.section_title(),
+            heading_path: outline.heading_path(),
+            language: None,
+        });
+        self.has_body = false;
+    }
+}
+
+fn pdf_heading_level(line: &str) -> Option<usize> {
+    let words = line.split_whitespace().count();
+    if line.chars().count() > MAX_HEADING_CHARS || words > MAX_HEADING_WORDS {
+        return None;
+    }
+    if line.ends_with(['.', ',', ';', ':', '!', '?']) {
+        return None;
+    }
+    if let Some(depth) = numbered_heading_depth(line) {
+        return Some(depth);
+    }
+
+    let first_word = line.split_whitespace().next()?.to_ascii_lowercase();
+    if matches!(
+        first_word.as_str(),
+        "chapter" | "part" | "section" | "appendix"
+    ) && words >= 2
+    {
+        return Some(1);
+    }
+
+    let letters = line.chars().filter(|c| c.is_alphabetic()).count();
+    let all_caps = letters >= 4 && !line.chars().any(|c| c.is_lowercase());
+    all_caps.then_some(1)
+}
+
+/// "2.1 Results" or "3. Method" -> depth 2 or 1. The title must start with a
+/// capital letter, which rules out table rows and bare page numbers.
+fn numbered_heading_depth(line: &str) -> Option<usize> {
+    let (number, title) = line.split_once(char::is_whitespace)?;
+    let number = number.strip_suffix('.').unwrap_or(number);
+    let parts = number.split('.').collect::<Vec<_>>();
+    if parts.len() > MAX_NUMBERED_DEPTH
+        || parts.iter().any(|part| {
+            part.is_empty() || part.len() > 2 || !part.bytes().all(|b| b.is_ascii_digit())
+        })
+    {
+        return None;
+    }
+    title
+        .trim_start()
+        .chars()
+        .next()
+        .filter(|c| c.is_uppercase())
+        .map(|_| parts.len())
+}
+
+fn markdown_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let title = line[level..]
+        .strip_prefix(' ')?
+        .trim()
+        .trim_end_matches('#')
+        .trim();
+    (!title.is_empty()).then_some((level, title))
+}
+
+fn push_paragraph(
+    builder: &mut BlockBuilder,
+    blocks: &mut Vec<TextBlock>,
+    outline: &mut Outline,
+    heading_level: Option<usize>,
+    paragraph: &mut String,
+) -> bool {
+    let text = paragraph.split_whitespace().collect::<Vec<_>>().join(" ");
+    paragraph.clear();
+    if text.is_empty() {
+        return false;
+    }
+    match heading_level {
+        Some(level) => builder.heading(blocks, outline, level, &text),
+        None => builder.body(&text),
+    }
+    true
+}
+
+fn parse_xhtml(xhtml: &str, outline: &mut Outline, blocks: &mut Vec<TextBlock>) {
+    walk_html(&xml_tokens(xhtml), is_hidden_element, outline, blocks);
+}
+
+/// Turns HTML into blocks: `h1`-`h6` start sections and block elements end
+/// paragraphs. Text inside elements `is_hidden` accepts is dropped.
+fn walk_html(
+    tokens: &[XmlToken<'_>],
+    is_hidden: fn(&str) -> bool,
+    outline: &mut Outline,
+    blocks: &mut Vec<TextBlock>,
+) {
+    let mut builder = BlockBuilder::new(None);
+    let mut paragraph = String::new();
+    let mut heading_level = None;
+    let mut hidden_depth = 0usize;
+    for token in tokens {
+        match *token {
+            XmlToken::Open {
+                name, self_closing, ..
+            } => {
+                let name = local_name(name).to_ascii_lowercase();
+                if is_hidden(&name) {
+                    if !self_closing {
+                        hidden_depth += 1;
+                    }
+                    continue;
+                }
+                if name == "br" {
+                    paragraph.push('\n');
+                    continue;
+                }
+                let level = html_heading_level(&name);
+                if level.is_some() || is_block_element(&name) {
+                    if push_paragraph(&mut builder, blocks, outline, heading_level, &mut paragraph)
+                    {
+                        heading_level = None;
+                    }
+                    if level.is_some() {
+                        heading_level = level;
+                    }
+                }
+            }
+            XmlToken::Close(name) => {
+                let name = local_name(name).to_ascii_lowercase();
+                if is_hidden(&name) {
+                    hidden_depth = hidden_depth.saturating_sub(1);
+                    continue;
+                }
+                let is_heading = html_heading_level(&name).is_some();
+                if is_heading || is_block_element(&name) {
+                    push_paragraph(&mut builder, blocks, outline, heading_level, &mut paragraph);
+                    heading_level = None;
+                }
+            }
+            XmlToken::Text(text) if hidden_depth == 0 => {
+                paragraph.push_str(&decode_entities(text));
+            }
+            XmlToken::Text(_) => {}
+        }
+    }
+    push_paragraph(&mut builder, blocks, outline, heading_level, &mut paragraph);
+    builder.finish(blocks, outline);
+}
+
+fn is_hidden_element(name: &str) -> bool {
+    matches!(name, "head" | "script" | "style")
+}
+
+/// Page chrome that never holds the content a reader came for.
+fn is_boilerplate_element(name: &str) -> bool {
+    is_hidden_element(name)
+        || matches!(
+            name,
+            "nav"
+                | "footer"
+                | "aside"
+                | "form"
+                | "button"
+                | "select"
+                | "noscript"
+                | "template"
+                | "svg"
+                | "iframe"
+                | "dialog"
+        )
+}
+
+fn page_title(tokens: &[XmlToken<'_>]) -> Option<String> {
+    let start = tokens.iter().position(
+        |token| matches!(token, XmlToken::Open { name, .. } if name.eq_ignore_ascii_case("title")),
+    )?;
+    let text = tokens[start + 1..]
+        .iter()
+        .map_while(|token| match token {
+            XmlToken::Text(text) => Some(decode_entities(text)),
+            _ => None,
+        })
+        .collect::<String>();
+    let title = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+/// The tokens of `<main>`, else of the first `<article>`, else all of them.
+fn main_content<'t, 'a>(tokens: &'t [XmlToken<'a>]) -> &'t [XmlToken<'a>] {
+    for container in ["main", "article"] {
+        let is_container = |name: &str| local_name(name).eq_ignore_ascii_case(container);
+        let Some(start) = tokens.iter().position(|token| {
+            matches!(token, XmlToken::Open { name, self_closing: false, .. } if is_container(name))
+        }) else {
+            continue;
+        };
+
+        let mut depth = 0usize;
+        for (offset, token) in tokens[start..].iter().enumerate() {
+            match token {
+                XmlToken::Open {
+                    name,
+                    self_closing: false,
+                    ..
+                } if is_container(name) => depth += 1,
+                XmlToken::Close(name) if is_container(name) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return &tokens[start..=start + offset];
+                    }
+                }
+                _ => {}
+            }
+        }
+        return &tokens[start..];
+    }
+    tokens
+}
+
+fn is_block_element(name: &str) -> bool {
+    matches!(
+        name,
+        "p" | "div"
+            | "li"
+            | "blockquote"
+            | "pre"
+            | "tr"
+            | "td"
+            | "th"
+            | "section"
+            | "article"
+            | "figcaption"
+            | "dt"
+            | "dd"
+    )
+}
+
+fn html_heading_level(name: &str) -> Option<usize> {
+    let level = name.strip_prefix('h')?.parse::<usize>().ok()?;
+    (1..=6).contains(&level).then_some(level)
+}
+
+fn docx_heading_level(style: &str) -> Option<usize> {
+    let style = style.to_ascii_lowercase().replace(' ', "");
+    if style == "title" {
+        return Some(1);
+    }
+    style
+        .strip_prefix("heading")?
+        .parse::<usize>()
+        .ok()
+        .filter(|level| (1..=9).contains(level))
+}
+
+fn code_block(text: String, title: Option<String>, language: &str) -> TextBlock {
+    TextBlock {
+        text,
+        page_number: None,
+        section_title: title,
+        heading_path: None,
+        language: Some(language.to_string()),
+    }
+}
+
+fn is_definition(line: &str) -> bool {
+    let indent = line
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum::<usize>();
+    if indent > MAX_DEFINITION_INDENT {
+        return false;
+    }
+    let mut words = line.split_whitespace();
+    let Some(keyword) = words.find(|word| !DEFINITION_MODIFIERS.contains(word)) else {
+        return false;
+    };
+    // `impl<T>` and `class Foo:` still count; `type = 3` does not.
+    let keyword = keyword
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+        .unwrap_or(keyword);
+    DEFINITION_KEYWORDS.contains(&keyword)
+        && !words
+            .next()
+            .is_some_and(|next| next.starts_with('=') || next.starts_with(':'))
+}
+
+/// Doc comments, attributes and decorators belong to the definition below them.
+fn is_definition_preamble(line: &str) -> bool {
+    let line = line.trim_start();
+    ["///", "//", "#[", "#", "@", "/*", "*"]
+        .iter()
+        .any(|prefix| line.starts_with(prefix))
+}
+
+fn signature(line: &str) -> String {
+    let signature = line.trim().trim_end_matches('{').trim_end();
+    match signature.char_indices().nth(MAX_HEADING_CHARS) {
+        Some((end, _)) => format!("{}…", &signature[..end]),
+        None => signature.to_string(),
+    }
+}
+
+enum XmlToken<'a> {
+    Open {
+        name: &'a str,
+        /// Everything between `<` and `>`, for attribute lookups.
+        tag: &'a str,
+        self_closing: bool,
+    },
+    Close(&'a str),
+    Text(&'a str),
+}
+
+/// Just enough XML tokenizing for document bodies: comments, declarations
+/// and processing instructions are skipped, and CDATA is passed through as text.
+fn xml_tokens(xml: &str) -> Vec<XmlToken<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = xml;
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            tokens.push(XmlToken::Text(rest));
+            break;
+        };
+        if start > 0 {
+            tokens.push(XmlToken::Text(&rest[..start]));
+            rest = &rest[start..];
+        }
+
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let end = after.find("]]>").unwrap_or(after.len());
+            tokens.push(XmlToken::Text(&after[..end]));
+            rest = after.get(end + 3..).unwrap_or("");
+            continue;
+        }
+
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        if tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+        if let Some(name) = tag.strip_prefix('/') {
+            tokens.push(XmlToken::Close(name.trim()));
+            continue;
+        }
+        let self_closing = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let name = tag.split_whitespace().next().unwrap_or("");
+        tokens.push(XmlToken::Open {
+            name,
+            tag,
+            self_closing,
+        });
+
+        // Script and style bodies are raw text; a `<` inside them is not a tag.
+        if !self_closing
+            && (name.eq_ignore_ascii_case("script") || name.eq_ignore_ascii_case("style"))
+        {
+            let close = format!("</{}", name.to_ascii_lowercase());
+            let end = rest.to_ascii_lowercase().find(&close).unwrap_or(rest.len());
+            tokens.push(XmlToken::Text(&rest[..end]));
+            rest = &rest[end..];
+        }
+    }
+    tokens
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut search = tag;
+    while let Some(position) = search.find(name) {
+        let preceded_by_space = search[..position].ends_with(char::is_whitespace);
+        let after = &search[position + name.len()..];
+        if preceded_by_space {
+            if let Some(value) = after.trim_start().strip_prefix('=') {
+                let value = value.trim_start();
+                let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+                let value = &value[1..];
+                return value.find(quote).map(|end| &value[..end]);
+            }
+        }
+        search = after;
+    }
+    None
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').filter(|end| *end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let ch = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                "ndash" => Some('–'),
+                "mdash" => Some('—'),
+                "hellip" => Some('…'),
+                "lsquo" => Some('‘'),
+                "rsquo" => Some('’'),
+                "ldquo" => Some('“'),
+                "rdquo" => Some('”'),
+                "copy" => Some('©'),
+                _ => entity.strip_prefix('#').and_then(|code| {
+                    match code.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => code.parse::<u32>().ok(),
+                    }
+                    .and_then(char::from_u32)
+                }),
+            };
+            ch.map(|ch| (ch, end))
+        });
+        match decoded {
+            Some((ch, end)) => {
+                out.push(ch);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn open_zip(path: &Path) -> Result<zip::ZipArchive<std::fs::File>, AppError> {
+    let file = std::fs::File::open(path)?;
+    zip::ZipArchive::new(file).map_err(|e| AppError::Io(format!("Failed to open archive: {e}")))
+}
+
+fn read_zip_entry<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    name: &str,
+) -> Result<String, AppError> {
+    let mut entry = archive
+        .by_name(name)
+        .map_err(|e| AppError::Io(format!("Failed to read {name}: {e}")))?;
+    let mut text = String::new();
+    entry.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+/// Resolves a package-relative href to a zip entry name.
+fn resolve_href(base: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or(href);
+    let mut parts = base
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>();
+    for segment in href.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(segment),
+        }
+    }
+    percent_decode(&parts.join("/"))
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' && index + 2 < bytes.len() {
+            let byte = std::str::from_utf8(&bytes[index + 1..index + 3])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            if let Some(byte) = byte {
+                out.push(byte);
+                index += 3;
+                continue;
+            }
+        }
+        out.push(bytes[index]);
+        index += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
@@ -1,17 +1,50 @@
 use std::hash::{Hash, Hasher};
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::{SystemTime, UNIX_EPOCH};
-use fastembed::{InitOptions, TextEmbedding};
+use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
 use moka::future::Cache;
 
 use crate::error::AppError;
 use crate::repositories::embedding_repo::EmbeddingRepo;
 use crate::services::hardware_service::{HardwareService, PerformanceMode};
 
+/// Model chosen by the last completed re-index; it wins over the tier default.
+pub const EMBEDDING_SETTINGS_NAMESPACE: &str = "embedding";
+pub const EMBEDDING_MODEL_KEY: &str = "model";
+
+/// Models `reindex_documents` can switch to, by the names used in tier configs.
+pub const SUPPORTED_EMBEDDING_MODELS: &[&str] = &[
+    "bge-small-en-v1.5",
+    "bge-base-en-v1.5",
+    "bge-large-en-v1.5",
+    "all-minilm-l6-v2",
+    "multilingual-e5-small",
+    "multilingual-e5-base",
+    "nomic-embed-text-v1.5",
+];
+
+fn fastembed_model(model_name: &str) -> Option<EmbeddingModel> {
+    match model_name {
+        "bge-small-en-v1.5" => Some(EmbeddingModel::BGESmallENV15),
+        "bge-base-en-v1.5" => Some(EmbeddingModel::BGEBaseENV15),
+        "bge-large-en-v1.5" => Some(EmbeddingModel::BGELargeENV15),
+        "all-minilm-l6-v2" => Some(EmbeddingModel::AllMiniLML6V2),
+        "multilingual-e5-small" => Some(EmbeddingModel::MultilingualE5Small),
+        "multilingual-e5-base" => Some(EmbeddingModel::MultilingualE5Base),
+        "nomic-embed-text-v1.5" => Some(EmbeddingModel::NomicEmbedTextV15),
+        _ => None,
+    }
+}
+
+pub fn is_supported_model(model_name: &str) -> bool {
+    fastembed_model(model_name).is_some()
+}
+
 pub struct EmbeddingService {
-    model_name: String,
+    /// Shared by clones so a re-index swap switches every holder at once.
+    model_name: Arc<RwLock<String>>,
     hardware: Arc<HardwareService>,
     engine: Arc<Mutex<Option<TextEmbedding>>>,
     initialized: AtomicBool,
@@ -24,7 +57,7 @@ pub struct EmbeddingService {
 impl Clone for EmbeddingService {
     fn clone(&self) -> Self {
         Self {
-            model_name: self.model_name.clone(),
+            model_name: Arc::clone(&self.model_name),
             hardware: Arc::clone(&self.hardware),
             engine: Arc::clone(&self.engine),
             initialized: AtomicBool::new(self.initialized.load(Ordering::Relaxed)),
@@ -44,7 +77,7 @@ impl EmbeddingService {
         hardware: Arc<HardwareService>,
     ) -> Result<Self, AppError> {
         Ok(Self {
-            model_name: model_name.to_string(),
+            model_name: Arc::new(RwLock::new(model_name.to_string())),
             hardware,
             engine: Arc::new(Mutex::new(None)),
             initialized: AtomicBool::new(false),
@@ -87,7 +120,8 @@ impl EmbeddingService {
             // On Windows, use DirectML ONLY. DirectML provides GPU acceleration via DirectX 
             // and is native to Windows, avoiding the "missing cublasLt64_12.dll" errors 
             // common with the CUDA provider on systems without the full CUDA Toolkit.
-            if stats.gpu_vram_mb.unwrap_or(0) >= 1024 {
+            // Metal-only machines report unified memory as VRAM but have no ONNX GPU provider here.
+            if stats.gpu_vram_mb.unwrap_or(0) >= 1024 && stats.supports_cuda + stats.supports_vulkan > 0 {
                 if cfg!(target_os = "windows") {
                     providers.push(ort::execution_providers::DirectMLExecutionProvider::default().build());
                 } else {
@@ -96,7 +130,11 @@ impl EmbeddingService {
                 crate::log_info!("sarah.embedding", "Enabled ONNX GPU Execution Providers for Embeddings");
             }
 
-            let options = InitOptions::new(fastembed::EmbeddingModel::BGESmallENV15)
+            // Names outside the supported list predate model selection and
+            // always meant the small BGE model.
+            let model = fastembed_model(&self.model_name())
+                .unwrap_or(EmbeddingModel::BGESmallENV15);
+            let options = InitOptions::new(model)
                 .with_show_download_progress(true)
                 .with_execution_providers(providers);
 
@@ -146,10 +184,77 @@ impl EmbeddingService {
         });
     }
 
+    pub fn model_name(&self) -> String {
+        self.model_name
+            .read()
+            .map(|name| name.clone())
+            .unwrap_or_default()
+    }
+
+    /// A separate, lazily loaded instance of `model_name` that shares this one's
+    /// hardware governance and repo, e.g. to re-embed while this one serves queries.
+    pub fn with_model(&self, model_name: &str) -> Result<Self, AppError> {
+        if !is_supported_model(model_name) {
+            return Err(AppError::Embedding(format!(
+                "Unsupported embedding model: {model_name}"
+            )));
+        }
+        Self::new(
+            model_name,
+            self._cache_dir.clone(),
+            self.embedding_repo.clone(),
+            Arc::clone(&self.hardware),
+        )
+    }
+
+    /// Switches to `model_name` for every clone. The old session is dropped and
+    /// cached vectors, which belong to the old model, are discarded.
+    pub fn switch_model(&self, model_name: &str) -> Result<(), AppError> {
+        if !is_supported_model(model_name) {
+            return Err(AppError::Embedding(format!(
+                "Unsupported embedding model: {model_name}"
+            )));
+        }
+        {
+            let mut name = self
+                .model_name
+                .write()
+                .map_err(|_| AppError::Embedding("Embedding model lock poisoned".to_string()))?;
+            *name = model_name.to_string();
+        }
+        self.unload();
+        self.clear_cache();
+        Ok(())
+    }
+
     pub fn is_initialized(&self) -> bool {
         self.initialized.load(Ordering::Relaxed)
     }
 
+    /// Whether the ONNX session is resident, which can differ from `is_initialized`
+    /// after an idle or budget-driven unload.
+    pub fn is_loaded(&self) -> bool {
+        self.engine.lock().map(|guard| guard.is_some()).unwrap_or(false)
+    }
+
+    /// Drops the ONNX session; the next call re-initializes it lazily.
+    pub fn unload(&self) -> bool {
+        let Ok(mut guard) = self.engine.lock() else {
+            return false;
+        };
+        let was_loaded = guard.take().is_some();
+        self.initialized.store(false, Ordering::Relaxed);
+        was_loaded
+    }
+
+    pub fn cached_vectors(&self) -> u64 {
+        self.cache.entry_count()
+    }
+
+    pub fn clear_cache(&self) {
+        self.cache.invalidate_all();
+    }
+
     pub async fn embed_text(&self, text: &str) -> Result<Vec<f32>, AppError> {
         let key = self.hash_text(text);
         if let Some(value) = self.cache.get(&key).await {
@@ -255,7 +360,7 @@ impl EmbeddingService {
                 user_id,
                 namespace,
                 vector,
-                &self.model_name,
+                &self.model_name(),
             )
             .await
     }
@@ -0,0 +1,872 @@
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::error::AppError;
+use crate::repositories::settings_repo::SettingsRepo;
+use crate::services::document_parser;
+
+/// Seeded by migration 0028; `McpService::call_tool` hands its calls here.
+pub const BUILTIN_FILESYSTEM_MCP_ID: &str = "builtin-filesystem";
+pub const FILESYSTEM_SETTINGS_NAMESPACE: &str = "filesystem_tools";
+const ROOTS_KEY: &str = "roots";
+
+/// The model sees at most this much of a file; the rest is cut.
+const MAX_READ_BYTES: usize = 256 * 1024;
+const MAX_WRITE_BYTES: usize = 1024 * 1024;
+const MAX_LIST_ENTRIES: usize = 500;
+const MAX_SEARCH_RESULTS: usize = 100;
+const MAX_SEARCH_DEPTH: usize = 8;
+/// Files and folders a search looks at before giving up.
+const MAX_SEARCH_VISITS: usize = 20_000;
+/// Larger files are matched by name only when searching contents.
+const MAX_CONTENT_SEARCH_BYTES: u64 = 1024 * 1024;
+/// A NUL in the first bytes marks a file as binary.
+const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+
+/// A folder the file tools may use. Only writable roots accept `write_file`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilesystemRoot {
+    /// Canonical, as stored by `add_root`.
+    pub path: String,
+    pub writable: bool,
+}
+
+/// JSON schemas for the tools, in the shape MCP servers advertise them.
+pub fn tool_schemas() -> Vec<Value> {
+    vec![
+        json!({
+            "name": "list_dir",
+            "description": "List the files and folders in a folder the user approved, with sizes and modification times. Without a path, lists the approved folders.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Folder to list, absolute or starting with ~" }
+                }
+            }
+        }),
+        json!({
+            "name": "read_file",
+            "description": "Read a text, PDF or Word file in a folder the user approved.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "File to read, absolute or starting with ~" }
+                },
+                "required": ["path"]
+            }
+        }),
+        json!({
+            "name": "search_files",
+            "description": "Find files by name, and optionally by text they contain, in the folders the user approved.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "pattern": { "type": "string", "description": "Part of the file name; * matches anything, e.g. *.pdf" },
+                    "contains": { "type": "string", "description": "Only files containing this text" },
+                    "path": { "type": "string", "description": "Folder to search in; all approved folders when omitted" }
+                },
+                "required": ["pattern"]
+            }
+        }),
+        json!({
+            "name": "write_file",
+            "description": "Create or overwrite a text file in a folder the user approved for writing.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "File to write, absolute or starting with ~" },
+                    "content": { "type": "string" },
+                    "append": { "type": "boolean", "description": "Add to the end instead of replacing" }
+                },
+                "required": ["path", "content"]
+            }
+        }),
+    ]
+}
+
+/// The built-in file tools: `list_dir`, `read_file`, `search_files` and
+/// `write_file`, confined to the folders the user approved. Paths are
+/// canonicalized before the check, so `..` and symlinks can't step outside.
+#[derive(Clone)]
+pub struct FilesystemTools {
+    settings_repo: SettingsRepo,
+}
+
+impl FilesystemTools {
+    pub fn new(settings_repo: SettingsRepo) -> Self {
+        Self { settings_repo }
+    }
+
+    pub async fn roots(&self) -> Result<Vec<FilesystemRoot>, AppError> {
+        let setting = self
+            .settings_repo
+            .get_setting(None, FILESYSTEM_SETTINGS_NAMESPACE, ROOTS_KEY)
+            .await?;
+        Ok(setting
+            .and_then(|setting| serde_json::from_str(&setting.value).ok())
+            .unwrap_or_default())
+    }
+
+    /// Approves `path`, or changes whether it is writable if already approved.
+    pub async fn add_root(
+        &self,
+        path: &str,
+        writable: bool,
+    ) -> Result<Vec<FilesystemRoot>, AppError> {
+        let canonical =
+            std::fs::canonicalize(expand_home(path.trim())).map_err(|e| AppError::Validation {
+                field: "path".to_string(),
+                message: format!("Can't open {path}: {e}"),
+            })?;
+        if !canonical.is_dir() {
+            return Err(AppError::Validation {
+                field: "path".to_string(),
+                message: format!("{path} is not a folder"),
+            });
+        }
+        let canonical = canonical.to_string_lossy().to_string();
+
+        let mut roots = self.roots().await?;
+        match roots.iter_mut().find(|root| root.path == canonical) {
+            Some(root) => root.writable = writable,
+            None => roots.push(FilesystemRoot {
+                path: canonical,
+                writable,
+            }),
+        }
+        self.save_roots(&roots).await?;
+        Ok(roots)
+    }
+
+    pub async fn remove_root(&self, path: &str) -> Result<Vec<FilesystemRoot>, AppError> {
+        let mut roots = self.roots().await?;
+        let before = roots.len();
+        roots.retain(|root| root.path != path);
+        if roots.len() == before {
+            return Err(AppError::NotFound {
+                entity: "filesystem_root".to_string(),
+                id: path.to_string(),
+            });
+        }
+        self.save_roots(&roots).await?;
+        Ok(roots)
+    }
+
+    async fn save_roots(&self, roots: &[FilesystemRoot]) -> Result<(), AppError> {
+        let json = serde_json::to_string(roots)
+            .map_err(|e| AppError::Internal(format!("Failed to save folders: {e}")))?;
+        self.settings_repo
+            .upsert_setting(
+                None,
+                FILESYSTEM_SETTINGS_NAMESPACE,
+                ROOTS_KEY,
+                &json,
+                "json",
+                false,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Runs one tool and returns the text the model sees.
+    pub async fn call(&self, tool_name: &str, args: Value) -> Result<String, AppError> {
+        let roots = self.roots().await?;
+        if roots.is_empty() {
+            return Err(AppError::Validation {
+                field: "path".to_string(),
+                message: "No folders are approved for file tools yet. Ask the user to add one in Settings.".to_string(),
+            });
+        }
+        let tool_name = tool_name.to_string();
+        tokio::task::spawn_blocking(move || match tool_name.as_str() {
+            "list_dir" => list_dir(&roots, optional_arg(&args, "path")),
+            "read_file" => read_file(&roots, required_arg(&args, "path")?),
+            "search_files" => search_files(
+                &roots,
+                required_arg(&args, "pattern")?,
+                optional_arg(&args, "contains"),
+                optional_arg(&args, "path"),
+            ),
+            "write_file" => write_file(
+                &roots,
+                required_arg(&args, "path")?,
+                args.get("content")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default(),
+                args.get("append").and_then(Value::as_bool).unwrap_or(false),
+            ),
+            other => Err(AppError::Validation {
+                field: "tool".to_string(),
+                message: format!("Unknown file tool: {other}"),
+            }),
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("File tool task failed: {e}")))?
+    }
+}
+
+/// `path` as an approved folder, or the first approved folder without one.
+/// Other tools, such as the shell, use this to stay inside the roots too.
+pub(crate) fn approved_dir(
+    roots: &[FilesystemRoot],
+    path: Option<&str>,
+) -> Result<PathBuf, AppError> {
+    let Some(first) = roots.first() else {
+        return Err(AppError::Validation {
+            field: "path".to_string(),
+            message: "No folders are approved yet. Ask the user to add one in Settings."
+                .to_string(),
+        });
+    };
+    let Some(path) = path.map(str::trim).filter(|path| !path.is_empty()) else {
+        return Ok(PathBuf::from(&first.path));
+    };
+    let (dir, _) = resolve(roots, path, true)?;
+    if !dir.is_dir() {
+        return Err(not_a(path, "folder"));
+    }
+    Ok(dir)
+}
+
+/// Checks that `path`, relative to `base`, stays inside an approved root once
+/// links are followed, for tools that get paths mixed in with other
+/// arguments. Parts that don't exist yet are taken as written, except `..`,
+/// which counts as outside.
+pub(crate) fn check_within_roots(
+    roots: &[FilesystemRoot],
+    base: &Path,
+    path: &str,
+) -> Result<(), AppError> {
+    let requested = base.join(expand_home(path));
+    let mut existing = requested.as_path();
+    let mut missing = Vec::new();
+    let canonical = loop {
+        if let Ok(canonical) = std::fs::canonicalize(existing) {
+            break Some(
+                missing
+                    .iter()
+                    .rev()
+                    .fold(canonical, |path, name| path.join(name)),
+            );
+        }
+        // A link to a missing file: it would be created wherever it points.
+        if std::fs::symlink_metadata(existing).is_ok() {
+            break None;
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => break None,
+        }
+    };
+    if canonical.is_some_and(|canonical| roots.iter().any(|root| canonical.starts_with(&root.path)))
+    {
+        return Ok(());
+    }
+    Err(outside_roots(path, roots))
+}
+
+fn optional_arg<'a>(args: &'a Value, key: &str) -> Option<&'a str> {
+    args.get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+fn required_arg<'a>(args: &'a Value, key: &str) -> Result<&'a str, AppError> {
+    optional_arg(args, key).ok_or_else(|| AppError::Validation {
+        field: key.to_string(),
+        message: format!("`{key}` is required"),
+    })
+}
+
+fn list_dir(roots: &[FilesystemRoot], path: Option<&str>) -> Result<String, AppError> {
+    let Some(path) = path else {
+        let lines = roots
+            .iter()
+            .map(|root| {
+                let access = if root.writable {
+                    "read/write"
+                } else {
+                    "read only"
+                };
+                format!("[dir] {} ({access})", display_path(Path::new(&root.path)))
+            })
+            .collect::<Vec<_>>();
+        return Ok(format!("Approved folders:\n{}", lines.join("\n")));
+    };
+    let (dir, _) = resolve(roots, path, true)?;
+    if !dir.is_dir() {
+        return Err(not_a(path, "folder"));
+    }
+
+    let mut entries = std::fs::read_dir(&dir)?
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some((entry.file_name().to_string_lossy().to_string(), metadata))
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by(|(a_name, a), (b_name, b)| {
+        b.is_dir()
+            .cmp(&a.is_dir())
+            .then_with(|| a_name.to_lowercase().cmp(&b_name.to_lowercase()))
+    });
+
+    let total = entries.len();
+    let mut lines = entries
+        .iter()
+        .take(MAX_LIST_ENTRIES)
+        .map(|(name, metadata)| {
+            let modified = metadata.modified().map(format_time).unwrap_or_default();
+            if metadata.is_dir() {
+                format!("[dir] {name}  {modified}")
+            } else {
+                format!("[file] {name}  {}  {modified}", format_size(metadata.len()))
+            }
+        })
+        .collect::<Vec<_>>();
+    if total > MAX_LIST_ENTRIES {
+        lines.push(format!("... and {} more", total - MAX_LIST_ENTRIES));
+    }
+    if lines.is_empty() {
+        return Ok(format!("{} is empty", display_path(&dir)));
+    }
+    Ok(format!("{}:\n{}", display_path(&dir), lines.join("\n")))
+}
+
+fn read_file(roots: &[FilesystemRoot], path: &str) -> Result<String, AppError> {
+    let (file, _) = resolve(roots, path, true)?;
+    if !file.is_file() {
+        return Err(not_a(path, "file"));
+    }
+    let extension = file
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let text = match extension.as_str() {
+        "pdf" => join_blocks(document_parser::parse_pdf(&file)?),
+        "docx" => join_blocks(document_parser::parse_docx(&file)?),
+        _ => {
+            let mut bytes = Vec::new();
+            std::fs::File::open(&file)?
+                .take(MAX_READ_BYTES as u64 + 1)
+                .read_to_end(&mut bytes)?;
+            if is_binary(&bytes) {
+                return Err(AppError::Validation {
+                    field: "path".to_string(),
+                    message: format!("{path} is a binary file"),
+                });
+            }
+            String::from_utf8_lossy(&bytes).to_string()
+        }
+    };
+    Ok(truncate_bytes(text, MAX_READ_BYTES))
+}
+
+fn search_files(
+    roots: &[FilesystemRoot],
+    pattern: &str,
+    contains: Option<&str>,
+    path: Option<&str>,
+) -> Result<String, AppError> {
+    let starts = match path {
+        Some(path) => vec![resolve(roots, path, true)?.0],
+        None => roots.iter().map(|root| PathBuf::from(&root.path)).collect(),
+    };
+    let pattern = pattern.to_lowercase();
+    let contains = contains.map(str::to_lowercase);
+
+    let mut results = Vec::new();
+    let mut visits = 0;
+    let mut stack = starts
+        .into_iter()
+        .map(|start| (start, 0usize))
+        .collect::<Vec<_>>();
+    'walk: while let Some((dir, depth)) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(Result::ok) {
+            visits += 1;
+            if visits > MAX_SEARCH_VISITS || results.len() >= MAX_SEARCH_RESULTS {
+                break 'walk;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            // Not followed: a link could lead out of the approved folders.
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                if depth < MAX_SEARCH_DEPTH {
+                    stack.push((entry.path(), depth + 1));
+                }
+                continue;
+            }
+            if !file_type.is_file() || !matches_pattern(&name.to_lowercase(), &pattern) {
+                continue;
+            }
+            let path = entry.path();
+            match contains.as_deref() {
+                None => results.push(display_path(&path)),
+                Some(needle) => {
+                    if let Some((line_number, line)) = find_in_file(&path, needle) {
+                        results.push(format!("{}:{line_number}: {line}", display_path(&path)));
+                    }
+                }
+            }
+        }
+    }
+
+    if results.is_empty() {
+        return Ok("No matching files".to_string());
+    }
+    let mut output = results.join("\n");
+    if results.len() >= MAX_SEARCH_RESULTS || visits > MAX_SEARCH_VISITS {
+        output.push_str("\n(search stopped early; narrow the pattern or folder for more)");
+    }
+    Ok(output)
+}
+
+fn write_file(
+    roots: &[FilesystemRoot],
+    path: &str,
+    content: &str,
+    append: bool,
+) -> Result<String, AppError> {
+    if content.len() > MAX_WRITE_BYTES {
+        return Err(AppError::Validation {
+            field: "content".to_string(),
+            message: format!(
+                "Files over {} can't be written",
+                format_size(MAX_WRITE_BYTES as u64)
+            ),
+        });
+    }
+    let (file, writable) = resolve(roots, path, false)?;
+    if !writable {
+        return Err(AppError::Validation {
+            field: "path".to_string(),
+            message: format!("{path} is in a folder approved for reading only"),
+        });
+    }
+    // `file` is canonical, so a link here was put in since it was resolved.
+    // New files are created with `create_new`, which won't follow one either.
+    let mut options = std::fs::OpenOptions::new();
+    match std::fs::symlink_metadata(&file) {
+        Ok(metadata) if metadata.file_type().is_symlink() => {
+            return Err(AppError::Validation {
+                field: "path".to_string(),
+                message: format!("{path} is a link; links can't be written through"),
+            });
+        }
+        Ok(metadata) if metadata.is_dir() => return Err(not_a(path, "file")),
+        Ok(_) if append => options.append(true),
+        Ok(_) => options.write(true).truncate(true),
+        Err(_) => options.write(true).create_new(true),
+    };
+    options.open(&file)?.write_all(content.as_bytes())?;
+    Ok(format!(
+        "Wrote {} to {}",
+        format_size(content.len() as u64),
+        display_path(&file)
+    ))
+}
+
+/// Canonicalizes `path` and checks it lies inside an approved root; also
+/// returns whether one of the roots containing it is writable. Relative paths
+/// are tried against each root in turn. With `must_exist` false only the
+/// parent folder has to exist, for files about to be created, and writable
+/// roots are tried first.
+fn resolve(
+    roots: &[FilesystemRoot],
+    path: &str,
+    must_exist: bool,
+) -> Result<(PathBuf, bool), AppError> {
+    let requested = expand_home(path);
+    let candidates = if requested.is_absolute() {
+        vec![requested]
+    } else {
+        let mut ordered = roots.iter().collect::<Vec<_>>();
+        if !must_exist {
+            ordered.sort_by_key(|root| !root.writable);
+        }
+        ordered
+            .into_iter()
+            .map(|root| Path::new(&root.path).join(&requested))
+            .collect()
+    };
+
+    for candidate in candidates {
+        let canonical = if must_exist {
+            std::fs::canonicalize(&candidate).ok()
+        } else {
+            canonicalize_new(&candidate)
+        };
+        let Some(canonical) = canonical else {
+            continue;
+        };
+        let containing = roots
+            .iter()
+            .filter(|root| canonical.starts_with(&root.path))
+            .collect::<Vec<_>>();
+        if containing.is_empty() {
+            return Err(outside_roots(path, roots));
+        }
+        let writable = containing.iter().any(|root| root.writable);
+        return Ok((canonical, writable));
+    }
+    Err(AppError::NotFound {
+        entity: "path".to_string(),
+        id: path.to_string(),
+    })
+}
+
+fn outside_roots(path: &str, roots: &[FilesystemRoot]) -> AppError {
+    AppError::Validation {
+        field: "path".to_string(),
+        message: format!(
+            "{path} is outside the approved folders: {}",
+            roots
+                .iter()
+                .map(|root| display_path(Path::new(&root.path)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// The canonical path of a file that may not exist yet: its folder must.
+fn canonicalize_new(path: &Path) -> Option<PathBuf> {
+    if let Ok(canonical) = std::fs::canonicalize(path) {
+        return Some(canonical);
+    }
+    // Something is there that doesn't resolve, such as a link to a missing
+    // file; writing would create that file wherever the link points.
+    if std::fs::symlink_metadata(path).is_ok() {
+        return None;
+    }
+    let name = match path.components().next_back()? {
+        Component::Normal(name) => name.to_owned(),
+        _ => return None,
+    };
+    let parent = std::fs::canonicalize(path.parent()?).ok()?;
+    Some(parent.join(name))
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"));
+    match (path.strip_prefix('~'), home) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+            PathBuf::from(home).join(rest.trim_start_matches(['/', '\\']))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Without the `\\?\` prefix Windows puts on canonical paths.
+fn display_path(path: &Path) -> String {
+    let text = path.to_string_lossy();
+    text.strip_prefix(r"\\?\").unwrap_or(&text).to_string()
+}
+
+/// `*` matches any run of characters; a pattern without one matches anywhere
+/// in the name.
+fn matches_pattern(name: &str, pattern: &str) -> bool {
+    if !pattern.contains('*') {
+        return name.contains(pattern);
+    }
+    let parts = pattern.split('*').collect::<Vec<_>>();
+    let mut rest = name;
+    for (index, part) in parts.iter().enumerate() {
+        if part.is_empty() {
+            continue;
+        }
+        if index == 0 {
+            let Some(after) = rest.strip_prefix(part) else {
+                return false;
+            };
+            rest = after;
+        } else if index == parts.len() - 1 {
+            return rest.ends_with(part);
+        } else {
+            let Some(at) = rest.find(part) else {
+                return false;
+            };
+            rest = &rest[at + part.len()..];
+        }
+    }
+    true
+}
+
+/// The first line containing `needle` (lowercase), numbered from 1.
+fn find_in_file(path: &Path, needle: &str) -> Option<(usize, String)> {
+    if std::fs::metadata(path).ok()?.len() > MAX_CONTENT_SEARCH_BYTES {
+        return None;
+    }
+    let bytes = std::fs::read(path).ok()?;
+    if is_binary(&bytes) {
+        return None;
+    }
+    String::from_utf8_lossy(&bytes)
+        .lines()
+        .enumerate()
+        .find(|(_, line)| line.to_lowercase().contains(needle))
+        .map(|(index, line)| (index + 1, line.trim().chars().take(200).collect()))
+}
+
+fn is_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0)
+}
+
+fn join_blocks(blocks: Vec<document_parser::TextBlock>) -> String {
+    blocks
+        .into_iter()
+        .map(|block| block.text)
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn truncate_bytes(mut text: String, max_bytes: usize) -> String {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+    text.push_str("\n[... file cut off]");
+    text
+}
+
+fn not_a(path: &str, kind: &str) -> AppError {
+    AppError::Validation {
+        field: "path".to_string(),
+        message: format!("{path} is not a {kind}"),
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+fn format_time(time: SystemTime) -> String {
+    chrono::DateTime::<chrono::Local>::from(time)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{canonicalize_new, matches_pattern, resolve, write_file, FilesystemRoot};
+    use crate::error::AppError;
+
+    /// A fresh folder under the system temp dir, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let nanos = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos();
+            let dir = std::env::temp_dir()
+                .join(format!("sarah-fs-{name}-{}-{nanos}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(std::fs::canonicalize(dir).unwrap())
+        }
+
+        fn path(&self) -> &Path {
+            &self.0
+        }
+
+        fn root(&self, writable: bool) -> FilesystemRoot {
+            FilesystemRoot {
+                path: self.0.to_string_lossy().to_string(),
+                writable,
+            }
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn text(path: &Path) -> String {
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn patterns_match_names() {
+        assert!(matches_pattern("report.pdf", "*.pdf"));
+        assert!(!matches_pattern("report.pdf.txt", "*.pdf"));
+        assert!(matches_pattern("report.pdf", "rep*"));
+        assert!(!matches_pattern("my-report.pdf", "rep*"));
+        assert!(matches_pattern("axxbyyc", "a*b*c"));
+        assert!(!matches_pattern("ac", "a*b*c"));
+        assert!(!matches_pattern("a", "a*a"));
+        assert!(matches_pattern("anything", "*"));
+        assert!(matches_pattern("quarterly report", "report"));
+        assert!(!matches_pattern("summary", "report"));
+    }
+
+    #[test]
+    fn paths_inside_roots_resolve() {
+        let root = TempDir::new("inside");
+        std::fs::create_dir(root.path().join("sub")).unwrap();
+        std::fs::write(root.path().join("sub/notes.txt"), "hi").unwrap();
+        let roots = [root.root(false)];
+
+        let absolute = root.path().join("sub/notes.txt");
+        let (resolved, writable) = resolve(&roots, &text(&absolute), true).unwrap();
+        assert_eq!(resolved, absolute);
+        assert!(!writable);
+
+        let (resolved, _) = resolve(&roots, "sub/./notes.txt", true).unwrap();
+        assert_eq!(resolved, absolute);
+
+        let (resolved, _) = resolve(&roots, "sub/../sub/notes.txt", true).unwrap();
+        assert_eq!(resolved, absolute);
+
+        assert!(matches!(
+            resolve(&roots, "sub/missing.txt", true),
+            Err(AppError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn parent_traversal_cannot_leave_the_roots() {
+        let root = TempDir::new("traversal-root");
+        let outside = TempDir::new("traversal-outside");
+        std::fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+        let roots = [root.root(true)];
+
+        let escape = format!(
+            "../{}/secret.txt",
+            outside.path().file_name().unwrap().to_string_lossy()
+        );
+        assert!(matches!(
+            resolve(&roots, &escape, true),
+            Err(AppError::Validation { .. })
+        ));
+        assert!(matches!(
+            resolve(&roots, &text(&outside.path().join("secret.txt")), true),
+            Err(AppError::Validation { .. })
+        ));
+
+        let new_file = format!(
+            "../{}/new.txt",
+            outside.path().file_name().unwrap().to_string_lossy()
+        );
+        assert!(write_file(&roots, &new_file, "x", false).is_err());
+        assert!(!outside.path().join("new.txt").exists());
+    }
+
+    #[test]
+    fn new_files_canonicalize_through_their_folder() {
+        let root = TempDir::new("new");
+        assert_eq!(
+            canonicalize_new(&root.path().join("sub/../new.txt")),
+            None,
+            "the folder must exist"
+        );
+        std::fs::create_dir(root.path().join("sub")).unwrap();
+        assert_eq!(
+            canonicalize_new(&root.path().join("sub/../new.txt")),
+            Some(root.path().join("new.txt"))
+        );
+        assert_eq!(
+            canonicalize_new(&root.path().join("sub/new.txt")),
+            Some(root.path().join("sub/new.txt"))
+        );
+        assert_eq!(canonicalize_new(&root.path().join("missing/new.txt")), None);
+        assert_eq!(canonicalize_new(&root.path().join("missing/..")), None);
+    }
+
+    #[test]
+    fn relative_writes_go_to_a_writable_root() {
+        let read_only = TempDir::new("write-ro");
+        let writable = TempDir::new("write-rw");
+        let roots = [read_only.root(false), writable.root(true)];
+
+        write_file(&roots, "note.txt", "first", false).unwrap();
+        assert!(!read_only.path().join("note.txt").exists());
+        assert_eq!(
+            std::fs::read_to_string(writable.path().join("note.txt")).unwrap(),
+            "first"
+        );
+
+        write_file(&roots, "note.txt", " second", true).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(writable.path().join("note.txt")).unwrap(),
+            "first second"
+        );
+
+        let read_only_file = text(&read_only.path().join("note.txt"));
+        assert!(write_file(&roots, &read_only_file, "x", false).is_err());
+        assert!(!read_only.path().join("note.txt").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_cannot_leave_the_roots() {
+        use std::os::unix::fs::symlink;
+
+        let root = TempDir::new("link-root");
+        let outside = TempDir::new("link-outside");
+        std::fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+        symlink(outside.path(), root.path().join("linked-dir")).unwrap();
+        symlink(
+            outside.path().join("secret.txt"),
+            root.path().join("linked-file"),
+        )
+        .unwrap();
+        symlink(
+            outside.path().join("created.txt"),
+            root.path().join("dangling"),
+        )
+        .unwrap();
+        let roots = [root.root(true)];
+
+        assert!(resolve(&roots, "linked-dir/secret.txt", true).is_err());
+        assert!(resolve(&roots, "linked-file", true).is_err());
+
+        assert!(write_file(&roots, "linked-file", "changed", false).is_err());
+        assert!(write_file(&roots, "linked-dir/new.txt", "x", false).is_err());
+        assert!(write_file(&roots, "dangling", "x", false).is_err());
+        assert!(write_file(&roots, "dangling", "x", true).is_err());
+
+        assert_eq!(
+            std::fs::read_to_string(outside.path().join("secret.txt")).unwrap(),
+            "secret"
+        );
+        assert!(!outside.path().join("new.txt").exists());
+        assert!(!outside.path().join("created.txt").exists());
+    }
+}
@@ -0,0 +1,188 @@
+use crate::db::models::{GenerationOptions, GenerationPreset};
+use crate::error::AppError;
+use crate::repositories::settings_repo::SettingsRepo;
+
+pub const GENERATION_PRESETS_NAMESPACE: &str = "generation_presets";
+
+/// (name, temperature, top_p, top_k) shipped out of the box.
+const BUILTIN_PRESETS: &[(&str, f32, f32, i32)] = &[
+    ("precise", 0.1, 0.85, 20),
+    ("balanced", 0.5, 0.95, 40),
+    ("creative", 0.9, 0.98, 80),
+];
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredPreset {
+    temperature: f32,
+    top_p: f32,
+    top_k: i32,
+}
+
+/// Named sampler configurations. Built-ins can be edited and reset; users may add their own.
+#[derive(Clone)]
+pub struct GenerationPresetService {
+    settings_repo: SettingsRepo,
+}
+
+impl GenerationPresetService {
+    pub fn new(settings_repo: SettingsRepo) -> Self {
+        Self { settings_repo }
+    }
+
+    pub async fn list(&self) -> Result<Vec<GenerationPreset>, AppError> {
+        let stored = self
+            .settings_repo
+            .list_namespace(None, GENERATION_PRESETS_NAMESPACE)
+            .await?;
+
+        let mut presets = BUILTIN_PRESETS
+            .iter()
+            .map(|(name, temperature, top_p, top_k)| {
+                let custom = stored
+                    .iter()
+                    .find(|setting| setting.key == *name)
+                    .and_then(|setting| serde_json::from_str::<StoredPreset>(&setting.value).ok());
+                match custom {
+                    Some(values) => to_preset(name, values, true, true),
+                    None => GenerationPreset {
+                        name: name.to_string(),
+                        temperature: *temperature,
+                        top_p: *top_p,
+                        top_k: *top_k,
+                        is_builtin: true,
+                        is_customized: false,
+                    },
+                }
+            })
+            .collect::<Vec<_>>();
+
+        for setting in stored {
+            if is_builtin(&setting.key) {
+                continue;
+            }
+            if let Ok(values) = serde_json::from_str::<StoredPreset>(&setting.value) {
+                presets.push(to_preset(&setting.key, values, false, true));
+            }
+        }
+        Ok(presets)
+    }
+
+    pub async fn get(&self, name: &str) -> Result<GenerationPreset, AppError> {
+        let name = validate_preset_name(name)?;
+        self.list()
+            .await?
+            .into_iter()
+            .find(|preset| preset.name == name)
+            .ok_or_else(|| AppError::NotFound {
+                entity: "generation_preset".to_string(),
+                id: name.clone(),
+            })
+    }
+
+    pub async fn save(
+        &self,
+        name: &str,
+        temperature: f32,
+        top_p: f32,
+        top_k: i32,
+    ) -> Result<GenerationPreset, AppError> {
+        let name = validate_preset_name(name)?;
+        if !(0.0..=2.0).contains(&temperature) {
+            return Err(invalid(
+                "temperature",
+                "Temperature must be between 0 and 2",
+            ));
+        }
+        if !(0.0..=1.0).contains(&top_p) || top_p <= 0.0 {
+            return Err(invalid(
+                "topP",
+                "Top-p must be greater than 0 and at most 1",
+            ));
+        }
+        if !(0..=500).contains(&top_k) {
+            return Err(invalid("topK", "Top-k must be between 0 and 500"));
+        }
+
+        let values = StoredPreset {
+            temperature,
+            top_p,
+            top_k,
+        };
+        let encoded = serde_json::to_string(&values).unwrap_or_else(|_| "{}".to_string());
+        self.settings_repo
+            .upsert_setting(
+                None,
+                GENERATION_PRESETS_NAMESPACE,
+                &name,
+                &encoded,
+                "json",
+                false,
+            )
+            .await?;
+
+        Ok(to_preset(&name, values, is_builtin(&name), true))
+    }
+
+    /// Restores a built-in to its shipped values, or deletes a user preset.
+    pub async fn reset(&self, name: &str) -> Result<(), AppError> {
+        let name = validate_preset_name(name)?;
+        self.settings_repo
+            .delete_setting(None, GENERATION_PRESETS_NAMESPACE, &name)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Overrides the sampler fields of `options`; the token budget stays with the governor.
+pub fn apply_preset(options: GenerationOptions, preset: &GenerationPreset) -> GenerationOptions {
+    GenerationOptions {
+        temperature: preset.temperature,
+        top_p: preset.top_p,
+        top_k: preset.top_k,
+        ..options
+    }
+}
+
+pub fn validate_preset_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim().to_ascii_lowercase();
+    let valid = !name.is_empty()
+        && name.len() <= 32
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-');
+    if !valid {
+        return Err(invalid(
+            "name",
+            "Preset names use 1-32 letters, digits, '-' or '_'",
+        ));
+    }
+    Ok(name)
+}
+
+fn is_builtin(name: &str) -> bool {
+    BUILTIN_PRESETS.iter().any(|(builtin, ..)| *builtin == name)
+}
+
+fn to_preset(
+    name: &str,
+    values: StoredPreset,
+    is_builtin: bool,
+    is_customized: bool,
+) -> GenerationPreset {
+    GenerationPreset {
+        name: name.to_string(),
+        temperature: values.temperature,
+        top_p: values.top_p,
+        top_k: values.top_k,
+        is_builtin,
+        is_customized,
+    }
+}
+
+fn invalid(field: &str, message: &str) -> AppError {
+    AppError::Validation {
+        field: field.to_string(),
+        message: message.to_string(),
+    }
+}
@@ -1,7 +1,8 @@
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::time::Instant;
 
-use sysinfo::{Disks, System};
+use sysinfo::{Components, Disks, System};
 use uuid::Uuid;
 
 use crate::db::models::{BenchmarkResult, SystemProfile};
@@ -9,6 +10,19 @@ use crate::error::AppError;
 use crate::repositories::settings_repo::SettingsRepo;
 use crate::repositories::system_repo::SystemRepo;
 
+pub const PERFORMANCE_SETTINGS_NAMESPACE: &str = "app_performance";
+pub const PERFORMANCE_MODE_KEY: &str = "mode";
+/// Optional override for how many chat models may stay loaded at once.
+pub const MAX_RESIDENT_MODELS_KEY: &str = "max_resident_models";
+/// Hard ceiling for the override, whatever the machine.
+const MAX_RESIDENT_MODELS_CEILING: usize = 4;
+const AMD_VENDOR_ID: u32 = 0x1002;
+const INTEL_VENDOR_ID: u32 = 0x8086;
+/// Smallest Arc card; used when the driver doesn't report VRAM. The loader's
+/// offload fallback trims layers if it turns out to be too much.
+#[cfg(target_os = "linux")]
+const MIN_INTEL_ARC_VRAM_MB: i64 = 4096;
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum PerformanceMode {
     Max,
@@ -16,6 +30,16 @@ pub enum PerformanceMode {
     Multitasking,
 }
 
+impl PerformanceMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PerformanceMode::Max => "max",
+            PerformanceMode::Balanced => "balanced",
+            PerformanceMode::Multitasking => "multitasking",
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum DeviceTier {
     Ultra,
@@ -50,10 +74,13 @@ impl SystemProfile {
         
         // Unified Memory / Dynamic VRAM mapping
         let mut vram = self.gpu_vram_mb.unwrap_or(0);
-        if let Some(backend) = &self.gpu_backend {
-            if backend == "metal" || (backend == "cuda" && vram == 0) {
+        if vram == 0 {
+            match self.gpu_backend.as_deref() {
+                // Profiles saved before the budget was recorded
+                Some("metal") => vram = unified_memory_budget_mb(self.total_ram_mb),
                 // Approximate Unified Memory as half of total system RAM
-                vram = (self.total_ram_mb / 2) as i64;
+                Some("cuda") => vram = self.total_ram_mb / 2,
+                _ => {}
             }
         }
         
@@ -79,6 +106,14 @@ impl SystemProfile {
     }
 }
 
+/// How much of the RAM an Apple Silicon GPU may use, mirroring Metal's
+/// recommended working set: roughly two thirds of RAM, three quarters on
+/// larger machines.
+pub fn unified_memory_budget_mb(total_ram_mb: i64) -> i64 {
+    let share = if total_ram_mb >= 36 * 1024 { 0.75 } else { 0.66 };
+    ((total_ram_mb as f64) * share) as i64
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum LoadDecision {
     LoadNow,
@@ -87,11 +122,28 @@ pub enum LoadDecision {
     Skip,
 }
 
+/// How many llama.cpp models the inference pool may keep resident, and how
+/// much RAM (weights plus KV cache) they may take together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPoolLimits {
+    pub max_resident_models: usize,
+    pub ram_budget_mb: u64,
+}
+
 pub struct HardwareService {
     system_repo: SystemRepo,
     settings_repo: SettingsRepo,
     last_stats: std::sync::Arc<std::sync::Mutex<crate::db::models::LiveSystemStats>>,
     last_check: AtomicU64,
+    /// Resolved modes per user (`None` = global), dropped by the settings watcher on change.
+    performance_modes: std::sync::Arc<std::sync::Mutex<HashMap<Option<String>, PerformanceMode>>>,
+    /// Set by the runtime governor while its battery or thermal policy holds
+    /// background jobs back.
+    background_paused: std::sync::Arc<AtomicBool>,
+    /// Set by the runtime governor while the user has touched keyboard or mouse
+    /// within the policy's idle threshold.
+    user_active: std::sync::Arc<AtomicBool>,
 }
 
 impl Clone for HardwareService {
@@ -101,6 +153,9 @@ impl Clone for HardwareService {
             settings_repo: self.settings_repo.clone(),
             last_stats: std::sync::Arc::clone(&self.last_stats),
             last_check: AtomicU64::new(self.last_check.load(Ordering::Relaxed)),
+            performance_modes: std::sync::Arc::clone(&self.performance_modes),
+            background_paused: std::sync::Arc::clone(&self.background_paused),
+            user_active: std::sync::Arc::clone(&self.user_active),
         }
     }
 }
@@ -122,6 +177,9 @@ impl HardwareService {
                 },
             )),
             last_check: AtomicU64::new(0),
+            performance_modes: std::sync::Arc::new(std::sync::Mutex::new(HashMap::new())),
+            background_paused: std::sync::Arc::new(AtomicBool::new(false)),
+            user_active: std::sync::Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -150,7 +208,7 @@ impl HardwareService {
             supports_cuda,
             supports_metal,
             supports_vulkan,
-        ) = self.detect_gpu().unwrap_or_else(|_| {
+        ) = self.detect_gpu(total_ram_mb).unwrap_or_else(|_| {
             (
                 None,
                 Some("none".to_string()),
@@ -227,16 +285,8 @@ impl HardwareService {
         self.system_repo.upsert_profile(profile).await
     }
 
+    /// Synthetic CPU loop, for when no model is installed to measure with.
     pub async fn run_benchmark(&self) -> Result<BenchmarkResult, AppError> {
-        let mut profile = self
-            .system_repo
-            .get_current_profile()
-            .await?
-            .ok_or_else(|| AppError::NotFound {
-                entity: "system_profile".to_string(),
-                id: "current".to_string(),
-            })?;
-
         let token_start = Instant::now();
         let mut acc = 0u64;
         for i in 0..1_500_000 {
@@ -244,28 +294,39 @@ impl HardwareService {
         }
         let token_duration = token_start.elapsed();
         let tokens_per_sec = 50.0 / token_duration.as_secs_f64().max(0.001);
+        let embed_ms = synthetic_embed_ms(acc);
 
-        let embed_start = Instant::now();
-        for _ in 0..10 {
-            let mut tmp = vec![0f32; 1024];
-            for (idx, slot) in tmp.iter_mut().enumerate() {
-                *slot = ((idx as f32 * 0.001) + (acc as f32 * 0.000001)).sin();
-            }
-        }
-        let embed_duration = embed_start.elapsed();
-        let embed_ms = embed_duration.as_millis() as f64 / 10.0;
+        self.record_benchmark(BenchmarkResult {
+            profile_id: String::new(),
+            tokens_per_sec,
+            embed_ms,
+            model_id: None,
+            prompt_tokens_per_sec: None,
+        })
+        .await
+    }
+
+    /// Stores benchmark figures on the current profile. `profile_id` is filled in.
+    pub async fn record_benchmark(
+        &self,
+        mut result: BenchmarkResult,
+    ) -> Result<BenchmarkResult, AppError> {
+        let mut profile = self
+            .system_repo
+            .get_current_profile()
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "system_profile".to_string(),
+                id: "current".to_string(),
+            })?;
 
-        profile.benchmark_tokens_per_sec = Some(tokens_per_sec);
-        profile.benchmark_embed_ms = Some(embed_ms);
+        profile.benchmark_tokens_per_sec = Some(result.tokens_per_sec);
+        profile.benchmark_embed_ms = Some(result.embed_ms);
         profile.capability_score = Some(self.compute_capability_score(&profile) as f64);
 
         let updated = self.system_repo.upsert_profile(profile).await?;
-
-        Ok(BenchmarkResult {
-            profile_id: updated.id,
-            tokens_per_sec,
-            embed_ms,
-        })
+        result.profile_id = updated.id;
+        Ok(result)
     }
 
     pub fn compute_capability_score(&self, profile: &SystemProfile) -> f32 {
@@ -330,13 +391,85 @@ impl HardwareService {
     }
 
     pub async fn get_performance_mode(&self, user_id: Option<&str>) -> PerformanceMode {
-        match self.settings_repo.get_setting(user_id, "app_performance", "mode").await {
+        let cache_key = user_id.map(str::to_string);
+        if let Some(mode) = self
+            .performance_modes
+            .lock()
+            .ok()
+            .and_then(|modes| modes.get(&cache_key).cloned())
+        {
+            return mode;
+        }
+
+        let mode = match self
+            .settings_repo
+            .get_setting(user_id, PERFORMANCE_SETTINGS_NAMESPACE, PERFORMANCE_MODE_KEY)
+            .await
+        {
             Ok(Some(setting)) => match setting.value.as_str() {
                 "max" => PerformanceMode::Max,
                 "multitasking" => PerformanceMode::Multitasking,
                 _ => PerformanceMode::Balanced,
             },
-            _ => PerformanceMode::Balanced,
+            Ok(None) => PerformanceMode::Balanced,
+            // Don't cache a read failure; the next call retries.
+            Err(_) => return PerformanceMode::Balanced,
+        };
+
+        if let Ok(mut modes) = self.performance_modes.lock() {
+            modes.insert(cache_key, mode.clone());
+        }
+        mode
+    }
+
+    /// Forgets the cached mode for `user_id` so the next read picks up the new
+    /// setting; `None` forgets every cached mode.
+    pub fn invalidate_performance_mode(&self, user_id: Option<&str>) {
+        if let Ok(mut modes) = self.performance_modes.lock() {
+            match user_id {
+                Some(uid) => {
+                    modes.remove(&Some(uid.to_string()));
+                }
+                None => modes.clear(),
+            }
+        }
+    }
+
+    /// Pool limits for the machine's tier and the current performance mode.
+    /// `app_performance.max_resident_models` overrides the model count.
+    pub async fn model_pool_limits(&self, profile: &SystemProfile) -> ModelPoolLimits {
+        let mode = self.get_performance_mode(None).await;
+        let (share, default_models) = match profile.classify() {
+            DeviceTier::Ultra => (0.50, 3),
+            DeviceTier::High => (0.40, 3),
+            DeviceTier::Medium => (0.35, 2),
+            DeviceTier::Low => (0.30, 2),
+            DeviceTier::Minimal => (0.25, 1),
+            DeviceTier::Potato => (0.20, 1),
+        };
+        let (share, default_models) = if mode == PerformanceMode::Multitasking {
+            (share / 2.0, 1)
+        } else {
+            (share, default_models)
+        };
+
+        let max_resident_models = match self
+            .settings_repo
+            .get_setting(None, PERFORMANCE_SETTINGS_NAMESPACE, MAX_RESIDENT_MODELS_KEY)
+            .await
+        {
+            Ok(Some(setting)) => setting
+                .value
+                .trim()
+                .parse::<usize>()
+                .map(|count| count.clamp(1, MAX_RESIDENT_MODELS_CEILING))
+                .unwrap_or(default_models),
+            _ => default_models,
+        };
+
+        ModelPoolLimits {
+            max_resident_models,
+            ram_budget_mb: (profile.total_ram_mb.max(0) as f64 * share).round() as u64,
         }
     }
 
@@ -450,6 +583,10 @@ impl HardwareService {
         let memory_used_mb =
             memory_total_mb.saturating_sub(system.available_memory() / 1024 / 1024);
 
+        let (on_battery, battery_pct) = read_power_state();
+        let (cpu_temp_c, gpu_temp_c) = read_temperatures();
+        let idle_secs = read_idle_secs();
+
         let stats = crate::db::models::LiveSystemStats {
             cpu_usage_pct,
             memory_used_mb,
@@ -457,6 +594,11 @@ impl HardwareService {
             process_count: system.processes().len(),
             gpu_name: None,
             gpu_usage_pct: None,
+            on_battery,
+            battery_pct,
+            cpu_temp_c,
+            gpu_temp_c,
+            idle_secs,
         };
 
         if let Ok(mut guard) = self.last_stats.lock() {
@@ -467,8 +609,29 @@ impl HardwareService {
         stats
     }
 
+    /// Whether background jobs should wait regardless of load; see
+    /// `RuntimeGovernorService::start_device_monitor`.
+    pub fn background_paused(&self) -> bool {
+        self.background_paused.load(Ordering::Relaxed)
+    }
+
+    pub fn set_background_paused(&self, paused: bool) {
+        self.background_paused.store(paused, Ordering::Relaxed);
+    }
+
+    /// Whether heavy background work (downloads, re-indexing, memory
+    /// consolidation) should wait for the user to step away.
+    pub fn user_active(&self) -> bool {
+        self.user_active.load(Ordering::Relaxed)
+    }
+
+    pub fn set_user_active(&self, active: bool) {
+        self.user_active.store(active, Ordering::Relaxed);
+    }
+
     fn detect_gpu(
         &self,
+        total_ram_mb: i64,
     ) -> Result<
         (
             Option<String>,
@@ -512,6 +675,46 @@ impl HardwareService {
             ));
         }
 
+        if let Some(gpu) = detect_vulkan_gpu() {
+            return Ok((
+                Some(gpu.name),
+                Some(gpu.vendor.to_string()),
+                gpu.vram_mb,
+                Some("vulkan".to_string()),
+                0,
+                0,
+                1,
+            ));
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            // Every Apple Silicon Mac has a Metal GPU, even if system_profiler can't say so.
+            let apple = detect_apple_gpu(total_ram_mb).or_else(|| {
+                cfg!(target_arch = "aarch64").then(|| AppleGpu {
+                    name: "Apple GPU".to_string(),
+                    vendor: "apple".to_string(),
+                    vram_mb: unified_memory_budget_mb(total_ram_mb),
+                    unified_memory: true,
+                })
+            });
+            if let Some(apple) = apple {
+                // Intel Macs support Metal too, but llama.cpp runs faster on their CPU.
+                let backend = if apple.unified_memory { "metal" } else { "cpu" };
+                return Ok((
+                    Some(apple.name),
+                    Some(apple.vendor),
+                    Some(apple.vram_mb),
+                    Some(backend.to_string()),
+                    0,
+                    1,
+                    0,
+                ));
+            }
+        }
+        #[cfg(not(target_os = "macos"))]
+        let _ = total_ram_mb;
+
         Ok((
             None,
             Some("none".to_string()),
@@ -549,3 +752,423 @@ impl Clone for TierConfig {
         }
     }
 }
+
+/// One system battery: whether it's discharging, and its charge from 0 to 1.
+struct BatteryReading {
+    discharging: bool,
+    charge: f32,
+}
+
+/// Batteries under `/sys/class/power_supply`. Wireless mice and other
+/// peripherals report there too, with a `Device` scope, and are skipped.
+#[cfg(target_os = "linux")]
+fn read_batteries() -> Vec<BatteryReading> {
+    let Ok(entries) = std::fs::read_dir("/sys/class/power_supply") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let read = |name: &str| {
+                std::fs::read_to_string(path.join(name))
+                    .ok()
+                    .map(|value| value.trim().to_string())
+            };
+            if read("type")? != "Battery" || read("scope").as_deref() == Some("Device") {
+                return None;
+            }
+            let capacity = read("capacity")?.parse::<f32>().ok()?;
+            Some(BatteryReading {
+                discharging: read("status").as_deref() == Some("Discharging"),
+                charge: (capacity / 100.0).clamp(0.0, 1.0),
+            })
+        })
+        .collect()
+}
+
+/// `GetSystemPowerStatus` reports all batteries as one.
+#[cfg(windows)]
+fn read_batteries() -> Vec<BatteryReading> {
+    use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    // Also set in 255, "status unknown".
+    const NO_SYSTEM_BATTERY: u8 = 128;
+    const UNKNOWN_PERCENT: u8 = 255;
+
+    let mut status = SYSTEM_POWER_STATUS::default();
+    // SAFETY: `status` is a valid SYSTEM_POWER_STATUS for the call to fill in.
+    if unsafe { GetSystemPowerStatus(&mut status) }.is_err()
+        || status.BatteryFlag & NO_SYSTEM_BATTERY != 0
+        || status.BatteryLifePercent == UNKNOWN_PERCENT
+    {
+        return Vec::new();
+    }
+    vec![BatteryReading {
+        // 0 is offline; 1 online and 255 unknown.
+        discharging: status.ACLineStatus == 0,
+        charge: (f32::from(status.BatteryLifePercent) / 100.0).min(1.0),
+    }]
+}
+
+/// Parses `pmset -g batt`, which prints the power source and then a line per
+/// battery: `Now drawing from 'Battery Power'`, then
+/// ` -InternalBattery-0 (id=4653155)\t84%; discharging; 4:01 remaining present: true`.
+#[cfg(target_os = "macos")]
+fn read_batteries() -> Vec<BatteryReading> {
+    let Ok(output) = std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+    else {
+        return Vec::new();
+    };
+    if !output.status.success() {
+        return Vec::new();
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let on_battery = text.contains("'Battery Power'");
+    text.lines()
+        .filter(|line| line.contains("InternalBattery"))
+        .filter_map(|line| {
+            let percent = line.split('\t').nth(1)?.split('%').next()?.trim();
+            let percent = percent.parse::<f32>().ok()?;
+            Some(BatteryReading {
+                discharging: on_battery,
+                charge: (percent / 100.0).clamp(0.0, 1.0),
+            })
+        })
+        .collect()
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+fn read_batteries() -> Vec<BatteryReading> {
+    Vec::new()
+}
+
+/// Whether the machine runs from a discharging battery, and its average charge.
+/// Desktops, and machines whose battery can't be read, count as on mains power.
+fn read_power_state() -> (bool, Option<f32>) {
+    let batteries = read_batteries();
+    if batteries.is_empty() {
+        return (false, None);
+    }
+
+    let on_battery = batteries.iter().any(|battery| battery.discharging);
+    let charge =
+        batteries.iter().map(|battery| battery.charge).sum::<f32>() / batteries.len() as f32;
+    (on_battery, Some(charge * 100.0))
+}
+
+/// Time since the last keyboard or mouse input, from `GetLastInputInfo`.
+#[cfg(windows)]
+fn read_idle_secs() -> Option<u64> {
+    use windows::Win32::System::SystemInformation::GetTickCount;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    // SAFETY: `info` is a valid LASTINPUTINFO with its size set, as the call requires.
+    if !unsafe { GetLastInputInfo(&mut info) }.as_bool() {
+        return None;
+    }
+    // Both are 32-bit tick counts, so the difference stays right across wraparound.
+    let idle_ms = unsafe { GetTickCount() }.wrapping_sub(info.dwTime);
+    Some(u64::from(idle_ms) / 1000)
+}
+
+/// Time since the last input, from the HID system's `HIDIdleTime` (nanoseconds).
+#[cfg(target_os = "macos")]
+fn read_idle_secs() -> Option<u64> {
+    let output = std::process::Command::new("ioreg")
+        .args(["-c", "IOHIDSystem", "-d", "4"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|line| line.contains("\"HIDIdleTime\""))
+        .and_then(|line| line.rsplit('=').next())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(|nanos| nanos / 1_000_000_000)
+}
+
+/// Time since the last input, asked of GNOME's idle monitor (Wayland and X11)
+/// and then `xprintidle` (other X11 desktops). `None` when neither answers.
+#[cfg(target_os = "linux")]
+fn read_idle_secs() -> Option<u64> {
+    let run = |program: &str, args: &[&str]| {
+        let output = std::process::Command::new(program)
+            .args(args)
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+    // Replies "(uint64 12345,)", in milliseconds.
+    let mutter = run(
+        "gdbus",
+        &[
+            "call",
+            "--session",
+            "--dest",
+            "org.gnome.Mutter.IdleMonitor",
+            "--object-path",
+            "/org/gnome/Mutter/IdleMonitor/Core",
+            "--method",
+            "org.gnome.Mutter.IdleMonitor.GetIdletime",
+        ],
+    )
+    .and_then(|reply| {
+        reply
+            .trim()
+            .trim_start_matches("(uint64")
+            .trim_end_matches(",)")
+            .trim()
+            .parse::<u64>()
+            .ok()
+    });
+    let idle_ms = mutter.or_else(|| run("xprintidle", &[])?.trim().parse::<u64>().ok())?;
+    Some(idle_ms / 1000)
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+fn read_idle_secs() -> Option<u64> {
+    None
+}
+
+/// Synthetic stand-in for embedding latency, used when no embedding model is
+/// loaded to time.
+pub fn synthetic_embed_ms(seed: u64) -> f64 {
+    let embed_start = Instant::now();
+    for _ in 0..10 {
+        let mut tmp = vec![0f32; 1024];
+        for (idx, slot) in tmp.iter_mut().enumerate() {
+            *slot = ((idx as f32 * 0.001) + (seed as f32 * 0.000001)).sin();
+        }
+    }
+    let embed_duration = embed_start.elapsed();
+    embed_duration.as_millis() as f64 / 10.0
+}
+
+/// Hottest CPU and GPU sensors, told apart by their driver labels. Sensors
+/// that are neither (drives, chipset, ACPI zones) are ignored.
+fn read_temperatures() -> (Option<f32>, Option<f32>) {
+    const CPU_LABELS: [&str; 6] = ["coretemp", "k10temp", "cpu", "package", "tctl", "tdie"];
+    const GPU_LABELS: [&str; 5] = ["gpu", "amdgpu", "nouveau", "nvidia", "radeon"];
+
+    let components = Components::new_with_refreshed_list();
+    let mut cpu: Option<f32> = None;
+    let mut gpu: Option<f32> = None;
+    for component in components.list() {
+        let Some(temperature) = component.temperature().filter(|value| value.is_finite()) else {
+            continue;
+        };
+        let label = component.label().to_ascii_lowercase();
+        let slot = if GPU_LABELS.iter().any(|name| label.contains(name)) {
+            &mut gpu
+        } else if CPU_LABELS.iter().any(|name| label.contains(name)) {
+            &mut cpu
+        } else {
+            continue;
+        };
+        *slot = Some(slot.map_or(temperature, |hottest| hottest.max(temperature)));
+    }
+    (cpu, gpu)
+}
+
+/// An AMD or Intel Arc GPU, which llama.cpp drives through Vulkan.
+struct VulkanGpu {
+    name: String,
+    vendor: &'static str,
+    vram_mb: Option<i64>,
+}
+
+fn vulkan_vendor(vendor_id: u32, device_id: u32) -> Option<&'static str> {
+    match vendor_id {
+        AMD_VENDOR_ID => Some("amd"),
+        // Integrated Intel graphics are slower than the CPU path for inference.
+        INTEL_VENDOR_ID if is_intel_arc(device_id) => Some("intel"),
+        _ => None,
+    }
+}
+
+/// Alchemist (A-series) and Battlemage (B-series) discrete cards.
+fn is_intel_arc(device_id: u32) -> bool {
+    (0x5690..=0x56C2).contains(&device_id) || (0xE202..=0xE212).contains(&device_id)
+}
+
+/// Reads the amdgpu/i915/xe devices from sysfs. With several, the one with the
+/// most VRAM wins, so a discrete card beats an APU.
+#[cfg(target_os = "linux")]
+fn detect_vulkan_gpu() -> Option<VulkanGpu> {
+    std::fs::read_dir("/sys/class/drm")
+        .ok()?
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            // cardN is the device; cardN-DP-1 and friends are its connectors.
+            name.starts_with("card") && !name.contains('-')
+        })
+        .filter_map(|entry| sysfs_gpu(&entry.path().join("device")))
+        .max_by_key(|gpu| gpu.vram_mb.unwrap_or(0))
+}
+
+#[cfg(target_os = "linux")]
+fn sysfs_gpu(device: &std::path::Path) -> Option<VulkanGpu> {
+    let read = |name: &str| {
+        std::fs::read_to_string(device.join(name))
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let hex = |value: String| u32::from_str_radix(value.trim_start_matches("0x"), 16).ok();
+    let vendor_id = read("vendor").and_then(hex)?;
+    let device_id = read("device").and_then(hex)?;
+    let vendor = vulkan_vendor(vendor_id, device_id)?;
+
+    let (name, vram_mb) = if vendor == "amd" {
+        let vram_mb = read("mem_info_vram_total")
+            .and_then(|bytes| bytes.parse::<i64>().ok())
+            .map(|bytes| bytes / 1024 / 1024);
+        let name = read("product_name").unwrap_or_else(|| "AMD Radeon GPU".to_string());
+        (name, vram_mb)
+    } else {
+        ("Intel Arc GPU".to_string(), Some(MIN_INTEL_ARC_VRAM_MB))
+    };
+    Some(VulkanGpu {
+        name,
+        vendor,
+        vram_mb,
+    })
+}
+
+/// Enumerates adapters through DXGI, which reports dedicated VRAM for AMD and
+/// Intel alike without their vendor libraries. Most VRAM wins.
+#[cfg(windows)]
+fn detect_vulkan_gpu() -> Option<VulkanGpu> {
+    use windows::Win32::Graphics::Dxgi::{
+        CreateDXGIFactory1, IDXGIFactory1, DXGI_ADAPTER_FLAG_SOFTWARE,
+    };
+
+    let factory = unsafe { CreateDXGIFactory1::<IDXGIFactory1>() }.ok()?;
+    let mut best: Option<VulkanGpu> = None;
+    for index in 0.. {
+        let Ok(adapter) = (unsafe { factory.EnumAdapters1(index) }) else {
+            break;
+        };
+        let Ok(desc) = (unsafe { adapter.GetDesc1() }) else {
+            continue;
+        };
+        if desc.Flags & DXGI_ADAPTER_FLAG_SOFTWARE.0 as u32 != 0 {
+            continue;
+        }
+        let Some(vendor) = vulkan_vendor(desc.VendorId, desc.DeviceId) else {
+            continue;
+        };
+        let name_len = desc
+            .Description
+            .iter()
+            .position(|&unit| unit == 0)
+            .unwrap_or(desc.Description.len());
+        let gpu = VulkanGpu {
+            name: String::from_utf16_lossy(&desc.Description[..name_len]),
+            vendor,
+            vram_mb: Some((desc.DedicatedVideoMemory / 1024 / 1024) as i64),
+        };
+        best = match best {
+            Some(current) if current.vram_mb >= gpu.vram_mb => Some(current),
+            _ => Some(gpu),
+        };
+    }
+    best
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn detect_vulkan_gpu() -> Option<VulkanGpu> {
+    None
+}
+
+#[cfg(target_os = "macos")]
+struct AppleGpu {
+    name: String,
+    vendor: String,
+    vram_mb: i64,
+    unified_memory: bool,
+}
+
+/// Queries the display subsystem for a Metal-capable GPU. On Apple Silicon the GPU
+/// shares system RAM, so the usable size is [`unified_memory_budget_mb`].
+#[cfg(target_os = "macos")]
+fn detect_apple_gpu(total_ram_mb: i64) -> Option<AppleGpu> {
+    let output = std::process::Command::new("system_profiler")
+        .args(["SPDisplaysDataType", "-json"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+    let gpu = parsed
+        .get("SPDisplaysDataType")?
+        .as_array()?
+        .iter()
+        .find(|entry| {
+            entry
+                .get("spdisplays_mtlgpufamilysupport")
+                .or_else(|| entry.get("spdisplays_metal"))
+                .is_some()
+        })?;
+
+    let name = gpu
+        .get("sppci_model")
+        .and_then(|value| value.as_str())
+        .unwrap_or("Apple GPU")
+        .to_string();
+    let unified_memory = cfg!(target_arch = "aarch64");
+
+    let vram_mb = if unified_memory {
+        unified_memory_budget_mb(total_ram_mb)
+    } else {
+        gpu.get("spdisplays_vram")
+            .or_else(|| gpu.get("spdisplays_vram_shared"))
+            .and_then(|value| value.as_str())
+            .and_then(parse_vram_mb)
+            .unwrap_or(0)
+    };
+
+    let vendor = if unified_memory {
+        "apple".to_string()
+    } else {
+        gpu.get("spdisplays_vendor")
+            .and_then(|value| value.as_str())
+            .map(|value| value.trim_start_matches("sppci_vendor_").to_lowercase())
+            .unwrap_or_else(|| "unknown".to_string())
+    };
+
+    Some(AppleGpu {
+        name,
+        vendor,
+        vram_mb,
+        unified_memory,
+    })
+}
+
+/// Parses system_profiler sizes such as "8 GB" or "1536 MB".
+#[cfg(target_os = "macos")]
+fn parse_vram_mb(value: &str) -> Option<i64> {
+    let mut parts = value.split_whitespace();
+    let amount: i64 = parts.next()?.parse().ok()?;
+    match parts.next()?.to_ascii_uppercase().as_str() {
+        "GB" => Some(amount * 1024),
+        "MB" => Some(amount),
+        _ => None,
+    }
+}
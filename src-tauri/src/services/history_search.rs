@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::repositories::conversation_repo::ConversationRepo;
+use crate::repositories::embedding_repo::EmbeddingRepo;
+use crate::services::embedding_service::EmbeddingService;
+
+/// Embedding namespace and entity type for chat messages.
+pub const HISTORY_NAMESPACE: &str = "conversation";
+const MESSAGE_ENTITY: &str = "message";
+
+/// Only the start of a long message is embedded; it carries the topic.
+pub const MAX_EMBED_CHARS: usize = 2_000;
+/// Greetings and one-word replies only add noise to the index.
+const MIN_EMBED_CHARS: usize = 12;
+/// Age at which a hit's recency weight has dropped halfway to its floor.
+const RECENCY_HALF_LIFE_DAYS: f64 = 30.0;
+/// Old conversations keep this share of their similarity, so they stay findable.
+const RECENCY_FLOOR: f64 = 0.5;
+/// Hits below this cosine similarity are unrelated.
+const MIN_SIMILARITY: f32 = 0.25;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistorySearchHit {
+    pub message_id: String,
+    pub session_id: String,
+    pub session_title: Option<String>,
+    pub role: String,
+    pub content: String,
+    pub created_at: String,
+    pub similarity: f32,
+    /// Similarity weighted by recency; hits are ordered by it.
+    pub score: f64,
+}
+
+/// Semantic search over past chat messages, backed by the embeddings table.
+#[derive(Clone)]
+pub struct HistorySearchService {
+    conversation_repo: ConversationRepo,
+    embedding_repo: EmbeddingRepo,
+    embedding_service: Option<Arc<EmbeddingService>>,
+}
+
+impl HistorySearchService {
+    pub fn new(
+        conversation_repo: ConversationRepo,
+        embedding_repo: EmbeddingRepo,
+        embedding_service: Option<Arc<EmbeddingService>>,
+    ) -> Self {
+        Self {
+            conversation_repo,
+            embedding_repo,
+            embedding_service,
+        }
+    }
+
+    /// The embedding model, if it is already loaded. Indexing never loads it on
+    /// its own; messages missed meanwhile are picked up by [`Self::index_pending`].
+    fn loaded_embedding(&self) -> Option<&Arc<EmbeddingService>> {
+        self.embedding_service
+            .as_ref()
+            .filter(|embedding| embedding.is_initialized())
+    }
+
+    pub async fn index_message(
+        &self,
+        user_id: &str,
+        message_id: &str,
+        content: &str,
+    ) -> Result<bool, AppError> {
+        let Some(embedding) = self.loaded_embedding() else {
+            return Ok(false);
+        };
+        let content = content.trim();
+        if content.chars().count() < MIN_EMBED_CHARS {
+            return Ok(false);
+        }
+        let text = content.chars().take(MAX_EMBED_CHARS).collect::<String>();
+        embedding
+            .embed_and_store(
+                MESSAGE_ENTITY,
+                message_id,
+                user_id,
+                HISTORY_NAMESPACE,
+                &text,
+            )
+            .await?;
+        Ok(true)
+    }
+
+    /// Embeds up to `limit` messages that have no embedding yet. Returns how
+    /// many were indexed.
+    pub async fn index_pending(&self, limit: i64) -> Result<usize, AppError> {
+        if self.loaded_embedding().is_none() {
+            return Ok(0);
+        }
+        let pending = self
+            .conversation_repo
+            .list_unembedded_messages(MIN_EMBED_CHARS as i64, limit)
+            .await?;
+        let mut indexed = 0;
+        for message in pending {
+            if self
+                .index_message(&message.user_id, &message.id, &message.content)
+                .await?
+            {
+                indexed += 1;
+            }
+        }
+        Ok(indexed)
+    }
+
+    /// Messages closest in meaning to `query`, weighted towards recent ones.
+    pub async fn search(
+        &self,
+        user_id: &str,
+        query: &str,
+        top_k: usize,
+    ) -> Result<Vec<HistorySearchHit>, AppError> {
+        let embedding = self.embedding_service.as_ref().ok_or_else(|| {
+            AppError::Embedding("Semantic search needs the embedding model".to_string())
+        })?;
+        let query_vec = embedding.embed_text(query).await?;
+
+        // Recency can at most halve a score, so the top hits come from here.
+        let similar = self
+            .embedding_repo
+            .search_nearest(
+                HISTORY_NAMESPACE,
+                user_id,
+                MESSAGE_ENTITY,
+                &query_vec,
+                top_k * 4,
+            )
+            .await?
+            .into_iter()
+            .filter(|(_, similarity)| *similarity >= MIN_SIMILARITY)
+            .collect::<Vec<_>>();
+
+        let ids = similar.iter().map(|(id, _)| id.clone()).collect::<Vec<_>>();
+        let similarity_by_id = similar.into_iter().collect::<HashMap<_, _>>();
+        let messages = self.conversation_repo.get_messages_by_ids(&ids).await?;
+
+        let mut titles: HashMap<String, Option<String>> = HashMap::new();
+        let mut hits = Vec::with_capacity(messages.len());
+        for message in messages {
+            let Some(similarity) = similarity_by_id.get(&message.id).copied() else {
+                continue;
+            };
+            if !titles.contains_key(&message.session_id) {
+                let session = self
+                    .conversation_repo
+                    .get_session(&message.session_id)
+                    .await?;
+                // Deleted sessions keep their rows; their messages aren't history anymore.
+                let title = session
+                    .filter(|session| session.status != "deleted")
+                    .map(|session| session.title);
+                match title {
+                    Some(title) => titles.insert(message.session_id.clone(), title),
+                    None => continue,
+                };
+            }
+            let score = similarity as f64 * recency_weight(&message.created_at);
+            hits.push(HistorySearchHit {
+                session_title: titles.get(&message.session_id).cloned().flatten(),
+                message_id: message.id,
+                session_id: message.session_id,
+                role: message.role,
+                content: message.content,
+                created_at: message.created_at,
+                similarity,
+                score,
+            });
+        }
+
+        hits.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        hits.truncate(top_k);
+        Ok(hits)
+    }
+}
+
+/// Decays from 1.0 towards `RECENCY_FLOOR` with message age.
+fn recency_weight(created_at: &str) -> f64 {
+    let created = chrono::DateTime::parse_from_rfc3339(created_at)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .or_else(|_| {
+            chrono::NaiveDateTime::parse_from_str(created_at, "%Y-%m-%d %H:%M:%S")
+                .map(|naive| naive.and_utc())
+        });
+    let Ok(created) = created else {
+        return 1.0;
+    };
+    let age_days = (chrono::Utc::now() - created).num_hours().max(0) as f64 / 24.0;
+    let decay = 0.5f64.powf(age_days / RECENCY_HALF_LIFE_DAYS);
+    RECENCY_FLOOR + (1.0 - RECENCY_FLOOR) * decay
+}
@@ -0,0 +1,994 @@
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::db::models::{ImportCandidate, ImportSummary, NewMessage, NewModel};
+use crate::error::AppError;
+use crate::repositories::conversation_repo::ConversationRepo;
+use crate::repositories::model_repo::ModelRepo;
+use crate::services::rag_service::RagService;
+
+pub const IMPORT_SOURCES: &[&str] = &["ollama", "lm_studio", "jan", "open_webui"];
+/// Hosted assistants whose account exports can be imported with `import_conversations`.
+pub const CHAT_EXPORT_FORMATS: &[&str] = &["chatgpt", "claude"];
+
+const OLLAMA_MODEL_LAYER: &str = "application/vnd.ollama.image.model";
+const MAX_WALK_DEPTH: usize = 5;
+const DOCUMENT_EXTENSIONS: &[&str] = &["pdf", "md", "txt", "docx", "html", "csv", "json"];
+/// Both services name the file this way inside their export archives.
+const CHAT_EXPORT_FILE: &str = "conversations.json";
+
+#[derive(Debug, Clone)]
+struct ImportedModel {
+    name: String,
+    display_name: String,
+    family: String,
+    quantization: Option<String>,
+    file_path: PathBuf,
+    file_size_mb: i64,
+}
+
+#[derive(Debug, Clone)]
+struct ImportedChat {
+    import_key: String,
+    title: Option<String>,
+    /// SQLite-format timestamps when the source records them.
+    created_at: Option<String>,
+    messages: Vec<ImportedMessage>,
+}
+
+#[derive(Debug, Clone)]
+struct ImportedMessage {
+    role: String,
+    content: String,
+    created_at: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct SourceData {
+    root: Option<PathBuf>,
+    models: Vec<ImportedModel>,
+    chats: Vec<ImportedChat>,
+    documents: Vec<PathBuf>,
+}
+
+#[derive(Clone)]
+pub struct ImportService {
+    model_repo: ModelRepo,
+    conversation_repo: ConversationRepo,
+    rag: Option<Arc<RagService>>,
+}
+
+impl ImportService {
+    pub fn new(
+        model_repo: ModelRepo,
+        conversation_repo: ConversationRepo,
+        rag: Option<Arc<RagService>>,
+    ) -> Self {
+        Self {
+            model_repo,
+            conversation_repo,
+            rag,
+        }
+    }
+
+    /// Looks for data left behind by other local AI apps under `home`.
+    pub async fn scan(&self, home: PathBuf) -> Vec<ImportCandidate> {
+        let mut candidates = Vec::new();
+        for source in IMPORT_SOURCES {
+            let home = home.clone();
+            let data = tokio::task::spawn_blocking(move || collect_source(source, &home, None))
+                .await
+                .unwrap_or_default();
+
+            let Some(root) = data.root else {
+                continue;
+            };
+            if data.models.is_empty() && data.chats.is_empty() && data.documents.is_empty() {
+                continue;
+            }
+
+            candidates.push(ImportCandidate {
+                source: source.to_string(),
+                label: source_label(source).to_string(),
+                path: root.to_string_lossy().to_string(),
+                model_count: data.models.len() as i64,
+                session_count: data.chats.len() as i64,
+                document_count: data.documents.len() as i64,
+            });
+        }
+        candidates
+    }
+
+    /// Copies models, chats and documents from `source` into Sarah's tables.
+    /// Chats already imported (matched by their external id) are skipped.
+    pub async fn import_from(
+        &self,
+        user_id: &str,
+        source: &str,
+        home: PathBuf,
+        path: Option<PathBuf>,
+    ) -> Result<ImportSummary, AppError> {
+        let Some(source) = IMPORT_SOURCES
+            .iter()
+            .copied()
+            .find(|known| *known == source)
+        else {
+            return Err(AppError::Validation {
+                field: "source".to_string(),
+                message: format!("Unknown import source '{source}'"),
+            });
+        };
+
+        let data =
+            tokio::task::spawn_blocking(move || collect_source(source, &home, path.as_deref()))
+                .await
+                .map_err(|e| AppError::Internal(e.to_string()))?;
+
+        if data.root.is_none() {
+            return Err(AppError::NotFound {
+                entity: "import_source".to_string(),
+                id: source.to_string(),
+            });
+        }
+
+        let mut summary = ImportSummary {
+            source: source.to_string(),
+            ..ImportSummary::default()
+        };
+
+        for model in data.models {
+            match self
+                .model_repo
+                .upsert_model(new_model(source, &model))
+                .await
+            {
+                Ok(_) => summary.models_imported += 1,
+                Err(e) => summary.errors.push(format!("{}: {e}", model.name)),
+            }
+        }
+
+        self.import_chats(user_id, source, data.chats, &mut summary)
+            .await?;
+
+        if !data.documents.is_empty() {
+            match self.rag.as_ref() {
+                Some(rag) => {
+                    for document in data.documents {
+                        let file_path = document.to_string_lossy().to_string();
+                        match rag.ingest_document(user_id, &file_path).await {
+                            Ok(_) => summary.documents_imported += 1,
+                            Err(e) => summary.errors.push(format!("{file_path}: {e}")),
+                        }
+                    }
+                }
+                None => summary.skipped += data.documents.len() as i64,
+            }
+        }
+
+        crate::log_info!(
+            "sarah.import",
+            "import from {} finished: models={}, sessions={}, messages={}, documents={}, skipped={}, errors={}",
+            source,
+            summary.models_imported,
+            summary.sessions_imported,
+            summary.messages_imported,
+            summary.documents_imported,
+            summary.skipped,
+            summary.errors.len()
+        );
+
+        Ok(summary)
+    }
+
+    /// Imports the conversations in a ChatGPT or Claude account export. `path`
+    /// may be the export zip, the folder it was unpacked into, or its
+    /// `conversations.json`; the format is detected when not given.
+    pub async fn import_conversations(
+        &self,
+        user_id: &str,
+        path: PathBuf,
+        source_format: Option<&str>,
+    ) -> Result<ImportSummary, AppError> {
+        let requested = match source_format
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+        {
+            None | Some("auto") => None,
+            Some(format) => Some(
+                CHAT_EXPORT_FORMATS
+                    .iter()
+                    .copied()
+                    .find(|known| known.eq_ignore_ascii_case(format))
+                    .ok_or_else(|| AppError::Validation {
+                        field: "source_format".to_string(),
+                        message: format!("Unknown export format '{format}'"),
+                    })?,
+            ),
+        };
+
+        let (source, chats) = tokio::task::spawn_blocking(move || {
+            let export = read_chat_export(&path)?;
+            parse_chat_export(&export, requested)
+        })
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))??;
+
+        let mut summary = ImportSummary {
+            source: source.to_string(),
+            ..ImportSummary::default()
+        };
+        self.import_chats(user_id, source, chats, &mut summary)
+            .await?;
+
+        crate::log_info!(
+            "sarah.import",
+            "conversation import from {} finished: sessions={}, messages={}, skipped={}, errors={}",
+            source,
+            summary.sessions_imported,
+            summary.messages_imported,
+            summary.skipped,
+            summary.errors.len()
+        );
+
+        Ok(summary)
+    }
+
+    /// Chats already imported (matched by their external id) are skipped.
+    async fn import_chats(
+        &self,
+        user_id: &str,
+        source: &str,
+        chats: Vec<ImportedChat>,
+        summary: &mut ImportSummary,
+    ) -> Result<(), AppError> {
+        for chat in chats {
+            if chat.messages.is_empty()
+                || self
+                    .conversation_repo
+                    .find_session_by_import_key(&chat.import_key)
+                    .await?
+                    .is_some()
+            {
+                summary.skipped += 1;
+                continue;
+            }
+
+            match self.import_chat(user_id, source, &chat).await {
+                Ok(count) => {
+                    summary.sessions_imported += 1;
+                    summary.messages_imported += count;
+                }
+                Err(e) => summary.errors.push(format!("{}: {e}", chat.import_key)),
+            }
+        }
+        Ok(())
+    }
+
+    async fn import_chat(
+        &self,
+        user_id: &str,
+        source: &str,
+        chat: &ImportedChat,
+    ) -> Result<i64, AppError> {
+        let session = self
+            .conversation_repo
+            .create_imported_session(user_id, chat.title.as_deref(), &chat.import_key, source)
+            .await?;
+
+        let metadata = serde_json::json!({ "imported": true, "importSource": source }).to_string();
+        let mut message_times = Vec::new();
+        let mut position = 0_i64;
+        for message in &chat.messages {
+            let stored = self
+                .conversation_repo
+                .insert_message(NewMessage {
+                    session_id: session.id.clone(),
+                    role: message.role.clone(),
+                    content: message.content.clone(),
+                    content_type: "text".to_string(),
+                    token_count: Some((message.content.chars().count() / 4) as i64),
+                    model_id: None,
+                    metadata: metadata.clone(),
+                    attachments: "[]".to_string(),
+                    position,
+                })
+                .await?;
+            if let Some(created_at) = message.created_at.clone() {
+                message_times.push((stored.id, created_at));
+            }
+            position += 1;
+        }
+
+        if chat.created_at.is_some() || !message_times.is_empty() {
+            self.conversation_repo
+                .restore_import_timestamps(&session.id, chat.created_at.as_deref(), &message_times)
+                .await?;
+        }
+        Ok(position)
+    }
+}
+
+fn source_label(source: &str) -> &'static str {
+    match source {
+        "ollama" => "Ollama",
+        "lm_studio" => "LM Studio",
+        "jan" => "Jan",
+        _ => "Open WebUI",
+    }
+}
+
+fn new_model(source: &str, model: &ImportedModel) -> NewModel {
+    // Rough footprint: weights plus runtime overhead, the same shape as the seeded catalog.
+    let min_ram_mb = (model.file_size_mb as f64 * 1.2) as i64 + 512;
+    let performance_tier = match model.file_size_mb {
+        0..=2500 => "fast",
+        2501..=5500 => "balanced",
+        _ => "quality",
+    };
+    let energy_tier = match performance_tier {
+        "fast" => "low",
+        "balanced" => "medium",
+        _ => "high",
+    };
+
+    NewModel {
+        name: model.name.clone(),
+        display_name: model.display_name.clone(),
+        family: model.family.clone(),
+        version: None,
+        parameter_count: None,
+        quantization: model.quantization.clone(),
+        file_format: "gguf".to_string(),
+        file_path: Some(model.file_path.to_string_lossy().to_string()),
+        file_size_mb: Some(model.file_size_mb),
+        context_length: 4096,
+        embedding_size: None,
+        category: "chat".to_string(),
+        capabilities: r#"["chat","local"]"#.to_string(),
+        min_ram_mb,
+        recommended_ram_mb: min_ram_mb * 3 / 2,
+        min_vram_mb: 0,
+        performance_tier: performance_tier.to_string(),
+        energy_tier: energy_tier.to_string(),
+        download_url: None,
+        sha256_checksum: None,
+        tags: r#"["gguf","local","imported"]"#.to_string(),
+        metadata: serde_json::json!({ "importedFrom": source }).to_string(),
+    }
+}
+
+fn collect_source(source: &str, home: &Path, path: Option<&Path>) -> SourceData {
+    match source {
+        "ollama" => collect_ollama(home, path),
+        "lm_studio" => collect_lm_studio(home, path),
+        "jan" => collect_jan(home, path),
+        _ => collect_open_webui(home, path),
+    }
+}
+
+fn first_existing(candidates: Vec<PathBuf>) -> Option<PathBuf> {
+    candidates.into_iter().find(|candidate| candidate.exists())
+}
+
+fn collect_ollama(home: &Path, path: Option<&Path>) -> SourceData {
+    let root = match path {
+        Some(path) => first_existing(vec![path.to_path_buf()]),
+        None => first_existing(
+            std::env::var_os("OLLAMA_MODELS")
+                .map(PathBuf::from)
+                .into_iter()
+                .chain([home.join(".ollama").join("models")])
+                .collect(),
+        ),
+    };
+    let Some(root) = root else {
+        return SourceData::default();
+    };
+
+    let manifests = root.join("manifests");
+    let mut models = Vec::new();
+    for manifest in walk_files(&manifests, MAX_WALK_DEPTH) {
+        // manifests/<registry>/<namespace>/<model>/<tag>
+        let Ok(relative) = manifest.strip_prefix(&manifests) else {
+            continue;
+        };
+        let parts = relative
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        if parts.len() != 4 {
+            continue;
+        }
+
+        let Some(digest) = read_json(&manifest).and_then(|value| {
+            value
+                .get("layers")?
+                .as_array()?
+                .iter()
+                .find(|layer| {
+                    layer.get("mediaType").and_then(Value::as_str) == Some(OLLAMA_MODEL_LAYER)
+                })?
+                .get("digest")?
+                .as_str()
+                .map(str::to_string)
+        }) else {
+            continue;
+        };
+
+        let blob = root.join("blobs").join(digest.replace(':', "-"));
+        let Ok(meta) = fs::metadata(&blob) else {
+            continue;
+        };
+
+        let (registry, namespace, model, tag) = (&parts[0], &parts[1], &parts[2], &parts[3]);
+        let display = if registry == "registry.ollama.ai" && namespace == "library" {
+            format!("{model}:{tag}")
+        } else {
+            format!("{namespace}/{model}:{tag}")
+        };
+
+        models.push(ImportedModel {
+            name: format!("ollama:{display}"),
+            display_name: format!("{display} (Ollama)"),
+            family: guess_family(model),
+            quantization: guess_quantization(tag),
+            file_path: blob,
+            file_size_mb: (meta.len() / (1024 * 1024)) as i64,
+        });
+    }
+
+    SourceData {
+        root: Some(root),
+        models,
+        ..SourceData::default()
+    }
+}
+
+fn collect_lm_studio(home: &Path, path: Option<&Path>) -> SourceData {
+    let roots = match path {
+        Some(path) => vec![path.to_path_buf()],
+        None => vec![
+            home.join(".lmstudio"),
+            home.join(".cache").join("lm-studio"),
+        ],
+    };
+    let Some(root) = first_existing(roots) else {
+        return SourceData::default();
+    };
+
+    let models_dir = root.join("models");
+    let models = walk_files(&models_dir, MAX_WALK_DEPTH)
+        .into_iter()
+        .filter_map(|file| gguf_model("lmstudio", "LM Studio", &models_dir, file))
+        .collect();
+
+    let chats = walk_files(&root.join("conversations"), MAX_WALK_DEPTH)
+        .into_iter()
+        .filter(|file| has_extension(file, "json"))
+        .filter_map(|file| {
+            let value = read_json(&file)?;
+            let messages = value
+                .get("messages")?
+                .as_array()?
+                .iter()
+                .filter_map(lm_studio_message)
+                .collect::<Vec<_>>();
+            Some(ImportedChat {
+                import_key: format!("lm_studio:{}", file_stem(&file)),
+                title: string_field(&value, "name"),
+                created_at: None,
+                messages,
+            })
+        })
+        .collect();
+
+    SourceData {
+        root: Some(root),
+        models,
+        chats,
+        ..SourceData::default()
+    }
+}
+
+/// LM Studio keeps every regenerated reply as a "version"; only the selected one is imported.
+fn lm_studio_message(message: &Value) -> Option<(String, String)> {
+    let selected = match message.get("versions").and_then(Value::as_array) {
+        Some(versions) => {
+            let index = message
+                .get("currentlySelected")
+                .and_then(Value::as_u64)
+                .unwrap_or(0) as usize;
+            versions.get(index).or_else(|| versions.last())?
+        }
+        None => message,
+    };
+
+    let role = normalize_role(selected.get("role").and_then(Value::as_str)?)?;
+    let content = selected
+        .get("content")
+        .map(extract_text)
+        .or_else(|| selected.get("steps").map(extract_text))
+        .unwrap_or_default();
+    (!content.trim().is_empty()).then_some(ImportedMessage {
+        role,
+        content,
+        created_at: None,
+    })
+}
+
+fn collect_jan(home: &Path, path: Option<&Path>) -> SourceData {
+    let roots = match path {
+        Some(path) => vec![path.to_path_buf()],
+        None => std::env::var_os("JAN_DATA_FOLDER")
+            .map(PathBuf::from)
+            .into_iter()
+            .chain([
+                home.join("jan"),
+                home.join("Library")
+                    .join("Application Support")
+                    .join("Jan")
+                    .join("data"),
+                home.join("AppData")
+                    .join("Roaming")
+                    .join("Jan")
+                    .join("data"),
+            ])
+            .collect(),
+    };
+    let Some(root) = first_existing(roots) else {
+        return SourceData::default();
+    };
+
+    let models_dir = root.join("models");
+    let models = walk_files(&models_dir, MAX_WALK_DEPTH)
+        .into_iter()
+        .filter_map(|file| gguf_model("jan", "Jan", &models_dir, file))
+        .collect();
+
+    let chats = fs::read_dir(root.join("threads"))
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|dir| dir.is_dir())
+                .filter_map(|dir| jan_thread(&dir))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    SourceData {
+        root: Some(root),
+        models,
+        chats,
+        ..SourceData::default()
+    }
+}
+
+fn jan_thread(dir: &Path) -> Option<ImportedChat> {
+    let thread = read_json(&dir.join("thread.json")).unwrap_or(Value::Null);
+    let raw = fs::read_to_string(dir.join("messages.jsonl")).ok()?;
+    let messages = raw
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter_map(|message| {
+            let role = normalize_role(message.get("role").and_then(Value::as_str)?)?;
+            let content = message.get("content").map(extract_text)?;
+            (!content.trim().is_empty()).then_some(ImportedMessage {
+                role,
+                content,
+                created_at: None,
+            })
+        })
+        .collect();
+
+    let id = string_field(&thread, "id").unwrap_or_else(|| file_stem(dir));
+    Some(ImportedChat {
+        import_key: format!("jan:{id}"),
+        title: string_field(&thread, "title"),
+        created_at: None,
+        messages,
+    })
+}
+
+fn collect_open_webui(home: &Path, path: Option<&Path>) -> SourceData {
+    let (root, exports) = match path {
+        Some(path) if path.is_file() => (Some(path.to_path_buf()), vec![path.to_path_buf()]),
+        Some(path) if path.is_dir() => (
+            Some(path.to_path_buf()),
+            walk_files(path, 0)
+                .into_iter()
+                .filter(|file| has_extension(file, "json"))
+                .collect(),
+        ),
+        Some(_) => (None, Vec::new()),
+        None => {
+            // Open WebUI only offers browser exports, which land in Downloads.
+            let downloads = home.join("Downloads");
+            let exports = walk_files(&downloads, 0)
+                .into_iter()
+                .filter(|file| {
+                    let name = file_stem(file);
+                    has_extension(file, "json")
+                        && (name.starts_with("chat-export") || name.starts_with("all-chats-export"))
+                })
+                .collect::<Vec<_>>();
+            let root = (!exports.is_empty()).then_some(downloads);
+            (root, exports)
+        }
+    };
+
+    let chats = exports
+        .iter()
+        .filter_map(|file| read_json(file))
+        .flat_map(|value| match value {
+            Value::Array(items) => items,
+            other => vec![other],
+        })
+        .filter_map(|entry| open_webui_chat(&entry))
+        .collect();
+
+    let documents = path
+        .filter(|path| path.is_dir())
+        .map(|path| {
+            walk_files(&path.join("uploads"), MAX_WALK_DEPTH)
+                .into_iter()
+                .filter(|file| {
+                    DOCUMENT_EXTENSIONS
+                        .iter()
+                        .any(|extension| has_extension(file, extension))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    SourceData {
+        root,
+        chats,
+        documents,
+        ..SourceData::default()
+    }
+}
+
+fn open_webui_chat(entry: &Value) -> Option<ImportedChat> {
+    let chat = entry.get("chat")?;
+    let id = string_field(entry, "id").or_else(|| string_field(chat, "id"))?;
+    let messages = chat
+        .get("messages")?
+        .as_array()?
+        .iter()
+        .filter_map(|message| {
+            let role = normalize_role(message.get("role").and_then(Value::as_str)?)?;
+            let content = message.get("content").map(extract_text)?;
+            (!content.trim().is_empty()).then_some(ImportedMessage {
+                role,
+                content,
+                created_at: None,
+            })
+        })
+        .collect();
+
+    Some(ImportedChat {
+        import_key: format!("open_webui:{id}"),
+        title: string_field(entry, "title").or_else(|| string_field(chat, "title")),
+        created_at: None,
+        messages,
+    })
+}
+
+/// Loads `conversations.json` from an export zip, its unpacked folder or the file itself.
+fn read_chat_export(path: &Path) -> Result<Value, AppError> {
+    let raw = if path.is_dir() {
+        fs::read_to_string(path.join(CHAT_EXPORT_FILE))?
+    } else if has_extension(path, "zip") {
+        let file = fs::File::open(path)?;
+        let mut archive = zip::ZipArchive::new(file)
+            .map_err(|e| AppError::Io(format!("Failed to open export archive: {e}")))?;
+        let mut entry = archive
+            .by_name(CHAT_EXPORT_FILE)
+            .map_err(|_| AppError::NotFound {
+                entity: "export_file".to_string(),
+                id: CHAT_EXPORT_FILE.to_string(),
+            })?;
+        let mut raw = String::new();
+        entry.read_to_string(&mut raw)?;
+        raw
+    } else {
+        fs::read_to_string(path)?
+    };
+
+    serde_json::from_str(&raw).map_err(|e| AppError::Validation {
+        field: "path".to_string(),
+        message: format!("Export is not valid JSON: {e}"),
+    })
+}
+
+fn parse_chat_export(
+    export: &Value,
+    requested: Option<&'static str>,
+) -> Result<(&'static str, Vec<ImportedChat>), AppError> {
+    let entries = export.as_array().map(Vec::as_slice).unwrap_or_default();
+    let source = requested
+        .or_else(|| {
+            entries.iter().find_map(|entry| {
+                if entry.get("mapping").is_some() {
+                    Some("chatgpt")
+                } else if entry.get("chat_messages").is_some() {
+                    Some("claude")
+                } else {
+                    None
+                }
+            })
+        })
+        .ok_or_else(|| AppError::Validation {
+            field: "source_format".to_string(),
+            message: "Could not recognize the export as ChatGPT or Claude".to_string(),
+        })?;
+
+    let chats = entries
+        .iter()
+        .filter_map(|entry| match source {
+            "chatgpt" => chatgpt_conversation(entry),
+            _ => claude_conversation(entry),
+        })
+        .collect();
+    Ok((source, chats))
+}
+
+/// ChatGPT stores every branch as a tree in `mapping`; the conversation as last
+/// seen is the path from `current_node` back to the root.
+fn chatgpt_conversation(entry: &Value) -> Option<ImportedChat> {
+    let id = string_field(entry, "conversation_id").or_else(|| string_field(entry, "id"))?;
+    let mapping = entry.get("mapping")?.as_object()?;
+
+    let mut path = Vec::new();
+    let mut node_id = string_field(entry, "current_node");
+    while let Some(node) = node_id.as_deref().and_then(|id| mapping.get(id)) {
+        // A malformed export could loop; a real path never exceeds the node count.
+        if path.len() > mapping.len() {
+            break;
+        }
+        path.push(node);
+        node_id = string_field(node, "parent");
+    }
+    path.reverse();
+
+    let messages = path
+        .into_iter()
+        .filter_map(|node| node.get("message"))
+        .filter(|message| {
+            message
+                .pointer("/metadata/is_visually_hidden_from_conversation")
+                .and_then(Value::as_bool)
+                != Some(true)
+        })
+        .filter_map(|message| {
+            let role = normalize_role(message.pointer("/author/role").and_then(Value::as_str)?)?;
+            let content = message.get("content")?;
+            let content = content
+                .get("parts")
+                .or_else(|| content.get("text"))
+                .map(extract_text)
+                .unwrap_or_default();
+            (!content.trim().is_empty()).then(|| ImportedMessage {
+                role,
+                content,
+                created_at: message.get("create_time").and_then(unix_timestamp),
+            })
+        })
+        .collect();
+
+    Some(ImportedChat {
+        import_key: format!("chatgpt:{id}"),
+        title: string_field(entry, "title"),
+        created_at: entry.get("create_time").and_then(unix_timestamp),
+        messages,
+    })
+}
+
+fn claude_conversation(entry: &Value) -> Option<ImportedChat> {
+    let id = string_field(entry, "uuid")?;
+    let messages = entry
+        .get("chat_messages")?
+        .as_array()?
+        .iter()
+        .filter_map(|message| {
+            let role = normalize_role(message.get("sender").and_then(Value::as_str)?)?;
+            // Prefer the flattened text; `content` also carries tool use blocks.
+            let content = string_field(message, "text").or_else(|| {
+                let parts = message.get("content")?.as_array()?;
+                let text = parts
+                    .iter()
+                    .filter(|part| part.get("type").and_then(Value::as_str) == Some("text"))
+                    .map(extract_text)
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                (!text.trim().is_empty()).then_some(text)
+            })?;
+            Some(ImportedMessage {
+                role,
+                content,
+                created_at: message.get("created_at").and_then(iso_timestamp),
+            })
+        })
+        .collect();
+
+    Some(ImportedChat {
+        import_key: format!("claude:{id}"),
+        title: string_field(entry, "name"),
+        created_at: entry.get("created_at").and_then(iso_timestamp),
+        messages,
+    })
+}
+
+/// Seconds since the epoch (ChatGPT uses fractional floats) in SQLite's format.
+fn unix_timestamp(value: &Value) -> Option<String> {
+    let seconds = value.as_f64()?;
+    chrono::DateTime::from_timestamp(seconds.trunc() as i64, 0)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+fn iso_timestamp(value: &Value) -> Option<String> {
+    chrono::DateTime::parse_from_rfc3339(value.as_str()?)
+        .ok()
+        .map(|time| {
+            time.with_timezone(&chrono::Utc)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+}
+
+fn gguf_model(
+    prefix: &str,
+    label: &str,
+    models_dir: &Path,
+    file: PathBuf,
+) -> Option<ImportedModel> {
+    let stem = file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    // Vision projectors ship next to the weights but aren't loadable on their own.
+    if !has_extension(&file, "gguf") || stem.to_ascii_lowercase().contains("mmproj") {
+        return None;
+    }
+
+    let relative = file
+        .strip_prefix(models_dir)
+        .ok()?
+        .with_extension("")
+        .to_string_lossy()
+        .replace('\\', "/");
+    let size = fs::metadata(&file).ok()?.len();
+
+    Some(ImportedModel {
+        name: format!("{prefix}:{relative}"),
+        display_name: format!("{stem} ({label})"),
+        family: guess_family(&stem),
+        quantization: guess_quantization(&stem),
+        file_path: file,
+        file_size_mb: (size / (1024 * 1024)) as i64,
+    })
+}
+
+fn normalize_role(role: &str) -> Option<String> {
+    match role.to_ascii_lowercase().as_str() {
+        "user" | "human" => Some("user".to_string()),
+        "assistant" | "bot" | "model" => Some("assistant".to_string()),
+        "system" => Some("system".to_string()),
+        _ => None,
+    }
+}
+
+/// Flattens the content shapes these apps use (plain strings, typed parts, `{value}` wrappers).
+fn extract_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .map(extract_text)
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Value::Object(map) => {
+            if map
+                .get("type")
+                .and_then(Value::as_str)
+                .is_some_and(|kind| kind != "text")
+            {
+                return map.get("content").map(extract_text).unwrap_or_default();
+            }
+            ["text", "value", "content"]
+                .iter()
+                .find_map(|key| map.get(*key))
+                .map(extract_text)
+                .unwrap_or_default()
+        }
+        _ => String::new(),
+    }
+}
+
+fn guess_family(name: &str) -> String {
+    let lower = name.to_ascii_lowercase();
+    [
+        "llama",
+        "qwen",
+        "mistral",
+        "mixtral",
+        "gemma",
+        "phi",
+        "deepseek",
+        "tinyllama",
+        "smollm",
+    ]
+    .iter()
+    .find(|family| lower.contains(*family))
+    .map(|family| family.to_string())
+    .unwrap_or_else(|| "other".to_string())
+}
+
+fn guess_quantization(name: &str) -> Option<String> {
+    let upper = name.to_ascii_uppercase();
+    upper
+        .split(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_'))
+        .find(|part| {
+            let rest = part.strip_prefix('I').unwrap_or(part);
+            rest.starts_with('Q') && rest[1..].starts_with(|ch: char| ch.is_ascii_digit())
+        })
+        .or_else(|| {
+            ["BF16", "F16", "F32"]
+                .into_iter()
+                .find(|kind| upper.contains(kind))
+        })
+        .map(str::to_string)
+}
+
+fn walk_files(dir: &Path, depth: usize) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return files;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth > 0 {
+                files.extend(walk_files(&path, depth - 1));
+            }
+        } else {
+            files.push(path);
+        }
+    }
+    files
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    let raw = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+fn string_field(value: &Value, key: &str) -> Option<String> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .and_then(|value| value.to_str())
+        .is_some_and(|value| value.eq_ignore_ascii_case(extension))
+}
+
+fn file_stem(path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    // LM Studio names files "<id>.conversation.json".
+    name.split('.').next().unwrap_or_default().to_string()
+}
@@ -0,0 +1,237 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InferencePriority {
+    Interactive,
+    Background,
+}
+
+impl InferencePriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Interactive => "interactive",
+            Self::Background => "background",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InferenceQueueEntry {
+    pub label: String,
+    pub priority: InferencePriority,
+    /// Time spent running (holder) or waiting (queued) so far.
+    pub elapsed_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InferenceQueueStatus {
+    pub holder: Option<InferenceQueueEntry>,
+    pub depth: usize,
+    pub interactive_waiting: usize,
+    pub background_waiting: usize,
+    pub waiting: Vec<InferenceQueueEntry>,
+    pub total_preemptions: u64,
+}
+
+struct Slot {
+    ticket: u64,
+    label: String,
+    priority: InferencePriority,
+    since: Instant,
+}
+
+impl Slot {
+    fn entry(&self) -> InferenceQueueEntry {
+        InferenceQueueEntry {
+            label: self.label.clone(),
+            priority: self.priority,
+            elapsed_ms: self.since.elapsed().as_millis() as i64,
+        }
+    }
+}
+
+#[derive(Default)]
+struct QueueState {
+    holder: Option<Slot>,
+    waiting: VecDeque<Slot>,
+    next_ticket: u64,
+    preemptions: u64,
+}
+
+impl QueueState {
+    /// Interactive requests jump every queued background job; FIFO within a lane.
+    fn next_index(&self) -> Option<usize> {
+        self.waiting
+            .iter()
+            .position(|slot| slot.priority == InferencePriority::Interactive)
+            .or_else(|| (!self.waiting.is_empty()).then_some(0))
+    }
+}
+
+/// Single-slot inference queue with an interactive and a background lane.
+/// Replaces a bare semaphore so waiters and the current holder are visible.
+#[derive(Clone, Default)]
+pub struct InferenceQueue {
+    state: Arc<Mutex<QueueState>>,
+    notify: Arc<Notify>,
+}
+
+impl InferenceQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn acquire(&self, label: &str, priority: InferencePriority) -> InferencePermit {
+        let enqueued_at = Instant::now();
+        let ticket = {
+            let mut state = self.lock();
+            let ticket = state.next_ticket;
+            state.next_ticket += 1;
+            state.waiting.push_back(Slot {
+                ticket,
+                label: label.to_string(),
+                priority,
+                since: enqueued_at,
+            });
+            ticket
+        };
+
+        // Takes the ticket back out of the queue if this future is dropped while waiting.
+        let mut pending = PendingTicket {
+            queue: self.clone(),
+            ticket,
+            armed: true,
+        };
+
+        loop {
+            let notified = self.notify.notified();
+            if self.try_promote(ticket) {
+                pending.armed = false;
+                return InferencePermit {
+                    queue: self.clone(),
+                    ticket,
+                    priority,
+                    wait_ms: enqueued_at.elapsed().as_millis() as i64,
+                };
+            }
+            notified.await;
+        }
+    }
+
+    pub fn status(&self) -> InferenceQueueStatus {
+        let state = self.lock();
+        let waiting = state.waiting.iter().map(Slot::entry).collect::<Vec<_>>();
+        let interactive_waiting = state
+            .waiting
+            .iter()
+            .filter(|slot| slot.priority == InferencePriority::Interactive)
+            .count();
+
+        InferenceQueueStatus {
+            holder: state.holder.as_ref().map(Slot::entry),
+            depth: waiting.len(),
+            interactive_waiting,
+            background_waiting: waiting.len() - interactive_waiting,
+            waiting,
+            total_preemptions: state.preemptions,
+        }
+    }
+
+    fn try_promote(&self, ticket: u64) -> bool {
+        let mut state = self.lock();
+        if state.holder.is_some() {
+            return false;
+        }
+        let Some(index) = state.next_index() else {
+            return false;
+        };
+        if state.waiting[index].ticket != ticket {
+            return false;
+        }
+        if let Some(mut slot) = state.waiting.remove(index) {
+            slot.since = Instant::now();
+            state.holder = Some(slot);
+            return true;
+        }
+        false
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, QueueState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+struct PendingTicket {
+    queue: InferenceQueue,
+    ticket: u64,
+    armed: bool,
+}
+
+impl Drop for PendingTicket {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        self.queue
+            .lock()
+            .waiting
+            .retain(|slot| slot.ticket != self.ticket);
+        self.queue.notify.notify_waiters();
+    }
+}
+
+/// Exclusive right to run inference; the next waiter is woken when it drops.
+pub struct InferencePermit {
+    queue: InferenceQueue,
+    ticket: u64,
+    priority: InferencePriority,
+    wait_ms: i64,
+}
+
+impl InferencePermit {
+    pub fn wait_ms(&self) -> i64 {
+        self.wait_ms
+    }
+
+    /// True when a background holder should stop at the next token boundary
+    /// because an interactive request is waiting.
+    pub fn should_yield(&self) -> bool {
+        self.priority == InferencePriority::Background
+            && self
+                .queue
+                .lock()
+                .waiting
+                .iter()
+                .any(|slot| slot.priority == InferencePriority::Interactive)
+    }
+
+    pub fn record_preemption(&self) {
+        self.queue.lock().preemptions += 1;
+    }
+}
+
+impl Drop for InferencePermit {
+    fn drop(&mut self) {
+        {
+            let mut state = self.queue.lock();
+            if state
+                .holder
+                .as_ref()
+                .is_some_and(|slot| slot.ticket == self.ticket)
+            {
+                state.holder = None;
+            }
+        }
+        self.queue.notify.notify_waiters();
+    }
+}
@@ -1,25 +1,75 @@
 use std::num::NonZeroU32;
+use std::collections::HashMap;
+use std::ffi::CString;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use encoding_rs::UTF_8;
 use llama_cpp_2::context::params::LlamaContextParams;
+use llama_cpp_2::context::LlamaContext;
 use llama_cpp_2::llama_backend::LlamaBackend;
 use llama_cpp_2::llama_batch::LlamaBatch;
 use llama_cpp_2::model::params::LlamaModelParams;
-use llama_cpp_2::model::{AddBos, LlamaModel};
+use llama_cpp_2::model::{AddBos, LlamaLoraAdapter, LlamaModel};
+use llama_cpp_2::mtmd::{
+    mtmd_default_marker, MtmdBitmap, MtmdContext, MtmdContextParams, MtmdInputText,
+};
 use llama_cpp_2::sampling::LlamaSampler;
-use tauri::Emitter;
-use tokio::sync::{mpsc, Semaphore};
+use llama_cpp_2::token::LlamaToken;
+use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
 
 use crate::db::models::{
     GenerationOptions, GenerationResult, Message, MessageStreamChunk, SystemProfile,
 };
 use crate::error::AppError;
-use crate::services::hardware_service::PerformanceMode;
+use crate::repositories::adapter_repo::AdapterRepo;
+use crate::repositories::model_repo::ModelRepo;
+use crate::services::analytics_service::AnalyticsService;
+use crate::services::chat_template::ChatTemplate;
+use crate::services::hardware_service::{
+    unified_memory_budget_mb, ModelPoolLimits, PerformanceMode,
+};
+use crate::services::inference_queue::{
+    InferencePermit, InferencePriority, InferenceQueue, InferenceQueueStatus,
+};
+use crate::services::json_grammar::schema_to_grammar;
+use crate::services::prompt_cache::{PromptCache, SessionCacheStats};
+
+/// Upper bound for the layer search when "all layers" failed; no GGUF we ship goes past this.
+const GPU_LAYER_SEARCH_CEILING: i32 = 128;
+/// Queue waits shorter than this are not worth a perf_logs row.
+const QUEUE_WAIT_LOG_THRESHOLD_MS: i64 = 250;
+/// After this many preemptions a background job runs to completion so it can't starve.
+const MAX_BACKGROUND_PREEMPTIONS: usize = 3;
+/// Extra room given to a session's cached context so follow-up turns fit without a rebuild.
+const CACHE_HEADROOM_TOKENS: usize = 2048;
+/// Largest context a generation allocates, whatever the model was trained on.
+pub const MAX_CONTEXT_TOKENS: usize = 8192;
+/// RAM counted per resident model on top of its weights, for KV caches and scratch buffers.
+const KV_RESERVE_MB: u64 = 512;
+/// Layer count assumed when sizing a partial offload, as the file isn't read
+/// until it loads. llama.cpp clamps the count to the real total.
+const ASSUMED_LAYER_COUNT: u64 = 32;
+/// Batch size used when evaluating prompts that contain images.
+const VISION_BATCH_SIZE: i32 = 512;
+
+const TOOL_INSTRUCTIONS: &str = "You can call tools. To call one, reply with only \
+<tool_call>{\"name\": \"<tool name>\", \"arguments\": {...}}</tool_call>, using one tag per \
+call. Tool results come back in the next message; then call more tools or answer the user \
+normally. Only call tools from this list:";
+
+const STRUCTURED_INSTRUCTIONS: &str =
+    "Reply with a single JSON value that matches this JSON schema:";
+
+/// A tool invocation found in model output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedToolCall {
+    pub name: String,
+    pub arguments: serde_json::Value,
+}
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -28,32 +78,415 @@ pub struct ModelInfo {
     pub context_length: usize,
     pub n_gpu_layers: i32,
     pub n_threads: usize,
+    pub chat_template: ChatTemplate,
+    /// Estimated RAM for the weights plus KV caches, used for pool eviction.
+    pub resident_mb: u64,
+    /// Multimodal projector loaded with the model; set for vision models.
+    pub vision_projector: Option<String>,
+}
+
+/// Limits the runtime governor sets while the CPU or GPU runs above its
+/// thermal threshold.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ThermalThrottle {
+    /// Halves inference threads, from the next generation on.
+    pub cpu_hot: bool,
+    /// Halves the GPU layers of the next model load.
+    pub gpu_hot: bool,
+}
+
+impl ThermalThrottle {
+    pub fn is_active(self) -> bool {
+        self.cpu_hot || self.gpu_hot
+    }
+
+    fn threads(self, budget: usize) -> usize {
+        if self.cpu_hot {
+            (budget / 2).max(1)
+        } else {
+            budget
+        }
+    }
+
+    fn gpu_layers(self, layers: i32) -> i32 {
+        if !self.gpu_hot || layers == 0 {
+            return layers;
+        }
+        let full = if layers < 0 {
+            ASSUMED_LAYER_COUNT as i32
+        } else {
+            layers
+        };
+        full / 2
+    }
+}
+
+/// A LoRA adapter initialized against a loaded model.
+struct LoadedAdapter {
+    path: String,
+    scale: f32,
+    adapter: LlamaLoraAdapter,
+}
+
+// SAFETY: adapters are only touched through the inference mutex, like the
+// model they were created from.
+unsafe impl Send for LoadedAdapter {}
+
+/// The mmproj that turns images into embeddings for a vision model.
+struct VisionProjector {
+    path: String,
+    ctx: MtmdContext,
 }
 
+// SAFETY: like adapters, the projector is only used under the inference mutex.
+unsafe impl Send for VisionProjector {}
+
+/// Field order matters: `prompt_cache` holds contexts that borrow `model` and
+/// the adapters, and the adapters and projector belong to `model`; each must
+/// be dropped before what it borrows (and before the backend).
 struct LoadedModel {
-    backend: LlamaBackend,
-    model: LlamaModel,
+    prompt_cache: PromptCache,
+    adapters: Vec<LoadedAdapter>,
+    vision: Option<VisionProjector>,
+    backend: Arc<LlamaBackend>,
+    model: Box<LlamaModel>,
     info: ModelInfo,
+    /// Thread budget before thermal throttling; `info.n_threads` is what runs.
+    full_threads: usize,
     seed: u32,
     last_used_secs: Arc<AtomicU64>,
 }
 
+impl LoadedModel {
+    fn touch(&self) {
+        self.last_used_secs.store(now_secs(), Ordering::Relaxed);
+    }
+
+    /// Initializes the adapter at `path`, replacing a previous one with the
+    /// same path. Cached contexts were built with the old adapter set, so they
+    /// are dropped.
+    fn apply_adapter(&mut self, path: &str, scale: f32) -> Result<(), AppError> {
+        self.prompt_cache = PromptCache::default();
+        self.adapters.retain(|adapter| adapter.path != path);
+        let adapter = self
+            .model
+            .lora_adapter_init(path)
+            .map_err(|e| AppError::Inference(format!("Failed to load LoRA adapter {path}: {e}")))?;
+        self.adapters.push(LoadedAdapter {
+            path: path.to_string(),
+            scale,
+            adapter,
+        });
+        Ok(())
+    }
+
+    fn remove_adapter(&mut self, path: &str) -> bool {
+        let before = self.adapters.len();
+        self.adapters.retain(|adapter| adapter.path != path);
+        if self.adapters.len() == before {
+            return false;
+        }
+        self.prompt_cache = PromptCache::default();
+        true
+    }
+}
+
+/// Attaches every adapter to a freshly created context.
+fn attach_adapters(
+    ctx: &mut LlamaContext<'_>,
+    adapters: &mut [LoadedAdapter],
+) -> Result<(), AppError> {
+    for loaded in adapters {
+        ctx.lora_adapter_set(&mut loaded.adapter, loaded.scale)
+            .map_err(|e| {
+                AppError::Inference(format!("Failed to apply LoRA adapter {}: {e}", loaded.path))
+            })?;
+    }
+    Ok(())
+}
+
+/// Models kept resident at once, keyed by file path. Generations run on the
+/// active one; switching to another resident model needs no reload.
+#[derive(Default)]
+struct ModelPool {
+    /// llama.cpp's backend can only be initialized once per process.
+    backend: Option<Arc<LlamaBackend>>,
+    models: HashMap<String, LoadedModel>,
+    active: Option<String>,
+    limits: Option<ModelPoolLimits>,
+}
+
+impl ModelPool {
+    fn active(&self) -> Option<&LoadedModel> {
+        self.active.as_ref().and_then(|path| self.models.get(path))
+    }
+
+    fn active_mut(&mut self) -> Option<&mut LoadedModel> {
+        self.active.as_ref().and_then(|path| self.models.get_mut(path))
+    }
+
+    /// The active model, marked as used.
+    fn for_generation(&mut self) -> Result<&mut LoadedModel, AppError> {
+        let loaded = self
+            .active_mut()
+            .ok_or_else(|| AppError::Inference("No active model loaded".to_string()))?;
+        loaded.touch();
+        Ok(loaded)
+    }
+
+    fn backend(&mut self) -> Result<Arc<LlamaBackend>, AppError> {
+        if let Some(backend) = self.backend.as_ref() {
+            return Ok(Arc::clone(backend));
+        }
+        let backend = Arc::new(
+            LlamaBackend::init()
+                .map_err(|e| AppError::Inference(format!("Failed to init llama backend: {e}")))?,
+        );
+        self.backend = Some(Arc::clone(&backend));
+        Ok(backend)
+    }
+
+    /// Makes an already resident model active. Returns false if it isn't loaded.
+    fn activate(&mut self, path: &str) -> bool {
+        match self.models.get(path) {
+            Some(loaded) => {
+                loaded.touch();
+                self.active = Some(path.to_string());
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn insert(&mut self, loaded: LoadedModel) {
+        let path = loaded.info.path.clone();
+        self.models.insert(path.clone(), loaded);
+        self.active = Some(path);
+    }
+
+    fn remove(&mut self, path: &str) -> Option<LoadedModel> {
+        if self.active.as_deref() == Some(path) {
+            self.active = None;
+        }
+        self.models.remove(path)
+    }
+
+    fn resident_mb(&self) -> u64 {
+        self.models.values().map(|loaded| loaded.info.resident_mb).sum()
+    }
+
+    /// Least recently used model, preferring any but the active one.
+    fn least_recent(&self) -> Option<String> {
+        self.models
+            .iter()
+            .filter(|(path, _)| self.models.len() == 1 || self.active.as_ref() != Some(*path))
+            .min_by_key(|(_, loaded)| loaded.last_used_secs.load(Ordering::Relaxed))
+            .map(|(path, _)| path.clone())
+    }
+
+    /// Evicts least recently used models until one more of `incoming_mb`
+    /// fits both the model count and the RAM budget.
+    fn make_room(&mut self, incoming_mb: u64) -> Vec<String> {
+        match self.limits {
+            Some(limits) => self.evict_until(
+                limits.max_resident_models.saturating_sub(1),
+                limits.ram_budget_mb.saturating_sub(incoming_mb),
+            ),
+            None => Vec::new(),
+        }
+    }
+
+    fn evict_until(&mut self, max_models: usize, max_mb: u64) -> Vec<String> {
+        let mut evicted = Vec::new();
+        while !self.models.is_empty()
+            && (self.models.len() > max_models || self.resident_mb() > max_mb)
+        {
+            let Some(path) = self.least_recent() else {
+                break;
+            };
+            self.remove(&path);
+            evicted.push(path);
+        }
+        evicted
+    }
+}
+
 #[derive(Clone)]
 pub struct InferenceService {
-    loaded: Arc<Mutex<Option<LoadedModel>>>,
-    limiter: Arc<Semaphore>,
+    loaded: Arc<Mutex<ModelPool>>,
+    queue: InferenceQueue,
+    model_repo: Option<ModelRepo>,
+    adapter_repo: Option<AdapterRepo>,
+    analytics: Option<AnalyticsService>,
+    /// Stop flags for in-flight streamed generations, keyed by session id.
+    cancellations: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+    thermal: Arc<Mutex<ThermalThrottle>>,
 }
 
 impl InferenceService {
     pub fn new() -> Self {
         Self {
-            loaded: Arc::new(Mutex::new(None)),
-            limiter: Arc::new(Semaphore::new(1)),
+            loaded: Arc::new(Mutex::new(ModelPool::default())),
+            queue: InferenceQueue::new(),
+            model_repo: None,
+            adapter_repo: None,
+            analytics: None,
+            cancellations: Arc::new(Mutex::new(HashMap::new())),
+            thermal: Arc::new(Mutex::new(ThermalThrottle::default())),
+        }
+    }
+
+    /// Records noticeable queue waits in perf_logs.
+    pub fn with_analytics(mut self, analytics: AnalyticsService) -> Self {
+        self.analytics = Some(analytics);
+        self
+    }
+
+    pub fn queue_status(&self) -> InferenceQueueStatus {
+        self.queue.status()
+    }
+
+    /// Asks the streamed generation for `session_id` to stop after its current
+    /// token. Returns false when nothing is generating for that session.
+    pub fn cancel_generation(&self, session_id: &str) -> bool {
+        let Ok(cancellations) = self.cancellations.lock() else {
+            return false;
+        };
+        match cancellations.get(session_id) {
+            Some(flag) => {
+                flag.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn register_cancellation(&self, session_id: &str) -> Arc<AtomicBool> {
+        let flag = Arc::new(AtomicBool::new(false));
+        if let Ok(mut cancellations) = self.cancellations.lock() {
+            cancellations.insert(session_id.to_string(), flag.clone());
         }
+        flag
+    }
+
+    async fn acquire_slot(&self, label: &str, priority: InferencePriority) -> InferencePermit {
+        let permit = self.queue.acquire(label, priority).await;
+        let wait_ms = permit.wait_ms();
+        if wait_ms >= QUEUE_WAIT_LOG_THRESHOLD_MS {
+            crate::log_info!(
+                "sarah.inference",
+                "{} ({}) waited {}ms for the inference slot",
+                label,
+                priority.as_str(),
+                wait_ms
+            );
+            if let Some(analytics) = self.analytics.clone() {
+                let metadata = serde_json::json!({
+                    "label": label,
+                    "priority": priority.as_str(),
+                })
+                .to_string();
+                tokio::spawn(async move {
+                    let _ = analytics
+                        .log_event("inference_queue_wait", wait_ms, true, Some(metadata))
+                        .await;
+                });
+            }
+        }
+        permit
+    }
+
+    /// Lets the service remember the GPU offload that worked for each model file.
+    pub fn with_model_repo(mut self, model_repo: ModelRepo) -> Self {
+        self.model_repo = Some(model_repo);
+        self
+    }
+
+    /// Lets freshly loaded models pick up their active LoRA adapters.
+    pub fn with_adapter_repo(mut self, adapter_repo: AdapterRepo) -> Self {
+        self.adapter_repo = Some(adapter_repo);
+        self
+    }
+
+    /// Applies a LoRA adapter to the resident model at `model_path`, replacing
+    /// the adapter's previous scale if it was already applied.
+    pub fn apply_lora_adapter(
+        &self,
+        model_path: &str,
+        adapter_path: &str,
+        scale: f32,
+    ) -> Result<(), AppError> {
+        let mut pool = self
+            .loaded
+            .lock()
+            .map_err(|_| AppError::Inference("Model lock poisoned".to_string()))?;
+        let loaded = pool.models.get_mut(model_path).ok_or_else(|| {
+            AppError::Inference(format!("Model is not loaded: {model_path}"))
+        })?;
+        loaded.apply_adapter(adapter_path, scale)
+    }
+
+    /// Detaches a LoRA adapter. Returns false when it wasn't applied.
+    pub fn remove_lora_adapter(&self, model_path: &str, adapter_path: &str) -> bool {
+        self.loaded
+            .lock()
+            .ok()
+            .and_then(|mut pool| {
+                pool.models
+                    .get_mut(model_path)
+                    .map(|loaded| loaded.remove_adapter(adapter_path))
+            })
+            .unwrap_or(false)
+    }
+
+    pub fn is_resident(&self, model_path: &str) -> bool {
+        self.loaded
+            .lock()
+            .map(|pool| pool.models.contains_key(model_path))
+            .unwrap_or(false)
     }
 
     pub async fn is_loaded(&self) -> bool {
-        self.loaded.lock().map(|g| g.is_some()).unwrap_or(false)
+        self.loaded
+            .lock()
+            .map(|pool| pool.active().is_some())
+            .unwrap_or(false)
+    }
+
+    /// Whether the active model can look at image attachments itself.
+    pub fn supports_vision(&self) -> bool {
+        self.loaded
+            .lock()
+            .map(|pool| pool.active().is_some_and(|loaded| loaded.vision.is_some()))
+            .unwrap_or(false)
+    }
+
+    /// Applies new pool limits, evicting least recently used models that no
+    /// longer fit.
+    pub fn configure_pool(&self, limits: ModelPoolLimits) {
+        let Ok(mut pool) = self.loaded.lock() else {
+            return;
+        };
+        pool.limits = Some(limits);
+        for path in pool.evict_until(limits.max_resident_models, limits.ram_budget_mb) {
+            crate::log_info!("sarah.inference", "Evicted {} to fit the model pool limits", path);
+        }
+    }
+
+    pub fn resident_models(&self) -> Vec<ModelInfo> {
+        self.loaded
+            .lock()
+            .map(|pool| pool.models.values().map(|loaded| loaded.info.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Unloads the least recently used resident model, sparing the active one
+    /// while others are loaded. Returns its path.
+    pub fn evict_least_recent_model(&self) -> Option<String> {
+        let mut pool = self.loaded.lock().ok()?;
+        let path = pool.least_recent()?;
+        pool.remove(&path);
+        Some(path)
     }
 
     pub async fn load_model(
@@ -68,59 +501,195 @@ impl InferenceService {
             )));
         }
 
-        let mut n_threads = hardware_profile.cpu_threads.max(1) as usize;
+        let already_resident = self
+            .loaded
+            .lock()
+            .map(|mut pool| pool.activate(model_path))
+            .unwrap_or(false);
+        if already_resident {
+            return Ok(());
+        }
+
+        let throttle = self.thermal_throttle();
+        let full_threads = thread_budget(hardware_profile.cpu_threads, &mode);
+        let n_threads = throttle.threads(full_threads);
         if mode == PerformanceMode::Multitasking {
-            // Brutally strict: max 25% of threads, minimum 1, max 4
-            n_threads = (n_threads / 4).clamp(1, 4);
             crate::log_info!("sarah.inference", "Multitasking mode active. Restricted inference to {} threads.", n_threads);
         }
 
+        // Free memory before loading rather than after.
+        let resident_mb = estimate_resident_mb(model_path);
+        let (backend, in_use_mb) = {
+            let mut pool = self
+                .loaded
+                .lock()
+                .map_err(|_| AppError::Inference("Model lock poisoned".to_string()))?;
+            for path in pool.make_room(resident_mb) {
+                crate::log_info!(
+                    "sarah.inference",
+                    "Evicted {} to make room for {}",
+                    path,
+                    model_path
+                );
+            }
+            (pool.backend()?, pool.resident_mb())
+        };
+
         // Aggressive GPU offloading: Llama 1B takes ~1GB VRAM. 
         // If the user has at least 1024MB of VRAM, offload ALL layers to the GPU.
-        let n_gpu_layers: i32 = if hardware_profile.gpu_vram_mb.unwrap_or(0) >= 1024 {
+        // Apple Silicon shares RAM with the GPU, so Metal gets what fits in its share.
+        let is_metal = hardware_profile.supports_metal > 0
+            && hardware_profile.gpu_backend.as_deref() == Some("metal");
+        // AMD and Intel Arc are only reachable through llama.cpp's Vulkan backend.
+        let is_vulkan = hardware_profile.supports_vulkan > 0
+            && hardware_profile.gpu_backend.as_deref() == Some("vulkan");
+        let vulkan_unavailable = is_vulkan && !cfg!(feature = "vulkan");
+        let n_gpu_layers: i32 = if vulkan_unavailable {
+            0
+        } else if is_metal {
+            let budget_mb = hardware_profile
+                .gpu_vram_mb
+                .filter(|budget| *budget > 0)
+                .unwrap_or_else(|| unified_memory_budget_mb(hardware_profile.total_ram_mb));
+            unified_memory_layers(budget_mb, in_use_mb, resident_mb)
+        } else if hardware_profile.gpu_vram_mb.unwrap_or(0) >= 1024 {
             -1 // -1 tells llama.cpp to offload all layers
         } else {
             0
         };
+        if is_metal {
+            crate::log_info!(
+                "sarah.inference",
+                "Metal backend detected. Offloading {} layers to unified memory.",
+                if n_gpu_layers < 0 { "all".to_string() } else { n_gpu_layers.to_string() }
+            );
+        }
+        if vulkan_unavailable {
+            crate::log_warn!(
+                "sarah.inference",
+                "{} GPU detected, but this build has no Vulkan backend. Running on the CPU.",
+                hardware_profile.gpu_vendor.as_deref().unwrap_or("unknown")
+            );
+        } else if is_vulkan && n_gpu_layers != 0 {
+            crate::log_info!("sarah.inference", "Vulkan backend detected. Offloading all layers to the GPU.");
+        }
+
+        // A previous load on this GPU may have found that only part of the model fits.
+        let vram_mb = hardware_profile.gpu_vram_mb.unwrap_or(0);
+        let mut preferred_layers = n_gpu_layers;
+        if n_gpu_layers != 0 {
+            if let Some(repo) = self.model_repo.as_ref() {
+                if let Ok(Some((layers, recorded_vram_mb))) = repo.get_gpu_offload(model_path).await {
+                    if recorded_vram_mb == vram_mb {
+                        // A smaller share left by other resident models still applies.
+                        preferred_layers = if n_gpu_layers < 0 {
+                            layers as i32
+                        } else {
+                            n_gpu_layers.min(layers as i32)
+                        };
+                    }
+                }
+            }
+        }
+        let throttled_layers = throttle.gpu_layers(preferred_layers);
+        if throttled_layers != preferred_layers {
+            crate::log_warn!(
+                "sarah.inference",
+                "GPU above its thermal limit. Offloading {} layers instead of {}.",
+                throttled_layers,
+                if preferred_layers < 0 { "all".to_string() } else { preferred_layers.to_string() }
+            );
+            preferred_layers = throttled_layers;
+        }
 
         let model_path_owned = model_path.to_string();
 
-        let loaded = tokio::task::spawn_blocking(move || -> Result<LoadedModel, AppError> {
-            let backend = LlamaBackend::init()
-                .map_err(|e| AppError::Inference(format!("Failed to init llama backend: {e}")))?;
-
-            let mut model_params = LlamaModelParams::default();
-            if n_gpu_layers > 0 {
-                model_params = model_params.with_n_gpu_layers(n_gpu_layers as u32);
-            } else if n_gpu_layers < 0 {
-                model_params = model_params.with_n_gpu_layers(1000);
-            }
+        let active_adapters = match self.adapter_repo.as_ref() {
+            Some(repo) => repo.active_for_model_path(model_path).await.unwrap_or_default(),
+            None => Vec::new(),
+        };
+        let projector_path = match self.model_repo.as_ref() {
+            Some(repo) => repo.get_vision_projector(model_path).await.unwrap_or_default(),
+            None => None,
+        };
 
-            let model = LlamaModel::load_from_file(&backend, &model_path_owned, &model_params)
-                .map_err(|e| AppError::Inference(format!("Failed to load GGUF model: {e}")))?;
+        let loaded = tokio::task::spawn_blocking(move || -> Result<LoadedModel, AppError> {
+            let (model, n_gpu_layers) =
+                load_with_gpu_fallback(&backend, &model_path_owned, preferred_layers)?;
 
             let context_length = model.n_ctx_train() as usize;
+            let chat_template = ChatTemplate::detect(
+                model.meta_val_str("tokenizer.chat_template").ok().as_deref(),
+                model.meta_val_str("general.architecture").ok().as_deref(),
+                &model_path_owned,
+            );
+            crate::log_info!(
+                "sarah.inference",
+                "Using the {} chat template for {}",
+                chat_template.as_str(),
+                model_path_owned
+            );
+            // Without its projector a vision model still works for text.
+            let vision = projector_path.and_then(|path| {
+                match load_projector(&model, &path, n_gpu_layers != 0, n_threads) {
+                    Ok(projector) => Some(projector),
+                    Err(error) => {
+                        crate::log_warn!("sarah.inference", "{}", error);
+                        None
+                    }
+                }
+            });
+            let vision_projector = vision.as_ref().map(|projector| projector.path.clone());
             Ok(LoadedModel {
+                prompt_cache: PromptCache::default(),
+                adapters: Vec::new(),
+                vision,
                 backend,
-                model,
+                model: Box::new(model),
                 info: ModelInfo {
                     path: model_path_owned,
                     context_length,
                     n_gpu_layers,
                     n_threads,
+                    chat_template,
+                    resident_mb,
+                    vision_projector,
                 },
+                full_threads,
                 seed: 1234,
-                last_used_secs: Arc::new(AtomicU64::new(SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs())),
+                last_used_secs: Arc::new(AtomicU64::new(now_secs())),
+            })
+            .map(|mut loaded| {
+                // A missing or incompatible adapter shouldn't keep the model from loading.
+                for adapter in &active_adapters {
+                    let scale = adapter.scale as f32;
+                    if let Err(error) = loaded.apply_adapter(&adapter.file_path, scale) {
+                        crate::log_warn!("sarah.inference", "{}", error);
+                    }
+                }
+                loaded
             })
         })
         .await
         .map_err(|e| AppError::Inference(e.to_string()))??;
 
-        let mut guard = self
+        if loaded.info.n_gpu_layers != preferred_layers {
+            if let Some(repo) = self.model_repo.as_ref() {
+                if let Err(error) = repo
+                    .record_gpu_offload(model_path, loaded.info.n_gpu_layers as i64, vram_mb)
+                    .await
+                {
+                    crate::log_warn!("sarah.inference", "Failed to record GPU offload for {}: {}", model_path, error);
+                }
+            }
+        }
+
+        let mut pool = self
             .loaded
             .lock()
             .map_err(|_| AppError::Inference("Model lock poisoned".to_string()))?;
-        *guard = Some(loaded);
+        pool.insert(loaded);
+        drop(pool);
 
         if mode == PerformanceMode::Multitasking {
             self.start_auto_unloader();
@@ -134,18 +703,27 @@ impl InferenceService {
         tokio::spawn(async move {
             loop {
                 tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
-                let mut guard = if let Ok(g) = loaded_ref.lock() { g } else { return; };
-                
-                if let Some(loaded) = guard.as_ref() {
-                    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
-                    let last_used = loaded.last_used_secs.load(Ordering::Relaxed);
-                    // 5 minutes (300 seconds) idle timeout
-                    if now.saturating_sub(last_used) > 300 {
-                        crate::log_info!("sarah.inference", "Model idle for 5+ minutes in Multitasking mode. Auto-unloading from memory.");
-                        *guard = None; // Drops LlamaModel, freeing RAM/VRAM
-                        break;
-                    }
-                } else {
+                let mut pool = if let Ok(g) = loaded_ref.lock() { g } else { return; };
+
+                let now = now_secs();
+                // 5 minutes (300 seconds) idle timeout
+                let idle = pool
+                    .models
+                    .iter()
+                    .filter(|(_, loaded)| {
+                        now.saturating_sub(loaded.last_used_secs.load(Ordering::Relaxed)) > 300
+                    })
+                    .map(|(path, _)| path.clone())
+                    .collect::<Vec<_>>();
+                for path in idle {
+                    crate::log_info!(
+                        "sarah.inference",
+                        "Model {} idle for 5+ minutes in Multitasking mode. Auto-unloading from memory.",
+                        path
+                    );
+                    pool.remove(&path); // Drops LlamaModel, freeing RAM/VRAM
+                }
+                if pool.models.is_empty() {
                     break;
                 }
             }
@@ -157,15 +735,14 @@ impl InferenceService {
         session_id: &str,
         messages: Vec<Message>,
         opts: GenerationOptions,
-        app_handle: Option<tauri::AppHandle>,
     ) -> Result<ReceiverStream<MessageStreamChunk>, AppError> {
         {
-            let guard = self
+            let pool = self
                 .loaded
                 .lock()
                 .map_err(|_| AppError::Inference("Model lock poisoned".to_string()))?;
-            if let Some(loaded) = guard.as_ref() {
-                loaded.last_used_secs.store(SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(), Ordering::Relaxed);
+            if let Some(loaded) = pool.active() {
+                loaded.touch();
             } else {
                 return Err(AppError::Inference(
                     "No active model loaded. Register a local GGUF model first.".to_string(),
@@ -173,16 +750,27 @@ impl InferenceService {
             }
         }
 
+        // Registered before queueing so a stop request also covers a turn still waiting for a slot.
+        let cancel = self.register_cancellation(session_id);
         let permit = self
-            .limiter
-            .clone()
-            .acquire_owned()
-            .await
-            .map_err(|e| AppError::Inference(e.to_string()))?;
+            .acquire_slot(&format!("chat:{session_id}"), InferencePriority::Interactive)
+            .await;
 
-        let prompt = Self::build_prompt(&messages);
+        // Images only reach a model with a projector; for the others the caller
+        // folds their recognized text into the messages instead.
+        let images = if self.supports_vision() {
+            messages.iter().flat_map(Message::image_paths).collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+        let prompt = if images.is_empty() {
+            self.chat_template().render(&messages)
+        } else {
+            self.chat_template().render(&with_media_markers(messages))
+        };
         let session_id_owned = session_id.to_string();
         let loaded = self.loaded.clone();
+        let cancellations = self.cancellations.clone();
 
         let (tx, rx) = mpsc::channel::<MessageStreamChunk>(256);
 
@@ -190,48 +778,82 @@ impl InferenceService {
             let _permit_guard = permit;
 
             let generation = (|| -> Result<GenerationResult, AppError> {
-                let mut guard = loaded
+                if cancel.load(Ordering::SeqCst) {
+                    return Ok(GenerationResult {
+                        text: String::new(),
+                        tokens_generated: 0,
+                        finish_reason: "cancelled".to_string(),
+                        first_token_ms: None,
+                    });
+                }
+
+                let mut pool = loaded
                     .lock()
                     .map_err(|_| AppError::Inference("Model lock poisoned".to_string()))?;
-                let loaded = guard.as_mut().ok_or_else(|| {
-                    AppError::Inference("No model loaded for generation".to_string())
-                })?;
-
-                Self::generate_with_llama(loaded, &prompt, &opts, |piece| {
-                    if let Some(app) = app_handle.as_ref() {
-                        let _ = app.emit(
-                            "inference:token",
-                            MessageStreamChunk {
-                                session_id: session_id_owned.clone(),
-                                token: piece.to_string(),
-                                done: false,
-                            },
-                        );
-                    }
+                let loaded = pool.for_generation()?;
 
+                let on_token = |piece: &str| -> Result<(), AppError> {
                     tx.blocking_send(MessageStreamChunk {
                         session_id: session_id_owned.clone(),
                         token: piece.to_string(),
                         done: false,
+                        finish_reason: None,
+                        citations: Vec::new(),
                     })
                     .map_err(|e| AppError::Inference(e.to_string()))?;
 
                     Ok(())
-                })
+                };
+                if images.is_empty() {
+                    Self::generate_with_llama(
+                        loaded,
+                        Some(&session_id_owned),
+                        &prompt,
+                        &opts,
+                        Some(&cancel),
+                        on_token,
+                    )
+                } else {
+                    Self::generate_with_vision(
+                        loaded,
+                        &prompt,
+                        &images,
+                        &opts,
+                        Some(&cancel),
+                        on_token,
+                    )
+                }
             })();
 
-            if let Err(error) = generation {
-                let _ = tx.blocking_send(MessageStreamChunk {
-                    session_id: session_id_owned.clone(),
-                    token: format!("[inference error] {error}"),
-                    done: false,
-                });
+            if let Ok(mut cancellations) = cancellations.lock() {
+                if cancellations
+                    .get(&session_id_owned)
+                    .is_some_and(|flag| Arc::ptr_eq(flag, &cancel))
+                {
+                    cancellations.remove(&session_id_owned);
+                }
             }
 
+            let finish_reason = match generation {
+                Ok(result) => result.finish_reason,
+                Err(error) => {
+                    let _ = tx.blocking_send(MessageStreamChunk {
+                        session_id: session_id_owned.clone(),
+                        token: format!("[inference error] {error}"),
+                        done: false,
+                        finish_reason: None,
+                        citations: Vec::new(),
+                    });
+                    "error".to_string()
+                }
+            };
+
             let _ = tx.blocking_send(MessageStreamChunk {
                 session_id: session_id_owned,
                 token: String::new(),
                 done: true,
+                finish_reason: Some(finish_reason),
+                citations: Vec::new(),
             });
         });
 
@@ -243,40 +865,123 @@ impl InferenceService {
         messages: Vec<Message>,
         tool_schemas: &[String],
     ) -> Result<GenerationResult, AppError> {
-        let _permit = self
-            .limiter
-            .clone()
-            .acquire_owned()
+        self.generate_with_options(messages, tool_schemas, GenerationOptions::default())
             .await
-            .map_err(|e| AppError::Inference(e.to_string()))?;
+    }
 
-        let prompt = if tool_schemas.is_empty() {
-            Self::build_prompt(&messages)
-        } else {
-            format!(
-                "{}\n\nAvailable tools:\n{}",
-                Self::build_prompt(&messages),
-                tool_schemas.join("\n")
-            )
-        };
+    pub async fn generate_with_options(
+        &self,
+        messages: Vec<Message>,
+        tool_schemas: &[String],
+        opts: GenerationOptions,
+    ) -> Result<GenerationResult, AppError> {
+        let _permit = self
+            .acquire_slot("generate", InferencePriority::Interactive)
+            .await;
+
+        let prompt = Self::build_tool_prompt(self.chat_template(), messages, tool_schemas);
 
         let loaded = self.loaded.clone();
-        let opts = GenerationOptions::default();
 
         tokio::task::spawn_blocking(move || {
-            let mut guard = loaded
+            let mut pool = loaded
                 .lock()
                 .map_err(|_| AppError::Inference("Model lock poisoned".to_string()))?;
-            let loaded = guard
-                .as_mut()
-                .ok_or_else(|| AppError::Inference("No active model loaded".to_string()))?;
+            let loaded = pool.for_generation()?;
 
-            Self::generate_with_llama(loaded, &prompt, &opts, |_| Ok(()))
+            Self::generate_with_llama(loaded, None, &prompt, &opts, None, |_| Ok(()))
         })
         .await
         .map_err(|e| AppError::Inference(e.to_string()))?
     }
 
+    /// Generates a JSON value that conforms to `json_schema`. Sampling is
+    /// constrained by a grammar built from the schema, so even small models
+    /// cannot emit malformed output; the result is still parsed as a check.
+    pub async fn generate_structured(
+        &self,
+        prompt: &str,
+        json_schema: &serde_json::Value,
+    ) -> Result<serde_json::Value, AppError> {
+        let grammar = schema_to_grammar(json_schema)?;
+        let messages = vec![
+            prompt_message("system", format!("{STRUCTURED_INSTRUCTIONS}\n{json_schema}")),
+            prompt_message("user", prompt.to_string()),
+        ];
+        let opts = GenerationOptions {
+            temperature: 0.0,
+            max_tokens: 1024,
+            grammar: Some(grammar),
+            ..GenerationOptions::default()
+        };
+
+        let generated = self.generate_with_options(messages, &[], opts).await?;
+        if generated.finish_reason == "length" {
+            return Err(AppError::Inference(
+                "Structured output was cut off before the JSON was complete".to_string(),
+            ));
+        }
+        serde_json::from_str(generated.text.trim()).map_err(|e| {
+            AppError::Inference(format!("Structured output was not valid JSON: {e}"))
+        })
+    }
+
+    /// Runs a low-priority generation that steps aside between tokens whenever an
+    /// interactive request is queued, then re-queues and starts over.
+    pub async fn generate_background(
+        &self,
+        label: &str,
+        messages: Vec<Message>,
+        opts: GenerationOptions,
+    ) -> Result<GenerationResult, AppError> {
+        let prompt = self.chat_template().render(&messages);
+
+        for attempt in 0..=MAX_BACKGROUND_PREEMPTIONS {
+            let permit = self.acquire_slot(label, InferencePriority::Background).await;
+            let preemptible = attempt < MAX_BACKGROUND_PREEMPTIONS;
+            let preempted = Arc::new(AtomicBool::new(false));
+
+            let loaded = self.loaded.clone();
+            let prompt = prompt.clone();
+            let opts = opts.clone();
+            let preempted_flag = Arc::clone(&preempted);
+
+            let result = tokio::task::spawn_blocking(move || {
+                let mut pool = loaded
+                    .lock()
+                    .map_err(|_| AppError::Inference("Model lock poisoned".to_string()))?;
+                let loaded = pool.for_generation()?;
+
+                Self::generate_with_llama(loaded, None, &prompt, &opts, None, |_| {
+                    if preemptible && permit.should_yield() {
+                        permit.record_preemption();
+                        preempted_flag.store(true, Ordering::Relaxed);
+                        return Err(AppError::Inference(
+                            "Preempted by an interactive request".to_string(),
+                        ));
+                    }
+                    Ok(())
+                })
+            })
+            .await
+            .map_err(|e| AppError::Inference(e.to_string()))?;
+
+            if !preempted.load(Ordering::Relaxed) {
+                return result;
+            }
+            crate::log_info!(
+                "sarah.inference",
+                "{} yielded to an interactive request (attempt {})",
+                label,
+                attempt + 1
+            );
+        }
+
+        Err(AppError::Inference(format!(
+            "{label} was preempted too many times"
+        )))
+    }
+
     pub async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>, AppError> {
         let mut vec = vec![0.0f32; 384];
         for (idx, byte) in text.as_bytes().iter().enumerate() {
@@ -293,15 +998,73 @@ impl InferenceService {
         self.loaded
             .lock()
             .ok()
-            .and_then(|guard| guard.as_ref().map(|loaded| loaded.info.clone()))
+            .and_then(|pool| pool.active().map(|loaded| loaded.info.clone()))
+    }
+
+    /// Re-applies the performance mode to the resident models. Threads are read
+    /// when each generation builds its context, so the next request already uses
+    /// them; cached session contexts are dropped so they are rebuilt with the new count.
+    pub fn apply_performance_mode(&self, mode: &PerformanceMode, cpu_threads: i64) {
+        let Ok(mut pool) = self.loaded.lock() else {
+            return;
+        };
+        let full_threads = thread_budget(cpu_threads, mode);
+        let n_threads = self.thermal_throttle().threads(full_threads);
+        for loaded in pool.models.values_mut() {
+            loaded.full_threads = full_threads;
+            if loaded.info.n_threads != n_threads {
+                crate::log_info!(
+                    "sarah.inference",
+                    "Inference threads {} -> {} for {:?} mode",
+                    loaded.info.n_threads,
+                    n_threads,
+                    mode
+                );
+                loaded.info.n_threads = n_threads;
+                loaded.prompt_cache = PromptCache::default();
+            }
+        }
+    }
+
+    /// Applies the runtime governor's thermal limits. Resident models switch
+    /// thread counts right away; GPU layers only drop on the next load.
+    pub fn set_thermal_throttle(&self, throttle: ThermalThrottle) {
+        match self.thermal.lock() {
+            Ok(mut current) => *current = throttle,
+            Err(_) => return,
+        }
+        let Ok(mut pool) = self.loaded.lock() else {
+            return;
+        };
+        for loaded in pool.models.values_mut() {
+            let n_threads = throttle.threads(loaded.full_threads);
+            if loaded.info.n_threads != n_threads {
+                crate::log_info!(
+                    "sarah.inference",
+                    "Inference threads {} -> {} for thermal throttling",
+                    loaded.info.n_threads,
+                    n_threads
+                );
+                loaded.info.n_threads = n_threads;
+                loaded.prompt_cache = PromptCache::default();
+            }
+        }
+    }
+
+    fn thermal_throttle(&self) -> ThermalThrottle {
+        self.thermal
+            .lock()
+            .map(|throttle| *throttle)
+            .unwrap_or_default()
     }
 
     pub async fn unload_model(&self) -> Result<(), AppError> {
-        let mut guard = self
+        let mut pool = self
             .loaded
             .lock()
             .map_err(|_| AppError::Inference("Model lock poisoned".to_string()))?;
-        *guard = None;
+        pool.models.clear();
+        pool.active = None;
         Ok(())
     }
 
@@ -314,33 +1077,65 @@ impl InferenceService {
         }
     }
 
-    fn build_prompt(messages: &[Message]) -> String {
-        let mut prompt = String::new();
-        prompt.push_str("<|begin_of_text|>");
+    /// KV-cache reuse per session for the loaded model, most recent first.
+    pub fn session_cache_stats(&self) -> Vec<SessionCacheStats> {
+        self.loaded
+            .lock()
+            .ok()
+            .and_then(|pool| pool.active().map(|loaded| loaded.prompt_cache.stats()))
+            .unwrap_or_default()
+    }
 
-        for message in messages {
-            let role = match message.role.as_str() {
-                "user" => "user",
-                "assistant" => "assistant",
-                "system" => "system",
-                _ => "user",
-            };
+    /// Length of `text` in tokens of the active model. `None` when no model is
+    /// loaded or a generation holds it, so callers estimate instead of waiting.
+    pub fn count_tokens(&self, text: &str) -> Option<usize> {
+        let pool = self.loaded.try_lock().ok()?;
+        let loaded = pool.active()?;
+        loaded
+            .model
+            .str_to_token(text, AddBos::Never)
+            .ok()
+            .map(|tokens| tokens.len())
+    }
 
-            prompt.push_str(&format!(
-                "<|start_header_id|>{role}<|end_header_id|>\n\n{}<|eot_id|>",
-                message.content.trim()
-            ));
-        }
+    /// Tokens a generation with the active model can hold, prompt and reply
+    /// together. Same fallbacks as [`Self::count_tokens`].
+    pub fn context_window(&self) -> Option<usize> {
+        let pool = self.loaded.try_lock().ok()?;
+        pool.active().map(|loaded| loaded.info.context_length.min(MAX_CONTEXT_TOKENS))
+    }
 
-        // Final header to trigger assistant response
-        prompt.push_str("<|start_header_id|>assistant<|end_header_id|>\n\n");
-        prompt
+    /// Layout of the loaded model; Llama 3 when nothing is loaded yet.
+    fn chat_template(&self) -> ChatTemplate {
+        self.loaded
+            .lock()
+            .ok()
+            .and_then(|pool| pool.active().map(|loaded| loaded.info.chat_template))
+            .unwrap_or(ChatTemplate::Llama3)
+    }
+
+    /// Renders `messages` with the tool-calling instructions and schemas as the
+    /// leading system turn.
+    fn build_tool_prompt(
+        template: ChatTemplate,
+        mut messages: Vec<Message>,
+        tool_schemas: &[String],
+    ) -> String {
+        if !tool_schemas.is_empty() {
+            let tools = format!("{TOOL_INSTRUCTIONS}\n{}", tool_schemas.join("\n"));
+            messages.insert(0, prompt_message("system", tools));
+        }
+        template.render(&messages)
     }
 
+    /// With a `cache_key` the session's cached context is reused and only the
+    /// part of the prompt that differs from the last turn is decoded.
     fn generate_with_llama(
         loaded: &mut LoadedModel,
+        cache_key: Option<&str>,
         prompt: &str,
         opts: &GenerationOptions,
+        cancel: Option<&AtomicBool>,
         mut on_token: impl FnMut(&str) -> Result<(), AppError>,
     ) -> Result<GenerationResult, AppError> {
         let prompt_tokens = loaded
@@ -358,9 +1153,24 @@ impl InferenceService {
         // Llama 3.2 defaults to 131,072 which would instantly consume 4.1GB of RAM for the blank KV Cache!
         let required_ctx = prompt_tokens.len() + opts.max_tokens;
         // Clamp dynamically to at least 1024, at most 8192 to heavily protect system RAM from overflowing 
-        let safe_ctx_len = (required_ctx as u32).max(1024).min(8192).min(loaded.info.context_length as u32);
+        let max_ctx = loaded.info.context_length as u32;
+        let clamp_ctx =
+            |len: usize| (len as u32).max(1024).min(MAX_CONTEXT_TOKENS as u32).min(max_ctx);
+        let safe_ctx_len = clamp_ctx(required_ctx);
 
-        let n_ctx = NonZeroU32::new(safe_ctx_len)
+        if required_ctx > safe_ctx_len as usize {
+            return Err(AppError::Inference(format!(
+                "Context overflow: prompt + max_tokens is {} but we clamped context to {} to prevent RAM exhaustion. Please send a shorter message.",
+                required_ctx, safe_ctx_len
+            )));
+        }
+
+        // Cached contexts get headroom so the next few turns still fit.
+        let ctx_len = match cache_key {
+            Some(_) => clamp_ctx(required_ctx + CACHE_HEADROOM_TOKENS),
+            None => safe_ctx_len,
+        };
+        let n_ctx = NonZeroU32::new(ctx_len)
             .ok_or_else(|| AppError::Inference("Invalid context window size computed".to_string()))?;
 
         // Enforce the hardware-profile driven CPU thread limits (e.g., 20-30% in multitasking)
@@ -371,26 +1181,171 @@ impl InferenceService {
             .with_n_ctx(Some(n_ctx))
             .with_n_threads(safe_threads)
             .with_n_threads_batch(safe_threads);
-        
+
+        let Some(session_id) = cache_key else {
+            let mut ctx = loaded
+                .model
+                .new_context(&loaded.backend, ctx_params)
+                .map_err(|e| AppError::Inference(format!("Failed to create llama context: {e}")))?;
+            attach_adapters(&mut ctx, &mut loaded.adapters)?;
+            return Self::decode_and_sample(
+                &mut ctx,
+                &loaded.model,
+                loaded.seed,
+                prompt_tokens,
+                0,
+                opts,
+                cancel,
+                &mut on_token,
+            )
+            .map(|(result, _)| result);
+        };
+
+        let LoadedModel {
+            prompt_cache,
+            adapters,
+            model,
+            backend,
+            seed,
+            ..
+        } = loaded;
+        // SAFETY: the cache is a field of the same `LoadedModel` as the boxed
+        // model and is declared first, so it is dropped before the model.
+        let (ctx, reused) = unsafe {
+            prompt_cache.checkout(
+                session_id,
+                model,
+                backend,
+                ctx_params,
+                required_ctx,
+                &prompt_tokens,
+            )?
+        };
+        // Setting the same adapters again is a no-op for a reused context.
+        if let Err(error) = attach_adapters(ctx, adapters) {
+            prompt_cache.invalidate(session_id);
+            return Err(error);
+        }
+        let prompt_len = prompt_tokens.len();
+        match Self::decode_and_sample(
+            ctx,
+            model,
+            *seed,
+            prompt_tokens,
+            reused,
+            opts,
+            cancel,
+            &mut on_token,
+        ) {
+            Ok((result, evaluated)) => {
+                prompt_cache.commit(session_id, evaluated, prompt_len, reused);
+                Ok(result)
+            }
+            Err(error) => {
+                prompt_cache.invalidate(session_id);
+                Err(error)
+            }
+        }
+    }
+
+    /// Runs a prompt containing one media marker per image through the model's
+    /// projector. Image embeddings aren't cached, so this always starts from a
+    /// fresh context.
+    fn generate_with_vision(
+        loaded: &mut LoadedModel,
+        prompt: &str,
+        images: &[String],
+        opts: &GenerationOptions,
+        cancel: Option<&AtomicBool>,
+        mut on_token: impl FnMut(&str) -> Result<(), AppError>,
+    ) -> Result<GenerationResult, AppError> {
+        let started = Instant::now();
+        let projector = loaded.vision.as_ref().ok_or_else(|| {
+            AppError::Inference("The active model has no vision projector".to_string())
+        })?;
+        let bitmaps = images
+            .iter()
+            .map(|path| {
+                MtmdBitmap::from_file(&projector.ctx, path)
+                    .map_err(|e| AppError::Inference(format!("Failed to read image {path}: {e}")))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let chunks = projector
+            .ctx
+            .tokenize(
+                MtmdInputText {
+                    text: prompt.to_string(),
+                    add_special: true,
+                    parse_special: true,
+                },
+                &bitmaps.iter().collect::<Vec<_>>(),
+            )
+            .map_err(|e| AppError::Inference(format!("Multimodal tokenization failed: {e}")))?;
+
+        let required_ctx = chunks.total_tokens() + opts.max_tokens;
+        let ctx_len = (required_ctx as u32)
+            .max(1024)
+            .min(MAX_CONTEXT_TOKENS as u32)
+            .min(loaded.info.context_length as u32);
+        if required_ctx > ctx_len as usize {
+            return Err(AppError::Inference(format!(
+                "Context overflow: the images and prompt need {} tokens but the context is limited to {}. Attach fewer or smaller images.",
+                required_ctx, ctx_len
+            )));
+        }
+        let n_ctx = NonZeroU32::new(ctx_len)
+            .ok_or_else(|| AppError::Inference("Invalid context window size computed".to_string()))?;
+        let safe_threads = loaded.info.n_threads.max(1) as i32;
+        let ctx_params = LlamaContextParams::default()
+            .with_n_ctx(Some(n_ctx))
+            .with_n_threads(safe_threads)
+            .with_n_threads_batch(safe_threads);
+
         let mut ctx = loaded
             .model
             .new_context(&loaded.backend, ctx_params)
             .map_err(|e| AppError::Inference(format!("Failed to create llama context: {e}")))?;
+        attach_adapters(&mut ctx, &mut loaded.adapters)?;
+        let n_past = chunks
+            .eval_chunks(&projector.ctx, &ctx, 0, 0, VISION_BATCH_SIZE, true)
+            .map_err(|e| AppError::Inference(format!("Image evaluation failed: {e}")))?;
 
-        let n_len = prompt_tokens.len() + opts.max_tokens;
-        let n_ctx_i32 = ctx.n_ctx() as usize;
-        if n_len > n_ctx_i32 {
-            return Err(AppError::Inference(format!(
-                "Context overflow: prompt + max_tokens is {} but we clamped context to {} to prevent RAM exhaustion. Please send a shorter message.",
-                n_len, n_ctx_i32
-            )));
-        }
+        Self::sample(
+            &mut ctx,
+            &loaded.model,
+            loaded.seed,
+            Vec::new(),
+            n_past,
+            opts,
+            cancel,
+            &mut on_token,
+            started,
+        )
+        .map(|(result, _)| result)
+    }
+
+    /// Decodes `tokens[start..]` on top of a context that already holds
+    /// `tokens[..start]`, then samples. Also returns every token now in the KV
+    /// cache: the prompt followed by the generated tokens.
+    #[allow(clippy::too_many_arguments)]
+    fn decode_and_sample(
+        ctx: &mut LlamaContext<'_>,
+        model: &LlamaModel,
+        seed: u32,
+        tokens: Vec<LlamaToken>,
+        start: usize,
+        opts: &GenerationOptions,
+        cancel: Option<&AtomicBool>,
+        on_token: &mut impl FnMut(&str) -> Result<(), AppError>,
+    ) -> Result<(GenerationResult, Vec<LlamaToken>), AppError> {
+        let started = Instant::now();
 
         // Increase batch size to avoid "Insufficient Space" errors on long prompts
-        let batch_size = (prompt_tokens.len() + 128).max(1024).min(4096);
+        let pending = &tokens[start..];
+        let batch_size = (pending.len() + 128).max(1024).min(4096);
         let mut batch = LlamaBatch::new(batch_size, 1);
-        let last_index = (prompt_tokens.len() - 1) as i32;
-        for (idx, token) in (0_i32..).zip(prompt_tokens.into_iter()) {
+        let last_index = (tokens.len() - 1) as i32;
+        for (idx, token) in (start as i32..).zip(pending.iter().copied()) {
             let is_last = idx == last_index;
             batch
                 .add(token, idx, &[0], is_last)
@@ -400,34 +1355,75 @@ impl InferenceService {
         ctx.decode(&mut batch)
             .map_err(|e| AppError::Inference(format!("Initial decode failed: {e}")))?;
 
-        let mut sampler = if opts.temperature <= 0.0 {
-            LlamaSampler::chain_simple([LlamaSampler::greedy()])
+        let n_past = tokens.len() as i32;
+        Self::sample(ctx, model, seed, tokens, n_past, opts, cancel, on_token, started)
+    }
+
+    /// Samples on top of a context whose last decoded position holds the
+    /// prompt's logits, with `n_past` positions already filled. Generated
+    /// tokens are appended to `tokens`, which is returned with the result.
+    #[allow(clippy::too_many_arguments)]
+    fn sample(
+        ctx: &mut LlamaContext<'_>,
+        model: &LlamaModel,
+        seed: u32,
+        mut tokens: Vec<LlamaToken>,
+        n_past: i32,
+        opts: &GenerationOptions,
+        cancel: Option<&AtomicBool>,
+        on_token: &mut impl FnMut(&str) -> Result<(), AppError>,
+        started: Instant,
+    ) -> Result<(GenerationResult, Vec<LlamaToken>), AppError> {
+        let mut first_token_ms = None;
+
+        // The grammar goes first so the remaining samplers only see tokens it allows.
+        let mut samplers = Vec::new();
+        if let Some(grammar) = opts.grammar.as_deref() {
+            samplers.push(
+                LlamaSampler::grammar(model, grammar, "root")
+                    .map_err(|e| AppError::Inference(format!("Invalid grammar: {e}")))?,
+            );
+        }
+        if opts.temperature <= 0.0 {
+            samplers.push(LlamaSampler::greedy());
         } else {
-            LlamaSampler::chain_simple([
+            samplers.extend([
+                LlamaSampler::top_k(opts.top_k),
+                LlamaSampler::top_p(opts.top_p, 1),
                 LlamaSampler::temp(opts.temperature),
-                LlamaSampler::dist(loaded.seed),
-                LlamaSampler::greedy(),
-            ])
-        };
+                LlamaSampler::dist(opts.seed.unwrap_or(seed)),
+            ]);
+        }
+        let mut sampler = LlamaSampler::chain_simple(samplers);
 
         let mut generated = String::new();
         let mut decoder = UTF_8.new_decoder();
-        let mut n_cur = batch.n_tokens();
+        // Empty at first, so the first sample reads index -1: the prompt's last logits.
+        let mut batch = LlamaBatch::new(1, 1);
+        let mut n_cur = n_past;
         let mut n_decode = 0usize;
+        let mut cancelled = false;
 
         while n_decode < opts.max_tokens {
-            let token = sampler.sample(&ctx, batch.n_tokens() - 1);
+            if cancel.is_some_and(|flag| flag.load(Ordering::SeqCst)) {
+                cancelled = true;
+                break;
+            }
+
+            let token = sampler.sample(ctx, batch.n_tokens() - 1);
             sampler.accept(token);
 
-            if loaded.model.is_eog_token(token) {
+            if model.is_eog_token(token) {
                 break;
             }
 
-            let piece = loaded
-                .model
+            let piece = model
                 .token_to_piece(token, &mut decoder, true, None)
                 .map_err(|e| AppError::Inference(format!("Token decode failed: {e}")))?;
 
+            if first_token_ms.is_none() {
+                first_token_ms = Some(started.elapsed().as_millis() as i64);
+            }
             on_token(&piece)?;
             generated.push_str(&piece);
 
@@ -441,16 +1437,327 @@ impl InferenceService {
 
             ctx.decode(&mut batch)
                 .map_err(|e| AppError::Inference(format!("Decode failed: {e}")))?;
+            tokens.push(token);
         }
 
-        Ok(GenerationResult {
+        let result = GenerationResult {
             text: generated,
             tokens_generated: n_decode,
-            finish_reason: if n_decode >= opts.max_tokens {
+            finish_reason: if cancelled {
+                "cancelled".to_string()
+            } else if n_decode >= opts.max_tokens {
                 "length".to_string()
             } else {
                 "stop".to_string()
             },
-        })
+            first_token_ms,
+        };
+        Ok((result, tokens))
+    }
+}
+
+/// A prompt-only message for generations that are not part of a session.
+pub(crate) fn prompt_message(role: &str, content: String) -> Message {
+    let now = chrono::Utc::now().to_rfc3339();
+    Message {
+        id: uuid::Uuid::new_v4().to_string(),
+        session_id: String::new(),
+        role: role.to_string(),
+        content,
+        content_type: "text".to_string(),
+        thinking: None,
+        token_count: None,
+        model_id: None,
+        latency_ms: None,
+        tokens_per_sec: None,
+        finish_reason: None,
+        is_error: 0,
+        error_message: None,
+        parent_message_id: None,
+        is_active_variant: 1,
+        edited_at: None,
+        original_content: None,
+        metadata: "{}".to_string(),
+        attachments: "[]".to_string(),
+        position: 0,
+        created_at: now.clone(),
+        updated_at: now,
     }
 }
+
+/// Extracts tool calls from model output. Understands `<tool_call>{...}</tool_call>`
+/// tags, Llama-style `<function=name>{...}</function>` tags, and a reply that is
+/// nothing but JSON (optionally fenced) shaped like `{"name": ..., "arguments": ...}`,
+/// an array of those, or `{"tool_calls": [...]}`.
+pub fn parse_tool_calls(text: &str) -> Vec<ParsedToolCall> {
+    let mut calls = Vec::new();
+
+    for body in tagged_sections(text, "<tool_call>", "</tool_call>") {
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(body.trim()) {
+            collect_tool_calls(&value, &mut calls);
+        }
+    }
+    for body in tagged_sections(text, "<function=", "</function>") {
+        let Some((name, arguments)) = body.split_once('>') else {
+            continue;
+        };
+        let arguments = arguments.trim();
+        calls.push(ParsedToolCall {
+            name: name.trim().to_string(),
+            arguments: if arguments.is_empty() {
+                serde_json::json!({})
+            } else {
+                serde_json::from_str(arguments).unwrap_or_else(|_| serde_json::json!({}))
+            },
+        });
+    }
+    if !calls.is_empty() {
+        return calls;
+    }
+
+    let trimmed = text.trim();
+    let unfenced = match trimmed.strip_prefix("```") {
+        Some(fenced) => fenced
+            .split_once('\n')
+            .map(|(_, body)| body)
+            .unwrap_or("")
+            .trim_end()
+            .trim_end_matches("```"),
+        None => trimmed,
+    };
+    if let Ok(value) = serde_json::from_str::<serde_json::Value>(unfenced.trim()) {
+        collect_tool_calls(&value, &mut calls);
+    }
+    calls
+}
+
+/// Bodies between `open` and `close`; an unclosed final tag runs to the end.
+fn tagged_sections<'a>(text: &'a str, open: &str, close: &str) -> Vec<&'a str> {
+    let mut sections = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(open) {
+        let after = &rest[start + open.len()..];
+        match after.find(close) {
+            Some(end) => {
+                sections.push(&after[..end]);
+                rest = &after[end + close.len()..];
+            }
+            None => {
+                sections.push(after);
+                break;
+            }
+        }
+    }
+    sections
+}
+
+fn collect_tool_calls(value: &serde_json::Value, calls: &mut Vec<ParsedToolCall>) {
+    match value {
+        serde_json::Value::Array(items) => {
+            for item in items {
+                collect_tool_calls(item, calls);
+            }
+        }
+        serde_json::Value::Object(object) => {
+            if let Some(nested) = object.get("tool_calls") {
+                collect_tool_calls(nested, calls);
+            } else if let Some(function) = object.get("function").filter(|f| f.is_object()) {
+                collect_tool_calls(function, calls);
+            } else if let Some(name) = object.get("name").and_then(serde_json::Value::as_str) {
+                let arguments = ["arguments", "parameters", "args"]
+                    .iter()
+                    .find_map(|key| object.get(*key))
+                    .cloned()
+                    .unwrap_or_else(|| serde_json::json!({}));
+                // Some models send the arguments as a JSON-encoded string.
+                let arguments = match arguments {
+                    serde_json::Value::String(raw) => {
+                        serde_json::from_str(&raw).unwrap_or(serde_json::Value::String(raw))
+                    }
+                    other => other,
+                };
+                calls.push(ParsedToolCall {
+                    name: name.to_string(),
+                    arguments,
+                });
+            }
+        }
+        _ => {}
+    }
+}
+
+fn load_projector(
+    model: &LlamaModel,
+    path: &str,
+    use_gpu: bool,
+    n_threads: usize,
+) -> Result<VisionProjector, AppError> {
+    let params = MtmdContextParams {
+        use_gpu,
+        print_timings: false,
+        n_threads: n_threads.max(1) as i32,
+        media_marker: CString::new(mtmd_default_marker())
+            .map_err(|e| AppError::Inference(e.to_string()))?,
+    };
+    let ctx = MtmdContext::init_from_file(path, model, &params).map_err(|e| {
+        AppError::Inference(format!("Failed to load vision projector {path}: {e}"))
+    })?;
+    Ok(VisionProjector {
+        path: path.to_string(),
+        ctx,
+    })
+}
+
+/// Puts one media marker per image attachment in front of its message, where
+/// the projector splices in the image embeddings.
+fn with_media_markers(mut messages: Vec<Message>) -> Vec<Message> {
+    for message in &mut messages {
+        let images = message.image_paths().len();
+        if images > 0 {
+            let markers = mtmd_default_marker().repeat(images);
+            message.content = format!("{markers}\n{}", message.content);
+        }
+    }
+    messages
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Weights plus room for KV caches; the GGUF size is close to the weights' footprint.
+fn estimate_resident_mb(model_path: &str) -> u64 {
+    let file_mb = std::fs::metadata(model_path)
+        .map(|meta| meta.len() / (1024 * 1024))
+        .unwrap_or(0);
+    file_mb + KV_RESERVE_MB
+}
+
+/// GPU layers for a model on unified memory: all of them when it fits in the
+/// GPU's share of RAM next to the models already resident, otherwise the
+/// share of its layers that does.
+fn unified_memory_layers(budget_mb: i64, in_use_mb: u64, resident_mb: u64) -> i32 {
+    let free_mb = (budget_mb.max(0) as u64).saturating_sub(in_use_mb);
+    if free_mb >= resident_mb {
+        return -1;
+    }
+    let per_layer_mb = (resident_mb / ASSUMED_LAYER_COUNT).max(1);
+    (free_mb / per_layer_mb) as i32
+}
+
+fn thread_budget(cpu_threads: i64, mode: &PerformanceMode) -> usize {
+    let threads = cpu_threads.max(1) as usize;
+    if *mode == PerformanceMode::Multitasking {
+        // Brutally strict: max 25% of threads, minimum 1, max 4
+        (threads / 4).clamp(1, 4)
+    } else {
+        threads
+    }
+}
+
+fn try_load_model(
+    backend: &LlamaBackend,
+    model_path: &str,
+    n_gpu_layers: i32,
+) -> Result<LlamaModel, AppError> {
+    // Negative means "everything"; llama.cpp clamps the count to the real layer total.
+    let layers = if n_gpu_layers < 0 { 1000 } else { n_gpu_layers as u32 };
+    let model_params = LlamaModelParams::default().with_n_gpu_layers(layers);
+    LlamaModel::load_from_file(backend, model_path, &model_params)
+        .map_err(|e| AppError::Inference(format!("Failed to load GGUF model: {e}")))
+}
+
+fn is_gpu_allocation_failure(error: &AppError) -> bool {
+    let message = error.to_string().to_ascii_lowercase();
+    message.contains("alloc") || message.contains("out of memory")
+}
+
+/// llama.cpp reports most load failures, a full GPU among them, as a bare null model.
+fn is_opaque_load_failure(error: &AppError) -> bool {
+    error.to_string().to_ascii_lowercase().contains("null result")
+}
+
+/// Whether the file starts like a GGUF model llama.cpp can read (format 2 or 3).
+fn has_gguf_header(model_path: &str) -> bool {
+    use std::io::Read;
+
+    let mut header = [0u8; 8];
+    std::fs::File::open(model_path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok()
+        && &header[..4] == b"GGUF"
+        && matches!(u32::from_le_bytes([header[4], header[5], header[6], header[7]]), 2 | 3)
+}
+
+/// Loads with the preferred offload and, if the GPU runs out of memory, binary-searches
+/// for the largest layer count that still loads. Returns the model and the count used.
+///
+/// Other failures are returned as they are. A bare null result only counts as
+/// running out of memory when the file is a valid GGUF that loads on the CPU;
+/// a corrupt or unsupported model fails there too.
+fn load_with_gpu_fallback(
+    backend: &LlamaBackend,
+    model_path: &str,
+    preferred_layers: i32,
+) -> Result<(LlamaModel, i32), AppError> {
+    let first_error = match try_load_model(backend, model_path, preferred_layers) {
+        Ok(model) => return Ok((model, preferred_layers)),
+        Err(error) if preferred_layers == 0 => return Err(error),
+        Err(error) if is_gpu_allocation_failure(&error) => error,
+        Err(error) if is_opaque_load_failure(&error) => {
+            if !has_gguf_header(model_path) {
+                return Err(AppError::Inference(format!(
+                    "{model_path} is not a GGUF model llama.cpp can read; it may be corrupt or incomplete"
+                )));
+            }
+            match try_load_model(backend, model_path, 0) {
+                // The probe only proves the CPU can hold it; free it before searching.
+                Ok(model) => drop(model),
+                Err(_) => return Err(error),
+            }
+            error
+        }
+        Err(error) => return Err(error),
+    };
+
+    crate::log_warn!(
+        "sarah.inference",
+        "Loading with {} GPU layers failed ({}). Searching for a smaller offload.",
+        preferred_layers,
+        first_error
+    );
+
+    // `low` is the largest count known to load (-1: none yet), `high` the smallest known to fail.
+    let mut low = -1;
+    let mut high = if preferred_layers < 0 {
+        GPU_LAYER_SEARCH_CEILING
+    } else {
+        preferred_layers
+    };
+    let mut kept = None;
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        match try_load_model(backend, model_path, mid) {
+            Ok(model) => {
+                low = mid;
+                // Keep the model only if the search is over; otherwise free its VRAM first.
+                kept = (high - low == 1).then_some(model);
+            }
+            Err(_) => high = mid,
+        }
+    }
+
+    if low < 0 {
+        return Err(first_error);
+    }
+
+    let model = match kept {
+        Some(model) => model,
+        None => try_load_model(backend, model_path, low)?,
+    };
+    crate::log_info!("sarah.inference", "Loaded {} with {} GPU layers after fallback.", model_path, low);
+    Ok((model, low))
+}
@@ -0,0 +1,355 @@
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Value;
+
+use crate::error::AppError;
+
+/// Deeper schemas are rejected rather than risking a runaway conversion.
+const MAX_SCHEMA_DEPTH: usize = 32;
+
+const SPACE_RULE: &str = r#"| " " | "\n" [ \t]{0,20}"#;
+const CHAR_RULE: &str = r#"[^"\\\x7F\x00-\x1F] | [\\] (["\\bfnrt] | "u" [0-9a-fA-F]{4})"#;
+const INTEGER_RULE: &str = r#"("-"? ([0-9] | [1-9] [0-9]{0,15})) space"#;
+const NUMBER_RULE: &str =
+    r#"("-"? ([0-9] | [1-9] [0-9]{0,15})) ("." [0-9]+)? ([eE] [-+]? [0-9]+)? space"#;
+const VALUE_RULE: &str = "object | array | string | number | boolean | null";
+const OBJECT_RULE: &str =
+    r#""{" space ( string ":" space value ("," space string ":" space value)* )? "}" space"#;
+const ARRAY_RULE: &str = r#""[" space ( value ("," space value)* )? "]" space"#;
+
+/// Converts a JSON schema into a GBNF grammar whose `root` rule only accepts
+/// matching JSON. Supports `type` (including unions), `properties`/`required`,
+/// `items` with `minItems`/`maxItems`, `enum`, `const`, `anyOf`/`oneOf`,
+/// string `minLength`/`maxLength` and local `$ref`s into `$defs`/`definitions`.
+/// Other keywords (patterns, formats, numeric bounds) are not enforced.
+pub fn schema_to_grammar(schema: &Value) -> Result<String, AppError> {
+    let mut converter = GrammarBuilder::new(schema);
+    let root = converter.visit(schema, "root", 0)?;
+    if root != "root" {
+        converter.add_rule("root", root);
+    }
+    Ok(converter.render())
+}
+
+struct GrammarBuilder<'a> {
+    root_schema: &'a Value,
+    rules: Vec<(String, String)>,
+    names: HashSet<String>,
+    refs: HashMap<String, String>,
+}
+
+impl<'a> GrammarBuilder<'a> {
+    fn new(root_schema: &'a Value) -> Self {
+        Self {
+            root_schema,
+            rules: Vec::new(),
+            names: HashSet::new(),
+            refs: HashMap::new(),
+        }
+    }
+
+    /// Returns a grammar expression for `schema`, adding named rules as needed.
+    fn visit(&mut self, schema: &Value, name: &str, depth: usize) -> Result<String, AppError> {
+        if depth > MAX_SCHEMA_DEPTH {
+            return Err(invalid(format!(
+                "Schema is nested more than {MAX_SCHEMA_DEPTH} levels deep"
+            )));
+        }
+        let schema = match schema {
+            Value::Bool(true) => return Ok(self.primitive("value")),
+            Value::Object(schema) => schema,
+            _ => return Err(invalid("Schemas must be objects".to_string())),
+        };
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            return self.visit_ref(reference, depth);
+        }
+        if let Some(value) = schema.get("const") {
+            let literal = literal(value);
+            return Ok(self.add_rule(name, literal));
+        }
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            if values.is_empty() {
+                return Err(invalid("'enum' cannot be empty".to_string()));
+            }
+            let body = values.iter().map(literal).collect::<Vec<_>>().join(" | ");
+            return Ok(self.add_rule(name, body));
+        }
+        if let Some(variants) = schema
+            .get("anyOf")
+            .or_else(|| schema.get("oneOf"))
+            .and_then(Value::as_array)
+        {
+            let mut alternatives = Vec::with_capacity(variants.len());
+            for (idx, variant) in variants.iter().enumerate() {
+                alternatives.push(self.visit(variant, &format!("{name}-{idx}"), depth + 1)?);
+            }
+            return Ok(self.add_rule(name, alternatives.join(" | ")));
+        }
+
+        match schema.get("type") {
+            Some(Value::String(kind)) => self.visit_type(schema, kind, name, depth),
+            Some(Value::Array(kinds)) => {
+                let mut alternatives = Vec::with_capacity(kinds.len());
+                for kind in kinds {
+                    let kind = kind
+                        .as_str()
+                        .ok_or_else(|| invalid("'type' entries must be strings".to_string()))?;
+                    alternatives.push(self.visit_type(
+                        schema,
+                        kind,
+                        &format!("{name}-{kind}"),
+                        depth,
+                    )?);
+                }
+                Ok(self.add_rule(name, alternatives.join(" | ")))
+            }
+            Some(_) => Err(invalid("'type' must be a string or an array".to_string())),
+            None if schema.contains_key("properties") => {
+                self.visit_type(schema, "object", name, depth)
+            }
+            None if schema.contains_key("items") => self.visit_type(schema, "array", name, depth),
+            None => Ok(self.primitive("value")),
+        }
+    }
+
+    fn visit_type(
+        &mut self,
+        schema: &serde_json::Map<String, Value>,
+        kind: &str,
+        name: &str,
+        depth: usize,
+    ) -> Result<String, AppError> {
+        match kind {
+            "object" => self.visit_object(schema, name, depth),
+            "array" => self.visit_array(schema, name, depth),
+            "string" => {
+                let min = schema.get("minLength").and_then(Value::as_u64);
+                let max = schema.get("maxLength").and_then(Value::as_u64);
+                if min.is_none() && max.is_none() {
+                    return Ok(self.primitive("string"));
+                }
+                self.primitive("char");
+                let chars = format!("char{}", repetition(min.unwrap_or(0), max));
+                Ok(self.add_rule(name, format!(r#""\"" {chars} "\"" space"#)))
+            }
+            "integer" | "number" | "boolean" | "null" => Ok(self.primitive(kind)),
+            other => Err(invalid(format!("Unsupported schema type '{other}'"))),
+        }
+    }
+
+    fn visit_object(
+        &mut self,
+        schema: &serde_json::Map<String, Value>,
+        name: &str,
+        depth: usize,
+    ) -> Result<String, AppError> {
+        let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+            return Ok(self.primitive("object"));
+        };
+        let required = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|keys| {
+                keys.iter()
+                    .filter_map(Value::as_str)
+                    .collect::<HashSet<_>>()
+            })
+            .unwrap_or_default();
+
+        let mut mandatory = Vec::new();
+        let mut optional = Vec::new();
+        for (key_name, property) in properties {
+            let value = self.visit(property, &format!("{name}-{key_name}"), depth + 1)?;
+            let key = serde_json::to_string(key_name).unwrap_or_default();
+            let pair = format!("{} space \":\" space {value}", quote(&key));
+            if required.contains(key_name.as_str()) {
+                mandatory.push(pair);
+            } else {
+                optional.push(pair);
+            }
+        }
+
+        let body = if mandatory.is_empty() {
+            optional_chain(&optional)
+                .map(|chain| format!("( {chain} )?"))
+                .unwrap_or_default()
+        } else {
+            let mut body = mandatory.join(r#" "," space "#);
+            for pair in &optional {
+                body.push_str(&format!(r#" ( "," space {pair} )?"#));
+            }
+            body
+        };
+        Ok(self.add_rule(name, format!(r#""{{" space {body} "}}" space"#)))
+    }
+
+    fn visit_array(
+        &mut self,
+        schema: &serde_json::Map<String, Value>,
+        name: &str,
+        depth: usize,
+    ) -> Result<String, AppError> {
+        let item = match schema.get("items") {
+            Some(items) => self.visit(items, &format!("{name}-item"), depth + 1)?,
+            None => self.primitive("value"),
+        };
+        let min = schema.get("minItems").and_then(Value::as_u64).unwrap_or(0);
+        let max = schema.get("maxItems").and_then(Value::as_u64);
+        if max == Some(0) {
+            return Ok(self.add_rule(name, r#""[" space "]" space"#.to_string()));
+        }
+
+        let rest = repetition(min.saturating_sub(1), max.map(|max| max - 1));
+        let items = format!(r#"{item} ( "," space {item} ){rest}"#);
+        let items = if min == 0 {
+            format!("( {items} )?")
+        } else {
+            items
+        };
+        Ok(self.add_rule(name, format!(r#""[" space {items} "]" space"#)))
+    }
+
+    fn visit_ref(&mut self, reference: &str, depth: usize) -> Result<String, AppError> {
+        if let Some(rule) = self.refs.get(reference) {
+            return Ok(rule.clone());
+        }
+        let path = reference
+            .strip_prefix("#/")
+            .ok_or_else(|| invalid(format!("Only local $refs are supported, got '{reference}'")))?;
+        let target = path
+            .split('/')
+            .try_fold(self.root_schema, |node, segment| node.get(segment))
+            .ok_or_else(|| invalid(format!("Unresolved $ref '{reference}'")))?;
+
+        // Reserve the name first so recursive schemas refer back to it.
+        let def = path.rsplit('/').next().unwrap_or("ref");
+        let rule = self.reserve_name(&format!("def-{def}"));
+        self.refs.insert(reference.to_string(), rule.clone());
+        let body = self.visit(target, &format!("{rule}-body"), depth + 1)?;
+        self.rules.push((rule.clone(), body));
+        Ok(rule)
+    }
+
+    /// Adds one of the shared primitive rules (and what it depends on) once.
+    fn primitive(&mut self, name: &str) -> String {
+        if self.names.contains(name) {
+            return name.to_string();
+        }
+        let (body, deps): (&str, &[&str]) = match name {
+            "space" => (SPACE_RULE, &[]),
+            "char" => (CHAR_RULE, &[]),
+            "string" => (r#""\"" char* "\"" space"#, &["char", "space"]),
+            "integer" => (INTEGER_RULE, &["space"]),
+            "number" => (NUMBER_RULE, &["space"]),
+            "boolean" => (r#"("true" | "false") space"#, &["space"]),
+            "null" => (r#""null" space"#, &["space"]),
+            "object" => (OBJECT_RULE, &["string", "value", "space"]),
+            "array" => (ARRAY_RULE, &["value", "space"]),
+            _ => (
+                VALUE_RULE,
+                &["object", "array", "string", "number", "boolean", "null"],
+            ),
+        };
+        self.names.insert(name.to_string());
+        self.rules.push((name.to_string(), body.to_string()));
+        for dep in deps {
+            self.primitive(dep);
+        }
+        name.to_string()
+    }
+
+    fn add_rule(&mut self, name: &str, body: String) -> String {
+        self.primitive("space");
+        let name = self.reserve_name(name);
+        self.rules.push((name.clone(), body));
+        name
+    }
+
+    fn reserve_name(&mut self, name: &str) -> String {
+        let base = rule_name(name);
+        let mut candidate = base.clone();
+        let mut suffix = 1;
+        while self.names.contains(&candidate) {
+            candidate = format!("{base}{suffix}");
+            suffix += 1;
+        }
+        self.names.insert(candidate.clone());
+        candidate
+    }
+
+    fn render(&self) -> String {
+        let mut rules = self.rules.iter().collect::<Vec<_>>();
+        // llama.cpp starts at `root`; list it first for readability.
+        rules.sort_by_key(|(name, _)| name != "root");
+        rules
+            .iter()
+            .map(|(name, body)| format!("{name} ::= {body}\n"))
+            .collect()
+    }
+}
+
+/// `a ("," b)? ("," c)? | b ("," c)? | c` — any non-empty ordered subset.
+fn optional_chain(pairs: &[String]) -> Option<String> {
+    if pairs.is_empty() {
+        return None;
+    }
+    let alternatives = (0..pairs.len())
+        .map(|start| {
+            let mut alternative = pairs[start].clone();
+            for pair in &pairs[start + 1..] {
+                alternative.push_str(&format!(r#" ( "," space {pair} )?"#));
+            }
+            alternative
+        })
+        .collect::<Vec<_>>();
+    Some(alternatives.join(" | "))
+}
+
+fn repetition(min: u64, max: Option<u64>) -> String {
+    match (min, max) {
+        (0, None) => "*".to_string(),
+        (1, None) => "+".to_string(),
+        (min, None) => format!("{{{min},}}"),
+        (min, Some(max)) => format!("{{{min},{}}}", max.max(min)),
+    }
+}
+
+/// The exact JSON text of `value`, followed by optional whitespace.
+fn literal(value: &Value) -> String {
+    format!("{} space", quote(&value.to_string()))
+}
+
+/// A GBNF string literal matching `text` verbatim.
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => quoted.push_str(r#"\""#),
+            '\\' => quoted.push_str(r"\\"),
+            _ => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn rule_name(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '-' })
+        .collect::<String>();
+    if name.is_empty() {
+        "rule".to_string()
+    } else {
+        name
+    }
+}
+
+fn invalid(message: String) -> AppError {
+    AppError::Validation {
+        field: "json_schema".to_string(),
+        message,
+    }
+}
@@ -0,0 +1,256 @@
+use serde::Serialize;
+
+/// Fewer Latin-script words than this are too ambiguous to classify.
+const MIN_LATIN_WORDS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedLanguage {
+    /// ISO 639-1 code.
+    pub code: &'static str,
+    pub name: &'static str,
+    pub confidence: f32,
+}
+
+impl DetectedLanguage {
+    pub fn is_english(&self) -> bool {
+        self.code == "en"
+    }
+}
+
+/// (code, name, stopwords, distinctive characters) for Latin-script languages.
+const LATIN_LANGUAGES: &[(&str, &str, &[&str], &[char])] = &[
+    (
+        "en",
+        "English",
+        &[
+            "the", "and", "is", "are", "to", "of", "in", "that", "it", "for", "with", "you",
+            "this", "what", "how", "can", "my", "was", "be", "do", "have", "on", "not",
+        ],
+        &[],
+    ),
+    (
+        "es",
+        "Spanish",
+        &[
+            "el", "la", "los", "las", "de", "que", "y", "en", "un", "una", "es", "por", "para",
+            "con", "no", "como", "pero", "mi", "qué", "cómo", "está", "del", "al", "se",
+        ],
+        &['ñ', '¿', '¡'],
+    ),
+    (
+        "fr",
+        "French",
+        &[
+            "le", "la", "les", "de", "des", "et", "est", "un", "une", "que", "qui", "dans", "pour",
+            "pas", "je", "vous", "il", "sur", "avec", "ce", "du", "mon", "comment",
+        ],
+        &['ç', 'è', 'ê', 'à', 'ù', 'œ'],
+    ),
+    (
+        "de",
+        "German",
+        &[
+            "der", "die", "das", "und", "ist", "ich", "nicht", "ein", "eine", "zu", "mit", "den",
+            "von", "sie", "wie", "was", "auf", "für", "es", "dem", "bitte", "kann",
+        ],
+        &['ß', 'ä', 'ö', 'ü'],
+    ),
+    (
+        "it",
+        "Italian",
+        &[
+            "il", "lo", "la", "gli", "di", "che", "e", "è", "un", "una", "per", "non", "sono",
+            "con", "come", "mi", "del", "della", "perché", "questo", "ho",
+        ],
+        &['ò', 'ì'],
+    ),
+    (
+        "pt",
+        "Portuguese",
+        &[
+            "o", "a", "os", "as", "de", "que", "e", "do", "da", "em", "um", "uma", "para", "não",
+            "com", "por", "como", "meu", "você", "é", "isso", "mais",
+        ],
+        &['ã', 'õ'],
+    ),
+    (
+        "nl",
+        "Dutch",
+        &[
+            "de", "het", "een", "en", "van", "is", "dat", "ik", "niet", "je", "op", "te", "met",
+            "voor", "zijn", "hoe", "wat", "mijn", "ook", "maar",
+        ],
+        &[],
+    ),
+    (
+        "pl",
+        "Polish",
+        &[
+            "i", "w", "nie", "na", "się", "jest", "to", "że", "z", "do", "jak", "co", "czy", "mój",
+            "dla", "ale", "tak", "mam",
+        ],
+        &['ł', 'ś', 'ż', 'ź', 'ą', 'ę', 'ć', 'ń'],
+    ),
+    (
+        "tr",
+        "Turkish",
+        &[
+            "ve", "bir", "bu", "da", "de", "ne", "için", "ile", "mi", "ben", "nasıl", "çok",
+            "değil", "var", "gibi", "ama",
+        ],
+        &['ğ', 'ş', 'ı'],
+    ),
+];
+
+/// Identifies the language of `text` from its script, then from stopwords and
+/// distinctive letters for Latin-script text. Returns `None` when unsure.
+pub fn detect_language(text: &str) -> Option<DetectedLanguage> {
+    let mut letters = 0usize;
+    let mut latin = 0usize;
+    let mut counts = ScriptCounts::default();
+    for ch in text.chars().filter(|ch| ch.is_alphabetic()) {
+        letters += 1;
+        match ch as u32 {
+            0x0041..=0x024F => latin += 1,
+            0x0370..=0x03FF => counts.greek += 1,
+            0x0400..=0x04FF => {
+                counts.cyrillic += 1;
+                if matches!(ch, 'і' | 'ї' | 'є' | 'ґ' | 'І' | 'Ї' | 'Є' | 'Ґ') {
+                    counts.ukrainian_marks += 1;
+                }
+            }
+            0x0590..=0x05FF => counts.hebrew += 1,
+            0x0600..=0x06FF => {
+                counts.arabic += 1;
+                if matches!(ch, 'پ' | 'چ' | 'ژ' | 'گ') {
+                    counts.persian_marks += 1;
+                }
+            }
+            0x0900..=0x097F => counts.devanagari += 1,
+            0x0E00..=0x0E7F => counts.thai += 1,
+            0x3040..=0x30FF => counts.kana += 1,
+            0xAC00..=0xD7AF | 0x1100..=0x11FF => counts.hangul += 1,
+            0x4E00..=0x9FFF | 0x3400..=0x4DBF => counts.han += 1,
+            _ => {}
+        }
+    }
+    if letters == 0 {
+        return None;
+    }
+
+    if let Some(language) = counts.dominant(letters) {
+        return Some(language);
+    }
+    if latin * 2 < letters {
+        return None;
+    }
+    detect_latin(text)
+}
+
+/// Moves items written in `language` ahead of the rest, keeping relative order
+/// within each group. Items whose language can't be told stay between the two.
+pub fn prefer_language<T>(items: &mut [T], language: &str, text_of: impl Fn(&T) -> &str) {
+    items.sort_by_cached_key(|item| match detect_language(text_of(item)) {
+        Some(detected) if detected.code == language => 0u8,
+        None => 1,
+        Some(_) => 2,
+    });
+}
+
+#[derive(Default)]
+struct ScriptCounts {
+    greek: usize,
+    cyrillic: usize,
+    ukrainian_marks: usize,
+    hebrew: usize,
+    arabic: usize,
+    persian_marks: usize,
+    devanagari: usize,
+    thai: usize,
+    kana: usize,
+    hangul: usize,
+    han: usize,
+}
+
+impl ScriptCounts {
+    fn dominant(&self, letters: usize) -> Option<DetectedLanguage> {
+        let share = |count: usize| count as f32 / letters as f32;
+        // Japanese mixes kana with Han, so any real amount of kana decides it.
+        let cjk = self.kana + self.han;
+        let candidates = [
+            (self.hangul, "ko", "Korean"),
+            (
+                if self.kana * 10 >= cjk { cjk } else { 0 },
+                "ja",
+                "Japanese",
+            ),
+            (if self.kana * 10 < cjk { cjk } else { 0 }, "zh", "Chinese"),
+            (
+                self.cyrillic,
+                if self.ukrainian_marks > 0 { "uk" } else { "ru" },
+                if self.ukrainian_marks > 0 {
+                    "Ukrainian"
+                } else {
+                    "Russian"
+                },
+            ),
+            (
+                self.arabic,
+                if self.persian_marks > 0 { "fa" } else { "ar" },
+                if self.persian_marks > 0 {
+                    "Persian"
+                } else {
+                    "Arabic"
+                },
+            ),
+            (self.hebrew, "he", "Hebrew"),
+            (self.greek, "el", "Greek"),
+            (self.devanagari, "hi", "Hindi"),
+            (self.thai, "th", "Thai"),
+        ];
+
+        candidates
+            .into_iter()
+            .filter(|(count, ..)| share(*count) >= 0.4)
+            .max_by_key(|(count, ..)| *count)
+            .map(|(count, code, name)| DetectedLanguage {
+                code,
+                name,
+                confidence: share(count).min(1.0),
+            })
+    }
+}
+
+fn detect_latin(text: &str) -> Option<DetectedLanguage> {
+    let lowered = text.to_lowercase();
+    let words = lowered
+        .split(|ch: char| !ch.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>();
+    if words.len() < MIN_LATIN_WORDS {
+        return None;
+    }
+
+    let mut scores = LATIN_LANGUAGES
+        .iter()
+        .map(|(code, name, stopwords, marks)| {
+            let hits = words.iter().filter(|word| stopwords.contains(word)).count() as f32;
+            let marked = lowered.chars().filter(|ch| marks.contains(ch)).count() as f32;
+            (*code, *name, hits + marked * 1.5)
+        })
+        .collect::<Vec<_>>();
+    scores.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+    let (code, name, best) = scores[0];
+    let runner_up = scores.get(1).map(|score| score.2).unwrap_or(0.0);
+    if best < 2.0 || best <= runner_up {
+        return None;
+    }
+
+    Some(DetectedLanguage {
+        code,
+        name,
+        confidence: ((best - runner_up) / best).clamp(0.0, 1.0),
+    })
+}
@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use tokio::sync::Mutex;
+
+use crate::db::models::Session;
+use crate::error::AppError;
+use crate::repositories::conversation_repo::ConversationRepo;
+use crate::repositories::settings_repo::SettingsRepo;
+use crate::state::AppCache;
+
+pub const LAUNCH_STATE_NAMESPACE: &str = "launch_state";
+const LAST_SESSION_KEY: &str = "last_session_id";
+const OPEN_WINDOWS_KEY: &str = "open_windows";
+
+/// Aux windows worth reopening on launch; transient ones like the audio bar are left out.
+pub const RESTORABLE_WINDOWS: &[&str] = &["history", "settings", "models", "mcp"];
+
+#[derive(Clone)]
+pub struct LaunchStateService {
+    settings_repo: SettingsRepo,
+    conversation_repo: ConversationRepo,
+    cache: Arc<AppCache>,
+    // Serializes read-modify-write of the open window list.
+    windows_lock: Arc<Mutex<()>>,
+}
+
+impl LaunchStateService {
+    pub fn new(
+        settings_repo: SettingsRepo,
+        conversation_repo: ConversationRepo,
+        cache: Arc<AppCache>,
+    ) -> Self {
+        Self {
+            settings_repo,
+            conversation_repo,
+            cache,
+            windows_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub async fn set_last_session(&self, session_id: &str) -> Result<(), AppError> {
+        self.settings_repo
+            .upsert_setting(
+                None,
+                LAUNCH_STATE_NAMESPACE,
+                LAST_SESSION_KEY,
+                session_id,
+                "string",
+                false,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the last open session if it still exists and hasn't been deleted.
+    pub async fn last_session(&self) -> Result<Option<Session>, AppError> {
+        let Some(setting) = self
+            .settings_repo
+            .get_setting(None, LAUNCH_STATE_NAMESPACE, LAST_SESSION_KEY)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        if let Some(session) = self.cache.session_metadata.get(&setting.value).await {
+            return Ok(Some(session));
+        }
+
+        Ok(self
+            .conversation_repo
+            .get_session(&setting.value)
+            .await?
+            .filter(|session| session.status != "deleted"))
+    }
+
+    pub async fn set_window_open(&self, label: &str, open: bool) -> Result<(), AppError> {
+        if !RESTORABLE_WINDOWS.contains(&label) {
+            return Ok(());
+        }
+
+        let _guard = self.windows_lock.lock().await;
+        let mut windows = self.open_windows().await?;
+        let present = windows.iter().any(|existing| existing == label);
+        if open == present {
+            return Ok(());
+        }
+
+        if open {
+            windows.push(label.to_string());
+        } else {
+            windows.retain(|existing| existing != label);
+        }
+
+        let encoded = serde_json::to_string(&windows).unwrap_or_else(|_| "[]".to_string());
+        self.settings_repo
+            .upsert_setting(
+                None,
+                LAUNCH_STATE_NAMESPACE,
+                OPEN_WINDOWS_KEY,
+                &encoded,
+                "json",
+                false,
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn open_windows(&self) -> Result<Vec<String>, AppError> {
+        let windows = self
+            .settings_repo
+            .get_setting(None, LAUNCH_STATE_NAMESPACE, OPEN_WINDOWS_KEY)
+            .await?
+            .and_then(|setting| serde_json::from_str::<Vec<String>>(&setting.value).ok())
+            .unwrap_or_default();
+
+        Ok(windows
+            .into_iter()
+            .filter(|label| RESTORABLE_WINDOWS.contains(&label.as_str()))
+            .collect())
+    }
+
+    /// Loads the last session into the metadata cache so the overlay's first read is warm.
+    pub async fn prewarm_last_session(&self) -> Result<Option<Session>, AppError> {
+        let session = self.last_session().await?;
+        if let Some(session) = session.as_ref() {
+            self.cache
+                .session_metadata
+                .insert(session.id.clone(), session.clone())
+                .await;
+        }
+        Ok(session)
+    }
+}
@@ -0,0 +1,943 @@
+use std::convert::Infallible;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::Manager;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::db::models::{GenerationOptions, Message, Model};
+use crate::error::AppError;
+use crate::repositories::settings_repo::SettingsRepo;
+use crate::services::crypto_service::CryptoService;
+use crate::services::inference_service::prompt_message;
+use crate::state::AppState;
+
+pub const LOCAL_API_SETTINGS_NAMESPACE: &str = "local_api";
+const LOCAL_API_SETTINGS_KEY: &str = "settings";
+const LOCAL_API_TOKEN_KEY: &str = "token";
+/// One above Ollama's default, so both can run side by side.
+pub const DEFAULT_LOCAL_API_PORT: u16 = 11435;
+/// Model names that leave the choice to the task router.
+const AUTO_MODEL_NAMES: &[&str] = &["auto", "sarah"];
+const MAX_COMPLETION_TOKENS: usize = 4096;
+const MAX_EMBEDDING_INPUTS: usize = 256;
+/// Written to the app data directory while the server runs, so the `sarah ask`
+/// command line can find it.
+pub const LOCAL_API_DISCOVERY_FILE: &str = "local-api.json";
+/// How long open requests get to finish when the server stops.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(3);
+
+/// Whether the OpenAI-compatible server runs, and on which port. Off by default.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LocalApiSettings {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for LocalApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_LOCAL_API_PORT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalApiStatus {
+    pub enabled: bool,
+    pub running: bool,
+    pub port: u16,
+    /// What to give clients as their OpenAI base URL, while running.
+    pub base_url: Option<String>,
+    pub last_error: Option<String>,
+}
+
+/// The contents of `LOCAL_API_DISCOVERY_FILE`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalApiDiscovery {
+    pub port: u16,
+    pub token: String,
+}
+
+struct RunningServer {
+    port: u16,
+    shutdown: CancellationToken,
+    handle: JoinHandle<()>,
+}
+
+/// Serves `/v1/chat/completions`, `/v1/embeddings` and `/v1/models` on
+/// 127.0.0.1 for editors and other tools, behind a bearer token.
+#[derive(Clone)]
+pub struct LocalApiService {
+    app_handle: tauri::AppHandle,
+    settings_repo: SettingsRepo,
+    crypto: CryptoService,
+    server: Arc<Mutex<Option<RunningServer>>>,
+    last_error: Arc<std::sync::Mutex<Option<String>>>,
+}
+
+impl LocalApiService {
+    pub fn new(
+        app_handle: tauri::AppHandle,
+        settings_repo: SettingsRepo,
+        crypto: CryptoService,
+    ) -> Self {
+        Self {
+            app_handle,
+            settings_repo,
+            crypto,
+            server: Arc::new(Mutex::new(None)),
+            last_error: Arc::new(std::sync::Mutex::new(None)),
+        }
+    }
+
+    pub async fn settings(&self) -> Result<LocalApiSettings, AppError> {
+        let stored = self
+            .settings_repo
+            .get_setting(None, LOCAL_API_SETTINGS_NAMESPACE, LOCAL_API_SETTINGS_KEY)
+            .await?;
+        Ok(stored
+            .and_then(|setting| serde_json::from_str(&setting.value).ok())
+            .unwrap_or_default())
+    }
+
+    /// Saves the settings and starts, stops or moves the server to match.
+    pub async fn set_settings(
+        &self,
+        settings: LocalApiSettings,
+    ) -> Result<LocalApiStatus, AppError> {
+        if settings.port < 1024 {
+            return Err(AppError::Validation {
+                field: "port".to_string(),
+                message: "Use a port from 1024 to 65535".to_string(),
+            });
+        }
+        let value = serde_json::to_string(&settings)
+            .map_err(|e| AppError::Internal(format!("Failed to encode local API settings: {e}")))?;
+        self.settings_repo
+            .upsert_setting(
+                None,
+                LOCAL_API_SETTINGS_NAMESPACE,
+                LOCAL_API_SETTINGS_KEY,
+                &value,
+                "json",
+                false,
+            )
+            .await?;
+
+        self.stop().await;
+        if settings.enabled {
+            self.start(settings.port).await?;
+        }
+        self.status().await
+    }
+
+    pub async fn status(&self) -> Result<LocalApiStatus, AppError> {
+        let settings = self.settings().await?;
+        let running_port = self.server.lock().await.as_ref().map(|server| server.port);
+        Ok(LocalApiStatus {
+            enabled: settings.enabled,
+            running: running_port.is_some(),
+            port: running_port.unwrap_or(settings.port),
+            base_url: running_port.map(|port| format!("http://127.0.0.1:{port}/v1")),
+            last_error: self.last_error.lock().ok().and_then(|error| error.clone()),
+        })
+    }
+
+    /// The bearer token clients must send, created the first time it is asked for.
+    pub async fn token(&self) -> Result<String, AppError> {
+        let stored = self
+            .settings_repo
+            .get_setting(None, LOCAL_API_SETTINGS_NAMESPACE, LOCAL_API_TOKEN_KEY)
+            .await?;
+        if let Some(setting) = stored {
+            let mut plaintext = self.crypto.decrypt(&setting.value)?;
+            let token = String::from_utf8(plaintext.clone())
+                .map_err(|e| AppError::Crypto(e.to_string()))?;
+            CryptoService::zeroize_after_use(&mut plaintext);
+            return Ok(token);
+        }
+        self.store_new_token().await
+    }
+
+    /// Replaces the token; clients using the old one are refused from now on.
+    pub async fn rotate_token(&self) -> Result<String, AppError> {
+        let token = self.store_new_token().await?;
+        let running_port = self.server.lock().await.as_ref().map(|server| server.port);
+        if let Some(port) = running_port {
+            self.stop().await;
+            self.start(port).await?;
+        }
+        Ok(token)
+    }
+
+    async fn store_new_token(&self) -> Result<String, AppError> {
+        let token = format!(
+            "sarah-{}{}",
+            Uuid::new_v4().simple(),
+            Uuid::new_v4().simple()
+        );
+        let encrypted = self.crypto.encrypt_to_compact(token.as_bytes())?;
+        self.settings_repo
+            .upsert_setting(
+                None,
+                LOCAL_API_SETTINGS_NAMESPACE,
+                LOCAL_API_TOKEN_KEY,
+                &encrypted,
+                "string",
+                true,
+            )
+            .await?;
+        Ok(token)
+    }
+
+    /// Starts the server at launch when the user turned it on.
+    pub async fn resume(&self) {
+        match self.settings().await {
+            Ok(settings) if settings.enabled => {
+                if let Err(error) = self.start(settings.port).await {
+                    tracing::warn!("Failed to start the local API server: {error}");
+                }
+            }
+            Ok(_) => {}
+            Err(error) => tracing::warn!("Failed to read local API settings: {error}"),
+        }
+    }
+
+    async fn start(&self, port: u16) -> Result<(), AppError> {
+        let token = self.token().await?;
+        let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+        let listener = match tokio::net::TcpListener::bind(address).await {
+            Ok(listener) => listener,
+            Err(error) => {
+                let message = format!("Couldn't listen on port {port}: {error}");
+                self.set_last_error(Some(message.clone()));
+                return Err(AppError::Config(message));
+            }
+        };
+        self.set_last_error(None);
+        self.write_discovery_file(port, &token).await;
+
+        let api = ApiState {
+            app_handle: self.app_handle.clone(),
+            token: Arc::from(token),
+        };
+        let router = Router::new()
+            .route("/v1/models", get(list_models))
+            .route("/v1/chat/completions", post(chat_completions))
+            .route("/v1/embeddings", post(embeddings))
+            .layer(middleware::from_fn_with_state(api.clone(), require_token))
+            .with_state(api);
+
+        let shutdown = CancellationToken::new();
+        let server_shutdown = shutdown.clone();
+        let last_error = Arc::clone(&self.last_error);
+        let handle = tokio::spawn(async move {
+            let served = axum::serve(listener, router)
+                .with_graceful_shutdown(server_shutdown.cancelled_owned())
+                .await;
+            if let Err(error) = served {
+                tracing::warn!("Local API server stopped: {error}");
+                if let Ok(mut last_error) = last_error.lock() {
+                    *last_error = Some(error.to_string());
+                }
+            }
+        });
+
+        tracing::info!("Local API server listening on {address}");
+        *self.server.lock().await = Some(RunningServer {
+            port,
+            shutdown,
+            handle,
+        });
+        Ok(())
+    }
+
+    /// Stops the server and waits until its port is free again.
+    pub async fn stop(&self) {
+        let Some(mut server) = self.server.lock().await.take() else {
+            return;
+        };
+        server.shutdown.cancel();
+        if tokio::time::timeout(SHUTDOWN_GRACE, &mut server.handle)
+            .await
+            .is_err()
+        {
+            server.handle.abort();
+        }
+        if let Some(path) = self.discovery_path() {
+            let _ = tokio::fs::remove_file(path).await;
+        }
+    }
+
+    fn discovery_path(&self) -> Option<PathBuf> {
+        self.app_handle
+            .path()
+            .app_data_dir()
+            .ok()
+            .map(|dir| dir.join(LOCAL_API_DISCOVERY_FILE))
+    }
+
+    async fn write_discovery_file(&self, port: u16, token: &str) {
+        let Some(path) = self.discovery_path() else {
+            return;
+        };
+        let discovery = LocalApiDiscovery {
+            port,
+            token: token.to_string(),
+        };
+        let contents = serde_json::to_string(&discovery).unwrap_or_default();
+        if let Err(error) = write_private_file(&path, &contents).await {
+            tracing::warn!("Failed to write {}: {error}", path.display());
+        }
+    }
+
+    fn set_last_error(&self, error: Option<String>) {
+        if let Ok(mut last_error) = self.last_error.lock() {
+            *last_error = error;
+        }
+    }
+}
+
+/// Writes a file only the current user can read; it holds the API token. The
+/// contents go to a new file created with those permissions, which then
+/// replaces `path`, so the token is never readable by others, even briefly.
+async fn write_private_file(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+    // Left over from a crash; `create_new` below won't open an existing file.
+    let _ = tokio::fs::remove_file(&temp_path).await;
+
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let written = async {
+        let mut file = options.open(&temp_path).await?;
+        file.write_all(contents.as_bytes()).await?;
+        file.sync_all().await?;
+        drop(file);
+        tokio::fs::rename(&temp_path, path).await
+    }
+    .await;
+    if written.is_err() {
+        let _ = tokio::fs::remove_file(&temp_path).await;
+    }
+    written
+}
+
+#[derive(Clone)]
+struct ApiState {
+    app_handle: tauri::AppHandle,
+    token: Arc<str>,
+}
+
+impl ApiState {
+    fn app_state(&self) -> Result<Arc<AppState>, ApiError> {
+        self.app_handle
+            .try_state::<Arc<AppState>>()
+            .map(|state| Arc::clone(&state))
+            .ok_or_else(|| {
+                ApiError::new(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "server_error",
+                    "Sarah is still starting",
+                )
+            })
+    }
+}
+
+/// An error in OpenAI's `{"error": {...}}` shape.
+struct ApiError {
+    status: StatusCode,
+    kind: &'static str,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, kind: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            kind,
+            message: message.into(),
+        }
+    }
+
+    fn invalid(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "invalid_request_error", message)
+    }
+}
+
+impl From<AppError> for ApiError {
+    fn from(error: AppError) -> Self {
+        match error {
+            AppError::Validation { .. } => Self::invalid(error.to_string()),
+            AppError::NotFound { .. } => Self::new(
+                StatusCode::NOT_FOUND,
+                "invalid_request_error",
+                error.to_string(),
+            ),
+            other => Self::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "server_error",
+                other.to_string(),
+            ),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = json!({
+            "error": { "message": self.message, "type": self.kind, "code": Value::Null }
+        });
+        (self.status, Json(body)).into_response()
+    }
+}
+
+async fn require_token(State(api): State<ApiState>, request: Request, next: Next) -> Response {
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    if !constant_time_eq(presented.trim().as_bytes(), api.token.as_bytes()) {
+        return ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            "invalid_request_error",
+            "Missing or wrong API token",
+        )
+        .into_response();
+    }
+    next.run(request).await
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+async fn list_models(State(api): State<ApiState>) -> Result<Json<Value>, ApiError> {
+    let state = api.app_state()?;
+    let installed = state.model_repo.list_installed().await?;
+    let mut data =
+        vec![json!({ "id": "auto", "object": "model", "created": 0, "owned_by": "sarah" })];
+    data.extend(
+        installed
+            .iter()
+            .filter(|model| model.category == "chat")
+            .map(|model| {
+                json!({
+                    "id": model.name,
+                    "object": "model",
+                    "created": unix_seconds(&model.created_at),
+                    "owned_by": "sarah",
+                })
+            }),
+    );
+    Ok(Json(json!({ "object": "list", "data": data })))
+}
+
+#[derive(Deserialize)]
+struct ChatCompletionRequest {
+    #[serde(default)]
+    model: Option<String>,
+    messages: Vec<ChatMessage>,
+    #[serde(default)]
+    temperature: Option<f32>,
+    #[serde(default)]
+    top_p: Option<f32>,
+    #[serde(default)]
+    max_tokens: Option<usize>,
+    #[serde(default)]
+    max_completion_tokens: Option<usize>,
+    #[serde(default)]
+    seed: Option<u32>,
+    #[serde(default)]
+    stream: bool,
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    role: String,
+    #[serde(default)]
+    content: Value,
+}
+
+impl ChatCompletionRequest {
+    /// The messages as Sarah prompts; `developer` counts as `system`.
+    fn prompt_messages(&self) -> Result<Vec<Message>, ApiError> {
+        self.messages
+            .iter()
+            .map(|message| {
+                let role = match message.role.as_str() {
+                    "system" | "developer" => "system",
+                    "user" => "user",
+                    "assistant" => "assistant",
+                    other => {
+                        return Err(ApiError::invalid(format!(
+                            "Unsupported message role '{other}'"
+                        )))
+                    }
+                };
+                Ok(prompt_message(role, message_text(&message.content)))
+            })
+            .collect()
+    }
+
+    /// Sampling options clamped to what Sarah supports. Without a token limit
+    /// the router's budget applies, when it picked the model.
+    fn generation_options(&self, routed_max_tokens: Option<usize>) -> GenerationOptions {
+        let defaults = GenerationOptions::default();
+        GenerationOptions {
+            temperature: self
+                .temperature
+                .unwrap_or(defaults.temperature)
+                .clamp(0.0, 2.0),
+            top_p: self.top_p.unwrap_or(defaults.top_p).clamp(0.0, 1.0),
+            max_tokens: self
+                .max_completion_tokens
+                .or(self.max_tokens)
+                .or(routed_max_tokens)
+                .unwrap_or(defaults.max_tokens)
+                .clamp(1, MAX_COMPLETION_TOKENS),
+            seed: self.seed,
+            ..defaults
+        }
+    }
+}
+
+/// Stops the generation when dropped. A streamed reply holds one, so a client
+/// that goes away stops the turn instead of leaving the model busy, even
+/// before the first token is sent.
+struct CancelOnDrop {
+    state: Arc<AppState>,
+    id: String,
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        // A no-op once the turn has finished.
+        self.state.inference.cancel_generation(&self.id);
+    }
+}
+
+/// Runs a chat completion on the requested model, or on the one the task
+/// router picks for "auto".
+async fn chat_completions(
+    State(api): State<ApiState>,
+    Json(request): Json<ChatCompletionRequest>,
+) -> Result<Response, ApiError> {
+    let state = api.app_state()?;
+    let messages = request.prompt_messages()?;
+    let Some(last_user) = messages.iter().rev().find(|message| message.role == "user") else {
+        return Err(ApiError::invalid("Send at least one user message"));
+    };
+
+    let prompt_tokens = messages
+        .iter()
+        .map(|message| {
+            state
+                .inference
+                .count_tokens(&message.content)
+                .unwrap_or(message.content.len() / 4 + 1)
+        })
+        .sum::<usize>();
+    let requested = request
+        .model
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty() && !AUTO_MODEL_NAMES.contains(name));
+    let (model, routed_max_tokens) =
+        select_model(&state, requested, &last_user.content, prompt_tokens).await?;
+    state.conversation.ensure_model_ready(&model).await?;
+
+    let opts = request.generation_options(routed_max_tokens);
+
+    let id = format!("chatcmpl-{}", Uuid::new_v4().simple());
+    let created = chrono::Utc::now().timestamp();
+    if !request.stream {
+        let result = state
+            .inference
+            .generate_with_options(messages, &[], opts)
+            .await?;
+        let body = completion_body(
+            &id,
+            created,
+            &model.name,
+            &result.text,
+            &result.finish_reason,
+            prompt_tokens,
+            result.tokens_generated,
+        );
+        return Ok(Json(body).into_response());
+    }
+
+    let cancel = CancelOnDrop {
+        state: Arc::clone(&state),
+        id: id.clone(),
+    };
+    let chunks = state.inference.generate_stream(&id, messages, opts).await?;
+    let chunk = move |delta: Value, finish: Option<&str>| {
+        completion_chunk(&id, created, &model.name, delta, finish).to_string()
+    };
+    let opening = chunk(json!({ "role": "assistant" }), None);
+    let events = stream::once(async move { opening })
+        .chain(chunks.map(move |piece| {
+            // Owned by the stream, so dropping the response cancels the turn.
+            let _ = &cancel;
+            if piece.done {
+                let reason = piece.finish_reason.as_deref().unwrap_or("stop");
+                chunk(json!({}), Some(finish_reason(reason)))
+            } else {
+                chunk(json!({ "content": piece.token }), None)
+            }
+        }))
+        .chain(stream::once(async { "[DONE]".to_string() }))
+        .map(|data| Ok::<_, Infallible>(Event::default().data(data)));
+    Ok(Sse::new(events).into_response())
+}
+
+/// The model to answer with and, when the router chose it, its token budget.
+async fn select_model(
+    state: &AppState,
+    requested: Option<&str>,
+    content: &str,
+    prompt_tokens: usize,
+) -> Result<(Model, Option<usize>), ApiError> {
+    if let Some(name) = requested {
+        let model = state
+            .conversation
+            .resolve_selected_model(name)
+            .await?
+            .filter(|model| model.is_downloaded == 1)
+            .ok_or_else(|| {
+                ApiError::new(
+                    StatusCode::NOT_FOUND,
+                    "invalid_request_error",
+                    format!("The model '{name}' is not installed"),
+                )
+            })?;
+        return Ok((model, None));
+    }
+
+    let user = state.user_repo.get_or_create_default_user().await?;
+    let routing = state
+        .task_router
+        .route(
+            &user.id,
+            None,
+            content,
+            None,
+            None,
+            false,
+            Some(prompt_tokens),
+        )
+        .await?;
+    let model = state
+        .conversation
+        .resolve_target_model_for_routing(&routing)
+        .await?
+        .ok_or_else(|| {
+            ApiError::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "server_error",
+                "No chat model is installed",
+            )
+        })?;
+    Ok((model, Some(routing.max_tokens)))
+}
+
+#[derive(Deserialize)]
+struct EmbeddingRequest {
+    input: Value,
+}
+
+async fn embeddings(
+    State(api): State<ApiState>,
+    Json(request): Json<EmbeddingRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let state = api.app_state()?;
+    let embedding = state.embedding.clone().ok_or_else(|| {
+        ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "server_error",
+            "Embeddings are unavailable on this device",
+        )
+    })?;
+    let inputs = match request.input {
+        Value::String(text) => vec![text],
+        Value::Array(items) => items
+            .into_iter()
+            .map(|item| match item {
+                Value::String(text) => Ok(text),
+                _ => Err(ApiError::invalid("`input` must be a string or strings")),
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        _ => return Err(ApiError::invalid("`input` must be a string or strings")),
+    };
+    if inputs.is_empty() || inputs.len() > MAX_EMBEDDING_INPUTS {
+        return Err(ApiError::invalid(format!(
+            "Send 1-{MAX_EMBEDDING_INPUTS} inputs"
+        )));
+    }
+
+    let prompt_tokens = inputs.iter().map(|text| text.len() / 4 + 1).sum::<usize>();
+    let vectors = embedding.embed_batch(inputs).await?;
+    let data = vectors
+        .into_iter()
+        .enumerate()
+        .map(
+            |(index, vector)| json!({ "object": "embedding", "index": index, "embedding": vector }),
+        )
+        .collect::<Vec<_>>();
+    Ok(Json(json!({
+        "object": "list",
+        "data": data,
+        "model": embedding.model_name(),
+        "usage": { "prompt_tokens": prompt_tokens, "total_tokens": prompt_tokens },
+    })))
+}
+
+/// A whole `chat.completion` response.
+fn completion_body(
+    id: &str,
+    created: i64,
+    model: &str,
+    text: &str,
+    reason: &str,
+    prompt_tokens: usize,
+    completion_tokens: usize,
+) -> Value {
+    json!({
+        "id": id,
+        "object": "chat.completion",
+        "created": created,
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": text },
+            "finish_reason": finish_reason(reason),
+        }],
+        "usage": {
+            "prompt_tokens": prompt_tokens,
+            "completion_tokens": completion_tokens,
+            "total_tokens": prompt_tokens + completion_tokens,
+        },
+    })
+}
+
+/// One `chat.completion.chunk` server-sent event.
+fn completion_chunk(
+    id: &str,
+    created: i64,
+    model: &str,
+    delta: Value,
+    finish: Option<&str>,
+) -> Value {
+    json!({
+        "id": id,
+        "object": "chat.completion.chunk",
+        "created": created,
+        "model": model,
+        "choices": [{ "index": 0, "delta": delta, "finish_reason": finish }],
+    })
+}
+
+/// Text parts of a message; images and other parts are dropped.
+fn message_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|part| part.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+fn finish_reason(reason: &str) -> &'static str {
+    match reason {
+        "length" => "length",
+        _ => "stop",
+    }
+}
+
+fn unix_seconds(timestamp: &str) -> i64 {
+    chrono::NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S")
+        .map(|time| time.and_utc().timestamp())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{
+        completion_body, completion_chunk, finish_reason, message_text, unix_seconds,
+        ChatCompletionRequest, MAX_COMPLETION_TOKENS,
+    };
+    use crate::db::models::GenerationOptions;
+
+    fn request(body: serde_json::Value) -> ChatCompletionRequest {
+        serde_json::from_value(body).unwrap()
+    }
+
+    #[test]
+    fn parses_a_minimal_request() {
+        let request = request(json!({ "messages": [{ "role": "user", "content": "hi" }] }));
+        assert!(request.model.is_none());
+        assert!(!request.stream);
+
+        let messages = request.prompt_messages().ok().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].role, "user");
+        assert_eq!(messages[0].content, "hi");
+    }
+
+    #[test]
+    fn maps_roles_and_content_parts() {
+        let request = request(json!({
+            "model": "auto",
+            "stream": true,
+            "messages": [
+                { "role": "developer", "content": "be brief" },
+                { "role": "system", "content": "you are Sarah" },
+                { "role": "assistant" },
+                { "role": "user", "content": [
+                    { "type": "text", "text": "first" },
+                    { "type": "image_url", "image_url": { "url": "data:" } },
+                    { "type": "text", "text": "second" }
+                ] }
+            ]
+        }));
+        assert!(request.stream);
+        let messages = request.prompt_messages().ok().unwrap();
+        let roles = messages
+            .iter()
+            .map(|message| message.role.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(roles, ["system", "system", "assistant", "user"]);
+        assert_eq!(messages[2].content, "");
+        assert_eq!(messages[3].content, "first\nsecond");
+    }
+
+    #[test]
+    fn rejects_unsupported_roles() {
+        let request = request(json!({
+            "messages": [{ "role": "tool", "content": "{}" }]
+        }));
+        let error = request.prompt_messages().err().unwrap();
+        assert_eq!(error.status, axum::http::StatusCode::BAD_REQUEST);
+        assert!(error.message.contains("tool"));
+    }
+
+    #[test]
+    fn clamps_generation_options() {
+        let defaults = GenerationOptions::default();
+        let plain = request(json!({ "messages": [] })).generation_options(None);
+        assert_eq!(plain.temperature, defaults.temperature);
+        assert_eq!(plain.max_tokens, defaults.max_tokens);
+        assert_eq!(
+            request(json!({ "messages": [] }))
+                .generation_options(Some(300))
+                .max_tokens,
+            300
+        );
+
+        let opts = request(json!({
+            "messages": [],
+            "temperature": 5.0,
+            "top_p": -1.0,
+            "max_tokens": 100,
+            "max_completion_tokens": 1_000_000,
+            "seed": 7
+        }))
+        .generation_options(Some(300));
+        assert_eq!(opts.temperature, 2.0);
+        assert_eq!(opts.top_p, 0.0);
+        assert_eq!(opts.max_tokens, MAX_COMPLETION_TOKENS);
+        assert_eq!(opts.seed, Some(7));
+
+        let opts = request(json!({ "messages": [], "max_tokens": 0 })).generation_options(None);
+        assert_eq!(opts.max_tokens, 1);
+    }
+
+    #[test]
+    fn completion_has_openai_shape() {
+        let body = completion_body(
+            "chatcmpl-1",
+            1_700_000_000,
+            "qwen",
+            "Hello",
+            "length",
+            12,
+            3,
+        );
+        assert_eq!(
+            body,
+            json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 1_700_000_000,
+                "model": "qwen",
+                "choices": [{
+                    "index": 0,
+                    "message": { "role": "assistant", "content": "Hello" },
+                    "finish_reason": "length",
+                }],
+                "usage": { "prompt_tokens": 12, "completion_tokens": 3, "total_tokens": 15 },
+            })
+        );
+    }
+
+    #[test]
+    fn chunks_have_openai_shape() {
+        let chunk = completion_chunk("chatcmpl-1", 5, "qwen", json!({ "content": "Hi" }), None);
+        assert_eq!(
+            chunk,
+            json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion.chunk",
+                "created": 5,
+                "model": "qwen",
+                "choices": [{ "index": 0, "delta": { "content": "Hi" }, "finish_reason": null }],
+            })
+        );
+        let last = completion_chunk("chatcmpl-1", 5, "qwen", json!({}), Some("stop"));
+        assert_eq!(last["choices"][0]["finish_reason"], "stop");
+        assert_eq!(last["choices"][0]["delta"], json!({}));
+    }
+
+    #[test]
+    fn maps_finish_reasons_and_times() {
+        assert_eq!(finish_reason("length"), "length");
+        for reason in ["stop", "cancelled", "error", ""] {
+            assert_eq!(finish_reason(reason), "stop");
+        }
+        assert_eq!(unix_seconds("1970-01-02 00:00:00"), 86_400);
+        assert_eq!(unix_seconds("not a time"), 0);
+        assert_eq!(message_text(&json!(null)), "");
+    }
+}
@@ -1,17 +1,899 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use dashmap::DashMap;
-use tokio::process::Command;
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use reqwest::StatusCode;
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{oneshot, Mutex};
+use tokio::task::JoinHandle;
 
 use crate::db::models::{Intent, Mcp, McpHealthStatus, ToolResult};
 use crate::error::AppError;
 use crate::repositories::mcp_repo::McpRepo;
+use crate::repositories::settings_repo::SettingsRepo;
 use crate::services::crypto_service::CryptoService;
+use crate::services::filesystem_tools::{self, FilesystemTools, BUILTIN_FILESYSTEM_MCP_ID};
 use crate::services::intent_service::IntentService;
+use crate::services::shell_tool::{self, ShellTool, BUILTIN_SHELL_MCP_ID};
+
+const MCP_PROTOCOL_VERSION: &str = "2024-11-05";
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(20);
+const DEFAULT_TOOL_TIMEOUT: Duration = Duration::from_secs(30);
+const HTTP_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const SESSION_HEADER: &str = "mcp-session-id";
+const HEALTH_PING_TIMEOUT: Duration = Duration::from_secs(10);
+const RESTART_BASE_DELAY: Duration = Duration::from_secs(5);
+const RESTART_MAX_DELAY: Duration = Duration::from_secs(10 * 60);
+
+type PendingReplies = DashMap<u64, oneshot::Sender<Result<Value, String>>>;
+
+/// Where an MCP server lives. A changed spec replaces the running connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum McpServerSpec {
+    Stdio(StdioServerSpec),
+    Http(HttpServerSpec),
+}
+
+impl McpServerSpec {
+    pub fn from_mcp(mcp: &Mcp) -> Result<Self, AppError> {
+        match mcp.mcp_type.as_str() {
+            "stdio" => StdioServerSpec::from_mcp(mcp).map(Self::Stdio),
+            "http" => HttpServerSpec::from_mcp(mcp).map(Self::Http),
+            other => Err(AppError::McpError {
+                mcp_id: mcp.id.clone(),
+                message: format!("Unsupported MCP type: {other}"),
+            }),
+        }
+    }
+}
+
+/// How to launch a stdio MCP server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StdioServerSpec {
+    pub command: String,
+    pub args: Vec<String>,
+    pub env: HashMap<String, String>,
+    pub cwd: Option<PathBuf>,
+}
+
+impl StdioServerSpec {
+    /// Reads `command`, the JSON `args` array and the JSON `env_vars` object of a registered MCP.
+    pub fn from_mcp(mcp: &Mcp) -> Result<Self, AppError> {
+        let command = mcp.command.clone().ok_or_else(|| AppError::McpError {
+            mcp_id: mcp.id.clone(),
+            message: "stdio MCP missing command".to_string(),
+        })?;
+        Ok(Self {
+            command,
+            args: serde_json::from_str(&mcp.args).unwrap_or_default(),
+            env: serde_json::from_str(&mcp.env_vars).unwrap_or_default(),
+            cwd: None,
+        })
+    }
+}
+
+/// A remote server reached over streamable HTTP, or the older HTTP+SSE
+/// transport for servers that predate it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpServerSpec {
+    pub url: String,
+    /// Sent with every request, typically `Authorization`. Empty until
+    /// `McpService::server_spec` decrypts them.
+    pub headers: HashMap<String, String>,
+}
+
+impl HttpServerSpec {
+    /// Reads `url`; the headers are filled in by `McpService::server_spec`.
+    pub fn from_mcp(mcp: &Mcp) -> Result<Self, AppError> {
+        let url = mcp
+            .url
+            .clone()
+            .filter(|url| !url.trim().is_empty())
+            .ok_or_else(|| AppError::McpError {
+                mcp_id: mcp.id.clone(),
+                message: "http MCP missing url".to_string(),
+            })?;
+        Ok(Self {
+            url,
+            headers: HashMap::new(),
+        })
+    }
+
+    /// The `headerNames` array of the MCP's JSON `metadata`.
+    pub fn header_names(mcp: &Mcp) -> Vec<String> {
+        serde_json::from_str::<Value>(&mcp.metadata)
+            .ok()
+            .and_then(|metadata| metadata.get("headerNames").cloned())
+            .and_then(|names| serde_json::from_value(names).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Remote servers are shared by every user, so their header secrets are kept
+/// under the default user.
+const REMOTE_SECRET_USER_ID: &str = "default";
+
+fn header_secret_key(name: &str) -> String {
+    format!("header:{name}")
+}
+
+/// One tool advertised by a registered MCP.
+#[derive(Debug, Clone)]
+pub struct McpTool {
+    pub mcp_id: String,
+    pub name: String,
+    pub schema: Value,
+}
+
+#[derive(Debug, Clone)]
+pub struct McpToolOutput {
+    pub text: String,
+    /// The server ran the call but reported the tool itself as failed.
+    pub is_error: bool,
+}
+
+/// A persistent connection to an MCP server speaking JSON-RPC 2.0, either over
+/// a child process's stdio or over HTTP. Requests are multiplexed by id, so
+/// concurrent tool calls share one connection.
+pub struct McpClient {
+    server_id: String,
+    spec: McpServerSpec,
+    transport: Transport,
+    pending: Arc<PendingReplies>,
+    next_id: AtomicU64,
+    alive: Arc<AtomicBool>,
+    last_used_at: std::sync::Mutex<Instant>,
+    server_info: Value,
+    tools: Vec<Value>,
+    resources: Vec<Value>,
+}
+
+enum Transport {
+    Stdio(StdioTransport),
+    Http(Arc<HttpTransport>),
+}
+
+impl McpClient {
+    /// Connects and completes the initialize handshake, then lists the
+    /// server's tools and (when advertised) resources.
+    pub async fn connect(server_id: &str, spec: McpServerSpec) -> Result<Self, AppError> {
+        let pending: Arc<PendingReplies> = Arc::new(DashMap::new());
+        let alive = Arc::new(AtomicBool::new(true));
+        let transport = match &spec {
+            McpServerSpec::Stdio(stdio) => Transport::Stdio(StdioTransport::spawn(
+                server_id,
+                stdio,
+                Arc::clone(&pending),
+                Arc::clone(&alive),
+            )?),
+            McpServerSpec::Http(http) => Transport::Http(Arc::new(HttpTransport::new(
+                server_id,
+                http.clone(),
+                Arc::clone(&pending),
+                Arc::clone(&alive),
+            )?)),
+        };
+
+        let mut client = Self {
+            server_id: server_id.to_string(),
+            spec,
+            transport,
+            pending,
+            next_id: AtomicU64::new(1),
+            alive,
+            last_used_at: std::sync::Mutex::new(Instant::now()),
+            server_info: Value::Null,
+            tools: Vec::new(),
+            resources: Vec::new(),
+        };
+
+        let initialized = match client.initialize().await {
+            Ok(initialized) => initialized,
+            Err(error) => match &client.transport {
+                // Servers on the 2024-11-05 HTTP+SSE transport reject the
+                // initialize POST; they expect a GET event stream first.
+                Transport::Http(http) if http.initialize_rejected() => {
+                    http.open_legacy_stream()
+                        .await
+                        .map_err(|message| client.error(message))?;
+                    client.initialize().await?
+                }
+                _ => return Err(error),
+            },
+        };
+        client
+            .notify("notifications/initialized", serde_json::json!({}))
+            .await?;
+
+        client.tools = client.list_all("tools/list", "tools").await?;
+        if initialized
+            .get("capabilities")
+            .and_then(|caps| caps.get("resources"))
+            .is_some()
+        {
+            client.resources = client
+                .list_all("resources/list", "resources")
+                .await
+                .unwrap_or_default();
+        }
+        client.server_info = initialized
+            .get("serverInfo")
+            .cloned()
+            .unwrap_or(Value::Null);
+
+        crate::log_info!(
+            "sarah.mcp",
+            "Connected to MCP server {} ({} tools)",
+            client.server_id,
+            client.tools.len()
+        );
+        Ok(client)
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.alive.load(Ordering::SeqCst)
+    }
+
+    pub fn spec(&self) -> &McpServerSpec {
+        &self.spec
+    }
+
+    pub fn server_info(&self) -> &Value {
+        &self.server_info
+    }
+
+    pub fn tools(&self) -> &[Value] {
+        &self.tools
+    }
+
+    pub fn resources(&self) -> &[Value] {
+        &self.resources
+    }
+
+    pub fn idle_for(&self) -> Duration {
+        self.last_used_at
+            .lock()
+            .map(|at| at.elapsed())
+            .unwrap_or_default()
+    }
+
+    pub async fn call_tool(
+        &self,
+        tool_name: &str,
+        args: Value,
+        timeout: Duration,
+    ) -> Result<McpToolOutput, AppError> {
+        let result = self
+            .request(
+                "tools/call",
+                serde_json::json!({ "name": tool_name, "arguments": args }),
+                timeout,
+            )
+            .await?;
+
+        let text = result
+            .get("content")
+            .and_then(Value::as_array)
+            .map(|items| {
+                items
+                    .iter()
+                    .map(|item| match item.get("text").and_then(Value::as_str) {
+                        Some(text) => text.to_string(),
+                        None => item.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .unwrap_or_else(|| result.to_string());
+
+        Ok(McpToolOutput {
+            text,
+            is_error: result
+                .get("isError")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+        })
+    }
+
+    /// `timeout` covers both sending the request and waiting for its reply,
+    /// since HTTP servers may answer in the body of the POST itself.
+    pub async fn request(
+        &self,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value, AppError> {
+        if let Ok(mut at) = self.last_used_at.lock() {
+            *at = Instant::now();
+        }
+        self.exchange(method, params, timeout).await
+    }
+
+    /// Health probe that doesn't count as use, so idle cleanup still applies.
+    pub async fn ping(&self, timeout: Duration) -> Result<(), AppError> {
+        self.exchange("tools/list", serde_json::json!({}), timeout)
+            .await
+            .map(|_| ())
+    }
+
+    async fn exchange(
+        &self,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value, AppError> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = oneshot::channel();
+        self.pending.insert(id, tx);
+
+        let message = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+        let exchange = async {
+            self.send(&message).await?;
+            rx.await.map_err(|_| self.exited_error())
+        };
+
+        match tokio::time::timeout(timeout, exchange).await {
+            Ok(Ok(Ok(result))) => Ok(result),
+            Ok(Ok(Err(message))) => Err(self.error(format!("{method} failed: {message}"))),
+            Ok(Err(error)) => {
+                self.pending.remove(&id);
+                Err(error)
+            }
+            Err(_) => {
+                self.pending.remove(&id);
+                Err(self.error(format!(
+                    "{method} timed out after {} seconds",
+                    timeout.as_secs()
+                )))
+            }
+        }
+    }
+
+    pub async fn notify(&self, method: &str, params: Value) -> Result<(), AppError> {
+        self.send(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+        }))
+        .await
+    }
+
+    pub async fn shutdown(&self) {
+        self.alive.store(false, Ordering::SeqCst);
+        match &self.transport {
+            Transport::Stdio(stdio) => {
+                let _ = stdio.child.lock().await.start_kill();
+            }
+            Transport::Http(http) => http.close().await,
+        }
+    }
+
+    async fn initialize(&self) -> Result<Value, AppError> {
+        self.request(
+            "initialize",
+            serde_json::json!({
+                "protocolVersion": MCP_PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": { "name": "sarah", "version": env!("CARGO_PKG_VERSION") },
+            }),
+            HANDSHAKE_TIMEOUT,
+        )
+        .await
+    }
+
+    /// Follows `nextCursor` until the listing is complete.
+    async fn list_all(&self, method: &str, field: &str) -> Result<Vec<Value>, AppError> {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match cursor.as_deref() {
+                Some(cursor) => serde_json::json!({ "cursor": cursor }),
+                None => serde_json::json!({}),
+            };
+            let page = self.request(method, params, HANDSHAKE_TIMEOUT).await?;
+            if let Some(listed) = page.get(field).and_then(Value::as_array) {
+                items.extend(listed.iter().cloned());
+            }
+            cursor = page
+                .get("nextCursor")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            if cursor.is_none() {
+                return Ok(items);
+            }
+        }
+    }
+
+    async fn send(&self, message: &Value) -> Result<(), AppError> {
+        if !self.is_alive() {
+            return Err(self.exited_error());
+        }
+        match &self.transport {
+            Transport::Stdio(stdio) => write_line(&stdio.stdin, message)
+                .await
+                .map_err(|error| self.error(format!("Failed to write to MCP server: {error}"))),
+            Transport::Http(http) => http
+                .post(message)
+                .await
+                .map_err(|message| self.error(message)),
+        }
+    }
+
+    fn exited_error(&self) -> AppError {
+        match &self.transport {
+            Transport::Stdio(stdio) => {
+                let detail = stdio
+                    .last_stderr
+                    .lock()
+                    .ok()
+                    .and_then(|last| last.clone())
+                    .map(|line| format!(": {line}"))
+                    .unwrap_or_default();
+                self.error(format!("MCP server exited{detail}"))
+            }
+            Transport::Http(_) => self.error("MCP server connection closed".to_string()),
+        }
+    }
+
+    fn error(&self, message: String) -> AppError {
+        AppError::McpError {
+            mcp_id: self.server_id.clone(),
+            message,
+        }
+    }
+}
+
+/// Routes a reply to its waiting request. Server-initiated requests get the
+/// response to send back: pings are answered, everything else is declined.
+/// Notifications are ignored.
+fn route_incoming(pending: &PendingReplies, message: Value) -> Option<Value> {
+    let id = message.get("id").cloned()?;
+
+    if let Some(method) = message.get("method").and_then(Value::as_str) {
+        return Some(if method == "ping" {
+            serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": {} })
+        } else {
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": -32601, "message": format!("Method not found: {method}") },
+            })
+        });
+    }
+
+    let (_, reply) = id.as_u64().and_then(|id| pending.remove(&id))?;
+    let outcome = match message.get("error") {
+        Some(error) => Err(error
+            .get("message")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| error.to_string())),
+        None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+    };
+    let _ = reply.send(outcome);
+    None
+}
+
+struct StdioTransport {
+    child: Mutex<Child>,
+    stdin: Arc<Mutex<ChildStdin>>,
+    last_stderr: Arc<std::sync::Mutex<Option<String>>>,
+}
+
+impl StdioTransport {
+    fn spawn(
+        server_id: &str,
+        spec: &StdioServerSpec,
+        pending: Arc<PendingReplies>,
+        alive: Arc<AtomicBool>,
+    ) -> Result<Self, AppError> {
+        let mut command = Command::new(&spec.command);
+        command
+            .args(&spec.args)
+            .envs(&spec.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(cwd) = spec.cwd.as_ref() {
+            command.current_dir(cwd);
+        }
+
+        let mut child = command.spawn().map_err(|error| AppError::McpError {
+            mcp_id: server_id.to_string(),
+            message: format!("Failed to start '{}': {error}", spec.command),
+        })?;
+        let (Some(stdin), Some(stdout), Some(stderr)) =
+            (child.stdin.take(), child.stdout.take(), child.stderr.take())
+        else {
+            return Err(AppError::McpError {
+                mcp_id: server_id.to_string(),
+                message: "MCP process did not expose stdio pipes".to_string(),
+            });
+        };
+
+        let stdin = Arc::new(Mutex::new(stdin));
+        let last_stderr = Arc::new(std::sync::Mutex::new(None));
+
+        tokio::spawn(read_replies(
+            server_id.to_string(),
+            BufReader::new(stdout),
+            Arc::clone(&stdin),
+            pending,
+            alive,
+        ));
+        let stderr_sink = Arc::clone(&last_stderr);
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if !line.trim().is_empty() {
+                    if let Ok(mut last) = stderr_sink.lock() {
+                        *last = Some(line);
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            child: Mutex::new(child),
+            stdin,
+            last_stderr,
+        })
+    }
+}
+
+async fn write_line(stdin: &Mutex<ChildStdin>, message: &Value) -> std::io::Result<()> {
+    let mut line = message.to_string();
+    line.push('\n');
+    let mut stdin = stdin.lock().await;
+    stdin.write_all(line.as_bytes()).await?;
+    stdin.flush().await
+}
+
+/// Routes replies to their waiting requests until the server closes stdout.
+/// Non-JSON lines (servers that log to stdout) are skipped.
+async fn read_replies(
+    server_id: String,
+    stdout: BufReader<tokio::process::ChildStdout>,
+    stdin: Arc<Mutex<ChildStdin>>,
+    pending: Arc<PendingReplies>,
+    alive: Arc<AtomicBool>,
+) {
+    let mut lines = stdout.lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        if let Some(reply) = route_incoming(&pending, message) {
+            let _ = write_line(&stdin, &reply).await;
+        }
+    }
+
+    alive.store(false, Ordering::SeqCst);
+    // Dropping the senders fails every in-flight request with "server exited".
+    pending.clear();
+    crate::log_warn!("sarah.mcp", "MCP server {} closed its output", server_id);
+}
+
+/// Streamable HTTP: every message is POSTed and replies come back either as
+/// the JSON body or as an event stream on that response. In legacy mode the
+/// replies arrive on one long-lived GET stream instead.
+struct HttpTransport {
+    server_id: String,
+    http: reqwest::Client,
+    spec: HttpServerSpec,
+    /// Legacy servers announce their own message endpoint.
+    post_url: std::sync::Mutex<String>,
+    session_id: std::sync::Mutex<Option<String>>,
+    legacy: AtomicBool,
+    initialize_rejected: AtomicBool,
+    legacy_stream: std::sync::Mutex<Option<JoinHandle<()>>>,
+    pending: Arc<PendingReplies>,
+    alive: Arc<AtomicBool>,
+}
+
+impl HttpTransport {
+    fn new(
+        server_id: &str,
+        spec: HttpServerSpec,
+        pending: Arc<PendingReplies>,
+        alive: Arc<AtomicBool>,
+    ) -> Result<Self, AppError> {
+        let http = reqwest::Client::builder()
+            .connect_timeout(HTTP_CONNECT_TIMEOUT)
+            .build()
+            .map_err(|error| AppError::McpError {
+                mcp_id: server_id.to_string(),
+                message: format!("Failed to create HTTP client: {error}"),
+            })?;
+        Ok(Self {
+            server_id: server_id.to_string(),
+            http,
+            post_url: std::sync::Mutex::new(spec.url.clone()),
+            spec,
+            session_id: std::sync::Mutex::new(None),
+            legacy: AtomicBool::new(false),
+            initialize_rejected: AtomicBool::new(false),
+            legacy_stream: std::sync::Mutex::new(None),
+            pending,
+            alive,
+        })
+    }
+
+    fn initialize_rejected(&self) -> bool {
+        !self.legacy.load(Ordering::SeqCst) && self.initialize_rejected.load(Ordering::SeqCst)
+    }
+
+    fn session_id(&self) -> Option<String> {
+        self.session_id.lock().ok().and_then(|id| id.clone())
+    }
+
+    fn with_headers(&self, mut request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        for (name, value) in &self.spec.headers {
+            request = request.header(name, value);
+        }
+        if let Some(session_id) = self.session_id() {
+            request = request.header(SESSION_HEADER, session_id);
+        }
+        request
+    }
+
+    async fn post(self: &Arc<Self>, message: &Value) -> Result<(), String> {
+        let url = self
+            .post_url
+            .lock()
+            .map(|url| url.clone())
+            .unwrap_or_else(|_| self.spec.url.clone());
+        let response = self
+            .with_headers(self.http.post(&url))
+            .header(ACCEPT, "application/json, text/event-stream")
+            .json(message)
+            .send()
+            .await
+            .map_err(|error| format!("Request to {url} failed: {error}"))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            if status == StatusCode::NOT_FOUND && self.session_id().is_some() {
+                // The server dropped our session; a reconnect starts a new one.
+                self.alive.store(false, Ordering::SeqCst);
+            }
+            if message.get("method").and_then(Value::as_str) == Some("initialize")
+                && matches!(status.as_u16(), 400 | 404 | 405)
+            {
+                self.initialize_rejected.store(true, Ordering::SeqCst);
+            }
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("HTTP {status}: {}", body.trim()));
+        }
+
+        if let Some(session_id) = response
+            .headers()
+            .get(SESSION_HEADER)
+            .and_then(|value| value.to_str().ok())
+        {
+            if let Ok(mut current) = self.session_id.lock() {
+                *current = Some(session_id.to_string());
+            }
+        }
+        if self.legacy.load(Ordering::SeqCst) || status == StatusCode::ACCEPTED {
+            return Ok(());
+        }
+
+        let is_stream = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("text/event-stream"));
+        if is_stream {
+            tokio::spawn(pump_events(
+                Arc::clone(self),
+                SseReader::new(response),
+                false,
+            ));
+            return Ok(());
+        }
+
+        let body = response
+            .text()
+            .await
+            .map_err(|error| format!("Failed to read MCP response: {error}"))?;
+        if body.trim().is_empty() {
+            return Ok(());
+        }
+        let message = serde_json::from_str::<Value>(&body)
+            .map_err(|error| format!("Invalid MCP response: {error}"))?;
+        self.receive(message);
+        Ok(())
+    }
+
+    /// Opens the GET event stream of the HTTP+SSE transport and waits for the
+    /// `endpoint` event that says where to POST messages.
+    async fn open_legacy_stream(self: &Arc<Self>) -> Result<(), String> {
+        let response = self
+            .with_headers(self.http.get(&self.spec.url))
+            .header(ACCEPT, "text/event-stream")
+            .send()
+            .await
+            .map_err(|error| format!("Request to {} failed: {error}", self.spec.url))?;
+        if !response.status().is_success() {
+            return Err(format!("HTTP {} opening event stream", response.status()));
+        }
+
+        let mut reader = SseReader::new(response);
+        let endpoint = tokio::time::timeout(HANDSHAKE_TIMEOUT, async {
+            while let Some(event) = reader.next_event().await {
+                if event.event == "endpoint" {
+                    return Some(event.data);
+                }
+            }
+            None
+        })
+        .await
+        .ok()
+        .flatten()
+        .ok_or_else(|| "MCP server did not announce a message endpoint".to_string())?;
+
+        let post_url = reqwest::Url::parse(&self.spec.url)
+            .and_then(|base| base.join(endpoint.trim()))
+            .map_err(|error| format!("Invalid message endpoint '{endpoint}': {error}"))?;
+        if let Ok(mut url) = self.post_url.lock() {
+            *url = post_url.to_string();
+        }
+        self.legacy.store(true, Ordering::SeqCst);
+
+        let stream = tokio::spawn(pump_events(Arc::clone(self), reader, true));
+        if let Ok(mut slot) = self.legacy_stream.lock() {
+            *slot = Some(stream);
+        }
+        Ok(())
+    }
+
+    /// Accepts a single message or a JSON-RPC batch.
+    fn receive(self: &Arc<Self>, message: Value) {
+        let messages = match message {
+            Value::Array(batch) => batch,
+            message => vec![message],
+        };
+        for message in messages {
+            if let Some(reply) = route_incoming(&self.pending, message) {
+                let transport = Arc::clone(self);
+                tokio::spawn(async move { transport.post_reply(&reply).await });
+            }
+        }
+    }
+
+    /// Answers a server-initiated request; the response body carries nothing.
+    async fn post_reply(&self, reply: &Value) {
+        let url = self
+            .post_url
+            .lock()
+            .map(|url| url.clone())
+            .unwrap_or_else(|_| self.spec.url.clone());
+        let _ = self
+            .with_headers(self.http.post(&url))
+            .header(ACCEPT, "application/json, text/event-stream")
+            .json(reply)
+            .send()
+            .await;
+    }
+
+    /// Stops the legacy stream and ends the server-side session, if any.
+    async fn close(&self) {
+        if let Some(stream) = self
+            .legacy_stream
+            .lock()
+            .ok()
+            .and_then(|mut slot| slot.take())
+        {
+            stream.abort();
+        }
+        if self.session_id().is_some() {
+            let _ = self
+                .with_headers(self.http.delete(&self.spec.url))
+                .send()
+                .await;
+        }
+        self.pending.clear();
+    }
+}
+
+/// Feeds `message` events to the transport. When the legacy stream ends the
+/// connection is over; a per-request stream simply finishes.
+async fn pump_events(transport: Arc<HttpTransport>, mut reader: SseReader, long_lived: bool) {
+    while let Some(event) = reader.next_event().await {
+        if event.event != "message" || event.data.trim().is_empty() {
+            continue;
+        }
+        if let Ok(message) = serde_json::from_str::<Value>(&event.data) {
+            transport.receive(message);
+        }
+    }
+
+    if long_lived {
+        transport.alive.store(false, Ordering::SeqCst);
+        transport.pending.clear();
+        crate::log_warn!(
+            "sarah.mcp",
+            "MCP server {} closed its event stream",
+            transport.server_id
+        );
+    }
+}
+
+struct SseEvent {
+    event: String,
+    data: String,
+}
+
+/// Minimal `text/event-stream` parser over a response body.
+struct SseReader {
+    response: reqwest::Response,
+    buffer: Vec<u8>,
+}
+
+impl SseReader {
+    fn new(response: reqwest::Response) -> Self {
+        Self {
+            response,
+            buffer: Vec::new(),
+        }
+    }
+
+    async fn next_event(&mut self) -> Option<SseEvent> {
+        loop {
+            if let Some(event) = self.take_event() {
+                return Some(event);
+            }
+            match self.response.chunk().await {
+                Ok(Some(chunk)) => self
+                    .buffer
+                    .extend(chunk.iter().copied().filter(|byte| *byte != b'\r')),
+                _ => return None,
+            }
+        }
+    }
+
+    /// Pops complete events off the buffer, skipping comment-only blocks.
+    fn take_event(&mut self) -> Option<SseEvent> {
+        loop {
+            let end = self.buffer.windows(2).position(|pair| pair == b"\n\n")?;
+            let block = self.buffer.drain(..end + 2).collect::<Vec<_>>();
+            let block = String::from_utf8_lossy(&block);
+
+            let mut event = String::from("message");
+            let mut data = Vec::new();
+            for line in block.lines() {
+                if let Some(value) = line.strip_prefix("event:") {
+                    event = value.trim().to_string();
+                } else if let Some(value) = line.strip_prefix("data:") {
+                    data.push(value.strip_prefix(' ').unwrap_or(value));
+                }
+            }
+            if !data.is_empty() {
+                return Some(SseEvent {
+                    event,
+                    data: data.join("\n"),
+                });
+            }
+        }
+    }
+}
 
 #[derive(Clone)]
-struct McpClient {
+struct PooledMcp {
     mcp: Mcp,
     last_used_at: Instant,
 }
@@ -21,21 +903,97 @@ pub struct McpService {
     repo: McpRepo,
     crypto: CryptoService,
     intent: IntentService,
-    pool: Arc<DashMap<String, McpClient>>,
+    filesystem: FilesystemTools,
+    shell: ShellTool,
+    pool: Arc<DashMap<String, PooledMcp>>,
+    /// Running stdio servers keyed by MCP id (or a fixed key such as "spotify").
+    clients: Arc<DashMap<String, Arc<McpClient>>>,
+    /// Serializes spawns so concurrent first calls don't start duplicate processes.
+    spawn_lock: Arc<Mutex<()>>,
     breaker: Arc<DashMap<String, (u32, Instant, String)>>,
+    /// Crashed servers waiting to be restarted by the health probe.
+    restarts: Arc<DashMap<String, RestartBackoff>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RestartBackoff {
+    failures: u32,
+    next_attempt_at: Instant,
+}
+
+impl RestartBackoff {
+    /// 5s, 10s, 20s, ... capped at ten minutes.
+    fn delay(failures: u32) -> Duration {
+        RESTART_BASE_DELAY
+            .saturating_mul(1 << failures.saturating_sub(1).min(16))
+            .min(RESTART_MAX_DELAY)
+    }
+}
+
+fn health_status(mcp: &Mcp, status: &str, last_error: Option<String>) -> McpHealthStatus {
+    McpHealthStatus {
+        mcp_id: mcp.id.clone(),
+        health_status: status.to_string(),
+        last_error,
+    }
 }
 
 impl McpService {
-    pub fn new(repo: McpRepo, crypto: CryptoService, intent: IntentService) -> Self {
+    pub fn new(
+        repo: McpRepo,
+        crypto: CryptoService,
+        intent: IntentService,
+        settings_repo: SettingsRepo,
+    ) -> Self {
         Self {
             repo,
             crypto,
             intent,
+            filesystem: FilesystemTools::new(settings_repo.clone()),
+            shell: ShellTool::new(settings_repo.clone(), FilesystemTools::new(settings_repo)),
             pool: Arc::new(DashMap::new()),
+            clients: Arc::new(DashMap::new()),
+            spawn_lock: Arc::new(Mutex::new(())),
             breaker: Arc::new(DashMap::new()),
+            restarts: Arc::new(DashMap::new()),
         }
     }
 
+    pub async fn stdio_client(
+        &self,
+        key: &str,
+        spec: StdioServerSpec,
+    ) -> Result<Arc<McpClient>, AppError> {
+        self.client(key, McpServerSpec::Stdio(spec)).await
+    }
+
+    /// Returns the live client for `key`, connecting (or reconnecting) when it
+    /// is missing, has disconnected, or `spec` changed.
+    pub async fn client(&self, key: &str, spec: McpServerSpec) -> Result<Arc<McpClient>, AppError> {
+        if let Some(client) = self.live_client(key, &spec) {
+            return Ok(client);
+        }
+
+        let _guard = self.spawn_lock.lock().await;
+        if let Some(client) = self.live_client(key, &spec) {
+            return Ok(client);
+        }
+        if let Some((_, stale)) = self.clients.remove(key) {
+            stale.shutdown().await;
+        }
+
+        let client = Arc::new(McpClient::connect(key, spec).await?);
+        self.clients.insert(key.to_string(), Arc::clone(&client));
+        Ok(client)
+    }
+
+    fn live_client(&self, key: &str, spec: &McpServerSpec) -> Option<Arc<McpClient>> {
+        self.clients
+            .get(key)
+            .filter(|client| client.is_alive() && client.spec() == spec)
+            .map(|client| Arc::clone(client.value()))
+    }
+
     pub async fn ensure_connected(&self, mcp_id: &str) -> Result<Mcp, AppError> {
         if let Some(state) = self.breaker.get(mcp_id) {
             let (errors, opened_at, breaker_state) = state.value();
@@ -53,9 +1011,9 @@ impl McpService {
             }
         }
 
-        if let Some(mut client) = self.pool.get_mut(mcp_id) {
-            client.last_used_at = Instant::now();
-            return Ok(client.mcp.clone());
+        if let Some(mut pooled) = self.pool.get_mut(mcp_id) {
+            pooled.last_used_at = Instant::now();
+            return Ok(pooled.mcp.clone());
         }
 
         let mcp = self
@@ -67,12 +1025,20 @@ impl McpService {
                 id: mcp_id.to_string(),
             })?;
 
-        let client = McpClient {
-            mcp: mcp.clone(),
-            last_used_at: Instant::now(),
+        let mcp = if matches!(mcp.mcp_type.as_str(), "stdio" | "http") {
+            let client = self.client(mcp_id, self.server_spec(&mcp).await?).await?;
+            self.sync_schemas(mcp, &client).await?
+        } else {
+            mcp
         };
 
-        self.pool.insert(mcp_id.to_string(), client);
+        self.pool.insert(
+            mcp_id.to_string(),
+            PooledMcp {
+                mcp: mcp.clone(),
+                last_used_at: Instant::now(),
+            },
+        );
         self.repo
             .upsert_connection_state(mcp_id, "default", "connected", "closed", None, true)
             .await?;
@@ -80,6 +1046,64 @@ impl McpService {
         Ok(mcp)
     }
 
+    /// Stores the tools and resources the server advertised during the
+    /// handshake, so newly added servers show up with their schemas.
+    async fn sync_schemas(&self, mut mcp: Mcp, client: &McpClient) -> Result<Mcp, AppError> {
+        let tool_schemas = Value::Array(client.tools().to_vec()).to_string();
+        let resource_schemas = Value::Array(client.resources().to_vec()).to_string();
+        if mcp.tool_schemas == tool_schemas && mcp.resource_schemas == resource_schemas {
+            return Ok(mcp);
+        }
+
+        self.repo
+            .update_schemas(&mcp.id, client.tools(), client.resources())
+            .await?;
+        crate::log_info!(
+            "sarah.mcp",
+            "discovered {} tools for MCP {}",
+            client.tools().len(),
+            mcp.id
+        );
+        mcp.tool_schemas = tool_schemas;
+        mcp.resource_schemas = resource_schemas;
+        Ok(mcp)
+    }
+
+    /// Stores the schemas of the tools served in-process, so they are routed
+    /// like any other MCP's.
+    pub async fn sync_builtin_tools(&self) -> Result<(), AppError> {
+        self.repo
+            .update_schemas(
+                BUILTIN_FILESYSTEM_MCP_ID,
+                &filesystem_tools::tool_schemas(),
+                &[],
+            )
+            .await?;
+        self.repo
+            .update_schemas(BUILTIN_SHELL_MCP_ID, &shell_tool::tool_schemas(), &[])
+            .await
+    }
+
+    /// The folders the file tools may use.
+    pub fn filesystem(&self) -> &FilesystemTools {
+        &self.filesystem
+    }
+
+    /// The programs `run_command` may start.
+    pub fn shell(&self) -> &ShellTool {
+        &self.shell
+    }
+
+    /// Drops cached state for an MCP and stops its server process, if any.
+    pub async fn disconnect(&self, mcp_id: &str) {
+        self.pool.remove(mcp_id);
+        self.breaker.remove(mcp_id);
+        self.restarts.remove(mcp_id);
+        if let Some((_, client)) = self.clients.remove(mcp_id) {
+            client.shutdown().await;
+        }
+    }
+
     pub async fn call_tool(
         &self,
         mcp_id: &str,
@@ -91,6 +1115,10 @@ impl McpService {
         let started = Instant::now();
 
         let output = match mcp.mcp_type.as_str() {
+            "builtin" if mcp.id == BUILTIN_FILESYSTEM_MCP_ID => {
+                self.filesystem.call(tool_name, args).await?
+            }
+            "builtin" if mcp.id == BUILTIN_SHELL_MCP_ID => self.shell.call(tool_name, args).await?,
             "builtin" => serde_json::json!({
                 "tool": tool_name,
                 "status": "ok",
@@ -98,49 +1126,18 @@ impl McpService {
                 "args": args,
             })
             .to_string(),
-            "stdio" => {
-                let command = mcp.command.clone().ok_or_else(|| AppError::McpError {
-                    mcp_id: mcp_id.to_string(),
-                    message: "stdio MCP missing command".to_string(),
-                })?;
-
-                let mut cmd = Command::new(command);
-
-                let args_json = args.to_string();
-                if let Ok(parsed_args) = serde_json::from_str::<Vec<String>>(&mcp.args) {
-                    for arg in parsed_args {
-                        cmd.arg(arg);
-                    }
-                }
-
-                cmd.arg(tool_name).arg(args_json);
-
-                let timed = tokio::time::timeout(Duration::from_secs(30), cmd.output()).await;
-                let result = match timed {
-                    Ok(Ok(output)) => output,
-                    Ok(Err(err)) => {
-                        return Err(AppError::McpError {
-                            mcp_id: mcp_id.to_string(),
-                            message: err.to_string(),
-                        })
-                    }
-                    Err(_) => {
-                        return Err(AppError::McpError {
-                            mcp_id: mcp_id.to_string(),
-                            message: "Tool call timed out after 30 seconds".to_string(),
-                        })
-                    }
-                };
-
-                if !result.status.success() {
-                    let stderr = String::from_utf8_lossy(&result.stderr).to_string();
+            "stdio" | "http" => {
+                let client = self.client(mcp_id, self.server_spec(&mcp).await?).await?;
+                let output = client
+                    .call_tool(tool_name, args, DEFAULT_TOOL_TIMEOUT)
+                    .await?;
+                if output.is_error {
                     return Err(AppError::McpError {
                         mcp_id: mcp_id.to_string(),
-                        message: format!("Tool failed: {stderr}"),
+                        message: format!("Tool failed: {}", output.text),
                     });
                 }
-
-                String::from_utf8_lossy(&result.stdout).to_string()
+                output.text
             }
             _ => {
                 return Err(AppError::McpError {
@@ -182,6 +1179,8 @@ impl McpService {
         })
     }
 
+    /// Cheap status for every active MCP: configuration problems are caught
+    /// here, live status comes from the last `probe_health` run.
     pub async fn health_check_all(&self) -> Result<Vec<McpHealthStatus>, AppError> {
         let mcps = self.repo.list_mcps(true).await?;
         let mut statuses = Vec::new();
@@ -192,40 +1191,144 @@ impl McpService {
             }
 
             let health = if mcp.mcp_type == "builtin" {
-                McpHealthStatus {
-                    mcp_id: mcp.id.clone(),
-                    health_status: "healthy".to_string(),
-                    last_error: None,
-                }
-            } else if mcp.mcp_type == "stdio" {
-                if mcp.command.is_some() {
-                    McpHealthStatus {
-                        mcp_id: mcp.id.clone(),
-                        health_status: "healthy".to_string(),
-                        last_error: None,
-                    }
-                } else {
-                    McpHealthStatus {
-                        mcp_id: mcp.id.clone(),
-                        health_status: "down".to_string(),
-                        last_error: Some("Missing command".to_string()),
-                    }
-                }
+                health_status(&mcp, "healthy", None)
+            } else if let Err(error) = McpServerSpec::from_mcp(&mcp) {
+                health_status(&mcp, "down", Some(error.to_string()))
             } else {
-                McpHealthStatus {
-                    mcp_id: mcp.id.clone(),
-                    health_status: "unknown".to_string(),
-                    last_error: None,
-                }
+                health_status(&mcp, &mcp.health_status, mcp.last_error.clone())
             };
 
+            if health.health_status != mcp.health_status || health.last_error != mcp.last_error {
+                self.repo
+                    .update_health(&mcp.id, &health.health_status, health.last_error.as_deref())
+                    .await?;
+            }
+            statuses.push(health);
+        }
+
+        Ok(statuses)
+    }
+
+    /// Pings every active MCP, restarting crashed servers with exponential
+    /// backoff. Returns the statuses that changed since the previous check.
+    pub async fn probe_health(&self) -> Result<Vec<McpHealthStatus>, AppError> {
+        let mut changed = Vec::new();
+        for mcp in self.repo.list_mcps(true).await? {
+            if mcp.is_active == 0 {
+                continue;
+            }
+
+            let health = self.check_health(&mcp).await;
             self.repo
                 .update_health(&mcp.id, &health.health_status, health.last_error.as_deref())
                 .await?;
-            statuses.push(health);
+            if health.health_status != mcp.health_status || health.last_error != mcp.last_error {
+                changed.push(health);
+            }
         }
+        Ok(changed)
+    }
 
-        Ok(statuses)
+    /// Live status of one MCP. Connected servers are pinged with `tools/list`
+    /// and crashed ones are reconnected once their backoff has elapsed; servers
+    /// that were never started, or were closed while idle, are left alone.
+    pub async fn check_health(&self, mcp: &Mcp) -> McpHealthStatus {
+        if mcp.mcp_type == "builtin" {
+            return health_status(mcp, "healthy", None);
+        }
+        let spec = match self.server_spec(mcp).await {
+            Ok(spec) => spec,
+            Err(error) => return health_status(mcp, "down", Some(error.to_string())),
+        };
+
+        let client = self
+            .clients
+            .get(&mcp.id)
+            .map(|client| Arc::clone(client.value()));
+        match client {
+            Some(client) if client.is_alive() => match client.ping(HEALTH_PING_TIMEOUT).await {
+                Ok(()) => {
+                    self.restarts.remove(&mcp.id);
+                    health_status(mcp, "healthy", None)
+                }
+                Err(error) => health_status(mcp, "degraded", Some(error.to_string())),
+            },
+            Some(_) => self.restart_crashed(mcp, spec).await,
+            None if self.restarts.contains_key(&mcp.id) => self.restart_crashed(mcp, spec).await,
+            None => health_status(mcp, "idle", None),
+        }
+    }
+
+    async fn restart_crashed(&self, mcp: &Mcp, spec: McpServerSpec) -> McpHealthStatus {
+        let backoff = self
+            .restarts
+            .get(&mcp.id)
+            .map(|entry| *entry.value())
+            .unwrap_or_else(|| RestartBackoff {
+                failures: 0,
+                next_attempt_at: Instant::now(),
+            });
+        if backoff.next_attempt_at > Instant::now() {
+            let error = mcp
+                .last_error
+                .clone()
+                .unwrap_or_else(|| "Server stopped; waiting to restart".to_string());
+            return health_status(mcp, "down", Some(error));
+        }
+
+        match self.client(&mcp.id, spec).await {
+            Ok(_) => {
+                self.restarts.remove(&mcp.id);
+                crate::log_info!("sarah.mcp", "Restarted MCP server {}", mcp.id);
+                health_status(mcp, "healthy", None)
+            }
+            Err(error) => {
+                let failures = backoff.failures + 1;
+                let delay = RestartBackoff::delay(failures);
+                self.restarts.insert(
+                    mcp.id.clone(),
+                    RestartBackoff {
+                        failures,
+                        next_attempt_at: Instant::now() + delay,
+                    },
+                );
+                crate::log_warn!(
+                    "sarah.mcp",
+                    "Restart of MCP server {} failed (attempt {}), retrying in {}s: {}",
+                    mcp.id,
+                    failures,
+                    delay.as_secs(),
+                    error
+                );
+                health_status(mcp, "down", Some(error.to_string()))
+            }
+        }
+    }
+
+    /// Tools of every active MCP as stored on its last connect. When two MCPs
+    /// expose the same name the first one listed wins.
+    pub async fn active_tools(&self) -> Result<Vec<McpTool>, AppError> {
+        let mut tools: Vec<McpTool> = Vec::new();
+        for mcp in self.repo.list_mcps(true).await? {
+            if mcp.is_active == 0 {
+                continue;
+            }
+            let schemas = serde_json::from_str::<Vec<Value>>(&mcp.tool_schemas).unwrap_or_default();
+            for schema in schemas {
+                let Some(name) = schema.get("name").and_then(Value::as_str) else {
+                    continue;
+                };
+                if tools.iter().any(|tool| tool.name == name) {
+                    continue;
+                }
+                tools.push(McpTool {
+                    mcp_id: mcp.id.clone(),
+                    name: name.to_string(),
+                    schema: schema.clone(),
+                });
+            }
+        }
+        Ok(tools)
     }
 
     pub async fn route_mcps_for_query(
@@ -275,6 +1378,67 @@ impl McpService {
             .await
     }
 
+    /// `McpServerSpec::from_mcp` with a remote server's headers decrypted.
+    pub async fn server_spec(&self, mcp: &Mcp) -> Result<McpServerSpec, AppError> {
+        let mut spec = McpServerSpec::from_mcp(mcp)?;
+        if let McpServerSpec::Http(http) = &mut spec {
+            for name in HttpServerSpec::header_names(mcp) {
+                let value = self
+                    .decrypt_mcp_secret(&mcp.id, REMOTE_SECRET_USER_ID, &header_secret_key(&name))
+                    .await?;
+                if let Some(value) = value {
+                    http.headers.insert(name, value);
+                }
+            }
+        }
+        Ok(spec)
+    }
+
+    /// Stores a remote server's header values encrypted. Their names belong in
+    /// the MCP's `metadata.headerNames`.
+    pub async fn save_remote_headers(
+        &self,
+        mcp_id: &str,
+        headers: &HashMap<String, String>,
+    ) -> Result<(), AppError> {
+        for (name, value) in headers {
+            self.save_mcp_secret(
+                mcp_id,
+                REMOTE_SECRET_USER_ID,
+                &header_secret_key(name),
+                value,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Moves header values that older builds kept in plaintext under
+    /// `metadata.headers` into `mcp_secrets`, leaving only their names.
+    pub async fn seal_plaintext_headers(&self) -> Result<(), AppError> {
+        for mcp in self.repo.list_mcps(false).await? {
+            let Ok(Value::Object(mut metadata)) = serde_json::from_str::<Value>(&mcp.metadata)
+            else {
+                continue;
+            };
+            let Some(headers) = metadata.remove("headers").and_then(|headers| {
+                serde_json::from_value::<HashMap<String, String>>(headers).ok()
+            }) else {
+                continue;
+            };
+            self.save_remote_headers(&mcp.id, &headers).await?;
+            metadata.insert(
+                "headerNames".to_string(),
+                Value::from(headers.into_keys().collect::<Vec<_>>()),
+            );
+            self.repo
+                .set_metadata(&mcp.id, &Value::Object(metadata))
+                .await?;
+            crate::log_info!("sarah.mcp", "Encrypted stored headers of MCP {}", mcp.id);
+        }
+        Ok(())
+    }
+
     pub async fn decrypt_mcp_secret(
         &self,
         mcp_id: &str,
@@ -293,6 +1457,10 @@ impl McpService {
         Ok(Some(output))
     }
 
+    pub fn connection_count(&self) -> usize {
+        self.clients.len()
+    }
+
     pub async fn cleanup_idle_connections(&self, idle_ttl: Duration) -> Result<(), AppError> {
         let mut remove_ids = Vec::new();
         for entry in self.pool.iter() {
@@ -305,6 +1473,18 @@ impl McpService {
             self.pool.remove(&id);
         }
 
+        let idle_clients = self
+            .clients
+            .iter()
+            .filter(|entry| !entry.value().is_alive() || entry.value().idle_for() > idle_ttl)
+            .map(|entry| entry.key().clone())
+            .collect::<Vec<_>>();
+        for key in idle_clients {
+            if let Some((_, client)) = self.clients.remove(&key) {
+                client.shutdown().await;
+            }
+        }
+
         Ok(())
     }
 
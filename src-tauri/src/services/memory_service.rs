@@ -1,12 +1,37 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Instant;
 
-use crate::db::models::{Memory, Message, NewMemory};
+use crate::db::models::{
+    Memory, MemoryConsolidationReport, MemoryDecayPolicy, MemoryDecayReport, MemoryGraph,
+    MemoryPatch, Message, NewMemory,
+};
 use crate::error::AppError;
 use crate::repositories::embedding_repo::EmbeddingRepo;
 use crate::repositories::memory_repo::MemoryRepo;
+use crate::repositories::settings_repo::SettingsRepo;
 use crate::services::embedding_service::EmbeddingService;
 use crate::services::inference_service::InferenceService;
+use crate::services::webhook_service::{WebhookEvent, WebhookService};
+
+pub const MEMORY_SETTINGS_NAMESPACE: &str = "memory";
+const DECAY_POLICY_KEY: &str = "decay_policy";
+const DECAY_LAST_RUN_KEY: &str = "decay_last_run_at";
+/// A pass after a long shutdown catches up at most this many days at once.
+const MAX_DECAY_DAYS: f64 = 30.0;
+
+/// Subjects too generic to relate memories by; nearly every memory is about
+/// the user.
+const GENERIC_SUBJECTS: &[&str] = &["user", "the user", "me", "you", "sarah", "assistant"];
+const MAX_ENTITIES_PER_MEMORY: usize = 6;
+const MAX_LINKS_PER_MEMORY: usize = 8;
+const MAX_GRAPH_DEPTH: i64 = 4;
+const MAX_GRAPH_NODES: usize = 200;
+
+/// Cosine similarity above which two memories count as the same fact.
+const DUPLICATE_SIMILARITY: f32 = 0.92;
+/// Most memories compared in one consolidation pass, by importance.
+const CONSOLIDATION_BATCH: i64 = 500;
 
 #[derive(Clone)]
 pub struct MemoryService {
@@ -14,6 +39,8 @@ pub struct MemoryService {
     embedding_repo: EmbeddingRepo,
     embedding_service: Option<Arc<EmbeddingService>>,
     inference_service: InferenceService,
+    settings_repo: SettingsRepo,
+    webhooks: WebhookService,
 }
 
 impl MemoryService {
@@ -22,12 +49,16 @@ impl MemoryService {
         embedding_repo: EmbeddingRepo,
         embedding_service: Option<Arc<EmbeddingService>>,
         inference_service: InferenceService,
+        settings_repo: SettingsRepo,
+        webhooks: WebhookService,
     ) -> Self {
         Self {
             memory_repo,
             embedding_repo,
             embedding_service,
             inference_service,
+            settings_repo,
+            webhooks,
         }
     }
 
@@ -127,9 +158,31 @@ impl MemoryService {
             .ok_or_else(|| AppError::Embedding("Embedding service not available".to_string()))?;
 
         let query_vec = embedding.embed_text(query).await?;
+        let mut vector_scores: HashMap<String, f32> = self
+            .embedding_repo
+            .search_nearest("memory", user_id, "memory", &query_vec, limit * 4)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+
+        // Nearest neighbours can match in meaning without sharing a word.
+        let mut candidate_memories = candidate_memories;
+        for id in vector_scores.keys() {
+            if candidate_memories.iter().any(|memory| &memory.id == id) {
+                continue;
+            }
+            if let Some(memory) = self.memory_repo.get_memory(id).await? {
+                if memory.is_archived == 0 {
+                    candidate_memories.push(memory);
+                }
+            }
+        }
+
         let mut candidate_ids: Vec<String> = candidate_memories
             .iter()
             .map(|memory| memory.id.clone())
+            .filter(|id| !vector_scores.contains_key(id))
             .collect();
         candidate_ids.sort();
         candidate_ids.dedup();
@@ -140,7 +193,6 @@ impl MemoryService {
             .await
             .unwrap_or_default();
 
-        let mut vector_scores: HashMap<String, f32> = HashMap::new();
         for row in all_embeddings {
             let vec = crate::repositories::blob_to_vector(&row.vector);
             if vec.len() != query_vec.len() {
@@ -170,48 +222,229 @@ impl MemoryService {
         Ok(top)
     }
 
-    pub async fn apply_decay_job(&self, user_id: &str) -> Result<u64, AppError> {
-        self.memory_repo.apply_time_decay(user_id).await
+    pub async fn decay_policy(&self) -> Result<MemoryDecayPolicy, AppError> {
+        let stored = self
+            .settings_repo
+            .get_setting(None, MEMORY_SETTINGS_NAMESPACE, DECAY_POLICY_KEY)
+            .await?;
+        Ok(stored
+            .and_then(|setting| serde_json::from_str(&setting.value).ok())
+            .unwrap_or_default())
+    }
+
+    pub async fn set_decay_policy(
+        &self,
+        policy: MemoryDecayPolicy,
+    ) -> Result<MemoryDecayPolicy, AppError> {
+        if !(0.0..=1000.0).contains(&policy.decay_multiplier) {
+            return Err(AppError::Validation {
+                field: "decay_multiplier".to_string(),
+                message: "Must be between 0 and 1000".to_string(),
+            });
+        }
+        if !(0.0..=100.0).contains(&policy.access_weight) {
+            return Err(AppError::Validation {
+                field: "access_weight".to_string(),
+                message: "Must be between 0 and 100".to_string(),
+            });
+        }
+        if !(0.0..=1.0).contains(&policy.archive_below) {
+            return Err(AppError::Validation {
+                field: "archive_below".to_string(),
+                message: "Must be between 0 and 1".to_string(),
+            });
+        }
+
+        let value = serde_json::to_string(&policy)
+            .map_err(|e| AppError::Internal(format!("Failed to encode decay policy: {e}")))?;
+        self.settings_repo
+            .upsert_setting(
+                None,
+                MEMORY_SETTINGS_NAMESPACE,
+                DECAY_POLICY_KEY,
+                &value,
+                "json",
+                false,
+            )
+            .await?;
+        Ok(policy)
+    }
+
+    /// Deletes expired memories, then fades the rest by the time since the
+    /// last pass and archives those that drop below the policy's threshold.
+    /// Pinned memories never fade. Expiry applies even with decay disabled.
+    pub async fn apply_decay_job(&self, user_id: &str) -> Result<MemoryDecayReport, AppError> {
+        let mut report = MemoryDecayReport::default();
+
+        let expired = self.memory_repo.delete_expired(user_id).await?;
+        for id in &expired {
+            let _ = self
+                .embedding_repo
+                .delete_embedding_for_entity("memory", id)
+                .await;
+        }
+        report.expired = expired.len();
+
+        let policy = self.decay_policy().await?;
+        if !policy.enabled {
+            return Ok(report);
+        }
+
+        let now = chrono::Utc::now();
+        let last_run = self
+            .settings_repo
+            .get_setting(None, MEMORY_SETTINGS_NAMESPACE, DECAY_LAST_RUN_KEY)
+            .await?
+            .and_then(|setting| chrono::DateTime::parse_from_rfc3339(&setting.value).ok());
+        self.settings_repo
+            .upsert_setting(
+                None,
+                MEMORY_SETTINGS_NAMESPACE,
+                DECAY_LAST_RUN_KEY,
+                &now.to_rfc3339(),
+                "string",
+                false,
+            )
+            .await?;
+        // The first pass counts as one day.
+        let days = last_run
+            .map(|at| (now - at.with_timezone(&chrono::Utc)).num_seconds() as f64 / 86_400.0)
+            .unwrap_or(1.0)
+            .clamp(0.0, MAX_DECAY_DAYS);
+        report.elapsed_days = days;
+
+        let updates: Vec<(String, f64)> = self
+            .memory_repo
+            .decay_candidates(user_id)
+            .await?
+            .into_iter()
+            .filter_map(|(id, importance, decay_rate, access_count)| {
+                let slowdown = 1.0 + policy.access_weight * access_count.max(0) as f64;
+                let decayed = importance
+                    * (-decay_rate.max(0.0) * policy.decay_multiplier * days / slowdown).exp();
+                (decayed < importance).then_some((id, decayed.max(0.0)))
+            })
+            .collect();
+        self.memory_repo.set_importances(&updates).await?;
+        report.decayed = updates.len();
+        report.archived = self
+            .memory_repo
+            .archive_below(user_id, policy.archive_below)
+            .await?;
+
+        Ok(report)
     }
 
-    pub async fn consolidate_memories(&self, user_id: &str) -> Result<(), AppError> {
-        let Some(embedding) = &self.embedding_service else {
-            tracing::warn!("Cannot consolidate memories - embedding service not available");
-            return Ok(());
+    /// Merges near-duplicate memories. Memories of the same type whose
+    /// vectors are closer than `DUPLICATE_SIMILARITY` form a cluster; the most
+    /// confident one is kept and absorbs the others' access counts and
+    /// relations.
+    pub async fn consolidate_memories(
+        &self,
+        user_id: &str,
+    ) -> Result<MemoryConsolidationReport, AppError> {
+        let started = Instant::now();
+        let mut report = MemoryConsolidationReport {
+            user_id: user_id.to_string(),
+            ..Default::default()
         };
 
-        if !embedding.is_initialized() {
+        let Some(embedding) = self
+            .embedding_service
+            .as_ref()
+            .filter(|embedding| embedding.is_initialized())
+        else {
             tracing::warn!("Cannot consolidate memories - embedding model not loaded");
-            return Ok(());
-        }
+            return Ok(report);
+        };
 
-        let memories = self.memory_repo.get_memories(user_id, None, 500).await?;
+        let mut memories = self
+            .memory_repo
+            .get_memories(user_id, None, CONSOLIDATION_BATCH)
+            .await?;
+        report.memories_scanned = memories.len();
 
-        let mut merged_ids = std::collections::HashSet::new();
-        for i in 0..memories.len() {
-            if merged_ids.contains(&memories[i].id) {
+        let ids: Vec<String> = memories.iter().map(|memory| memory.id.clone()).collect();
+        let mut vectors: HashMap<String, Vec<f32>> = self
+            .embedding_repo
+            .get_embeddings_for_entities("memory", user_id, "memory", &ids)
+            .await?
+            .into_iter()
+            .map(|row| {
+                (
+                    row.entity_id,
+                    crate::repositories::blob_to_vector(&row.vector),
+                )
+            })
+            .collect();
+        // Memories saved while the embedder was off have no vector yet.
+        for memory in &memories {
+            if vectors.contains_key(&memory.id) {
                 continue;
             }
+            embedding
+                .embed_and_store("memory", &memory.id, user_id, "memory", &memory.content)
+                .await?;
+            vectors.insert(
+                memory.id.clone(),
+                embedding.embed_text(&memory.content).await?,
+            );
+            report.embedded += 1;
+        }
 
-            for j in (i + 1)..memories.len() {
-                if merged_ids.contains(&memories[j].id) {
-                    continue;
-                }
+        memories.sort_by(|a, b| {
+            b.confidence
+                .partial_cmp(&a.confidence)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(
+                    b.importance
+                        .partial_cmp(&a.importance)
+                        .unwrap_or(std::cmp::Ordering::Equal),
+                )
+        });
+
+        let mut merged = HashSet::new();
+        for (index, keeper) in memories.iter().enumerate() {
+            if merged.contains(&keeper.id) {
+                continue;
+            }
+            let Some(keeper_vec) = vectors.get(&keeper.id) else {
+                continue;
+            };
 
-                let left_vec = embedding.embed_text(&memories[i].content).await?;
-                let right_vec = embedding.embed_text(&memories[j].content).await?;
-                let similarity = cosine_similarity(&left_vec, &right_vec);
-                if similarity > 0.92 {
-                    merged_ids.insert(memories[j].id.clone());
-                }
+            let duplicates: Vec<String> = memories[index + 1..]
+                .iter()
+                .filter(|other| {
+                    other.memory_type == keeper.memory_type && !merged.contains(&other.id)
+                })
+                .filter(|other| {
+                    vectors.get(&other.id).is_some_and(|other_vec| {
+                        other_vec.len() == keeper_vec.len()
+                            && cosine_similarity(keeper_vec, other_vec) > DUPLICATE_SIMILARITY
+                    })
+                })
+                .map(|other| other.id.clone())
+                .collect();
+            if duplicates.is_empty() {
+                continue;
             }
-        }
 
-        for id in merged_ids {
-            let _ = self.memory_repo.delete_memory(&id).await;
+            self.memory_repo
+                .merge_memories(&keeper.id, &duplicates)
+                .await?;
+            for id in &duplicates {
+                let _ = self
+                    .embedding_repo
+                    .delete_embedding_for_entity("memory", id)
+                    .await;
+            }
+            report.clusters += 1;
+            report.merged += duplicates.len();
+            merged.extend(duplicates);
         }
 
-        Ok(())
+        report.duration_ms = started.elapsed().as_millis() as i64;
+        Ok(report)
     }
 
     pub async fn build_memory_context(
@@ -245,7 +478,7 @@ impl MemoryService {
         let embedding_opt = self.embedding_service.as_ref();
 
         for memory in extracted {
-            let row = self.memory_repo.upsert_memory(memory).await?;
+            let (row, inserted) = self.memory_repo.upsert_memory(memory).await?;
             if let Some(embedding) = embedding_opt {
                 if embedding.is_initialized() {
                     let _ = embedding
@@ -253,19 +486,116 @@ impl MemoryService {
                         .await;
                 }
             }
+            if let Err(error) = self.link_related(&row).await {
+                tracing::warn!("Failed to link memory {}: {error}", row.id);
+            }
+            if inserted {
+                self.webhooks
+                    .publish(
+                        Some(&row.user_id),
+                        WebhookEvent::MemoryCreated,
+                        serde_json::to_value(&row).unwrap_or_default(),
+                    )
+                    .await;
+            }
             saved.push(row);
         }
         Ok(saved)
     }
 
+    /// Applies a user's edit, re-embedding the memory when its text changed.
+    pub async fn update_memory(&self, id: &str, patch: &MemoryPatch) -> Result<Memory, AppError> {
+        let row = self.memory_repo.update_memory(id, patch).await?;
+        if patch.content.is_some() && self.is_embedding_available() {
+            if let Some(embedding) = &self.embedding_service {
+                embedding
+                    .embed_and_store("memory", &row.id, &row.user_id, "memory", &row.content)
+                    .await?;
+            }
+        }
+        Ok(row)
+    }
+
+    /// Removes a memory and its vector, so it can't come back through
+    /// semantic retrieval.
+    pub async fn delete_memory(&self, id: &str) -> Result<(), AppError> {
+        self.memory_repo.delete_memory(id).await?;
+        self.embedding_repo
+            .delete_embedding_for_entity("memory", id)
+            .await
+    }
+
+    /// Deletes everything remembered about `subject`. Returns how many
+    /// memories were removed.
+    pub async fn forget_about(&self, user_id: &str, subject: &str) -> Result<usize, AppError> {
+        let memories = self.memory_repo.memories_about(user_id, subject).await?;
+        for memory in &memories {
+            self.delete_memory(&memory.id).await?;
+        }
+        Ok(memories.len())
+    }
+
+    /// Relates a memory to others about the same things: a shared subject or
+    /// object, or a name both mention. The most overlapping ones are linked
+    /// first. Returns how many relations were added.
+    async fn link_related(&self, memory: &Memory) -> Result<usize, AppError> {
+        let mut shared: HashMap<String, usize> = HashMap::new();
+        for entity in memory_entities(memory) {
+            for other in self
+                .memory_repo
+                .memories_about(&memory.user_id, &entity)
+                .await?
+            {
+                if other.id != memory.id && other.is_archived == 0 {
+                    *shared.entry(other.id).or_default() += 1;
+                }
+            }
+        }
+
+        let mut ranked: Vec<(String, usize)> = shared.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        let mut linked = 0;
+        for (other_id, count) in ranked.into_iter().take(MAX_LINKS_PER_MEMORY) {
+            let strength = (0.3 + 0.2 * count as f64).min(1.0);
+            if self
+                .memory_repo
+                .link_memories(
+                    &memory.user_id,
+                    &memory.id,
+                    &other_id,
+                    "shares_entity",
+                    strength,
+                )
+                .await?
+            {
+                linked += 1;
+            }
+        }
+        Ok(linked)
+    }
+
+    /// The knowledge graph around `root_memory_id`, `depth` hops out.
     pub async fn get_memory_graph(
         &self,
-        user_id: &str,
-        memory_id: &str,
+        root_memory_id: &str,
         depth: i64,
-    ) -> Result<crate::db::models::MemoryGraph, AppError> {
+    ) -> Result<MemoryGraph, AppError> {
+        let root = self
+            .memory_repo
+            .get_memory(root_memory_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound {
+                entity: "memory".to_string(),
+                id: root_memory_id.to_string(),
+            })?;
         self.memory_repo
-            .get_memory_graph(user_id, memory_id, depth)
+            .get_memory_graph(
+                &root.user_id,
+                root_memory_id,
+                depth.clamp(1, MAX_GRAPH_DEPTH),
+                MAX_GRAPH_NODES,
+            )
             .await
     }
 
@@ -285,9 +615,11 @@ impl MemoryService {
             is_error: 0,
             error_message: None,
             parent_message_id: None,
+            is_active_variant: 1,
             edited_at: None,
             original_content: None,
             metadata: "{}".to_string(),
+            attachments: "[]".to_string(),
             position: 0,
             created_at: String::new(),
             updated_at: String::new(),
@@ -299,6 +631,46 @@ impl MemoryService {
     }
 }
 
+/// What a memory is about: its subject and object unless generic, then runs
+/// of capitalised words after the first, as a cheap stand-in for names.
+fn memory_entities(memory: &Memory) -> Vec<String> {
+    let mut entities: Vec<String> = Vec::new();
+    let mut push = |entity: &str| {
+        let entity = entity.trim();
+        if entity.len() >= 3
+            && !GENERIC_SUBJECTS.contains(&entity.to_lowercase().as_str())
+            && !entities
+                .iter()
+                .any(|existing| existing.eq_ignore_ascii_case(entity))
+        {
+            entities.push(entity.to_string());
+        }
+    };
+
+    for field in [&memory.subject, &memory.object].into_iter().flatten() {
+        push(field);
+    }
+
+    let mut run: Vec<&str> = Vec::new();
+    for (index, word) in memory.content.split_whitespace().enumerate() {
+        let word = word.trim_matches(|ch: char| !ch.is_alphanumeric());
+        if index > 0 && word.chars().next().is_some_and(char::is_uppercase) {
+            run.push(word);
+            continue;
+        }
+        if !run.is_empty() {
+            push(&run.join(" "));
+            run.clear();
+        }
+    }
+    if !run.is_empty() {
+        push(&run.join(" "));
+    }
+
+    entities.truncate(MAX_ENTITIES_PER_MEMORY);
+    entities
+}
+
 fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     let mut dot = 0.0f32;
     let mut norm_a = 0.0f32;
@@ -1,23 +1,53 @@
 pub mod adaptive_memory_manager;
 pub mod analytics_service;
 pub mod background_service;
+pub mod benchmark_report;
+pub mod capture_library;
+pub mod chat_template;
+pub mod clarification_service;
 pub mod context_service;
 pub mod conversation_service;
 pub mod crypto_service;
+pub mod document_parser;
 pub mod embedding_service;
+pub mod filesystem_tools;
+pub mod generation_presets;
 pub mod hardware_service;
+pub mod history_search;
+pub mod import_service;
+pub mod inference_queue;
 pub mod inference_service;
 pub mod intent_service;
+pub mod json_grammar;
+pub mod language_detector;
+pub mod launch_state_service;
+pub mod local_api_service;
 pub mod mcp_service;
 pub mod memory_service;
 pub mod model_manager_service;
+pub mod notification_service;
+pub mod ocr;
 pub mod predictive_preloader;
+pub mod prompt_cache;
 pub mod rag_service;
 pub mod recommendation_service;
+pub mod recovery_service;
+pub mod reindex_service;
 pub mod reranker_service;
+pub mod retention_service;
 pub mod runtime_governor_service;
 pub mod runtime_orchestrator_service;
+pub mod session_export;
+pub mod settings_watcher;
 pub mod setup_orchestrator_service;
+pub mod shell_tool;
 pub mod smart_query_classifier;
+pub mod speech_service;
+pub mod stream_coalescer;
+pub mod takeout_service;
 pub mod task_router_service;
+pub mod tool_approval_service;
+pub mod tts_service;
 pub mod usage_learner;
+pub mod wake_word_service;
+pub mod webhook_service;
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::error::AppError;
+use crate::repositories::settings_repo::SettingsRepo;
+
+pub const NOTIFICATION_SETTINGS_NAMESPACE: &str = "notifications";
+const NOTIFICATION_PREFERENCES_KEY: &str = "preferences";
+
+/// Notifications show the start of long bodies, such as a model's reply.
+const MAX_BODY_CHARS: usize = 240;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationCategory {
+    ModelDownloads,
+    Setup,
+    ModelUpgrades,
+    Captures,
+    ScheduledPrompts,
+    DeepLinks,
+}
+
+/// Which desktop notifications the user wants. All are on by default.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationPreferences {
+    pub enabled: bool,
+    /// Held back while a Sarah window has focus, since the window already
+    /// shows the outcome.
+    pub only_when_unfocused: bool,
+    pub model_downloads: bool,
+    pub setup: bool,
+    pub model_upgrades: bool,
+    pub captures: bool,
+    pub scheduled_prompts: bool,
+    /// Replies to `sarah://ask` links opened with `silent=1`.
+    pub deep_links: bool,
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            only_when_unfocused: true,
+            model_downloads: true,
+            setup: true,
+            model_upgrades: true,
+            captures: true,
+            scheduled_prompts: true,
+            deep_links: true,
+        }
+    }
+}
+
+impl NotificationPreferences {
+    fn allows(&self, category: NotificationCategory) -> bool {
+        self.enabled
+            && match category {
+                NotificationCategory::ModelDownloads => self.model_downloads,
+                NotificationCategory::Setup => self.setup,
+                NotificationCategory::ModelUpgrades => self.model_upgrades,
+                NotificationCategory::Captures => self.captures,
+                NotificationCategory::ScheduledPrompts => self.scheduled_prompts,
+                NotificationCategory::DeepLinks => self.deep_links,
+            }
+    }
+}
+
+/// Desktop notifications for work that finishes while the user looks away.
+#[derive(Clone)]
+pub struct NotificationService {
+    app_handle: tauri::AppHandle,
+    settings_repo: SettingsRepo,
+}
+
+impl NotificationService {
+    pub fn new(app_handle: tauri::AppHandle, settings_repo: SettingsRepo) -> Self {
+        Self {
+            app_handle,
+            settings_repo,
+        }
+    }
+
+    pub async fn preferences(&self) -> Result<NotificationPreferences, AppError> {
+        let stored = self
+            .settings_repo
+            .get_setting(
+                None,
+                NOTIFICATION_SETTINGS_NAMESPACE,
+                NOTIFICATION_PREFERENCES_KEY,
+            )
+            .await?;
+        Ok(stored
+            .and_then(|setting| serde_json::from_str(&setting.value).ok())
+            .unwrap_or_default())
+    }
+
+    pub async fn set_preferences(
+        &self,
+        preferences: NotificationPreferences,
+    ) -> Result<NotificationPreferences, AppError> {
+        let value = serde_json::to_string(&preferences).map_err(|e| {
+            AppError::Internal(format!("Failed to encode notification preferences: {e}"))
+        })?;
+        self.settings_repo
+            .upsert_setting(
+                None,
+                NOTIFICATION_SETTINGS_NAMESPACE,
+                NOTIFICATION_PREFERENCES_KEY,
+                &value,
+                "json",
+                false,
+            )
+            .await?;
+        Ok(preferences)
+    }
+
+    /// Shows a notification if the user allows `category`. Failures are
+    /// logged, never returned: a missed notification shouldn't fail the work
+    /// it reports on.
+    pub async fn notify(&self, category: NotificationCategory, title: &str, body: &str) {
+        let preferences = match self.preferences().await {
+            Ok(preferences) => preferences,
+            Err(error) => {
+                tracing::warn!("Failed to read notification preferences: {error}");
+                NotificationPreferences::default()
+            }
+        };
+        if !preferences.allows(category) {
+            return;
+        }
+        if preferences.only_when_unfocused && self.app_has_focus() {
+            return;
+        }
+
+        let body: String = body.trim().chars().take(MAX_BODY_CHARS).collect();
+        if let Err(error) = self
+            .app_handle
+            .notification()
+            .builder()
+            .title(title)
+            .body(body)
+            .show()
+        {
+            tracing::warn!("Failed to show notification: {error}");
+        }
+    }
+
+    fn app_has_focus(&self) -> bool {
+        self.app_handle
+            .webview_windows()
+            .values()
+            .any(|window| window.is_focused().unwrap_or(false))
+    }
+}
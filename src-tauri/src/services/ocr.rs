@@ -0,0 +1,134 @@
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::error::AppError;
+
+/// Formats both engines decode.
+pub const OCR_IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "bmp", "gif", "tif", "tiff"];
+const TESSERACT_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrText {
+    /// Recognized lines joined with newlines, top to bottom.
+    pub text: String,
+    pub line_count: usize,
+    /// `windows` (Windows.Media.Ocr) or `tesseract`.
+    pub engine: String,
+}
+
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|extension| OCR_IMAGE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Reads the text in an image. Windows' built-in engine is used when it has a
+/// language pack for the user's languages; otherwise a `tesseract` on the PATH.
+pub async fn recognize_text(path: &Path) -> Result<OcrText, AppError> {
+    if !is_image(path) {
+        return Err(AppError::Validation {
+            field: "path".to_string(),
+            message: format!(
+                "Unsupported image type; expected one of: {}",
+                OCR_IMAGE_EXTENSIONS.join(", ")
+            ),
+        });
+    }
+    if !tokio::fs::try_exists(path).await.unwrap_or(false) {
+        return Err(AppError::NotFound {
+            entity: "image".to_string(),
+            id: path.display().to_string(),
+        });
+    }
+
+    #[cfg(windows)]
+    {
+        let image = path.to_path_buf();
+        match tokio::task::spawn_blocking(move || windows_ocr(&image)).await {
+            Ok(Ok(lines)) => return Ok(ocr_text(lines, "windows")),
+            Ok(Err(error)) => {
+                crate::log_warn!(
+                    "sarah.ocr",
+                    "Windows OCR failed, trying tesseract: {}",
+                    error
+                );
+            }
+            Err(error) => return Err(AppError::Internal(error.to_string())),
+        }
+    }
+
+    let lines = tesseract_ocr(path).await?;
+    Ok(ocr_text(lines, "tesseract"))
+}
+
+fn ocr_text(lines: Vec<String>, engine: &str) -> OcrText {
+    let lines = lines
+        .into_iter()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    OcrText {
+        text: lines.join("\n"),
+        line_count: lines.len(),
+        engine: engine.to_string(),
+    }
+}
+
+#[cfg(windows)]
+fn windows_ocr(path: &Path) -> windows::core::Result<Vec<String>> {
+    use windows::core::HSTRING;
+    use windows::Graphics::Imaging::BitmapDecoder;
+    use windows::Media::Ocr::OcrEngine;
+    use windows::Storage::{FileAccessMode, StorageFile};
+
+    let file = StorageFile::GetFileFromPathAsync(&HSTRING::from(path))?.get()?;
+    let stream = file.OpenAsync(FileAccessMode::Read)?.get()?;
+    let decoder = BitmapDecoder::CreateAsync(&stream)?.get()?;
+    let bitmap = decoder.GetSoftwareBitmapAsync()?.get()?;
+    // Fails when none of the user's languages has an OCR pack installed.
+    let engine = OcrEngine::TryCreateFromUserProfileLanguages()?;
+    let result = engine.RecognizeAsync(&bitmap)?.get()?;
+
+    let mut lines = Vec::new();
+    for line in result.Lines()? {
+        lines.push(line.Text()?.to_string_lossy());
+    }
+    Ok(lines)
+}
+
+async fn tesseract_ocr(path: &Path) -> Result<Vec<String>, AppError> {
+    let mut command = tokio::process::Command::new("tesseract");
+    command
+        .arg(path)
+        .arg("stdout")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let output = tokio::time::timeout(TESSERACT_TIMEOUT, command.output())
+        .await
+        .map_err(|_| AppError::Timeout("Text recognition timed out".to_string()))?
+        .map_err(|error| {
+            AppError::Config(format!(
+                "No OCR engine available; install an OCR language pack in Windows settings or Tesseract ({error})"
+            ))
+        })?;
+    if !output.status.success() {
+        return Err(AppError::Internal(format!(
+            "tesseract exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+use llama_cpp_2::context::params::LlamaContextParams;
+use llama_cpp_2::context::LlamaContext;
+use llama_cpp_2::llama_backend::LlamaBackend;
+use llama_cpp_2::model::LlamaModel;
+use llama_cpp_2::token::LlamaToken;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+/// Each cached context pins a KV cache of up to 8k tokens, so only the most
+/// recently used sessions keep theirs.
+const MAX_CACHED_SESSIONS: usize = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionCacheStats {
+    pub session_id: String,
+    /// Tokens currently held in the session's KV cache.
+    pub cached_tokens: usize,
+    pub context_size: usize,
+    pub turns: u64,
+    /// Prompt tokens of the last turn, and how many of them came from the cache.
+    pub last_prompt_tokens: usize,
+    pub last_reused_tokens: usize,
+    pub total_reused_tokens: u64,
+    pub last_used_at: String,
+}
+
+struct CachedContext {
+    context: LlamaContext<'static>,
+    /// Exactly the tokens whose keys and values are in `context`, in order.
+    tokens: Vec<LlamaToken>,
+    context_size: usize,
+    stats: SessionCacheStats,
+}
+
+/// Keeps one llama.cpp context alive per chat session so a follow-up turn only
+/// decodes what changed since the previous prompt.
+///
+/// Contexts borrow the model they were created from. The cache is owned by the
+/// loaded model alongside a boxed `LlamaModel`, declared before it so it is
+/// dropped first; that is what makes the `'static` contexts sound.
+#[derive(Default)]
+pub struct PromptCache {
+    entries: HashMap<String, CachedContext>,
+}
+
+// SAFETY: a llama context is not tied to the thread that created it; the cache
+// is only ever reached through the inference mutex, so it is never shared.
+unsafe impl Send for PromptCache {}
+
+impl PromptCache {
+    /// Returns the session's context with everything after the longest prefix
+    /// shared with `prompt` evicted, plus the length of that prefix. At least the
+    /// last prompt token is always left to decode so there are fresh logits. A
+    /// context smaller than `required_size` is rebuilt from `params`.
+    ///
+    /// # Safety
+    ///
+    /// `model` must outlive this cache.
+    pub unsafe fn checkout(
+        &mut self,
+        session_id: &str,
+        model: &LlamaModel,
+        backend: &LlamaBackend,
+        params: LlamaContextParams,
+        required_size: usize,
+        prompt: &[LlamaToken],
+    ) -> Result<(&mut LlamaContext<'static>, usize), AppError> {
+        let reusable = self
+            .entries
+            .get(session_id)
+            .is_some_and(|entry| entry.context_size >= required_size);
+        if !reusable {
+            self.entries.remove(session_id);
+            if self.entries.len() >= MAX_CACHED_SESSIONS {
+                self.evict_least_recent();
+            }
+            let context_size = params
+                .n_ctx()
+                .map_or(required_size, |n_ctx| n_ctx.get() as usize);
+            let context = model
+                .new_context(backend, params)
+                .map_err(|e| AppError::Inference(format!("Failed to create llama context: {e}")))?;
+            // SAFETY: the caller guarantees `model` outlives the cache.
+            let context =
+                unsafe { std::mem::transmute::<LlamaContext<'_>, LlamaContext<'static>>(context) };
+            self.entries.insert(
+                session_id.to_string(),
+                CachedContext {
+                    context,
+                    tokens: Vec::new(),
+                    context_size,
+                    stats: empty_stats(session_id, context_size),
+                },
+            );
+        }
+
+        let entry = self
+            .entries
+            .get_mut(session_id)
+            .ok_or_else(|| AppError::Internal("Prompt cache entry vanished".to_string()))?;
+        let shared = entry
+            .tokens
+            .iter()
+            .zip(prompt)
+            .take_while(|(cached, fresh)| cached == fresh)
+            .count()
+            .min(prompt.len().saturating_sub(1));
+
+        let trimmed = entry
+            .context
+            .clear_kv_cache_seq(Some(0), Some(shared as u32), None)
+            .unwrap_or(false);
+        let reused = if trimmed {
+            shared
+        } else {
+            // Some architectures can't drop a range; start over instead.
+            entry.context.clear_kv_cache();
+            0
+        };
+        entry.tokens.truncate(reused);
+        Ok((&mut entry.context, reused))
+    }
+
+    /// Records what the context holds after a successful generation.
+    pub fn commit(
+        &mut self,
+        session_id: &str,
+        tokens: Vec<LlamaToken>,
+        prompt_tokens: usize,
+        reused: usize,
+    ) {
+        if let Some(entry) = self.entries.get_mut(session_id) {
+            entry.tokens = tokens;
+            entry.stats.cached_tokens = entry.tokens.len();
+            entry.stats.turns += 1;
+            entry.stats.last_prompt_tokens = prompt_tokens;
+            entry.stats.last_reused_tokens = reused;
+            entry.stats.total_reused_tokens += reused as u64;
+            entry.stats.last_used_at = chrono::Utc::now().to_rfc3339();
+        }
+    }
+
+    /// Drops a context whose KV state is unknown, e.g. after a failed decode.
+    pub fn invalidate(&mut self, session_id: &str) {
+        self.entries.remove(session_id);
+    }
+
+    pub fn stats(&self) -> Vec<SessionCacheStats> {
+        let mut stats = self
+            .entries
+            .values()
+            .map(|entry| entry.stats.clone())
+            .collect::<Vec<_>>();
+        stats.sort_by(|a, b| b.last_used_at.cmp(&a.last_used_at));
+        stats
+    }
+
+    fn evict_least_recent(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by(|(_, a), (_, b)| a.stats.last_used_at.cmp(&b.stats.last_used_at))
+            .map(|(session_id, _)| session_id.clone());
+        if let Some(session_id) = oldest {
+            self.entries.remove(&session_id);
+        }
+    }
+}
+
+fn empty_stats(session_id: &str, context_size: usize) -> SessionCacheStats {
+    SessionCacheStats {
+        session_id: session_id.to_string(),
+        cached_tokens: 0,
+        context_size,
+        turns: 0,
+        last_prompt_tokens: 0,
+        last_reused_tokens: 0,
+        total_reused_tokens: 0,
+        last_used_at: chrono::Utc::now().to_rfc3339(),
+    }
+}
@@ -1,19 +1,39 @@
 use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use calamine::Reader;
+use sha2::{Digest, Sha256};
 use sqlx::SqlitePool;
 use uuid::Uuid;
 
-use crate::db::models::{NewChunk, NewDocument, RankCandidate, RetrievedChunk};
+use crate::db::models::{
+    Citation, Document, NewChunk, NewDocument, RagCollectionSummary, RagStats, RankCandidate,
+    RetrievedChunk,
+};
 use crate::error::AppError;
 use crate::repositories::document_repo::DocumentRepo;
 use crate::repositories::embedding_repo::EmbeddingRepo;
+use crate::repositories::workspace_repo::WorkspaceRepo;
+use crate::services::document_parser::{self, TextBlock};
 use crate::services::embedding_service::EmbeddingService;
+use crate::services::ocr;
 use crate::services::reranker_service::RerankerService;
+use crate::services::runtime_governor_service::RuntimeGovernorService;
+
+const DEFAULT_RERANK_CANDIDATES: usize = 15;
+/// Pages larger than this are almost never documentation worth chunking.
+const MAX_URL_BYTES: usize = 5 * 1024 * 1024;
+const URL_FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+/// Namespace used when the user has no active workspace.
+pub const DEFAULT_NAMESPACE: &str = "personal";
+/// BM25 and vector search each contribute at most 20 ids to the fusion.
+pub const MAX_RERANK_CANDIDATES: usize = 40;
+/// Damping constant of reciprocal-rank fusion; larger values flatten the gap
+/// between top and lower ranks.
+const RRF_K: f64 = 60.0;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -23,6 +43,10 @@ pub struct TextChunk {
     pub token_count: i64,
     pub start_char: i64,
     pub end_char: i64,
+    pub page_number: Option<i64>,
+    pub section_title: Option<String>,
+    pub heading_path: Option<String>,
+    pub language: Option<String>,
 }
 
 #[derive(Clone)]
@@ -31,6 +55,8 @@ pub struct RagService {
     embedding_repo: EmbeddingRepo,
     embedding_service: Arc<EmbeddingService>,
     reranker_service: Arc<RerankerService>,
+    workspace_repo: WorkspaceRepo,
+    runtime_governor: RuntimeGovernorService,
     write_pool: SqlitePool,
 }
 
@@ -40,6 +66,8 @@ impl RagService {
         embedding_repo: EmbeddingRepo,
         embedding_service: Arc<EmbeddingService>,
         reranker_service: Arc<RerankerService>,
+        workspace_repo: WorkspaceRepo,
+        runtime_governor: RuntimeGovernorService,
         write_pool: SqlitePool,
     ) -> Self {
         Self {
@@ -47,10 +75,23 @@ impl RagService {
             embedding_repo,
             embedding_service,
             reranker_service,
+            workspace_repo,
+            runtime_governor,
             write_pool,
         }
     }
 
+    /// The active workspace's namespace, so ingestion and retrieval stay inside it.
+    pub async fn default_namespace(&self, user_id: &str) -> String {
+        self.workspace_repo
+            .active_workspace(user_id)
+            .await
+            .ok()
+            .flatten()
+            .map(|workspace| workspace.rag_namespace)
+            .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string())
+    }
+
     pub async fn ingest_document(
         &self,
         user_id: &str,
@@ -64,14 +105,12 @@ impl RagService {
             });
         }
 
-        let mime = mime_guess::from_path(path)
-            .first_raw()
-            .unwrap_or("application/octet-stream")
-            .to_string();
-
+        let mime = mime_type(path);
         let metadata = tokio::fs::metadata(path).await?;
-        let content = self.extract_text(path, &mime).await?;
-        let chunks = self.chunker(&content, 512, 64);
+        let checksum = file_checksum(path).await?;
+        let blocks = self.parse_document(path, &mime).await?;
+        let namespace = self.default_namespace(user_id).await;
+        let chunks = self.chunk_blocks(&blocks, 512, 64);
 
         let title = path
             .file_name()
@@ -89,42 +128,269 @@ impl RagService {
                 source_type: "file".to_string(),
                 mime_type: Some(mime),
                 file_size_bytes: Some(metadata.len() as i64),
-                namespace: "personal".to_string(),
-                checksum: None,
+                namespace,
+                checksum: Some(checksum),
                 metadata: "{}".to_string(),
             })
             .await?;
 
+        self.store_chunks(&document.id, user_id, chunks).await?;
+        Ok(document.id)
+    }
+
+    /// Brings the document for `path` in line with the file: new files are
+    /// ingested, changed ones (by checksum) are re-chunked in place and
+    /// unchanged ones are skipped. Returns the document id when its chunks
+    /// need embedding.
+    pub async fn sync_file(
+        &self,
+        user_id: &str,
+        path: &Path,
+        namespace: &str,
+    ) -> Result<Option<String>, AppError> {
+        let file_path = path.to_string_lossy().to_string();
+        let checksum = file_checksum(path).await?;
+        let existing = self
+            .document_repo
+            .find_by_file_path(user_id, &file_path)
+            .await?;
+        if let Some(document) = &existing {
+            if document.checksum.as_deref() == Some(checksum.as_str())
+                && document.namespace == namespace
+                && document.index_status != "failed"
+            {
+                return Ok(None);
+            }
+        }
+
+        let mime = mime_type(path);
+        let metadata = tokio::fs::metadata(path).await?;
+        let blocks = self.parse_document(path, &mime).await?;
+        let chunks = self.chunk_blocks(&blocks, 512, 64);
+
+        let document_id = match existing {
+            Some(document) => {
+                self.clear_chunks(&document.id).await?;
+                self.document_repo
+                    .update_source(&document.id, &checksum, metadata.len() as i64, namespace)
+                    .await?;
+                document.id
+            }
+            None => {
+                let title = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or("document")
+                    .to_string();
+                self.document_repo
+                    .insert_document(NewDocument {
+                        user_id: user_id.to_string(),
+                        title,
+                        file_path: Some(file_path),
+                        source_url: None,
+                        source_type: "watched_folder".to_string(),
+                        mime_type: Some(mime),
+                        file_size_bytes: Some(metadata.len() as i64),
+                        namespace: namespace.to_string(),
+                        checksum: Some(checksum),
+                        metadata: "{}".to_string(),
+                    })
+                    .await?
+                    .id
+            }
+        };
+
+        self.store_chunks(&document_id, user_id, chunks).await?;
+        Ok(Some(document_id))
+    }
+
+    /// Fetches a web page and indexes its readable text into `namespace`.
+    /// Fetching the same URL again replaces the earlier copy. Returns the
+    /// document id; its chunks still need embedding.
+    pub async fn ingest_url(
+        &self,
+        client: &reqwest::Client,
+        user_id: &str,
+        url: &str,
+        namespace: &str,
+    ) -> Result<String, AppError> {
+        let parsed = reqwest::Url::parse(url).map_err(|e| AppError::Validation {
+            field: "url".to_string(),
+            message: format!("Invalid URL: {e}"),
+        })?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(AppError::Validation {
+                field: "url".to_string(),
+                message: "Only http and https URLs can be ingested".to_string(),
+            });
+        }
+
+        let response = client
+            .get(parsed.clone())
+            .timeout(URL_FETCH_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| AppError::Io(format!("Failed to fetch {url}: {e}")))?;
+        if !response.status().is_success() {
+            return Err(AppError::Io(format!(
+                "Fetching {url} failed with status {}",
+                response.status()
+            )));
+        }
+        if response
+            .content_length()
+            .is_some_and(|length| length as usize > MAX_URL_BYTES)
+        {
+            return Err(AppError::Validation {
+                field: "url".to_string(),
+                message: format!("Page is larger than {} MB", MAX_URL_BYTES / (1024 * 1024)),
+            });
+        }
+
+        let mime = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .unwrap_or("text/html")
+            .trim()
+            .to_ascii_lowercase();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| AppError::Io(format!("Failed to read {url}: {e}")))?;
+        if body.len() > MAX_URL_BYTES {
+            return Err(AppError::Validation {
+                field: "url".to_string(),
+                message: format!("Page is larger than {} MB", MAX_URL_BYTES / (1024 * 1024)),
+            });
+        }
+
+        let (title, blocks) = if mime.contains("html") {
+            let page = document_parser::parse_html(&body);
+            (page.title, page.blocks)
+        } else if mime.contains("markdown") || parsed.path().ends_with(".md") {
+            (None, document_parser::parse_markdown(&body))
+        } else if mime.starts_with("text/") {
+            (None, document_parser::plain_text(body.clone()))
+        } else {
+            return Err(AppError::Validation {
+                field: "url".to_string(),
+                message: format!("Unsupported content type: {mime}"),
+            });
+        };
+        let chunks = self.chunk_blocks(&blocks, 512, 64);
+        if chunks.is_empty() {
+            return Err(AppError::Validation {
+                field: "url".to_string(),
+                message: "No readable text found on the page".to_string(),
+            });
+        }
+
+        let checksum = format!("{:x}", Sha256::digest(body.as_bytes()));
+        let existing = self.document_repo.find_by_source_url(user_id, url).await?;
+        let document_id = match existing {
+            Some(document) => {
+                self.clear_chunks(&document.id).await?;
+                self.document_repo
+                    .update_source(&document.id, &checksum, body.len() as i64, namespace)
+                    .await?;
+                document.id
+            }
+            None => {
+                let title = title.unwrap_or_else(|| url.to_string());
+                self.document_repo
+                    .insert_document(NewDocument {
+                        user_id: user_id.to_string(),
+                        title,
+                        file_path: None,
+                        source_url: Some(url.to_string()),
+                        source_type: "url".to_string(),
+                        mime_type: Some(mime),
+                        file_size_bytes: Some(body.len() as i64),
+                        namespace: namespace.to_string(),
+                        checksum: Some(checksum),
+                        metadata: "{}".to_string(),
+                    })
+                    .await?
+                    .id
+            }
+        };
+
+        self.store_chunks(&document_id, user_id, chunks).await?;
+        Ok(document_id)
+    }
+
+    /// Drops the document ingested from `path`, with its chunks and vectors.
+    /// Returns `false` when nothing was indexed for it.
+    pub async fn remove_file(&self, user_id: &str, path: &Path) -> Result<bool, AppError> {
+        let Some(document) = self
+            .document_repo
+            .find_by_file_path(user_id, &path.to_string_lossy())
+            .await?
+        else {
+            return Ok(false);
+        };
+        self.clear_chunks(&document.id).await?;
+        self.document_repo
+            .soft_delete_document(&document.id)
+            .await?;
+        Ok(true)
+    }
+
+    /// Paths of live documents ingested from `path` itself or from files below it.
+    pub async fn indexed_files_under(
+        &self,
+        user_id: &str,
+        path: &Path,
+    ) -> Result<Vec<PathBuf>, AppError> {
+        Ok(self
+            .document_repo
+            .list_documents(user_id)
+            .await?
+            .into_iter()
+            .filter_map(|document| document.file_path.map(PathBuf::from))
+            .filter(|file_path| file_path.starts_with(path))
+            .collect())
+    }
+
+    async fn store_chunks(
+        &self,
+        document_id: &str,
+        user_id: &str,
+        chunks: Vec<TextChunk>,
+    ) -> Result<(), AppError> {
+        let chunk_count = chunks.len() as i64;
         for chunk in chunks {
             self.document_repo
                 .insert_chunk(NewChunk {
-                    document_id: document.id.clone(),
+                    document_id: document_id.to_string(),
                     user_id: user_id.to_string(),
                     chunk_index: chunk.chunk_index,
                     content: chunk.content,
                     token_count: chunk.token_count,
                     start_char: Some(chunk.start_char),
                     end_char: Some(chunk.end_char),
-                    page_number: None,
-                    section_title: None,
-                    heading_path: None,
-                    metadata: "{}".to_string(),
+                    page_number: chunk.page_number,
+                    section_title: chunk.section_title,
+                    heading_path: chunk.heading_path,
+                    metadata: chunk_metadata(&chunk.language),
                 })
                 .await?;
         }
 
         self.document_repo
-            .update_index_status(
-                &document.id,
-                "indexing",
-                self.document_repo
-                    .get_chunks_by_document(&document.id)
-                    .await?
-                    .len() as i64,
-            )
-            .await?;
+            .update_index_status(document_id, "indexing", chunk_count)
+            .await
+    }
 
-        Ok(document.id)
+    async fn clear_chunks(&self, document_id: &str) -> Result<(), AppError> {
+        for chunk_id in self.document_repo.delete_chunks(document_id).await? {
+            self.embedding_repo
+                .delete_embedding_for_entity("chunk", &chunk_id)
+                .await?;
+        }
+        Ok(())
     }
 
     pub async fn embed_document_chunks(&self, document_id: &str) -> Result<(), AppError> {
@@ -141,6 +407,7 @@ impl RagService {
 
         let texts: Vec<String> = chunks.iter().map(|chunk| chunk.content.clone()).collect();
         let vectors = self.embedding_service.embed_batch(texts).await?;
+        let model_name = self.embedding_service.model_name();
 
         for (chunk, vector) in chunks.iter().zip(vectors.into_iter()) {
             let embedding_id = self
@@ -151,7 +418,7 @@ impl RagService {
                     &chunk.user_id,
                     "default",
                     vector,
-                    "fastembed",
+                    &model_name,
                 )
                 .await?;
 
@@ -190,6 +457,10 @@ impl RagService {
                 start_char: idx as i64,
                 end_char: end as i64,
                 content,
+                page_number: None,
+                section_title: None,
+                heading_path: None,
+                language: None,
             });
 
             if end == words.len() {
@@ -203,69 +474,178 @@ impl RagService {
         out
     }
 
+    /// Like `chunker`, but never splits a line and keeps line breaks, so code
+    /// stays readable. Overlap is whole lines covering at least `overlap` words.
+    pub fn line_chunker(&self, text: &str, chunk_size: usize, overlap: usize) -> Vec<TextChunk> {
+        let lines: Vec<(&str, usize)> = text
+            .lines()
+            .map(|line| (line, line.split_whitespace().count()))
+            .collect();
+        let total_words = lines.iter().map(|(_, words)| words).sum::<usize>();
+        if total_words == 0 {
+            return Vec::new();
+        }
+
+        let mut out = Vec::new();
+        let mut start = 0usize;
+        let mut start_word = 0usize;
+        while start < lines.len() {
+            let mut end = start;
+            let mut words = 0usize;
+            while end < lines.len() && (end == start || words + lines[end].1 <= chunk_size) {
+                words += lines[end].1;
+                end += 1;
+            }
+
+            let content = lines[start..end]
+                .iter()
+                .map(|(line, _)| *line)
+                .collect::<Vec<_>>()
+                .join("\n");
+            if words > 0 {
+                out.push(TextChunk {
+                    chunk_index: out.len() as i64,
+                    token_count: words as i64,
+                    start_char: start_word as i64,
+                    end_char: (start_word + words) as i64,
+                    content,
+                    page_number: None,
+                    section_title: None,
+                    heading_path: None,
+                    language: None,
+                });
+            }
+            if end == lines.len() {
+                break;
+            }
+
+            let mut next = end;
+            let mut overlap_words = 0usize;
+            while next > start + 1 && overlap_words < overlap {
+                next -= 1;
+                overlap_words += lines[next].1;
+            }
+            start_word += words - overlap_words;
+            start = next;
+        }
+
+        out
+    }
+
+    /// Chunks each block on its own so every chunk keeps its block's page and
+    /// section. Indexes and word offsets run across the whole document.
+    pub fn chunk_blocks(
+        &self,
+        blocks: &[TextBlock],
+        chunk_size: usize,
+        overlap: usize,
+    ) -> Vec<TextChunk> {
+        let mut out = Vec::new();
+        let mut word_offset = 0i64;
+        for block in blocks {
+            let chunks = if block.language.is_some() {
+                self.line_chunker(&block.text, chunk_size, overlap)
+            } else {
+                self.chunker(&block.text, chunk_size, overlap)
+            };
+            for mut chunk in chunks {
+                chunk.chunk_index = out.len() as i64;
+                chunk.start_char += word_offset;
+                chunk.end_char += word_offset;
+                chunk.page_number = block.page_number;
+                chunk.section_title = block.section_title.clone();
+                chunk.heading_path = block.heading_path.clone();
+                chunk.language = block.language.clone();
+                out.push(chunk);
+            }
+            word_offset += block.text.split_whitespace().count() as i64;
+        }
+        out
+    }
+
     pub async fn retrieve(
         &self,
         user_id: &str,
         query: &str,
         namespace: &str,
         limit: usize,
+    ) -> Result<Vec<RetrievedChunk>, AppError> {
+        self.retrieve_with_rerank(
+            user_id,
+            query,
+            &[namespace.to_string()],
+            limit,
+            DEFAULT_RERANK_CANDIDATES,
+        )
+        .await
+    }
+
+    /// Like `retrieve`, but across several namespaces and with the number of
+    /// fused candidates sent to the reranker chosen by the caller (per-session
+    /// collections and retrieval strength).
+    pub async fn retrieve_with_rerank(
+        &self,
+        user_id: &str,
+        query: &str,
+        namespaces: &[String],
+        limit: usize,
+        rerank_top_k: usize,
     ) -> Result<Vec<RetrievedChunk>, AppError> {
         let started = Instant::now();
         let query_embedding = self.embedding_service.embed_text(query).await?;
 
         let bm25 = self
             .document_repo
-            .search_chunks_bm25(user_id, query, namespace, 20)
+            .search_chunks_bm25(user_id, query, namespaces, 20)
             .await
             .unwrap_or_default();
 
-        let mut candidate_ids: Vec<String> = bm25.iter().map(|row| row.id.clone()).collect();
-        if candidate_ids.is_empty() {
-            candidate_ids = self
-                .document_repo
-                .get_recent_chunk_ids(user_id, namespace, 48)
-                .await
-                .unwrap_or_default();
-        }
-        candidate_ids.sort();
-        candidate_ids.dedup();
-
-        let candidate_embeddings = self
+        // Chunk vectors of every namespace share one index, so over-fetch and
+        // keep those in the requested namespaces.
+        let nearest = self
             .embedding_repo
-            .get_embeddings_for_entities("default", user_id, "chunk", &candidate_ids)
+            .search_nearest("default", user_id, "chunk", &query_embedding, 60)
             .await
             .unwrap_or_default();
-
-        let mut vector_ranked: Vec<(String, f32)> = candidate_embeddings
+        let nearest_ids = nearest.iter().map(|(id, _)| id.clone()).collect::<Vec<_>>();
+        let in_namespace = self
+            .document_repo
+            .filter_chunk_ids_in_namespaces(user_id, namespaces, &nearest_ids)
+            .await
+            .unwrap_or_default()
             .into_iter()
-            .filter_map(|row| {
-                let vec = crate::repositories::blob_to_vector(&row.vector);
-                if vec.len() != query_embedding.len() {
-                    return None;
-                }
-                Some((row.entity_id, cosine_similarity(&query_embedding, &vec)))
-            })
+            .collect::<HashSet<_>>();
+        let mut vector_ranked: Vec<(String, f32)> = nearest
+            .into_iter()
+            .filter(|(chunk_id, _)| in_namespace.contains(chunk_id))
             .collect();
-
-        vector_ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
         vector_ranked.truncate(20);
 
-        let mut ranks: HashMap<String, f32> = HashMap::new();
-        let k = 60.0f32;
+        let policy = self
+            .runtime_governor
+            .get_policy(Some(user_id))
+            .await
+            .unwrap_or_default();
+        let mut ranks: HashMap<String, f64> = HashMap::new();
 
         for (idx, item) in bm25.iter().enumerate() {
-            *ranks.entry(item.id.clone()).or_insert(0.0) += 1.0 / (k + idx as f32 + 1.0);
+            *ranks.entry(item.id.clone()).or_insert(0.0) +=
+                policy.retrieval_bm25_weight / (RRF_K + idx as f64 + 1.0);
         }
 
         for (idx, (chunk_id, _score)) in vector_ranked.iter().enumerate() {
-            *ranks.entry(chunk_id.clone()).or_insert(0.0) += 1.0 / (k + idx as f32 + 1.0);
+            *ranks.entry(chunk_id.clone()).or_insert(0.0) +=
+                policy.retrieval_vector_weight / (RRF_K + idx as f64 + 1.0);
         }
+        // A ranking weighted to zero adds nothing, so its ids shouldn't reach
+        // the reranker either.
+        ranks.retain(|_, score| *score > 0.0);
 
-        let mut fused: Vec<(String, f32)> = ranks.into_iter().collect();
+        let mut fused: Vec<(String, f64)> = ranks.into_iter().collect();
         fused.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
 
         let mut candidates = Vec::new();
-        for (chunk_id, _) in fused.iter().take(15) {
+        for (chunk_id, _) in fused.iter().take(rerank_top_k.max(limit)) {
             if let Some(chunk) = self.document_repo.get_chunk(chunk_id).await? {
                 candidates.push(chunk);
             }
@@ -303,10 +683,12 @@ impl RagService {
                         .iter()
                         .find(|(id, _)| id == &chunk.id)
                         .map(|(_, score)| *score);
+                    // FTS5's bm25() is negative with better matches lower; flip it
+                    // so higher is better like the other scores.
                     let bm25_score = bm25
                         .iter()
                         .find(|entry| entry.id == chunk.id)
-                        .map(|entry| entry.score as f32);
+                        .map(|entry| -entry.score as f32);
 
                     with_neighbors.push(RetrievedChunk {
                         chunk,
@@ -319,11 +701,14 @@ impl RagService {
         }
 
         let latency_ms = started.elapsed().as_millis() as i64;
+        // A retrieval over several collections is logged under all of them
+        // joined, so it doesn't skew any single namespace's hit rate.
+        let namespace = namespaces.join(",");
 
         sqlx::query(
             r#"
-            INSERT INTO rag_retrievals (id, query_text, retrieved_chunk_ids, reranked_chunk_ids, strategy, latency_ms)
-            VALUES (?1, ?2, ?3, ?4, 'hybrid', ?5)
+            INSERT INTO rag_retrievals (id, query_text, retrieved_chunk_ids, reranked_chunk_ids, strategy, latency_ms, namespace)
+            VALUES (?1, ?2, ?3, ?4, 'hybrid', ?5, ?6)
             "#,
         )
         .bind(Uuid::new_v4().to_string())
@@ -331,23 +716,225 @@ impl RagService {
         .bind(serde_json::to_string(&selected_ids).unwrap_or_else(|_| "[]".to_string()))
         .bind(serde_json::to_string(&selected_ids).unwrap_or_else(|_| "[]".to_string()))
         .bind(latency_ms)
+        .bind(&namespace)
         .execute(&self.write_pool)
         .await?;
 
         Ok(with_neighbors)
     }
 
-    async fn extract_text(&self, path: &Path, mime: &str) -> Result<String, AppError> {
+    /// Resolves cited chunks to citations carrying their document's title and
+    /// source. Chunks whose document is gone are dropped.
+    pub async fn citations(&self, cited: &[(usize, &RetrievedChunk)]) -> Vec<Citation> {
+        let mut documents: HashMap<String, Option<Document>> = HashMap::new();
+        let mut citations = Vec::new();
+        for (marker, row) in cited {
+            let chunk = &row.chunk;
+            if !documents.contains_key(&chunk.document_id) {
+                let document = self
+                    .document_repo
+                    .get_document(&chunk.document_id)
+                    .await
+                    .ok()
+                    .flatten();
+                documents.insert(chunk.document_id.clone(), document);
+            }
+            let Some(Some(document)) = documents.get(&chunk.document_id) else {
+                continue;
+            };
+            citations.push(Citation {
+                marker: *marker,
+                chunk_id: chunk.id.clone(),
+                document_id: document.id.clone(),
+                document_title: document.title.clone(),
+                page_number: chunk.page_number,
+                section_title: chunk.section_title.clone(),
+                source: document
+                    .source_url
+                    .clone()
+                    .or_else(|| document.file_path.clone()),
+            });
+        }
+        citations
+    }
+
+    pub async fn record_citations(
+        &self,
+        session_id: &str,
+        message_id: &str,
+        chunk_ids: &[String],
+    ) -> Result<(), AppError> {
+        for chunk_id in chunk_ids {
+            self.document_repo
+                .record_citation(chunk_id, Some(session_id), Some(message_id))
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn stats(&self, namespace: &str) -> Result<RagStats, AppError> {
+        self.document_repo.namespace_stats(namespace).await
+    }
+
+    /// Expects `name` and `namespace` to be validated already.
+    pub async fn create_collection(
+        &self,
+        user_id: &str,
+        name: &str,
+        namespace: &str,
+        description: Option<&str>,
+    ) -> Result<RagCollectionSummary, AppError> {
+        let existing = self.list_collections(user_id).await?;
+        if let Some(collection) = existing.iter().find(|c| c.namespace == namespace) {
+            return Err(AppError::Validation {
+                field: "namespace".to_string(),
+                message: format!(
+                    "Namespace '{namespace}' is already used by '{}'",
+                    collection.name
+                ),
+            });
+        }
+
+        let collection = self
+            .document_repo
+            .create_collection(user_id, name, namespace, description)
+            .await?;
+        Ok(RagCollectionSummary {
+            namespace: collection.namespace,
+            name: collection.name,
+            description: collection.description,
+            collection_id: Some(collection.id),
+            document_count: 0,
+            chunk_count: 0,
+            workspaces: Vec::new(),
+        })
+    }
+
+    /// Created collections plus every namespace that holds documents or backs a
+    /// workspace, and always the default one.
+    pub async fn list_collections(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<RagCollectionSummary>, AppError> {
+        let mut summaries = Vec::new();
+        for collection in self.document_repo.list_collections(user_id).await? {
+            let summary = collection_entry(&mut summaries, &collection.namespace);
+            summary.name = collection.name;
+            summary.description = collection.description;
+            summary.collection_id = Some(collection.id);
+        }
+        collection_entry(&mut summaries, DEFAULT_NAMESPACE);
+        for workspace in self.workspace_repo.list_workspaces(user_id).await? {
+            collection_entry(&mut summaries, &workspace.rag_namespace)
+                .workspaces
+                .push(workspace.name);
+        }
+        for (namespace, documents, chunks) in self.document_repo.namespace_counts(user_id).await? {
+            let summary = collection_entry(&mut summaries, &namespace);
+            summary.document_count = documents;
+            summary.chunk_count = chunks;
+        }
+
+        summaries.sort_by_key(|summary| summary.name.to_lowercase());
+        Ok(summaries)
+    }
+
+    /// Deletes the collection and every document in it, with their chunks and
+    /// vectors. Returns how many documents were removed. The default namespace
+    /// and namespaces that back a workspace can't be deleted.
+    pub async fn delete_collection(
+        &self,
+        user_id: &str,
+        namespace: &str,
+    ) -> Result<usize, AppError> {
+        if namespace == DEFAULT_NAMESPACE {
+            return Err(AppError::Validation {
+                field: "namespace".to_string(),
+                message: "The default collection can't be deleted".to_string(),
+            });
+        }
+        let collections = self.list_collections(user_id).await?;
+        let Some(collection) = collections.iter().find(|c| c.namespace == namespace) else {
+            return Err(AppError::NotFound {
+                entity: "collection".to_string(),
+                id: namespace.to_string(),
+            });
+        };
+        if !collection.workspaces.is_empty() {
+            return Err(AppError::Validation {
+                field: "namespace".to_string(),
+                message: format!(
+                    "Used by workspace {}; change its namespace first",
+                    collection.workspaces.join(", ")
+                ),
+            });
+        }
+
+        let document_ids = self
+            .document_repo
+            .list_document_ids_in_namespace(user_id, namespace)
+            .await?;
+        for document_id in &document_ids {
+            self.clear_chunks(document_id).await?;
+            self.document_repo.soft_delete_document(document_id).await?;
+        }
+        self.document_repo
+            .delete_collection(user_id, namespace)
+            .await?;
+
+        crate::log_info!(
+            "sarah.rag",
+            "deleted collection {} with {} documents",
+            namespace,
+            document_ids.len()
+        );
+        Ok(document_ids.len())
+    }
+
+    /// Splits the file into blocks that carry page numbers and headings where
+    /// the format has them.
+    async fn parse_document(&self, path: &Path, mime: &str) -> Result<Vec<TextBlock>, AppError> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+
+        // Checked before the MIME type: `.ts` guesses as a video stream.
+        if let Some(language) = document_parser::code_language(&extension) {
+            let text = tokio::fs::read_to_string(path).await?;
+            return Ok(document_parser::parse_code(&text, language));
+        }
+
+        if mime.contains("wordprocessingml") || extension == "docx" {
+            return parse_blocking(path, document_parser::parse_docx).await;
+        }
+
+        if mime.contains("epub") || extension == "epub" {
+            return parse_blocking(path, document_parser::parse_epub).await;
+        }
+
         if mime.contains("pdf") {
-            return pdf_extract::extract_text(path).map_err(|e| AppError::Io(e.to_string()));
+            return parse_blocking(path, document_parser::parse_pdf).await;
         }
 
-        if mime.contains("markdown") || path.extension().and_then(|e| e.to_str()) == Some("md") {
-            return tokio::fs::read_to_string(path)
-                .await
-                .map_err(AppError::from);
+        // Scans and screenshots carry their text as pixels.
+        if ocr::is_image(path) {
+            let recognized = ocr::recognize_text(path).await?;
+            return Ok(document_parser::plain_text(recognized.text));
+        }
+
+        if mime.contains("markdown") || extension == "md" {
+            let text = tokio::fs::read_to_string(path).await?;
+            return Ok(document_parser::parse_markdown(&text));
         }
 
+        Ok(document_parser::plain_text(
+            self.extract_text(path, mime).await?,
+        ))
+    }
+
+    async fn extract_text(&self, path: &Path, mime: &str) -> Result<String, AppError> {
         if mime.contains("sheet")
             || matches!(
                 path.extension().and_then(|e| e.to_str()),
@@ -377,16 +964,92 @@ impl RagService {
     }
 }
 
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
-    let mut dot = 0.0f32;
-    let mut norm_a = 0.0f32;
-    let mut norm_b = 0.0f32;
+/// Whether `parse_document` can make sense of the file, judged by extension.
+/// Watched folders skip everything else instead of indexing binaries as text.
+pub fn is_supported_file(path: &Path) -> bool {
+    let Some(extension) = path.extension().and_then(|e| e.to_str()) else {
+        return false;
+    };
+    let extension = extension.to_ascii_lowercase();
+    document_parser::code_language(&extension).is_some()
+        || matches!(
+            extension.as_str(),
+            "pdf"
+                | "docx"
+                | "epub"
+                | "md"
+                | "markdown"
+                | "txt"
+                | "text"
+                | "rst"
+                | "org"
+                | "csv"
+                | "tsv"
+                | "json"
+                | "yaml"
+                | "yml"
+                | "toml"
+                | "xml"
+                | "html"
+                | "htm"
+                | "xls"
+                | "xlsx"
+        )
+}
 
-    for (x, y) in a.iter().zip(b.iter()) {
-        dot += x * y;
-        norm_a += x * x;
-        norm_b += y * y;
-    }
+/// The summary for `namespace`, added with defaults if it isn't listed yet.
+fn collection_entry<'a>(
+    summaries: &'a mut Vec<RagCollectionSummary>,
+    namespace: &str,
+) -> &'a mut RagCollectionSummary {
+    let index = match summaries.iter().position(|s| s.namespace == namespace) {
+        Some(index) => index,
+        None => {
+            summaries.push(RagCollectionSummary {
+                namespace: namespace.to_string(),
+                name: namespace.to_string(),
+                description: None,
+                collection_id: None,
+                document_count: 0,
+                chunk_count: 0,
+                workspaces: Vec::new(),
+            });
+            summaries.len() - 1
+        }
+    };
+    &mut summaries[index]
+}
 
-    dot / ((norm_a.sqrt() * norm_b.sqrt()).max(1e-6))
+fn mime_type(path: &Path) -> String {
+    mime_guess::from_path(path)
+        .first_raw()
+        .unwrap_or("application/octet-stream")
+        .to_string()
+}
+
+/// Hex SHA-256 of the file contents, stored as the document checksum.
+async fn file_checksum(path: &Path) -> Result<String, AppError> {
+    let bytes = tokio::fs::read(path).await?;
+    let digest = tokio::task::spawn_blocking(move || Sha256::digest(&bytes))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    Ok(format!("{digest:x}"))
+}
+
+/// Runs a zip- or PDF-backed parser off the async runtime.
+async fn parse_blocking(
+    path: &Path,
+    parse: fn(&Path) -> Result<Vec<TextBlock>, AppError>,
+) -> Result<Vec<TextBlock>, AppError> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || parse(&path))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+}
+
+fn chunk_metadata(language: &Option<String>) -> String {
+    match language {
+        Some(language) => serde_json::json!({ "language": language }).to_string(),
+        None => "{}".to_string(),
+    }
 }
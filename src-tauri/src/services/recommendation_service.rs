@@ -1,11 +1,16 @@
+use std::collections::HashMap;
+
 use uuid::Uuid;
 
-use crate::db::models::{ModelRecommendation, SystemProfile};
+use crate::db::models::{ModelRecommendation, ModelUsageSignal, SystemProfile};
 use crate::error::AppError;
 use crate::repositories::analytics_repo::AnalyticsRepo;
 use crate::repositories::model_repo::ModelRepo;
 use crate::services::hardware_service::PerformanceMode;
 
+const USAGE_SIGNAL_WINDOW_DAYS: i64 = 30;
+const MIN_OBSERVED_SAMPLES: i64 = 3;
+
 #[derive(Clone)]
 pub struct RecommendationService {
     model_repo: ModelRepo,
@@ -29,9 +34,17 @@ impl RecommendationService {
             .model_repo
             .list_compatible_models(profile.total_ram_mb, profile.gpu_vram_mb.unwrap_or(0))
             .await?;
+        let signals = self
+            .analytics_repo
+            .get_model_usage_signals(USAGE_SIGNAL_WINDOW_DAYS)
+            .await?
+            .into_iter()
+            .map(|signal| (signal.model_id.clone(), signal))
+            .collect::<HashMap<String, ModelUsageSignal>>();
 
         let mut recs = Vec::new();
         for model in &candidates {
+            let signal = signals.get(&model.id);
             let ram_fit =
                 (profile.total_ram_mb as f64 / model.recommended_ram_mb.max(1) as f64).min(1.0);
             let vram_fit = if model.min_vram_mb <= 0 {
@@ -40,13 +53,20 @@ impl RecommendationService {
                 (profile.gpu_vram_mb.unwrap_or(0) as f64 / model.min_vram_mb as f64).min(1.0)
             };
 
-            let perf_fit = model
-                .avg_tokens_per_sec
+            // Observed throughput from real chats beats the catalog/benchmark figure once
+            // there are enough samples to trust it.
+            let observed_tps = signal
+                .filter(|signal| signal.sample_count >= MIN_OBSERVED_SAMPLES)
+                .and_then(|signal| signal.avg_tokens_per_sec);
+            let perf_fit = observed_tps
+                .or(model.avg_tokens_per_sec)
                 .map(|tps| (tps / 45.0).clamp(0.25, 1.0))
                 .unwrap_or(0.55);
+            let feedback_adj = signal.map(feedback_adjustment).unwrap_or(0.0);
+
+            let mut score =
+                (ram_fit * 0.40) + (vram_fit * 0.35) + (perf_fit * 0.25) + feedback_adj;
 
-            let mut score = (ram_fit * 0.40) + (vram_fit * 0.35) + (perf_fit * 0.25);
-            
             if mode == PerformanceMode::Multitasking && model.recommended_ram_mb > 3500 {
                 // Heavily penalize large models in Eco Multitasking mode. We want tiny 1.5B/3B models.
                 score *= 0.3;
@@ -72,8 +92,8 @@ impl RecommendationService {
                 recommendation_tier: tier.to_string(),
                 score,
                 reasoning: format!(
-                    "RAM fit {:.2}, VRAM fit {:.2}, perf fit {:.2}, tier {}",
-                    ram_fit, vram_fit, perf_fit, tier
+                    "RAM fit {:.2}, VRAM fit {:.2}, perf fit {:.2}, feedback {:+.2}, tier {}",
+                    ram_fit, vram_fit, perf_fit, feedback_adj, tier
                 ),
                 performance_estimate: Some(
                     serde_json::json!({
@@ -120,3 +140,19 @@ impl RecommendationService {
         self.analytics_repo.get_recommendations(profile_id).await
     }
 }
+
+/// Thumbs up/down on assistant messages nudge a model's score by at most ±0.10.
+/// The +2 prior keeps a single rating from swinging the ranking.
+fn feedback_adjustment(signal: &ModelUsageSignal) -> f64 {
+    let positive = signal.positive_feedback.max(0) as f64;
+    let negative = signal.negative_feedback.max(0) as f64;
+    ((positive - negative) / (positive + negative + 2.0)) * 0.10
+}
+
+/// Returns true when the hardware changed enough that cached recommendations are stale.
+pub fn profile_changed(previous: &SystemProfile, current: &SystemProfile) -> bool {
+    previous.total_ram_mb != current.total_ram_mb
+        || previous.cpu_threads != current.cpu_threads
+        || previous.gpu_name != current.gpu_name
+        || previous.gpu_vram_mb != current.gpu_vram_mb
+}
@@ -0,0 +1,221 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use sqlx::SqlitePool;
+
+use crate::db::models::{DatabaseBackup, StartupFailure};
+use crate::db::Database;
+use crate::error::AppError;
+
+const BACKUP_DIR: &str = "backups";
+const BACKUP_PREFIX: &str = "app-";
+
+/// Managed instead of `AppState` when startup failed. Only the recovery
+/// commands work in this mode; the database, if it opens at all, is read-only.
+pub struct SafeMode {
+    pub failure: StartupFailure,
+    pub db: Option<Arc<Database>>,
+    pub db_path: PathBuf,
+}
+
+impl SafeMode {
+    pub async fn enter(app_handle: &tauri::AppHandle, failure: StartupFailure) -> Self {
+        let db = match Database::open_read_only(app_handle).await {
+            Ok(db) => Some(Arc::new(db)),
+            Err(error) => {
+                crate::log_warn!(
+                    "sarah.recovery",
+                    "Safe mode could not open the database: {}",
+                    error
+                );
+                None
+            }
+        };
+        let db_path = crate::db::database_path(app_handle).unwrap_or_default();
+
+        Self {
+            failure,
+            db,
+            db_path,
+        }
+    }
+
+    pub async fn quick_check(&self) -> Option<String> {
+        let db = self.db.as_ref()?;
+        let rows = sqlx::query_scalar::<_, String>("PRAGMA quick_check")
+            .fetch_all(db.read_pool())
+            .await
+            .ok()?;
+        Some(rows.join("; "))
+    }
+}
+
+pub fn backups_dir(db_path: &Path) -> PathBuf {
+    db_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(BACKUP_DIR)
+}
+
+/// Snapshots the database into the backups folder. `VACUUM INTO` gives a
+/// consistent copy through a live pool; without one the file and its WAL are
+/// copied as-is.
+pub async fn create_backup(
+    db_path: &Path,
+    pool: Option<&SqlitePool>,
+) -> Result<DatabaseBackup, AppError> {
+    create_named_backup(db_path, pool, "").await
+}
+
+pub async fn list_backups(db_path: &Path) -> Result<Vec<DatabaseBackup>, AppError> {
+    let dir = backups_dir(db_path);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    let mut entries = tokio::fs::read_dir(&dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let is_backup = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(BACKUP_PREFIX) && name.ends_with(".db"));
+        if is_backup {
+            backups.push(describe_backup(&path).await?);
+        }
+    }
+    backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(backups)
+}
+
+/// Replaces the live database with a backup. The current file is kept as a
+/// "pre-restore" backup first. Every pool on the file must be closed beforehand.
+pub async fn restore_backup(db_path: &Path, file_name: &str) -> Result<(), AppError> {
+    let is_plain_name = !file_name.is_empty()
+        && !file_name.contains(['/', '\\'])
+        && file_name.starts_with(BACKUP_PREFIX)
+        && file_name.ends_with(".db");
+    if !is_plain_name {
+        return Err(AppError::Validation {
+            field: "fileName".to_string(),
+            message: "Pick a backup from the list".to_string(),
+        });
+    }
+
+    let source = backups_dir(db_path).join(file_name);
+    if !source.exists() {
+        return Err(AppError::NotFound {
+            entity: "database_backup".to_string(),
+            id: file_name.to_string(),
+        });
+    }
+
+    if db_path.exists() {
+        create_named_backup(db_path, None, "pre-restore-").await?;
+    }
+
+    for suffix in ["-wal", "-shm"] {
+        let _ = tokio::fs::remove_file(sidecar(db_path, suffix)).await;
+    }
+    tokio::fs::copy(&source, db_path).await?;
+    let source_wal = sidecar(&source, "-wal");
+    if source_wal.exists() {
+        tokio::fs::copy(&source_wal, sidecar(db_path, "-wal")).await?;
+    }
+
+    crate::log_info!("sarah.recovery", "Restored database from {}", file_name);
+    Ok(())
+}
+
+/// Puts a freshly exported SQLCipher copy in place of the plaintext database.
+/// Older backups are still plaintext; `remove_plaintext_backups` deletes them.
+/// Every pool on the file must be closed beforehand.
+pub async fn swap_in_encrypted(
+    db_path: &Path,
+    encrypted_path: &Path,
+    remove_plaintext_backups: bool,
+) -> Result<(), AppError> {
+    for suffix in ["-wal", "-shm"] {
+        let _ = tokio::fs::remove_file(sidecar(db_path, suffix)).await;
+    }
+    tokio::fs::rename(encrypted_path, db_path).await?;
+
+    if remove_plaintext_backups {
+        for backup in list_backups(db_path).await? {
+            let path = PathBuf::from(&backup.path);
+            if !crate::db::is_encrypted_file(&path) {
+                tokio::fs::remove_file(&path).await?;
+                let _ = tokio::fs::remove_file(sidecar(&path, "-wal")).await;
+            }
+        }
+    }
+
+    crate::log_info!("sarah.recovery", "Database is now encrypted at rest");
+    Ok(())
+}
+
+async fn create_named_backup(
+    db_path: &Path,
+    pool: Option<&SqlitePool>,
+    label: &str,
+) -> Result<DatabaseBackup, AppError> {
+    let dir = backups_dir(db_path);
+    tokio::fs::create_dir_all(&dir).await?;
+
+    let stamp = chrono::Utc::now().format("%Y%m%d-%H%M%S");
+    let target = dir.join(format!("{BACKUP_PREFIX}{label}{stamp}.db"));
+
+    let vacuumed = match pool {
+        Some(pool) => match sqlx::query("VACUUM INTO ?1")
+            .bind(target.to_string_lossy().to_string())
+            .execute(pool)
+            .await
+        {
+            Ok(_) => true,
+            Err(error) => {
+                crate::log_warn!(
+                    "sarah.recovery",
+                    "VACUUM INTO failed, falling back to file copy: {}",
+                    error
+                );
+                false
+            }
+        },
+        None => false,
+    };
+
+    if !vacuumed {
+        tokio::fs::copy(db_path, &target).await?;
+        let wal = sidecar(db_path, "-wal");
+        if wal.exists() {
+            tokio::fs::copy(&wal, sidecar(&target, "-wal")).await?;
+        }
+    }
+
+    describe_backup(&target).await
+}
+
+async fn describe_backup(path: &Path) -> Result<DatabaseBackup, AppError> {
+    let metadata = tokio::fs::metadata(path).await?;
+    let created_at = metadata
+        .modified()
+        .map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339())
+        .unwrap_or_default();
+
+    Ok(DatabaseBackup {
+        file_name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path: path.to_string_lossy().to_string(),
+        size_bytes: metadata.len(),
+        created_at,
+    })
+}
+
+fn sidecar(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
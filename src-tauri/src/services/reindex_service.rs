@@ -0,0 +1,226 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tauri::Emitter;
+
+use crate::db::models::ReindexJob;
+use crate::error::AppError;
+use crate::repositories::embedding_repo::EmbeddingRepo;
+use crate::repositories::reindex_repo::ReindexRepo;
+use crate::repositories::settings_repo::SettingsRepo;
+use crate::services::embedding_service::{
+    is_supported_model, EmbeddingService, EMBEDDING_MODEL_KEY, EMBEDDING_SETTINGS_NAMESPACE,
+    SUPPORTED_EMBEDDING_MODELS,
+};
+use crate::services::history_search::MAX_EMBED_CHARS;
+use crate::services::runtime_governor_service::RuntimeGovernorService;
+
+const REINDEX_BATCH_SIZE: i64 = 64;
+/// Short pause between batches so interactive work gets the CPU in between.
+const REINDEX_BATCH_PAUSE: Duration = Duration::from_millis(250);
+const REINDEX_PRESSURE_BACKOFF: Duration = Duration::from_secs(15);
+const REINDEX_PROGRESS_EVENT: &str = "rag:reindex-progress";
+
+/// Moves every stored vector to another embedding model. New vectors are
+/// staged next to the live ones and swapped in at the end, so retrieval keeps
+/// working on the old model until the new one is complete.
+#[derive(Clone)]
+pub struct ReindexService {
+    app_handle: tauri::AppHandle,
+    repo: ReindexRepo,
+    embedding_repo: EmbeddingRepo,
+    embedding_service: Option<Arc<EmbeddingService>>,
+    runtime_governor: RuntimeGovernorService,
+    settings_repo: SettingsRepo,
+    /// Set while this process is running a job.
+    active: Arc<AtomicBool>,
+}
+
+impl ReindexService {
+    pub fn new(
+        app_handle: tauri::AppHandle,
+        repo: ReindexRepo,
+        embedding_repo: EmbeddingRepo,
+        embedding_service: Option<Arc<EmbeddingService>>,
+        runtime_governor: RuntimeGovernorService,
+        settings_repo: SettingsRepo,
+    ) -> Self {
+        Self {
+            app_handle,
+            repo,
+            embedding_repo,
+            embedding_service,
+            runtime_governor,
+            settings_repo,
+            active: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Starts re-embedding everything with `target_model` in the background.
+    pub async fn start(&self, target_model: &str) -> Result<ReindexJob, AppError> {
+        let target_model = target_model.trim();
+        if !is_supported_model(target_model) {
+            return Err(AppError::Validation {
+                field: "target_model".to_string(),
+                message: format!(
+                    "Unsupported embedding model; expected one of: {}",
+                    SUPPORTED_EMBEDDING_MODELS.join(", ")
+                ),
+            });
+        }
+        let embedding = self.embedding_service.as_ref().ok_or_else(|| {
+            AppError::Embedding("Embeddings are not available on this device".to_string())
+        })?;
+        if self.active.load(Ordering::SeqCst) || self.repo.running_job().await?.is_some() {
+            return Err(AppError::Validation {
+                field: "target_model".to_string(),
+                message: "A re-index is already running".to_string(),
+            });
+        }
+
+        let total = self.repo.count_items(MAX_EMBED_CHARS).await?;
+        let job = self
+            .repo
+            .create_job(&embedding.model_name(), target_model, total)
+            .await?;
+        crate::log_info!(
+            "sarah.reindex",
+            "re-indexing {} vectors from {} to {}",
+            total,
+            job.source_model,
+            job.target_model
+        );
+        self.spawn(job.clone());
+        Ok(job)
+    }
+
+    pub async fn status(&self) -> Result<Option<ReindexJob>, AppError> {
+        self.repo.latest_job().await
+    }
+
+    /// Picks up a job interrupted by a restart; its staged vectors are kept.
+    pub async fn resume(&self) {
+        match self.repo.running_job().await {
+            Ok(Some(job)) if self.embedding_service.is_some() => {
+                crate::log_info!("sarah.reindex", "resuming re-index job {}", job.id);
+                self.spawn(job);
+            }
+            Ok(Some(job)) => {
+                let _ = self
+                    .repo
+                    .fail_job(&job.id, "Embeddings are not available on this device")
+                    .await;
+            }
+            Ok(None) => {}
+            Err(error) => tracing::warn!("Failed to look up re-index jobs: {error}"),
+        }
+    }
+
+    fn spawn(&self, job: ReindexJob) {
+        if self.active.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let service = self.clone();
+        tokio::spawn(async move {
+            let result = service.run(&job).await;
+            if let Err(error) = result {
+                crate::log_error!("sarah.reindex", "re-index job {} failed: {}", job.id, error);
+                let _ = service.repo.fail_job(&job.id, &error.to_string()).await;
+            }
+            service.active.store(false, Ordering::SeqCst);
+            service.emit_progress(&job.id).await;
+        });
+    }
+
+    async fn run(&self, job: &ReindexJob) -> Result<(), AppError> {
+        let live = self.embedding_service.as_ref().ok_or_else(|| {
+            AppError::Embedding("Embeddings are not available on this device".to_string())
+        })?;
+        let target = live.with_model(&job.target_model)?;
+
+        loop {
+            if self.under_pressure().await {
+                tokio::time::sleep(REINDEX_PRESSURE_BACKOFF).await;
+                continue;
+            }
+            let items = self
+                .repo
+                .list_pending_items(&job.id, MAX_EMBED_CHARS, REINDEX_BATCH_SIZE)
+                .await?;
+            if items.is_empty() {
+                if self
+                    .repo
+                    .swap_in(&job.id, &job.target_model, MAX_EMBED_CHARS)
+                    .await?
+                {
+                    break;
+                }
+                continue;
+            }
+
+            let texts = items.iter().map(|item| item.text.clone()).collect();
+            let vectors = target.embed_batch(texts).await?;
+            let staged = items
+                .into_iter()
+                .map(|item| item.embedding_id)
+                .zip(vectors)
+                .collect::<Vec<_>>();
+            self.repo.stage_vectors(&job.id, &staged).await?;
+            self.emit_progress(&job.id).await;
+            tokio::time::sleep(REINDEX_BATCH_PAUSE).await;
+        }
+
+        live.switch_model(&job.target_model)?;
+        self.embedding_repo.reset_index().await;
+        target.unload();
+        self.settings_repo
+            .upsert_setting(
+                None,
+                EMBEDDING_SETTINGS_NAMESPACE,
+                EMBEDDING_MODEL_KEY,
+                &job.target_model,
+                "string",
+                false,
+            )
+            .await?;
+
+        crate::log_info!(
+            "sarah.reindex",
+            "re-index job {} completed; now embedding with {}",
+            job.id,
+            job.target_model
+        );
+        Ok(())
+    }
+
+    /// Batches wait while the machine is busy, on battery or running hot, or
+    /// while the user is at it, unless the policy says otherwise.
+    async fn under_pressure(&self) -> bool {
+        let policy = self
+            .runtime_governor
+            .get_policy(None)
+            .await
+            .unwrap_or_default();
+        let stats = self.runtime_governor.current_stats();
+        if self.runtime_governor.background_paused()
+            || (policy.battery_defer_background && stats.on_battery)
+        {
+            return true;
+        }
+        if !self.runtime_governor.is_user_idle(&stats, &policy) {
+            return true;
+        }
+        if !policy.defer_background_under_pressure {
+            return false;
+        }
+        let pressure = self.runtime_governor.classify_pressure(&stats, &policy);
+        matches!(pressure.as_str(), "high" | "critical")
+    }
+
+    async fn emit_progress(&self, job_id: &str) {
+        if let Ok(Some(job)) = self.repo.get_job(job_id).await {
+            let _ = self.app_handle.emit(REINDEX_PROGRESS_EVENT, &job);
+        }
+    }
+}
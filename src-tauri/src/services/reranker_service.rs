@@ -76,7 +76,8 @@ impl RerankerService {
             // On Windows, use DirectML ONLY. DirectML provides GPU acceleration via DirectX 
             // and is native to Windows, avoiding the "missing cublasLt64_12.dll" errors 
             // common with the CUDA provider on systems without the full CUDA Toolkit.
-            if stats.gpu_vram_mb.unwrap_or(0) >= 1024 {
+            // Metal-only machines report unified memory as VRAM but have no ONNX GPU provider here.
+            if stats.gpu_vram_mb.unwrap_or(0) >= 1024 && stats.supports_cuda + stats.supports_vulkan > 0 {
                 if cfg!(target_os = "windows") {
                     providers.push(ort::execution_providers::DirectMLExecutionProvider::default().build());
                 } else {
@@ -139,6 +140,22 @@ impl RerankerService {
         self.initialized.load(Ordering::Relaxed)
     }
 
+    /// Whether the ONNX session is resident, which can differ from `is_initialized`
+    /// after an idle or budget-driven unload.
+    pub fn is_loaded(&self) -> bool {
+        self.engine.lock().map(|guard| guard.is_some()).unwrap_or(false)
+    }
+
+    /// Drops the ONNX session; the next call re-initializes it lazily.
+    pub fn unload(&self) -> bool {
+        let Ok(mut guard) = self.engine.lock() else {
+            return false;
+        };
+        let was_loaded = guard.take().is_some();
+        self.initialized.store(false, Ordering::Relaxed);
+        was_loaded
+    }
+
     pub async fn rerank(
         &self,
         query: &str,
@@ -0,0 +1,282 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::repositories::conversation_repo::ConversationRepo;
+use crate::repositories::embedding_repo::EmbeddingRepo;
+use crate::repositories::settings_repo::SettingsRepo;
+
+pub const RETENTION_SETTINGS_NAMESPACE: &str = "retention";
+const RETENTION_POLICY_KEY: &str = "policy";
+
+const MIN_MAX_AGE_DAYS: i64 = 1;
+/// Below this the schema, indexes and models table alone can exceed the cap.
+const MIN_MAX_DATABASE_MB: i64 = 32;
+
+/// What the scheduled cleanup removes. Nothing is removed until `enabled` is set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetentionPolicy {
+    pub enabled: bool,
+    /// Sessions with no activity for this many days are deleted.
+    pub max_age_days: Option<i64>,
+    /// Pinned sessions are never deleted, whatever their age or size.
+    pub keep_pinned: bool,
+    /// Least recently active sessions are deleted until the database fits,
+    /// unless deleting every one of them still wouldn't make it fit.
+    pub max_database_mb: Option<i64>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_age_days: None,
+            keep_pinned: true,
+            max_database_mb: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionReason {
+    Age,
+    DatabaseSize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionCandidate {
+    pub session_id: String,
+    pub title: Option<String>,
+    pub last_activity_at: String,
+    pub message_count: i64,
+    pub approx_bytes: i64,
+    pub reason: RetentionReason,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionReport {
+    /// `true` when nothing was deleted and the report is only a preview.
+    pub dry_run: bool,
+    pub sessions: Vec<RetentionCandidate>,
+    pub messages_removed: i64,
+    pub approx_bytes_removed: i64,
+    pub database_bytes: i64,
+}
+
+/// Applies the user's retention policy to stored conversations.
+#[derive(Clone)]
+pub struct RetentionService {
+    settings_repo: SettingsRepo,
+    conversation_repo: ConversationRepo,
+    embedding_repo: EmbeddingRepo,
+}
+
+impl RetentionService {
+    pub fn new(
+        settings_repo: SettingsRepo,
+        conversation_repo: ConversationRepo,
+        embedding_repo: EmbeddingRepo,
+    ) -> Self {
+        Self {
+            settings_repo,
+            conversation_repo,
+            embedding_repo,
+        }
+    }
+
+    pub async fn policy(&self) -> Result<RetentionPolicy, AppError> {
+        let stored = self
+            .settings_repo
+            .get_setting(None, RETENTION_SETTINGS_NAMESPACE, RETENTION_POLICY_KEY)
+            .await?;
+        Ok(stored
+            .and_then(|setting| serde_json::from_str(&setting.value).ok())
+            .unwrap_or_default())
+    }
+
+    pub async fn set_policy(&self, policy: RetentionPolicy) -> Result<RetentionPolicy, AppError> {
+        if policy
+            .max_age_days
+            .is_some_and(|days| days < MIN_MAX_AGE_DAYS)
+        {
+            return Err(AppError::Validation {
+                field: "max_age_days".to_string(),
+                message: format!("Must be at least {MIN_MAX_AGE_DAYS} day"),
+            });
+        }
+        if policy
+            .max_database_mb
+            .is_some_and(|mb| mb < MIN_MAX_DATABASE_MB)
+        {
+            return Err(AppError::Validation {
+                field: "max_database_mb".to_string(),
+                message: format!("Must be at least {MIN_MAX_DATABASE_MB} MB"),
+            });
+        }
+
+        let value = serde_json::to_string(&policy)
+            .map_err(|e| AppError::Internal(format!("Failed to encode retention policy: {e}")))?;
+        self.settings_repo
+            .upsert_setting(
+                None,
+                RETENTION_SETTINGS_NAMESPACE,
+                RETENTION_POLICY_KEY,
+                &value,
+                "json",
+                false,
+            )
+            .await?;
+        Ok(policy)
+    }
+
+    /// Lists what `policy` (the saved one when `None`) would delete right now,
+    /// whether or not it is enabled.
+    pub async fn preview(
+        &self,
+        policy: Option<RetentionPolicy>,
+    ) -> Result<RetentionReport, AppError> {
+        let policy = match policy {
+            Some(policy) => policy,
+            None => self.policy().await?,
+        };
+        let database_bytes = self.conversation_repo.database_size_bytes().await?;
+        let sessions = self.candidates(&policy, database_bytes).await?;
+        Ok(report(true, sessions, 0, database_bytes))
+    }
+
+    /// Deletes what the saved policy selects. Returns `None` when retention is off.
+    pub async fn run(&self) -> Result<Option<RetentionReport>, AppError> {
+        let policy = self.policy().await?;
+        if !policy.enabled {
+            return Ok(None);
+        }
+
+        let database_bytes = self.conversation_repo.database_size_bytes().await?;
+        let sessions = self.candidates(&policy, database_bytes).await?;
+        if sessions.is_empty() {
+            return Ok(Some(report(false, sessions, 0, database_bytes)));
+        }
+
+        let ids = sessions
+            .iter()
+            .map(|candidate| candidate.session_id.clone())
+            .collect::<Vec<_>>();
+        let messages_removed = self.conversation_repo.purge_sessions(&ids).await?;
+        // The purge deletes message embeddings with plain SQL.
+        self.embedding_repo.reset_index().await;
+        // Deleted rows only become free pages; a size cap needs the file to shrink.
+        if policy.max_database_mb.is_some() {
+            self.conversation_repo.vacuum().await?;
+        }
+        let database_bytes = self.conversation_repo.database_size_bytes().await?;
+
+        crate::log_info!(
+            "sarah.retention",
+            "retention removed {} sessions and {} messages",
+            ids.len(),
+            messages_removed
+        );
+        Ok(Some(report(
+            false,
+            sessions,
+            messages_removed,
+            database_bytes,
+        )))
+    }
+
+    async fn candidates(
+        &self,
+        policy: &RetentionPolicy,
+        database_bytes: i64,
+    ) -> Result<Vec<RetentionCandidate>, AppError> {
+        let cutoff = policy.max_age_days.map(|days| {
+            (chrono::Utc::now() - chrono::Duration::days(days))
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        });
+        let mut excess = policy
+            .max_database_mb
+            .map(|mb| database_bytes - mb * 1024 * 1024)
+            .unwrap_or(0);
+
+        // Oldest first, so the size cap removes the least recently used chats.
+        let sessions = self
+            .conversation_repo
+            .list_session_storage()
+            .await?
+            .into_iter()
+            .filter(|session| !(policy.keep_pinned && session.pinned == 1))
+            .map(|session| {
+                let expired = cutoff
+                    .as_deref()
+                    .is_some_and(|cutoff| session.last_activity_at.as_str() < cutoff);
+                (session, expired)
+            })
+            .collect::<Vec<_>>();
+
+        // Documents, embeddings and models count towards the file too. When
+        // deleting every remaining chat still wouldn't get under the cap, the
+        // cap can't be met this way, so no chat is deleted for size.
+        if excess > 0 {
+            let reclaimable: i64 = sessions
+                .iter()
+                .map(|(session, _)| session.approx_bytes)
+                .sum();
+            if reclaimable < excess {
+                crate::log_warn!(
+                    "sarah.retention",
+                    "database is {} bytes over its size cap but chats hold only {}; skipping size-based cleanup",
+                    excess,
+                    reclaimable
+                );
+                excess = 0;
+            }
+        }
+
+        let mut candidates = Vec::new();
+        for (session, expired) in sessions {
+            let reason = if expired {
+                RetentionReason::Age
+            } else if excess > 0 {
+                RetentionReason::DatabaseSize
+            } else {
+                continue;
+            };
+
+            excess -= session.approx_bytes;
+            candidates.push(RetentionCandidate {
+                session_id: session.id,
+                title: session.title,
+                last_activity_at: session.last_activity_at,
+                message_count: session.message_count,
+                approx_bytes: session.approx_bytes,
+                reason,
+            });
+        }
+        Ok(candidates)
+    }
+}
+
+fn report(
+    dry_run: bool,
+    sessions: Vec<RetentionCandidate>,
+    messages_removed: i64,
+    database_bytes: i64,
+) -> RetentionReport {
+    let approx_bytes_removed = sessions.iter().map(|session| session.approx_bytes).sum();
+    let messages_removed = if dry_run {
+        sessions.iter().map(|session| session.message_count).sum()
+    } else {
+        messages_removed
+    };
+    RetentionReport {
+        dry_run,
+        sessions,
+        messages_removed,
+        approx_bytes_removed,
+        database_bytes,
+    }
+}
@@ -1,15 +1,36 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
 use sqlx::SqlitePool;
+use tauri::Emitter;
 use uuid::Uuid;
 
 use crate::db::models::{GenerationOptions, LiveSystemStats, RuntimePolicy, RuntimePolicyPatch};
 use crate::error::AppError;
+use crate::repositories::settings_repo::{SettingChange, SettingsRepo};
+use crate::services::analytics_service::AnalyticsService;
 use crate::services::hardware_service::HardwareService;
+use crate::services::inference_service::{InferenceService, ThermalThrottle};
+
+/// The policy lives in its own table; changes are announced on the settings bus
+/// under this namespace.
+pub const RUNTIME_POLICY_NAMESPACE: &str = "runtime_policy";
+pub const RUNTIME_POLICY_KEY: &str = "policy";
+pub const POWER_STATE_EVENT: &str = "power://state-changed";
+const DEVICE_CHECK_INTERVAL: Duration = Duration::from_secs(20);
+/// A throttled part has to cool this far below its threshold before limits
+/// lift, so a sensor hovering at the line doesn't flip them every check.
+const THERMAL_HYSTERESIS_C: f64 = 5.0;
 
 #[derive(Clone)]
 pub struct RuntimeGovernorService {
     read_pool: SqlitePool,
     write_pool: SqlitePool,
     hardware_service: HardwareService,
+    settings_repo: SettingsRepo,
+    /// Policies resolved per user (`None` = global). Read on every generation.
+    policies: Arc<Mutex<HashMap<Option<String>, RuntimePolicy>>>,
 }
 
 impl RuntimeGovernorService {
@@ -17,15 +38,49 @@ impl RuntimeGovernorService {
         read_pool: SqlitePool,
         write_pool: SqlitePool,
         hardware_service: HardwareService,
+        settings_repo: SettingsRepo,
     ) -> Self {
         Self {
             read_pool,
             write_pool,
             hardware_service,
+            settings_repo,
+            policies: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Drops cached policies after a change. A global change affects every user
+    /// without an override, so it clears everything.
+    pub fn invalidate_policy(&self, user_id: Option<&str>) {
+        if let Ok(mut policies) = self.policies.lock() {
+            match user_id {
+                Some(uid) => {
+                    policies.remove(&Some(uid.to_string()));
+                }
+                None => policies.clear(),
+            }
         }
     }
 
     pub async fn get_policy(&self, user_id: Option<&str>) -> Result<RuntimePolicy, AppError> {
+        let cache_key = user_id.map(str::to_string);
+        if let Some(policy) = self
+            .policies
+            .lock()
+            .ok()
+            .and_then(|policies| policies.get(&cache_key).cloned())
+        {
+            return Ok(policy);
+        }
+
+        let policy = self.load_policy(user_id).await?;
+        if let Ok(mut policies) = self.policies.lock() {
+            policies.insert(cache_key, policy.clone());
+        }
+        Ok(policy)
+    }
+
+    async fn load_policy(&self, user_id: Option<&str>) -> Result<RuntimePolicy, AppError> {
         let policy = if let Some(uid) = user_id {
             let scoped = sqlx::query_scalar::<_, String>(
                 "SELECT policy_json FROM runtime_policy_overrides WHERE user_id = ?1 LIMIT 1",
@@ -63,7 +118,7 @@ impl RuntimeGovernorService {
         user_id: Option<&str>,
         patch: RuntimePolicyPatch,
     ) -> Result<RuntimePolicy, AppError> {
-        let mut next = self.get_policy(user_id).await?;
+        let mut next = self.load_policy(user_id).await?;
         apply_patch(&mut next, patch);
 
         let encoded = serde_json::to_string(&next)
@@ -102,6 +157,13 @@ impl RuntimeGovernorService {
             .await?;
         }
 
+        self.invalidate_policy(user_id);
+        self.settings_repo.publish(SettingChange {
+            user_id: user_id.map(str::to_string),
+            namespace: RUNTIME_POLICY_NAMESPACE.to_string(),
+            key: RUNTIME_POLICY_KEY.to_string(),
+            value: Some(encoded),
+        });
         Ok(next)
     }
 
@@ -109,6 +171,140 @@ impl RuntimeGovernorService {
         self.hardware_service.live_stats()
     }
 
+    pub fn on_battery(&self) -> bool {
+        self.hardware_service.live_stats().on_battery
+    }
+
+    /// Which parts run above the policy's thermal thresholds, given whether
+    /// they were throttled at the last check.
+    pub fn classify_thermal(
+        &self,
+        stats: &LiveSystemStats,
+        policy: &RuntimePolicy,
+        previous: ThermalThrottle,
+    ) -> ThermalThrottle {
+        if !policy.thermal_protection {
+            return ThermalThrottle::default();
+        }
+        let is_hot = |temperature: Option<f32>, threshold: f64, was_hot: bool| {
+            let threshold = if was_hot {
+                threshold - THERMAL_HYSTERESIS_C
+            } else {
+                threshold
+            };
+            temperature.is_some_and(|value| value as f64 >= threshold)
+        };
+        ThermalThrottle {
+            cpu_hot: is_hot(stats.cpu_temp_c, policy.thermal_cpu_c, previous.cpu_hot),
+            gpu_hot: is_hot(stats.gpu_temp_c, policy.thermal_gpu_c, previous.gpu_hot),
+        }
+    }
+
+    /// Whether background jobs are being held back for the battery or heat.
+    pub fn background_paused(&self) -> bool {
+        self.hardware_service.background_paused()
+    }
+
+    /// Whether the user has been away from keyboard and mouse for the policy's
+    /// idle threshold. Platforms without an idle probe always count as idle.
+    pub fn is_user_idle(&self, stats: &LiveSystemStats, policy: &RuntimePolicy) -> bool {
+        !matches!(stats.idle_secs, Some(idle) if idle < policy.idle_threshold_secs)
+    }
+
+    /// [`Self::is_user_idle`] as of the device monitor's last check.
+    pub fn user_idle(&self) -> bool {
+        !self.hardware_service.user_active()
+    }
+
+    /// Watches the power source and temperatures. On battery or above a thermal
+    /// threshold, background jobs are held back if the policy asks for it; heat
+    /// also throttles inference. Power switches are announced to the frontend and
+    /// thermal changes recorded in perf_logs. Also tracks whether the user is
+    /// idle, for [`Self::user_idle`].
+    pub fn start_device_monitor(
+        &self,
+        app_handle: tauri::AppHandle,
+        inference: InferenceService,
+        analytics: AnalyticsService,
+    ) {
+        let governor = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(DEVICE_CHECK_INTERVAL);
+            let mut last_on_battery: Option<bool> = None;
+            let mut throttle = ThermalThrottle::default();
+            loop {
+                ticker.tick().await;
+                let stats = governor.current_stats();
+                let policy = governor.get_policy(None).await.unwrap_or_default();
+
+                let next_throttle = governor.classify_thermal(&stats, &policy, throttle);
+                if next_throttle != throttle {
+                    governor.record_thermal_change(&analytics, &stats, next_throttle);
+                    inference.set_thermal_throttle(next_throttle);
+                    throttle = next_throttle;
+                }
+                governor
+                    .hardware_service
+                    .set_user_active(!governor.is_user_idle(&stats, &policy));
+                governor.hardware_service.set_background_paused(
+                    (stats.on_battery && policy.battery_defer_background) || throttle.is_active(),
+                );
+
+                if last_on_battery.is_some_and(|previous| previous != stats.on_battery) {
+                    crate::log_info!(
+                        "sarah.runtime",
+                        "Power source changed: {}",
+                        if stats.on_battery { "battery" } else { "mains" }
+                    );
+                    let _ = app_handle.emit(
+                        POWER_STATE_EVENT,
+                        serde_json::json!({
+                            "onBattery": stats.on_battery,
+                            "batteryPct": stats.battery_pct,
+                        }),
+                    );
+                }
+                last_on_battery = Some(stats.on_battery);
+            }
+        });
+    }
+
+    fn record_thermal_change(
+        &self,
+        analytics: &AnalyticsService,
+        stats: &LiveSystemStats,
+        throttle: ThermalThrottle,
+    ) {
+        if throttle.is_active() {
+            crate::log_warn!(
+                "sarah.runtime",
+                "Thermal limits engaged (CPU {:?}°C, GPU {:?}°C)",
+                stats.cpu_temp_c,
+                stats.gpu_temp_c
+            );
+        } else {
+            crate::log_info!("sarah.runtime", "Thermal limits lifted");
+        }
+        let metadata = serde_json::json!({
+            "cpuHot": throttle.cpu_hot,
+            "gpuHot": throttle.gpu_hot,
+            "cpuTempC": stats.cpu_temp_c,
+            "gpuTempC": stats.gpu_temp_c,
+        })
+        .to_string();
+        let event_type = if throttle.is_active() {
+            "thermal_degraded"
+        } else {
+            "thermal_recovered"
+        };
+        let analytics = analytics.clone();
+        tokio::spawn(async move {
+            let _ = analytics
+                .log_event(event_type, 0, true, Some(metadata))
+                .await;
+        });
+    }
+
     pub fn classify_pressure(&self, stats: &LiveSystemStats, policy: &RuntimePolicy) -> String {
         let mem_pct = if stats.memory_total_mb == 0 {
             0.0
@@ -137,11 +333,14 @@ impl RuntimeGovernorService {
         pressure: &str,
         is_background: bool,
     ) -> GenerationOptions {
-        let lane_cap = if is_background {
+        let mut lane_cap = if is_background {
             policy.background_max_tokens
         } else {
             policy.interactive_max_tokens
         };
+        if self.on_battery() {
+            lane_cap = lane_cap.min(policy.battery_max_tokens);
+        }
 
         let qos_factor = match qos {
             "fast" => 0.72,
@@ -193,4 +392,36 @@ fn apply_patch(policy: &mut RuntimePolicy, patch: RuntimePolicyPatch) {
     if let Some(value) = patch.defer_background_under_pressure {
         policy.defer_background_under_pressure = value;
     }
+    if let Some(value) = patch.battery_max_tokens {
+        policy.battery_max_tokens = value.clamp(64, 4096);
+    }
+    if let Some(value) = patch.battery_defer_background {
+        policy.battery_defer_background = value;
+    }
+    if let Some(value) = patch.battery_disable_preload {
+        policy.battery_disable_preload = value;
+    }
+    if let Some(value) = patch.thermal_protection {
+        policy.thermal_protection = value;
+    }
+    if let Some(value) = patch.thermal_cpu_c {
+        policy.thermal_cpu_c = value.clamp(60.0, 105.0);
+    }
+    if let Some(value) = patch.thermal_gpu_c {
+        policy.thermal_gpu_c = value.clamp(60.0, 105.0);
+    }
+    if let Some(value) = patch.idle_threshold_secs {
+        policy.idle_threshold_secs = value.min(3600);
+    }
+    if let Some(value) = patch.retrieval_bm25_weight {
+        policy.retrieval_bm25_weight = value.clamp(0.0, 4.0);
+    }
+    if let Some(value) = patch.retrieval_vector_weight {
+        policy.retrieval_vector_weight = value.clamp(0.0, 4.0);
+    }
+    // With both rankings off nothing would be retrieved; fall back to an even mix.
+    if policy.retrieval_bm25_weight == 0.0 && policy.retrieval_vector_weight == 0.0 {
+        policy.retrieval_bm25_weight = 1.0;
+        policy.retrieval_vector_weight = 1.0;
+    }
 }
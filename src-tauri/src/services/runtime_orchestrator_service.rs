@@ -170,9 +170,11 @@ impl RuntimeOrchestratorService {
         max_tokens = max_tokens.clamp(96, budget.interactive_max_tokens);
 
         let context_window_hint = self.query_classifier.context_window_hint().await;
-        let defer_background = allow_background_defer
-            && policy.defer_background_under_pressure
+        let under_pressure = policy.defer_background_under_pressure
             && matches!(pressure.as_str(), "high" | "critical");
+        let on_battery = policy.battery_defer_background && stats.on_battery;
+        let defer_background = allow_background_defer
+            && (under_pressure || on_battery || self.runtime_governor.background_paused());
 
         Ok(OrchestratedRequest {
             task_type,
@@ -189,6 +191,17 @@ impl RuntimeOrchestratorService {
         if !self.feature_gates.predictive_preload_enabled {
             return;
         }
+        // Loading a model speculatively costs more battery than it saves time.
+        if self.runtime_governor.on_battery() {
+            let policy = self
+                .runtime_governor
+                .get_policy(None)
+                .await
+                .unwrap_or_default();
+            if policy.battery_disable_preload {
+                return;
+            }
+        }
         self.predictive_preloader
             .maybe_preload(model_path, profile)
             .await;
@@ -318,15 +331,21 @@ impl RuntimeOrchestratorService {
             tier_budget.retrieval_candidate_limit
         };
 
+        let battery_cap = if stats.on_battery {
+            policy.battery_max_tokens
+        } else {
+            usize::MAX
+        };
+
         ServiceBudget {
             interactive_max_tokens: min(
                 ((tier_budget.interactive_max_tokens as f64) * factor).round() as usize,
-                policy.interactive_max_tokens,
+                policy.interactive_max_tokens.min(battery_cap),
             )
             .max(96),
             background_max_tokens: min(
                 ((tier_budget.background_max_tokens as f64) * factor).round() as usize,
-                policy.background_max_tokens,
+                policy.background_max_tokens.min(battery_cap),
             )
             .max(64),
             retrieval_candidate_limit: min(
@@ -347,6 +366,29 @@ impl RuntimeOrchestratorService {
     }
 }
 
+/// Maps the chat UI's response-mode toggle onto a QoS lane understood by the
+/// runtime governor. Unknown modes return `None` so callers fall back to inference.
+pub fn qos_for_response_mode(mode: &str) -> Option<&'static str> {
+    match mode.trim().to_lowercase().replace(['-', ' '], "_").as_str() {
+        "quick" | "quick_answer" | "fast" => Some("fast"),
+        "standard" | "balanced" => Some("balanced"),
+        "deep" | "deep_work" | "max_quality" => Some("max_quality"),
+        _ => None,
+    }
+}
+
+/// Explicit `qos` wins over a response-mode hint; empty values are ignored.
+pub fn resolve_requested_qos(qos: Option<&str>, response_mode: Option<&str>) -> Option<String> {
+    qos.map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(sanitize_qos)
+        .or_else(|| {
+            response_mode
+                .and_then(qos_for_response_mode)
+                .map(str::to_string)
+        })
+}
+
 fn sanitize_task_type(task_type: &str) -> String {
     match task_type.trim().to_lowercase().as_str() {
         "chat" | "code" | "reasoning" | "retrieval_heavy" | "tool_heavy" => {
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, RwLock};
+
+use crate::db::models::SystemProfile;
+use crate::repositories::settings_repo::SettingChange;
+use crate::services::hardware_service::{
+    HardwareService, MAX_RESIDENT_MODELS_KEY, PERFORMANCE_MODE_KEY, PERFORMANCE_SETTINGS_NAMESPACE,
+};
+use crate::services::inference_service::InferenceService;
+use crate::services::runtime_governor_service::{RuntimeGovernorService, RUNTIME_POLICY_NAMESPACE};
+use crate::services::tts_service::VOICE_SETTINGS_NAMESPACE;
+use crate::services::wake_word_service::{WakeWordService, WAKE_WORD_SETTINGS_KEY};
+use crate::state::AppCache;
+
+/// Changes arriving within this window of the first one are applied together,
+/// keeping only the latest value per key.
+const APPLY_THROTTLE: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsWatcherStatus {
+    pub batches_applied: u64,
+    pub changes_applied: u64,
+    pub last_applied_at: Option<String>,
+    pub last_keys: Vec<String>,
+}
+
+/// Applies settings changes to the live services so nothing has to re-read them
+/// on a timer or wait for a restart.
+#[derive(Clone)]
+pub struct SettingsWatcher {
+    cache: Arc<AppCache>,
+    hardware: Arc<RwLock<Option<SystemProfile>>>,
+    hardware_service: Arc<HardwareService>,
+    runtime_governor: Arc<RuntimeGovernorService>,
+    inference: Arc<InferenceService>,
+    wake_word: Arc<WakeWordService>,
+    status: Arc<Mutex<SettingsWatcherStatus>>,
+}
+
+impl SettingsWatcher {
+    pub fn new(
+        cache: Arc<AppCache>,
+        hardware: Arc<RwLock<Option<SystemProfile>>>,
+        hardware_service: Arc<HardwareService>,
+        runtime_governor: Arc<RuntimeGovernorService>,
+        inference: Arc<InferenceService>,
+        wake_word: Arc<WakeWordService>,
+    ) -> Self {
+        Self {
+            cache,
+            hardware,
+            hardware_service,
+            runtime_governor,
+            inference,
+            wake_word,
+            status: Arc::new(Mutex::new(SettingsWatcherStatus::default())),
+        }
+    }
+
+    pub fn status(&self) -> SettingsWatcherStatus {
+        self.status
+            .lock()
+            .map(|status| status.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn start(&self, mut changes: broadcast::Receiver<SettingChange>) {
+        let watcher = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let mut pending = HashMap::new();
+                let mut resync = false;
+                match changes.recv().await {
+                    Ok(change) => {
+                        pending.insert(change_key(&change), change);
+                    }
+                    Err(RecvError::Lagged(_)) => resync = true,
+                    Err(RecvError::Closed) => break,
+                }
+
+                let deadline = tokio::time::Instant::now() + APPLY_THROTTLE;
+                let mut closed = false;
+                loop {
+                    match tokio::time::timeout_at(deadline, changes.recv()).await {
+                        Ok(Ok(change)) => {
+                            pending.insert(change_key(&change), change);
+                        }
+                        Ok(Err(RecvError::Lagged(skipped))) => {
+                            crate::log_warn!(
+                                "sarah.settings",
+                                "Settings watcher skipped {} changes; resyncing",
+                                skipped
+                            );
+                            resync = true;
+                        }
+                        Ok(Err(RecvError::Closed)) => {
+                            closed = true;
+                            break;
+                        }
+                        Err(_) => break,
+                    }
+                }
+
+                let batch = pending.into_values().collect::<Vec<_>>();
+                watcher.apply(&batch, resync).await;
+                if closed {
+                    break;
+                }
+            }
+        });
+    }
+
+    async fn apply(&self, changes: &[SettingChange], resync: bool) {
+        let mut performance_changed = resync;
+        let mut wake_word_changed = resync;
+        self.cache.user_settings.invalidate_all();
+        if resync {
+            self.runtime_governor.invalidate_policy(None);
+        }
+
+        for change in changes {
+            if change.namespace == PERFORMANCE_SETTINGS_NAMESPACE
+                && change.key == PERFORMANCE_MODE_KEY
+            {
+                self.hardware_service
+                    .invalidate_performance_mode(change.user_id.as_deref());
+                performance_changed |= change.user_id.is_none();
+            } else if change.namespace == PERFORMANCE_SETTINGS_NAMESPACE
+                && change.key == MAX_RESIDENT_MODELS_KEY
+            {
+                performance_changed |= change.user_id.is_none();
+            } else if change.namespace == RUNTIME_POLICY_NAMESPACE {
+                self.runtime_governor
+                    .invalidate_policy(change.user_id.as_deref());
+            } else if change.namespace == VOICE_SETTINGS_NAMESPACE
+                && change.key == WAKE_WORD_SETTINGS_KEY
+            {
+                wake_word_changed = true;
+            }
+        }
+
+        if resync {
+            self.hardware_service.invalidate_performance_mode(None);
+        }
+        if performance_changed {
+            let mode = self.hardware_service.get_performance_mode(None).await;
+            self.cache.apply_performance_mode(&mode);
+            if let Some(profile) = self.hardware.read().await.as_ref() {
+                self.inference
+                    .apply_performance_mode(&mode, profile.cpu_threads);
+                self.inference
+                    .configure_pool(self.hardware_service.model_pool_limits(profile).await);
+            }
+            crate::log_info!(
+                "sarah.settings",
+                "Applied performance mode {}",
+                mode.as_str()
+            );
+        }
+        // The wake word detector pauses in Multitasking mode.
+        if wake_word_changed || performance_changed {
+            self.wake_word.refresh().await;
+        }
+
+        if let Ok(mut status) = self.status.lock() {
+            status.batches_applied += 1;
+            status.changes_applied += changes.len() as u64;
+            status.last_applied_at = Some(chrono::Utc::now().to_rfc3339());
+            status.last_keys = changes
+                .iter()
+                .map(|change| format!("{}.{}", change.namespace, change.key))
+                .collect();
+        }
+    }
+}
+
+fn change_key(change: &SettingChange) -> (Option<String>, String, String) {
+    (
+        change.user_id.clone(),
+        change.namespace.clone(),
+        change.key.clone(),
+    )
+}
@@ -3,18 +3,25 @@ use uuid::Uuid;
 
 use crate::db::models::SetupState;
 use crate::error::AppError;
+use crate::services::notification_service::{NotificationCategory, NotificationService};
 
 #[derive(Clone)]
 pub struct SetupOrchestratorService {
     read_pool: SqlitePool,
     write_pool: SqlitePool,
+    notifications: NotificationService,
 }
 
 impl SetupOrchestratorService {
-    pub fn new(read_pool: SqlitePool, write_pool: SqlitePool) -> Self {
+    pub fn new(
+        read_pool: SqlitePool,
+        write_pool: SqlitePool,
+        notifications: NotificationService,
+    ) -> Self {
         Self {
             read_pool,
             write_pool,
+            notifications,
         }
     }
 
@@ -73,17 +80,26 @@ impl SetupOrchestratorService {
     }
 
     pub async fn mark_completed(&self, user_id: Option<&str>) -> Result<SetupState, AppError> {
-        self.upsert_state(
-            user_id,
-            "completed",
-            "stage_d_background_upgrade_queued",
-            100.0,
-            None,
-            None,
-            None,
-            Some(r#"{"autoUpgrade":"queued"}"#),
-        )
-        .await
+        let state = self
+            .upsert_state(
+                user_id,
+                "completed",
+                "stage_d_background_upgrade_queued",
+                100.0,
+                None,
+                None,
+                None,
+                Some(r#"{"autoUpgrade":"queued"}"#),
+            )
+            .await?;
+        self.notifications
+            .notify(
+                NotificationCategory::Setup,
+                "Sarah is ready",
+                "Setup finished. Your starter model is installed and ready to chat.",
+            )
+            .await;
+        Ok(state)
     }
 
     pub async fn mark_failed(
@@ -92,8 +108,17 @@ impl SetupOrchestratorService {
         stage: &str,
         error: &str,
     ) -> Result<SetupState, AppError> {
-        self.upsert_state(user_id, "failed", stage, 0.0, None, None, Some(error), None)
-            .await
+        let state = self
+            .upsert_state(user_id, "failed", stage, 0.0, None, None, Some(error), None)
+            .await?;
+        self.notifications
+            .notify(
+                NotificationCategory::Setup,
+                "Setup needs attention",
+                &format!("Setup stopped: {error}"),
+            )
+            .await;
+        Ok(state)
     }
 
     pub async fn skip_quality_upgrade(
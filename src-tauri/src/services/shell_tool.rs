@@ -0,0 +1,656 @@
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::error::AppError;
+use crate::repositories::settings_repo::SettingsRepo;
+use crate::services::filesystem_tools::{
+    approved_dir, check_within_roots, FilesystemRoot, FilesystemTools,
+};
+
+/// Seeded by migration 0029. Every call is confirmed by the user, whatever its
+/// stored permission; see `ToolApprovalService::policy`.
+pub const BUILTIN_SHELL_MCP_ID: &str = "builtin-shell";
+pub const SHELL_SETTINGS_NAMESPACE: &str = "shell_tool";
+const ALLOWLIST_KEY: &str = "allowed_commands";
+
+/// Used until the user edits the list: commands that only look at things.
+/// `git` is left out: a repository's own config can make it run programs.
+const DEFAULT_ALLOWED_COMMANDS: [&str; 13] = [
+    "ls", "pwd", "whoami", "hostname", "date", "uname", "uptime", "df", "du", "wc", "head", "tail",
+    "grep",
+];
+/// Git options that run another program, load config that can, write files
+/// or switch to another repository, refused even when the user allowlisted
+/// `git`. Matched as prefixes, so `--exec` also covers `--exec-path`,
+/// `--config` covers `--config-env` and `-c` covers `-ckey=value`.
+const REFUSED_GIT_OPTIONS: [&str; 11] = [
+    "-c",
+    "-C",
+    "--config",
+    "--git-dir",
+    "--work-tree",
+    "--namespace",
+    "--upload-pack",
+    "--receive-pack",
+    "--exec",
+    "--template",
+    "--output",
+];
+/// Short forms refused only after these subcommands: `clone -u` is
+/// `--upload-pack`, `rebase -x` is `--exec` and `difftool -x` is `--extcmd`.
+const REFUSED_GIT_SUBCOMMAND_OPTIONS: [(&str, &str); 4] = [
+    ("clone", "-u"),
+    ("rebase", "-x"),
+    ("difftool", "-x"),
+    ("difftool", "--extcmd"),
+];
+/// Git options before the subcommand whose value is the next argument.
+const GIT_OPTIONS_WITH_VALUE: [&str; 7] = [
+    "-c",
+    "-C",
+    "--config-env",
+    "--git-dir",
+    "--work-tree",
+    "--namespace",
+    "--super-prefix",
+];
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_TIMEOUT: Duration = Duration::from_secs(120);
+/// Per stream; the rest of the output is read and dropped.
+const MAX_OUTPUT_BYTES: usize = 16 * 1024;
+
+pub fn tool_schemas() -> Vec<Value> {
+    vec![json!({
+        "name": "run_command",
+        "description": "Run a program on the user's computer and return its output, e.g. `ls -la`. Only allowlisted programs can run, without a shell (no pipes, redirects or variables), in a folder the user approved and only on paths inside the approved folders, and the user confirms every command.",
+        "inputSchema": {
+            "type": "object",
+            "properties": {
+                "command": { "type": "string", "description": "The program, e.g. ls; may include its arguments, e.g. ls -la" },
+                "args": { "type": "array", "items": { "type": "string" }, "description": "Arguments, when not given in command" },
+                "cwd": { "type": "string", "description": "Folder to run in, one the user approved or inside one; absolute, starting with ~ or relative to the first approved folder, which is used when omitted" },
+                "timeoutSecs": { "type": "integer", "description": "Seconds before the program is stopped; at most 120" }
+            },
+            "required": ["command"]
+        }
+    })]
+}
+
+/// The built-in `run_command` tool. Programs are started directly, never
+/// through a shell, only when their name is on the user's allowlist, and only
+/// in the folders approved for the file tools.
+#[derive(Clone)]
+pub struct ShellTool {
+    settings_repo: SettingsRepo,
+    filesystem: FilesystemTools,
+}
+
+impl ShellTool {
+    pub fn new(settings_repo: SettingsRepo, filesystem: FilesystemTools) -> Self {
+        Self {
+            settings_repo,
+            filesystem,
+        }
+    }
+
+    pub async fn allowlist(&self) -> Result<Vec<String>, AppError> {
+        let setting = self
+            .settings_repo
+            .get_setting(None, SHELL_SETTINGS_NAMESPACE, ALLOWLIST_KEY)
+            .await?;
+        Ok(setting
+            .and_then(|setting| serde_json::from_str(&setting.value).ok())
+            .unwrap_or_else(|| {
+                DEFAULT_ALLOWED_COMMANDS
+                    .iter()
+                    .map(|command| command.to_string())
+                    .collect()
+            }))
+    }
+
+    /// Replaces the allowlist. Entries are bare program names, not paths.
+    pub async fn set_allowlist(&self, commands: Vec<String>) -> Result<Vec<String>, AppError> {
+        let mut allowlist = Vec::new();
+        for command in commands {
+            let command = command.trim().to_string();
+            if command.is_empty() {
+                continue;
+            }
+            if !is_bare_name(&command) {
+                return Err(AppError::Validation {
+                    field: "commands".to_string(),
+                    message: format!("{command} must be a program name without a folder"),
+                });
+            }
+            if !allowlist.contains(&command) {
+                allowlist.push(command);
+            }
+        }
+        let json = serde_json::to_string(&allowlist)
+            .map_err(|e| AppError::Internal(format!("Failed to save allowed commands: {e}")))?;
+        self.settings_repo
+            .upsert_setting(
+                None,
+                SHELL_SETTINGS_NAMESPACE,
+                ALLOWLIST_KEY,
+                &json,
+                "json",
+                false,
+            )
+            .await?;
+        Ok(allowlist)
+    }
+
+    /// Runs `run_command` and returns the exit code and output. A program that
+    /// runs and fails is not an error: the model reads why from its output.
+    pub async fn call(&self, tool_name: &str, args: Value) -> Result<String, AppError> {
+        if tool_name != "run_command" {
+            return Err(AppError::Validation {
+                field: "tool".to_string(),
+                message: format!("Unknown shell tool: {tool_name}"),
+            });
+        }
+        let (program, program_args) = command_line(&args)?;
+        let allowlist = self.allowlist().await?;
+        if !allowlist
+            .iter()
+            .any(|allowed| program_name(allowed) == program_name(&program))
+        {
+            return Err(AppError::Validation {
+                field: "command".to_string(),
+                message: format!(
+                    "{program} is not an allowed command. Allowed: {}",
+                    allowlist.join(", ")
+                ),
+            });
+        }
+        if program_name(&program) == "git" {
+            check_git_args(&program_args)?;
+        }
+        let roots = self.filesystem.roots().await?;
+        let cwd = approved_dir(&roots, args.get("cwd").and_then(Value::as_str))
+            .map_err(|error| with_field(error, "cwd"))?;
+        check_paths(&roots, &cwd, &program_args)?;
+        let timeout = args
+            .get("timeoutSecs")
+            .and_then(Value::as_u64)
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TIMEOUT)
+            .clamp(Duration::from_secs(1), MAX_TIMEOUT);
+
+        let mut command = tokio::process::Command::new(&program);
+        command
+            .args(&program_args)
+            .current_dir(&cwd)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        #[cfg(windows)]
+        command.creation_flags(0x0800_0000); // CREATE_NO_WINDOW
+
+        let mut child = command.spawn().map_err(|e| AppError::Validation {
+            field: "command".to_string(),
+            message: format!("Could not start {program}: {e}"),
+        })?;
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let run = async { tokio::join!(read_capped(stdout), read_capped(stderr), child.wait()) };
+        let (stdout, stderr, status) = tokio::time::timeout(timeout, run).await.map_err(|_| {
+            AppError::Timeout(format!(
+                "{program} was stopped after {} seconds",
+                timeout.as_secs()
+            ))
+        })?;
+        let status = status?;
+
+        let mut output = format!(
+            "$ {}\nexit code: {}",
+            std::iter::once(program.as_str())
+                .chain(program_args.iter().map(String::as_str))
+                .collect::<Vec<_>>()
+                .join(" "),
+            status
+                .code()
+                .map(|code| code.to_string())
+                .unwrap_or_else(|| "none (killed)".to_string())
+        );
+        for (label, text) in [("stdout", stdout), ("stderr", stderr)] {
+            if !text.trim().is_empty() {
+                output.push_str(&format!("\n--- {label} ---\n{}", text.trim_end()));
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// `command` alone may hold the whole command line; `args`, when given, wins.
+fn command_line(args: &Value) -> Result<(String, Vec<String>), AppError> {
+    let command = args
+        .get("command")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|command| !command.is_empty())
+        .ok_or_else(|| AppError::Validation {
+            field: "command".to_string(),
+            message: "`command` is required".to_string(),
+        })?;
+    let explicit_args = args.get("args").and_then(Value::as_array).map(|values| {
+        values
+            .iter()
+            .map(|value| match value {
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let (program, args) = match explicit_args {
+        Some(args) => (command.to_string(), args),
+        None => {
+            let mut words = split_words(command).into_iter();
+            let program = words.next().unwrap_or_default();
+            (program, words.collect())
+        }
+    };
+    if !is_bare_name(&program) {
+        return Err(AppError::Validation {
+            field: "command".to_string(),
+            message: format!("{program} must be a program name without a folder"),
+        });
+    }
+    Ok((program, args))
+}
+
+/// Whitespace-separated words; single or double quotes keep spaces in one.
+/// Backslashes are plain characters, so Windows paths survive.
+fn split_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    for ch in line.chars() {
+        match quote {
+            Some(open) if ch == open => quote = None,
+            Some(_) => current.push(ch),
+            None if ch == '"' || ch == '\'' => {
+                quote = Some(ch);
+                in_word = true;
+            }
+            None if ch.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            None => {
+                current.push(ch);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+    words
+}
+
+fn is_bare_name(program: &str) -> bool {
+    !program.is_empty() && !program.contains(['/', '\\', ':']) && program != "." && program != ".."
+}
+
+/// Case and `.exe` are ignored so Windows names match.
+fn program_name(program: &str) -> String {
+    let lower = program.to_lowercase();
+    lower.strip_suffix(".exe").unwrap_or(&lower).to_string()
+}
+
+fn check_git_args(args: &[String]) -> Result<(), AppError> {
+    let subcommand = git_subcommand(args);
+    let refused = args.iter().find(|arg| {
+        REFUSED_GIT_OPTIONS
+            .iter()
+            .any(|option| arg.starts_with(option))
+            || REFUSED_GIT_SUBCOMMAND_OPTIONS
+                .iter()
+                .any(|(name, option)| subcommand == Some(*name) && arg.starts_with(option))
+    });
+    match refused {
+        Some(arg) => Err(AppError::Validation {
+            field: "args".to_string(),
+            message: format!("git {arg} is not allowed: it can run other programs or write files"),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// The first argument that is neither an option nor an option's value.
+fn git_subcommand(args: &[String]) -> Option<&str> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') {
+            return Some(arg);
+        }
+        if GIT_OPTIONS_WITH_VALUE.contains(&arg.as_str()) {
+            args.next();
+        }
+    }
+    None
+}
+
+/// Refuses arguments that name something outside the approved folders. Any
+/// argument may be a path, so all are checked relative to `cwd`: words that
+/// aren't paths stay inside it. For options, the value after `=` is checked,
+/// or what follows `-x` when it holds a path, as in `-f/etc/passwd`.
+fn check_paths(roots: &[FilesystemRoot], cwd: &Path, args: &[String]) -> Result<(), AppError> {
+    for arg in args {
+        let path = match arg.strip_prefix('-') {
+            None => Some(arg.as_str()),
+            Some(option) => match option.split_once('=') {
+                Some((_, value)) => Some(value),
+                None if !option.starts_with('-') => option
+                    .find(['/', '\\', '~'])
+                    .map(|at| option[..at].trim_end_matches('.').len())
+                    .map(|start| &option[start..]),
+                None => None,
+            },
+        };
+        let Some(path) = path.filter(|path| !path.is_empty()) else {
+            continue;
+        };
+        check_within_roots(roots, cwd, path).map_err(|error| with_field(error, "args"))?;
+    }
+    Ok(())
+}
+
+fn with_field(error: AppError, field: &str) -> AppError {
+    match error {
+        AppError::Validation { message, .. } => AppError::Validation {
+            field: field.to_string(),
+            message,
+        },
+        other => other,
+    }
+}
+
+/// Reads a stream to the end, keeping the first `MAX_OUTPUT_BYTES`.
+async fn read_capped(stream: Option<impl AsyncRead + Unpin>) -> String {
+    let Some(mut stream) = stream else {
+        return String::new();
+    };
+    let mut kept = Vec::new();
+    let _ = (&mut stream)
+        .take(MAX_OUTPUT_BYTES as u64)
+        .read_to_end(&mut kept)
+        .await;
+    let dropped = tokio::io::copy(&mut stream, &mut tokio::io::sink())
+        .await
+        .unwrap_or(0);
+
+    let mut text = String::from_utf8_lossy(&kept).to_string();
+    if dropped > 0 {
+        text.push_str(&format!("\n[... {dropped} more bytes cut off]"));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use serde_json::json;
+
+    use super::{
+        check_git_args, check_paths, command_line, git_subcommand, is_bare_name, split_words,
+    };
+    use crate::error::AppError;
+    use crate::services::filesystem_tools::{approved_dir, FilesystemRoot};
+
+    fn words(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    /// A fresh folder under the system temp dir, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let nanos = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos();
+            let path = std::env::temp_dir().join(format!("sarah-shell-{name}-{nanos}"));
+            std::fs::create_dir_all(&path).unwrap();
+            Self(std::fs::canonicalize(path).unwrap())
+        }
+
+        fn path(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn root(dir: &Path) -> FilesystemRoot {
+        FilesystemRoot {
+            path: dir.to_string_lossy().to_string(),
+            writable: false,
+        }
+    }
+
+    #[test]
+    fn split_words_keeps_quoted_spaces() {
+        assert_eq!(split_words("  ls   -la  "), words(&["ls", "-la"]));
+        assert_eq!(
+            split_words(r#"grep "two words" 'it''s' x"y z"w"#),
+            words(&["grep", "two words", "its", "xy zw"])
+        );
+        assert_eq!(split_words(r#"echo "" ''"#), words(&["echo", "", ""]));
+        assert_eq!(split_words(r#"echo "it's""#), words(&["echo", "it's"]));
+        // An unterminated quote runs to the end of the line.
+        assert_eq!(split_words("echo 'a b"), words(&["echo", "a b"]));
+        assert!(split_words("   ").is_empty());
+    }
+
+    #[test]
+    fn split_words_keeps_backslashes() {
+        assert_eq!(
+            split_words(r#"dir C:\Users\me a\ b "c\"d"#),
+            words(&["dir", r"C:\Users\me", r"a\", "b", r"c\d"])
+        );
+    }
+
+    #[test]
+    fn command_line_splits_or_takes_args() {
+        let (program, args) = command_line(&json!({ "command": " ls -la 'my dir' " })).unwrap();
+        assert_eq!(program, "ls");
+        assert_eq!(args, words(&["-la", "my dir"]));
+
+        let (program, args) =
+            command_line(&json!({ "command": "grep", "args": ["a b", 3, true] })).unwrap();
+        assert_eq!(program, "grep");
+        assert_eq!(args, words(&["a b", "3", "true"]));
+    }
+
+    #[test]
+    fn command_line_rejects_missing_or_path_programs() {
+        for args in [
+            json!({}),
+            json!({ "command": "  " }),
+            json!({ "command": 1 }),
+        ] {
+            assert!(matches!(
+                command_line(&args),
+                Err(AppError::Validation { field, .. }) if field == "command"
+            ));
+        }
+        for command in ["/bin/ls", "./ls -la", r"C:\Windows\cmd.exe", "'' ls"] {
+            assert!(
+                command_line(&json!({ "command": command })).is_err(),
+                "{command}"
+            );
+        }
+        // With `args`, the whole of `command` is the program name.
+        assert!(command_line(&json!({ "command": "ls -la", "args": [] })).is_ok());
+        assert!(command_line(&json!({ "command": "bin/ls", "args": [] })).is_err());
+    }
+
+    #[test]
+    fn bare_names() {
+        for name in ["ls", "git", "python3", "rg.exe", "my tool"] {
+            assert!(is_bare_name(name), "{name}");
+        }
+        for name in ["", ".", "..", "/bin/ls", "bin/ls", r"bin\ls", "C:ls"] {
+            assert!(!is_bare_name(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn git_options_that_run_programs_are_refused() {
+        for args in [
+            &["-c", "core.pager=sh", "log"][..],
+            &["-ccore.sshCommand=sh", "fetch"],
+            &["--config-env=core.pager=X", "log"],
+            &["--exec-path=/tmp", "status"],
+            &["fetch", "--upload-pack=sh"],
+            &["push", "--receive-pack", "sh"],
+            &["rebase", "--exec", "sh"],
+            &["clone", "-u", "sh", "repo"],
+            &["clone", "--template=/tmp/t", "repo"],
+            &["log", "--output=/tmp/x"],
+            &["-C", "/some/other/repo", "status"],
+            &["-C/some/other/repo", "status"],
+            &["--git-dir=/some/other/repo/.git", "log"],
+            &["--git-dir", "/some/other/repo/.git", "log"],
+            &["--work-tree=/", "status"],
+            &["--namespace", "x", "log"],
+            &["clone", "--config", "core.sshCommand=sh", "repo"],
+            &["rebase", "-x", "sh", "main"],
+            &["difftool", "--extcmd=sh"],
+            &["--no-pager", "clone", "-usha", "repo"],
+        ] {
+            assert!(check_git_args(&words(args)).is_err(), "{args:?}");
+        }
+        for args in [
+            &["status"][..],
+            &["log", "--oneline", "-n", "5"],
+            &["diff", "--cached"],
+            &["log", "-u"],
+            &["log", "-x"],
+            &[],
+        ] {
+            assert!(check_git_args(&words(args)).is_ok(), "{args:?}");
+        }
+    }
+
+    #[test]
+    fn git_subcommand_skips_option_values() {
+        for (args, subcommand) in [
+            (&["status"][..], Some("status")),
+            (&["-C", "repo", "clone", "-u", "x"], Some("clone")),
+            (
+                &["--git-dir", "x/.git", "--work-tree", "x", "log"],
+                Some("log"),
+            ),
+            (&["-c", "a=b", "--no-pager", "diff"], Some("diff")),
+            (&["--git-dir=x", "log"], Some("log")),
+            (&["--version"], None),
+        ] {
+            assert_eq!(git_subcommand(&words(args)), subcommand, "{args:?}");
+        }
+    }
+
+    #[test]
+    fn cwd_must_be_an_approved_folder() {
+        let approved = TempDir::new("cwd");
+        let other = TempDir::new("cwd-other");
+        std::fs::create_dir(approved.path().join("sub")).unwrap();
+        std::fs::write(approved.path().join("file.txt"), "x").unwrap();
+        let roots = [root(approved.path())];
+
+        assert_eq!(approved_dir(&roots, None).unwrap(), approved.path());
+        assert_eq!(approved_dir(&roots, Some(" ")).unwrap(), approved.path());
+        assert_eq!(
+            approved_dir(&roots, Some("sub")).unwrap(),
+            approved.path().join("sub")
+        );
+        let other_path = other.path().to_string_lossy().to_string();
+        assert!(approved_dir(&roots, Some(&other_path)).is_err());
+        assert!(approved_dir(&roots, Some("..")).is_err());
+        assert!(approved_dir(&roots, Some("file.txt")).is_err());
+        assert!(approved_dir(&[], None).is_err());
+    }
+
+    #[test]
+    fn paths_outside_the_approved_folders_are_refused() {
+        let approved = TempDir::new("paths");
+        let other = TempDir::new("paths-other");
+        std::fs::create_dir(approved.path().join("sub")).unwrap();
+        std::fs::write(approved.path().join("notes.txt"), "x").unwrap();
+        std::fs::write(other.path().join("id_rsa"), "secret").unwrap();
+        let roots = [root(approved.path())];
+        let cwd = approved.path().join("sub");
+        let secret = other.path().join("id_rsa").to_string_lossy().to_string();
+
+        for args in [
+            vec!["status".to_string()],
+            words(&["-la", "../notes.txt", "new/file.txt", "--color=auto", "-n5"]),
+            words(&["-r", "TODO", "."]),
+            vec![format!("{}/notes.txt", approved.path().display())],
+        ] {
+            assert!(check_paths(&roots, &cwd, &args).is_ok(), "{args:?}");
+        }
+        for args in [
+            vec![secret.clone()],
+            vec![
+                "diff".to_string(),
+                "--no-index".to_string(),
+                secret.clone(),
+                "x".to_string(),
+            ],
+            vec![format!("--file={secret}")],
+            vec![format!("-f{secret}")],
+            words(&["../../outside.txt"]),
+            words(&["-f../../outside.txt"]),
+            words(&["missing/../../../outside.txt"]),
+            words(&["~/.aws/credentials"]),
+        ] {
+            assert!(
+                matches!(
+                    check_paths(&roots, &cwd, &args),
+                    Err(AppError::Validation { field, .. }) if field == "args"
+                ),
+                "{args:?}"
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_out_of_the_approved_folders_are_refused() {
+        let approved = TempDir::new("links");
+        let other = TempDir::new("links-other");
+        std::fs::write(other.path().join("secret"), "x").unwrap();
+        std::os::unix::fs::symlink(other.path().join("secret"), approved.path().join("link"))
+            .unwrap();
+        std::os::unix::fs::symlink(other.path(), approved.path().join("dir")).unwrap();
+        std::os::unix::fs::symlink(other.path().join("gone"), approved.path().join("dangling"))
+            .unwrap();
+        let roots = [root(approved.path())];
+
+        for arg in ["link", "dir/secret", "dir/new.txt", "dangling"] {
+            assert!(
+                check_paths(&roots, approved.path(), &words(&[arg])).is_err(),
+                "{arg}"
+            );
+        }
+    }
+}
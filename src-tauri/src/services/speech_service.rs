@@ -0,0 +1,400 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::Emitter;
+use tokio::sync::oneshot;
+use uuid::Uuid;
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+use crate::audio_capture::{self, StereoResampler};
+use crate::db::models::Model;
+use crate::error::AppError;
+use crate::repositories::model_repo::ModelRepo;
+
+/// `category` of whisper models in the model catalog.
+pub const SPEECH_MODEL_CATEGORY: &str = "stt";
+pub const VOICE_TRANSCRIPT_EVENT: &str = "voice://transcript";
+pub const WHISPER_SAMPLE_RATE: u32 = 16_000;
+/// How often the audio so far is re-decoded for a partial transcript.
+const PARTIAL_INTERVAL: Duration = Duration::from_millis(1500);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// A capture nobody stops ends on its own after this long.
+const MAX_CAPTURE_DURATION: Duration = Duration::from_secs(60);
+const MIN_PARTIAL_SAMPLES: usize = WHISPER_SAMPLE_RATE as usize / 2;
+
+/// Emitted as `voice://transcript` while a capture runs; the last one of a
+/// capture has `is_final` set and is what goes into the prompt box.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceTranscript {
+    pub capture_id: String,
+    pub text: String,
+    pub is_final: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceCaptureHandle {
+    pub capture_id: String,
+    pub model_id: String,
+}
+
+struct ActiveCapture {
+    id: String,
+    stop: Arc<AtomicBool>,
+    finished: oneshot::Receiver<Result<String, AppError>>,
+}
+
+/// Local speech-to-text with whisper.cpp. One microphone capture runs at a
+/// time, on its own thread, since audio streams can't move between threads.
+#[derive(Clone)]
+pub struct SpeechService {
+    app_handle: tauri::AppHandle,
+    model_repo: ModelRepo,
+    /// The last whisper model loaded, by file path; kept for the next capture.
+    loaded: Arc<Mutex<Option<(String, Arc<WhisperContext>)>>>,
+    active: Arc<Mutex<Option<ActiveCapture>>>,
+}
+
+impl SpeechService {
+    pub fn new(app_handle: tauri::AppHandle, model_repo: ModelRepo) -> Self {
+        Self {
+            app_handle,
+            model_repo,
+            loaded: Arc::new(Mutex::new(None)),
+            active: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Starts recording the microphone with `model_id`, or the first installed
+    /// speech model. `language` is a whisper code such as `en`; detected when
+    /// left out.
+    pub async fn start_capture(
+        &self,
+        model_id: Option<&str>,
+        language: Option<String>,
+        device: Option<String>,
+    ) -> Result<VoiceCaptureHandle, AppError> {
+        if self.is_capturing() {
+            return Err(already_capturing());
+        }
+        let model = self.resolve_model(model_id).await?;
+        let context = self.load_context(&model).await?;
+
+        let capture_id = Uuid::new_v4().to_string();
+        let stop = Arc::new(AtomicBool::new(false));
+        let (ready_tx, ready_rx) = oneshot::channel();
+        let (finished_tx, finished_rx) = oneshot::channel();
+        let capture = CaptureThread {
+            app_handle: self.app_handle.clone(),
+            context,
+            capture_id: capture_id.clone(),
+            language,
+            stop: Arc::clone(&stop),
+        };
+        std::thread::Builder::new()
+            .name("sarah-voice-capture".to_string())
+            .spawn(move || {
+                let _ = finished_tx.send(capture.run(device, ready_tx));
+            })
+            .map_err(|error| AppError::Internal(error.to_string()))?;
+        ready_rx
+            .await
+            .map_err(|_| AppError::Hardware("Voice capture ended unexpectedly".to_string()))??;
+
+        let mut active = self
+            .active
+            .lock()
+            .map_err(|_| AppError::Internal("Voice capture lock was poisoned".to_string()))?;
+        if active.as_mut().is_some_and(ActiveCapture::is_running) {
+            stop.store(true, Ordering::SeqCst);
+            return Err(already_capturing());
+        }
+        *active = Some(ActiveCapture {
+            id: capture_id.clone(),
+            stop,
+            finished: finished_rx,
+        });
+        crate::log_info!("sarah.speech", "voice capture {} started", capture_id);
+
+        Ok(VoiceCaptureHandle {
+            capture_id,
+            model_id: model.id,
+        })
+    }
+
+    /// Stops the capture and returns its final transcript.
+    pub async fn stop_capture(&self) -> Result<VoiceTranscript, AppError> {
+        let active = self
+            .active
+            .lock()
+            .map_err(|_| AppError::Internal("Voice capture lock was poisoned".to_string()))?
+            .take()
+            .ok_or_else(|| AppError::NotFound {
+                entity: "voice_capture".to_string(),
+                id: "active".to_string(),
+            })?;
+        active.stop.store(true, Ordering::SeqCst);
+        let text = active
+            .finished
+            .await
+            .map_err(|_| AppError::Internal("Voice capture ended unexpectedly".to_string()))??;
+
+        Ok(VoiceTranscript {
+            capture_id: active.id,
+            text,
+            is_final: true,
+        })
+    }
+
+    /// Loads `model_id`, or the first installed speech model, for use outside
+    /// a capture.
+    pub async fn load_model(
+        &self,
+        model_id: Option<&str>,
+    ) -> Result<Arc<WhisperContext>, AppError> {
+        let model = self.resolve_model(model_id).await?;
+        self.load_context(&model).await
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.active
+            .lock()
+            .map(|mut active| active.as_mut().is_some_and(ActiveCapture::is_running))
+            .unwrap_or(false)
+    }
+
+    async fn resolve_model(&self, model_id: Option<&str>) -> Result<Model, AppError> {
+        let requested = model_id.map(str::trim).filter(|value| !value.is_empty());
+        let model = match requested {
+            Some(id) => match self.model_repo.get_by_id(id).await? {
+                Some(model) => Some(model),
+                None => self.model_repo.get_by_name(id).await?,
+            },
+            None => self
+                .model_repo
+                .list_by_category(SPEECH_MODEL_CATEGORY, true)
+                .await?
+                .into_iter()
+                .next(),
+        };
+
+        let model = model.ok_or_else(|| match requested {
+            Some(id) => AppError::NotFound {
+                entity: "model".to_string(),
+                id: id.to_string(),
+            },
+            None => AppError::Config(
+                "No speech model is installed; download one from the model catalog".to_string(),
+            ),
+        })?;
+        if model.category != SPEECH_MODEL_CATEGORY {
+            return Err(AppError::Validation {
+                field: "model_id".to_string(),
+                message: format!("{} is not a speech model", model.display_name),
+            });
+        }
+        if model.is_downloaded == 0 || model.file_path.is_none() {
+            return Err(AppError::Validation {
+                field: "model_id".to_string(),
+                message: format!("{} has not been downloaded yet", model.display_name),
+            });
+        }
+        Ok(model)
+    }
+
+    async fn load_context(&self, model: &Model) -> Result<Arc<WhisperContext>, AppError> {
+        let path = model.file_path.clone().unwrap_or_default();
+        if let Ok(loaded) = self.loaded.lock() {
+            if let Some((loaded_path, context)) = loaded.as_ref() {
+                if *loaded_path == path {
+                    return Ok(Arc::clone(context));
+                }
+            }
+        }
+
+        let model_path = path.clone();
+        let context = tokio::task::spawn_blocking(move || {
+            WhisperContext::new_with_params(&model_path, WhisperContextParameters::default())
+        })
+        .await
+        .map_err(|error| AppError::Internal(error.to_string()))?
+        .map_err(|error| AppError::Inference(format!("Failed to load speech model: {error}")))?;
+        let context = Arc::new(context);
+        if let Ok(mut loaded) = self.loaded.lock() {
+            *loaded = Some((path, Arc::clone(&context)));
+        }
+        crate::log_info!("sarah.speech", "loaded speech model {}", model.name);
+        Ok(context)
+    }
+}
+
+impl ActiveCapture {
+    /// False once the capture thread has finished, e.g. at the time limit.
+    fn is_running(&mut self) -> bool {
+        matches!(
+            self.finished.try_recv(),
+            Err(oneshot::error::TryRecvError::Empty)
+        )
+    }
+}
+
+struct CaptureThread {
+    app_handle: tauri::AppHandle,
+    context: Arc<WhisperContext>,
+    capture_id: String,
+    language: Option<String>,
+    stop: Arc<AtomicBool>,
+}
+
+impl CaptureThread {
+    /// Records until stopped, emitting partial transcripts along the way, and
+    /// returns the final one. `ready` reports whether the microphone opened.
+    fn run(
+        self,
+        device: Option<String>,
+        ready: oneshot::Sender<Result<(), AppError>>,
+    ) -> Result<String, AppError> {
+        let samples = Arc::new(Mutex::new(Vec::<f32>::new()));
+        let sink = Arc::clone(&samples);
+        let mut resampler = None;
+        let mut converted = Vec::new();
+        let stream = audio_capture::start_microphone(device.as_deref(), move |frames, rate| {
+            let resampler =
+                resampler.get_or_insert_with(|| StereoResampler::new(rate, WHISPER_SAMPLE_RATE));
+            converted.clear();
+            resampler.process(frames.iter().copied(), &mut converted);
+            if let Ok(mut samples) = sink.lock() {
+                samples.extend(
+                    converted
+                        .chunks_exact(2)
+                        .map(|pair| (pair[0] + pair[1]) * 0.5),
+                );
+            }
+        });
+        let stream = match stream {
+            Ok(stream) => {
+                let _ = ready.send(Ok(()));
+                stream
+            }
+            Err(error) => {
+                let _ = ready.send(Err(AppError::Hardware(error.clone())));
+                return Err(AppError::Hardware(error));
+            }
+        };
+
+        let started = Instant::now();
+        let mut last_partial = Instant::now();
+        let mut partial_samples = 0;
+        while !self.stop.load(Ordering::SeqCst) && started.elapsed() < MAX_CAPTURE_DURATION {
+            std::thread::sleep(POLL_INTERVAL);
+            if last_partial.elapsed() < PARTIAL_INTERVAL {
+                continue;
+            }
+            last_partial = Instant::now();
+
+            let snapshot = samples
+                .lock()
+                .map(|samples| samples.clone())
+                .unwrap_or_default();
+            if snapshot.len() < MIN_PARTIAL_SAMPLES || snapshot.len() == partial_samples {
+                continue;
+            }
+            partial_samples = snapshot.len();
+            match self.transcribe(&snapshot) {
+                Ok(text) => self.emit(text, false),
+                Err(error) => {
+                    crate::log_warn!("sarah.speech", "Partial transcription failed: {}", error);
+                }
+            }
+        }
+        drop(stream);
+
+        let audio = samples
+            .lock()
+            .map(|mut samples| std::mem::take(&mut *samples))
+            .unwrap_or_default();
+        let text = self.transcribe(&audio)?;
+        self.emit(text.clone(), true);
+        crate::log_info!(
+            "sarah.speech",
+            "voice capture {} finished after {:.1}s",
+            self.capture_id,
+            audio.len() as f32 / WHISPER_SAMPLE_RATE as f32
+        );
+        Ok(text)
+    }
+
+    fn transcribe(&self, samples: &[f32]) -> Result<String, AppError> {
+        transcribe(
+            &self.context,
+            samples,
+            self.language.as_deref(),
+            transcribe_threads(),
+        )
+    }
+
+    fn emit(&self, text: String, is_final: bool) {
+        let _ = self.app_handle.emit(
+            VOICE_TRANSCRIPT_EVENT,
+            VoiceTranscript {
+                capture_id: self.capture_id.clone(),
+                text,
+                is_final,
+            },
+        );
+    }
+}
+
+/// Runs whisper over 16 kHz mono `samples`. `language` is detected when unset.
+pub fn transcribe(
+    context: &WhisperContext,
+    samples: &[f32],
+    language: Option<&str>,
+    threads: i32,
+) -> Result<String, AppError> {
+    if samples.is_empty() {
+        return Ok(String::new());
+    }
+    let mut state = context
+        .create_state()
+        .map_err(|error| AppError::Inference(format!("Speech model error: {error}")))?;
+
+    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    params.set_language(Some(language.unwrap_or("auto")));
+    params.set_n_threads(threads);
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_special(false);
+    params.set_print_timestamps(false);
+    state
+        .full(params, samples)
+        .map_err(|error| AppError::Inference(format!("Transcription failed: {error}")))?;
+
+    let segments = state
+        .full_n_segments()
+        .map_err(|error| AppError::Inference(format!("Transcription failed: {error}")))?;
+    let mut text = String::new();
+    for segment in 0..segments {
+        let piece = state
+            .full_get_segment_text(segment)
+            .map_err(|error| AppError::Inference(format!("Transcription failed: {error}")))?;
+        text.push_str(&piece);
+    }
+    Ok(text.trim().to_string())
+}
+
+fn transcribe_threads() -> i32 {
+    std::thread::available_parallelism()
+        .map(|threads| threads.get().min(8) as i32)
+        .unwrap_or(4)
+}
+
+fn already_capturing() -> AppError {
+    AppError::Validation {
+        field: "voice_capture".to_string(),
+        message: "Voice capture is already running".to_string(),
+    }
+}
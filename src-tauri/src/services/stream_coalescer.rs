@@ -0,0 +1,79 @@
+use std::time::{Duration, Instant};
+
+use crate::services::hardware_service::PerformanceMode;
+
+/// Flush thresholds for batching streamed tokens before they cross the IPC bridge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CoalescePolicy {
+    pub flush_interval: Duration,
+    pub max_tokens: usize,
+}
+
+impl CoalescePolicy {
+    pub fn for_mode(mode: &PerformanceMode) -> Self {
+        match mode {
+            PerformanceMode::Max => Self {
+                flush_interval: Duration::from_millis(16),
+                max_tokens: 4,
+            },
+            PerformanceMode::Balanced => Self {
+                flush_interval: Duration::from_millis(40),
+                max_tokens: 8,
+            },
+            PerformanceMode::Multitasking => Self {
+                flush_interval: Duration::from_millis(90),
+                max_tokens: 16,
+            },
+        }
+    }
+}
+
+pub struct TokenCoalescer {
+    policy: CoalescePolicy,
+    buffer: String,
+    buffered_tokens: usize,
+    last_flush: Instant,
+}
+
+impl TokenCoalescer {
+    pub fn new(policy: CoalescePolicy) -> Self {
+        Self {
+            policy,
+            buffer: String::new(),
+            buffered_tokens: 0,
+            last_flush: Instant::now(),
+        }
+    }
+
+    pub fn flush_interval(&self) -> Duration {
+        self.policy.flush_interval
+    }
+
+    /// Buffers a token and returns the pending batch once either threshold is hit.
+    pub fn push(&mut self, token: &str) -> Option<String> {
+        if token.is_empty() {
+            return None;
+        }
+
+        self.buffer.push_str(token);
+        self.buffered_tokens += 1;
+
+        if self.buffered_tokens >= self.policy.max_tokens
+            || self.last_flush.elapsed() >= self.policy.flush_interval
+        {
+            return self.take();
+        }
+        None
+    }
+
+    /// Drains whatever is buffered, regardless of thresholds.
+    pub fn take(&mut self) -> Option<String> {
+        self.last_flush = Instant::now();
+        if self.buffer.is_empty() {
+            return None;
+        }
+
+        self.buffered_tokens = 0;
+        Some(std::mem::take(&mut self.buffer))
+    }
+}
@@ -0,0 +1,246 @@
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::db::models::{ModelRecommendation, PerfLog, SessionFilter};
+use crate::error::AppError;
+use crate::repositories::analytics_repo::AnalyticsRepo;
+use crate::repositories::conversation_repo::ConversationRepo;
+use crate::repositories::document_repo::DocumentRepo;
+use crate::repositories::memory_repo::MemoryRepo;
+use crate::repositories::model_repo::ModelRepo;
+use crate::repositories::settings_repo::{Setting, SettingsRepo};
+use crate::repositories::user_repo::{User, UserRepo};
+use crate::services::session_export::{write_zip_archive, SessionExport};
+
+/// Bumped whenever a file is added or a field changes meaning. The layout is
+/// documented in docs/takeout-schema.md.
+pub const TAKEOUT_SCHEMA_VERSION: u32 = 1;
+
+/// Stands in for encrypted setting values; the ciphertext is useless outside
+/// this machine's keychain.
+const ENCRYPTED_PLACEHOLDER: &str = "[encrypted]";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TakeoutFile {
+    pub name: String,
+    pub records: usize,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TakeoutManifest {
+    pub schema_version: u32,
+    pub app_version: String,
+    pub exported_at: String,
+    pub user_id: String,
+    pub files: Vec<TakeoutFile>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TakeoutExport {
+    pub path: String,
+    pub manifest: TakeoutManifest,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AnalyticsTakeout {
+    perf_logs: Vec<PerfLog>,
+    recommendations: Vec<ModelRecommendation>,
+}
+
+/// Bundles everything Sarah stores about the user into one zip of JSON files.
+/// Model weights, embeddings and document chunks are left out; they are large
+/// and can be rebuilt from the sources.
+#[derive(Clone)]
+pub struct TakeoutService {
+    user_repo: UserRepo,
+    conversation_repo: ConversationRepo,
+    memory_repo: MemoryRepo,
+    document_repo: DocumentRepo,
+    settings_repo: SettingsRepo,
+    analytics_repo: AnalyticsRepo,
+    model_repo: ModelRepo,
+}
+
+impl TakeoutService {
+    pub fn new(
+        user_repo: UserRepo,
+        conversation_repo: ConversationRepo,
+        memory_repo: MemoryRepo,
+        document_repo: DocumentRepo,
+        settings_repo: SettingsRepo,
+        analytics_repo: AnalyticsRepo,
+        model_repo: ModelRepo,
+    ) -> Self {
+        Self {
+            user_repo,
+            conversation_repo,
+            memory_repo,
+            document_repo,
+            settings_repo,
+            analytics_repo,
+            model_repo,
+        }
+    }
+
+    pub async fn export(&self, path: &Path) -> Result<TakeoutExport, AppError> {
+        let user = self.user_repo.get_or_create_default_user().await?;
+        let mut files = Vec::new();
+        let mut entries = Vec::new();
+
+        let conversations = self.conversations(&user.id).await?;
+        push_json(
+            &mut files,
+            &mut entries,
+            "conversations.json",
+            "Sessions with their full message trees and tool calls",
+            &conversations,
+            conversations.len(),
+        )?;
+
+        let memories = self.memory_repo.list_all_memories(&user.id).await?;
+        push_json(
+            &mut files,
+            &mut entries,
+            "memories.json",
+            "Long-term memories, archived ones included",
+            &memories,
+            memories.len(),
+        )?;
+
+        let documents = self.document_repo.list_documents(&user.id).await?;
+        push_json(
+            &mut files,
+            &mut entries,
+            "documents.json",
+            "Metadata of indexed documents; contents and chunks are not included",
+            &documents,
+            documents.len(),
+        )?;
+
+        let settings = self.settings(&user.id).await?;
+        push_json(
+            &mut files,
+            &mut entries,
+            "settings.json",
+            "Global and per-user settings; encrypted values are replaced",
+            &settings,
+            settings.len(),
+        )?;
+
+        let analytics = AnalyticsTakeout {
+            perf_logs: self.analytics_repo.get_recent_perf_logs(i64::MAX).await?,
+            recommendations: self.analytics_repo.list_all_recommendations().await?,
+        };
+        let analytics_records = analytics.perf_logs.len() + analytics.recommendations.len();
+        push_json(
+            &mut files,
+            &mut entries,
+            "analytics.json",
+            "Local performance logs and model recommendations",
+            &analytics,
+            analytics_records,
+        )?;
+
+        let models = self.model_repo.list_all().await?;
+        push_json(
+            &mut files,
+            &mut entries,
+            "models.json",
+            "Model catalog entries; weights are not included",
+            &models,
+            models.len(),
+        )?;
+
+        push_json(
+            &mut files,
+            &mut entries,
+            "profile.json",
+            "The local user profile",
+            &user,
+            1,
+        )?;
+
+        let manifest = manifest(&user, files);
+        let manifest_json = serde_json::to_string_pretty(&manifest)
+            .map_err(|e| AppError::Internal(format!("Failed to encode takeout manifest: {e}")))?;
+        entries.insert(0, ("manifest.json".to_string(), manifest_json));
+
+        let archive_path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || write_zip_archive(&archive_path, &entries))
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))??;
+
+        crate::log_info!("sarah.takeout", "exported user data to {}", path.display());
+        Ok(TakeoutExport {
+            path: path.to_string_lossy().to_string(),
+            manifest,
+        })
+    }
+
+    async fn conversations(&self, user_id: &str) -> Result<Vec<SessionExport>, AppError> {
+        let sessions = self
+            .conversation_repo
+            .list_sessions(user_id, &SessionFilter::default(), i64::MAX, None)
+            .await?;
+        let mut conversations = Vec::with_capacity(sessions.len());
+        for session in sessions {
+            let messages = self.conversation_repo.get_message_tree(&session.id).await?;
+            let tool_calls = self
+                .conversation_repo
+                .list_session_tool_calls(&session.id)
+                .await?;
+            conversations.push(SessionExport {
+                session,
+                messages,
+                tool_calls,
+            });
+        }
+        Ok(conversations)
+    }
+
+    async fn settings(&self, user_id: &str) -> Result<Vec<Setting>, AppError> {
+        let mut settings = self.settings_repo.list_for_user(user_id).await?;
+        for setting in settings
+            .iter_mut()
+            .filter(|setting| setting.is_encrypted != 0)
+        {
+            setting.value = ENCRYPTED_PLACEHOLDER.to_string();
+        }
+        Ok(settings)
+    }
+}
+
+fn push_json<T: Serialize>(
+    files: &mut Vec<TakeoutFile>,
+    entries: &mut Vec<(String, String)>,
+    name: &str,
+    description: &str,
+    value: &T,
+    records: usize,
+) -> Result<(), AppError> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| AppError::Internal(format!("Failed to encode {name}: {e}")))?;
+    entries.push((name.to_string(), json));
+    files.push(TakeoutFile {
+        name: name.to_string(),
+        records,
+        description: description.to_string(),
+    });
+    Ok(())
+}
+
+fn manifest(user: &User, files: Vec<TakeoutFile>) -> TakeoutManifest {
+    TakeoutManifest {
+        schema_version: TAKEOUT_SCHEMA_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        user_id: user.id.clone(),
+        files,
+    }
+}
@@ -4,11 +4,18 @@ use uuid::Uuid;
 use crate::db::models::{GenerationOptions, RoutingDecision};
 use crate::error::AppError;
 use crate::repositories::model_repo::ModelRepo;
+use crate::repositories::settings_repo::SettingsRepo;
+use crate::repositories::workspace_repo::{decode_routing_rules, WorkspaceRepo};
 use crate::services::runtime_governor_service::RuntimeGovernorService;
 
+pub const MODEL_PIN_NAMESPACE: &str = "model_pins";
+pub const TASK_TYPES: &[&str] = &["chat", "code", "reasoning", "retrieval_heavy", "tool_heavy"];
+
 #[derive(Clone)]
 pub struct TaskRouterService {
     model_repo: ModelRepo,
+    settings_repo: SettingsRepo,
+    workspace_repo: WorkspaceRepo,
     runtime_governor: RuntimeGovernorService,
     write_pool: SqlitePool,
 }
@@ -16,16 +23,74 @@ pub struct TaskRouterService {
 impl TaskRouterService {
     pub fn new(
         model_repo: ModelRepo,
+        settings_repo: SettingsRepo,
+        workspace_repo: WorkspaceRepo,
         runtime_governor: RuntimeGovernorService,
         write_pool: SqlitePool,
     ) -> Self {
         Self {
             model_repo,
+            settings_repo,
+            workspace_repo,
             runtime_governor,
             write_pool,
         }
     }
 
+    pub async fn pin_model(
+        &self,
+        user_id: Option<&str>,
+        task_type: &str,
+        model_id: &str,
+    ) -> Result<(), AppError> {
+        let task = validate_task_type(task_type)?;
+        self.settings_repo
+            .upsert_setting(user_id, MODEL_PIN_NAMESPACE, &task, model_id, "string", false)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn unpin_model(&self, user_id: Option<&str>, task_type: &str) -> Result<bool, AppError> {
+        let task = validate_task_type(task_type)?;
+        self.settings_repo
+            .delete_setting(user_id, MODEL_PIN_NAMESPACE, &task)
+            .await
+    }
+
+    /// The workspace's routing rule wins, then user pins, then global ones; pins
+    /// to models that are no longer installed are ignored so routing never
+    /// targets a missing file.
+    async fn pinned_model(
+        &self,
+        user_id: &str,
+        session_id: Option<&str>,
+        task_type: &str,
+        installed: &[crate::db::models::Model],
+    ) -> Result<Option<crate::db::models::Model>, AppError> {
+        let workspace_rule = self
+            .workspace_repo
+            .workspace_for_session(user_id, session_id)
+            .await?
+            .and_then(|workspace| decode_routing_rules(&workspace.routing_rules).remove(task_type));
+        let pin = match workspace_rule {
+            Some(model_id) => Some(model_id),
+            None => match self
+                .settings_repo
+                .get_setting(Some(user_id), MODEL_PIN_NAMESPACE, task_type)
+                .await?
+            {
+                Some(setting) => Some(setting.value),
+                None => self
+                    .settings_repo
+                    .get_setting(None, MODEL_PIN_NAMESPACE, task_type)
+                    .await?
+                    .map(|setting| setting.value),
+            },
+        };
+
+        Ok(pin.and_then(|model_id| installed.iter().find(|row| row.id == model_id).cloned()))
+    }
+
     pub async fn route(
         &self,
         user_id: &str,
@@ -34,6 +99,7 @@ impl TaskRouterService {
         task_type: Option<&str>,
         qos: Option<&str>,
         is_background: bool,
+        context_tokens: Option<usize>,
     ) -> Result<RoutingDecision, AppError> {
         let task = task_type
             .map(normalize_task_type)
@@ -45,7 +111,14 @@ impl TaskRouterService {
         let pressure = self.runtime_governor.classify_pressure(&stats, &policy);
 
         let installed = self.model_repo.list_installed().await?;
-        let selected = select_model(&installed, &task, &requested_qos);
+        let required_context = context_tokens.map(|tokens| tokens + base_max_tokens(&task));
+        let fit = fit_candidates(&installed, required_context);
+        let pinned = self
+            .pinned_model(user_id, session_id, &task, &installed)
+            .await?
+            .filter(|model| fit.action == ContextAction::Compress || fit.contains(model));
+        let is_pinned = pinned.is_some();
+        let selected = pinned.or_else(|| select_model(&fit.candidates, &task, &requested_qos));
 
         let mut base_opts = GenerationOptions::default();
         base_opts.max_tokens = base_max_tokens(&task);
@@ -57,14 +130,24 @@ impl TaskRouterService {
             is_background,
         );
 
-        let fallback_chain = fallback_chain(&installed, selected.as_ref().map(|m| m.id.as_str()));
-        let reason = format!(
-            "task={}, qos={}, pressure={}, fallback={}",
+        let fallback_chain =
+            fallback_chain(&fit.candidates, selected.as_ref().map(|m| m.id.as_str()));
+        let mut reason = format!(
+            "task={}, qos={}, pressure={}, fallback={}, pinned={}",
             task,
             requested_qos,
             pressure,
-            fallback_chain.len()
+            fallback_chain.len(),
+            is_pinned
         );
+        if let Some(required) = required_context {
+            reason.push_str(&format!(
+                ", context={}/{}, context_action={}",
+                required,
+                selected.as_ref().map(|m| m.context_length).unwrap_or(0),
+                fit.action.as_str()
+            ));
+        }
 
         let decision = RoutingDecision {
             task_type: task,
@@ -99,7 +182,14 @@ impl TaskRouterService {
         let stats = self.runtime_governor.current_stats();
         let pressure = self.runtime_governor.classify_pressure(&stats, &policy);
         let installed = self.model_repo.list_installed().await?;
-        let selected = select_model(&installed, &task, &requested_qos);
+        let required_context = content.len() / 4 + 1 + base_max_tokens(&task);
+        let fit = fit_candidates(&installed, Some(required_context));
+        let pinned = self
+            .pinned_model(user_id, None, &task, &installed)
+            .await?
+            .filter(|model| fit.action == ContextAction::Compress || fit.contains(model));
+        let is_pinned = pinned.is_some();
+        let selected = pinned.or_else(|| select_model(&fit.candidates, &task, &requested_qos));
 
         let mut base_opts = GenerationOptions::default();
         base_opts.max_tokens = base_max_tokens(&task);
@@ -118,8 +208,15 @@ impl TaskRouterService {
             selected_model_name: selected.as_ref().map(|m| m.display_name.clone()),
             max_tokens: tuned.max_tokens,
             pressure_level: pressure,
-            reason: "preview".to_string(),
-            fallback_chain: fallback_chain(&installed, selected.as_ref().map(|m| m.id.as_str())),
+            reason: format!(
+                "preview{}; context_action={}",
+                if is_pinned { " (pinned)" } else { "" },
+                fit.action.as_str()
+            ),
+            fallback_chain: fallback_chain(
+                &fit.candidates,
+                selected.as_ref().map(|m| m.id.as_str()),
+            ),
         })
     }
 
@@ -162,6 +259,18 @@ fn normalize_task_type(value: &str) -> String {
     }
 }
 
+pub(crate) fn validate_task_type(value: &str) -> Result<String, AppError> {
+    let task = value.trim().to_lowercase();
+    if TASK_TYPES.contains(&task.as_str()) {
+        Ok(task)
+    } else {
+        Err(AppError::Validation {
+            field: "task_type".to_string(),
+            message: format!("Unknown task type '{value}'. Expected one of {}", TASK_TYPES.join(", ")),
+        })
+    }
+}
+
 fn normalize_qos(value: &str) -> String {
     match value.trim().to_lowercase().as_str() {
         "fast" | "balanced" | "max_quality" => value.trim().to_lowercase(),
@@ -250,6 +359,76 @@ fn select_model(
         .or_else(|| installed.first().cloned())
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ContextAction {
+    Fits,
+    BiggerModel,
+    Compress,
+}
+
+impl ContextAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            ContextAction::Fits => "fits",
+            ContextAction::BiggerModel => "bigger_model",
+            ContextAction::Compress => "compress",
+        }
+    }
+}
+
+struct ContextFit {
+    candidates: Vec<crate::db::models::Model>,
+    action: ContextAction,
+}
+
+impl ContextFit {
+    fn contains(&self, model: &crate::db::models::Model) -> bool {
+        self.candidates.iter().any(|row| row.id == model.id)
+    }
+}
+
+/// Drops models whose window can't hold the assembled context plus the reply budget.
+/// When nothing fits, only the largest window is kept and the caller must compress.
+fn fit_candidates(
+    installed: &[crate::db::models::Model],
+    required_tokens: Option<usize>,
+) -> ContextFit {
+    let Some(required) = required_tokens else {
+        return ContextFit {
+            candidates: installed.to_vec(),
+            action: ContextAction::Fits,
+        };
+    };
+
+    let fitting: Vec<_> = installed
+        .iter()
+        .filter(|row| row.context_length.max(0) as usize >= required)
+        .cloned()
+        .collect();
+
+    if fitting.len() == installed.len() {
+        ContextFit {
+            candidates: fitting,
+            action: ContextAction::Fits,
+        }
+    } else if !fitting.is_empty() {
+        ContextFit {
+            candidates: fitting,
+            action: ContextAction::BiggerModel,
+        }
+    } else {
+        ContextFit {
+            candidates: installed
+                .iter()
+                .max_by_key(|row| row.context_length)
+                .cloned()
+                .into_iter()
+                .collect(),
+            action: ContextAction::Compress,
+        }
+    }
+}
+
 fn fallback_chain(installed: &[crate::db::models::Model], selected: Option<&str>) -> Vec<String> {
     installed
         .iter()
@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::db::models::ToolApprovalRequest;
+use crate::error::AppError;
+use crate::repositories::mcp_repo::McpRepo;
+use crate::repositories::settings_repo::SettingsRepo;
+use crate::services::filesystem_tools::BUILTIN_FILESYSTEM_MCP_ID;
+use crate::services::shell_tool::BUILTIN_SHELL_MCP_ID;
+
+/// Keyed by MCP id; user rows win over global ones.
+pub const TOOL_PERMISSIONS_NAMESPACE: &str = "mcp_tool_permissions";
+
+/// An unanswered prompt counts as a denial so a turn never hangs forever.
+const APPROVAL_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// (MCP id, tool) pairs confirmed on every call unless their MCP is denied,
+/// whatever its stored policy: they change files or run programs on the
+/// user's machine.
+const ALWAYS_CONFIRMED_TOOLS: &[(&str, &str)] = &[
+    (BUILTIN_FILESYSTEM_MCP_ID, "write_file"),
+    (BUILTIN_SHELL_MCP_ID, "run_command"),
+];
+
+/// Words in a tool name that mark it as changing or running something. Such
+/// tools ask first by default, whichever MCP provides them.
+const DESTRUCTIVE_TOOL_WORDS: &[&str] = &[
+    "write",
+    "append",
+    "overwrite",
+    "create",
+    "update",
+    "edit",
+    "modify",
+    "set",
+    "insert",
+    "delete",
+    "remove",
+    "rm",
+    "drop",
+    "truncate",
+    "move",
+    "rename",
+    "exec",
+    "execute",
+    "run",
+    "shell",
+    "command",
+    "kill",
+    "install",
+    "uninstall",
+    "send",
+    "post",
+    "put",
+    "upload",
+    "publish",
+    "push",
+    "commit",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolPermission {
+    AlwaysAllow,
+    Ask,
+    Deny,
+}
+
+impl ToolPermission {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::AlwaysAllow => "always_allow",
+            Self::Ask => "ask",
+            Self::Deny => "deny",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "always_allow" => Some(Self::AlwaysAllow),
+            "ask" => Some(Self::Ask),
+            "deny" => Some(Self::Deny),
+            _ => None,
+        }
+    }
+}
+
+/// Per-MCP permission policy plus the tool calls currently waiting on the user.
+#[derive(Clone)]
+pub struct ToolApprovalService {
+    settings_repo: SettingsRepo,
+    mcp_repo: McpRepo,
+    pending: Arc<Mutex<HashMap<String, oneshot::Sender<bool>>>>,
+}
+
+impl ToolApprovalService {
+    pub fn new(settings_repo: SettingsRepo, mcp_repo: McpRepo) -> Self {
+        Self {
+            settings_repo,
+            mcp_repo,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// The MCP's policy: the stored one, or else the default. Builtin MCPs are
+    /// trusted by default; anything else asks first. The shell tool always asks
+    /// unless denied: each command is confirmed on its own.
+    pub async fn policy(&self, user_id: &str, mcp_id: &str) -> Result<ToolPermission, AppError> {
+        let stored = self.stored_policy(user_id, mcp_id).await?;
+        if mcp_id == BUILTIN_SHELL_MCP_ID {
+            return Ok(match stored {
+                Some(ToolPermission::Deny) => ToolPermission::Deny,
+                _ => ToolPermission::Ask,
+            });
+        }
+        if let Some(permission) = stored {
+            return Ok(permission);
+        }
+
+        let builtin = self
+            .mcp_repo
+            .get_mcp(mcp_id)
+            .await?
+            .is_some_and(|mcp| mcp.is_builtin == 1);
+        Ok(if builtin {
+            ToolPermission::AlwaysAllow
+        } else {
+            ToolPermission::Ask
+        })
+    }
+
+    /// The policy for one call. A tool that writes, deletes or executes asks
+    /// first unless the user set the MCP's policy themselves, even when the MCP
+    /// is a trusted builtin. The built-in `write_file` and `run_command` ask
+    /// every time, whatever the user set.
+    pub async fn tool_policy(
+        &self,
+        user_id: &str,
+        mcp_id: &str,
+        tool_name: &str,
+    ) -> Result<ToolPermission, AppError> {
+        let permission = self.policy(user_id, mcp_id).await?;
+        if permission != ToolPermission::Deny && is_always_confirmed(mcp_id, tool_name) {
+            return Ok(ToolPermission::Ask);
+        }
+        if permission == ToolPermission::AlwaysAllow
+            && is_destructive_tool(tool_name)
+            && self.stored_policy(user_id, mcp_id).await?.is_none()
+        {
+            return Ok(ToolPermission::Ask);
+        }
+        Ok(permission)
+    }
+
+    /// The user's stored policy for the MCP, else the global one.
+    async fn stored_policy(
+        &self,
+        user_id: &str,
+        mcp_id: &str,
+    ) -> Result<Option<ToolPermission>, AppError> {
+        let stored = match self
+            .settings_repo
+            .get_setting(Some(user_id), TOOL_PERMISSIONS_NAMESPACE, mcp_id)
+            .await?
+        {
+            Some(setting) => Some(setting),
+            None => {
+                self.settings_repo
+                    .get_setting(None, TOOL_PERMISSIONS_NAMESPACE, mcp_id)
+                    .await?
+            }
+        };
+        Ok(stored.and_then(|setting| ToolPermission::parse(&setting.value)))
+    }
+
+    /// Stores the MCP's policy. `AlwaysAllow` is refused for the shell, whose
+    /// only tool always asks; on the file tools it covers reading only, as
+    /// `write_file` always asks.
+    pub async fn set_policy(
+        &self,
+        user_id: Option<&str>,
+        mcp_id: &str,
+        permission: ToolPermission,
+    ) -> Result<(), AppError> {
+        if mcp_id == BUILTIN_SHELL_MCP_ID && permission == ToolPermission::AlwaysAllow {
+            return Err(AppError::Validation {
+                field: "permission".to_string(),
+                message: "Commands always need confirming; choose ask or deny".to_string(),
+            });
+        }
+        self.settings_repo
+            .upsert_setting(
+                user_id,
+                TOOL_PERMISSIONS_NAMESPACE,
+                mcp_id,
+                permission.as_str(),
+                "string",
+                false,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Registers `request` and waits for `respond`, announcing it through
+    /// `on_request` once the answer can be received.
+    pub async fn await_approval(
+        &self,
+        request: &ToolApprovalRequest,
+        on_request: impl Fn(&ToolApprovalRequest),
+    ) -> bool {
+        let (tx, rx) = oneshot::channel();
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.insert(request.id.clone(), tx);
+        }
+        on_request(request);
+
+        let approved = matches!(
+            tokio::time::timeout(APPROVAL_TIMEOUT, rx).await,
+            Ok(Ok(true))
+        );
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.remove(&request.id);
+        }
+        approved
+    }
+
+    /// Returns `false` when the request already timed out or was answered.
+    pub fn respond(&self, approval_id: &str, approved: bool) -> bool {
+        let sender = self
+            .pending
+            .lock()
+            .ok()
+            .and_then(|mut pending| pending.remove(approval_id));
+        match sender {
+            Some(sender) => sender.send(approved).is_ok(),
+            None => false,
+        }
+    }
+}
+
+fn is_always_confirmed(mcp_id: &str, tool_name: &str) -> bool {
+    ALWAYS_CONFIRMED_TOOLS
+        .iter()
+        .any(|&(mcp, tool)| mcp == mcp_id && tool == tool_name)
+}
+
+/// Splits `writeFile`, `write_file` or `write-file` into words and looks for
+/// one that changes or runs something.
+fn is_destructive_tool(tool_name: &str) -> bool {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut previous_lower = false;
+    for ch in tool_name.chars() {
+        if !ch.is_alphanumeric() || (ch.is_uppercase() && previous_lower) {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+        if ch.is_alphanumeric() {
+            word.extend(ch.to_lowercase());
+        }
+        previous_lower = ch.is_lowercase() || ch.is_ascii_digit();
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+        .iter()
+        .any(|word| DESTRUCTIVE_TOOL_WORDS.contains(&word.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_always_confirmed, is_destructive_tool};
+    use crate::services::filesystem_tools::BUILTIN_FILESYSTEM_MCP_ID;
+    use crate::services::shell_tool::BUILTIN_SHELL_MCP_ID;
+
+    #[test]
+    fn write_delete_and_exec_tools_are_destructive() {
+        for name in [
+            "write_file",
+            "writeFile",
+            "delete-event",
+            "run_command",
+            "execSql",
+            "git_commit",
+        ] {
+            assert!(is_destructive_tool(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn read_tools_are_not_destructive() {
+        for name in [
+            "list_dir",
+            "read_file",
+            "search_files",
+            "getWeather",
+            "runtime_info",
+            "settings",
+        ] {
+            assert!(!is_destructive_tool(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn builtin_write_and_shell_tools_are_always_confirmed() {
+        assert!(is_always_confirmed(BUILTIN_FILESYSTEM_MCP_ID, "write_file"));
+        assert!(is_always_confirmed(BUILTIN_SHELL_MCP_ID, "run_command"));
+        assert!(!is_always_confirmed(BUILTIN_FILESYSTEM_MCP_ID, "read_file"));
+        assert!(!is_always_confirmed("some-server", "write_file"));
+    }
+}
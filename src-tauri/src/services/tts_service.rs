@@ -0,0 +1,455 @@
+use std::sync::mpsc;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+use tts::Tts;
+
+use crate::error::AppError;
+use crate::repositories::conversation_repo::ConversationRepo;
+use crate::repositories::settings_repo::SettingsRepo;
+
+pub const VOICE_SETTINGS_NAMESPACE: &str = "voice";
+pub const TTS_SETTINGS_KEY: &str = "tts";
+const MIN_RATE: f32 = 0.5;
+const MAX_RATE: f32 = 3.0;
+/// Longer text is cut so one request can't keep the voice busy for minutes.
+const MAX_SPOKEN_CHARS: usize = 20_000;
+
+/// A user's text-to-speech preferences, stored per user.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TtsSettings {
+    /// Voice id from `list_tts_voices`; the system default when unset.
+    pub voice: Option<String>,
+    /// Speed relative to the voice's normal rate.
+    pub rate: f32,
+    /// Read streamed replies aloud sentence by sentence as they arrive.
+    pub auto_read_aloud: bool,
+}
+
+impl Default for TtsSettings {
+    fn default() -> Self {
+        Self {
+            voice: None,
+            rate: 1.0,
+            auto_read_aloud: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TtsVoice {
+    pub id: String,
+    pub name: String,
+    pub language: String,
+}
+
+enum TtsCommand {
+    Speak {
+        text: String,
+        voice: Option<String>,
+        rate: f32,
+        /// Cut off whatever is playing instead of queueing behind it.
+        interrupt: bool,
+        done: Option<oneshot::Sender<Result<(), String>>>,
+    },
+    Stop,
+    ListVoices(oneshot::Sender<Result<Vec<TtsVoice>, String>>),
+}
+
+/// Speaks text with the operating system's voices. The engine lives on its
+/// own thread since the platform backends can't be shared across threads.
+#[derive(Clone)]
+pub struct TtsService {
+    commands: mpsc::Sender<TtsCommand>,
+    settings_repo: SettingsRepo,
+    conversation_repo: ConversationRepo,
+}
+
+impl TtsService {
+    pub fn new(settings_repo: SettingsRepo, conversation_repo: ConversationRepo) -> Self {
+        let (commands, receiver) = mpsc::channel();
+        let spawned = std::thread::Builder::new()
+            .name("sarah-tts".to_string())
+            .spawn(move || run_engine(receiver));
+        if let Err(error) = spawned {
+            crate::log_warn!("sarah.tts", "Failed to start speech thread: {}", error);
+        }
+        Self {
+            commands,
+            settings_repo,
+            conversation_repo,
+        }
+    }
+
+    pub async fn settings(&self, user_id: &str) -> Result<TtsSettings, AppError> {
+        let stored = self
+            .settings_repo
+            .get_setting(Some(user_id), VOICE_SETTINGS_NAMESPACE, TTS_SETTINGS_KEY)
+            .await?;
+        Ok(stored
+            .and_then(|setting| serde_json::from_str(&setting.value).ok())
+            .unwrap_or_default())
+    }
+
+    pub async fn set_settings(
+        &self,
+        user_id: &str,
+        settings: TtsSettings,
+    ) -> Result<TtsSettings, AppError> {
+        let rate = validate_rate(settings.rate)?;
+        let settings = TtsSettings {
+            voice: settings
+                .voice
+                .map(|voice| voice.trim().to_string())
+                .filter(|voice| !voice.is_empty()),
+            rate,
+            ..settings
+        };
+        let value = serde_json::to_string(&settings)
+            .map_err(|e| AppError::Internal(format!("Failed to encode voice settings: {e}")))?;
+        self.settings_repo
+            .upsert_setting(
+                Some(user_id),
+                VOICE_SETTINGS_NAMESPACE,
+                TTS_SETTINGS_KEY,
+                &value,
+                "json",
+                false,
+            )
+            .await?;
+        Ok(settings)
+    }
+
+    pub async fn voices(&self) -> Result<Vec<TtsVoice>, AppError> {
+        let (reply, response) = oneshot::channel();
+        self.send(TtsCommand::ListVoices(reply))?;
+        response
+            .await
+            .map_err(|_| engine_unavailable())?
+            .map_err(AppError::Hardware)
+    }
+
+    /// Speaks `text`, or the stored message `message_id`, interrupting anything
+    /// already playing. `voice` and `rate` override the user's settings.
+    pub async fn speak(
+        &self,
+        user_id: Option<&str>,
+        message_id: Option<&str>,
+        text: Option<&str>,
+        voice: Option<String>,
+        rate: Option<f32>,
+    ) -> Result<(), AppError> {
+        let (text, owner) = match (message_id, text) {
+            (Some(message_id), _) => {
+                let message = self
+                    .conversation_repo
+                    .get_message_by_id(message_id)
+                    .await?
+                    .ok_or_else(|| AppError::NotFound {
+                        entity: "message".to_string(),
+                        id: message_id.to_string(),
+                    })?;
+                let owner = self
+                    .conversation_repo
+                    .get_session(&message.session_id)
+                    .await?
+                    .map(|session| session.user_id);
+                (message.content, owner)
+            }
+            (None, Some(text)) => (text.to_string(), None),
+            (None, None) => {
+                return Err(AppError::Validation {
+                    field: "text".to_string(),
+                    message: "Provide a message id or text to speak".to_string(),
+                })
+            }
+        };
+
+        let text = spoken_text(&text);
+        if text.is_empty() {
+            return Err(AppError::Validation {
+                field: "text".to_string(),
+                message: "Nothing to speak".to_string(),
+            });
+        }
+
+        let settings = match user_id.map(str::to_string).or(owner) {
+            Some(user_id) => self.settings(&user_id).await?,
+            None => TtsSettings::default(),
+        };
+        let rate = match rate {
+            Some(rate) => validate_rate(rate)?,
+            None => settings.rate,
+        };
+
+        let (done, response) = oneshot::channel();
+        self.send(TtsCommand::Speak {
+            text,
+            voice: voice.or(settings.voice),
+            rate,
+            interrupt: true,
+            done: Some(done),
+        })?;
+        response
+            .await
+            .map_err(|_| engine_unavailable())?
+            .map_err(AppError::Hardware)
+    }
+
+    pub fn stop(&self) -> Result<(), AppError> {
+        self.send(TtsCommand::Stop)
+    }
+
+    /// A reader for the reply streaming into `session_id`, if its owner has
+    /// read-aloud turned on.
+    pub async fn read_aloud_for_session(&self, session_id: &str) -> Option<ReadAloud> {
+        let session = self
+            .conversation_repo
+            .get_session(session_id)
+            .await
+            .ok()
+            .flatten()?;
+        let settings = self.settings(&session.user_id).await.ok()?;
+        if !settings.auto_read_aloud {
+            return None;
+        }
+        // A new reply replaces whatever was still being read.
+        let _ = self.stop();
+        Some(ReadAloud {
+            service: self.clone(),
+            settings,
+            splitter: SentenceSplitter::default(),
+        })
+    }
+
+    fn send(&self, command: TtsCommand) -> Result<(), AppError> {
+        self.commands
+            .send(command)
+            .map_err(|_| engine_unavailable())
+    }
+}
+
+/// Speaks a streamed reply one sentence at a time, as each one completes.
+pub struct ReadAloud {
+    service: TtsService,
+    settings: TtsSettings,
+    splitter: SentenceSplitter,
+}
+
+impl ReadAloud {
+    pub fn push(&mut self, token: &str) {
+        for sentence in self.splitter.push(token) {
+            self.speak(sentence);
+        }
+    }
+
+    /// Speaks whatever is left once the stream ends.
+    pub fn finish(mut self) {
+        if let Some(rest) = self.splitter.finish() {
+            self.speak(rest);
+        }
+    }
+
+    fn speak(&self, text: String) {
+        let _ = self.service.send(TtsCommand::Speak {
+            text,
+            voice: self.settings.voice.clone(),
+            rate: self.settings.rate,
+            interrupt: false,
+            done: None,
+        });
+    }
+}
+
+/// Cuts streamed text into sentences, dropping fenced code blocks and
+/// markdown markup that would otherwise be read out.
+#[derive(Default)]
+pub struct SentenceSplitter {
+    buffer: String,
+    in_code_block: bool,
+}
+
+impl SentenceSplitter {
+    pub fn push(&mut self, text: &str) -> Vec<String> {
+        self.buffer.push_str(text);
+        let mut sentences = Vec::new();
+        while let Some(end) = sentence_end(&self.buffer) {
+            let sentence = self.buffer.drain(..end).collect::<String>();
+            if let Some(sentence) = self.speakable(&sentence) {
+                sentences.push(sentence);
+            }
+        }
+        sentences
+    }
+
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = std::mem::take(&mut self.buffer);
+        self.speakable(&rest)
+    }
+
+    fn speakable(&mut self, sentence: &str) -> Option<String> {
+        if sentence.trim_start().starts_with("```") {
+            self.in_code_block = !self.in_code_block;
+            return None;
+        }
+        if self.in_code_block {
+            return None;
+        }
+        let text = spoken_text(sentence);
+        (!text.is_empty()).then_some(text)
+    }
+}
+
+/// End of the first complete sentence: after terminal punctuation followed
+/// by whitespace, or after a line break.
+fn sentence_end(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, ch)) = chars.next() {
+        if ch == '\n' {
+            return Some(index + 1);
+        }
+        if matches!(ch, '.' | '!' | '?') {
+            if let Some(&(next_index, next)) = chars.peek() {
+                if next.is_whitespace() {
+                    return Some(next_index);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Strips markdown emphasis, headings, quotes and list markers.
+fn spoken_text(text: &str) -> String {
+    let mut spoken = String::new();
+    for line in text.lines() {
+        let line = line
+            .trim()
+            .trim_start_matches(['#', '>'])
+            .trim_start()
+            .trim_start_matches("- ")
+            .trim_start_matches("* ");
+        let line = line.replace(['*', '`', '_'], "");
+        let line = line.trim();
+        if line.is_empty() || !line.chars().any(char::is_alphanumeric) {
+            continue;
+        }
+        if !spoken.is_empty() {
+            spoken.push(' ');
+        }
+        spoken.push_str(line);
+    }
+    spoken.chars().take(MAX_SPOKEN_CHARS).collect()
+}
+
+fn validate_rate(rate: f32) -> Result<f32, AppError> {
+    if !(MIN_RATE..=MAX_RATE).contains(&rate) {
+        return Err(AppError::Validation {
+            field: "rate".to_string(),
+            message: format!("Rate must be between {MIN_RATE} and {MAX_RATE}"),
+        });
+    }
+    Ok(rate)
+}
+
+fn engine_unavailable() -> AppError {
+    AppError::Hardware("Text-to-speech is not available".to_string())
+}
+
+fn run_engine(commands: mpsc::Receiver<TtsCommand>) {
+    let mut engine = match Tts::default() {
+        Ok(engine) => Some(engine),
+        Err(error) => {
+            crate::log_warn!("sarah.tts", "Text-to-speech is unavailable: {}", error);
+            None
+        }
+    };
+
+    while let Ok(command) = commands.recv() {
+        let Some(engine) = engine.as_mut() else {
+            match command {
+                TtsCommand::Speak {
+                    done: Some(done), ..
+                } => {
+                    let _ = done.send(Err("No speech engine is available".to_string()));
+                }
+                TtsCommand::ListVoices(reply) => {
+                    let _ = reply.send(Err("No speech engine is available".to_string()));
+                }
+                _ => {}
+            }
+            continue;
+        };
+
+        match command {
+            TtsCommand::Speak {
+                text,
+                voice,
+                rate,
+                interrupt,
+                done,
+            } => {
+                let result = speak(engine, &text, voice.as_deref(), rate, interrupt);
+                if let Err(error) = &result {
+                    crate::log_warn!("sarah.tts", "Speech failed: {}", error);
+                }
+                if let Some(done) = done {
+                    let _ = done.send(result);
+                }
+            }
+            TtsCommand::Stop => {
+                if let Err(error) = engine.stop() {
+                    crate::log_warn!("sarah.tts", "Failed to stop speech: {}", error);
+                }
+            }
+            TtsCommand::ListVoices(reply) => {
+                let voices = engine
+                    .voices()
+                    .map(|voices| {
+                        voices
+                            .into_iter()
+                            .map(|voice| TtsVoice {
+                                id: voice.id(),
+                                name: voice.name(),
+                                language: voice.language().to_string(),
+                            })
+                            .collect()
+                    })
+                    .map_err(|error| format!("Failed to list voices: {error}"));
+                let _ = reply.send(voices);
+            }
+        }
+    }
+}
+
+fn speak(
+    engine: &mut Tts,
+    text: &str,
+    voice: Option<&str>,
+    rate: f32,
+    interrupt: bool,
+) -> Result<(), String> {
+    if let Some(voice_id) = voice {
+        // An unknown voice (e.g. uninstalled since it was picked) falls back
+        // to the current one rather than failing the whole request.
+        if let Some(found) = engine
+            .voices()
+            .ok()
+            .and_then(|voices| voices.into_iter().find(|voice| voice.id() == voice_id))
+        {
+            engine
+                .set_voice(&found)
+                .map_err(|error| format!("Failed to select voice: {error}"))?;
+        }
+    }
+    let engine_rate = (engine.normal_rate() * rate).clamp(engine.min_rate(), engine.max_rate());
+    engine
+        .set_rate(engine_rate)
+        .map_err(|error| format!("Failed to set speech rate: {error}"))?;
+    engine
+        .speak(text, interrupt)
+        .map(|_| ())
+        .map_err(|error| format!("Failed to speak: {error}"))
+}
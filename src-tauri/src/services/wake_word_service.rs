@@ -0,0 +1,419 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager};
+use whisper_rs::WhisperContext;
+
+use crate::audio_capture::{self, StereoResampler};
+use crate::error::AppError;
+use crate::repositories::settings_repo::SettingsRepo;
+use crate::services::hardware_service::{HardwareService, PerformanceMode};
+use crate::services::speech_service::{self, SpeechService, WHISPER_SAMPLE_RATE};
+use crate::services::tts_service::VOICE_SETTINGS_NAMESPACE;
+
+pub const WAKE_WORD_SETTINGS_KEY: &str = "wake_word";
+pub const WAKE_WORD_EVENT: &str = "voice://wake-word";
+/// Heard as whisper tends to spell them; matched after lowercasing and
+/// dropping punctuation.
+const WAKE_PHRASES: &[&str] = &["hey sarah", "hey sara", "hi sarah", "hi sara", "hay sarah"];
+const FRAME: Duration = Duration::from_millis(100);
+const FRAME_SAMPLES: usize = WHISPER_SAMPLE_RATE as usize / 10;
+/// Audio kept from before the voice started, so the first syllable isn't cut.
+const PRE_ROLL_FRAMES: usize = 3;
+/// Silence that ends an utterance.
+const END_SILENCE_FRAMES: usize = 4;
+const MIN_UTTERANCE_FRAMES: usize = 4;
+/// Anything longer is ordinary speech, not a wake phrase, and isn't decoded.
+const MAX_UTTERANCE_FRAMES: usize = 30;
+const MIN_SPEECH_LEVEL: f32 = 0.01;
+/// How far above the background noise a frame has to be to count as voice.
+const SPEECH_TO_NOISE: f32 = 3.0;
+const COOLDOWN: Duration = Duration::from_secs(3);
+/// One decoding thread keeps the detector out of the way of everything else.
+const DETECTOR_THREADS: i32 = 1;
+
+/// Machine-wide, since there's one microphone however many users there are.
+/// Off unless turned on.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WakeWordSettings {
+    pub enabled: bool,
+    /// Speech model used to recognize the phrase and then for the dictation
+    /// it starts; the first installed one when unset.
+    pub model_id: Option<String>,
+    pub device: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WakeWordStatus {
+    pub enabled: bool,
+    pub listening: bool,
+    /// Why an enabled detector isn't listening.
+    pub reason: Option<String>,
+}
+
+struct Listener {
+    settings: WakeWordSettings,
+    stop: Arc<AtomicBool>,
+    finished: Arc<AtomicBool>,
+}
+
+/// Listens for "Hey Sarah" and answers it like the overlay shortcut, then
+/// starts dictation. Runs only while enabled and outside Multitasking mode.
+#[derive(Clone)]
+pub struct WakeWordService {
+    app_handle: tauri::AppHandle,
+    settings_repo: SettingsRepo,
+    hardware_service: Arc<HardwareService>,
+    speech: Arc<SpeechService>,
+    listener: Arc<tokio::sync::Mutex<Option<Listener>>>,
+    reason: Arc<Mutex<Option<String>>>,
+}
+
+impl WakeWordService {
+    pub fn new(
+        app_handle: tauri::AppHandle,
+        settings_repo: SettingsRepo,
+        hardware_service: Arc<HardwareService>,
+        speech: Arc<SpeechService>,
+    ) -> Self {
+        Self {
+            app_handle,
+            settings_repo,
+            hardware_service,
+            speech,
+            listener: Arc::new(tokio::sync::Mutex::new(None)),
+            reason: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub async fn settings(&self) -> Result<WakeWordSettings, AppError> {
+        let stored = self
+            .settings_repo
+            .get_setting(None, VOICE_SETTINGS_NAMESPACE, WAKE_WORD_SETTINGS_KEY)
+            .await?;
+        Ok(stored
+            .and_then(|setting| serde_json::from_str(&setting.value).ok())
+            .unwrap_or_default())
+    }
+
+    /// Saves the settings and starts or stops the detector to match.
+    pub async fn set_settings(
+        &self,
+        settings: WakeWordSettings,
+    ) -> Result<WakeWordStatus, AppError> {
+        let settings = WakeWordSettings {
+            model_id: trimmed(settings.model_id),
+            device: trimmed(settings.device),
+            ..settings
+        };
+        let value = serde_json::to_string(&settings)
+            .map_err(|e| AppError::Internal(format!("Failed to encode wake word settings: {e}")))?;
+        self.settings_repo
+            .upsert_setting(
+                None,
+                VOICE_SETTINGS_NAMESPACE,
+                WAKE_WORD_SETTINGS_KEY,
+                &value,
+                "json",
+                false,
+            )
+            .await?;
+        self.refresh().await;
+        Ok(self.status().await)
+    }
+
+    pub async fn status(&self) -> WakeWordStatus {
+        let enabled = self
+            .settings()
+            .await
+            .map(|settings| settings.enabled)
+            .unwrap_or(false);
+        let listening = self
+            .listener
+            .lock()
+            .await
+            .as_ref()
+            .is_some_and(|listener| !listener.finished.load(Ordering::SeqCst));
+        let reason = if enabled && !listening {
+            self.reason.lock().ok().and_then(|reason| reason.clone())
+        } else {
+            None
+        };
+        WakeWordStatus {
+            enabled,
+            listening,
+            reason,
+        }
+    }
+
+    /// Starts, restarts or stops the detector to match the saved settings and
+    /// the current performance mode. Safe to call any number of times.
+    pub async fn refresh(&self) {
+        let mut listener = self.listener.lock().await;
+        let settings = self.settings().await.unwrap_or_default();
+        let blocked = if !settings.enabled {
+            Some("Wake word is turned off".to_string())
+        } else if self.hardware_service.get_performance_mode(None).await
+            == PerformanceMode::Multitasking
+        {
+            Some("Paused in Multitasking mode".to_string())
+        } else {
+            None
+        };
+
+        if let Some(running) = listener.as_ref() {
+            let alive = !running.finished.load(Ordering::SeqCst);
+            if alive && blocked.is_none() && running.settings == settings {
+                return;
+            }
+            running.stop.store(true, Ordering::SeqCst);
+            *listener = None;
+            crate::log_info!("sarah.wake_word", "wake word detector stopped");
+        }
+
+        if let Some(reason) = blocked {
+            self.set_reason(Some(reason));
+            return;
+        }
+
+        let context = match self.speech.load_model(settings.model_id.as_deref()).await {
+            Ok(context) => context,
+            Err(error) => {
+                crate::log_warn!("sarah.wake_word", "Wake word unavailable: {}", error);
+                self.set_reason(Some(error.to_string()));
+                return;
+            }
+        };
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let finished = Arc::new(AtomicBool::new(false));
+        let detector = Detector {
+            service: self.clone(),
+            context,
+            device: settings.device.clone(),
+            stop: Arc::clone(&stop),
+            finished: Arc::clone(&finished),
+        };
+        let spawned = std::thread::Builder::new()
+            .name("sarah-wake-word".to_string())
+            .spawn(move || detector.run());
+        if let Err(error) = spawned {
+            self.set_reason(Some(format!("Failed to start wake word detector: {error}")));
+            return;
+        }
+
+        self.set_reason(None);
+        *listener = Some(Listener {
+            settings,
+            stop,
+            finished,
+        });
+        crate::log_info!("sarah.wake_word", "wake word detector started");
+    }
+
+    /// Brings up the overlay and starts dictation, as if the shortcut had
+    /// been pressed and the microphone clicked.
+    async fn on_wake(&self) {
+        crate::log_info!("sarah.wake_word", "wake word heard");
+        crate::overlay_position::place_main_window(&self.app_handle).await;
+        if let Some(window) = self.app_handle.get_webview_window("main") {
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+        let _ = self.app_handle.emit("sarah://show-overlay", ());
+
+        let settings = self.settings().await.unwrap_or_default();
+        match self
+            .speech
+            .start_capture(settings.model_id.as_deref(), None, settings.device)
+            .await
+        {
+            Ok(capture) => {
+                let _ = self.app_handle.emit(WAKE_WORD_EVENT, capture);
+            }
+            Err(error) => {
+                crate::log_warn!(
+                    "sarah.wake_word",
+                    "Failed to start voice capture: {}",
+                    error
+                );
+            }
+        }
+    }
+
+    fn set_reason(&self, reason: Option<String>) {
+        if let Ok(mut current) = self.reason.lock() {
+            *current = reason;
+        }
+    }
+}
+
+struct Detector {
+    service: WakeWordService,
+    context: Arc<WhisperContext>,
+    device: Option<String>,
+    stop: Arc<AtomicBool>,
+    finished: Arc<AtomicBool>,
+}
+
+impl Detector {
+    /// Waits for short bursts of voice and decodes only those, so the model
+    /// stays idle while the room is quiet.
+    fn run(self) {
+        lower_thread_priority();
+
+        let samples = Arc::new(Mutex::new(Vec::<f32>::new()));
+        let sink = Arc::clone(&samples);
+        let mut resampler = None;
+        let mut converted = Vec::new();
+        let stream =
+            audio_capture::start_microphone(self.device.as_deref(), move |frames, rate| {
+                let resampler = resampler
+                    .get_or_insert_with(|| StereoResampler::new(rate, WHISPER_SAMPLE_RATE));
+                converted.clear();
+                resampler.process(frames.iter().copied(), &mut converted);
+                if let Ok(mut samples) = sink.lock() {
+                    samples.extend(
+                        converted
+                            .chunks_exact(2)
+                            .map(|pair| (pair[0] + pair[1]) * 0.5),
+                    );
+                }
+            });
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                crate::log_warn!("sarah.wake_word", "Wake word microphone failed: {}", error);
+                self.service.set_reason(Some(error));
+                self.finished.store(true, Ordering::SeqCst);
+                return;
+            }
+        };
+
+        let mut pending = Vec::new();
+        let mut pre_roll = VecDeque::new();
+        let mut utterance = Vec::new();
+        let mut voiced_frames = 0;
+        let mut silent_frames = 0;
+        let mut noise_level = MIN_SPEECH_LEVEL / SPEECH_TO_NOISE;
+        let mut cooldown_until = Instant::now();
+
+        while !self.stop.load(Ordering::SeqCst) {
+            std::thread::sleep(FRAME);
+            if let Ok(mut samples) = samples.lock() {
+                pending.append(&mut samples);
+            }
+            // Dictation has the microphone; don't trigger on what's said to it.
+            if self.service.speech.is_capturing() || Instant::now() < cooldown_until {
+                pending.clear();
+                utterance.clear();
+                pre_roll.clear();
+                voiced_frames = 0;
+                continue;
+            }
+
+            let whole_frames = pending.len() / FRAME_SAMPLES * FRAME_SAMPLES;
+            let ready = pending.drain(..whole_frames).collect::<Vec<_>>();
+            for frame in ready.chunks(FRAME_SAMPLES) {
+                let level = rms(frame);
+                let voiced = level >= (noise_level * SPEECH_TO_NOISE).max(MIN_SPEECH_LEVEL);
+
+                if voiced_frames == 0 {
+                    if !voiced {
+                        noise_level = noise_level * 0.95 + level * 0.05;
+                        pre_roll.push_back(frame.to_vec());
+                        if pre_roll.len() > PRE_ROLL_FRAMES {
+                            pre_roll.pop_front();
+                        }
+                        continue;
+                    }
+                    utterance.extend(pre_roll.drain(..).flatten());
+                }
+
+                utterance.extend_from_slice(frame);
+                voiced_frames += 1;
+                silent_frames = if voiced { 0 } else { silent_frames + 1 };
+                if voiced_frames < MAX_UTTERANCE_FRAMES && silent_frames < END_SILENCE_FRAMES {
+                    continue;
+                }
+
+                let too_long = voiced_frames >= MAX_UTTERANCE_FRAMES;
+                let heard = voiced_frames - silent_frames;
+                let candidate = std::mem::take(&mut utterance);
+                voiced_frames = 0;
+                silent_frames = 0;
+                if too_long || heard < MIN_UTTERANCE_FRAMES {
+                    continue;
+                }
+                if self.is_wake_phrase(&candidate) {
+                    cooldown_until = Instant::now() + COOLDOWN;
+                    let service = self.service.clone();
+                    tauri::async_runtime::spawn(async move { service.on_wake().await });
+                    break;
+                }
+            }
+        }
+
+        drop(stream);
+        self.finished.store(true, Ordering::SeqCst);
+    }
+
+    fn is_wake_phrase(&self, samples: &[f32]) -> bool {
+        match speech_service::transcribe(&self.context, samples, Some("en"), DETECTOR_THREADS) {
+            Ok(text) => {
+                let heard = normalize(&text);
+                WAKE_PHRASES.iter().any(|phrase| heard.contains(phrase))
+            }
+            Err(error) => {
+                crate::log_warn!("sarah.wake_word", "Wake word decoding failed: {}", error);
+                false
+            }
+        }
+    }
+}
+
+fn rms(frame: &[f32]) -> f32 {
+    if frame.is_empty() {
+        return 0.0;
+    }
+    (frame.iter().map(|sample| sample * sample).sum::<f32>() / frame.len() as f32).sqrt()
+}
+
+fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|ch| if ch.is_alphanumeric() { ch } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn trimmed(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+#[cfg(windows)]
+fn lower_thread_priority() {
+    use windows::Win32::System::Threading::{
+        GetCurrentThread, SetThreadPriority, THREAD_PRIORITY_LOWEST,
+    };
+    // SAFETY: the pseudo-handle from GetCurrentThread is always valid for the calling thread.
+    if let Err(error) = unsafe { SetThreadPriority(GetCurrentThread(), THREAD_PRIORITY_LOWEST) } {
+        crate::log_warn!(
+            "sarah.wake_word",
+            "Failed to lower thread priority: {}",
+            error
+        );
+    }
+}
+
+#[cfg(not(windows))]
+fn lower_thread_priority() {}
@@ -0,0 +1,352 @@
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::db::models::{BackgroundJob, NewWebhook, Webhook};
+use crate::error::AppError;
+use crate::repositories::job_repo::JobRepo;
+use crate::repositories::webhook_repo::WebhookRepo;
+use crate::services::crypto_service::CryptoService;
+
+pub const JOB_WEBHOOK_DELIVERY: &str = "webhook_delivery";
+/// With the job queue's doubling backoff, the last try comes about 15 minutes
+/// after the first.
+const WEBHOOK_DELIVERY_ATTEMPTS: i64 = 5;
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_WEBHOOK_NAME_CHARS: usize = 64;
+/// Sent on `test_webhook`, whatever the webhook is subscribed to.
+const TEST_EVENT: &str = "webhook.test";
+
+/// What a webhook can subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookEvent {
+    #[serde(rename = "message.completed")]
+    MessageCompleted,
+    #[serde(rename = "memory.created")]
+    MemoryCreated,
+    #[serde(rename = "model.download_finished")]
+    DownloadFinished,
+    #[serde(rename = "recording.saved")]
+    RecordingSaved,
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 4] = [
+        WebhookEvent::MessageCompleted,
+        WebhookEvent::MemoryCreated,
+        WebhookEvent::DownloadFinished,
+        WebhookEvent::RecordingSaved,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebhookEvent::MessageCompleted => "message.completed",
+            WebhookEvent::MemoryCreated => "memory.created",
+            WebhookEvent::DownloadFinished => "model.download_finished",
+            WebhookEvent::RecordingSaved => "recording.saved",
+        }
+    }
+}
+
+/// Outgoing webhooks. `publish` queues a delivery job per subscribed webhook;
+/// `BackgroundService` runs them through `deliver`, retrying failures.
+///
+/// Each POST carries `{ id, event, occurredAt, data }`. `X-Sarah-Signature-256`
+/// is `sha256=` and the hex HMAC-SHA256 of the body, keyed with the webhook's
+/// secret. Retries keep the same `id` so receivers can drop repeats.
+#[derive(Clone)]
+pub struct WebhookService {
+    webhook_repo: WebhookRepo,
+    job_repo: JobRepo,
+    crypto: CryptoService,
+    http: reqwest::Client,
+}
+
+impl WebhookService {
+    pub fn new(webhook_repo: WebhookRepo, job_repo: JobRepo, crypto: CryptoService) -> Self {
+        Self {
+            webhook_repo,
+            job_repo,
+            crypto,
+            http: reqwest::Client::builder()
+                .timeout(WEBHOOK_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    pub async fn list(&self, user_id: &str) -> Result<Vec<Webhook>, AppError> {
+        self.webhook_repo.list(user_id).await
+    }
+
+    pub async fn create(&self, webhook: NewWebhook) -> Result<Webhook, AppError> {
+        let (webhook, events) = validate_webhook(webhook)?;
+        let secret = self.encrypt_secret(&new_secret())?;
+        self.webhook_repo.create(&webhook, &events, &secret).await
+    }
+
+    pub async fn update(&self, id: &str, webhook: NewWebhook) -> Result<Webhook, AppError> {
+        let (webhook, events) = validate_webhook(webhook)?;
+        self.webhook_repo.update(id, &webhook, &events).await
+    }
+
+    pub async fn set_enabled(&self, id: &str, enabled: bool) -> Result<Webhook, AppError> {
+        self.webhook_repo.set_enabled(id, enabled).await
+    }
+
+    /// Deletes the webhook and its delivery jobs; finished deliveries of
+    /// other webhooks are pruned by the job dispatcher.
+    pub async fn delete(&self, id: &str) -> Result<(), AppError> {
+        self.webhook_repo.delete(id).await?;
+        if let Err(error) = self
+            .job_repo
+            .delete_by_payload(JOB_WEBHOOK_DELIVERY, "webhookId", id)
+            .await
+        {
+            tracing::warn!("Failed to delete deliveries of webhook {id}: {error}");
+        }
+        Ok(())
+    }
+
+    /// The key receivers check signatures with.
+    pub async fn secret(&self, id: &str) -> Result<String, AppError> {
+        let webhook = self.webhook_repo.get_required(id).await?;
+        self.decrypt_secret(&webhook.secret)
+    }
+
+    /// Replaces the secret; deliveries signed from now on use the new one.
+    pub async fn rotate_secret(&self, id: &str) -> Result<String, AppError> {
+        let secret = new_secret();
+        self.webhook_repo
+            .set_secret(id, &self.encrypt_secret(&secret)?)
+            .await?;
+        Ok(secret)
+    }
+
+    /// Queues a delivery of `event` to every enabled webhook of `user_id`
+    /// subscribed to it. Events no user owns, such as model downloads, pass
+    /// `None` and go to every user's webhooks. Never fails: an event nobody
+    /// can hear about isn't worth failing the work that raised it.
+    pub async fn publish(&self, user_id: Option<&str>, event: WebhookEvent, data: Value) {
+        let webhooks = match self.webhook_repo.list_enabled(user_id).await {
+            Ok(webhooks) => webhooks,
+            Err(error) => {
+                tracing::warn!("Failed to list webhooks for {}: {error}", event.as_str());
+                return;
+            }
+        };
+        let occurred_at = chrono::Utc::now().to_rfc3339();
+        for webhook in webhooks
+            .iter()
+            .filter(|webhook| subscribed_events(webhook).contains(&event))
+        {
+            let payload = json!({
+                "webhookId": webhook.id,
+                "deliveryId": Uuid::new_v4().to_string(),
+                "event": event.as_str(),
+                "occurredAt": occurred_at,
+                "data": data,
+            });
+            if let Err(error) = self
+                .job_repo
+                .enqueue(
+                    JOB_WEBHOOK_DELIVERY,
+                    &payload.to_string(),
+                    None,
+                    None,
+                    WEBHOOK_DELIVERY_ATTEMPTS,
+                )
+                .await
+            {
+                tracing::warn!("Failed to queue webhook {}: {error}", webhook.id);
+            }
+        }
+    }
+
+    /// Runs a `JOB_WEBHOOK_DELIVERY` job. Deliveries to webhooks since deleted
+    /// or turned off are dropped.
+    pub async fn deliver(&self, job: &BackgroundJob) -> Result<(), AppError> {
+        let payload: Value = serde_json::from_str(&job.payload).unwrap_or(Value::Null);
+        let webhook_id = payload
+            .get("webhookId")
+            .and_then(Value::as_str)
+            .ok_or_else(|| AppError::Validation {
+                field: "payload".to_string(),
+                message: "Webhook job has no webhookId".to_string(),
+            })?;
+        let Some(webhook) = self.webhook_repo.get(webhook_id).await? else {
+            return Ok(());
+        };
+        if webhook.is_enabled == 0 {
+            return Ok(());
+        }
+        let body = json!({
+            "id": payload.get("deliveryId"),
+            "event": payload.get("event"),
+            "occurredAt": payload.get("occurredAt"),
+            "data": payload.get("data"),
+        });
+        let event = payload
+            .get("event")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let result = self.post(&webhook, event, &body).await;
+        let _ = self
+            .webhook_repo
+            .record_delivery(
+                &webhook.id,
+                result.as_ref().err().map(ToString::to_string).as_deref(),
+            )
+            .await;
+        result
+    }
+
+    /// Sends a `webhook.test` event right away, without queueing or retrying.
+    pub async fn send_test(&self, id: &str) -> Result<Webhook, AppError> {
+        let webhook = self.webhook_repo.get_required(id).await?;
+        let body = json!({
+            "id": Uuid::new_v4().to_string(),
+            "event": TEST_EVENT,
+            "occurredAt": chrono::Utc::now().to_rfc3339(),
+            "data": { "webhookId": webhook.id, "name": webhook.name },
+        });
+        let result = self.post(&webhook, TEST_EVENT, &body).await;
+        self.webhook_repo
+            .record_delivery(
+                &webhook.id,
+                result.as_ref().err().map(ToString::to_string).as_deref(),
+            )
+            .await?;
+        result?;
+        self.webhook_repo.get_required(id).await
+    }
+
+    async fn post(&self, webhook: &Webhook, event: &str, body: &Value) -> Result<(), AppError> {
+        let body = body.to_string();
+        let secret = self.decrypt_secret(&webhook.secret)?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+            .map_err(|e| AppError::Crypto(e.to_string()))?;
+        mac.update(body.as_bytes());
+        let signature = mac
+            .finalize()
+            .into_bytes()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+
+        let response = self
+            .http
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(reqwest::header::USER_AGENT, "Sarah-Webhooks/1")
+            .header("X-Sarah-Event", event)
+            .header("X-Sarah-Signature-256", format!("sha256={signature}"))
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| {
+                if e.is_timeout() {
+                    AppError::Timeout(format!("Webhook {} did not answer", webhook.name))
+                } else {
+                    AppError::Internal(format!("Webhook {} failed: {e}", webhook.name))
+                }
+            })?;
+        if !response.status().is_success() {
+            return Err(AppError::Internal(format!(
+                "Webhook {} answered {}",
+                webhook.name,
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+
+    fn encrypt_secret(&self, secret: &str) -> Result<String, AppError> {
+        self.crypto.encrypt_to_compact(secret.as_bytes())
+    }
+
+    fn decrypt_secret(&self, encrypted: &str) -> Result<String, AppError> {
+        let mut plaintext = self.crypto.decrypt(encrypted)?;
+        let secret =
+            String::from_utf8(plaintext.clone()).map_err(|e| AppError::Crypto(e.to_string()))?;
+        CryptoService::zeroize_after_use(&mut plaintext);
+        Ok(secret)
+    }
+}
+
+fn new_secret() -> String {
+    format!(
+        "whsec_{}{}",
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    )
+}
+
+/// Unknown names, say from a newer version, are skipped.
+fn subscribed_events(webhook: &Webhook) -> Vec<WebhookEvent> {
+    serde_json::from_str::<Vec<Value>>(&webhook.events)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|event| serde_json::from_value(event).ok())
+        .collect()
+}
+
+/// Trims the webhook and returns it with its events as the JSON to store.
+fn validate_webhook(webhook: NewWebhook) -> Result<(NewWebhook, String), AppError> {
+    let name = webhook.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_WEBHOOK_NAME_CHARS {
+        return Err(AppError::Validation {
+            field: "name".to_string(),
+            message: format!("Names must be 1-{MAX_WEBHOOK_NAME_CHARS} characters"),
+        });
+    }
+    let url = webhook.url.trim().to_string();
+    let parsed = reqwest::Url::parse(&url).map_err(|e| AppError::Validation {
+        field: "url".to_string(),
+        message: format!("Not a valid URL: {e}"),
+    })?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err(AppError::Validation {
+            field: "url".to_string(),
+            message: "Webhook URLs must start with http:// or https://".to_string(),
+        });
+    }
+
+    let mut events = Vec::new();
+    for name in &webhook.events {
+        let event = WebhookEvent::ALL
+            .into_iter()
+            .find(|event| event.as_str() == name.trim())
+            .ok_or_else(|| AppError::Validation {
+                field: "events".to_string(),
+                message: format!("Unknown event: {name}"),
+            })?;
+        if !events.contains(&event) {
+            events.push(event);
+        }
+    }
+    if events.is_empty() {
+        return Err(AppError::Validation {
+            field: "events".to_string(),
+            message: "Pick at least one event".to_string(),
+        });
+    }
+    let stored = serde_json::to_string(&events)
+        .map_err(|e| AppError::Internal(format!("Failed to store webhook events: {e}")))?;
+    Ok((
+        NewWebhook {
+            name,
+            url,
+            events: events
+                .iter()
+                .map(|event| event.as_str().to_string())
+                .collect(),
+            ..webhook
+        },
+        stored,
+    ))
+}
@@ -7,43 +7,72 @@ use tokio::sync::RwLock;
 
 use tauri::Manager;
 
-use crate::db::models::{Memory, Model, Session, SystemProfile};
+use crate::db::models::{Memory, Model, Session, StartupFailure, SystemProfile};
 use crate::db::Database;
 use crate::error::AppError;
 use crate::log_info;
+use crate::repositories::adapter_repo::AdapterRepo;
 use crate::repositories::analytics_repo::AnalyticsRepo;
+use crate::repositories::capture_repo::CaptureRepo;
 use crate::repositories::conversation_repo::ConversationRepo;
 use crate::repositories::document_repo::DocumentRepo;
 use crate::repositories::embedding_repo::EmbeddingRepo;
+use crate::repositories::job_repo::JobRepo;
 use crate::repositories::mcp_repo::McpRepo;
 use crate::repositories::memory_repo::MemoryRepo;
 use crate::repositories::model_repo::ModelRepo;
+use crate::repositories::persona_repo::PersonaRepo;
+use crate::repositories::reindex_repo::ReindexRepo;
+use crate::repositories::saved_prompt_repo::SavedPromptRepo;
+use crate::repositories::scheduled_prompt_repo::ScheduledPromptRepo;
 use crate::repositories::settings_repo::{Setting, SettingsRepo};
 use crate::repositories::system_repo::SystemRepo;
 use crate::repositories::user_repo::UserRepo;
-use crate::services::adaptive_memory_manager::AdaptiveMemoryManager;
+use crate::repositories::watched_folder_repo::WatchedFolderRepo;
+use crate::repositories::webhook_repo::WebhookRepo;
+use crate::repositories::workspace_repo::WorkspaceRepo;
+use crate::services::adaptive_memory_manager::{AdaptiveMemoryManager, MemoryBudget};
 use crate::services::analytics_service::AnalyticsService;
 use crate::services::background_service::BackgroundService;
+use crate::services::capture_library::CaptureLibrary;
+use crate::services::clarification_service::ClarificationService;
 use crate::services::context_service::ContextService;
 use crate::services::conversation_service::ConversationService;
 use crate::services::crypto_service::CryptoService;
-use crate::services::embedding_service::EmbeddingService;
-use crate::services::hardware_service::{DeviceTier, HardwareService, TierConfig};
+use crate::services::embedding_service::{
+    EmbeddingService, EMBEDDING_MODEL_KEY, EMBEDDING_SETTINGS_NAMESPACE,
+};
+use crate::services::generation_presets::GenerationPresetService;
+use crate::services::hardware_service::{DeviceTier, HardwareService, PerformanceMode, TierConfig};
+use crate::services::history_search::HistorySearchService;
+use crate::services::import_service::ImportService;
 use crate::services::inference_service::InferenceService;
 use crate::services::intent_service::IntentService;
+use crate::services::launch_state_service::LaunchStateService;
+use crate::services::local_api_service::LocalApiService;
+use crate::services::retention_service::RetentionService;
+use crate::services::takeout_service::TakeoutService;
 use crate::services::mcp_service::McpService;
 use crate::services::memory_service::MemoryService;
 use crate::services::model_manager_service::ModelManagerService;
+use crate::services::notification_service::NotificationService;
 use crate::services::predictive_preloader::PredictivePreloader;
 use crate::services::rag_service::RagService;
-use crate::services::recommendation_service::RecommendationService;
+use crate::services::recommendation_service::{profile_changed, RecommendationService};
+use crate::services::reindex_service::ReindexService;
 use crate::services::reranker_service::RerankerService;
 use crate::services::runtime_governor_service::RuntimeGovernorService;
 use crate::services::runtime_orchestrator_service::{FeatureGate, RuntimeOrchestratorService};
+use crate::services::settings_watcher::SettingsWatcher;
 use crate::services::setup_orchestrator_service::SetupOrchestratorService;
 use crate::services::smart_query_classifier::SmartQueryClassifier;
+use crate::services::speech_service::SpeechService;
 use crate::services::task_router_service::TaskRouterService;
+use crate::services::tool_approval_service::ToolApprovalService;
+use crate::services::tts_service::TtsService;
 use crate::services::usage_learner::UsageLearner;
+use crate::services::wake_word_service::WakeWordService;
+use crate::services::webhook_service::WebhookService;
 
 #[derive(Clone)]
 pub struct AppCache {
@@ -89,6 +118,15 @@ impl AppCache {
                 .build(),
         }
     }
+
+    /// Capacities are fixed at startup, so switching to multitasking frees the
+    /// large caches instead; they refill lazily at whatever pace the mode allows.
+    pub fn apply_performance_mode(&self, mode: &PerformanceMode) {
+        if *mode == PerformanceMode::Multitasking {
+            self.text_embeddings.invalidate_all();
+            self.recent_memories.invalidate_all();
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -112,7 +150,12 @@ pub struct AppState {
     pub document_repo: Arc<DocumentRepo>,
     pub embedding_repo: Arc<EmbeddingRepo>,
     pub settings_repo: Arc<SettingsRepo>,
+    pub saved_prompt_repo: Arc<SavedPromptRepo>,
+    pub workspace_repo: Arc<WorkspaceRepo>,
+    pub persona_repo: Arc<PersonaRepo>,
+    pub scheduled_prompt_repo: Arc<ScheduledPromptRepo>,
     pub analytics_repo: Arc<AnalyticsRepo>,
+    pub adapter_repo: Arc<AdapterRepo>,
 
     pub hardware_service: Arc<HardwareService>,
     pub inference: Arc<InferenceService>,
@@ -122,6 +165,7 @@ pub struct AppState {
     pub intent: Arc<IntentService>,
     pub memory: Arc<MemoryService>,
     pub rag: Option<Arc<RagService>>,
+    pub history_search: Arc<HistorySearchService>,
     pub mcp: Arc<McpService>,
     pub context: Arc<ContextService>,
     pub conversation: Arc<ConversationService>,
@@ -133,9 +177,55 @@ pub struct AppState {
     pub runtime_orchestrator: Arc<RuntimeOrchestratorService>,
     pub setup_orchestrator: Arc<SetupOrchestratorService>,
     pub background: Arc<BackgroundService>,
+    pub launch_state: Arc<LaunchStateService>,
+    pub retention: Arc<RetentionService>,
+    pub notifications: Arc<NotificationService>,
+    pub local_api: Arc<LocalApiService>,
+    pub webhooks: Arc<WebhookService>,
+    pub takeout: Arc<TakeoutService>,
+    pub reindex: Arc<ReindexService>,
+    pub importer: Arc<ImportService>,
+    pub captures: Arc<CaptureLibrary>,
+    pub speech: Arc<SpeechService>,
+    pub tts: Arc<TtsService>,
+    pub wake_word: Arc<WakeWordService>,
+    pub generation_presets: Arc<GenerationPresetService>,
+    pub settings_watcher: Arc<SettingsWatcher>,
+    pub clarifications: Arc<ClarificationService>,
+    pub tool_approvals: Arc<ToolApprovalService>,
 }
 
 impl AppState {
+    /// Runs `initialize`, turning both errors and panics (a bad migration, a
+    /// corrupt profile row, a native library failing to load) into a
+    /// `StartupFailure` the caller can fall back to safe mode with.
+    pub async fn initialize_guarded(app_handle: &tauri::AppHandle) -> Result<Self, StartupFailure> {
+        use futures::FutureExt;
+
+        let outcome = std::panic::AssertUnwindSafe(Self::initialize(app_handle))
+            .catch_unwind()
+            .await;
+        let (message, panicked) = match outcome {
+            Ok(Ok(state)) => return Ok(state),
+            Ok(Err(error)) => (error.to_string(), false),
+            Err(payload) => {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|text| text.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "Startup panicked".to_string());
+                (message, true)
+            }
+        };
+
+        crate::log_error!("sarah", "Startup failed (panicked: {}): {}", panicked, message);
+        Err(StartupFailure {
+            message,
+            panicked,
+            occurred_at: chrono::Utc::now().to_rfc3339(),
+        })
+    }
+
     pub async fn initialize(app_handle: &tauri::AppHandle) -> Result<Self, AppError> {
         let startup_clock = Instant::now();
         let startup_started_at_utc = chrono::Utc::now().to_rfc3339();
@@ -171,13 +261,38 @@ impl AppState {
             read_pool.clone(),
             write_pool.clone(),
         ));
+        let saved_prompt_repo = Arc::new(SavedPromptRepo::with_pools(
+            read_pool.clone(),
+            write_pool.clone(),
+        ));
+        let workspace_repo = Arc::new(WorkspaceRepo::with_pools(
+            read_pool.clone(),
+            write_pool.clone(),
+        ));
+        let persona_repo = Arc::new(PersonaRepo::with_pools(
+            read_pool.clone(),
+            write_pool.clone(),
+        ));
+        let scheduled_prompt_repo = Arc::new(ScheduledPromptRepo::with_pools(
+            read_pool.clone(),
+            write_pool.clone(),
+        ));
         let analytics_repo = Arc::new(AnalyticsRepo::with_pools(
             read_pool.clone(),
             write_pool.clone(),
         ));
+        let adapter_repo = Arc::new(AdapterRepo::with_pools(
+            read_pool.clone(),
+            write_pool.clone(),
+        ));
 
         let hardware_service = Arc::new(HardwareService::new((*system_repo).clone(), (*settings_repo).clone()));
+        let previous_profile = system_repo.get_current_profile().await.ok().flatten();
         let detected_profile = hardware_service.detect_hardware().await?;
+        let hardware_changed = previous_profile
+            .as_ref()
+            .map(|previous| profile_changed(previous, &detected_profile))
+            .unwrap_or(true);
 
         let detected_tier = detected_profile.classify();
         let startup_tier = match detected_tier {
@@ -208,6 +323,11 @@ impl AppState {
 
         let bundle_id = app_handle.config().identifier.clone();
         let crypto = Arc::new(CryptoService::new(&bundle_id)?);
+        let webhooks = Arc::new(WebhookService::new(
+            WebhookRepo::with_pools(read_pool.clone(), write_pool.clone()),
+            JobRepo::with_pools(read_pool.clone(), write_pool.clone()),
+            (*crypto).clone(),
+        ));
 
         let cache_dir = app_handle
             .path()
@@ -215,11 +335,18 @@ impl AppState {
             .map_err(|e| AppError::Config(format!("Failed to resolve cache dir: {e}")))?;
         tokio::fs::create_dir_all(&cache_dir).await?;
 
+        // A completed re-index replaces the tier's default embedding model.
+        let saved_embedding_model = settings_repo
+            .get_setting(None, EMBEDDING_SETTINGS_NAMESPACE, EMBEDDING_MODEL_KEY)
+            .await
+            .ok()
+            .flatten()
+            .map(|setting| setting.value);
         let embedding: Option<Arc<EmbeddingService>> = if let Some(ref model_name) =
             tier_config.embedding_model
         {
             match EmbeddingService::new(
-                model_name,
+                saved_embedding_model.as_deref().unwrap_or(model_name),
                 cache_dir.join("embeddings"),
                 (*embedding_repo).clone(),
                 hardware_service.clone(),
@@ -266,7 +393,13 @@ impl AppState {
             };
 
         let intent = Arc::new(IntentService::new());
-        let inference = Arc::new(InferenceService::new());
+        let inference = Arc::new(
+            InferenceService::new()
+                .with_model_repo((*model_repo).clone())
+                .with_adapter_repo((*adapter_repo).clone())
+                .with_analytics(AnalyticsService::new((*analytics_repo).clone())),
+        );
+        inference.configure_pool(hardware_service.model_pool_limits(&detected_profile).await);
 
         let embedding_for_memory = embedding.clone();
         let memory = Arc::new(MemoryService::new(
@@ -274,8 +407,21 @@ impl AppState {
             (*embedding_repo).clone(),
             embedding_for_memory,
             (*inference).clone(),
+            (*settings_repo).clone(),
+            (*webhooks).clone(),
         ));
 
+        let runtime_governor = Arc::new(RuntimeGovernorService::new(
+            read_pool.clone(),
+            write_pool.clone(),
+            (*hardware_service).clone(),
+            (*settings_repo).clone(),
+        ));
+        runtime_governor.start_device_monitor(
+            app_handle.clone(),
+            (*inference).clone(),
+            AnalyticsService::new((*analytics_repo).clone()),
+        );
         let rag: Option<Arc<RagService>> =
             if let (Some(ref emb), Some(ref rer)) = (embedding.as_ref(), reranker.as_ref()) {
                 Some(Arc::new(RagService::new(
@@ -283,6 +429,8 @@ impl AppState {
                     (*embedding_repo).clone(),
                     Arc::clone(emb),
                     Arc::clone(rer),
+                    (*workspace_repo).clone(),
+                    (*runtime_governor).clone(),
                     write_pool.clone(),
                 )))
             } else {
@@ -294,7 +442,11 @@ impl AppState {
             (*mcp_repo).clone(),
             (*crypto).clone(),
             (*intent).clone(),
+            (*settings_repo).clone(),
         ));
+        if let Err(error) = mcp.sync_builtin_tools().await {
+            tracing::warn!("Failed to register built-in tools: {error}");
+        }
 
         let context = Arc::new(ContextService::new(
             (*memory).clone(),
@@ -302,35 +454,111 @@ impl AppState {
             (*intent).clone(),
             (*mcp).clone(),
             (*conversation_repo).clone(),
+            (*document_repo).clone(),
+            (*workspace_repo).clone(),
+            (*persona_repo).clone(),
             (*model_repo).clone(),
-        ));
+            (*settings_repo).clone(),
+        )
+        .with_inference(Arc::clone(&inference)));
 
         let analytics = Arc::new(AnalyticsService::new((*analytics_repo).clone()));
         let recommendation = Arc::new(RecommendationService::new(
             (*model_repo).clone(),
             (*analytics_repo).clone(),
         ));
-        let runtime_governor = Arc::new(RuntimeGovernorService::new(
-            read_pool.clone(),
-            write_pool.clone(),
-            (*hardware_service).clone(),
+        let speech = Arc::new(SpeechService::new(
+            app_handle.clone(),
+            (*model_repo).clone(),
+        ));
+        let wake_word = Arc::new(WakeWordService::new(
+            app_handle.clone(),
+            (*settings_repo).clone(),
+            Arc::clone(&hardware_service),
+            Arc::clone(&speech),
         ));
+        let settings_watcher = Arc::new(SettingsWatcher::new(
+            Arc::clone(&cache),
+            Arc::clone(&hardware),
+            Arc::clone(&hardware_service),
+            Arc::clone(&runtime_governor),
+            Arc::clone(&inference),
+            Arc::clone(&wake_word),
+        ));
+        settings_watcher.start(settings_repo.subscribe());
+        let startup_wake_word = Arc::clone(&wake_word);
+        tokio::spawn(async move { startup_wake_word.refresh().await });
         let task_router = Arc::new(TaskRouterService::new(
             (*model_repo).clone(),
+            (*settings_repo).clone(),
+            (*workspace_repo).clone(),
+            (*runtime_governor).clone(),
+            write_pool.clone(),
+        ));
+        let launch_state = Arc::new(LaunchStateService::new(
+            (*settings_repo).clone(),
+            (*conversation_repo).clone(),
+            Arc::clone(&cache),
+        ));
+        let retention = Arc::new(RetentionService::new(
+            (*settings_repo).clone(),
+            (*conversation_repo).clone(),
+            (*embedding_repo).clone(),
+        ));
+        let notifications = Arc::new(NotificationService::new(
+            app_handle.clone(),
+            (*settings_repo).clone(),
+        ));
+        let takeout = Arc::new(TakeoutService::new(
+            (*user_repo).clone(),
+            (*conversation_repo).clone(),
+            (*memory_repo).clone(),
+            (*document_repo).clone(),
+            (*settings_repo).clone(),
+            (*analytics_repo).clone(),
+            (*model_repo).clone(),
+        ));
+        let reindex = Arc::new(ReindexService::new(
+            app_handle.clone(),
+            ReindexRepo::with_pools(read_pool.clone(), write_pool.clone()),
+            (*embedding_repo).clone(),
+            embedding.clone(),
             (*runtime_governor).clone(),
+            (*settings_repo).clone(),
+        ));
+        let captures = Arc::new(CaptureLibrary::new(CaptureRepo::with_pools(
+            read_pool.clone(),
             write_pool.clone(),
+        )));
+        let tts = Arc::new(TtsService::new(
+            (*settings_repo).clone(),
+            (*conversation_repo).clone(),
+        ));
+        let importer = Arc::new(ImportService::new(
+            (*model_repo).clone(),
+            (*conversation_repo).clone(),
+            rag.clone(),
         ));
         let setup_orchestrator = Arc::new(SetupOrchestratorService::new(
             read_pool.clone(),
             write_pool.clone(),
+            (*notifications).clone(),
         ));
 
         let query_classifier = Arc::new(SmartQueryClassifier::new());
         let usage_learner = Arc::new(UsageLearner::new());
-        let adaptive_memory = Arc::new(AdaptiveMemoryManager::new(
-            Arc::clone(&hardware_service),
-            Arc::clone(&inference),
-        ));
+        let memory_budget = MemoryBudget::new(
+            detected_tier,
+            detected_profile.total_ram_mb.max(0) as u64,
+            Arc::clone(&cache),
+            embedding.clone(),
+            reranker.clone(),
+            Arc::clone(&mcp),
+        );
+        let adaptive_memory = Arc::new(
+            AdaptiveMemoryManager::new(Arc::clone(&hardware_service), Arc::clone(&inference))
+                .with_budget(memory_budget),
+        );
         let predictive_preloader = Arc::new(PredictivePreloader::new(
             Arc::clone(&inference),
             embedding.clone(),
@@ -356,6 +584,17 @@ impl AppState {
         ));
         runtime_orchestrator.start_background_loops().await;
 
+        let generation_presets = Arc::new(GenerationPresetService::new((*settings_repo).clone()));
+        let tool_approvals = Arc::new(ToolApprovalService::new(
+            (*settings_repo).clone(),
+            (*mcp_repo).clone(),
+        ));
+        let history_search = Arc::new(HistorySearchService::new(
+            (*conversation_repo).clone(),
+            (*embedding_repo).clone(),
+            embedding.clone(),
+        ));
+
         let conversation = Arc::new(ConversationService::new(
             (*conversation_repo).clone(),
             (*context).clone(),
@@ -370,6 +609,10 @@ impl AppState {
             Arc::clone(&runtime_orchestrator),
             (*system_repo).clone(),
             Arc::clone(&hardware_service),
+            (*generation_presets).clone(),
+            (*tool_approvals).clone(),
+            (*history_search).clone(),
+            (*webhooks).clone(),
         ));
 
         let background = Arc::new(BackgroundService::new(
@@ -383,10 +626,27 @@ impl AppState {
             (*hardware_service).clone(),
             (*conversation_repo).clone(),
             (*system_repo).clone(),
+            (*history_search).clone(),
+            (*retention).clone(),
+            WatchedFolderRepo::with_pools(read_pool.clone(), write_pool.clone()),
+            JobRepo::with_pools(read_pool.clone(), write_pool.clone()),
+            (*scheduled_prompt_repo).clone(),
+            (*notifications).clone(),
+            (*webhooks).clone(),
             tier_config.background_tasks_enabled,
         ));
 
         background.start_critical_tasks().await?;
+        reindex.resume().await;
+        let local_api = Arc::new(LocalApiService::new(
+            app_handle.clone(),
+            (*settings_repo).clone(),
+            (*crypto).clone(),
+        ));
+        local_api.resume().await;
+        if hardware_changed {
+            background.request_recommendation_refresh();
+        }
 
         let model_manager =
             if tier_config.auto_load_model && embedding.is_some() && reranker.is_some() {
@@ -414,6 +674,10 @@ impl AppState {
             };
 
         let _ = user_repo.get_or_create_default_user().await?;
+        // Remote MCP header secrets are stored under the default user.
+        if let Err(error) = mcp.seal_plaintext_headers().await {
+            tracing::warn!("Failed to encrypt stored MCP headers: {error}");
+        }
 
         let startup_completed_at_utc = chrono::Utc::now().to_rfc3339();
         let startup_init_ms = startup_clock.elapsed().as_millis() as i64;
@@ -437,7 +701,12 @@ impl AppState {
             document_repo,
             embedding_repo,
             settings_repo,
+            saved_prompt_repo,
+            workspace_repo,
+            persona_repo,
+            scheduled_prompt_repo,
             analytics_repo,
+            adapter_repo,
             hardware_service,
             inference,
             embedding,
@@ -446,6 +715,7 @@ impl AppState {
             intent,
             memory,
             rag,
+            history_search,
             mcp,
             context,
             conversation,
@@ -457,6 +727,22 @@ impl AppState {
             runtime_orchestrator,
             setup_orchestrator,
             background,
+            launch_state,
+            retention,
+            notifications,
+            local_api,
+            webhooks,
+            takeout,
+            reindex,
+            importer,
+            captures,
+            speech,
+            tts,
+            wake_word,
+            generation_presets,
+            settings_watcher,
+            clarifications: Arc::new(ClarificationService::new()),
+            tool_approvals,
         })
     }
 
@@ -0,0 +1,378 @@
+use std::sync::{Arc, Mutex, OnceLock};
+
+use tauri::menu::{
+    CheckMenuItem, IsMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu,
+};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Manager, Wry};
+
+use crate::commands::integration_commands::open_history_window;
+use crate::db::models::SessionFilter;
+use crate::native_capture::{self, CaptureSurface};
+use crate::state::AppState;
+
+const TRAY_ID: &str = "sarah-tray";
+const RECENT_SESSION_COUNT: i64 = 5;
+const MAX_TITLE_CHARS: usize = 40;
+const HISTORY_FOCUS_EVENT: &str = "history:focus-session";
+const TRAY_SCREENSHOT_EVENT: &str = "capture://tray-screenshot";
+
+const TOGGLE_ID: &str = "toggle";
+const QUIT_ID: &str = "quit";
+const SCREENSHOT_ID: &str = "screenshot";
+const RECORDING_ID: &str = "recording";
+const SESSION_PREFIX: &str = "session:";
+const MODEL_PREFIX: &str = "model:";
+
+/// What the dynamic part of the menu shows, read from the app state.
+#[derive(Default)]
+struct TrayMenuState {
+    /// Id and title of the most recent sessions.
+    sessions: Vec<(String, String)>,
+    /// Id and name of the installed chat models, and whether it's the default.
+    models: Vec<(String, String, bool)>,
+    recording: bool,
+}
+
+/// Session the history window should open on, for a window that wasn't
+/// listening yet when the tray asked for it.
+fn pending_history_focus() -> &'static Mutex<Option<String>> {
+    static PENDING: OnceLock<Mutex<Option<String>>> = OnceLock::new();
+    PENDING.get_or_init(|| Mutex::new(None))
+}
+
+/// Creates the tray icon with its static entries; `refresh` fills in the rest.
+pub fn setup(app: &AppHandle) -> tauri::Result<()> {
+    let menu = build_menu(app, &TrayMenuState::default())?;
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Sarah AI")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(handle_menu_event)
+        .on_tray_icon_event(handle_tray_event);
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    refresh(app);
+    Ok(())
+}
+
+/// Rebuilds the menu from current state. Call after anything it shows changes:
+/// sessions, the default model, or whether a recording is running.
+pub fn refresh(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let menu_state = read_menu_state(&app).await;
+        let handle = app.clone();
+        let _ = app.run_on_main_thread(move || {
+            let Some(tray) = handle.tray_by_id(TRAY_ID) else {
+                return;
+            };
+            match build_menu(&handle, &menu_state) {
+                Ok(menu) => {
+                    let _ = tray.set_menu(Some(menu));
+                }
+                Err(error) => {
+                    crate::log_warn!("sarah.tray", "Failed to rebuild tray menu: {}", error);
+                }
+            }
+        });
+    });
+}
+
+/// Hands over the session picked from the tray, once.
+#[tauri::command]
+pub fn take_pending_history_focus() -> Option<String> {
+    crate::log_info!("sarah.command", "take_pending_history_focus invoked");
+    pending_history_focus()
+        .lock()
+        .ok()
+        .and_then(|mut pending| pending.take())
+}
+
+async fn read_menu_state(app: &AppHandle) -> TrayMenuState {
+    let recording = native_capture::is_recording();
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return TrayMenuState {
+            recording,
+            ..TrayMenuState::default()
+        };
+    };
+
+    let sessions = match state.user_repo.get_or_create_default_user().await {
+        Ok(user) => state
+            .conversation_repo
+            .list_sessions(
+                &user.id,
+                &SessionFilter::default(),
+                RECENT_SESSION_COUNT,
+                None,
+            )
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|session| {
+                let title = session
+                    .title
+                    .filter(|title| !title.trim().is_empty())
+                    .unwrap_or_else(|| "Untitled chat".to_string());
+                (session.id, shorten(&title))
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    let models = state
+        .model_repo
+        .list_installed()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|model| (model.id, model.display_name, model.is_default == 1))
+        .collect();
+
+    TrayMenuState {
+        sessions,
+        models,
+        recording,
+    }
+}
+
+fn build_menu(app: &AppHandle, menu_state: &TrayMenuState) -> tauri::Result<Menu<Wry>> {
+    let toggle = MenuItem::with_id(app, TOGGLE_ID, "Show/Hide Sarah", true, None::<&str>)?;
+
+    let session_items = menu_state
+        .sessions
+        .iter()
+        .map(|(id, title)| {
+            MenuItem::with_id(
+                app,
+                format!("{SESSION_PREFIX}{id}"),
+                title,
+                true,
+                None::<&str>,
+            )
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let no_sessions =
+        MenuItem::with_id(app, "sessions-empty", "No chats yet", false, None::<&str>)?;
+    let session_refs = if session_items.is_empty() {
+        vec![&no_sessions as &dyn IsMenuItem<Wry>]
+    } else {
+        session_items
+            .iter()
+            .map(|item| item as &dyn IsMenuItem<Wry>)
+            .collect()
+    };
+    let recent = Submenu::with_items(app, "Recent chats", true, &session_refs)?;
+
+    let screenshot = MenuItem::with_id(app, SCREENSHOT_ID, "Take screenshot", true, None::<&str>)?;
+    let recording_label = if menu_state.recording {
+        "Stop recording"
+    } else {
+        "Start recording"
+    };
+    let recording = MenuItem::with_id(app, RECORDING_ID, recording_label, true, None::<&str>)?;
+
+    let current_model = menu_state
+        .models
+        .iter()
+        .find(|(_, _, is_default)| *is_default)
+        .map(|(_, name, _)| format!("Model: {name}"))
+        .unwrap_or_else(|| "Model: none selected".to_string());
+    let model_items = menu_state
+        .models
+        .iter()
+        .map(|(id, name, is_default)| {
+            CheckMenuItem::with_id(
+                app,
+                format!("{MODEL_PREFIX}{id}"),
+                name,
+                !is_default,
+                *is_default,
+                None::<&str>,
+            )
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let no_models = MenuItem::with_id(
+        app,
+        "models-empty",
+        "No models installed",
+        false,
+        None::<&str>,
+    )?;
+    let model_refs = if model_items.is_empty() {
+        vec![&no_models as &dyn IsMenuItem<Wry>]
+    } else {
+        model_items
+            .iter()
+            .map(|item| item as &dyn IsMenuItem<Wry>)
+            .collect()
+    };
+    let models = Submenu::with_items(app, current_model, true, &model_refs)?;
+
+    let quit = MenuItem::with_id(app, QUIT_ID, "Quit", true, None::<&str>)?;
+    Menu::with_items(
+        app,
+        &[
+            &toggle,
+            &PredefinedMenuItem::separator(app)?,
+            &recent,
+            &screenshot,
+            &recording,
+            &models,
+            &PredefinedMenuItem::separator(app)?,
+            &quit,
+        ],
+    )
+}
+
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    let id = event.id().as_ref();
+    match id {
+        TOGGLE_ID => toggle_main_window(app),
+        QUIT_ID => app.exit(0),
+        SCREENSHOT_ID => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                match native_capture::take_native_screenshot(
+                    app.clone(),
+                    CaptureSurface::Screen,
+                    None,
+                    None,
+                    None,
+                )
+                .await
+                {
+                    Ok(screenshot) => {
+                        let _ = app.emit(
+                            TRAY_SCREENSHOT_EVENT,
+                            serde_json::json!({
+                                "screenshotPath": screenshot.screenshot_path,
+                                "captureId": screenshot.capture_id,
+                            }),
+                        );
+                    }
+                    Err(error) => {
+                        crate::log_warn!("sarah.tray", "Tray screenshot failed: {}", error);
+                    }
+                }
+            });
+        }
+        RECORDING_ID => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let result = if native_capture::is_recording() {
+                    native_capture::stop_native_screen_recording(app.clone(), None)
+                        .await
+                        .map(|_| ())
+                } else {
+                    native_capture::start_native_screen_recording(
+                        app.clone(),
+                        CaptureSurface::Screen,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                    )
+                };
+                if let Err(error) = result {
+                    crate::log_warn!("sarah.tray", "Tray recording toggle failed: {}", error);
+                    refresh(&app);
+                }
+            });
+        }
+        _ => {
+            if let Some(session_id) = id.strip_prefix(SESSION_PREFIX) {
+                focus_history_session(app, session_id.to_string());
+            } else if let Some(model_id) = id.strip_prefix(MODEL_PREFIX) {
+                switch_model(app, model_id.to_string());
+            }
+        }
+    }
+}
+
+fn handle_tray_event(tray: &TrayIcon, event: TrayIconEvent) {
+    if let TrayIconEvent::Click {
+        button: MouseButton::Left,
+        button_state: MouseButtonState::Up,
+        ..
+    } = event
+    {
+        toggle_main_window(tray.app_handle());
+    }
+}
+
+fn toggle_main_window(app: &AppHandle) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    if window.is_visible().unwrap_or(false) {
+        let _ = window.hide();
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        crate::overlay_position::place_main_window(&app).await;
+        let _ = window.show();
+        let _ = window.set_focus();
+        let _ = app.emit("sarah://show-overlay", ());
+    });
+}
+
+fn focus_history_session(app: &AppHandle, session_id: String) {
+    if let Ok(mut pending) = pending_history_focus().lock() {
+        *pending = Some(session_id.clone());
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(error) = open_history_window(app.clone()).await {
+            crate::log_warn!("sarah.tray", "Failed to open history: {}", error);
+            return;
+        }
+        // An already open window picks this up; a new one asks on load instead.
+        let _ = app.emit_to(
+            "history",
+            HISTORY_FOCUS_EVENT,
+            serde_json::json!({ "sessionId": session_id }),
+        );
+    });
+}
+
+fn switch_model(app: &AppHandle, model_id: String) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let Some(state) = app.try_state::<Arc<AppState>>() else {
+            return;
+        };
+        let state = Arc::clone(&state);
+        let result = async {
+            state.model_repo.set_default_model(&model_id).await?;
+            crate::commands::model_commands::refresh_installed_cache(&state).await
+        }
+        .await;
+        match result {
+            Ok(()) => {
+                let _ = app.emit(
+                    "models:default-changed",
+                    serde_json::json!({ "modelId": model_id }),
+                );
+            }
+            Err(error) => {
+                crate::log_warn!("sarah.tray", "Failed to switch model: {}", error);
+            }
+        }
+        refresh(&app);
+    });
+}
+
+fn shorten(title: &str) -> String {
+    if title.chars().count() <= MAX_TITLE_CHARS {
+        return title.to_string();
+    }
+    let cut = title.chars().take(MAX_TITLE_CHARS - 1).collect::<String>();
+    format!("{}…", cut.trim_end())
+}
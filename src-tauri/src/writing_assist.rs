@@ -0,0 +1,492 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
+
+use crate::clipboard;
+use crate::commands::integration_commands::{open_writing_window, WRITING_WINDOW_LABEL};
+use crate::commands::local_commands::{ensure_model_loaded, resolve_installed_model};
+use crate::db::models::GenerationOptions;
+use crate::error::AppError;
+use crate::services::inference_service::prompt_message;
+use crate::state::AppState;
+
+const WRITING_RESULT_EVENT: &str = "writing://result";
+pub const WRITING_SETTINGS_NAMESPACE: &str = "writing_assist";
+const SHORTCUTS_KEY: &str = "shortcuts";
+/// Lets the app under the overlay take focus back before its selection is copied.
+const FOCUS_SETTLE: Duration = Duration::from_millis(150);
+/// Rewrites replace the selection, so text that would have to be cut is refused.
+const MAX_REWRITE_CHARS: usize = clipboard::MAX_CLIPBOARD_CHARS;
+const SUMMARY_MAX_TOKENS: usize = 384;
+const REWRITE_MAX_TOKENS: usize = 4096;
+
+/// How `rewrite_selection` changes the text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RewriteStyle {
+    /// Clearer and better flowing, same length and tone.
+    #[default]
+    Improve,
+    Shorter,
+    Formal,
+    Casual,
+    /// Spelling and grammar only.
+    FixGrammar,
+}
+
+impl RewriteStyle {
+    fn instruction(self) -> &'static str {
+        match self {
+            Self::Improve => {
+                "so it reads more clearly and flows better, keeping its length and tone"
+            }
+            Self::Shorter => "to be noticeably shorter, keeping every important point",
+            Self::Formal => "in a formal, professional tone",
+            Self::Casual => "in a relaxed, friendly tone",
+            Self::FixGrammar => {
+                "with spelling, grammar and punctuation fixed, changing nothing else"
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WritingAction {
+    Rewrite,
+    Summarize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WritingResult {
+    pub action: WritingAction,
+    pub style: Option<RewriteStyle>,
+    pub original: String,
+    pub text: String,
+    /// Whether `text` was pasted over the selection rather than shown in the popup.
+    pub replaced: bool,
+}
+
+/// Global shortcuts for the writing actions, in the `Ctrl+Shift+R` form. Off
+/// until the user turns them on, since any combination may already mean
+/// something in another app or keyboard layout.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WritingShortcuts {
+    pub enabled: bool,
+    /// Rewrites the selection in place in the `improve` style.
+    pub rewrite: String,
+    /// Summarizes the selection in the popup.
+    pub summarize: String,
+}
+
+impl Default for WritingShortcuts {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rewrite: "CommandOrControl+Shift+Period".to_string(),
+            summarize: "CommandOrControl+Shift+Comma".to_string(),
+        }
+    }
+}
+
+/// Result the popup should show, for a window that wasn't listening yet when
+/// it was produced.
+fn pending_result() -> &'static Mutex<Option<WritingResult>> {
+    static PENDING: OnceLock<Mutex<Option<WritingResult>>> = OnceLock::new();
+    PENDING.get_or_init(|| Mutex::new(None))
+}
+
+/// The writing shortcuts currently registered, so a settings change can
+/// release them.
+fn registered_shortcuts() -> &'static Mutex<Vec<Shortcut>> {
+    static REGISTERED: OnceLock<Mutex<Vec<Shortcut>>> = OnceLock::new();
+    REGISTERED.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// Set while an action runs, so a repeated shortcut doesn't copy the
+/// selection again halfway through.
+fn busy() -> &'static AtomicBool {
+    static BUSY: AtomicBool = AtomicBool::new(false);
+    &BUSY
+}
+
+/// Rewrites the selected text in whatever app has focus. The result replaces
+/// the selection unless `replace` is false, in which case it's shown in the
+/// popup.
+#[tauri::command]
+pub async fn rewrite_selection(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    style: RewriteStyle,
+    replace: Option<bool>,
+) -> Result<WritingResult, AppError> {
+    crate::log_info!("sarah.command", "rewrite_selection invoked");
+    run_action(
+        &app,
+        &state,
+        WritingAction::Rewrite,
+        Some(style),
+        replace.unwrap_or(true),
+    )
+    .await
+}
+
+/// Summarizes the selected text in whatever app has focus, in the popup.
+#[tauri::command]
+pub async fn summarize_selection(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<WritingResult, AppError> {
+    crate::log_info!("sarah.command", "summarize_selection invoked");
+    run_action(&app, &state, WritingAction::Summarize, None, false).await
+}
+
+/// Hands the popup the result it was opened for, once.
+#[tauri::command]
+pub fn take_writing_result() -> Option<WritingResult> {
+    crate::log_info!("sarah.command", "take_writing_result invoked");
+    pending_result()
+        .lock()
+        .ok()
+        .and_then(|mut pending| pending.take())
+}
+
+#[tauri::command]
+pub async fn get_writing_shortcuts(
+    state: State<'_, Arc<AppState>>,
+) -> Result<WritingShortcuts, AppError> {
+    crate::log_info!("sarah.command", "get_writing_shortcuts invoked");
+    load_shortcuts(&state).await
+}
+
+/// Saves the shortcuts and registers them right away, or releases them when
+/// they are turned off.
+#[tauri::command]
+pub async fn set_writing_shortcuts(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    shortcuts: WritingShortcuts,
+) -> Result<WritingShortcuts, AppError> {
+    crate::log_info!("sarah.command", "set_writing_shortcuts invoked");
+    let shortcuts = WritingShortcuts {
+        enabled: shortcuts.enabled,
+        rewrite: shortcuts.rewrite.trim().to_string(),
+        summarize: shortcuts.summarize.trim().to_string(),
+    };
+    let bindings = shortcut_bindings(&shortcuts)?;
+    let json = serde_json::to_string(&shortcuts)
+        .map_err(|e| AppError::Internal(format!("Failed to save writing shortcuts: {e}")))?;
+    state
+        .settings_repo
+        .upsert_setting(
+            None,
+            WRITING_SETTINGS_NAMESPACE,
+            SHORTCUTS_KEY,
+            &json,
+            "json",
+            false,
+        )
+        .await?;
+
+    let failed = apply_shortcuts(&app, if shortcuts.enabled { &bindings } else { &[] });
+    if let Some((action, error)) = failed.into_iter().next() {
+        let text = match action {
+            WritingAction::Rewrite => &shortcuts.rewrite,
+            WritingAction::Summarize => &shortcuts.summarize,
+        };
+        return Err(AppError::Validation {
+            field: "shortcuts".to_string(),
+            message: format!("{text} could not be registered: {error}"),
+        });
+    }
+    Ok(shortcuts)
+}
+
+/// Registers the saved writing shortcuts, if the user turned them on.
+pub fn register_writing_shortcuts(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let Some(state) = app.try_state::<Arc<AppState>>() else {
+            return;
+        };
+        let bindings = match load_shortcuts(&state).await {
+            Ok(shortcuts) if !shortcuts.enabled => return,
+            Ok(shortcuts) => shortcut_bindings(&shortcuts),
+            Err(error) => Err(error),
+        };
+        let bindings = match bindings {
+            Ok(bindings) => bindings,
+            Err(error) => {
+                crate::log_warn!("sarah.writing", "Writing shortcuts not loaded: {}", error);
+                return;
+            }
+        };
+        for (action, error) in apply_shortcuts(&app, &bindings) {
+            crate::log_warn!(
+                "sarah.writing",
+                "Failed to register {:?} shortcut: {}",
+                action,
+                error
+            );
+        }
+    });
+}
+
+async fn load_shortcuts(state: &AppState) -> Result<WritingShortcuts, AppError> {
+    let setting = state
+        .settings_repo
+        .get_setting(None, WRITING_SETTINGS_NAMESPACE, SHORTCUTS_KEY)
+        .await?;
+    Ok(setting
+        .and_then(|setting| serde_json::from_str(&setting.value).ok())
+        .unwrap_or_default())
+}
+
+/// Parses both shortcuts, even when they are off, so a bad one is caught
+/// when it is saved rather than when it is turned on.
+fn shortcut_bindings(
+    shortcuts: &WritingShortcuts,
+) -> Result<Vec<(Shortcut, WritingAction)>, AppError> {
+    let mut bindings = Vec::new();
+    for (text, action) in [
+        (&shortcuts.rewrite, WritingAction::Rewrite),
+        (&shortcuts.summarize, WritingAction::Summarize),
+    ] {
+        let invalid = |message: String| AppError::Validation {
+            field: "shortcuts".to_string(),
+            message,
+        };
+        let shortcut = text
+            .parse::<Shortcut>()
+            .map_err(|error| invalid(format!("{text} is not a valid shortcut: {error}")))?;
+        if shortcut.mods.is_empty() || shortcut.mods == Modifiers::SHIFT {
+            return Err(invalid(format!(
+                "{text} needs Ctrl, Alt or Cmd, or it would catch normal typing"
+            )));
+        }
+        // Windows and Linux read Ctrl+Alt as AltGr, which types characters on
+        // many keyboard layouts.
+        if !cfg!(target_os = "macos")
+            && shortcut.mods & (Modifiers::CONTROL | Modifiers::ALT | Modifiers::SUPER)
+                == Modifiers::CONTROL | Modifiers::ALT
+        {
+            return Err(invalid(format!(
+                "{text} is also AltGr on many keyboards; add Shift or pick another combination"
+            )));
+        }
+        if bindings.iter().any(|(other, _)| *other == shortcut) {
+            return Err(invalid(
+                "Rewrite and summarize need different shortcuts".to_string(),
+            ));
+        }
+        bindings.push((shortcut, action));
+    }
+    Ok(bindings)
+}
+
+/// Releases the previous writing shortcuts and registers `bindings`.
+/// Returns the actions whose shortcut couldn't be registered, e.g. because
+/// another app holds it.
+fn apply_shortcuts(
+    app: &AppHandle,
+    bindings: &[(Shortcut, WritingAction)],
+) -> Vec<(WritingAction, String)> {
+    let global = app.global_shortcut();
+    let Ok(mut registered) = registered_shortcuts().lock() else {
+        return Vec::new();
+    };
+    for shortcut in registered.drain(..) {
+        let _ = global.unregister(shortcut);
+    }
+
+    let mut failed = Vec::new();
+    for &(shortcut, action) in bindings {
+        let result = global.on_shortcut(shortcut, move |app, _shortcut, event| {
+            // On release, so the shortcut's own keys are up before copy is pressed.
+            if event.state != ShortcutState::Released {
+                return;
+            }
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let Some(state) = app.try_state::<Arc<AppState>>() else {
+                    return;
+                };
+                let state = Arc::clone(&state);
+                let (style, replace) = match action {
+                    WritingAction::Rewrite => (Some(RewriteStyle::default()), true),
+                    WritingAction::Summarize => (None, false),
+                };
+                if let Err(error) = run_action(&app, &state, action, style, replace).await {
+                    crate::log_warn!("sarah.writing", "Selection action failed: {}", error);
+                }
+            });
+        });
+        match result {
+            Ok(()) => registered.push(shortcut),
+            Err(error) => failed.push((action, error.to_string())),
+        }
+    }
+    failed
+}
+
+async fn run_action(
+    app: &AppHandle,
+    state: &Arc<AppState>,
+    action: WritingAction,
+    style: Option<RewriteStyle>,
+    replace: bool,
+) -> Result<WritingResult, AppError> {
+    if busy().swap(true, Ordering::SeqCst) {
+        return Err(AppError::Validation {
+            field: "selection".to_string(),
+            message: "A selection action is already running.".to_string(),
+        });
+    }
+    let result = transform_selection(app, state, action, style, replace).await;
+    busy().store(false, Ordering::SeqCst);
+    result
+}
+
+async fn transform_selection(
+    app: &AppHandle,
+    state: &Arc<AppState>,
+    action: WritingAction,
+    style: Option<RewriteStyle>,
+    replace: bool,
+) -> Result<WritingResult, AppError> {
+    return_focus(app).await;
+    let original = tokio::task::spawn_blocking(clipboard::copy_selection)
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .map_err(AppError::Io)?
+        .ok_or_else(|| AppError::Validation {
+            field: "selection".to_string(),
+            message: "Select some text first.".to_string(),
+        })?;
+
+    let (system, max_tokens) = match action {
+        WritingAction::Rewrite => {
+            if original.chars().count() > MAX_REWRITE_CHARS {
+                return Err(AppError::Validation {
+                    field: "selection".to_string(),
+                    message: format!("Select under {MAX_REWRITE_CHARS} characters to rewrite."),
+                });
+            }
+            let instruction = style.unwrap_or_default().instruction();
+            let system = format!(
+                "You are a writing assistant. Rewrite the user's text {instruction}. Keep its \
+                 meaning, its language and any formatting such as lists or line breaks. Reply \
+                 with only the rewritten text: no preamble, notes or quotation marks."
+            );
+            (system, REWRITE_MAX_TOKENS)
+        }
+        WritingAction::Summarize => {
+            let system = "You are a writing assistant. Summarize the user's text in a few \
+                          sentences, or short bullet points if it covers several topics. Write \
+                          in the text's language. Reply with only the summary."
+                .to_string();
+            (system, SUMMARY_MAX_TOKENS)
+        }
+    };
+    let input = original
+        .chars()
+        .take(clipboard::MAX_CLIPBOARD_CHARS)
+        .collect::<String>();
+    let text = generate(state, system, input, max_tokens).await?;
+
+    let replaced = replace && {
+        let pasted = text.clone();
+        match tokio::task::spawn_blocking(move || clipboard::paste_text(&pasted)).await {
+            Ok(Ok(())) => true,
+            Ok(Err(error)) => {
+                crate::log_warn!("sarah.writing", "{}", error);
+                false
+            }
+            Err(error) => {
+                crate::log_warn!("sarah.writing", "Paste task failed: {}", error);
+                false
+            }
+        }
+    };
+
+    let result = WritingResult {
+        action,
+        style,
+        original,
+        text,
+        replaced,
+    };
+    // A rewrite that couldn't be pasted lands in the popup instead of being lost.
+    if !replaced {
+        show_result(app, &result).await;
+    }
+    Ok(result)
+}
+
+/// Runs the prompt through the loaded model, loading the default one if none is.
+async fn generate(
+    state: &Arc<AppState>,
+    system: String,
+    input: String,
+    max_tokens: usize,
+) -> Result<String, AppError> {
+    if !state.inference.is_loaded().await {
+        let model = resolve_installed_model(state, None)
+            .await
+            .map_err(AppError::Inference)?;
+        ensure_model_loaded(state, &model)
+            .await
+            .map_err(AppError::Inference)?;
+    }
+
+    let messages = vec![
+        prompt_message("system", system),
+        prompt_message("user", input),
+    ];
+    let options = GenerationOptions {
+        temperature: 0.3,
+        max_tokens,
+        ..GenerationOptions::default()
+    };
+    let generated = state
+        .inference
+        .generate_with_options(messages, &[], options)
+        .await?;
+    let text = generated.text.trim().to_string();
+    if text.is_empty() {
+        return Err(AppError::Inference(
+            "Local model returned an empty response.".to_string(),
+        ));
+    }
+    Ok(text)
+}
+
+/// Hides the overlay when it has focus, so the copy reaches the app the user
+/// was working in.
+async fn return_focus(app: &AppHandle) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    if window.is_focused().unwrap_or(false) {
+        let _ = window.hide();
+        tokio::time::sleep(FOCUS_SETTLE).await;
+    }
+}
+
+async fn show_result(app: &AppHandle, result: &WritingResult) {
+    if let Ok(mut pending) = pending_result().lock() {
+        *pending = Some(result.clone());
+    }
+    if let Err(error) = open_writing_window(app.clone()).await {
+        crate::log_warn!("sarah.writing", "Failed to open result popup: {}", error);
+        return;
+    }
+    // An already open popup picks this up; a new one asks on load instead.
+    let _ = app.emit_to(WRITING_WINDOW_LABEL, WRITING_RESULT_EVENT, result);
+}
@@ -5,14 +5,17 @@ import { Component, Suspense, lazy, useEffect, useMemo, useState, type ErrorInfo
 import { useTheme } from "@/hooks/useTheme";
 import "@/styles/sarah-ai.css";
 
+import type { StartupStatus } from "@/components/SafeModeWindow";
 import type { SetupState } from "@/components/SetupWindow";
 
 const SetupWindow = lazy(() => import("@/components/SetupWindow"));
+const SafeModeWindow = lazy(() => import("@/components/SafeModeWindow"));
 const MainOverlayApp = lazy(() => import("@/components/MainOverlayApp"));
 const SettingsWindow = lazy(() => import("@/components/SettingsWindow"));
 const HistoryWindow = lazy(() => import("@/components/HistoryWindow"));
 const ModelsWindow = lazy(() => import("@/components/ModelsWindow"));
 const McpMarketplaceWindow = lazy(() => import("@/components/McpMarketplaceWindow"));
+const WritingResultWindow = lazy(() => import("@/components/WritingResultWindow"));
 const SpotifyAudioPlayer = lazy(() =>
   import("@/components/SpotifyAudioPlayer").then((module) => ({
     default: module.SpotifyAudioPlayer,
@@ -20,7 +23,7 @@ const SpotifyAudioPlayer = lazy(() =>
 );
 import { getCurrentWindow } from "@tauri-apps/api/window";
 
-type WindowType = "main" | "settings" | "history" | "models" | "mcp" | "audio";
+type WindowType = "main" | "settings" | "history" | "models" | "mcp" | "audio" | "writing";
 
 declare global {
   interface Window {
@@ -36,7 +39,8 @@ function normalizeWindowType(value: null | string | undefined): null | WindowTyp
     normalized === "history" ||
     normalized === "models" ||
     normalized === "mcp" ||
-    normalized === "audio"
+    normalized === "audio" ||
+    normalized === "writing"
   ) {
     return normalized;
   }
@@ -140,6 +144,7 @@ function App() {
   const { isDarkTheme, theme, toggleTheme } = useTheme();
   const [isBackendReady, setIsBackendReady] = useState(false);
   const [setupState, setSetupState] = useState<SetupState | null | undefined>(undefined);
+  const [safeModeStatus, setSafeModeStatus] = useState<StartupStatus | null>(null);
 
   useEffect(() => {
     document.documentElement.setAttribute("data-window-type", windowType);
@@ -151,6 +156,15 @@ function App() {
       setIsBackendReady(true);
     });
 
+    const loadStartupStatus = () =>
+      invoke<StartupStatus>("get_startup_status")
+        .then((status) => setSafeModeStatus(status.safeMode ? status : null))
+        .catch(() => { });
+    const unlistenSafeMode = listen("backend-safe-mode", () => {
+      void loadStartupStatus();
+    });
+    void loadStartupStatus();
+
     // Also invoke a ping just in case the event already fired before we started listening
     invoke("get_startup_telemetry")
       .then(() => setIsBackendReady(true))
@@ -162,6 +176,7 @@ function App() {
 
     return () => {
       unlisten.then(f => f());
+      unlistenSafeMode.then(f => f());
     };
   }, []);
 
@@ -184,6 +199,8 @@ function App() {
             <ModelsWindow />
           ) : windowType === "mcp" ? (
             <McpMarketplaceWindow />
+          ) : windowType === "writing" ? (
+            <WritingResultWindow />
           ) : windowType === "audio" ? (
             <main className="sarah-audio-window" aria-label="Spotify audio window">
               <SpotifyAudioPlayer
@@ -208,6 +225,15 @@ function App() {
     );
   }
 
+  // Startup failed: only the recovery commands are available
+  if (safeModeStatus) {
+    return (
+      <Suspense fallback={null}>
+        <SafeModeWindow initialStatus={safeModeStatus} />
+      </Suspense>
+    );
+  }
+
   if (setupState === undefined) {
     return (
       <div className="flex h-screen w-screen items-center justify-center bg-background text-foreground" data-tauri-drag-region>
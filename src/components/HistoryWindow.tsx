@@ -1,7 +1,8 @@
 import { getCurrentWindow } from "@tauri-apps/api/window";
-import { Clock3, History, Trash2, Search, ArrowLeft, MessageSquare } from "lucide-react";
+import { Clock3, History, Trash2, Search, ArrowLeft, MessageSquare, Share2 } from "lucide-react";
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { Button } from "@/components/ui/button";
 
 interface HistoryWindowProps {
@@ -12,6 +13,7 @@ interface HistoryWindowProps {
 interface Session {
   id: string;
   title: string | null;
+  summary: string | null;
   messageCount: number;
   lastMessageAt: string | null;
   createdAt: string;
@@ -53,7 +55,33 @@ export default function HistoryWindow({ embedded = false, onRequestClose }: Hist
 
   useEffect(() => {
     sync();
-    // No polling, relying on manual refresh or reopening
+    // No polling; background summaries push a refresh when they land
+    const unlisten = listen("session:summarized", () => {
+      sync();
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    // A chat picked from the tray menu: asked for once on load, pushed after that
+    void invoke<string | null>("take_pending_history_focus")
+      .then((sessionId) => {
+        if (sessionId) {
+          handleSelectSession(sessionId);
+        }
+      })
+      .catch(() => {
+        // Ignore if not running in Tauri context.
+      });
+    const unlisten = listen<{ sessionId: string }>("history:focus-session", (event) => {
+      void invoke("take_pending_history_focus").catch(() => undefined);
+      handleSelectSession(event.payload.sessionId);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
   }, []);
 
   useEffect(() => {
@@ -99,6 +127,15 @@ export default function HistoryWindow({ embedded = false, onRequestClose }: Hist
     }
   };
 
+  const handleShareSession = async (sessionId: string, e: React.MouseEvent) => {
+    e.stopPropagation();
+    try {
+      await invoke<string | null>("share_session", { sessionId });
+    } catch (error) {
+      console.error("Failed to share session", error);
+    }
+  };
+
   const handleSelectSession = async (sessionId: string) => {
     try {
       const messages = await invoke<Message[]>("get_session_messages", {
@@ -200,11 +237,17 @@ export default function HistoryWindow({ embedded = false, onRequestClose }: Hist
                     <p className="text-sm font-medium truncate text-card-foreground">
                       {session.title || "New Conversation"}
                     </p>
+                    {session.summary && (
+                      <p className="text-xs text-muted-foreground line-clamp-2 mt-0.5">{session.summary}</p>
+                    )}
                     <div className="flex items-center gap-3 mt-1 text-xs text-muted-foreground">
                       <span className="flex items-center gap-1"><MessageSquare className="size-3 h-[10px] w-[10px]" /> {session.messageCount} msgs</span>
                       <span className="flex items-center gap-1"><Clock3 className="size-3 h-[10px] w-[10px]" /> {session.lastMessageAt ? new Date(session.lastMessageAt).toLocaleDateString() : new Date(session.createdAt).toLocaleDateString()}</span>
                     </div>
                   </div>
+                  <Button variant="ghost" size="icon" onClick={(e) => handleShareSession(session.id, e)} className="opacity-0 group-hover:opacity-100 transition-opacity shrink-0" aria-label="Share conversation">
+                    <Share2 className="h-4 w-4" />
+                  </Button>
                   <Button variant="ghost" size="icon" onClick={(e) => handleDeleteSession(session.id, e)} className="opacity-0 group-hover:opacity-100 transition-opacity text-destructive hover:text-destructive hover:bg-destructive/10 shrink-0">
                     <Trash2 className="h-4 w-4" />
                   </Button>
@@ -3,7 +3,7 @@ import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { LogicalSize, getCurrentWindow } from "@tauri-apps/api/window";
 import { AnimatePresence, motion } from "framer-motion";
-import { AudioLines, Bot, ChevronDown, MoonStar, Store, Sun } from "lucide-react";
+import { AudioLines, Bot, ChevronDown, ClipboardList, MoonStar, Store, Sun, X } from "lucide-react";
 import { Suspense, lazy, useCallback, useEffect, useMemo, useRef, useState } from "react";
 import AssistantInput from "@/components/AssistantInput";
 import { useAppPreferences } from "@/hooks/useAppPreferences";
@@ -12,7 +12,7 @@ import {
   useQuickSwitchModels,
 } from "@/hooks/useQuickSwitchModels";
 import { useScreenRecording } from "@/hooks/useScreenRecording";
-import { useUIState } from "@/hooks/useUIState";
+import { type ClipboardContext, useUIState } from "@/hooks/useUIState";
 import type { DesktopWindowSource } from "@/types/screenSources";
 import "@/styles/sarah-ai.css";
 
@@ -74,6 +74,17 @@ interface NativeScreenshotResult {
   screenshotPath: string;
 }
 
+interface VoiceTranscriptPayload {
+  captureId: string;
+  text: string;
+  isFinal: boolean;
+}
+
+interface DeepLinkAsk {
+  prompt: string;
+  model: string | null;
+}
+
 function buildQuickSwitchOptions(
   availableModels: string[],
   quickSwitchModels: string[],
@@ -194,12 +205,14 @@ function MainOverlayApp({ isDarkTheme, onToggleTheme }: MainOverlayAppProps) {
     amplitude,
     clearConversation,
     clearPrompt,
+    clipboardContext,
     conversations,
     cycleState,
     isPromptLocked,
     modelSelectionMode,
     prompt,
     selectedModel,
+    setClipboardContext,
     setModelSelectionMode,
     setPrompt,
     setSelectedModel,
@@ -812,6 +825,10 @@ function MainOverlayApp({ isDarkTheme, onToggleTheme }: MainOverlayAppProps) {
           : WINDOW_HEIGHT_HIDDEN;
 
         await currentWindow.setSize(new LogicalSize(WINDOW_WIDTH, targetHeight));
+        if (isUiVisible) {
+          // Center / follow-cursor / remembered placement, per the overlay settings.
+          await invoke("position_overlay");
+        }
       } catch {
         // Ignore if not running in Tauri context.
       }
@@ -858,6 +875,136 @@ function MainOverlayApp({ isDarkTheme, onToggleTheme }: MainOverlayAppProps) {
     };
   }, []);
 
+  useEffect(() => {
+    let unlisten: null | (() => void) = null;
+    let disposed = false;
+
+    void listen<VoiceTranscriptPayload>("voice://transcript", (event) => {
+      if (event.payload.text.trim().length > 0) {
+        setPrompt(event.payload.text);
+      }
+    })
+      .then((dispose) => {
+        if (disposed) {
+          dispose();
+          return;
+        }
+        unlisten = dispose;
+      })
+      .catch(() => {
+        // Ignore if not running in Tauri context.
+      });
+
+    return () => {
+      disposed = true;
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, [setPrompt]);
+
+  useEffect(() => {
+    let unlisten: null | (() => void) = null;
+    let disposed = false;
+
+    void listen<ClipboardContext | null>("sarah://clipboard-context", (event) => {
+      setIsUiVisible(true);
+      setClipboardContext(event.payload);
+    })
+      .then((dispose) => {
+        if (disposed) {
+          dispose();
+          return;
+        }
+        unlisten = dispose;
+      })
+      .catch(() => {
+        // Ignore if not running in Tauri context.
+      });
+
+    return () => {
+      disposed = true;
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, [setClipboardContext]);
+
+  useEffect(() => {
+    let unlisten: null | (() => void) = null;
+    let disposed = false;
+
+    const applyDeepLink = (ask: DeepLinkAsk) => {
+      setIsUiVisible(true);
+      if (ask.prompt) {
+        setPrompt(ask.prompt);
+      }
+      if (ask.model && ask.model.toLowerCase() !== "auto") {
+        setSelectedModel(ask.model);
+        setModelSelectionMode("manual");
+      } else if (ask.model) {
+        setModelSelectionMode("auto");
+      }
+    };
+
+    // A sarah://ask link: the one the app was launched with, then any opened later
+    void invoke<DeepLinkAsk | null>("take_pending_deep_link")
+      .then((ask) => {
+        if (ask && !disposed) {
+          applyDeepLink(ask);
+        }
+      })
+      .catch(() => {
+        // Ignore if not running in Tauri context.
+      });
+
+    void listen<DeepLinkAsk>("sarah://deep-link-ask", (event) => {
+      void invoke("take_pending_deep_link").catch(() => undefined);
+      applyDeepLink(event.payload);
+    })
+      .then((dispose) => {
+        if (disposed) {
+          dispose();
+          return;
+        }
+        unlisten = dispose;
+      })
+      .catch(() => {
+        // Ignore if not running in Tauri context.
+      });
+
+    return () => {
+      disposed = true;
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, [setModelSelectionMode, setPrompt, setSelectedModel]);
+
+  useEffect(() => {
+    if (!isUiVisible) {
+      return;
+    }
+
+    let cancelled = false;
+    void (async () => {
+      const enabled = await invoke<boolean>("get_use_clipboard_context");
+      if (!enabled || cancelled) {
+        return;
+      }
+      const context = await invoke<ClipboardContext | null>("read_clipboard_context");
+      if (!cancelled && context) {
+        setClipboardContext(context);
+      }
+    })().catch(() => {
+      // Ignore if not running in Tauri context.
+    });
+
+    return () => {
+      cancelled = true;
+    };
+  }, [isUiVisible, setClipboardContext]);
+
   useEffect(() => {
     let unlisten: null | (() => void) = null;
     let disposed = false;
@@ -1000,6 +1147,22 @@ function MainOverlayApp({ isDarkTheme, onToggleTheme }: MainOverlayAppProps) {
               data-tauri-disable-drag-region="true"
             >
               <div className="sarah-response-toolbar__actions">
+                {clipboardContext && (
+                  <button
+                    type="button"
+                    className="sarah-clipboard-context-chip"
+                    onClick={() => setClipboardContext(null)}
+                    title={
+                      clipboardContext.truncated
+                        ? "Copied text attached, cut to fit. Click to remove."
+                        : "Copied text attached. Click to remove."
+                    }
+                  >
+                    <ClipboardList className="size-3.5" />
+                    <span>{clipboardContext.charCount.toLocaleString()} chars</span>
+                    <X className="size-3" />
+                  </button>
+                )}
                 <button
                   type="button"
                   className="sarah-response-toggle-button"
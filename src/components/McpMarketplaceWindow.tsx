@@ -13,6 +13,7 @@ import {
   Server,
   ShieldCheck,
   Sparkles,
+  Trash2,
   X,
 } from "lucide-react";
 import { useCallback, useEffect, useMemo, useRef, useState } from "react";
@@ -62,6 +63,40 @@ const DEFAULT_AUTH_NOTICE: SpotifyAuthNotice = {
   message: "OAuth not completed yet. Run OAuth after saving credentials.",
 };
 
+type McpServerRecord = {
+  id: string;
+  name: string;
+  displayName: string;
+  category: string;
+  mcpType: string;
+  command: string | null;
+  args: string;
+  url: string | null;
+  toolSchemas: string;
+  isActive: number;
+  isBuiltin: number;
+  healthStatus: string;
+  lastError: string | null;
+};
+
+type CustomServerDraft = {
+  name: string;
+  command: string;
+  args: string;
+  env: string;
+  authHeader: string;
+};
+
+const EMPTY_SERVER_DRAFT: CustomServerDraft = {
+  name: "",
+  command: "",
+  args: "",
+  env: "",
+  authHeader: "",
+};
+
+const REMOTE_URL_PATTERN = /^https?:\/\//i;
+
 interface McpMarketplaceWindowProps {
   embedded?: boolean;
   onRequestClose?: () => void;
@@ -112,6 +147,46 @@ const TOOL_COVERAGE = [
   },
 ];
 
+function parseToolNames(toolSchemas: string): string[] {
+  try {
+    const parsed = JSON.parse(toolSchemas) as Array<{ name?: unknown }>;
+    return Array.isArray(parsed)
+      ? parsed.map((tool) => (typeof tool?.name === "string" ? tool.name : "")).filter(Boolean)
+      : [];
+  } catch {
+    return [];
+  }
+}
+
+function formatLaunchCommand(server: McpServerRecord): string {
+  if (server.mcpType === "http") {
+    return server.url ?? "";
+  }
+  try {
+    const args = JSON.parse(server.args) as unknown;
+    const argList = Array.isArray(args) ? args.map(String) : [];
+    return [server.command ?? "", ...argList].join(" ").trim();
+  } catch {
+    return server.command ?? "";
+  }
+}
+
+/** "KEY=value, OTHER=value" -> { KEY: "value", OTHER: "value" } */
+function parseEnvPairs(raw: string): Record<string, string> {
+  return raw
+    .split(/[,\n]/)
+    .map((pair) => pair.trim())
+    .filter((pair) => pair.includes("="))
+    .reduce<Record<string, string>>((env, pair) => {
+      const index = pair.indexOf("=");
+      const key = pair.slice(0, index).trim();
+      if (key) {
+        env[key] = pair.slice(index + 1).trim();
+      }
+      return env;
+    }, {});
+}
+
 function readConfig(): SpotifyMcpConfig {
   if (typeof window === "undefined") {
     return DEFAULT_CONFIG;
@@ -194,6 +269,10 @@ function McpMarketplaceWindow({
   const [isAuthWorking, setIsAuthWorking] = useState(false);
   const [isBuildWorking, setIsBuildWorking] = useState(false);
   const [isSavingConfig, setIsSavingConfig] = useState(false);
+  const [customServers, setCustomServers] = useState<McpServerRecord[]>([]);
+  const [serverDraft, setServerDraft] = useState<CustomServerDraft>(EMPTY_SERVER_DRAFT);
+  const [customNotice, setCustomNotice] = useState("");
+  const [isCustomWorking, setIsCustomWorking] = useState(false);
   const pendingRef = useRef(false);
   const autoStartAttemptedRef = useRef(false);
 
@@ -242,10 +321,93 @@ function McpMarketplaceWindow({
     }
   }, []);
 
+  const refreshCustomServers = useCallback(async () => {
+    try {
+      const servers = await invoke<McpServerRecord[]>("list_mcps", { installedOnly: true });
+      setCustomServers(servers.filter((server) => server.category === "custom"));
+    } catch (error) {
+      console.error("Failed to list MCP servers.", error);
+      setCustomNotice("Could not load registered MCP servers.");
+    }
+  }, []);
+
   useEffect(() => {
     void refreshStatus();
   }, [refreshStatus]);
 
+  useEffect(() => {
+    void refreshCustomServers();
+  }, [refreshCustomServers]);
+
+  const handleAddServer = useCallback(async () => {
+    const name = serverDraft.name.trim();
+    const command = serverDraft.command.trim();
+    if (!name || !command) {
+      setCustomNotice("A name and a launch command or URL are required.");
+      return;
+    }
+
+    setIsCustomWorking(true);
+    try {
+      const server = REMOTE_URL_PATTERN.test(command)
+        ? await invoke<McpServerRecord>("add_remote_mcp_server", {
+            name,
+            url: command,
+            authHeader: serverDraft.authHeader.trim() || null,
+          })
+        : await invoke<McpServerRecord>("add_mcp_server", {
+            name,
+            command,
+            args: serverDraft.args.split(/\s+/).filter(Boolean),
+            env: parseEnvPairs(serverDraft.env),
+          });
+      const toolCount = parseToolNames(server.toolSchemas).length;
+      const failure = server.lastError ?? "unknown error";
+      setCustomNotice(
+        server.healthStatus === "down"
+          ? `Registered ${server.displayName}, but it failed to start: ${failure}`
+          : `Registered ${server.displayName} with ${toolCount} tools.`,
+      );
+      setServerDraft(EMPTY_SERVER_DRAFT);
+      await refreshCustomServers();
+    } catch (error) {
+      console.error("Failed to add MCP server.", error);
+      setCustomNotice(`Could not add server: ${String(error)}`);
+    } finally {
+      setIsCustomWorking(false);
+    }
+  }, [refreshCustomServers, serverDraft]);
+
+  const handleRemoveServer = useCallback(
+    async (server: McpServerRecord) => {
+      setIsCustomWorking(true);
+      try {
+        await invoke("remove_mcp_server", { mcpId: server.id });
+        setCustomNotice(`Removed ${server.displayName}.`);
+        await refreshCustomServers();
+      } catch (error) {
+        console.error("Failed to remove MCP server.", error);
+        setCustomNotice(`Could not remove ${server.displayName}.`);
+      } finally {
+        setIsCustomWorking(false);
+      }
+    },
+    [refreshCustomServers],
+  );
+
+  const handleToggleServer = useCallback(
+    async (server: McpServerRecord, enabled: boolean) => {
+      try {
+        await invoke("enable_mcp_server", { mcpId: server.id, enabled });
+        await refreshCustomServers();
+      } catch (error) {
+        console.error("Failed to toggle MCP server.", error);
+        setCustomNotice(`Could not update ${server.displayName}.`);
+      }
+    },
+    [refreshCustomServers],
+  );
+
   useEffect(() => {
     void hydrateConfigFromDisk(config.serverRoot);
     // Hydrate only once on open so user edits are not overwritten while typing.
@@ -253,8 +415,12 @@ function McpMarketplaceWindow({
   }, [hydrateConfigFromDisk]);
 
   const handleRefresh = useCallback(async () => {
-    await Promise.all([refreshStatus(), hydrateConfigFromDisk(config.serverRoot)]);
-  }, [config.serverRoot, hydrateConfigFromDisk, refreshStatus]);
+    await Promise.all([
+      refreshStatus(),
+      hydrateConfigFromDisk(config.serverRoot),
+      refreshCustomServers(),
+    ]);
+  }, [config.serverRoot, hydrateConfigFromDisk, refreshCustomServers, refreshStatus]);
 
   const handleStart = useCallback(async () => {
     if (pendingRef.current) {
@@ -697,6 +863,136 @@ function McpMarketplaceWindow({
                 </div>
               </article>
 
+              <article className="sarah-mcp-card sarah-mcp-card--full">
+                <header className="sarah-mcp-card__header">
+                  <div>
+                    <p className="sarah-mcp-card__title">Custom servers</p>
+                    <p className="sarah-mcp-card__subtitle">
+                      Register a local stdio MCP server or a remote http(s) endpoint. Tools are
+                      discovered on first connect.
+                    </p>
+                    {customNotice ? <p className="sarah-mcp-card__hint">{customNotice}</p> : null}
+                  </div>
+                  <div className="sarah-mcp-card__badge">
+                    <Server className="size-4" />
+                  </div>
+                </header>
+                <div className="sarah-mcp-card__body sarah-mcp-card__body--stack">
+                  <div>
+                    <label className="sarah-mcp-label">Name</label>
+                    <Input
+                      value={serverDraft.name}
+                      onChange={(event) =>
+                        setServerDraft((current) => ({ ...current, name: event.target.value }))
+                      }
+                      placeholder="filesystem"
+                    />
+                  </div>
+                  <div>
+                    <label className="sarah-mcp-label">Command or URL</label>
+                    <Input
+                      value={serverDraft.command}
+                      onChange={(event) =>
+                        setServerDraft((current) => ({ ...current, command: event.target.value }))
+                      }
+                      placeholder="npx"
+                    />
+                  </div>
+                  <div>
+                    <label className="sarah-mcp-label">Arguments</label>
+                    <Input
+                      value={serverDraft.args}
+                      onChange={(event) =>
+                        setServerDraft((current) => ({ ...current, args: event.target.value }))
+                      }
+                      placeholder="-y @modelcontextprotocol/server-filesystem ./notes"
+                    />
+                  </div>
+                  <div>
+                    <label className="sarah-mcp-label">Environment</label>
+                    <Input
+                      value={serverDraft.env}
+                      onChange={(event) =>
+                        setServerDraft((current) => ({ ...current, env: event.target.value }))
+                      }
+                      placeholder="API_KEY=value, OTHER=value"
+                    />
+                  </div>
+                  <div>
+                    <label className="sarah-mcp-label">Auth header (remote only)</label>
+                    <Input
+                      type="password"
+                      value={serverDraft.authHeader}
+                      onChange={(event) =>
+                        setServerDraft((current) => ({
+                          ...current,
+                          authHeader: event.target.value,
+                        }))
+                      }
+                      placeholder="Bearer token or Header-Name: value"
+                    />
+                  </div>
+                  <div className="sarah-mcp-credentials-actions">
+                    <Button
+                      type="button"
+                      variant="outline"
+                      className="sarah-mcp-outline"
+                      onClick={() => void handleAddServer()}
+                      disabled={isCustomWorking}
+                    >
+                      {isCustomWorking ? <Loader2 className="size-4 animate-spin" /> : null}
+                      Add server
+                    </Button>
+                  </div>
+                </div>
+                {customServers.length > 0 ? (
+                  <div className="sarah-mcp-card__body sarah-mcp-tool-grid sarah-mcp-tool-grid--detailed">
+                    {customServers.map((server) => {
+                      const toolNames = parseToolNames(server.toolSchemas);
+                      return (
+                        <div key={server.id} className="sarah-mcp-tool">
+                          <div className="sarah-mcp-tool__heading">
+                            <p className="sarah-mcp-tool__title">{server.displayName}</p>
+                            <span className="sarah-mcp-tool__count">{toolNames.length} tools</span>
+                          </div>
+                          <p className="sarah-mcp-tool__subtitle">
+                            {server.lastError && server.healthStatus === "down"
+                              ? server.lastError
+                              : formatLaunchCommand(server)}
+                          </p>
+                          <div className="sarah-mcp-toggle-row">
+                            <p className="sarah-mcp-toggle-row__subtitle">
+                              {server.isActive === 1 ? "Enabled" : "Disabled"}
+                            </p>
+                            <Switch
+                              checked={server.isActive === 1}
+                              onCheckedChange={(value) => void handleToggleServer(server, value)}
+                            />
+                            <Button
+                              type="button"
+                              variant="ghost"
+                              className="sarah-mcp-ghost sarah-mcp-ghost--icon"
+                              onClick={() => void handleRemoveServer(server)}
+                              disabled={isCustomWorking}
+                              aria-label={`Remove ${server.displayName}`}
+                              title={`Remove ${server.displayName}`}
+                            >
+                              <Trash2 className="size-4" />
+                            </Button>
+                          </div>
+                          <ul className="sarah-mcp-tool__list">
+                            {toolNames.map((toolName) => (
+                              <li key={toolName} className="sarah-mcp-tool__item">
+                                <span className="sarah-mcp-tool__name">{toolName}</span>
+                              </li>
+                            ))}
+                          </ul>
+                        </div>
+                      );
+                    })}
+                  </div>
+                ) : null}
+              </article>
             </section>
           </section>
         </div>
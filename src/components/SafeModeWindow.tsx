@@ -0,0 +1,139 @@
+import { invoke } from "@tauri-apps/api/core";
+import { AlertTriangle, DatabaseBackup, RotateCcw, RefreshCw } from "lucide-react";
+import { useEffect, useState } from "react";
+import { Button } from "@/components/ui/button";
+
+export interface StartupFailure {
+  message: string;
+  panicked: boolean;
+  occurredAt: string;
+}
+
+export interface DatabaseBackupInfo {
+  fileName: string;
+  path: string;
+  sizeBytes: number;
+  createdAt: string;
+}
+
+export interface StartupStatus {
+  safeMode: boolean;
+  failure: StartupFailure | null;
+  dbPath: string;
+  integrity: string | null;
+  backups: DatabaseBackupInfo[];
+}
+
+interface SafeModeWindowProps {
+  initialStatus: StartupStatus;
+}
+
+function formatSize(bytes: number) {
+  if (bytes >= 1024 * 1024) {
+    return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+  }
+  return `${Math.max(1, Math.round(bytes / 1024))} KB`;
+}
+
+export default function SafeModeWindow({ initialStatus }: SafeModeWindowProps) {
+  const [status, setStatus] = useState<StartupStatus>(initialStatus);
+  const [notice, setNotice] = useState<string | null>(null);
+  const [isBusy, setIsBusy] = useState(false);
+
+  const refresh = async () => {
+    try {
+      setStatus(await invoke<StartupStatus>("get_startup_status"));
+    } catch (e) {
+      console.error("Failed to refresh startup status:", e);
+    }
+  };
+
+  useEffect(() => {
+    setStatus(initialStatus);
+  }, [initialStatus]);
+
+  const handleBackup = async () => {
+    setIsBusy(true);
+    try {
+      const backup = await invoke<DatabaseBackupInfo>("backup_database");
+      setNotice(`Saved ${backup.fileName}`);
+      await refresh();
+    } catch (e) {
+      setNotice(`Backup failed: ${String(e)}`);
+    } finally {
+      setIsBusy(false);
+    }
+  };
+
+  const handleRestore = async (fileName: string) => {
+    if (!window.confirm(`Replace the current database with ${fileName}? The current file is backed up first.`)) {
+      return;
+    }
+    setIsBusy(true);
+    try {
+      // The backend relaunches the app once the file is swapped.
+      await invoke("restore_database_backup", { fileName });
+    } catch (e) {
+      setNotice(`Restore failed: ${String(e)}`);
+      setIsBusy(false);
+    }
+  };
+
+  const failure = status.failure;
+
+  return (
+    <div className="flex h-screen w-screen flex-col gap-4 overflow-y-auto bg-background p-6 text-sm text-foreground" data-tauri-drag-region>
+      <div className="flex items-center gap-2">
+        <AlertTriangle className="h-5 w-5 text-amber-500" />
+        <h1 className="text-base font-semibold">Sarah started in safe mode</h1>
+      </div>
+      <p className="text-muted-foreground">
+        Startup failed, so models, embeddings, the reranker and background tasks are off and the database is
+        opened read-only. Back up your data, restore an earlier backup, or try starting again.
+      </p>
+
+      {failure && (
+        <div className="rounded-md border border-border bg-card p-3">
+          <p className="font-medium">{failure.panicked ? "Startup crashed" : "Startup error"}</p>
+          <pre className="mt-2 whitespace-pre-wrap break-words text-xs text-muted-foreground">{failure.message}</pre>
+          <p className="mt-2 text-xs text-muted-foreground">{new Date(failure.occurredAt).toLocaleString()}</p>
+        </div>
+      )}
+
+      <div className="rounded-md border border-border bg-card p-3 text-xs text-muted-foreground">
+        <p>Database: {status.dbPath}</p>
+        <p>Integrity check: {status.integrity ?? "database could not be opened"}</p>
+      </div>
+
+      <div className="flex gap-2">
+        <Button size="sm" disabled={isBusy} onClick={() => void handleBackup()}>
+          <DatabaseBackup className="h-4 w-4" /> Back up now
+        </Button>
+        <Button size="sm" variant="outline" disabled={isBusy} onClick={() => void invoke("restart_app")}>
+          <RefreshCw className="h-4 w-4" /> Try again
+        </Button>
+      </div>
+      {notice && <p className="text-xs text-muted-foreground">{notice}</p>}
+
+      <div>
+        <p className="mb-2 font-medium">Backups</p>
+        {status.backups.length === 0 ? (
+          <p className="text-xs text-muted-foreground">No backups yet.</p>
+        ) : (
+          <ul className="flex flex-col gap-1">
+            {status.backups.map((backup) => (
+              <li key={backup.fileName} className="flex items-center justify-between rounded-md border border-border px-3 py-2">
+                <span className="text-xs">
+                  {backup.fileName} · {formatSize(backup.sizeBytes)} · {new Date(backup.createdAt).toLocaleString()}
+                </span>
+                <Button size="sm" variant="ghost" disabled={isBusy} onClick={() => void handleRestore(backup.fileName)}>
+                  <RotateCcw className="h-4 w-4" /> Restore
+                </Button>
+              </li>
+            ))}
+          </ul>
+        )}
+      </div>
+    </div>
+  );
+}
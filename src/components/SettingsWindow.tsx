@@ -80,6 +80,23 @@ interface SystemStats {
   memoryTotalMb: number;
 }
 
+interface BackgroundJob {
+  id: string;
+  jobType: string;
+  status: "queued" | "running" | "completed" | "failed" | "cancelled";
+  attempts: number;
+  maxAttempts: number;
+  lastError: string | null;
+  runAt: string;
+}
+
+function formatJobType(jobType: string) {
+  return jobType
+    .split("_")
+    .map((word) => word.charAt(0).toUpperCase() + word.slice(1))
+    .join(" ");
+}
+
 function SettingsWindow({
   embedded = false,
   onRequestClose,
@@ -149,6 +166,26 @@ function SettingsWindow({
   const [hardwareProfile, setHardwareProfile] = useState<HardwareProfile | null>(null);
   const [startupTelemetry, setStartupTelemetry] = useState<StartupTelemetry | null>(null);
   const [systemStats, setSystemStats] = useState<SystemStats | null>(null);
+  const [backgroundJobs, setBackgroundJobs] = useState<BackgroundJob[]>([]);
+  const [pendingJobId, setPendingJobId] = useState<null | string>(null);
+
+  const refreshBackgroundJobs = () => {
+    invoke<BackgroundJob[]>("list_background_jobs", { limit: 20 })
+      .then(setBackgroundJobs)
+      .catch(console.error);
+  };
+
+  const updateBackgroundJob = async (command: "cancel_job" | "retry_job", jobId: string) => {
+    setPendingJobId(jobId);
+    try {
+      await invoke(command, { jobId });
+    } catch (error) {
+      console.error(`Failed to run ${command}.`, error);
+    } finally {
+      setPendingJobId(null);
+      refreshBackgroundJobs();
+    }
+  };
 
   useEffect(() => {
     if (activeTab === "system") {
@@ -162,8 +199,10 @@ function SettingsWindow({
       }
 
       invoke<SystemStats>("get_system_stats").then(setSystemStats).catch(console.error);
+      refreshBackgroundJobs();
       const interval = setInterval(() => {
         invoke<SystemStats>("get_system_stats").then(setSystemStats).catch(console.error);
+        refreshBackgroundJobs();
       }, 3000);
       return () => clearInterval(interval);
     }
@@ -658,6 +697,52 @@ function SettingsWindow({
                       </p>
                     </div>
                   </article>
+                  <article className="sarah-settings-row">
+                    <div className="sarah-settings-row__copy">
+                      <p className="sarah-settings-row__title">Background Jobs</p>
+                      {backgroundJobs.length === 0 ? (
+                        <p className="sarah-settings-row__note">No background jobs yet.</p>
+                      ) : (
+                        <ul className="sarah-settings-row__note mt-2 space-y-2">
+                          {backgroundJobs.map((job) => (
+                            <li key={job.id} className="flex items-center justify-between gap-3">
+                              <span>
+                                {formatJobType(job.jobType)}: {job.status}
+                                {job.status === "queued" && ` (next run ${job.runAt} UTC)`}
+                                {job.status === "failed" &&
+                                  ` after ${job.attempts}/${job.maxAttempts} attempts`}
+                                {job.lastError && job.status !== "completed" && (
+                                  <span className="block opacity-70">{job.lastError}</span>
+                                )}
+                              </span>
+                              {(job.status === "queued" || job.status === "running") && (
+                                <Button
+                                  type="button"
+                                  size="sm"
+                                  variant="ghost"
+                                  disabled={pendingJobId === job.id}
+                                  onClick={() => void updateBackgroundJob("cancel_job", job.id)}
+                                >
+                                  Cancel
+                                </Button>
+                              )}
+                              {(job.status === "failed" || job.status === "cancelled") && (
+                                <Button
+                                  type="button"
+                                  size="sm"
+                                  variant="outline"
+                                  disabled={pendingJobId === job.id}
+                                  onClick={() => void updateBackgroundJob("retry_job", job.id)}
+                                >
+                                  Retry
+                                </Button>
+                              )}
+                            </li>
+                          ))}
+                        </ul>
+                      )}
+                    </div>
+                  </article>
                 </div>
               )}
             </div>
@@ -30,6 +30,7 @@ const SPOTIFY_WEB_DEVICE_STORAGE_KEY = "sarah_spotify_web_sdk_device_id_v1"
 const MCP_LEGACY_ROOT =
   "C:\\Users\\jesud\\OneDrive\\Desktop\\personal\\Sarah\\mcp\\spotify-mcp-server"
 const MCP_DEFAULT_ROOT = "F:\\Sarah\\mcp\\spotify-mcp-server"
+const NOW_PLAYING_POLL_MS = 5000
 
 interface SpotifyAudioPlayerProps {
   isOpen: boolean
@@ -219,6 +220,32 @@ function parseToolOutput(raw: string) {
   }
 }
 
+// Reads the markdown-ish getNowPlaying reply, e.g. **Track**: "Song" / **Progress**: 1:02 / 3:45.
+function parseNowPlaying(text: string): Partial<SpotifyPlaybackState> | null {
+  if (/\bnothing is (currently )?playing\b/i.test(text)) {
+    return { hasTrack: false, isPlaying: false, progressSec: 0 }
+  }
+
+  const field = (name: string) =>
+    text.match(new RegExp(`\\*{0,2}${name}\\*{0,2}:\\s*"?([^"\\n]+)"?`, "i"))?.[1]?.trim() || ""
+  const title = field("Track")
+  if (!title) {
+    return null
+  }
+
+  const progress = text.match(/(\d+):(\d{2})\s*\/\s*(\d+):(\d{2})/)
+  const toSeconds = (minutes: string, seconds: string) => Number(minutes) * 60 + Number(seconds)
+
+  return {
+    artist: field("Artist") || "Spotify",
+    durationSec: progress ? toSeconds(progress[3], progress[4]) : 0,
+    hasTrack: true,
+    isPlaying: !/\bpaused\b/i.test(text),
+    progressSec: progress ? toSeconds(progress[1], progress[2]) : 0,
+    title,
+  }
+}
+
 function toErrorMessage(error: unknown, fallback: string) {
   if (error instanceof Error && error.message.trim()) {
     return error.message
@@ -319,6 +346,7 @@ function SpotifyAudioPlayer({
   const autoplayAttemptedRef = useRef(false)
   const sdkPlayerRef = useRef<SpotifyWebPlaybackPlayer | null>(null)
   const sdkDeviceIdRef = useRef("")
+  const isPlayingRef = useRef(false)
   const [isActionPending, setIsActionPending] = useState(false)
   const [statusText, setStatusText] = useState("Sarah Audio is initializing.")
   const [playback, setPlayback] = useState<SpotifyPlaybackState>({
@@ -470,6 +498,19 @@ function SpotifyAudioPlayer({
     }
   }, [applyWebPlaybackState])
 
+  const pollNowPlaying = useCallback(async () => {
+    try {
+      const { isError, text } = await runSpotifyTool("getNowPlaying", {})
+      const nowPlaying = isError ? null : parseNowPlaying(text)
+      if (!nowPlaying) {
+        return
+      }
+      setPlayback((current) => ({ ...current, ...nowPlaying }))
+    } catch {
+      // The poller is best-effort; transport errors surface through their own actions.
+    }
+  }, [runSpotifyTool])
+
   const runTransportAction = useCallback(
     async (action: "next" | "pause" | "play" | "prev" | "stop") => {
       const toolByAction: Record<typeof action, string> = {
@@ -486,6 +527,9 @@ function SpotifyAudioPlayer({
         const { isError, text } = await runSpotifyTool(toolByAction[action], args)
         if (isError) {
           setStatusText(text)
+        } else {
+          // Confirm what Spotify actually did instead of assuming the command landed.
+          window.setTimeout(() => void pollNowPlaying(), 600)
         }
       } catch (error) {
         setStatusText(toErrorMessage(error, "Spotify command failed."))
@@ -493,7 +537,7 @@ function SpotifyAudioPlayer({
         setIsActionPending(false)
       }
     },
-    [runSpotifyTool],
+    [pollNowPlaying, runSpotifyTool],
   )
 
   const handleAdjustVolume = useCallback(
@@ -778,9 +822,20 @@ function SpotifyAudioPlayer({
   }, [autoplayOnOpen, isVisible, playback.isPlaying, runTransportAction])
 
   useEffect(() => {
+    isPlayingRef.current = playback.isPlaying
     onPlayingChange?.(playback.isPlaying)
   }, [onPlayingChange, playback.isPlaying])
 
+  useEffect(() => {
+    if (!isVisible) {
+      return
+    }
+
+    void pollNowPlaying()
+    const pollId = window.setInterval(() => void pollNowPlaying(), NOW_PLAYING_POLL_MS)
+    return () => window.clearInterval(pollId)
+  }, [isVisible, pollNowPlaying])
+
   useEffect(() => {
     if (!playback.isPlaying || playback.durationSec <= 0) {
       return
@@ -812,6 +867,12 @@ function SpotifyAudioPlayer({
         return
       }
 
+      if (action === "toggle") {
+        // Media play/pause key: resolve against the polled state, not the last command sent.
+        void runTransportAction(isPlayingRef.current ? "pause" : "play")
+        return
+      }
+
       if (action === "play" || action === "pause" || action === "stop" || action === "next" || action === "prev") {
         void runTransportAction(action)
       }
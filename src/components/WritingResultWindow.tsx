@@ -0,0 +1,95 @@
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import { Check, Copy, PenLine } from "lucide-react";
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { Button } from "@/components/ui/button";
+
+type RewriteStyle = "improve" | "shorter" | "formal" | "casual" | "fix-grammar";
+
+interface WritingResult {
+  action: "rewrite" | "summarize";
+  style: RewriteStyle | null;
+  original: string;
+  text: string;
+  replaced: boolean;
+}
+
+const STYLE_LABELS: Record<RewriteStyle, string> = {
+  improve: "Improved",
+  shorter: "Shorter",
+  formal: "Formal",
+  casual: "Casual",
+  "fix-grammar": "Grammar fixed",
+};
+
+function resultTitle(result: WritingResult): string {
+  if (result.action === "summarize") {
+    return "Summary";
+  }
+  return result.style ? `Rewrite · ${STYLE_LABELS[result.style]}` : "Rewrite";
+}
+
+export default function WritingResultWindow() {
+  const [result, setResult] = useState<WritingResult | null>(null);
+  const [isCopied, setIsCopied] = useState(false);
+
+  useEffect(() => {
+    // Asked for once on load, pushed after that
+    void invoke<WritingResult | null>("take_writing_result")
+      .then((pending) => {
+        if (pending) {
+          setResult(pending);
+        }
+      })
+      .catch(() => {
+        // Ignore if not running in Tauri context.
+      });
+    const unlisten = listen<WritingResult>("writing://result", (event) => {
+      void invoke("take_writing_result").catch(() => undefined);
+      setIsCopied(false);
+      setResult(event.payload);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  const handleCopy = async () => {
+    if (!result) {
+      return;
+    }
+    try {
+      await navigator.clipboard.writeText(result.text);
+      setIsCopied(true);
+    } catch (error) {
+      console.error("Failed to copy result", error);
+    }
+  };
+
+  return (
+    <main
+      className="flex h-screen w-screen flex-col gap-3 overflow-hidden rounded-xl border border-border/50 bg-background p-4 text-sm text-foreground"
+      aria-label="Sarah AI writing result"
+    >
+      <header className="flex shrink-0 items-center justify-between gap-2" data-tauri-drag-region>
+        <div className="flex items-center gap-2" data-tauri-drag-region>
+          <PenLine className="h-4 w-4 text-[#d4af37]" />
+          <h1 className="font-semibold">{result ? resultTitle(result) : "Working on it..."}</h1>
+        </div>
+        <div className="flex gap-2">
+          <Button type="button" variant="outline" size="sm" disabled={!result} onClick={() => void handleCopy()}>
+            {isCopied ? <Check className="h-4 w-4" /> : <Copy className="h-4 w-4" />}
+            {isCopied ? "Copied" : "Copy"}
+          </Button>
+          <Button type="button" variant="outline" size="sm" onClick={() => void getCurrentWindow().close()}>
+            Close
+          </Button>
+        </div>
+      </header>
+      <div className="flex-1 overflow-y-auto whitespace-pre-wrap break-words rounded-md border border-border bg-card p-3 leading-relaxed">
+        {result?.text}
+      </div>
+    </main>
+  );
+}
@@ -14,6 +14,7 @@ export interface Session {
     messageCount: number;
     status: string;
     summary: string | null;
+    summarizedAt: string | null;
     tags: string;
     pinned: number;
     forkedFromSessionId: string | null;
@@ -39,8 +40,14 @@ export function useSession() {
                 if (mounted) setDefaultUserId(user.id);
 
                 const storedId = window.localStorage.getItem(SESSION_STORAGE_KEY);
+                const lastSession = storedId
+                    ? null
+                    : await invoke<Session | null>("get_last_session").catch(() => null);
                 if (storedId) {
                     if (mounted) setCurrentSessionId(storedId);
+                } else if (lastSession) {
+                    if (mounted) setCurrentSessionId(lastSession.id);
+                    window.localStorage.setItem(SESSION_STORAGE_KEY, lastSession.id);
                 } else {
                     // If no stored session, find the most recent one or create a new one
                     const sessions = await invoke<Session[]>("list_sessions", {
@@ -80,6 +87,7 @@ export function useSession() {
     useEffect(() => {
         if (!currentSessionId) return;
         window.localStorage.setItem(SESSION_STORAGE_KEY, currentSessionId);
+        void invoke("set_last_session", { sessionId: currentSessionId }).catch(() => undefined);
     }, [currentSessionId]);
 
     const createNewSession = useCallback(async () => {
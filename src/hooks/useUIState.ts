@@ -25,6 +25,12 @@ export interface ChatHistoryItem {
   timestamp: string;
 }
 
+export interface ClipboardContext {
+  text: string;
+  charCount: number;
+  truncated: boolean;
+}
+
 const STATE_FLOW: UIVisualState[] = ["idle", "listening", "thinking", "speaking"];
 const HISTORY_LIMIT = 120;
 const DEFAULT_OLLAMA_MODEL = "llama3.1:8b";
@@ -431,6 +437,7 @@ export function useUIState(options: UseUIStateOptions = {}) {
   const { animate = true } = options;
   const [state, setState] = useState<UIVisualState>("idle");
   const [prompt, setPrompt] = useState("");
+  const [clipboardContext, setClipboardContext] = useState<ClipboardContext | null>(null);
   const [amplitude, setAmplitude] = useState(0.09);
   const [conversations, setConversations] = useState<ConversationItem[]>([]);
   const [selectedModel, setSelectedModelState] = useState(readStoredModel);
@@ -828,7 +835,9 @@ export function useUIState(options: UseUIStateOptions = {}) {
 
     clearPending();
     const requestId = activeRequestIdRef.current;
+    const attachedContext = clipboardContext?.text ?? null;
 
+    setClipboardContext(null);
     setPrompt(value);
     setIsPromptLocked(true);
     setAmplitude(0.6);
@@ -901,6 +910,7 @@ export function useUIState(options: UseUIStateOptions = {}) {
             attachments: [],
             modelSelectionMode,
             selectedModel: modelSelectionMode === "manual" ? selectedModel : null,
+            clipboardContext: attachedContext,
           },
         });
 
@@ -936,7 +946,7 @@ export function useUIState(options: UseUIStateOptions = {}) {
         setState("idle");
       }
     })();
-  }, [clearPending, isPromptLocked, modelSelectionMode, prompt, selectedModel]);
+  }, [clearPending, clipboardContext, isPromptLocked, modelSelectionMode, prompt, selectedModel]);
 
   const stopResponse = useCallback(() => {
     clearPending();
@@ -990,12 +1000,14 @@ export function useUIState(options: UseUIStateOptions = {}) {
     amplitude,
     clearConversation,
     clearPrompt,
+    clipboardContext,
     conversations,
     cycleState,
     isPromptLocked,
     prompt,
     modelSelectionMode,
     selectedModel,
+    setClipboardContext,
     setModelSelectionMode,
     setPrompt,
     setSelectedModel,